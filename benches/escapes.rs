@@ -0,0 +1,59 @@
+//! Time taken to paint a large `ls`-like listing, writing the escape codes
+//! through `fmt::Arguments`, straight to the writer, and from a prefix
+//! rendered once. Run with `cargo bench --bench escapes`.
+
+use nu_ansi_term::Color::{Blue, Cyan, Green, Rgb};
+use nu_ansi_term::Style;
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+const ENTRIES: usize = 200_000;
+
+/// The style of the `i`th entry of the listing, the way `ls` would color
+/// directories, executables, links and plain files.
+fn style_of(i: usize) -> Style {
+    match i % 4 {
+        0 => Blue.bold(),
+        1 => Green.bold(),
+        2 => Cyan.normal(),
+        _ => Rgb(200, 180, 90).on(Rgb(30, 30, 30)),
+    }
+}
+
+fn time(name: &str, mut paint: impl FnMut(&mut Vec<u8>, usize, &str)) -> Duration {
+    let names: Vec<String> = (0..ENTRIES).map(|i| format!("entry-{}", i)).collect();
+    let mut out = Vec::with_capacity(ENTRIES * 40);
+    let start = Instant::now();
+    for (i, name) in names.iter().enumerate() {
+        paint(&mut out, i, name);
+        out.push(b'\n');
+    }
+    let elapsed = start.elapsed();
+    println!("{:>10}: {:>8.2?} ({} bytes)", name, elapsed, out.len());
+    elapsed
+}
+
+fn main() {
+    println!("painting {} entries", ENTRIES);
+    let formatted = time("formatted", |out, i, name| {
+        let style = style_of(i);
+        write!(out, "{}{}{}", style.prefix(), name, style.suffix()).unwrap();
+    });
+    let direct = time("direct", |out, i, name| {
+        let style = style_of(i);
+        style.write_prefix_bytes(out).unwrap();
+        out.extend_from_slice(name.as_bytes());
+        style.write_suffix_bytes(out).unwrap();
+    });
+    let prefixes: Vec<_> = (0..4).map(|i| style_of(i).prefix_str()).collect();
+    let cached = time("cached", |out, i, name| {
+        out.extend_from_slice(prefixes[i % 4].as_bytes());
+        out.extend_from_slice(name.as_bytes());
+        style_of(i).write_suffix_bytes(out).unwrap();
+    });
+    println!(
+        "direct is {:.1}x, cached {:.1}x as fast as formatted",
+        formatted.as_secs_f64() / direct.as_secs_f64(),
+        formatted.as_secs_f64() / cached.as_secs_f64()
+    );
+}
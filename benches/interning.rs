@@ -0,0 +1,93 @@
+//! Memory used by a large table whose cells repeat a few values, with and
+//! without interning their content. Run with `cargo bench --bench interning`.
+
+use nu_ansi_term::Color::{Blue, Green};
+use nu_ansi_term::{AnsiString, AnsiStrings, ContentInterner, Style};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+/// Keeps track of the number of bytes currently allocated.
+struct Counting;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+const ROWS: usize = 20_000;
+
+/// The cells of a table with a padded status column, a small number column
+/// and borders, as a table renderer would produce them.
+fn cells() -> impl Iterator<Item = (Style, String)> {
+    let border = Style::new().dimmed();
+    (0..ROWS).flat_map(move |row| {
+        let status = match row % 3 {
+            0 => "connection refused by upstream",
+            1 => "ok",
+            _ => "retrying after timeout",
+        };
+        [
+            (border, "│".to_string()),
+            (Green.normal(), format!(" {:<60}", status)),
+            (border, "│".to_string()),
+            (Blue.normal(), format!(" {:>4} ", row % 16)),
+            (border, "│".to_string()),
+            (Style::new(), "\n".to_string()),
+        ]
+    })
+}
+
+/// Build a document with `build`, reporting the memory it holds on to.
+fn measure<'a>(name: &str, build: impl FnOnce() -> AnsiStrings<'a>) -> (AnsiStrings<'a>, usize) {
+    let before = ALLOCATED.load(Ordering::Relaxed);
+    let start = Instant::now();
+    let doc = build();
+    let elapsed = start.elapsed();
+    let held = ALLOCATED.load(Ordering::Relaxed).saturating_sub(before);
+    println!(
+        "{:<10} {:>10} bytes held, built in {:>8.2?}",
+        name, held, elapsed
+    );
+    (doc, held)
+}
+
+fn main() {
+    let (owned, owned_bytes) = measure("owned", || {
+        cells()
+            .map(|(style, cell)| style.paint(cell))
+            .collect::<Vec<AnsiString<'static>>>()
+            .into_iter()
+            .collect()
+    });
+
+    let mut interner = ContentInterner::new();
+    let (interned, interned_bytes) = measure("interned", || {
+        cells()
+            .map(|(style, cell)| interner.paint(style, cell.as_str()))
+            .collect::<Vec<AnsiString<'static>>>()
+            .into_iter()
+            .collect()
+    });
+    println!(
+        "{} unique cells of {} in total, {} bytes of content; {} bytes saved",
+        interner.len(),
+        ROWS * 6,
+        interner.content_size(),
+        owned_bytes.saturating_sub(interned_bytes)
+    );
+
+    assert_eq!(owned.to_string(), interned.to_string());
+}
@@ -0,0 +1,22 @@
+use nu_ansi_term::Theme;
+
+// This example prints the styles of a few generated themes, for eyeballing
+// how rendering holds up with varied styles. Pass a seed to see its theme.
+
+fn main() {
+    #[cfg(windows)]
+    nu_ansi_term::enable_ansi_support().unwrap();
+
+    let seeds: Vec<u64> = match std::env::args().nth(1) {
+        Some(seed) => vec![seed.parse().expect("the seed should be a number")],
+        None => (0..3).collect(),
+    };
+    for seed in seeds {
+        println!("seed {}:", seed);
+        let theme = Theme::generate(seed);
+        for (name, _) in theme.iter() {
+            print!("{} ", theme.paint(name, name));
+        }
+        println!("\n");
+    }
+}
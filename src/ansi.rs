@@ -1,22 +1,27 @@
 #![allow(missing_docs)]
-use crate::difference::StyleDelta;
+use crate::enabled::is_enabled;
+use crate::instrument::{self, ByteCount, SequenceKind};
+use crate::level::palette_index;
 use crate::style::{Color, FormatFlags, Style};
 use crate::write::{AnyWrite, StrLike, WriteResult};
-use crate::{fmt_write, write_fmt, write_str};
-use std::fmt;
+use crate::{fmt_write, io_write, write_str};
+use std::{fmt, io};
 
 impl FormatFlags {
     #[cfg(not(feature = "gnu_legacy"))]
-    fn as_format_char(self) -> Option<char> {
+    fn as_format_char(self) -> Option<&'static str> {
         match self {
-            FormatFlags::BOLD => '1'.into(),
-            FormatFlags::DIMMED => '2'.into(),
-            FormatFlags::ITALIC => '3'.into(),
-            FormatFlags::UNDERLINE => '4'.into(),
-            FormatFlags::BLINK => '5'.into(),
-            FormatFlags::REVERSE => '7'.into(),
-            FormatFlags::HIDDEN => '8'.into(),
-            FormatFlags::STRIKETHROUGH => '9'.into(),
+            FormatFlags::BOLD => "1".into(),
+            FormatFlags::DIMMED => "2".into(),
+            FormatFlags::ITALIC => "3".into(),
+            FormatFlags::UNDERLINE => "4".into(),
+            FormatFlags::BLINK => "5".into(),
+            FormatFlags::REVERSE => "7".into(),
+            FormatFlags::HIDDEN => "8".into(),
+            FormatFlags::STRIKETHROUGH => "9".into(),
+            FormatFlags::DOUBLE_UNDERLINE => "4:2".into(),
+            FormatFlags::UNDERCURL => "4:3".into(),
+            FormatFlags::OVERLINE => "53".into(),
             _ => None,
         }
     }
@@ -32,6 +37,9 @@ impl FormatFlags {
             FormatFlags::REVERSE => "07".into(),
             FormatFlags::HIDDEN => "08".into(),
             FormatFlags::STRIKETHROUGH => "09".into(),
+            FormatFlags::DOUBLE_UNDERLINE => "4:2".into(),
+            FormatFlags::UNDERCURL => "4:3".into(),
+            FormatFlags::OVERLINE => "53".into(),
             _ => None,
         }
     }
@@ -40,6 +48,36 @@ impl FormatFlags {
 impl Style {
     /// Write any codes that go *before* a piece of text to the given writer.
     pub fn write_prefix<W: AnyWrite + ?Sized>(&self, f: &mut W) -> WriteResult<W::Error>
+    where
+        str: AsRef<W::Buf>,
+        W::Buf: ToOwned,
+    {
+        self.write_prefix_unreported(f)?;
+        if instrument::is_hooked() && !self.is_empty() && is_enabled() {
+            self.report_prefix();
+        }
+        Ok(())
+    }
+
+    /// Report the codes written by [`write_prefix`](Self::write_prefix) to
+    /// the instrumentation hook.
+    fn report_prefix(&self) {
+        if self.prefix_before_reset {
+            instrument::emit(SequenceKind::Reset, RESET.len());
+        }
+        if !self.has_no_styling() {
+            let mut params = ByteCount::default();
+            let _ = self.write_sgr_params(fmt_write!(&mut params));
+            instrument::emit(SequenceKind::Sgr, "\x1B[".len() + params.0 + "m".len());
+        }
+    }
+
+    /// Write the prefix without reporting it to the instrumentation hook,
+    /// for rendering it without writing it out.
+    pub(crate) fn write_prefix_unreported<W: AnyWrite + ?Sized>(
+        &self,
+        f: &mut W,
+    ) -> WriteResult<W::Error>
     where
         str: AsRef<W::Buf>,
         W::Buf: ToOwned,
@@ -47,35 +85,59 @@ impl Style {
         // If there are actually no styles here, then don’t write *any* codes
         // as the prefix. An empty ANSI code may not affect the terminal
         // output at all, but a user may just want a code-free string.
-        if self.is_empty() {
+        if self.is_empty() || !is_enabled() {
             return Ok(());
         }
 
         // Prefix everything with reset characters if needed
         if self.prefix_before_reset {
-            write_str!(f, "\x1B[0m")?
+            write_str!(f, RESET)?
         }
 
         if self.has_no_styling() {
             return Ok(());
         }
 
-        fn write_front<W: AnyWrite + ?Sized>(
-            f: &mut W,
-            write_occurred: bool,
-        ) -> WriteResult<W::Error>
-        where
-            str: AsRef<W::Buf>,
-            W::Buf: ToOwned,
-        {
-            if write_occurred {
-                write_str!(f, ";")
-            } else {
-                // front of the ANSI escape code sequence
-                write_str!(f, "\x1B[")
+        write_str!(f, "\x1B[")?;
+        self.write_sgr_params(f)?;
+        // All the codes end with an `m`, because reasons.
+        write_str!(f, "m")
+    }
+
+    /// The parameters of the escape code setting this style, separated by
+    /// semicolons, as they are written in `LS_COLORS` and similar
+    /// variables. Styles which reset first start with a `0`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::Style;
+    /// use nu_ansi_term::Color::{Blue, Fixed};
+    ///
+    /// assert_eq!(Blue.bold().sgr_params(), "1;34");
+    /// assert_eq!(Fixed(208).on(Blue).sgr_params(), "44;38;5;208");
+    /// assert_eq!(Style::reset().underline().sgr_params(), "0;4");
+    /// ```
+    pub fn sgr_params(&self) -> String {
+        let mut params = String::new();
+        if self.prefix_before_reset {
+            params.push('0');
+            if !self.has_no_styling() {
+                params.push(';');
             }
         }
+        self.write_sgr_params(fmt_write!(&mut params))
+            .expect("writing to a String cannot fail");
+        params
+    }
 
+    /// Write the parameters of the escape code setting this style, separated
+    /// by semicolons, without the code around them (or any reset).
+    pub(crate) fn write_sgr_params<W: AnyWrite + ?Sized>(&self, f: &mut W) -> WriteResult<W::Error>
+    where
+        str: AsRef<W::Buf>,
+        W::Buf: ToOwned,
+    {
         fn write_code<W: AnyWrite + ?Sized, T, F: Fn(&mut W, T) -> WriteResult<W::Error>>(
             f: &mut W,
             input: Option<T>,
@@ -87,7 +149,9 @@ impl Style {
             W::Buf: ToOwned,
         {
             if let Some(x) = input {
-                write_front(f, write_occurred)?;
+                if write_occurred {
+                    write_str!(f, ";")?;
+                }
                 write_op(f, x)?;
                 Ok(true)
             } else {
@@ -100,7 +164,7 @@ impl Style {
             write_occurred = write_code(
                 f,
                 flag.as_format_char(),
-                |f, x| write_fmt!(f, "{}", x),
+                |f, x| write_str!(f, x),
                 write_occurred,
             )?;
         }
@@ -122,27 +186,241 @@ impl Style {
             write_occurred,
         )?;
 
-        if write_occurred {
-            // All the codes end with an `m`, because reasons.
-            write_str!(f, "m")?;
-        }
-
+        write_code(
+            f,
+            self.is_underline_color(),
+            |f, x| x.write_underline_code(f),
+            write_occurred,
+        )?;
         Ok(())
     }
 
     /// Write any bytes that go *after* a piece of text to the given writer.
-    fn write_suffix<W: AnyWrite + ?Sized>(&self, f: &mut W) -> WriteResult<W::Error> {
-        if self.is_empty() {
+    pub(crate) fn write_suffix<W: AnyWrite + ?Sized>(&self, f: &mut W) -> WriteResult<W::Error>
+    where
+        str: AsRef<W::Buf>,
+    {
+        // A style which only resets has nothing left to turn off afterwards.
+        if self.has_no_styling() || !is_enabled() {
             Ok(())
         } else {
-            write_fmt!(f, "{}", RESET)
+            write_str!(f, RESET)?;
+            instrument::emit(SequenceKind::Reset, RESET.len());
+            Ok(())
         }
     }
+
+    /// Write the codes that go *before* a piece of text straight to the
+    /// given byte writer, without going through [`fmt::Arguments`] (see
+    /// [`prefix`](Self::prefix)).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::Color::Red;
+    ///
+    /// let mut out = Vec::new();
+    /// Red.bold().write_prefix_bytes(&mut out).unwrap();
+    /// out.extend_from_slice(b"error");
+    /// Red.bold().write_suffix_bytes(&mut out).unwrap();
+    /// assert_eq!(out, b"\x1b[1;31merror\x1b[0m");
+    /// ```
+    pub fn write_prefix_bytes<W: io::Write>(&self, w: &mut W) -> io::Result<()> {
+        self.write_prefix(io_write!(w))
+    }
+
+    /// Write the codes that go *after* a piece of text straight to the
+    /// given byte writer (see [`suffix`](Self::suffix)).
+    pub fn write_suffix_bytes<W: io::Write>(&self, w: &mut W) -> io::Result<()> {
+        self.write_suffix(io_write!(w))
+    }
+
+    /// The codes that go *before* a piece of text, rendered once into a
+    /// buffer on the stack, to be written again and again without
+    /// rendering them each time, e.g. for every entry of a long listing
+    /// painted in the same style.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::Color::Blue;
+    ///
+    /// let dir = Blue.bold().prefix_str();
+    /// let listing: String = ["src", "tests"]
+    ///     .iter()
+    ///     .map(|name| format!("{}{}\x1b[0m ", dir, name))
+    ///     .collect();
+    /// assert_eq!(listing, "\x1b[1;34msrc\x1b[0m \x1b[1;34mtests\x1b[0m ");
+    /// assert_eq!(&*dir, Blue.bold().prefix().to_string());
+    /// ```
+    pub fn prefix_str(&self) -> PrefixStr {
+        let mut buf = PrefixStr {
+            bytes: [0; PREFIX_CAPACITY],
+            len: 0,
+        };
+        self.write_prefix_unreported(fmt_write!(&mut buf))
+            .expect("the longest prefix fits in the buffer");
+        buf
+    }
+
+    /// Number of bytes of the codes that go *before* a piece of text (see
+    /// [`prefix`](Self::prefix)), which take up no columns on the screen,
+    /// for output with a limited number of bytes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::Style;
+    /// use nu_ansi_term::Color::{Blue, Rgb};
+    ///
+    /// assert_eq!(Blue.bold().prefix_len(), "\x1b[1;34m".len());
+    /// assert_eq!(Rgb(255, 128, 0).normal().prefix_len(), 17);
+    /// assert_eq!(Style::new().prefix_len(), 0);
+    /// ```
+    pub fn prefix_len(&self) -> usize {
+        self.prefix_str().len()
+    }
+
+    /// Number of bytes of the codes that go *after* a piece of text (see
+    /// [`suffix`](Self::suffix)).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::Style;
+    /// use nu_ansi_term::Color::Blue;
+    ///
+    /// assert_eq!(Blue.bold().suffix_len(), "\x1b[0m".len());
+    /// assert_eq!(Style::new().suffix_len(), 0);
+    /// ```
+    pub fn suffix_len(&self) -> usize {
+        if self.has_no_styling() || !is_enabled() {
+            0
+        } else {
+            RESET.len()
+        }
+    }
+}
+
+/// Length of the longest prefix: a reset, every format code and three
+/// 24-bit colors.
+const PREFIX_CAPACITY: usize = 96;
+
+/// The prefix of a style, rendered into a buffer on the stack (see
+/// [`Style::prefix_str`]). Dereferences to `str`.
+#[derive(Clone, Copy)]
+pub struct PrefixStr {
+    bytes: [u8; PREFIX_CAPACITY],
+    len: usize,
+}
+
+impl PrefixStr {
+    /// The rendered prefix.
+    pub fn as_str(&self) -> &str {
+        std::str::from_utf8(&self.bytes[..self.len]).expect("only whole strings are written")
+    }
+}
+
+impl fmt::Write for PrefixStr {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let end = self.len + s.len();
+        if end > PREFIX_CAPACITY {
+            return Err(fmt::Error);
+        }
+        self.bytes[self.len..end].copy_from_slice(s.as_bytes());
+        self.len = end;
+        Ok(())
+    }
+}
+
+impl std::ops::Deref for PrefixStr {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<str> for PrefixStr {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
 }
 
-/// The code to send to reset all styles and return to `Style::default()`.
+impl fmt::Display for PrefixStr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl fmt::Debug for PrefixStr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+/// Write `n` in decimal, without going through [`fmt::Arguments`].
+fn write_u8<W: AnyWrite + ?Sized>(f: &mut W, n: u8) -> WriteResult<W::Error>
+where
+    str: AsRef<W::Buf>,
+{
+    const DIGITS: &str = "0123456789";
+    let digit = |d: u8| &DIGITS[d as usize..=d as usize];
+    if n >= 100 {
+        write_str!(f, digit(n / 100))?;
+    }
+    if n >= 10 {
+        write_str!(f, digit(n / 10 % 10))?;
+    }
+    write_str!(f, digit(n % 10))
+}
+
+/// Write the `code` introducing a color (`38`, `48` or `58`), followed by
+/// the parameters of `color`, which is a fixed or 24-bit color.
+fn write_extended<W: AnyWrite + ?Sized>(
+    f: &mut W,
+    code: &str,
+    color: Color,
+) -> WriteResult<W::Error>
+where
+    str: AsRef<W::Buf>,
+{
+    write_str!(f, code)?;
+    match color {
+        Color::Rgb(r, g, b) => {
+            write_str!(f, ";2;")?;
+            write_u8(f, r)?;
+            write_str!(f, ";")?;
+            write_u8(f, g)?;
+            write_str!(f, ";")?;
+            write_u8(f, b)
+        }
+        color => {
+            write_str!(f, ";5;")?;
+            write_u8(
+                f,
+                palette_index(color).expect("named and fixed colors are in the palette"),
+            )
+        }
+    }
+}
+
+/// The code to send to reset all styles and return to `Style::default()`
+/// (SGR 0).
+///
+/// # Examples
+///
+/// ```
+/// use nu_ansi_term::{Style, RESET};
+///
+/// assert_eq!(RESET, "\x1b[0m");
+/// assert_eq!(Style::reset().prefix().to_string(), RESET);
+/// ```
 pub static RESET: &str = "\x1B[0m";
 
+/// [`RESET`] as bytes, for writing to byte-oriented outputs.
+pub static RESET_BYTES: &[u8] = RESET.as_bytes();
+
 impl Color {
     fn write_foreground_code<W: AnyWrite + ?Sized>(&self, f: &mut W) -> WriteResult<W::Error>
     where
@@ -158,8 +436,7 @@ impl Color {
             Color::Magenta => write_str!(f, "35"),
             Color::Cyan => write_str!(f, "36"),
             Color::White => write_str!(f, "37"),
-            Color::Fixed(num) => write_fmt!(f, "38;5;{}", num),
-            Color::Rgb(r, g, b) => write_fmt!(f, "38;2;{};{};{}", r, g, b),
+            Color::Fixed(_) | Color::Rgb(..) => write_extended(f, "38", *self),
             Color::Default => write_str!(f, "39"),
             Color::DarkGray => write_str!(f, "90"),
             Color::LightRed => write_str!(f, "91"),
@@ -187,8 +464,7 @@ impl Color {
             Color::Magenta => write_str!(f, "45"),
             Color::Cyan => write_str!(f, "46"),
             Color::White => write_str!(f, "47"),
-            Color::Fixed(num) => write_fmt!(f, "48;5;{}", num),
-            Color::Rgb(r, g, b) => write_fmt!(f, "48;2;{};{};{}", r, g, b),
+            Color::Fixed(_) | Color::Rgb(..) => write_extended(f, "48", *self),
             Color::Default => write_str!(f, "49"),
             Color::DarkGray => write_str!(f, "100"),
             Color::LightRed => write_str!(f, "101"),
@@ -201,6 +477,18 @@ impl Color {
             Color::LightGray => write_str!(f, "107"),
         }
     }
+
+    /// Underline colors have no codes for the named colors, which are
+    /// written as their palette numbers instead.
+    fn write_underline_code<W: AnyWrite + ?Sized>(&self, f: &mut W) -> WriteResult<W::Error>
+    where
+        str: AsRef<W::Buf>,
+    {
+        match self {
+            Color::Default => write_str!(f, "59"),
+            color => write_extended(f, "58", *color),
+        }
+    }
 }
 
 /// Like `AnsiString`, but only displays the style prefix.
@@ -403,10 +691,7 @@ impl fmt::Display for Prefix {
 
 impl fmt::Display for Infix {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self.0.compute_delta(self.1) {
-            StyleDelta::ExtraStyles(style) => style.write_prefix(fmt_write!(f)),
-            StyleDelta::Empty => Ok(()),
-        }
+        fmt::Display::fmt(&self.0.compute_delta(self.1), f)
     }
 }
 
@@ -456,7 +741,6 @@ macro_rules! create_content_eq_tests {
 #[cfg(test)]
 #[cfg(not(feature = "gnu_legacy"))]
 mod test {
-    use crate::create_content_eq_tests;
     use crate::style::Color::*;
     use crate::style::Style;
     use crate::style_test;
@@ -502,6 +786,17 @@ mod test {
         [hidden: Style::new().hidden(), "hi", "\x1B[8mhi\x1B[0m"]
         [stricken: Style::new().strikethrough(), "hi", "\x1B[9mhi\x1B[0m"]
         [lr_on_lr: LightRed.on(LightRed), "hi", "\x1B[101;91mhi\x1B[0m"]
+        [rgb_digits: Rgb(0,9,10).normal().underline_color(Fixed(255)), "hi", "\x1B[38;2;0;9;10;58;5;255mhi\x1B[0m"]
+        @str_cmp [prefix_str: Rgb(1,2,3).on(Fixed(4)).prefix_str().to_string(), "\x1B[48;5;4;38;2;1;2;3m"]
+        @str_cmp [longest_prefix_str: Style {
+                formats: crate::FormatFlags::all(),
+                ..Style::new().reset_before_style()
+            }
+            .fg(Rgb(255, 255, 255))
+            .on(Rgb(255, 255, 255))
+            .underline_color(Rgb(255, 255, 255))
+            .prefix_str()
+            .len(), 84]
         @str_cmp [reset_format: Style::new().dimmed().infix(Style::new()).to_string(), "\x1B[0m"]
         @str_cmp [reset_then_style: White.dimmed().infix(White.normal()).to_string(), "\x1B[0m\x1B[37m"]
         @str_cmp [color_then_format: White.normal().infix(White.bold()).to_string(), "\x1B[1m"]
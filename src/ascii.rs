@@ -0,0 +1,237 @@
+use std::borrow::Cow;
+
+use crate::write::Content;
+use crate::{nested, AnsiGenericString, AnsiGenericStrings, AnsiString, AnsiStrings};
+
+/// The substitutions made by [`asciify`]: box drawing characters, quotes,
+/// dashes, arrows, symbols and blocks, each with the ASCII text written in
+/// its place.
+pub const SUBSTITUTIONS: &[(char, &str)] = &[
+    // Box drawing, in the sets of `BoxChars`.
+    ('─', "-"),
+    ('━', "-"),
+    ('═', "="),
+    ('│', "|"),
+    ('┃', "|"),
+    ('║', "|"),
+    ('┌', "+"),
+    ('┐', "+"),
+    ('└', "+"),
+    ('┘', "+"),
+    ('╭', "+"),
+    ('╮', "+"),
+    ('╰', "+"),
+    ('╯', "+"),
+    ('├', "+"),
+    ('┤', "+"),
+    ('┬', "+"),
+    ('┴', "+"),
+    ('┼', "+"),
+    ('┏', "+"),
+    ('┓', "+"),
+    ('┗', "+"),
+    ('┛', "+"),
+    ('┣', "+"),
+    ('┫', "+"),
+    ('┳', "+"),
+    ('┻', "+"),
+    ('╋', "+"),
+    ('╔', "+"),
+    ('╗', "+"),
+    ('╚', "+"),
+    ('╝', "+"),
+    ('╠', "+"),
+    ('╣', "+"),
+    ('╦', "+"),
+    ('╩', "+"),
+    ('╬', "+"),
+    // Punctuation.
+    ('‘', "'"),
+    ('’', "'"),
+    ('‚', "'"),
+    ('′', "'"),
+    ('“', "\""),
+    ('”', "\""),
+    ('„', "\""),
+    ('″', "\""),
+    ('‹', "<"),
+    ('›', ">"),
+    ('«', "<<"),
+    ('»', ">>"),
+    ('‐', "-"),
+    ('‑', "-"),
+    ('‒', "-"),
+    ('–', "-"),
+    ('—', "--"),
+    ('―', "--"),
+    ('…', "..."),
+    ('•', "*"),
+    ('·', "."),
+    ('\u{a0}', " "),
+    // Arrows.
+    ('→', "->"),
+    ('←', "<-"),
+    ('↔', "<->"),
+    ('⇒', "=>"),
+    ('⇐', "<="),
+    ('↑', "^"),
+    ('↓', "v"),
+    ('▶', ">"),
+    ('◀', "<"),
+    ('▲', "^"),
+    ('▼', "v"),
+    // Symbols.
+    ('−', "-"),
+    ('×', "x"),
+    ('÷', "/"),
+    ('±', "+/-"),
+    ('≤', "<="),
+    ('≥', ">="),
+    ('≠', "!="),
+    ('≈', "~"),
+    ('©', "(c)"),
+    ('®', "(R)"),
+    ('™', "(TM)"),
+    ('✓', "v"),
+    ('✔', "v"),
+    ('✗', "x"),
+    ('✘', "x"),
+    // Blocks, as drawn by progress bars and charts.
+    ('█', "#"),
+    ('▓', "#"),
+    ('▒', ":"),
+    ('░', "."),
+];
+
+/// `text` with the characters in `table` replaced by their ASCII text.
+/// Other characters are left as they are, so the result is only ASCII if
+/// the table has every character in `text`.
+///
+/// # Examples
+///
+/// ```
+/// use nu_ansi_term::ascii::{asciify, SUBSTITUTIONS};
+///
+/// assert_eq!(asciify("“done” → ok…", SUBSTITUTIONS), "\"done\" -> ok...");
+/// assert_eq!(asciify("…", &[('…', "~")]), "~");
+/// ```
+pub fn asciify<'t>(text: &'t str, table: &[(char, &str)]) -> Cow<'t, str> {
+    let substitute = |c: char| table.iter().find(|(from, _)| *from == c).map(|(_, to)| *to);
+    if text
+        .chars()
+        .all(|c| c.is_ascii() || substitute(c).is_none())
+    {
+        return Cow::Borrowed(text);
+    }
+    let mut ascii = String::with_capacity(text.len());
+    for c in text.chars() {
+        match substitute(c) {
+            Some(to) => ascii.push_str(to),
+            None => ascii.push(c),
+        }
+    }
+    Cow::Owned(ascii)
+}
+
+impl<'a> AnsiGenericString<'a, str> {
+    /// An owned copy of this string, keeping its style and link, with the
+    /// characters in [`SUBSTITUTIONS`] replaced by ASCII. Formatting
+    /// arguments are rendered in the process.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::Color::Green;
+    ///
+    /// assert_eq!(
+    ///     Green.paint("✓ passed — 3 tests").asciify().to_string(),
+    ///     "\x1b[32mv passed -- 3 tests\x1b[0m"
+    /// );
+    /// ```
+    pub fn asciify(&self) -> AnsiString<'static> {
+        self.asciify_with(SUBSTITUTIONS)
+    }
+
+    /// An owned copy of this string with the characters in `table`
+    /// replaced (see [`asciify`]).
+    pub fn asciify_with(&self, table: &[(char, &str)]) -> AnsiString<'static> {
+        let content = match self.content() {
+            Content::FmtArgs(args) => {
+                let text = nested::within(self.style, || args.to_string());
+                Content::owned(asciify(&text, table).into_owned())
+            }
+            Content::StrLike(s) => Content::owned(asciify(s, table).into_owned()),
+            Content::Shared(s) => match asciify(s, table) {
+                Cow::Borrowed(_) => Content::Shared(s.clone()),
+                Cow::Owned(ascii) => Content::owned(ascii),
+            },
+            Content::GenericStrings(strings) => {
+                Content::GenericStrings(strings.asciify_with(table))
+            }
+        };
+        self.with_content(content)
+    }
+}
+
+impl<'a> AnsiGenericStrings<'a, str> {
+    /// An owned copy of these strings, keeping their styles and links,
+    /// with the characters in [`SUBSTITUTIONS`] replaced by ASCII, so that
+    /// the output remains legible on consoles which can only show ASCII.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::{AnsiStrings, BoxChars, Style};
+    /// use nu_ansi_term::Color::Blue;
+    ///
+    /// let top = BoxChars::ROUNDED.top(6, Blue.normal());
+    /// let title = AnsiStrings([top, Style::new().paint(" «logs»")]);
+    /// assert_eq!(
+    ///     title.asciify().to_string(),
+    ///     "\x1b[34m+----+\x1b[0m <<logs>>"
+    /// );
+    /// ```
+    pub fn asciify(&self) -> AnsiStrings<'static> {
+        self.asciify_with(SUBSTITUTIONS)
+    }
+
+    /// An owned copy of these strings with the characters in `table`
+    /// replaced (see [`asciify`]).
+    pub fn asciify_with(&self, table: &[(char, &str)]) -> AnsiStrings<'static> {
+        self.map_owned(|s| s.asciify_with(table))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Color::*;
+
+    #[test]
+    fn nested_and_linked() {
+        let inner = Blue.paint("→");
+        let ascii = AnsiStrings([
+            Red.paint(format_args!("a {} b", inner)),
+            Green.paint(AnsiStrings([Yellow.paint("“c”"), "é".into()])),
+            Red.paint("docs…").hyperlink("https://example.com/…"),
+        ])
+        .asciify()
+        .to_string();
+        assert_eq!(
+            ascii,
+            AnsiStrings([
+                Red.paint(format_args!("a {} b", Blue.paint("->"))),
+                Green.paint(AnsiStrings([Yellow.paint("\"c\""), "é".into()])),
+                Red.paint("docs...").hyperlink("https://example.com/…"),
+            ])
+            .to_string()
+        );
+    }
+
+    #[test]
+    fn unchanged_text() {
+        assert!(matches!(asciify("plain", SUBSTITUTIONS), Cow::Borrowed(_)));
+        assert!(matches!(asciify("naïve", SUBSTITUTIONS), Cow::Borrowed(_)));
+        assert_eq!(asciify("a—b", &[]), "a—b");
+    }
+}
@@ -0,0 +1,192 @@
+use crate::difference::OptimizeFor;
+use crate::enabled::is_enabled;
+use crate::nested;
+use crate::{io_write, AnsiByteString, AnsiByteStrings, TerminalProfile};
+use std::future::Future;
+use std::io;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+/// How many rendered bytes are gathered before they are written, when
+/// streaming a sequence.
+const CHUNK: usize = 8 * 1024;
+
+/// Render the escape codes and bytes written by `write` into a buffer.
+fn render(write: impl FnOnce(&mut Vec<u8>) -> io::Result<()>) -> io::Result<Vec<u8>> {
+    let mut buffer = Vec::new();
+    write(&mut buffer).map(|()| buffer)
+}
+
+/// A future writing `rendered` to `w`.
+///
+/// Strings are rendered before the future is created, so the future only
+/// holds the buffer and the writer: it does not borrow the strings (whose
+/// content may not be [`Send`]), and can be spawned on a multi-threaded
+/// runtime if the writer can.
+async fn write_rendered<W: AsyncWrite + Unpin + ?Sized>(
+    w: &mut W,
+    rendered: io::Result<Vec<u8>>,
+) -> io::Result<()> {
+    w.write_all(&rendered?).await
+}
+
+/// Write `strings` to `w`, rendering a few segments at a time into a buffer
+/// of about [`CHUNK`] bytes (or one segment, if that is longer) and writing
+/// it out before rendering more.
+async fn write_streamed<W: AsyncWrite + Unpin + ?Sized>(
+    strings: &AnsiByteStrings<'_>,
+    w: &mut W,
+) -> io::Result<()> {
+    let resolved;
+    let strings = if strings.has_lazy_styles() {
+        resolved = strings.resolved(&TerminalProfile::rendering());
+        &resolved
+    } else {
+        strings
+    };
+    if !is_enabled() || nested::enclosing_style().is_some() {
+        return w.write_all(&strings.to_vec()).await;
+    }
+
+    let mut buffer = Vec::with_capacity(CHUNK);
+    let mut segments = strings.segment_writer(OptimizeFor::Transitions);
+    while segments.write_next(io_write!(&mut buffer))? {
+        if buffer.len() >= CHUNK {
+            w.write_all(&buffer).await?;
+            buffer.clear();
+        }
+    }
+    segments.finish(io_write!(&mut buffer))?;
+    w.write_all(&buffer).await
+}
+
+impl<'a> AnsiByteString<'a> {
+    /// Write an `AnsiByteString` to a [`tokio::io::AsyncWrite`], as
+    /// [`write_to`](Self::write_to) does to an `io::Write`.
+    ///
+    /// The string is rendered when this is called, and the returned future
+    /// only writes the rendered bytes, so it does not borrow the string.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::Color::Red;
+    /// # async fn log(socket: &mut (impl tokio::io::AsyncWrite + Unpin)) -> std::io::Result<()> {
+    /// Red.paint(&b"connection refused\n"[..]).write_to_async(socket).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn write_to_async<'w, W: AsyncWrite + Unpin + ?Sized>(
+        &self,
+        w: &'w mut W,
+    ) -> impl Future<Output = io::Result<()>> + 'w {
+        write_rendered(w, render(|buffer| self.write_to(buffer)))
+    }
+}
+
+impl<'a> AnsiByteStrings<'a> {
+    /// Write `AnsiByteStrings` to a [`tokio::io::AsyncWrite`], as
+    /// [`write_to`](Self::write_to) does to an `io::Write`.
+    ///
+    /// The strings are rendered a few kilobytes at a time as the future is
+    /// polled, rather than all at once, so the future borrows them and is
+    /// not `Send`. To write from a task on a multi-threaded runtime, render
+    /// the strings with [`to_vec`](Self::to_vec) and write the bytes.
+    pub async fn write_to_async<W: AsyncWrite + Unpin + ?Sized>(
+        &self,
+        w: &mut W,
+    ) -> io::Result<()> {
+        write_streamed(self, w).await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::CHUNK;
+    use crate::AnsiByteStrings;
+    use crate::Color::*;
+    use std::future::Future;
+    use std::io;
+    use std::pin::Pin;
+    use std::sync::Arc;
+    use std::task::{Context, Poll, Wake, Waker};
+    use tokio::io::AsyncWrite;
+
+    /// Poll a future which never waits (as writing to a `Vec` does not) to
+    /// completion.
+    fn ready<F: Future>(future: F) -> F::Output {
+        struct Noop;
+        impl Wake for Noop {
+            fn wake(self: Arc<Self>) {}
+        }
+        let waker = Waker::from(Arc::new(Noop));
+        match Box::pin(future)
+            .as_mut()
+            .poll(&mut Context::from_waker(&waker))
+        {
+            Poll::Ready(output) => output,
+            Poll::Pending => panic!("writing to a Vec should not wait"),
+        }
+    }
+
+    fn assert_send<T: Send>(value: T) -> T {
+        value
+    }
+
+    /// A writer remembering the size of each write.
+    #[derive(Default)]
+    struct Writes {
+        bytes: Vec<u8>,
+        sizes: Vec<usize>,
+    }
+
+    impl AsyncWrite for Writes {
+        fn poll_write(
+            mut self: Pin<&mut Self>,
+            _: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            self.bytes.extend_from_slice(buf);
+            self.sizes.push(buf.len());
+            Poll::Ready(Ok(buf.len()))
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_shutdown(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    #[test]
+    fn writes_the_same_bytes_as_write_to() {
+        let strings = AnsiByteStrings([Red.paint(&b"a"[..]), Red.bold().paint(&b"b"[..])]);
+        let mut expected = Vec::new();
+        strings.write_to(&mut expected).unwrap();
+
+        let mut written = Vec::new();
+        ready(strings.write_to_async(&mut written)).unwrap();
+        assert_eq!(written, expected);
+
+        let mut single = Vec::new();
+        ready(assert_send(
+            Blue.paint(&b"c"[..]).write_to_async(&mut single),
+        ))
+        .unwrap();
+        assert_eq!(single, b"\x1b[34mc\x1b[0m");
+    }
+
+    #[test]
+    fn long_sequences_are_written_in_chunks() {
+        let line = &b"0123456789abcdef\n"[..];
+        let strings: AnsiByteStrings = (0..4096)
+            .map(|ix| [Red, Green, Blue][ix % 3].paint(line))
+            .collect();
+        let mut writes = Writes::default();
+        ready(strings.write_to_async(&mut writes)).unwrap();
+        assert_eq!(writes.bytes, strings.to_vec());
+        assert!(writes.sizes.len() > 1);
+        assert!(writes.sizes.iter().all(|&size| size < CHUNK + 32));
+    }
+}
@@ -0,0 +1,79 @@
+//! The part of the crate which language bindings (such as pyo3 or napi
+//! modules) are meant to wrap: the style model, the computation of the
+//! codes between styles, and rendering into a caller supplied writer.
+//!
+//! Nothing reachable from here writes to stdout or stderr, or reads the
+//! environment: output only goes to the [`AnyWrite`] implementor (or
+//! `String`) it is rendered into, and terminal capabilities only come from
+//! the [`TerminalProfile`] passed in, or the one set with
+//! [`TerminalProfile::set_render_default`]. The functions which do read the
+//! environment are at the edge of the crate, for applications to call
+//! themselves:
+//!
+//! * [`TerminalProfile::detect`] and [`ColorLevel::detect`],
+//! * [`enabled_from_env`](crate::enabled_from_env),
+//! * [`Theme::from_env`](crate::Theme::from_env),
+//! * the [`terminal`](crate::terminal) size queries.
+//!
+//! A binding renders a document in three steps: build the segments from
+//! styles (or from their [`bits`](crate::bits) encoding) and text, adapt
+//! them to the capabilities of the target, and render them.
+//!
+//! ```
+//! use nu_ansi_term::bindings::{render, AnsiStrings, Color, ColorLevel, Style, TerminalProfile};
+//!
+//! // As received from the other side of the binding.
+//! let bits = Color::Rgb(200, 0, 0).bold().to_bits();
+//!
+//! let style = Style::from_bits(bits).unwrap();
+//! let strings = AnsiStrings([style.paint("hello")]);
+//! let profile = TerminalProfile::new().with_colors(ColorLevel::Ansi16);
+//! assert_eq!(render(&strings, &profile), "\x1b[1;31mhello\x1b[0m");
+//! ```
+
+use crate::fmt_write;
+pub use crate::{
+    AnsiGenericString, AnsiGenericStrings, AnsiString, AnsiStrings, AnyWrite, Color, ColorLevel,
+    Content, Style, StyleDelta, TerminalProfile,
+};
+use std::fmt;
+
+/// Render `strings` for a terminal with `profile` (see
+/// [`AnsiGenericStrings::write_for`]): lazily resolved styles are resolved
+/// for it, colors are downgraded to the ones it supports, and OSC sequences
+/// it does not support are left out.
+pub fn render(strings: &AnsiStrings<'_>, profile: &TerminalProfile) -> String {
+    let mut rendered = String::new();
+    strings
+        .write_for(profile, fmt_write!(&mut rendered))
+        .expect("writing to a String cannot fail");
+    rendered
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Color::*;
+
+    #[test]
+    fn render_leaves_out_what_the_profile_does_not_support() {
+        let rendered = |profile: &TerminalProfile| {
+            let link = Blue.paint("x").hyperlink("https://a");
+            render(
+                &AnsiStrings([
+                    AnsiString::title("t"),
+                    Green.paint(AnsiStrings([link.clone()])),
+                    link.clone(),
+                    Red.paint(format_args!("({})", link)),
+                ]),
+                profile,
+            )
+        };
+        let profile = TerminalProfile::new().without_hyperlinks().without_titles();
+        assert_eq!(
+            rendered(&profile),
+            "\x1b[32m\x1b[34mx\x1b[0m\x1b[34mx\x1b[31m(\x1b[34mx\x1b[31m)\x1b[0m"
+        );
+        assert!(rendered(&TerminalProfile::new()).contains("\x1b]8;;https://a"));
+    }
+}
@@ -0,0 +1,290 @@
+//! A compact encoding of [`Style`] as a `u64`, for FFI and plugin protocols
+//! which transmit styles without rendering and parsing escape codes.
+//!
+//! The layout is stable, with the least significant bit first:
+//!
+//! | bits    | meaning                                                         |
+//! |---------|-----------------------------------------------------------------|
+//! | 0–7     | bold, dimmed, italic, underline, blink, reverse, hidden, strikethrough |
+//! | 8       | reset before the style                                          |
+//! | 9–11    | double underline, undercurl, overline                           |
+//! | 12–37   | foreground color                                                |
+//! | 38–63   | background color                                                |
+//!
+//! A color takes 26 bits: the top two bits are its kind and the other 24 its
+//! value.
+//!
+//! | kind | color                 | value                                   |
+//! |------|-----------------------|-----------------------------------------|
+//! | 0    | none                  | 0                                       |
+//! | 1    | named                 | its index in declaration order of [`Color`] (`Black` is 0, `LightGray` 17, `Default` 18) |
+//! | 2    | [`Color::Fixed`]      | the color number                        |
+//! | 3    | [`Color::Rgb`]        | `red << 16 \| green << 8 \| blue`       |
+//!
+//! Formats which are explicitly turned off (see
+//! [`Style::clear_formats`]) only matter when inheriting styles, and are not
+//! encoded. Neither is the underline color (see
+//! [`Style::underline_color`]), for which there is no room left:
+//! [`Style::try_to_bits`] rejects styles which have one, and
+//! [`Style::underline_color_bits`] encodes it separately, in the 26 bits of
+//! a color.
+//!
+//! An encoding with an unknown kind of color, or an unknown named color, may
+//! come from a newer version of this crate: [`Style::from_bits`] rejects it,
+//! and [`Style::from_bits_truncate`] ignores whatever it does not
+//! understand.
+
+use crate::style::{Coloring, FormatFlags};
+use crate::{Color, Style};
+
+/// The formats, in the order of their bits.
+const FORMATS: [FormatFlags; 8] = [
+    FormatFlags::BOLD,
+    FormatFlags::DIMMED,
+    FormatFlags::ITALIC,
+    FormatFlags::UNDERLINE,
+    FormatFlags::BLINK,
+    FormatFlags::REVERSE,
+    FormatFlags::HIDDEN,
+    FormatFlags::STRIKETHROUGH,
+];
+
+/// The named colors, in the order of their values.
+const NAMED: [Color; 19] = [
+    Color::Black,
+    Color::DarkGray,
+    Color::Red,
+    Color::LightRed,
+    Color::Green,
+    Color::LightGreen,
+    Color::Yellow,
+    Color::LightYellow,
+    Color::Blue,
+    Color::LightBlue,
+    Color::Purple,
+    Color::LightPurple,
+    Color::Magenta,
+    Color::LightMagenta,
+    Color::Cyan,
+    Color::LightCyan,
+    Color::White,
+    Color::LightGray,
+    Color::Default,
+];
+
+/// The formats added after the reset bit, in the order of their bits.
+const EXTENDED_FORMATS: [FormatFlags; 3] = [
+    FormatFlags::DOUBLE_UNDERLINE,
+    FormatFlags::UNDERCURL,
+    FormatFlags::OVERLINE,
+];
+
+const RESET_BIT: u64 = 1 << 8;
+const EXTENDED_SHIFT: usize = 9;
+const FG_SHIFT: u32 = 12;
+const BG_SHIFT: u32 = 38;
+const COLOR_BITS: u64 = (1 << 26) - 1;
+
+fn encode_color(color: Option<Color>) -> u64 {
+    let (kind, value) = match color {
+        None => (0, 0),
+        Some(Color::Fixed(n)) => (2, n as u64),
+        Some(Color::Rgb(r, g, b)) => (3, (r as u64) << 16 | (g as u64) << 8 | b as u64),
+        Some(named) => {
+            let index = NAMED.iter().position(|c| *c == named);
+            (1, index.expect("every other color is named") as u64)
+        }
+    };
+    kind << 24 | value
+}
+
+/// The color encoded in `bits`, or `Err` if it is not understood.
+fn decode_color(bits: u64) -> Result<Option<Color>, ()> {
+    let value = bits & 0xFF_FFFF;
+    match bits >> 24 {
+        0 if value == 0 => Ok(None),
+        1 => NAMED.get(value as usize).copied().map(Some).ok_or(()),
+        2 if value <= 0xFF => Ok(Some(Color::Fixed(value as u8))),
+        3 => Ok(Some(Color::Rgb(
+            (value >> 16) as u8,
+            (value >> 8) as u8,
+            value as u8,
+        ))),
+        _ => Err(()),
+    }
+}
+
+impl Style {
+    /// Encode this style as a `u64`, in the layout described in the
+    /// [`bits`](crate::bits) module.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::Style;
+    /// use nu_ansi_term::Color::{Blue, Fixed};
+    ///
+    /// let style = Blue.on(Fixed(236)).bold().underline();
+    /// let bits = style.to_bits();
+    /// assert_eq!(bits & 0xFF, 0b1001);
+    /// assert_eq!(Style::from_bits(bits), Some(style));
+    /// ```
+    pub fn to_bits(&self) -> u64 {
+        let mut bits = 0;
+        let extended = EXTENDED_FORMATS.iter().zip(EXTENDED_SHIFT..);
+        for (flag, ix) in FORMATS.iter().zip(0..).chain(extended) {
+            if self.formats.contains(*flag) {
+                bits |= 1 << ix;
+            }
+        }
+        if self.prefix_before_reset {
+            bits |= RESET_BIT;
+        }
+        bits | encode_color(self.coloring.fg) << FG_SHIFT
+            | encode_color(self.coloring.bg) << BG_SHIFT
+    }
+
+    /// Encode this style as a `u64` (see [`to_bits`](Style::to_bits)), or
+    /// `None` if it has an underline color, which would be lost.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::Color::{Blue, Red};
+    ///
+    /// assert_eq!(Red.underline().try_to_bits(), Some(Red.underline().to_bits()));
+    /// assert_eq!(Red.underline().underline_color(Blue).try_to_bits(), None);
+    /// ```
+    pub fn try_to_bits(&self) -> Option<u64> {
+        match self.coloring.underline {
+            Some(_) => None,
+            None => Some(self.to_bits()),
+        }
+    }
+
+    /// Encode the underline color of this style in the 26 bits of a color
+    /// (see the [`bits`](crate::bits) module), 0 if it has none. Decode it
+    /// with [`with_underline_color_bits`](Style::with_underline_color_bits).
+    pub fn underline_color_bits(&self) -> u64 {
+        encode_color(self.coloring.underline)
+    }
+
+    /// This style with the underline color encoded in `bits` by
+    /// [`underline_color_bits`](Style::underline_color_bits), or without one
+    /// if `bits` is 0 or not understood.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::Style;
+    /// use nu_ansi_term::Color::{Rgb, Yellow};
+    ///
+    /// let style = Yellow.undercurl().underline_color(Rgb(255, 0, 0));
+    /// let (bits, underline) = (style.to_bits(), style.underline_color_bits());
+    /// assert_eq!(
+    ///     Style::from_bits_truncate(bits).with_underline_color_bits(underline),
+    ///     style
+    /// );
+    /// ```
+    pub fn with_underline_color_bits(self, bits: u64) -> Style {
+        let color = decode_color(bits & COLOR_BITS).unwrap_or(None);
+        self.set_underline_color(color)
+    }
+
+    /// Decode a style encoded by [`to_bits`](Style::to_bits), or `None` if
+    /// `bits` uses reserved values.
+    pub fn from_bits(bits: u64) -> Option<Style> {
+        let fg = decode_color(bits >> FG_SHIFT & COLOR_BITS).ok()?;
+        let bg = decode_color(bits >> BG_SHIFT & COLOR_BITS).ok()?;
+        Some(Self::from_parts(bits, fg, bg))
+    }
+
+    /// Decode a style encoded by [`to_bits`](Style::to_bits), leaving out
+    /// colors which are not understood.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::Style;
+    /// use nu_ansi_term::Color::Red;
+    ///
+    /// let from_the_future = Red.bold().to_bits() | (1 << 24 | 19) << 38;
+    /// assert_eq!(Style::from_bits(from_the_future), None);
+    /// assert_eq!(Style::from_bits_truncate(from_the_future), Red.bold());
+    /// ```
+    pub fn from_bits_truncate(bits: u64) -> Style {
+        let fg = decode_color(bits >> FG_SHIFT & COLOR_BITS).unwrap_or(None);
+        let bg = decode_color(bits >> BG_SHIFT & COLOR_BITS).unwrap_or(None);
+        Self::from_parts(bits, fg, bg)
+    }
+
+    fn from_parts(bits: u64, fg: Option<Color>, bg: Option<Color>) -> Style {
+        let mut formats = FormatFlags::empty();
+        let extended = EXTENDED_FORMATS.iter().zip(EXTENDED_SHIFT..);
+        for (flag, ix) in FORMATS.iter().zip(0..).chain(extended) {
+            if bits & 1 << ix != 0 {
+                formats |= *flag;
+            }
+        }
+        Style {
+            prefix_before_reset: bits & RESET_BIT != 0,
+            formats,
+            cleared: FormatFlags::empty(),
+            coloring: Coloring {
+                fg,
+                bg,
+                underline: None,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Color::*;
+
+    #[test]
+    fn round_trip() {
+        let styles = [
+            Style::new(),
+            Style::reset(),
+            Red.normal(),
+            Default.on(LightGray).italic(),
+            Fixed(255).on(Rgb(1, 2, 3)).strikethrough().hidden(),
+            Rgb(255, 255, 255).blink().reverse().dimmed(),
+            Blue.undercurl().overline().double_underline(),
+            Red.underline().underline_color(Fixed(3)),
+        ];
+        for style in styles {
+            let decoded = Style::from_bits(style.to_bits())
+                .unwrap()
+                .with_underline_color_bits(style.underline_color_bits());
+            assert_eq!(decoded, style);
+            assert_eq!(decoded.is_fg(), style.is_fg());
+            assert_eq!(decoded.is_bg(), style.is_bg());
+            assert_eq!(decoded.prefix_before_reset, style.prefix_before_reset);
+            assert_eq!(
+                style.try_to_bits().is_some(),
+                style.is_underline_color().is_none()
+            );
+        }
+    }
+
+    #[test]
+    fn layout() {
+        assert_eq!(Style::new().to_bits(), 0);
+        assert_eq!(Style::new().strikethrough().to_bits(), 1 << 7);
+        assert_eq!(Style::new().overline().to_bits(), 1 << 11);
+        assert_eq!(Red.normal().to_bits(), (1 << 24 | 2) << 12);
+        assert_eq!(Style::new().on(Fixed(7)).to_bits(), (2 << 24 | 7) << 38);
+    }
+
+    #[test]
+    fn unknown_colors() {
+        let unknown_named = (1 << 24 | 19) << 12;
+        assert_eq!(Style::from_bits(unknown_named), None);
+        assert_eq!(Style::from_bits_truncate(unknown_named), Style::new());
+        assert_eq!(Style::from_bits((2 << 24 | 256) << 38), None);
+    }
+}
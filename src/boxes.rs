@@ -0,0 +1,202 @@
+use crate::{AnsiString, Style, TerminalProfile};
+
+/// A set of characters for drawing boxes, rules and table borders.
+///
+/// # Examples
+///
+/// ```
+/// use nu_ansi_term::{BoxChars, TerminalProfile};
+/// use nu_ansi_term::Color::Blue;
+///
+/// let chars = BoxChars::ROUNDED.for_profile(&TerminalProfile::ascii());
+/// assert_eq!(chars, BoxChars::ASCII);
+///
+/// let style = Blue.normal();
+/// println!("{}", BoxChars::LIGHT.top(10, style));
+/// println!("{}", BoxChars::LIGHT.separator(10, style));
+/// println!("{}", BoxChars::LIGHT.bottom(10, style));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BoxChars {
+    /// Horizontal line.
+    pub horizontal: char,
+    /// Vertical line.
+    pub vertical: char,
+    /// Top left corner.
+    pub top_left: char,
+    /// Top right corner.
+    pub top_right: char,
+    /// Bottom left corner.
+    pub bottom_left: char,
+    /// Bottom right corner.
+    pub bottom_right: char,
+    /// Vertical line with a branch to the right (`├`).
+    pub left_tee: char,
+    /// Vertical line with a branch to the left (`┤`).
+    pub right_tee: char,
+    /// Horizontal line with a branch downwards (`┬`).
+    pub top_tee: char,
+    /// Horizontal line with a branch upwards (`┴`).
+    pub bottom_tee: char,
+    /// Crossing lines (`┼`).
+    pub cross: char,
+}
+
+impl BoxChars {
+    /// Light lines: `┌─┬─┐`.
+    pub const LIGHT: BoxChars = BoxChars {
+        horizontal: '─',
+        vertical: '│',
+        top_left: '┌',
+        top_right: '┐',
+        bottom_left: '└',
+        bottom_right: '┘',
+        left_tee: '├',
+        right_tee: '┤',
+        top_tee: '┬',
+        bottom_tee: '┴',
+        cross: '┼',
+    };
+
+    /// Light lines with rounded corners: `╭─┬─╮`.
+    pub const ROUNDED: BoxChars = BoxChars {
+        top_left: '╭',
+        top_right: '╮',
+        bottom_left: '╰',
+        bottom_right: '╯',
+        ..BoxChars::LIGHT
+    };
+
+    /// Heavy lines: `┏━┳━┓`.
+    pub const HEAVY: BoxChars = BoxChars {
+        horizontal: '━',
+        vertical: '┃',
+        top_left: '┏',
+        top_right: '┓',
+        bottom_left: '┗',
+        bottom_right: '┛',
+        left_tee: '┣',
+        right_tee: '┫',
+        top_tee: '┳',
+        bottom_tee: '┻',
+        cross: '╋',
+    };
+
+    /// Double lines: `╔═╦═╗`.
+    pub const DOUBLE: BoxChars = BoxChars {
+        horizontal: '═',
+        vertical: '║',
+        top_left: '╔',
+        top_right: '╗',
+        bottom_left: '╚',
+        bottom_right: '╝',
+        left_tee: '╠',
+        right_tee: '╣',
+        top_tee: '╦',
+        bottom_tee: '╩',
+        cross: '╬',
+    };
+
+    /// Plain ASCII: `+-+-+`, for terminals which cannot display the others.
+    pub const ASCII: BoxChars = BoxChars {
+        horizontal: '-',
+        vertical: '|',
+        top_left: '+',
+        top_right: '+',
+        bottom_left: '+',
+        bottom_right: '+',
+        left_tee: '+',
+        right_tee: '+',
+        top_tee: '+',
+        bottom_tee: '+',
+        cross: '+',
+    };
+
+    /// Use these characters if `profile` supports unicode, otherwise fall
+    /// back to [`BoxChars::ASCII`].
+    pub const fn for_profile(self, profile: &TerminalProfile) -> BoxChars {
+        if profile.unicode {
+            self
+        } else {
+            BoxChars::ASCII
+        }
+    }
+
+    /// Use these characters if the detected terminal supports unicode,
+    /// otherwise fall back to [`BoxChars::ASCII`]. See
+    /// [`TerminalProfile::detect`].
+    pub fn detect(self) -> BoxChars {
+        self.for_profile(&TerminalProfile::detect())
+    }
+
+    /// A horizontal rule `width` columns wide.
+    pub fn rule(&self, width: usize, style: Style) -> AnsiString<'static> {
+        self.line(self.horizontal, self.horizontal, width, style)
+    }
+
+    /// The top border of a box `width` columns wide.
+    pub fn top(&self, width: usize, style: Style) -> AnsiString<'static> {
+        self.line(self.top_left, self.top_right, width, style)
+    }
+
+    /// A horizontal separator inside a box `width` columns wide.
+    pub fn separator(&self, width: usize, style: Style) -> AnsiString<'static> {
+        self.line(self.left_tee, self.right_tee, width, style)
+    }
+
+    /// The bottom border of a box `width` columns wide.
+    pub fn bottom(&self, width: usize, style: Style) -> AnsiString<'static> {
+        self.line(self.bottom_left, self.bottom_right, width, style)
+    }
+
+    /// A horizontal line `width` columns wide, starting with `start` and ending
+    /// with `end` (as long as there is room for them).
+    fn line(&self, start: char, end: char, width: usize, style: Style) -> AnsiString<'static> {
+        let line: String = (0..width)
+            .map(|ix| match ix {
+                0 => start,
+                ix if ix + 1 == width => end,
+                _ => self.horizontal,
+            })
+            .collect();
+        style.paint(line)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Color::*;
+
+    #[test]
+    fn borders() {
+        let chars = BoxChars::LIGHT;
+        let style = Style::new();
+        assert_eq!(chars.top(5, style).to_string(), "┌───┐");
+        assert_eq!(chars.separator(3, style).to_string(), "├─┤");
+        assert_eq!(chars.bottom(2, style).to_string(), "└┘");
+        assert_eq!(chars.rule(3, style).to_string(), "───");
+        assert_eq!(chars.top(1, style).to_string(), "┌");
+        assert_eq!(chars.top(0, style).to_string(), "");
+    }
+
+    #[test]
+    fn styled() {
+        assert_eq!(
+            BoxChars::DOUBLE.top(3, Red.normal()).to_string(),
+            "\x1b[31m╔═╗\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn ascii_fallback() {
+        assert_eq!(
+            BoxChars::HEAVY.for_profile(&TerminalProfile::ascii()),
+            BoxChars::ASCII
+        );
+        assert_eq!(
+            BoxChars::HEAVY.for_profile(&TerminalProfile::new()),
+            BoxChars::HEAVY
+        );
+    }
+}
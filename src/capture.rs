@@ -0,0 +1,208 @@
+use crate::parse::{apply_sgr_noting, tokenize, SgrFidelity, Token};
+use crate::write::{AnyWrite, WriteResult};
+use crate::Style;
+use std::fmt;
+
+/// A piece of what was written to a [`CaptureWriter`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Captured {
+    /// Text, written in `style`. Text written in the same style without
+    /// anything in between is one piece, however it was split up.
+    Text { style: Style, content: String },
+
+    /// An escape sequence other than an SGR code, such as a cursor movement
+    /// or the OSC sequences around a hyperlink, as it was written. SGR codes
+    /// which a [`Style`] cannot represent exactly (a dotted underline, or
+    /// rapid blink, say) are recorded here too, after being applied to the
+    /// style as closely as it allows, so that they are not lost silently.
+    Control(String),
+}
+
+/// Writer which records what is written to it as styled text rather than as
+/// escape codes, so that tests of rendering code can check which text ends
+/// up in which style without depending on how the styles are encoded.
+///
+/// Write to it with [`write_to_any`](crate::AnsiGenericStrings::write_to_any),
+/// or as a [`fmt::Write`] implementor, then read back what was written with
+/// [`captured`](Self::captured).
+///
+/// What was written is read back with [`parse`](crate::parse), so the
+/// styles are those a terminal would show, whichever codes produced them.
+/// Formatting which has no [`Style`] of its own is recorded as a
+/// [`Captured::Control`] beside the text.
+///
+/// # Examples
+///
+/// ```
+/// use nu_ansi_term::{AnsiStrings, CaptureWriter, Captured, Style};
+/// use nu_ansi_term::Color::Red;
+///
+/// let mut capture = CaptureWriter::new();
+/// AnsiStrings([Red.bold().paint("error"), ": ".into(), Red.bold().paint("!")])
+///     .write_to_any(&mut capture)
+///     .unwrap();
+///
+/// assert_eq!(
+///     capture.captured(),
+///     [
+///         Captured::Text { style: Red.bold(), content: "error".into() },
+///         Captured::Text { style: Style::new(), content: ": ".into() },
+///         Captured::Text { style: Red.bold(), content: "!".into() },
+///     ]
+/// );
+/// assert_eq!(capture.text(), "error: !");
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CaptureWriter {
+    raw: String,
+}
+
+impl CaptureWriter {
+    /// Create a writer which has recorded nothing.
+    pub const fn new() -> Self {
+        Self { raw: String::new() }
+    }
+
+    /// Everything written so far, escape codes included.
+    pub fn raw(&self) -> &str {
+        &self.raw
+    }
+
+    /// The text written so far, without escape sequences.
+    pub fn text(&self) -> String {
+        self.captured()
+            .into_iter()
+            .filter_map(|captured| match captured {
+                Captured::Text { content, .. } => Some(content),
+                Captured::Control(_) => None,
+            })
+            .collect()
+    }
+
+    /// The text and escape sequences written so far, in order. Text is in
+    /// the style the codes written before it leave the terminal in, so it is
+    /// the same however the codes are minimized.
+    pub fn captured(&self) -> Vec<Captured> {
+        let mut captured = Vec::new();
+        let mut style = Style::new();
+        for token in tokenize(self.raw.as_bytes()) {
+            match token {
+                Token::Text(range) => match captured.last_mut() {
+                    Some(Captured::Text {
+                        style: last,
+                        content,
+                    }) if *last == style => content.push_str(&self.raw[range]),
+                    _ => captured.push(Captured::Text {
+                        style,
+                        content: self.raw[range].to_owned(),
+                    }),
+                },
+                Token::Sgr(params) => {
+                    let mut lossy = false;
+                    style = apply_sgr_noting(style, &self.raw[params.clone()], |fidelity| {
+                        lossy |= fidelity == SgrFidelity::Lossy;
+                    });
+                    if lossy {
+                        // The parameters follow `ESC [` and end before `m`.
+                        let sequence = params.start - 2..params.end + 1;
+                        captured.push(Captured::Control(self.raw[sequence].to_owned()));
+                    }
+                }
+                Token::Osc(_, sequence) | Token::Other(sequence) => {
+                    captured.push(Captured::Control(self.raw[sequence].to_owned()))
+                }
+            }
+        }
+        captured
+    }
+
+    /// Forget everything written so far.
+    pub fn clear(&mut self) {
+        self.raw.clear();
+    }
+}
+
+impl fmt::Write for CaptureWriter {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.raw.push_str(s);
+        Ok(())
+    }
+}
+
+impl AnyWrite for CaptureWriter {
+    type Buf = str;
+    type Error = fmt::Error;
+
+    fn write_fmt(&mut self, args: fmt::Arguments) -> WriteResult<Self::Error> {
+        fmt::Write::write_fmt(self, args)
+    }
+
+    fn write_str(&mut self, s: &Self::Buf) -> WriteResult<Self::Error> {
+        fmt::Write::write_str(self, s)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{CaptureWriter, Captured};
+    use crate::Color::*;
+    use crate::{AnsiString, AnsiStrings, Style};
+    use std::fmt::Write;
+
+    #[test]
+    fn records_styles_not_codes() {
+        let mut capture = CaptureWriter::new();
+        write!(capture, "{}", Blue.paint("a")).unwrap();
+        write!(capture, "{}", Blue.paint("b")).unwrap();
+        AnsiStrings([
+            AnsiString::control("\x1b[2K"),
+            Green
+                .underline()
+                .paint("c")
+                .hyperlink("https://example.com"),
+        ])
+        .write_to_any(&mut capture)
+        .unwrap();
+
+        assert_eq!(
+            capture.captured(),
+            [
+                Captured::Text {
+                    style: Blue.normal(),
+                    content: "ab".into()
+                },
+                Captured::Control("\x1b[2K".into()),
+                Captured::Control("\x1b]8;;https://example.com\x1b\\".into()),
+                Captured::Text {
+                    style: Green.underline(),
+                    content: "c".into()
+                },
+                Captured::Control("\x1b]8;;\x1b\\".into()),
+            ]
+        );
+        assert_eq!(capture.text(), "abc");
+
+        capture.clear();
+        assert_eq!(capture.captured(), []);
+    }
+
+    #[test]
+    fn records_codes_styles_cannot_represent() {
+        let mut capture = CaptureWriter::new();
+        capture.write_str("\x1b[1;4:4ma\x1b[24mb").unwrap();
+        assert_eq!(
+            capture.captured(),
+            [
+                Captured::Control("\x1b[1;4:4m".into()),
+                Captured::Text {
+                    style: Style::new().bold().underline(),
+                    content: "a".into()
+                },
+                Captured::Text {
+                    style: Style::new().bold(),
+                    content: "b".into()
+                },
+            ]
+        );
+    }
+}
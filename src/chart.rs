@@ -0,0 +1,211 @@
+use crate::width::paint_runs;
+use crate::{AnsiStrings, Color, MultiGradient, Style, TerminalProfile};
+
+/// Glyphs and styles for drawing sparklines and horizontal bars out of block
+/// characters.
+///
+/// # Examples
+///
+/// ```
+/// use nu_ansi_term::{ChartStyle, MultiGradient, Rgb, TerminalProfile};
+/// use nu_ansi_term::utils::unstyle;
+/// use nu_ansi_term::Color::Cyan;
+///
+/// let chart = ChartStyle::new()
+///     .colors(MultiGradient::new([Rgb::new(0, 200, 0), Rgb::new(220, 0, 0)]))
+///     .track('·', Cyan.dimmed());
+/// assert_eq!(unstyle(&chart.sparkline([1.0, 5.0, 3.0, 8.0])), "▁▅▃█");
+/// assert_eq!(unstyle(&chart.hbar(0.55, 1.0, 6)), "███▎··");
+///
+/// let ascii = chart.for_profile(&TerminalProfile::ascii());
+/// assert_eq!(unstyle(&ascii.hbar(0.55, 1.0, 6)), "###···");
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChartStyle {
+    levels: Vec<char>,
+    partials: Vec<char>,
+    track: char,
+    track_style: Style,
+    style: Style,
+    colors: Option<MultiGradient>,
+}
+
+impl Default for ChartStyle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ChartStyle {
+    /// Draw with the eight block characters from `▁` to `█`, unstyled,
+    /// leaving the rest of bars blank.
+    pub fn new() -> Self {
+        Self {
+            levels: "▁▂▃▄▅▆▇█".chars().collect(),
+            partials: "▏▎▍▌▋▊▉█".chars().collect(),
+            track: ' ',
+            track_style: Style::new(),
+            style: Style::new(),
+            colors: None,
+        }
+    }
+
+    /// Draw sparklines with `levels`, from the glyph of the least value to
+    /// that of the greatest.
+    pub fn levels(mut self, levels: impl IntoIterator<Item = char>) -> Self {
+        self.levels = levels.into_iter().collect();
+        self
+    }
+
+    /// Draw the cells of bars with `partials`, from the glyph of a cell
+    /// which is barely filled to that of a full one. The more glyphs there
+    /// are, the finer the ends of bars are drawn.
+    pub fn partials(mut self, partials: impl IntoIterator<Item = char>) -> Self {
+        self.partials = partials.into_iter().collect();
+        self
+    }
+
+    /// Fill the rest of bars with `track`, painted in `style`.
+    pub fn track(mut self, track: char, style: Style) -> Self {
+        self.track = track;
+        self.track_style = style;
+        self
+    }
+
+    /// Paint the glyphs in `style`.
+    pub fn style(mut self, style: Style) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Color the glyphs by their value, from the start of `colors` for the
+    /// least value to its end for the greatest.
+    pub fn colors(mut self, colors: MultiGradient) -> Self {
+        self.colors = Some(colors);
+        self
+    }
+
+    /// Use these glyphs if `profile` supports unicode, otherwise fall back
+    /// to ASCII ones, `_.-=#` for sparklines and `#` for bars. The track is
+    /// kept.
+    pub fn for_profile(self, profile: &TerminalProfile) -> Self {
+        if profile.unicode {
+            self
+        } else {
+            self.levels("_.-=#".chars()).partials(['#'])
+        }
+    }
+
+    /// Use these glyphs if the detected terminal supports unicode (see
+    /// [`for_profile`](Self::for_profile)).
+    pub fn detect(self) -> Self {
+        self.for_profile(&TerminalProfile::detect())
+    }
+
+    /// One glyph for each of `values`, as high as the value is between the
+    /// least and the greatest of them. Values which are not numbers are
+    /// left as gaps, and if all the values are the same, they are drawn
+    /// halfway up.
+    pub fn sparkline(&self, values: impl IntoIterator<Item = f64>) -> AnsiStrings<'static> {
+        let values: Vec<f64> = values.into_iter().collect();
+        let (min, max) = values
+            .iter()
+            .filter(|value| value.is_finite())
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), &value| {
+                (min.min(value), max.max(value))
+            });
+        let top = self.levels.len().saturating_sub(1);
+        AnsiStrings(paint_runs(values.into_iter().map(|value| {
+            if value.is_nan() || self.levels.is_empty() {
+                return (' ', Style::new());
+            }
+            let t = if max > min {
+                ((value - min) / (max - min)).clamp(0.0, 1.0)
+            } else {
+                0.5
+            };
+            (
+                self.levels[(t * top as f64).round() as usize],
+                self.style_at(t),
+            )
+        })))
+    }
+
+    /// A bar `width` columns wide, filled as far as `value` is towards
+    /// `max`. The end of the bar is drawn to a fraction of a column with
+    /// the partial glyphs (see [`partials`](Self::partials)).
+    pub fn hbar(&self, value: f64, max: f64, width: usize) -> AnsiStrings<'static> {
+        let t = if max > 0.0 && !value.is_nan() {
+            (value / max).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        let steps = self.partials.len().max(1);
+        let filled = (t * (width * steps) as f64).round() as usize;
+        let (full, partial) = (filled / steps, filled % steps);
+        let style = self.style_at(t);
+        let full_glyph = self.partials.last().copied().unwrap_or(' ');
+        let mut glyphs: Vec<(char, Style)> = vec![(full_glyph, style); full];
+        if partial > 0 {
+            glyphs.push((self.partials[partial - 1], style));
+        }
+        let track = width.saturating_sub(glyphs.len());
+        glyphs.extend(std::iter::repeat((self.track, self.track_style)).take(track));
+        AnsiStrings(paint_runs(glyphs))
+    }
+
+    /// The style of a glyph `t` of the way from the least value to the
+    /// greatest.
+    fn style_at(&self, t: f64) -> Style {
+        match &self.colors {
+            Some(colors) => self.style.fg(Color::from(colors.at(t as f32))),
+            None => self.style,
+        }
+    }
+}
+
+/// A sparkline of `values` drawn with the default [`ChartStyle`].
+pub fn sparkline(values: impl IntoIterator<Item = f64>) -> AnsiStrings<'static> {
+    ChartStyle::new().sparkline(values)
+}
+
+/// A bar of `value` out of `max`, `width` columns wide, drawn with the
+/// default [`ChartStyle`].
+pub fn hbar(value: f64, max: f64, width: usize) -> AnsiStrings<'static> {
+    ChartStyle::new().hbar(value, max, width)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::unstyle;
+    use crate::Color::*;
+
+    #[test]
+    fn sparklines() {
+        assert_eq!(unstyle(&sparkline([0.0, 1.0, f64::NAN, 7.0])), "▁▂ █");
+        assert_eq!(unstyle(&sparkline([2.0, 2.0])), "▅▅");
+        assert_eq!(unstyle(&sparkline([])), "");
+        let chart = ChartStyle::new().style(Red.normal()).levels(['a', 'b']);
+        assert_eq!(
+            chart.sparkline([1.0, 2.0, 2.0]).to_string(),
+            Red.paint("abb").to_string()
+        );
+    }
+
+    #[test]
+    fn bars() {
+        assert_eq!(unstyle(&hbar(1.0, 4.0, 4)), "█   ");
+        assert_eq!(unstyle(&hbar(1.0, 16.0, 4)), "▎   ");
+        assert_eq!(unstyle(&hbar(9.0, 4.0, 2)), "██");
+        assert_eq!(unstyle(&hbar(1.0, 0.0, 2)), "  ");
+        let colors = MultiGradient::new([crate::Rgb::gray(0), crate::Rgb::gray(255)]);
+        let chart = ChartStyle::new()
+            .colors(colors)
+            .style(Style::new().on(Blue));
+        assert_eq!(
+            chart.hbar(1.0, 1.0, 1).to_string(),
+            Rgb(255, 255, 255).on(Blue).paint("█").to_string()
+        );
+    }
+}
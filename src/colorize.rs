@@ -0,0 +1,204 @@
+use std::io::{self, BufRead, BufReader, Read, Write};
+
+use crate::Style;
+
+/// Reader adapter which styles every line read from an inner reader with the
+/// [`Style`] chosen for it by a callback.
+///
+/// Escape codes are only emitted when the style changes between lines, so a
+/// run of lines sharing a style is wrapped in a single prefix, and the
+/// output always ends with the terminal back in its default style.
+///
+/// Lines are handed to the callback without their line terminator. Lines
+/// which are not valid UTF-8 are passed to the callback lossily converted,
+/// but are written out unchanged.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Read;
+/// use nu_ansi_term::colorize_lines;
+/// use nu_ansi_term::Color::{Red, Yellow};
+/// use nu_ansi_term::Style;
+///
+/// let log = "INFO started\nWARN disk almost full\nERROR disk full\n";
+/// let mut colorized = String::new();
+/// colorize_lines(log.as_bytes(), |line| {
+///     if line.starts_with("ERROR") {
+///         Red.bold()
+///     } else if line.starts_with("WARN") {
+///         Yellow.normal()
+///     } else {
+///         Style::new()
+///     }
+/// })
+/// .read_to_string(&mut colorized)
+/// .unwrap();
+///
+/// assert_eq!(
+///     colorized,
+///     "INFO started\n\x1b[33mWARN disk almost full\n\x1b[1;31mERROR disk full\n\x1b[0m"
+/// );
+/// ```
+#[derive(Debug)]
+pub struct ColorizeReader<R, F> {
+    inner: R,
+    style_for: F,
+    current: Style,
+    line: Vec<u8>,
+    pending: Vec<u8>,
+    pos: usize,
+    finished: bool,
+}
+
+/// Colorize the lines read from `reader` with the styles returned by
+/// `style_for`. See [`ColorizeReader`].
+pub fn colorize_lines<R, F>(reader: R, style_for: F) -> ColorizeReader<BufReader<R>, F>
+where
+    R: Read,
+    F: FnMut(&str) -> Style,
+{
+    ColorizeReader::new(BufReader::new(reader), style_for)
+}
+
+impl<R, F> ColorizeReader<R, F>
+where
+    R: BufRead,
+    F: FnMut(&str) -> Style,
+{
+    /// Create a reader which colorizes the lines of `inner` with the styles
+    /// returned by `style_for`.
+    pub fn new(inner: R, style_for: F) -> Self {
+        Self {
+            inner,
+            style_for,
+            current: Style::new(),
+            line: Vec::new(),
+            pending: Vec::new(),
+            pos: 0,
+            finished: false,
+        }
+    }
+
+    /// Get a reference to the wrapped reader.
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Consume this adapter, returning the wrapped reader.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Read every remaining line and write the colorized output to `w`.
+    pub fn write_to<W: Write + ?Sized>(&mut self, w: &mut W) -> io::Result<u64> {
+        io::copy(self, w)
+    }
+
+    /// Refill `pending` with the colorized form of the next line, or with
+    /// the final reset once the inner reader is exhausted.
+    fn fill_pending(&mut self) -> io::Result<()> {
+        self.pending.clear();
+        self.pos = 0;
+        self.line.clear();
+        if self.inner.read_until(b'\n', &mut self.line)? == 0 {
+            write!(self.pending, "{}", self.current.infix(Style::new()))?;
+            self.current = Style::new();
+            self.finished = true;
+            return Ok(());
+        }
+
+        let mut content_len = self.line.len();
+        if self.line.ends_with(b"\r\n") {
+            content_len -= 2;
+        } else if self.line.ends_with(b"\n") {
+            content_len -= 1;
+        }
+        let content = &self.line[..content_len];
+        let style = (self.style_for)(&String::from_utf8_lossy(content));
+        write!(self.pending, "{}", self.current.infix(style))?;
+        self.pending.extend_from_slice(&self.line);
+        self.current = style;
+        Ok(())
+    }
+}
+
+impl<R, F> Read for ColorizeReader<R, F>
+where
+    R: BufRead,
+    F: FnMut(&str) -> Style,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.pending.len() {
+            if self.finished {
+                return Ok(0);
+            }
+            self.fill_pending()?;
+        }
+        let n = buf.len().min(self.pending.len() - self.pos);
+        buf[..n].copy_from_slice(&self.pending[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Color::*;
+
+    fn colorize(input: &str, style_for: impl FnMut(&str) -> Style) -> String {
+        let mut out = String::new();
+        colorize_lines(input.as_bytes(), style_for)
+            .read_to_string(&mut out)
+            .unwrap();
+        out
+    }
+
+    #[test]
+    fn unstyled_lines_are_untouched() {
+        assert_eq!(colorize("a\nb\n", |_| Style::new()), "a\nb\n");
+    }
+
+    #[test]
+    fn runs_share_a_prefix() {
+        assert_eq!(
+            colorize("x1\nx2\ny\n", |line| if line.starts_with('x') {
+                Red.normal()
+            } else {
+                Style::new()
+            }),
+            "\x1b[31mx1\nx2\n\x1b[0my\n"
+        );
+    }
+
+    #[test]
+    fn ends_with_reset() {
+        assert_eq!(
+            colorize("a\r\nb", |_| Blue.bold()),
+            "\x1b[1;34ma\r\nb\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn callback_sees_lines_without_terminators() {
+        let mut seen = Vec::new();
+        colorize("one\r\ntwo\n\nthree", |line| {
+            seen.push(line.to_string());
+            Style::new()
+        });
+        assert_eq!(seen, ["one", "two", "", "three"]);
+    }
+
+    #[test]
+    fn invalid_utf8_is_passed_through() {
+        let mut out = Vec::new();
+        colorize_lines(&b"\xffok\n"[..], |line| {
+            assert_eq!(line, "\u{fffd}ok");
+            Green.normal()
+        })
+        .write_to(&mut out)
+        .unwrap();
+        assert_eq!(out, b"\x1b[32m\xffok\n\x1b[0m");
+    }
+}
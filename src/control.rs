@@ -0,0 +1,306 @@
+//! Typed commands for moving the cursor and clearing the screen, written as
+//! the CSI escape sequences terminals understand.
+//!
+//! Every command implements [`Display`](fmt::Display), so it can be written
+//! with `write!` or `print!` alongside styled strings, and converts into a
+//! control segment (see [`AnsiString::control`]) for pushing into
+//! [`AnsiStrings`](crate::AnsiStrings). Unlike styles, commands are written
+//! even if styling is turned off (see [`set_enabled`](crate::set_enabled)).
+//!
+//! # Examples
+//!
+//! ```
+//! use nu_ansi_term::control::{ClearLine, CursorUp, HideCursor, MoveTo};
+//! use nu_ansi_term::AnsiStrings;
+//! use nu_ansi_term::Color::Green;
+//!
+//! print!("{}", HideCursor);
+//! assert_eq!(format!("{}{}", CursorUp(2), ClearLine), "\x1b[2A\x1b[2K");
+//! assert_eq!(MoveTo(0, 4).to_string(), "\x1b[5;1H");
+//!
+//! let mut progress = AnsiStrings([]);
+//! progress.push(ClearLine.into());
+//! progress.push(Green.paint("done"));
+//! assert_eq!(progress.to_string(), "\x1b[2K\x1b[32mdone\x1b[0m");
+//! ```
+
+use crate::write::Content;
+use crate::{AnsiString, AnsiStrings, Style};
+use std::fmt;
+
+/// Define commands which always write the same sequence.
+macro_rules! fixed_commands {
+    ($($(#[$doc:meta])* $name:ident => $sequence:literal,)*) => {
+        $(
+            $(#[$doc])*
+            #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+            pub struct $name;
+
+            impl fmt::Display for $name {
+                fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                    f.write_str($sequence)
+                }
+            }
+
+            impl From<$name> for AnsiString<'static> {
+                fn from(_: $name) -> Self {
+                    AnsiString::control($sequence)
+                }
+            }
+        )*
+    };
+}
+
+/// Define commands moving the cursor by a number of cells, which write
+/// nothing for a distance of 0 (which terminals would take as 1).
+macro_rules! movements {
+    ($($(#[$doc:meta])* $name:ident => $final:literal,)*) => {
+        $(
+            $(#[$doc])*
+            #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+            pub struct $name(pub u16);
+
+            impl fmt::Display for $name {
+                fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                    if self.0 == 0 {
+                        return Ok(());
+                    }
+                    write!(f, "\x1b[{}{}", self.0, $final)
+                }
+            }
+
+            impl From<$name> for AnsiString<'static> {
+                fn from(command: $name) -> Self {
+                    AnsiString::control(command.to_string())
+                }
+            }
+        )*
+    };
+}
+
+movements! {
+    /// Move the cursor up by a number of lines, staying in its column.
+    CursorUp => 'A',
+    /// Move the cursor down by a number of lines, staying in its column.
+    CursorDown => 'B',
+    /// Move the cursor right by a number of columns.
+    CursorForward => 'C',
+    /// Move the cursor left by a number of columns.
+    CursorBack => 'D',
+    /// Move the cursor to the start of the line a number of lines down.
+    CursorNextLine => 'E',
+    /// Move the cursor to the start of the line a number of lines up.
+    CursorPreviousLine => 'F',
+}
+
+/// Move the cursor to a column (counted from 0) of its line.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct CursorToColumn(pub u16);
+
+impl fmt::Display for CursorToColumn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "\x1b[{}G", u32::from(self.0) + 1)
+    }
+}
+
+impl From<CursorToColumn> for AnsiString<'static> {
+    fn from(command: CursorToColumn) -> Self {
+        AnsiString::control(command.to_string())
+    }
+}
+
+/// Move the cursor to a column and row (both counted from 0, from the top
+/// left corner of the screen).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct MoveTo(pub u16, pub u16);
+
+impl fmt::Display for MoveTo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "\x1b[{};{}H",
+            u32::from(self.1) + 1,
+            u32::from(self.0) + 1
+        )
+    }
+}
+
+impl From<MoveTo> for AnsiString<'static> {
+    fn from(command: MoveTo) -> Self {
+        AnsiString::control(command.to_string())
+    }
+}
+
+fixed_commands! {
+    /// Clear the whole line the cursor is on, without moving the cursor.
+    ClearLine => "\x1b[2K",
+    /// Clear the line from the cursor to its end.
+    ClearLineRight => "\x1b[K",
+    /// Clear the line from its start to the cursor.
+    ClearLineLeft => "\x1b[1K",
+    /// Clear the whole screen, without moving the cursor.
+    ClearScreen => "\x1b[2J",
+    /// Clear the screen from the cursor to its end.
+    ClearScreenBelow => "\x1b[J",
+    /// Clear the screen from its start to the cursor.
+    ClearScreenAbove => "\x1b[1J",
+    /// Save the position of the cursor (and the current style), to be
+    /// restored by [`RestoreCursor`].
+    SaveCursor => "\x1b7",
+    /// Restore the position of the cursor saved by [`SaveCursor`].
+    RestoreCursor => "\x1b8",
+    /// Save the position of the cursor with the CSI form (`CSI s`), which
+    /// saves only the position, to be restored by [`RestoreCursorPosition`].
+    SaveCursorPosition => "\x1b[s",
+    /// Restore the position of the cursor saved by [`SaveCursorPosition`].
+    RestoreCursorPosition => "\x1b[u",
+    /// Switch to the alternate screen, leaving the contents of the main
+    /// screen as they are until [`LeaveAlternateScreen`].
+    EnterAlternateScreen => "\x1b[?1049h",
+    /// Switch back to the main screen from the alternate screen.
+    LeaveAlternateScreen => "\x1b[?1049l",
+    /// Hide the cursor.
+    HideCursor => "\x1b[?25l",
+    /// Show the cursor again after [`HideCursor`].
+    ShowCursor => "\x1b[?25h",
+}
+
+/// The sequences which save and restore the position of the cursor around
+/// an [anchored](AnsiString::anchored) overlay.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum CursorSave {
+    /// [`SaveCursor`] and [`RestoreCursor`] (DECSC and DECRC), which nearly
+    /// every terminal supports.
+    #[default]
+    Dec,
+    /// [`SaveCursorPosition`] and [`RestoreCursorPosition`] (`CSI s` and
+    /// `CSI u`), for terminals which only support these.
+    Csi,
+}
+
+impl CursorSave {
+    /// The sequences saving and restoring the cursor.
+    fn sequences(self) -> (&'static str, &'static str) {
+        match self {
+            CursorSave::Dec => ("\x1b7", "\x1b8"),
+            CursorSave::Csi => ("\x1b[s", "\x1b[u"),
+        }
+    }
+}
+
+impl<'a> AnsiStrings<'a> {
+    /// These strings, drawn with the cursor moved back to where it was
+    /// before them (see [`CursorSave::Dec`]), for transient overlays such as
+    /// a tooltip or a validation message under a prompt. See
+    /// [`AnsiString::anchored`].
+    pub fn anchored(self) -> AnsiString<'a> {
+        self.anchored_with(CursorSave::Dec)
+    }
+
+    /// These strings, drawn with the cursor saved and restored around them
+    /// by `save`.
+    pub fn anchored_with(self, save: CursorSave) -> AnsiString<'a> {
+        let (save, restore) = save.sequences();
+        // Nested strings start and end in the default style, so the saved
+        // style is the default one, and no style is left set at the restore.
+        let overlay = Style::new().paint(Content::GenericStrings(self));
+        let mut strings = AnsiStrings::with_capacity(3);
+        strings.push(AnsiString::control(save));
+        strings.push(overlay);
+        strings.push(AnsiString::control(restore));
+        Style::new().paint(Content::GenericStrings(strings))
+    }
+}
+
+impl<'a> AnsiString<'a> {
+    /// This string, drawn with the cursor moved back to where it was before
+    /// it, so that the cursor stays put in the text being edited. Anything
+    /// drawing around the overlay, such as moving to the line below and
+    /// clearing it, goes inside it; undoing it is drawing another overlay
+    /// which clears what it drew.
+    ///
+    /// The overlay starts and ends in the default style, whatever the style
+    /// around it, so that restoring the cursor (which restores the style
+    /// saved with it) leaves the terminal in the style written there.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::control::{ClearLine, ClearScreenBelow, CursorNextLine};
+    /// use nu_ansi_term::{AnsiString, AnsiStrings};
+    /// use nu_ansi_term::Color::Red;
+    ///
+    /// let message = AnsiStrings([
+    ///     CursorNextLine(1).into(),
+    ///     ClearLine.into(),
+    ///     Red.paint("expected a number"),
+    /// ]);
+    /// let prompt = AnsiStrings(["> ".into(), "12a".into(), message.anchored()]);
+    /// assert_eq!(
+    ///     prompt.to_string(),
+    ///     "> 12a\x1b7\x1b[1E\x1b[2K\x1b[31mexpected a number\x1b[0m\x1b8"
+    /// );
+    ///
+    /// // Once the input is valid, clear the message again.
+    /// let undo = AnsiStrings([CursorNextLine(1).into(), ClearScreenBelow.into()]).anchored();
+    /// assert_eq!(undo.to_string(), "\x1b7\x1b[1E\x1b[J\x1b8");
+    /// ```
+    pub fn anchored(self) -> AnsiString<'a> {
+        self.anchored_with(CursorSave::Dec)
+    }
+
+    /// This string, drawn with the cursor saved and restored around it by
+    /// `save`.
+    pub fn anchored_with(self, save: CursorSave) -> AnsiString<'a> {
+        let mut strings = AnsiStrings::with_capacity(1);
+        strings.push(self);
+        strings.anchored_with(save)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::AnsiStrings;
+    use crate::Color::Red;
+
+    #[test]
+    fn sequences() {
+        assert_eq!(CursorDown(0).to_string(), "");
+        assert_eq!(CursorBack(12).to_string(), "\x1b[12D");
+        assert_eq!(CursorToColumn(0).to_string(), "\x1b[1G");
+        assert_eq!(MoveTo(u16::MAX, 0).to_string(), "\x1b[1;65536H");
+        assert_eq!(
+            format!("{}{}{}", EnterAlternateScreen, SaveCursor, ClearScreen),
+            "\x1b[?1049h\x1b7\x1b[2J"
+        );
+    }
+
+    #[test]
+    fn anchored_overlays_start_and_end_unstyled() {
+        let strings = AnsiStrings([
+            Red.paint("a"),
+            Red.bold().paint("tip").anchored_with(CursorSave::Csi),
+            Red.paint("b"),
+        ]);
+        assert_eq!(
+            strings.to_string(),
+            "\x1b[31ma\x1b[0m\x1b[s\x1b[1;31mtip\x1b[0m\x1b[u\x1b[31mb\x1b[0m"
+        );
+        assert_eq!(
+            AnsiStrings([]).anchored().to_string(),
+            format!("{}{}", SaveCursor, RestoreCursor)
+        );
+    }
+
+    #[test]
+    fn commands_keep_the_style_around_them() {
+        let strings = AnsiStrings([
+            Red.paint("a"),
+            CursorUp(1).into(),
+            ClearLineRight.into(),
+            Red.paint("b"),
+        ]);
+        assert_eq!(strings.to_string(), "\x1b[31ma\x1b[1A\x1b[Kb\x1b[0m");
+    }
+}
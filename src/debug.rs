@@ -2,7 +2,6 @@ use itertools::Itertools;
 
 use crate::{
     difference::StyleDelta,
-    fmt_write,
     style::{Coloring, FormatFlags, Style},
     write::Content,
     AnsiGenericString, Color, Infix,
@@ -29,10 +28,10 @@ fn debug_write_style_flags_to(
 
 fn debug_write_coloring_to(
     f: &mut dyn fmt::Write,
-    fg: Option<Color>,
-    bg: Option<Color>,
+    coloring: Coloring,
     mut sep_required: bool,
 ) -> Result<bool, fmt::Error> {
+    let Coloring { fg, bg, underline } = coloring;
     if let Some(color) = fg {
         f.write_fmt(format_args!(
             "{}foreground({color:?})",
@@ -45,6 +44,13 @@ fn debug_write_coloring_to(
             "{}background({color:?})",
             if sep_required { ", " } else { "" }
         ))?;
+        sep_required = true;
+    }
+    if let Some(color) = underline {
+        f.write_fmt(format_args!(
+            "{}underline_color({color:?})",
+            if sep_required { ", " } else { "" }
+        ))?;
     }
     Ok(sep_required)
 }
@@ -52,8 +58,7 @@ fn debug_write_coloring_to(
 fn debug_write_style_to(
     f: &mut dyn fmt::Write,
     flags: FormatFlags,
-    fg: Option<Color>,
-    bg: Option<Color>,
+    coloring: Coloring,
 ) -> Result<(), fmt::Error> {
     f.write_str("Style { ")?;
 
@@ -72,18 +77,25 @@ fn debug_write_style_to(
         .collect();
     flag_strings.sort_unstable();
     f.write_str(&flag_strings.join(", "))?;
-    debug_write_coloring_to(f, fg, bg, sep_required)?;
+    debug_write_coloring_to(f, coloring, sep_required)?;
     f.write_str(" }")
 }
 
+/// The alternate debug form of a style, for comparisons in tests.
+#[cfg(test)]
 fn debug_style_to_string(
     flags: FormatFlags,
     fg: Option<Color>,
     bg: Option<Color>,
 ) -> Result<String, fmt::Error> {
     let mut dbg_s = String::new();
-    let f = fmt_write!(&mut dbg_s);
-    debug_write_style_to(f, flags, fg, bg)?;
+    let f = crate::fmt_write!(&mut dbg_s);
+    let coloring = Coloring {
+        fg,
+        bg,
+        underline: None,
+    };
+    debug_write_style_to(f, flags, coloring)?;
     Ok(dbg_s)
 }
 
@@ -102,16 +114,15 @@ fn debug_style_to_string(
 impl Debug for Style {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         if !f.alternate() {
-            f.debug_struct("Style")
-                .field("flags", &self.formats)
-                .field("coloring", &self.coloring)
-                .finish()
+            let mut debug = f.debug_struct("Style");
+            debug.field("flags", &self.formats);
+            // Shown only when set, as it rarely is.
+            if !self.cleared.is_empty() {
+                debug.field("cleared", &self.cleared);
+            }
+            debug.field("coloring", &self.coloring).finish()
         } else {
-            write!(
-                f,
-                "{}",
-                debug_style_to_string(self.formats, self.coloring.fg, self.coloring.bg)?
-            )
+            debug_write_style_to(f, self.formats, self.coloring)
         }
     }
 }
@@ -122,9 +133,10 @@ impl Debug for Coloring {
             f.debug_struct("Coloring")
                 .field("foreground", &self.fg)
                 .field("background", &self.bg)
+                .field("underline", &self.underline)
                 .finish()
         } else {
-            debug_write_coloring_to(f, self.fg, self.bg, false)?;
+            debug_write_coloring_to(f, *self, false)?;
             Ok(())
         }
     }
@@ -257,12 +269,9 @@ pub trait DebugStylePaint: Clone + Copy {
 
     /// Paints the given text with this style, returning an ANSI string.
     #[inline]
-    fn style_input<'a, I: ToOwned, S: 'a + ToOwned + ?Sized>(
-        &self,
-        input: I,
-    ) -> AnsiGenericString<'a, S>
+    fn style_input<'a, I, S: 'a + ToOwned + ?Sized>(&self, input: I) -> AnsiGenericString<'a, S>
     where
-        I: Into<Content<'a, S>>,
+        I: ToOwned + Into<Content<'a, S>>,
     {
         AnsiGenericString::new(self.into_style(), input.into(), None)
     }
@@ -367,9 +376,10 @@ impl<T: DebugDiff> DebugDiff for Option<T> {
 impl DebugDiff for Coloring {
     fn debug_diff(&self, expected: &Self) -> String {
         format!(
-            "Coloring {{\n\tforeground: {},\n\tbackground: {},\n}}",
+            "Coloring {{\n\tforeground: {},\n\tbackground: {},\n\tunderline: {},\n}}",
             self.fg.debug_diff(&expected.fg),
-            expected.bg.debug_diff(&expected.bg)
+            expected.bg.debug_diff(&expected.bg),
+            self.underline.debug_diff(&expected.underline)
         )
     }
 }
@@ -410,7 +420,6 @@ mod test {
     use crate::debug::debug_style_to_string;
     use crate::style::Color::*;
     use crate::style::{FormatFlags, Style};
-    use crate::style_test;
     use paste::paste;
 
     macro_rules! create_style_eq_tests {
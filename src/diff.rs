@@ -0,0 +1,429 @@
+use crate::highlight::highlight_input;
+use crate::{AnsiString, AnsiStrings, Color, ContinuationMarker, Style};
+use std::ops::Range;
+
+/// How [`word_diff`] lays out the lines of the expected and actual text.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DiffLayout {
+    /// One line after another, with removed lines before the lines which
+    /// replace them, as `diff -u` shows them.
+    Inline,
+
+    /// Expected text on the left and actual text on the right, each column
+    /// `width` columns wide. Lines too wide for a column are truncated.
+    SideBySide { width: usize },
+}
+
+/// The styles and layout of a diff. See [`word_diff`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DiffStyle {
+    removed: Style,
+    added: Style,
+    emphasis: Style,
+    layout: DiffLayout,
+}
+
+impl Default for DiffStyle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DiffStyle {
+    /// An inline diff, with removed lines in red, added lines in green, and
+    /// the words which changed within a line in reverse video.
+    pub const fn new() -> Self {
+        Self {
+            removed: Color::Red.normal(),
+            added: Color::Green.normal(),
+            emphasis: Style::new().reverse(),
+            layout: DiffLayout::Inline,
+        }
+    }
+
+    /// Show lines of the expected text missing from the actual text in
+    /// `style`.
+    pub const fn removed(mut self, style: Style) -> Self {
+        self.removed = style;
+        self
+    }
+
+    /// Show lines of the actual text missing from the expected text in
+    /// `style`.
+    pub const fn added(mut self, style: Style) -> Self {
+        self.added = style;
+        self
+    }
+
+    /// Show the words which changed within a line in `style`, laid over the
+    /// style of the line (see [`Style::merge`]).
+    pub const fn emphasis(mut self, style: Style) -> Self {
+        self.emphasis = style;
+        self
+    }
+
+    /// Lay the diff out as `layout`.
+    pub const fn layout(mut self, layout: DiffLayout) -> Self {
+        self.layout = layout;
+        self
+    }
+}
+
+/// A step of the shortest edit turning one sequence into another.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Edit {
+    Keep(usize, usize),
+    Remove(usize),
+    Add(usize),
+}
+
+/// The edits turning `old` into `new`, keeping a longest common subsequence.
+///
+/// This is Myers' algorithm, taking O((n + m)·d) time for d edits, rather
+/// than filling in an n·m table. Within each run of changes, the removals
+/// come before the additions.
+fn edits<T: PartialEq>(old: &[T], new: &[T]) -> Vec<Edit> {
+    let (n, m) = (old.len() as isize, new.len() as isize);
+    let offset = n + m + 1;
+    let at = |k: isize| (k + offset) as usize;
+    // The furthest `x` reached on each diagonal `k = x - y`, and a copy of
+    // those after each round, to walk back through.
+    let mut furthest = vec![0_isize; 2 * offset as usize + 1];
+    let mut trace = Vec::new();
+    'search: for d in 0..=n + m {
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && furthest[at(k - 1)] < furthest[at(k + 1)]) {
+                furthest[at(k + 1)]
+            } else {
+                furthest[at(k - 1)] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && old[x as usize] == new[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            furthest[at(k)] = x;
+            if x >= n && y >= m {
+                trace.push(furthest.clone());
+                break 'search;
+            }
+        }
+        trace.push(furthest.clone());
+    }
+
+    let mut reversed = Vec::with_capacity(old.len() + new.len());
+    let (mut x, mut y) = (n, m);
+    for d in (0..trace.len() as isize).rev() {
+        let k = x - y;
+        if d == 0 {
+            while x > 0 {
+                x -= 1;
+                y -= 1;
+                reversed.push(Edit::Keep(x as usize, y as usize));
+            }
+            break;
+        }
+        let before = &trace[d as usize - 1];
+        let previous = if k == -d || (k != d && before[at(k - 1)] < before[at(k + 1)]) {
+            k + 1
+        } else {
+            k - 1
+        };
+        let start_x = before[at(previous)];
+        let start_y = start_x - previous;
+        while x > start_x && y > start_y {
+            x -= 1;
+            y -= 1;
+            reversed.push(Edit::Keep(x as usize, y as usize));
+        }
+        if x == start_x {
+            y -= 1;
+            reversed.push(Edit::Add(y as usize));
+        } else {
+            x -= 1;
+            reversed.push(Edit::Remove(x as usize));
+        }
+    }
+
+    let mut edits: Vec<Edit> = reversed.into_iter().rev().collect();
+    for run in edits.split_mut(|edit| matches!(edit, Edit::Keep(..))) {
+        run.sort_by_key(|edit| matches!(edit, Edit::Add(_)));
+    }
+    edits
+}
+
+/// The byte ranges of the words of `line`: runs of alphanumeric characters
+/// and underscores, runs of whitespace, and single other characters.
+fn words(line: &str) -> Vec<Range<usize>> {
+    let kind = |c: char| {
+        if c.is_alphanumeric() || c == '_' {
+            1
+        } else if c.is_whitespace() {
+            2
+        } else {
+            0
+        }
+    };
+    let mut words: Vec<Range<usize>> = Vec::new();
+    let mut last = None;
+    for (i, c) in line.char_indices() {
+        let end = i + c.len_utf8();
+        match words.last_mut() {
+            Some(word) if last == Some(kind(c)) && kind(c) != 0 => word.end = end,
+            _ => words.push(i..end),
+        }
+        last = Some(kind(c));
+    }
+    words
+}
+
+/// A line of the diff: the same in both texts, or changed, with the styled
+/// line on either side of the change which has one.
+enum Row<'a> {
+    Same(&'a str),
+    Changed(Option<AnsiStrings<'a>>, Option<AnsiStrings<'a>>),
+}
+
+/// Style a pair of lines which replace one another, emphasising the words
+/// which differ.
+fn changed_pair<'a>(old: &'a str, new: &'a str, style: &DiffStyle) -> Row<'a> {
+    let (old_words, new_words) = (words(old), words(new));
+    let old_text: Vec<_> = old_words.iter().map(|word| &old[word.clone()]).collect();
+    let new_text: Vec<_> = new_words.iter().map(|word| &new[word.clone()]).collect();
+
+    let (removed, added) = (style.removed, style.added);
+    let (removed_emphasis, added_emphasis) =
+        (removed.merge(style.emphasis), added.merge(style.emphasis));
+    let mut old_spans = Vec::with_capacity(old_words.len());
+    let mut new_spans = Vec::with_capacity(new_words.len());
+    for edit in edits(&old_text, &new_text) {
+        match edit {
+            Edit::Keep(i, j) => {
+                old_spans.push((old_words[i].clone(), removed));
+                new_spans.push((new_words[j].clone(), added));
+            }
+            Edit::Remove(i) => old_spans.push((old_words[i].clone(), removed_emphasis)),
+            Edit::Add(j) => new_spans.push((new_words[j].clone(), added_emphasis)),
+        }
+    }
+    Row::Changed(
+        Some(highlight_input(old, &old_spans)),
+        Some(highlight_input(new, &new_spans)),
+    )
+}
+
+/// The rows of the diff of `expected` and `actual`, line by line.
+fn rows<'a>(expected: &'a str, actual: &'a str, style: &DiffStyle) -> Vec<Row<'a>> {
+    let old: Vec<_> = expected.lines().collect();
+    let new: Vec<_> = actual.lines().collect();
+    let whole = |line: &'a str, style: Style| highlight_input(line, &[(0..line.len(), style)]);
+
+    let mut rows = Vec::with_capacity(old.len().max(new.len()));
+    let (mut removed, mut added) = (Vec::new(), Vec::new());
+    let flush = |rows: &mut Vec<Row<'a>>, removed: &mut Vec<&'a str>, added: &mut Vec<&'a str>| {
+        for k in 0..removed.len().max(added.len()) {
+            rows.push(match (removed.get(k), added.get(k)) {
+                (Some(old), Some(new)) => changed_pair(old, new, style),
+                (old, new) => Row::Changed(
+                    old.map(|old| whole(old, style.removed)),
+                    new.map(|new| whole(new, style.added)),
+                ),
+            });
+        }
+        removed.clear();
+        added.clear();
+    };
+    for edit in edits(&old, &new) {
+        match edit {
+            Edit::Keep(i, _) => {
+                flush(&mut rows, &mut removed, &mut added);
+                rows.push(Row::Same(old[i]));
+            }
+            Edit::Remove(i) => removed.push(old[i]),
+            Edit::Add(j) => added.push(new[j]),
+        }
+    }
+    flush(&mut rows, &mut removed, &mut added);
+    rows
+}
+
+/// Render a styled diff of `expected` and `actual`, as a test assertion
+/// shows when they differ.
+///
+/// Lines are compared first, and each removed line is then compared with
+/// the added line in its place word by word, so that the words which
+/// changed stand out in the emphasis style. Lines are marked with `- ` when
+/// removed from `expected`, `+ ` when added in `actual`, and two spaces when
+/// the same in both, so the diff is readable without colors. Lines are split
+/// as by [`str::lines`], and the diff ends without a newline.
+///
+/// # Examples
+///
+/// ```
+/// use nu_ansi_term::diff::{word_diff, DiffLayout, DiffStyle};
+/// use nu_ansi_term::utils::unstyle;
+///
+/// let expected = "name: nu\nversion: 0.1";
+/// let actual = "name: nu\nversion: 0.2";
+///
+/// let inline = word_diff(expected, actual, &DiffStyle::new());
+/// assert_eq!(unstyle(&inline), "  name: nu\n- version: 0.1\n+ version: 0.2");
+/// assert_eq!(
+///     inline.to_string(),
+///     "  name: nu\n\x1b[31m- version: 0.\x1b[7m1\x1b[0m\n\x1b[32m+ version: 0.\x1b[7m2\x1b[0m"
+/// );
+///
+/// let layout = DiffLayout::SideBySide { width: 14 };
+/// let side_by_side = word_diff(expected, actual, &DiffStyle::new().layout(layout));
+/// assert_eq!(
+///     unstyle(&side_by_side),
+///     "  name: nu     │   name: nu\n- version: 0.1 │ + version: 0.2"
+/// );
+/// ```
+pub fn word_diff<'a>(expected: &'a str, actual: &'a str, style: &DiffStyle) -> AnsiStrings<'a> {
+    let rows = rows(expected, actual, style);
+    let mut lines: Vec<AnsiStrings<'a>> = Vec::with_capacity(rows.len());
+    let marked = |marker: &'static str, marker_style: Style, line: AnsiStrings<'a>| {
+        let mut marked = AnsiStrings::with_capacity(line.len() + 1);
+        marked.push(marker_style.paint(marker));
+        marked += line;
+        marked
+    };
+    let same = |line: &'a str| marked("  ", Style::new(), highlight_input(line, &[]));
+    let removed = |line| marked("- ", style.removed, line);
+    let added = |line| marked("+ ", style.added, line);
+
+    match style.layout {
+        DiffLayout::Inline => {
+            for row in rows {
+                match row {
+                    Row::Same(line) => lines.push(same(line)),
+                    Row::Changed(old, new) => {
+                        lines.extend(old.map(removed));
+                        lines.extend(new.map(added));
+                    }
+                }
+            }
+        }
+        DiffLayout::SideBySide { width } => {
+            let column = |line: &mut AnsiStrings<'a>, side: Option<AnsiStrings<'a>>, pad: bool| {
+                let side = side
+                    .unwrap_or_else(|| AnsiStrings::with_capacity(0))
+                    .truncate(width, &ContinuationMarker::ELLIPSIS);
+                for segment in side.iter() {
+                    let segment: AnsiString<'a> = segment.style_ref().paint(segment.unstyled());
+                    line.push(segment);
+                }
+                let padding = width.saturating_sub(side.width());
+                if pad && padding > 0 {
+                    line.push(Style::new().paint(" ".repeat(padding)));
+                }
+            };
+            for row in rows {
+                let (old, new) = match row {
+                    Row::Same(line) => (Some(same(line)), Some(same(line))),
+                    Row::Changed(old, new) => (old.map(removed), new.map(added)),
+                };
+                let mut line = AnsiStrings::with_capacity(8);
+                column(&mut line, old, true);
+                line.push(Style::new().paint(" │ "));
+                column(&mut line, new, false);
+                lines.push(line);
+            }
+        }
+    }
+
+    let mut diff = AnsiStrings::with_capacity(lines.iter().map(|line| line.len() + 1).sum());
+    for (i, line) in lines.into_iter().enumerate() {
+        if i > 0 {
+            diff.push(Style::new().paint("\n"));
+        }
+        diff += line;
+    }
+    diff
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::unstyle;
+    use crate::Color::*;
+
+    #[test]
+    fn emphasises_changed_words_of_paired_lines() {
+        let style = DiffStyle::new().emphasis(Style::new().underline());
+        let diff = word_diff("a\nlet x = 1;\nb", "a\nlet y = 1;\nb\nc", &style);
+        assert_eq!(unstyle(&diff), "  a\n- let x = 1;\n+ let y = 1;\n  b\n+ c");
+        let emphasised: Vec<_> = diff
+            .iter()
+            .filter(|s| {
+                s.style_ref()
+                    .formats
+                    .contains(crate::FormatFlags::UNDERLINE)
+            })
+            .map(|s| (s.unstyled(), *s.style_ref()))
+            .collect();
+        assert_eq!(
+            emphasised,
+            [
+                ("x".to_string(), Red.underline()),
+                ("y".to_string(), Green.underline())
+            ]
+        );
+
+        assert_eq!(unstyle(&word_diff("", "", &style)), "");
+        let layout = DiffLayout::SideBySide { width: 4 };
+        let narrow = word_diff("same\nremoved", "same", &style.layout(layout));
+        assert_eq!(unstyle(&narrow), "  s… │   s…\n- r… │ ");
+    }
+
+    #[test]
+    fn edits_keep_a_longest_common_subsequence() {
+        let cases = [
+            ("", ""),
+            ("abc", ""),
+            ("", "abc"),
+            ("abcabba", "cbabac"),
+            ("kitten", "sitting"),
+            ("aaaa", "aa"),
+            ("xyz", "zyx"),
+        ];
+        for (old, new) in cases {
+            let (old, new): (Vec<char>, Vec<char>) = (old.chars().collect(), new.chars().collect());
+            let edits = edits(&old, &new);
+            let mut lengths = vec![vec![0; new.len() + 1]; old.len() + 1];
+            for i in (0..old.len()).rev() {
+                for j in (0..new.len()).rev() {
+                    lengths[i][j] = if old[i] == new[j] {
+                        lengths[i + 1][j + 1] + 1
+                    } else {
+                        lengths[i + 1][j].max(lengths[i][j + 1])
+                    };
+                }
+            }
+            let kept: Vec<_> = edits
+                .iter()
+                .filter_map(|edit| match edit {
+                    Edit::Keep(i, j) => Some((old[*i], new[*j])),
+                    _ => None,
+                })
+                .collect();
+            assert!(kept.iter().all(|(a, b)| a == b));
+            assert_eq!(kept.len(), lengths[0][0], "{:?} -> {:?}", old, new);
+            let (removed, added) = edits.iter().fold((0, 0), |(r, a), edit| match edit {
+                Edit::Remove(_) => (r + 1, a),
+                Edit::Add(_) => (r, a + 1),
+                Edit::Keep(..) => (r, a),
+            });
+            assert_eq!(
+                (removed, added),
+                (old.len() - kept.len(), new.len() - kept.len())
+            );
+        }
+
+        let old: Vec<usize> = (0..20_000).collect();
+        let mut new = old.clone();
+        new[10_000] = usize::MAX;
+        assert_eq!(edits(&old, &new).len(), 20_001);
+    }
+}
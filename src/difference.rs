@@ -1,4 +1,10 @@
+use crate::ansi::RESET;
+use crate::enabled::is_enabled;
+use crate::fmt_write;
+use crate::instrument::{self, ByteCount, SequenceKind};
 use crate::style::{Coloring, FormatFlags};
+use crate::write::{AnyWrite, WriteResult};
+use std::fmt;
 
 use super::Style;
 
@@ -15,6 +21,82 @@ pub enum StyleDelta {
     Empty,
 }
 
+/// What the codes switching between the styles of consecutive segments are
+/// chosen for when writing a sequence (see
+/// [`AnsiGenericStrings::write_optimized_to_any`]).
+///
+/// [`AnsiGenericStrings::write_optimized_to_any`]: crate::AnsiGenericStrings::write_optimized_to_any
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub enum OptimizeFor {
+    /// Write the codes computed by [`Style::compute_delta`] for each
+    /// transition: only what is turned on if nothing is turned off, and a
+    /// reset followed by the next style otherwise. This is how sequences are
+    /// displayed.
+    #[default]
+    Transitions,
+    /// Write the fewest bytes: each transition is written as the shortest
+    /// of the codes [`Style::compute_delta`] computes, the codes turning
+    /// formats and colors off one by one (see
+    /// [`Style::compute_delta_precise`]), and a reset combined with the next
+    /// style in a single code. Every one of these leaves the terminal in the
+    /// same state, so the shortest code for each transition makes for the
+    /// shortest document.
+    Bytes,
+}
+
+/// The difference between two styles, as computed by the original
+/// `ansi_term` crate, for code migrating from it. Prefer
+/// [`Style::compute_delta`], which this is computed from.
+///
+/// # Examples
+///
+/// ```
+/// use nu_ansi_term::Difference;
+/// use nu_ansi_term::Color::{Blue, Red};
+///
+/// assert_eq!(
+///     Difference::between(&Red.normal(), &Red.bold()),
+///     Difference::ExtraStyles(nu_ansi_term::Style::new().bold())
+/// );
+/// assert_eq!(
+///     Difference::between(&Red.bold(), &Blue.normal()),
+///     Difference::Reset
+/// );
+/// assert_eq!(
+///     Difference::between(&Blue.normal(), &Blue.normal()),
+///     Difference::NoDifference
+/// );
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Difference {
+    /// Print out the control codes specified by this style to end up looking
+    /// like the second string's styles.
+    ExtraStyles(Style),
+    /// Converting between these two is impossible, so just send a reset
+    /// command and then the second string's styles.
+    Reset,
+    /// The before style is exactly the same as the after style, so no further
+    /// control codes need to be printed.
+    NoDifference,
+}
+
+impl Difference {
+    /// Compute the difference between `first` and `next`.
+    pub fn between(first: &Style, next: &Style) -> Difference {
+        first.compute_delta(*next).into()
+    }
+}
+
+impl From<StyleDelta> for Difference {
+    fn from(delta: StyleDelta) -> Self {
+        match delta {
+            StyleDelta::ExtraStyles(style) if style.prefix_before_reset => Difference::Reset,
+            StyleDelta::ExtraStyles(style) => Difference::ExtraStyles(style),
+            StyleDelta::Empty => Difference::NoDifference,
+        }
+    }
+}
+
 /// Tracks which styling properties exist.
 #[derive(Clone, Copy, Debug)]
 pub struct BoolStyle {
@@ -31,12 +113,13 @@ pub struct BoolStyle {
 pub struct BoolColoring {
     pub foreground: bool,
     pub background: bool,
+    pub underline: bool,
 }
 
 impl BoolColoring {
     /// Check if there are no colors set.
     pub fn is_empty(&self) -> bool {
-        !(self.background || self.foreground)
+        !(self.background || self.foreground || self.underline)
     }
 }
 
@@ -45,12 +128,13 @@ impl From<Coloring> for BoolColoring {
         BoolColoring {
             foreground: coloring.fg.is_some(),
             background: coloring.bg.is_some(),
+            underline: coloring.underline.is_some(),
         }
     }
 }
 
 /// Trait for types which can compute how they changed.
-pub trait Difference: Clone + Copy {
+pub trait Toggles: Clone + Copy {
     /// Take the complement (for boolean types, this would be the `!` operator).
     fn not(self) -> Self;
 
@@ -68,7 +152,7 @@ pub trait Difference: Clone + Copy {
     }
 }
 
-impl Difference for bool {
+impl Toggles for bool {
     fn not(self) -> Self {
         !self
     }
@@ -78,11 +162,12 @@ impl Difference for bool {
     }
 }
 
-impl Difference for BoolColoring {
+impl Toggles for BoolColoring {
     fn not(self) -> Self {
         Self {
             foreground: self.foreground.not(),
             background: self.background.not(),
+            underline: self.underline.not(),
         }
     }
 
@@ -90,11 +175,12 @@ impl Difference for BoolColoring {
         Self {
             foreground: self.foreground.conjunction(other.foreground),
             background: self.background.conjunction(other.background),
+            underline: self.underline.conjunction(other.underline),
         }
     }
 }
 
-impl Difference for FormatFlags {
+impl Toggles for FormatFlags {
     fn not(self) -> Self {
         !self
     }
@@ -104,7 +190,7 @@ impl Difference for FormatFlags {
     }
 }
 
-impl Difference for BoolStyle {
+impl Toggles for BoolStyle {
     fn not(self) -> Self {
         Self {
             reset_before_style: self.reset_before_style.not(),
@@ -130,6 +216,7 @@ impl From<Style> for BoolStyle {
             prefix_before_reset: reset_before_style,
             formats,
             coloring,
+            ..
         } = style;
         Self {
             reset_before_style,
@@ -140,13 +227,21 @@ impl From<Style> for BoolStyle {
 }
 
 impl Style {
+    /// Whether this style is written with the same codes as `other`: the
+    /// formats each of them explicitly turns off only matter when inheriting
+    /// from a parent style, and are not written.
+    fn writes_same_codes(self, other: Style) -> bool {
+        Style {
+            cleared: other.cleared,
+            ..self
+        } == other
+    }
+
     /// Computes the differences between two consecutive styles, returning a
     /// result specifying the minimum `Style` required to change from the first
     /// (`self`) style to the `next` style.
     pub fn compute_delta(self, next: Style) -> StyleDelta {
-        println!("computing delta");
-        dbg!(self, next);
-        if self == next {
+        if self.writes_same_codes(next) {
             // If self is the same as next, no changes are required.
             StyleDelta::Empty
         } else if (next.is_empty() && !self.is_empty()) || next.is_reset_before_style() {
@@ -168,6 +263,9 @@ impl Style {
                 if self.is_bg() != next.is_bg() {
                     r = r.set_bg(next.coloring.bg);
                 }
+                if self.is_underline_color() != next.is_underline_color() {
+                    r = r.set_underline_color(next.coloring.underline);
+                }
                 StyleDelta::ExtraStyles(r)
             } else {
                 // If colors were turned off, we need to reset.
@@ -175,6 +273,156 @@ impl Style {
             }
         }
     }
+
+    /// Computes the differences between two consecutive styles like
+    /// [`compute_delta`](Self::compute_delta), but turns off formats and
+    /// colors which are no longer used with their own codes (such as `22`
+    /// for bold or `39` for the foreground color) rather than a full reset.
+    /// This leaves state the styles do not touch alone, and usually makes
+    /// for shorter output.
+    ///
+    /// The `next` style is still written after a reset if it asks for one
+    /// (see [`reset_before_style`](Self::reset_before_style)).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::Color::{Blue, Red};
+    ///
+    /// let delta = Red.bold().compute_delta_precise(Red.normal());
+    /// assert_eq!(delta.to_string(), "\x1b[22m");
+    ///
+    /// let delta = Red.bold().italic().compute_delta_precise(Blue.dimmed());
+    /// assert_eq!(delta.to_string(), "\x1b[22;23;2;34m");
+    /// ```
+    pub fn compute_delta_precise(self, next: Style) -> PreciseDelta {
+        if self.writes_same_codes(next) {
+            return PreciseDelta::default();
+        }
+        if next.is_reset_before_style() {
+            return PreciseDelta {
+                turned_on: next,
+                ..PreciseDelta::default()
+            };
+        }
+
+        let turned_off = self.formats.difference(next.formats);
+        let mut turned_on = self.formats.complement().intersection(next.formats);
+        // A single code turns off both bold and dimmed, and another every
+        // kind of underline, so the ones which stay on are turned on again.
+        for shared in [PreciseDelta::INTENSITY, PreciseDelta::UNDERLINES] {
+            if turned_off.intersects(shared) {
+                turned_on |= next.formats.intersection(shared);
+            }
+        }
+
+        let mut colors_off = BoolColoring::default();
+        let mut r = Style::default().insert_formats(turned_on);
+        if self.is_fg() != next.is_fg() {
+            colors_off.foreground = next.is_fg().is_none();
+            r = r.set_fg(next.coloring.fg);
+        }
+        if self.is_bg() != next.is_bg() {
+            colors_off.background = next.is_bg().is_none();
+            r = r.set_bg(next.coloring.bg);
+        }
+        if self.is_underline_color() != next.is_underline_color() {
+            colors_off.underline = next.is_underline_color().is_none();
+            r = r.set_underline_color(next.coloring.underline);
+        }
+
+        PreciseDelta {
+            turned_off,
+            colors_off,
+            turned_on: r,
+        }
+    }
+}
+
+impl Style {
+    /// Write the shortest codes switching the terminal from this style to
+    /// `next` (see [`OptimizeFor::Bytes`]), preferring the codes of
+    /// [`compute_delta`](Self::compute_delta) if others are no shorter.
+    pub(crate) fn write_shortest_transition<W: AnyWrite + ?Sized>(
+        self,
+        next: Style,
+        w: &mut W,
+    ) -> WriteResult<W::Error>
+    where
+        str: AsRef<W::Buf>,
+        W::Buf: ToOwned,
+    {
+        let shortest = self.shortest_transition(next);
+        if shortest.is_empty() {
+            return Ok(());
+        }
+        w.write_str(shortest.as_str().as_ref())?;
+        if instrument::is_hooked() {
+            let kind = if shortest == RESET {
+                SequenceKind::Reset
+            } else {
+                SequenceKind::Sgr
+            };
+            instrument::emit(kind, shortest.len());
+        }
+        Ok(())
+    }
+
+    /// The codes written by
+    /// [`write_shortest_transition`](Self::write_shortest_transition).
+    fn shortest_transition(self, next: Style) -> String {
+        let mut from = self;
+        from.prefix_before_reset = false;
+
+        let mut delta = String::new();
+        if let StyleDelta::ExtraStyles(style) = from.compute_delta(next) {
+            let _ = style.write_prefix_unreported(fmt_write!(&mut delta));
+        }
+        // Turning things off one by one is no use if `next` asks for a reset.
+        let precise_delta = from.compute_delta_precise(next);
+        let mut precise = None;
+        if !precise_delta.turned_on.prefix_before_reset {
+            let mut codes = String::new();
+            if !precise_delta.is_empty() {
+                let _ = precise_delta.write_codes(&mut codes);
+            }
+            precise = Some(codes);
+        }
+        let mut combined = String::from("\x1B[0");
+        if !next.has_no_styling() {
+            combined.push(';');
+            let _ = next.write_sgr_params(fmt_write!(&mut combined));
+        }
+        combined.push('m');
+
+        [Some(delta), precise, Some(combined)]
+            .into_iter()
+            .flatten()
+            .min_by_key(String::len)
+            .expect("there is always a combined reset")
+    }
+}
+
+/// Displays the exact escape codes which applying the delta writes.
+///
+/// # Examples
+///
+/// ```
+/// use nu_ansi_term::Color::Red;
+///
+/// let delta = Red.normal().compute_delta(Red.bold());
+/// assert_eq!(delta.to_string(), "\x1b[1m");
+///
+/// let delta = Red.bold().compute_delta(Red.normal());
+/// assert_eq!(delta.to_string(), "\x1b[0m\x1b[31m");
+/// ```
+impl fmt::Display for StyleDelta {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StyleDelta::ExtraStyles(style) => style.write_prefix(fmt_write!(f)),
+            StyleDelta::Empty => Ok(()),
+        }
+    }
 }
 
 impl StyleDelta {
@@ -186,11 +434,121 @@ impl StyleDelta {
     }
 }
 
+/// The codes switching from one style to the next which turn off formats and
+/// colors one by one, computed by
+/// [`Style::compute_delta_precise`](Style::compute_delta_precise).
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct PreciseDelta {
+    /// The formats turned off. Bold and dimmed are turned off together, as
+    /// are all kinds of underline.
+    turned_off: FormatFlags,
+    /// The colors turned off.
+    colors_off: BoolColoring,
+    /// The style turned on after turning things off, including formats
+    /// which were turned off together with others but stay on.
+    turned_on: Style,
+}
+
+impl PreciseDelta {
+    /// The formats turned off by the code `22`.
+    const INTENSITY: FormatFlags = FormatFlags::BOLD.union(FormatFlags::DIMMED);
+
+    /// The formats turned off by the code `24`.
+    const UNDERLINES: FormatFlags = FormatFlags::UNDERLINE
+        .union(FormatFlags::DOUBLE_UNDERLINE)
+        .union(FormatFlags::UNDERCURL);
+
+    /// The formats turned off.
+    pub fn turned_off(&self) -> FormatFlags {
+        self.turned_off
+    }
+
+    /// The style turned on after turning things off.
+    pub fn turned_on(&self) -> Style {
+        self.turned_on
+    }
+
+    /// Check if nothing needs to be written.
+    pub fn is_empty(&self) -> bool {
+        self.turned_off.is_empty() && self.colors_off.is_empty() && self.turned_on.is_empty()
+    }
+
+    /// The codes turning off formats and colors, in the order they are
+    /// written.
+    fn off_codes(&self) -> impl Iterator<Item = &'static str> + '_ {
+        let formats = [
+            (Self::INTENSITY, "22"),
+            (FormatFlags::ITALIC, "23"),
+            (Self::UNDERLINES, "24"),
+            (FormatFlags::BLINK, "25"),
+            (FormatFlags::REVERSE, "27"),
+            (FormatFlags::HIDDEN, "28"),
+            (FormatFlags::STRIKETHROUGH, "29"),
+            (FormatFlags::OVERLINE, "55"),
+        ];
+        let colors = [
+            (self.colors_off.foreground, "39"),
+            (self.colors_off.background, "49"),
+            (self.colors_off.underline, "59"),
+        ];
+        formats
+            .into_iter()
+            .filter(|(flags, _)| self.turned_off.intersects(*flags))
+            .map(|(_, code)| code)
+            .chain(
+                colors
+                    .into_iter()
+                    .filter(|(off, _)| *off)
+                    .map(|(_, code)| code),
+            )
+    }
+
+    fn write_codes(&self, f: &mut dyn fmt::Write) -> fmt::Result {
+        f.write_str("\x1B[")?;
+        let mut first = true;
+        for code in self.off_codes() {
+            if !first {
+                f.write_str(";")?;
+            }
+            f.write_str(code)?;
+            first = false;
+        }
+        if !self.turned_on.has_no_styling() {
+            if !first {
+                f.write_str(";")?;
+            }
+            self.turned_on.write_sgr_params(f)?;
+        }
+        f.write_str("m")
+    }
+}
+
+/// Displays the exact escape codes which applying the delta writes: a single
+/// code turning things off and on, or a reset and the next style if it asks
+/// for one.
+impl fmt::Display for PreciseDelta {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.turned_on.prefix_before_reset {
+            return self.turned_on.write_prefix(fmt_write!(f));
+        }
+        if self.is_empty() || !is_enabled() {
+            return Ok(());
+        }
+        self.write_codes(f)?;
+        if instrument::is_hooked() {
+            let mut len = ByteCount::default();
+            let _ = self.write_codes(&mut len);
+            instrument::emit(SequenceKind::Sgr, len.0);
+        }
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::StyleDelta::*;
     use crate::style::Color::*;
-    use crate::style::Style;
+    use crate::style::{BasedOn, Style};
 
     fn style() -> Style {
         Style::new()
@@ -230,4 +588,48 @@ mod test {
     test!(removal_of_hidden:          style().hidden();        style()  => ExtraStyles(style().reset_before_style()));
     test!(removal_of_dimmed:          style().dimmed();        style()  => ExtraStyles(style().reset_before_style()));
     test!(removal_of_blink:           style().blink();         style()  => ExtraStyles(style().reset_before_style()));
+
+    test!(addition_of_undercurl:      style(); style().undercurl()      => ExtraStyles(style().undercurl()));
+    test!(addition_of_overline:       Red.normal(); Red.overline()      => ExtraStyles(style().overline()));
+    test!(removal_of_double_underline: style().double_underline(); style() => ExtraStyles(style().reset_before_style()));
+    test!(underline_color_change:     style().underline_color(Red); style().underline_color(Blue) => ExtraStyles(style().underline_color(Blue)));
+    test!(removal_of_underline_color: Green.normal().underline_color(Red); Green.normal() => ExtraStyles(style().fg(Green).reset_before_style()));
+
+    test!(cleared_in_child: Green.bold(); style().clear_formats(crate::FormatFlags::BOLD).rebase_on(Green.bold()) => ExtraStyles(style().fg(Green).clear_formats(crate::FormatFlags::BOLD).reset_before_style()));
+
+    macro_rules! precise {
+        ($name: ident: $first: expr; $next: expr => $result: expr) => {
+            #[test]
+            fn $name() {
+                assert_eq!($first.compute_delta_precise($next).to_string(), $result);
+            }
+        };
+    }
+
+    precise!(precise_nothing:       Green.bold(); Green.bold()           => "");
+    precise!(precise_unbold:        Green.bold(); Green.normal()         => "\x1b[22m");
+    precise!(precise_bold_to_dim:   Green.bold(); Green.dimmed()         => "\x1b[22;2m");
+    precise!(precise_keeps_dimmed:  style().bold().dimmed(); style().dimmed() => "\x1b[22;2m");
+    precise!(precise_unitalic:      style().italic().blink(); style().blink() => "\x1b[23m");
+    precise!(precise_underlines:    style().underline(); style().undercurl() => "\x1b[24;4:3m");
+    precise!(precise_formats_off:   style().blink().reverse().hidden().strikethrough().overline(); style() => "\x1b[25;27;28;29;55m");
+    precise!(precise_colors_off:    Red.on(Blue).underline_color(Green); style() => "\x1b[39;49;59m");
+    precise!(precise_color_change:  Red.bold(); Blue.normal()            => "\x1b[22;34m");
+    precise!(precise_explicit_reset: Red.bold(); Blue.normal().reset_before_style() => "\x1b[0m\x1b[34m");
+
+    macro_rules! shortest {
+        ($name: ident: $first: expr; $next: expr => $result: expr) => {
+            #[test]
+            fn $name() {
+                assert_eq!($first.shortest_transition($next), $result);
+            }
+        };
+    }
+
+    shortest!(shortest_nothing:        Green.bold(); Green.bold()          => "");
+    shortest!(shortest_addition:       Green.normal(); Green.bold()        => "\x1b[1m");
+    shortest!(shortest_unbold:         Green.bold(); Green.normal()        => "\x1b[22m");
+    shortest!(shortest_to_plain:       Red.on(Blue).italic(); style()      => "\x1b[0m");
+    shortest!(shortest_combined_reset: Red.bold().italic(); Blue.normal()  => "\x1b[0;34m");
+    shortest!(shortest_explicit_reset: Red.bold(); Red.normal().reset_before_style() => "\x1b[0;31m");
 }
@@ -1,22 +1,59 @@
 use crate::ansi::RESET;
-use crate::difference::StyleDelta;
+use crate::difference::{OptimizeFor, StyleDelta};
+use crate::enabled::is_enabled;
+use crate::instrument::{self, SequenceKind};
+use crate::intern::ContentInterner;
+use crate::level::ColorLevel;
+use crate::profile::TerminalProfile;
+use crate::span::SourceSpan;
+use crate::strip::{strip_ansi_codes, strip_bytes};
 use crate::style::{BasedOn, Color, Style};
+use crate::width::pad_rendered;
 use crate::write::{AnyWrite, Content, StrLike, WriteResult};
-use crate::{fmt_write, io_write, write_fmt, write_str};
+use crate::{fmt_write, io_write, write_str};
+use crate::{nested, osc};
+use paste::paste;
 use std::borrow::Cow;
 use std::cell::{Ref, RefCell, RefMut};
 use std::fmt::{self, Debug};
+use std::hash::{Hash, Hasher};
 use std::io;
+use std::path::Path;
+use std::sync::Arc;
 
 /// Represents various features that require "OS Control" ANSI codes.
+///
+/// More kinds of control are added as terminals gain features, so matches
+/// on this enum need a wildcard arm.
+#[non_exhaustive]
 pub enum OSControl<'a, S: 'a + ToOwned + ?Sized> {
     /// Set the title of a terminal window.
     Title,
     /// Create a clickable-link.
+    #[non_exhaustive]
     Link {
         /// The url underlying the clickable link.
         url: Content<'a, S>,
+        /// The `key=value` parameters of the link, such as its `id`.
+        params: Vec<(String, String)>,
+    },
+    /// The content is itself a control sequence (such as a cursor movement or
+    /// a screen clear), which is written verbatim and does not change the
+    /// style of the text around it.
+    Control,
+    /// Copy the content, which is data encoded in base64, to the clipboard
+    /// (OSC 52).
+    Clipboard,
+    /// Show a desktop notification whose body is the content (OSC 777, or
+    /// OSC 9 if it has no title).
+    #[non_exhaustive]
+    Notify {
+        /// The title of the notification, which should not contain `;`.
+        title: Option<Content<'a, S>>,
     },
+    /// Report the current working directory, whose `file://` URL is the
+    /// content (OSC 7).
+    Cwd,
 }
 
 /// We manually implement [`Debug`](fmt::Debug) so that it is specifically only
@@ -28,7 +65,15 @@ where
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::Title => write!(f, "Title"),
-            Self::Link { url } => f.debug_struct("Link").field("url", url).finish(),
+            Self::Link { url, params } => f
+                .debug_struct("Link")
+                .field("url", url)
+                .field("params", params)
+                .finish(),
+            Self::Control => write!(f, "Control"),
+            Self::Clipboard => write!(f, "Clipboard"),
+            Self::Notify { title } => f.debug_struct("Notify").field("title", title).finish(),
+            Self::Cwd => write!(f, "Cwd"),
         }
     }
 }
@@ -36,8 +81,17 @@ where
 impl<'a, S: 'a + ToOwned + ?Sized> Clone for OSControl<'a, S> {
     fn clone(&self) -> Self {
         match self {
-            Self::Link { url: u } => Self::Link { url: u.clone() },
+            Self::Link { url: u, params } => Self::Link {
+                url: u.clone(),
+                params: params.clone(),
+            },
             Self::Title => Self::Title,
+            Self::Control => Self::Control,
+            Self::Clipboard => Self::Clipboard,
+            Self::Notify { title } => Self::Notify {
+                title: title.clone(),
+            },
+            Self::Cwd => Self::Cwd,
         }
     }
 }
@@ -87,6 +141,72 @@ impl<'a, S: 'a + ToOwned + ?Sized> Clone for AnsiGenericString<'a, S> {
     }
 }
 
+/// Strings are equal if they have equal styles, content and operating
+/// system controls (see [`Content`] for when contents are equal), so that
+/// painted strings can be compared in tests and kept in sets and maps.
+///
+/// ```
+/// use std::collections::HashSet;
+/// use nu_ansi_term::Color::{Green, Red};
+///
+/// let seen: HashSet<_> = [Red.paint("a"), Green.paint("b"), Red.paint("a")].into();
+/// assert_eq!(seen.len(), 2);
+/// assert!(seen.contains(&Red.paint(String::from("a"))));
+/// ```
+impl<'a, S: 'a + ToOwned + ?Sized + PartialEq> PartialEq for AnsiGenericString<'a, S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.style == other.style
+            && self.content == other.content
+            && self.oscontrol == other.oscontrol
+    }
+}
+
+impl<'a, S: 'a + ToOwned + ?Sized + Eq> Eq for AnsiGenericString<'a, S> {}
+
+impl<'a, S: 'a + ToOwned + ?Sized + Hash> Hash for AnsiGenericString<'a, S> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.style.hash(state);
+        self.content.hash(state);
+        self.oscontrol.hash(state);
+    }
+}
+
+impl<'a, S: 'a + ToOwned + ?Sized + PartialEq> PartialEq for OSControl<'a, S> {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (
+                Self::Link { url, params },
+                Self::Link {
+                    url: other_url,
+                    params: other_params,
+                },
+            ) => url == other_url && params == other_params,
+            (Self::Notify { title }, Self::Notify { title: other_title }) => title == other_title,
+            (Self::Title, Self::Title)
+            | (Self::Control, Self::Control)
+            | (Self::Clipboard, Self::Clipboard)
+            | (Self::Cwd, Self::Cwd) => true,
+            _ => false,
+        }
+    }
+}
+
+impl<'a, S: 'a + ToOwned + ?Sized + Eq> Eq for OSControl<'a, S> {}
+
+impl<'a, S: 'a + ToOwned + ?Sized + Hash> Hash for OSControl<'a, S> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            Self::Link { url, params } => {
+                url.hash(state);
+                params.hash(state);
+            }
+            Self::Notify { title } => title.hash(state),
+            Self::Title | Self::Control | Self::Clipboard | Self::Cwd => {}
+        }
+    }
+}
+
 // You might think that the hand-written Clone impl above is the same as the
 // one that gets generated with #[derive]. But it’s not *quite* the same!
 //
@@ -203,6 +323,27 @@ impl<'a, S: 'a + ToOwned + ?Sized> AnsiGenericString<'a, S> {
         &self.oscontrol
     }
 
+    /// Produce an ANSI string without content, which only resets the terminal
+    /// back to its default style.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::{AnsiString, RESET};
+    ///
+    /// assert_eq!(AnsiString::reset().to_string(), RESET);
+    /// ```
+    pub fn reset() -> Self
+    where
+        str: AsRef<S>,
+    {
+        Self {
+            style: Style::reset(),
+            content: Content::StrLike(Cow::Borrowed("".as_ref())),
+            oscontrol: None,
+        }
+    }
+
     // Instances that imply wrapping in OSC sequences
     // and do not get displayed in the terminal text
     // area.
@@ -269,6 +410,110 @@ impl<'a, S: 'a + ToOwned + ?Sized> AnsiGenericString<'a, S> {
         }
     }
 
+    /// Produce a segment whose content is a control sequence, such as a
+    /// cursor movement or a screen clear, rather than text. It is written
+    /// verbatim, and does not change the style of the segments around it
+    /// when collected into [`AnsiGenericStrings`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::{AnsiString, AnsiStrings};
+    /// use nu_ansi_term::Color::Red;
+    ///
+    /// let strings = AnsiStrings([
+    ///     Red.paint("progress"),
+    ///     AnsiString::control("\x1b[2K\r"),
+    ///     Red.paint("done"),
+    /// ]);
+    /// assert_eq!(strings.to_string(), "\x1b[31mprogress\x1b[2K\rdone\x1b[0m");
+    /// ```
+    pub fn control<I>(s: I) -> Self
+    where
+        I: Into<Content<'a, S>>,
+    {
+        Self {
+            style: Style::new(),
+            content: s.into(),
+            oscontrol: Some(OSControl::<S>::Control),
+        }
+    }
+
+    /// Produce a segment which shows a desktop notification with a title
+    /// (OSC 777), on terminals which support it. The title ends at its first
+    /// semicolon, so should not contain one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::AnsiString;
+    ///
+    /// assert_eq!(
+    ///     AnsiString::notify("build", "finished in 3s").to_string(),
+    ///     "\x1b]777;notify;build;finished in 3s\x1b\\"
+    /// );
+    /// ```
+    pub fn notify<T, B>(title: T, body: B) -> Self
+    where
+        T: Into<Content<'a, S>>,
+        B: Into<Content<'a, S>>,
+    {
+        Self {
+            style: Style::new(),
+            content: body.into(),
+            oscontrol: Some(OSControl::Notify {
+                title: Some(title.into()),
+            }),
+        }
+    }
+
+    /// Produce a segment which shows a desktop notification without a title
+    /// (OSC 9), which more terminals support than notifications with one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::AnsiString;
+    ///
+    /// assert_eq!(
+    ///     AnsiString::notify_body("finished in 3s").to_string(),
+    ///     "\x1b]9;finished in 3s\x1b\\"
+    /// );
+    /// ```
+    pub fn notify_body<B>(body: B) -> Self
+    where
+        B: Into<Content<'a, S>>,
+    {
+        Self {
+            style: Style::new(),
+            content: body.into(),
+            oscontrol: Some(OSControl::Notify { title: None }),
+        }
+    }
+
+    /// Whether this is a control segment (see [`control`](Self::control)),
+    /// or another command which shows no text, such as a notification.
+    pub const fn is_control(&self) -> bool {
+        matches!(
+            self.oscontrol,
+            Some(OSControl::Control)
+                | Some(OSControl::Clipboard)
+                | Some(OSControl::Notify { .. })
+                | Some(OSControl::Cwd)
+        )
+    }
+
+    /// The style this segment switches to when written in a sequence, or
+    /// `None` if it keeps the style of the segment before it. Titles and
+    /// control segments are not displayed as text, so their style would
+    /// only force needless codes around them.
+    pub(crate) fn scheduled_style(&self) -> Option<Style> {
+        match self.oscontrol {
+            Some(OSControl::Link { .. }) | None => Some(self.style),
+            Some(_) => None,
+        }
+    }
+
     //
     // Annotations (OSC sequences that do more than wrap)
     //
@@ -289,7 +534,10 @@ impl<'a, S: 'a + ToOwned + ?Sized> AnsiGenericString<'a, S> {
     where
         I: Into<Content<'a, S>>,
     {
-        self.oscontrol = Some(OSControl::Link { url: url.into() });
+        self.oscontrol = Some(OSControl::Link {
+            url: url.into(),
+            params: Vec::new(),
+        });
         self
     }
 
@@ -312,169 +560,857 @@ impl<'a, S: 'a + ToOwned + ?Sized> AnsiGenericString<'a, S> {
             content: self.content,
             oscontrol: Some(OSControl::Link {
                 url: Content::StrLike(Cow::Borrowed(url)),
+                params: Vec::new(),
             }),
         }
     }
 
+    /// Give the link of this string an `id`, so that terminals treat the
+    /// segments linking to the same URL with the same id as one link, e.g.
+    /// underlining all of them on hover. Does nothing if the string has no
+    /// link.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::Color::{Blue, Red};
+    ///
+    /// let start = Blue.paint("read ").hyperlink("https://example.com").link_id("docs");
+    /// let end = Red.paint("me").hyperlink("https://example.com").link_id("docs");
+    /// assert_eq!(
+    ///     start.to_string(),
+    ///     "\x1b[34m\x1b]8;id=docs;https://example.com\x1b\\read \x1b]8;;\x1b\\\x1b[0m"
+    /// );
+    /// ```
+    pub fn link_id(self, id: impl Into<String>) -> Self {
+        self.link_param("id", id)
+    }
+
+    /// Add the parameter `key=value` to the link of this string, replacing
+    /// any value `key` already had. Colons, semicolons and control
+    /// characters, which cannot be written in parameters, are removed.
+    /// Does nothing if the string has no link.
+    pub fn link_param(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        if let Some(OSControl::Link { params, .. }) = &mut self.oscontrol {
+            let key = key.into();
+            params.retain(|(other, _)| *other != key);
+            params.push((key, value.into()));
+        }
+        self
+    }
+
+    /// The parameters of the link of this string, which are empty if it has
+    /// no link.
+    pub fn link_params(&self) -> &[(String, String)] {
+        match &self.oscontrol {
+            Some(OSControl::Link { params, .. }) => params,
+            _ => &[],
+        }
+    }
+
     /// Get the url content for this string's oscontrol.
     pub const fn url_string(&self) -> Option<&Content<'a, S>> {
-        if let Some(osc) = &self.oscontrol {
-            match osc {
-                OSControl::Title => {}
-                OSControl::Link { url } => {
-                    return Some(url);
-                }
-            }
+        match &self.oscontrol {
+            Some(OSControl::Link { url, .. }) => Some(url),
+            _ => None,
         }
-        None
     }
-}
 
-/// A set of `AnsiGenericStrings`s collected together, in order to be
-/// written with a minimum of control characters.
-pub struct AnsiGenericStrings<'a, S: 'a + ToOwned + ?Sized> {
-    strings: Cow<'a, [AnsiGenericString<'a, S>]>,
-    style_updates: RefCell<Cow<'a, [StyleUpdate]>>,
+    /// Remove the link from this string, if it has one.
+    pub(crate) fn without_link(mut self) -> Self {
+        if let Some(OSControl::Link { .. }) = self.oscontrol {
+            self.oscontrol = None;
+        }
+        self
+    }
 }
 
-impl<'a, S: 'a + ToOwned + ?Sized> From<AnsiGenericString<'a, S>> for AnsiGenericStrings<'a, S> {
-    fn from(value: AnsiGenericString<'a, S>) -> Self {
-        let style = value.style;
-        Self {
-            strings: Cow::Owned(vec![value]),
-            style_updates: RefCell::new(Cow::Owned(vec![StyleUpdate {
-                style_delta: StyleDelta::ExtraStyles(style),
-                begins_at: 0,
-            }])),
+impl<'a, S: 'static + ToOwned + ?Sized> AnsiGenericString<'a, S>
+where
+    S::Owned: From<String>,
+{
+    /// Convert this string into one which borrows nothing, keeping its style
+    /// and link, so that it can be stored or returned from the function that
+    /// painted it. Borrowed contents are copied, and formatting arguments are
+    /// rendered.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::AnsiString;
+    /// use nu_ansi_term::Color::Red;
+    ///
+    /// fn error(name: &str) -> AnsiString<'static> {
+    ///     let url = format!("https://example.com/{}", name);
+    ///     Red.paint(name).hyperlink(url.as_str()).into_owned()
+    /// }
+    /// assert_eq!(
+    ///     error("E42").to_string(),
+    ///     Red.paint("E42").hyperlink("https://example.com/E42").to_string()
+    /// );
+    /// ```
+    pub fn into_owned(self) -> AnsiGenericString<'static, S> {
+        AnsiGenericString {
+            style: self.style,
+            content: content_into_owned(self.content, Some(self.style)),
+            oscontrol: self.oscontrol.map(|osc| match osc {
+                OSControl::Title => OSControl::Title,
+                OSControl::Control => OSControl::Control,
+                OSControl::Link { url, params } => OSControl::Link {
+                    url: content_into_owned(url, None),
+                    params,
+                },
+                OSControl::Clipboard => OSControl::Clipboard,
+                OSControl::Notify { title } => OSControl::Notify {
+                    title: title.map(|title| content_into_owned(title, None)),
+                },
+                OSControl::Cwd => OSControl::Cwd,
+            }),
         }
     }
 }
 
-impl<'a, S: 'a + ToOwned + ?Sized> Clone for AnsiGenericStrings<'a, S> {
-    fn clone(&self) -> Self {
-        Self {
-            style_updates: RefCell::new(self.style_updates.borrow_mut().clone()),
-            strings: self.strings.clone(),
+impl<'a, S: 'static + ToOwned + ?Sized> AnsiGenericStrings<'a, S>
+where
+    S::Owned: From<String>,
+{
+    /// Convert these strings into ones which borrow nothing (see
+    /// [`AnsiGenericString::into_owned`]), keeping their style updates and
+    /// lazily resolved styles.
+    pub fn into_owned(self) -> AnsiGenericStrings<'static, S> {
+        AnsiGenericStrings {
+            strings: Cow::Owned(
+                self.strings
+                    .into_owned()
+                    .into_iter()
+                    .map(AnsiGenericString::into_owned)
+                    .collect(),
+            ),
+            style_updates: RefCell::new(Cow::Owned(self.style_updates.into_inner().into_owned())),
+            lazy_styles: self.lazy_styles,
+            spans: self.spans,
         }
     }
 }
 
-/// We manually implement [`Debug`](fmt::Debug) so that it is specifically only
-/// implemented when `S` also implements `Debug`.
-impl<'a, S: 'a + ToOwned + ?Sized> Debug for AnsiGenericStrings<'a, S>
+impl<'a, S: 'static + ToOwned + ?Sized> AnsiGenericString<'a, S>
 where
-    S: fmt::Debug,
+    S::Owned: From<String>,
 {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_struct("AnsiGenericStrings")
-            .field("strings", &self.strings)
-            .field("style_updates", &self.style_updates.borrow_mut())
-            .finish()
+    /// Convert this string into one which borrows nothing (see
+    /// [`into_owned`](Self::into_owned)), wrapped so that it can be sent to
+    /// another thread.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::Color::Red;
+    ///
+    /// let name = String::from("E42");
+    /// let painted = Red.paint(format_args!("error {}", name)).into_sendable();
+    /// let rendered = std::thread::spawn(move || painted.into_inner().to_string())
+    ///     .join()
+    ///     .unwrap();
+    /// assert_eq!(rendered, "\x1b[31merror E42\x1b[0m");
+    /// ```
+    pub fn into_sendable(self) -> Sendable<AnsiGenericString<'static, S>> {
+        Sendable(sendable::SendableString::new(self))
     }
 }
 
-impl<'a, S: 'a + ToOwned + ?Sized> AnsiGenericStrings<'a, S> {
-    pub const fn new(strings: &'a [AnsiGenericString<'a, S>]) -> Self {
-        Self {
-            strings: Cow::Borrowed(strings),
-            style_updates: RefCell::new(Cow::Borrowed(&[])),
-        }
-    }
-    /// Create empty sequence with the given capacity.
-    pub fn with_capacity(capacity: usize) -> Self {
-        Self {
-            strings: Vec::with_capacity(capacity).into(),
-            style_updates: RefCell::new(Vec::with_capacity(capacity).into()),
-        }
+impl<'a, S: 'static + ToOwned + ?Sized> AnsiGenericStrings<'a, S>
+where
+    S::Owned: From<String>,
+{
+    /// Convert these strings into ones which borrow nothing (see
+    /// [`into_owned`](Self::into_owned)), wrapped so that they can be sent
+    /// to another thread.
+    pub fn into_sendable(self) -> Sendable<AnsiGenericStrings<'static, S>> {
+        Sendable(sendable::SendableStrings::new(self))
     }
+}
 
-    /// Iterate over the underlying generic strings.
-    pub fn iter(&self) -> impl Iterator<Item = &'_ AnsiGenericString<'a, S>> {
-        self.strings.iter()
+/// A painted string (or sequence of them) which borrows nothing, and can be
+/// sent to another thread. Created by
+/// [`AnsiGenericString::into_sendable`] and
+/// [`AnsiGenericStrings::into_sendable`].
+///
+/// `AnsiString<'static>` itself is not `Send`: its content may be
+/// [`fmt::Arguments`], and sequences cache their style updates in a
+/// `RefCell`. The strings are held here in a form which owns its contents
+/// and has no room for formatting arguments or caches, and are only turned
+/// back into painted strings by [`into_inner`](Self::into_inner).
+pub struct Sendable<T: sendable::FromParts>(T::Parts);
+
+impl<T: sendable::FromParts> Sendable<T> {
+    /// Unwrap the owned string, on whichever thread it was sent to.
+    pub fn into_inner(self) -> T {
+        T::from_parts(self.0)
     }
+}
 
-    fn calculate_style_updates(&self) {
-        let mut style_updates = Vec::with_capacity(self.strings.len());
-        for (ix, string) in self.strings.iter().enumerate() {
-            Self::push_style_into(&mut style_updates, string.style, ix);
-        }
-        *self.style_updates.borrow_mut() = Cow::Owned(style_updates);
+impl<T: sendable::FromParts> Debug for Sendable<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sendable").finish_non_exhaustive()
     }
+}
 
-    /// Get the style updates required to build this string.
+/// The form [`Sendable`] holds painted strings in.
+mod sendable {
+    use super::{AnsiGenericString, AnsiGenericStrings, LazyStyle, OSControl};
+    use crate::span::SourceSpan;
+    use crate::write::Content;
+    use crate::{nested, Style};
+    use std::sync::Arc;
+
+    /// Painted strings which can be rebuilt from the parts a [`Sendable`]
+    /// holds them in.
     ///
-    /// If they are not yet computed, they will be computed, otherwise the cached updates will be returned.
-    fn style_updates(&self) -> Ref<'_, Cow<'_, [StyleUpdate]>> {
-        if self.strings.len() != self.style_updates.borrow().len() {
-            self.calculate_style_updates();
-        }
-        self.style_updates.borrow()
+    /// [`Sendable`]: super::Sendable
+    pub trait FromParts {
+        type Parts;
+
+        fn from_parts(parts: Self::Parts) -> Self;
     }
 
-    /// Get mutable access to the style updates required to build this string.
-    ///
-    /// If they are not yet computed, they will be computed, otherwise the cached updates will be returned.
-    fn style_updates_mut(&self) -> RefMut<'_, Cow<'a, [StyleUpdate]>> {
-        if self.strings.len() != self.style_updates.borrow().len() {
-            self.calculate_style_updates();
-        }
-        self.style_updates.borrow_mut()
+    /// The parts of an [`AnsiGenericString`] which borrows nothing. See
+    /// [`Sendable`](super::Sendable).
+    pub struct SendableString<S: 'static + ToOwned + ?Sized> {
+        style: Style,
+        content: SendableContent<S>,
+        oscontrol: Option<SendableControl<S>>,
     }
 
-    /// Update specific generic strings.
-    ///
-    /// Depending on where the updates are made, not all style deltas will be
-    /// re-computed.
-    pub fn update_strings(
-        &mut self,
-        updates: impl IntoIterator<Item = (usize, AnsiGenericString<'a, S>)>,
-    ) -> Self {
-        let mut updates: Vec<(usize, AnsiGenericString<'a, S>)> = updates.into_iter().collect();
+    /// [`Content`] which borrows nothing, and cannot be formatting arguments.
+    enum SendableContent<S: 'static + ToOwned + ?Sized> {
+        Owned(S::Owned),
+        Shared(Arc<S>),
+        Strings(SendableStrings<S>),
+    }
 
-        if updates.is_empty() {
-            return self.clone();
-        }
+    /// An [`OSControl`] whose contents borrow nothing.
+    enum SendableControl<S: 'static + ToOwned + ?Sized> {
+        Title,
+        Link {
+            url: SendableContent<S>,
+            params: Vec<(String, String)>,
+        },
+        Control,
+        Clipboard,
+        Notify {
+            title: Option<SendableContent<S>>,
+        },
+        Cwd,
+    }
 
-        // Now we know updates are not empty.
-        updates.sort_unstable_by(|a, b| a.0.cmp(&b.0));
-        let min_changed_ix = updates.first().unwrap().0;
+    /// The parts of an [`AnsiGenericStrings`] which borrows nothing. Its style
+    /// updates are computed again when it is rebuilt.
+    pub struct SendableStrings<S: 'static + ToOwned + ?Sized> {
+        strings: Vec<SendableString<S>>,
+        lazy_styles: Vec<(usize, LazyStyle)>,
+        spans: Vec<(usize, SourceSpan)>,
+    }
 
-        let mut new_strings = self.strings.to_vec();
-        let original_len = new_strings.len();
+    impl<S: 'static + ToOwned + ?Sized> SendableContent<S>
+    where
+        S::Owned: From<String>,
+    {
+        /// Take `content` apart, rendering formatting arguments within `style`
+        /// if they are the text of a string painted in it.
+        pub(super) fn new(content: Content<'_, S>, style: Option<Style>) -> Self {
+            match content {
+                Content::FmtArgs(args) => SendableContent::Owned(
+                    match style {
+                        Some(style) => nested::within(style, || args.to_string()),
+                        None => args.to_string(),
+                    }
+                    .into(),
+                ),
+                Content::StrLike(s) => SendableContent::Owned(s.into_owned()),
+                Content::Shared(s) => SendableContent::Shared(s),
+                Content::GenericStrings(strings) => {
+                    SendableContent::Strings(SendableStrings::new(strings))
+                }
+            }
+        }
 
-        for (u_ix, u) in updates.into_iter() {
-            if u_ix < original_len {
-                new_strings[u_ix] = u;
-            } else {
-                new_strings.push(u);
+        fn into_content(self) -> Content<'static, S> {
+            match self {
+                SendableContent::Owned(value) => Content::owned(value),
+                SendableContent::Shared(s) => Content::Shared(s),
+                SendableContent::Strings(strings) => {
+                    Content::GenericStrings(strings.into_strings())
+                }
             }
         }
+    }
 
-        if min_changed_ix < original_len {
-            let unchanged_existing = &self.style_updates()[0..min_changed_ix];
-            let mut new_style_updates = Vec::with_capacity(new_strings.len());
-            new_style_updates.extend(unchanged_existing);
+    impl<S: 'static + ToOwned + ?Sized> SendableString<S>
+    where
+        S::Owned: From<String>,
+    {
+        pub(super) fn new(string: AnsiGenericString<'_, S>) -> Self {
+            SendableString {
+                style: string.style,
+                content: SendableContent::new(string.content, Some(string.style)),
+                oscontrol: string.oscontrol.map(|osc| match osc {
+                    OSControl::Title => SendableControl::Title,
+                    OSControl::Control => SendableControl::Control,
+                    OSControl::Link { url, params } => SendableControl::Link {
+                        url: SendableContent::new(url, None),
+                        params,
+                    },
+                    OSControl::Clipboard => SendableControl::Clipboard,
+                    OSControl::Notify { title } => SendableControl::Notify {
+                        title: title.map(|title| SendableContent::new(title, None)),
+                    },
+                    OSControl::Cwd => SendableControl::Cwd,
+                }),
+            }
+        }
 
-            for (ix, style) in new_strings[min_changed_ix..]
-                .iter()
-                .map(|s| s.style)
-                .enumerate()
-            {
-                Self::push_style_into(&mut new_style_updates, style, ix + min_changed_ix)
+        fn into_string(self) -> AnsiGenericString<'static, S> {
+            AnsiGenericString {
+                style: self.style,
+                content: self.content.into_content(),
+                oscontrol: self.oscontrol.map(|osc| match osc {
+                    SendableControl::Title => OSControl::Title,
+                    SendableControl::Control => OSControl::Control,
+                    SendableControl::Link { url, params } => OSControl::Link {
+                        url: url.into_content(),
+                        params,
+                    },
+                    SendableControl::Clipboard => OSControl::Clipboard,
+                    SendableControl::Notify { title } => OSControl::Notify {
+                        title: title.map(SendableContent::into_content),
+                    },
+                    SendableControl::Cwd => OSControl::Cwd,
+                }),
             }
+        }
+    }
 
-            Self {
-                strings: Cow::Owned(new_strings),
-                style_updates: RefCell::new(Cow::Owned(new_style_updates)),
+    impl<S: 'static + ToOwned + ?Sized> SendableStrings<S>
+    where
+        S::Owned: From<String>,
+    {
+        pub(super) fn new(strings: AnsiGenericStrings<'_, S>) -> Self {
+            SendableStrings {
+                strings: strings
+                    .strings
+                    .into_owned()
+                    .into_iter()
+                    .map(SendableString::new)
+                    .collect(),
+                lazy_styles: strings.lazy_styles,
+                spans: strings.spans,
+            }
+        }
+
+        fn into_strings(self) -> AnsiGenericStrings<'static, S> {
+            AnsiGenericStrings {
+                lazy_styles: self.lazy_styles,
+                spans: self.spans,
+                ..self
+                    .strings
+                    .into_iter()
+                    .map(SendableString::into_string)
+                    .collect()
             }
-        } else {
-            Self::from_iter(new_strings)
         }
     }
 
-    /// Rebase a nested string onto a parent's style. This is effectively an
+    impl<S: 'static + ToOwned + ?Sized> FromParts for AnsiGenericString<'static, S>
+    where
+        S::Owned: From<String>,
+    {
+        type Parts = SendableString<S>;
+
+        fn from_parts(parts: Self::Parts) -> Self {
+            parts.into_string()
+        }
+    }
+
+    impl<S: 'static + ToOwned + ?Sized> FromParts for AnsiGenericStrings<'static, S>
+    where
+        S::Owned: From<String>,
+    {
+        type Parts = SendableStrings<S>;
+
+        fn from_parts(parts: Self::Parts) -> Self {
+            parts.into_strings()
+        }
+    }
+}
+
+/// Convert `content` into content which borrows nothing. Formatting
+/// arguments are rendered, within `style` if they are the text of a string
+/// painted in it.
+fn content_into_owned<S: 'static + ToOwned + ?Sized>(
+    content: Content<'_, S>,
+    style: Option<Style>,
+) -> Content<'static, S>
+where
+    S::Owned: From<String>,
+{
+    match content {
+        Content::FmtArgs(args) => Content::owned(
+            match style {
+                Some(style) => nested::within(style, || args.to_string()),
+                None => args.to_string(),
+            }
+            .into(),
+        ),
+        Content::StrLike(s) => Content::owned(s.into_owned()),
+        Content::Shared(s) => Content::Shared(s),
+        Content::GenericStrings(strings) => Content::GenericStrings(strings.into_owned()),
+    }
+}
+
+impl<'a> AnsiGenericString<'a, str> {
+    /// A copy of this string whose content (and link) is shared through
+    /// `interner`.
+    pub fn interned(&self, interner: &mut ContentInterner) -> AnsiString<'static> {
+        AnsiGenericString {
+            style: self.style,
+            content: interner.intern_content(&self.content),
+            oscontrol: self.oscontrol.as_ref().map(|osc| match osc {
+                OSControl::Title => OSControl::Title,
+                OSControl::Control => OSControl::Control,
+                OSControl::Link { url, params } => OSControl::Link {
+                    url: interner.intern_content(url),
+                    params: params.clone(),
+                },
+                OSControl::Clipboard => OSControl::Clipboard,
+                OSControl::Notify { title } => OSControl::Notify {
+                    title: title.as_ref().map(|title| interner.intern_content(title)),
+                },
+                OSControl::Cwd => OSControl::Cwd,
+            }),
+        }
+    }
+
+    /// Produce a segment which copies `data` to the clipboard (OSC 52), on
+    /// terminals which allow it. The data is base64-encoded as the command
+    /// requires.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::AnsiString;
+    ///
+    /// assert_eq!(
+    ///     AnsiString::clipboard_copy("hello").to_string(),
+    ///     "\x1b]52;c;aGVsbG8=\x1b\\"
+    /// );
+    /// ```
+    pub fn clipboard_copy(data: impl AsRef<[u8]>) -> Self {
+        Self {
+            style: Style::new(),
+            content: Content::owned(osc::base64_encode(data.as_ref())),
+            oscontrol: Some(OSControl::Clipboard),
+        }
+    }
+
+    /// Produce a segment which tells the terminal the current working
+    /// directory is `path` (OSC 7), so that it can open new tabs there.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::AnsiString;
+    ///
+    /// assert_eq!(
+    ///     AnsiString::cwd("/home/me/my files").to_string(),
+    ///     "\x1b]7;file:///home/me/my%20files\x1b\\"
+    /// );
+    /// ```
+    pub fn cwd(path: impl AsRef<Path>) -> Self {
+        Self {
+            style: Style::new(),
+            content: Content::owned(osc::file_url(path.as_ref())),
+            oscontrol: Some(OSControl::Cwd),
+        }
+    }
+
+    /// An owned copy of this string (and its link) holding `content`
+    /// instead.
+    pub(crate) fn with_content(&self, content: Content<'static, str>) -> AnsiString<'static> {
+        AnsiGenericString {
+            style: self.style,
+            content,
+            oscontrol: self.oscontrol.as_ref().map(|osc| match osc {
+                OSControl::Title => OSControl::Title,
+                OSControl::Control => OSControl::Control,
+                OSControl::Link { url, params } => OSControl::Link {
+                    url: Content::owned(url.to_string()),
+                    params: params.clone(),
+                },
+                OSControl::Clipboard => OSControl::Clipboard,
+                OSControl::Notify { title } => OSControl::Notify {
+                    title: title
+                        .as_ref()
+                        .map(|title| Content::owned(title.to_string())),
+                },
+                OSControl::Cwd => OSControl::Cwd,
+            }),
+        }
+    }
+
+    /// The kind of OSC sequence this string is written in, if any.
+    pub(crate) fn sequence_kind(&self) -> Option<SequenceKind> {
+        osc_kind(&self.oscontrol)
+    }
+
+    /// The command written by this control segment (see
+    /// [`is_control`](Self::is_control)), whether the terminal supports it
+    /// or not.
+    pub(crate) fn control_command(&self) -> String {
+        let mut command = String::new();
+        Self::write_osc(&self.content, &self.oscontrol, fmt_write!(&mut command))
+            .expect("writing to a String cannot fail");
+        command
+    }
+}
+
+impl<'a> AnsiGenericStrings<'a, str> {
+    /// Render this sequence, choosing the codes between segments as
+    /// `optimize` says. [`OptimizeFor::Bytes`] makes for the shortest
+    /// output, e.g. for documents sent over slow links or stored in logs.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::{AnsiStrings, OptimizeFor};
+    /// use nu_ansi_term::Color::{Blue, Red};
+    ///
+    /// let strings = AnsiStrings([
+    ///     Red.bold().italic().paint("a"),
+    ///     Red.italic().paint("b"),
+    ///     Blue.normal().paint("c"),
+    /// ]);
+    /// assert_eq!(
+    ///     strings.render_optimized(OptimizeFor::Transitions),
+    ///     "\x1b[1;3;31ma\x1b[0m\x1b[3;31mb\x1b[0m\x1b[34mc\x1b[0m"
+    /// );
+    /// assert_eq!(
+    ///     strings.render_optimized(OptimizeFor::Bytes),
+    ///     "\x1b[1;3;31ma\x1b[22mb\x1b[0;34mc\x1b[0m"
+    /// );
+    /// ```
+    pub fn render_optimized(&self, optimize: OptimizeFor) -> String {
+        let mut rendered = String::new();
+        self.write_optimized_to_any(optimize, fmt_write!(&mut rendered))
+            .expect("writing to a String cannot fail");
+        rendered
+    }
+
+    /// An owned copy of these strings whose contents are shared through
+    /// `interner`, so that the memory used by documents with many repeated
+    /// segments is proportional to their unique content. Formatting
+    /// arguments are rendered in the process.
+    pub fn interned(&self, interner: &mut ContentInterner) -> AnsiStrings<'static> {
+        self.map_owned(|s| s.interned(interner))
+    }
+
+    /// An owned sequence of the strings `f` makes from these ones, with the
+    /// same style updates (which may have been rebased on an enclosing
+    /// style) and lazily resolved styles.
+    pub(crate) fn map_owned(
+        &self,
+        f: impl FnMut(&AnsiGenericString<'a, str>) -> AnsiString<'static>,
+    ) -> AnsiStrings<'static> {
+        AnsiGenericStrings {
+            strings: Cow::Owned(self.strings.iter().map(f).collect()),
+            style_updates: RefCell::new(Cow::Owned(self.style_updates.borrow().to_vec())),
+            lazy_styles: self.lazy_styles.clone(),
+            spans: self.spans.clone(),
+        }
+    }
+}
+
+/// A set of `AnsiGenericStrings`s collected together, in order to be
+/// written with a minimum of control characters.
+pub struct AnsiGenericStrings<'a, S: 'a + ToOwned + ?Sized> {
+    strings: Cow<'a, [AnsiGenericString<'a, S>]>,
+    style_updates: RefCell<Cow<'a, [StyleUpdate]>>,
+    /// Segments (by index) whose style is only resolved when rendering.
+    lazy_styles: Vec<(usize, LazyStyle)>,
+    /// Segments (by index) with the span of source they were rendered from.
+    spans: Vec<(usize, SourceSpan)>,
+}
+
+/// A style which is produced by a closure when its segment is rendered,
+/// rather than when the segment is created. See
+/// [`AnsiGenericStrings::push_lazy`].
+///
+/// The closure must be `'static` (it may own shared handles to a theme, for
+/// instance), as a borrowing closure would force the borrowed data to
+/// strictly outlive every sequence it is pushed into. It must also be
+/// `Send` and `Sync`, so that owned sequences can be sent to other threads
+/// (see [`Sendable`]).
+#[derive(Clone)]
+pub struct LazyStyle(Arc<dyn Fn(&TerminalProfile) -> Style + Send + Sync>);
+
+impl LazyStyle {
+    /// Wrap a closure choosing a style for the given terminal profile.
+    pub fn new(f: impl Fn(&TerminalProfile) -> Style + Send + Sync + 'static) -> Self {
+        Self(Arc::new(f))
+    }
+
+    /// Evaluate the closure for `profile`.
+    pub fn resolve(&self, profile: &TerminalProfile) -> Style {
+        (self.0)(profile)
+    }
+}
+
+impl Debug for LazyStyle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LazyStyle").finish_non_exhaustive()
+    }
+}
+
+impl<'a, S: 'a + ToOwned + ?Sized> From<AnsiGenericString<'a, S>> for AnsiGenericStrings<'a, S> {
+    fn from(value: AnsiGenericString<'a, S>) -> Self {
+        let mut style_updates = Vec::with_capacity(1);
+        Self::push_style_into(&mut style_updates, value.scheduled_style(), 0);
+        Self {
+            strings: Cow::Owned(vec![value]),
+            style_updates: RefCell::new(Cow::Owned(style_updates)),
+            lazy_styles: Vec::new(),
+            spans: Vec::new(),
+        }
+    }
+}
+
+impl<'a, S: 'a + ToOwned + ?Sized> Clone for AnsiGenericStrings<'a, S> {
+    fn clone(&self) -> Self {
+        Self {
+            style_updates: RefCell::new(self.style_updates.borrow_mut().clone()),
+            strings: self.strings.clone(),
+            lazy_styles: self.lazy_styles.clone(),
+            spans: self.spans.clone(),
+        }
+    }
+}
+
+/// Sequences are equal if their segments are equal, in order. Lazily
+/// resolved styles (see [`push_lazy`](AnsiGenericStrings::push_lazy)) and
+/// source spans are not compared.
+impl<'a, S: 'a + ToOwned + ?Sized + PartialEq> PartialEq for AnsiGenericStrings<'a, S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.strings == other.strings
+    }
+}
+
+impl<'a, S: 'a + ToOwned + ?Sized + Eq> Eq for AnsiGenericStrings<'a, S> {}
+
+impl<'a, S: 'a + ToOwned + ?Sized + Hash> Hash for AnsiGenericStrings<'a, S> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.strings.hash(state);
+    }
+}
+
+/// We manually implement [`Debug`](fmt::Debug) so that it is specifically only
+/// implemented when `S` also implements `Debug`.
+impl<'a, S: 'a + ToOwned + ?Sized> Debug for AnsiGenericStrings<'a, S>
+where
+    S: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AnsiGenericStrings")
+            .field("strings", &self.strings)
+            .field("style_updates", &self.style_updates.borrow_mut())
+            .field("lazy_styles", &self.lazy_styles)
+            .field("spans", &self.spans)
+            .finish()
+    }
+}
+
+impl<'a, S: 'a + ToOwned + ?Sized> AnsiGenericStrings<'a, S> {
+    pub const fn new(strings: &'a [AnsiGenericString<'a, S>]) -> Self {
+        Self {
+            strings: Cow::Borrowed(strings),
+            style_updates: RefCell::new(Cow::Borrowed(&[])),
+            lazy_styles: Vec::new(),
+            spans: Vec::new(),
+        }
+    }
+    /// Create empty sequence with the given capacity.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            strings: Vec::with_capacity(capacity).into(),
+            style_updates: RefCell::new(Vec::with_capacity(capacity).into()),
+            lazy_styles: Vec::new(),
+            spans: Vec::new(),
+        }
+    }
+
+    /// Iterate over the underlying generic strings.
+    pub fn iter(&self) -> impl Iterator<Item = &'_ AnsiGenericString<'a, S>> {
+        self.strings.iter()
+    }
+
+    /// Iterate over the contents of this sequence, each with the style the
+    /// terminal is in when it is written. Control and title segments carry
+    /// the style of the text before them. Lazily resolved styles (see
+    /// [`push_lazy`](Self::push_lazy)) are not resolved here; iterate over a
+    /// [`resolved`](Self::resolved) copy to see them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::{AnsiString, AnsiStrings, Style};
+    /// use nu_ansi_term::Color::Red;
+    ///
+    /// let strings = AnsiStrings([Red.paint("a"), AnsiString::control("\x1b[K")]);
+    /// let styles: Vec<Style> = strings.styled_contents().map(|(style, _)| style).collect();
+    /// assert_eq!(styles, [Red.normal(), Red.normal()]);
+    /// ```
+    pub fn styled_contents(&self) -> impl Iterator<Item = (Style, &'_ Content<'a, S>)> {
+        let styles: Vec<Style> = self
+            .style_updates()
+            .iter()
+            .map(|update| update.in_effect)
+            .collect();
+        styles
+            .into_iter()
+            .zip(self.strings.iter().map(AnsiGenericString::content))
+    }
+
+    /// The generic string at `ix`, if there is one.
+    pub fn get(&self, ix: usize) -> Option<&AnsiGenericString<'a, S>> {
+        self.strings.get(ix)
+    }
+
+    /// The number of generic strings in this sequence.
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    /// Whether this sequence has no generic strings.
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+
+    /// Remove every generic string from this sequence, keeping the memory
+    /// it allocated for reuse.
+    pub fn clear(&mut self) {
+        match &mut self.strings {
+            Cow::Owned(strings) => strings.clear(),
+            strings => *strings = Cow::Owned(Vec::new()),
+        }
+        match self.style_updates.get_mut() {
+            Cow::Owned(style_updates) => style_updates.clear(),
+            style_updates => *style_updates = Cow::Owned(Vec::new()),
+        }
+        self.lazy_styles.clear();
+        self.spans.clear();
+    }
+
+    fn calculate_style_updates(&self) {
+        let mut style_updates = Vec::with_capacity(self.strings.len());
+        for (ix, string) in self.strings.iter().enumerate() {
+            Self::push_style_into(&mut style_updates, string.scheduled_style(), ix);
+        }
+        *self.style_updates.borrow_mut() = Cow::Owned(style_updates);
+    }
+
+    /// Get the style updates required to build this string.
+    ///
+    /// If they are not yet computed, they will be computed, otherwise the cached updates will be returned.
+    fn style_updates(&self) -> Ref<'_, Cow<'_, [StyleUpdate]>> {
+        if self.strings.len() != self.style_updates.borrow().len() {
+            self.calculate_style_updates();
+        }
+        self.style_updates.borrow()
+    }
+
+    /// Get mutable access to the style updates required to build this string.
+    ///
+    /// If they are not yet computed, they will be computed, otherwise the cached updates will be returned.
+    fn style_updates_mut(&self) -> RefMut<'_, Cow<'a, [StyleUpdate]>> {
+        if self.strings.len() != self.style_updates.borrow().len() {
+            self.calculate_style_updates();
+        }
+        self.style_updates.borrow_mut()
+    }
+
+    /// Update specific generic strings.
+    ///
+    /// Depending on where the updates are made, not all style deltas will be
+    /// re-computed.
+    pub fn update_strings(
+        &mut self,
+        updates: impl IntoIterator<Item = (usize, AnsiGenericString<'a, S>)>,
+    ) -> Self {
+        let mut updates: Vec<(usize, AnsiGenericString<'a, S>)> = updates.into_iter().collect();
+
+        if updates.is_empty() {
+            return self.clone();
+        }
+
+        // Now we know updates are not empty.
+        updates.sort_unstable_by_key(|a| a.0);
+        let min_changed_ix = updates.first().unwrap().0;
+
+        let mut new_strings = self.strings.to_vec();
+        let original_len = new_strings.len();
+        let mut lazy_styles = self.lazy_styles.clone();
+        lazy_styles.retain(|(ix, _)| updates.binary_search_by_key(ix, |u| u.0).is_err());
+        let mut spans = self.spans.clone();
+        spans.retain(|(ix, _)| updates.binary_search_by_key(ix, |u| u.0).is_err());
+
+        for (u_ix, u) in updates.into_iter() {
+            if u_ix < original_len {
+                new_strings[u_ix] = u;
+            } else {
+                new_strings.push(u);
+            }
+        }
+
+        if min_changed_ix < original_len {
+            let unchanged_existing = &self.style_updates()[0..min_changed_ix];
+            let mut new_style_updates = Vec::with_capacity(new_strings.len());
+            new_style_updates.extend(unchanged_existing);
+
+            for (ix, style) in new_strings[min_changed_ix..]
+                .iter()
+                .map(AnsiGenericString::scheduled_style)
+                .enumerate()
+            {
+                Self::push_style_into(&mut new_style_updates, style, ix + min_changed_ix)
+            }
+
+            Self {
+                strings: Cow::Owned(new_strings),
+                style_updates: RefCell::new(Cow::Owned(new_style_updates)),
+                lazy_styles,
+                spans,
+            }
+        } else {
+            Self {
+                lazy_styles,
+                spans,
+                ..Self::from_iter(new_strings)
+            }
+        }
+    }
+
+    /// Rebase a nested string onto a parent's style. This is effectively an
     /// "OR" operation.
-    pub fn rebase_on(self, base: Style) -> Self {
+    pub fn rebase_on(mut self, base: Style) -> Self {
+        for (_, lazy) in self.lazy_styles.iter_mut() {
+            let inner = lazy.clone();
+            *lazy = LazyStyle::new(move |profile| {
+                let style = inner.resolve(profile);
+                if style.prefix_before_reset {
+                    style.rebase_on(base)
+                } else {
+                    style
+                }
+            });
+        }
         for update in self.style_updates_mut().to_mut().iter_mut() {
             update.style_delta = match update.style_delta {
                 StyleDelta::ExtraStyles(style) => {
@@ -490,328 +1426,1624 @@ impl<'a, S: 'a + ToOwned + ?Sized> AnsiGenericStrings<'a, S> {
         self
     }
 
-    /// Push given generic string into this [`AnsiGenericStrings`] instance.
-    #[inline]
-    pub fn push(&mut self, s: AnsiGenericString<'a, S>) {
-        self.strings.to_mut().push(s.clone());
-        self.push_style(*s.style_ref(), self.strings.len() - 1);
+    /// Push given generic string into this [`AnsiGenericStrings`] instance.
+    #[inline]
+    pub fn push(&mut self, s: AnsiGenericString<'a, S>) {
+        let style = s.scheduled_style();
+        self.strings.to_mut().push(s);
+        self.push_style(style, self.strings.len() - 1);
+    }
+
+    /// Push a control segment (see [`AnsiGenericString::control`]), such as
+    /// a cursor movement, which is written verbatim without changing the
+    /// current style.
+    pub fn push_control<I>(&mut self, control: I)
+    where
+        I: Into<Content<'a, S>>,
+    {
+        self.push(AnsiGenericString::control(control));
+    }
+
+    /// Push a segment without content, which only switches to `style` for
+    /// the segments after it.
+    pub fn push_style_change(&mut self, style: Style)
+    where
+        str: AsRef<S>,
+    {
+        self.push(AnsiGenericString::new(
+            style,
+            Content::StrLike(Cow::Borrowed("".as_ref())),
+            None,
+        ));
+    }
+
+    /// Push a segment whose style is chosen by `style` each time the sequence
+    /// is rendered, so that one composed value can render differently
+    /// depending on the terminal it is written to.
+    ///
+    /// When the sequence is displayed or written directly, the closure is
+    /// given the profile from [`TerminalProfile::render_default`]; use
+    /// [`write_for`](Self::write_for) or [`resolved`](Self::resolved) to
+    /// render for a specific profile.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::{AnsiStrings, Style, TerminalProfile};
+    /// use nu_ansi_term::Color::{Green, Yellow};
+    ///
+    /// let mut strings = AnsiStrings([Style::new().paint("status: ")]);
+    /// strings.push_lazy("ok", |profile: &TerminalProfile| {
+    ///     if profile.unicode { Green.bold() } else { Yellow.normal() }
+    /// });
+    ///
+    /// assert_eq!(
+    ///     strings.resolved(&TerminalProfile::ascii()).to_string(),
+    ///     "status: \x1b[33mok\x1b[0m"
+    /// );
+    /// ```
+    pub fn push_lazy<I>(
+        &mut self,
+        content: I,
+        style: impl Fn(&TerminalProfile) -> Style + Send + Sync + 'static,
+    ) where
+        I: Into<Content<'a, S>>,
+    {
+        self.lazy_styles
+            .push((self.strings.len(), LazyStyle::new(style)));
+        self.push(Style::new().paint(content));
+    }
+
+    /// Append a string rendered from the `span` of some source, so that
+    /// columns of the output can be mapped back to it (see
+    /// [`AnsiStrings::span_at_column`]).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::{AnsiStrings, SourceSpan};
+    /// use nu_ansi_term::Color::Red;
+    ///
+    /// let source = "let x = 1 +;";
+    /// let mut snippet = AnsiStrings(["let x = ".into()]);
+    /// snippet.push_spanned(Red.bold().paint(&source[8..11]), SourceSpan::new(8, 11));
+    /// assert_eq!(snippet.span(1), Some(SourceSpan::new(8, 11)));
+    /// assert_eq!(snippet.span(0), None);
+    /// ```
+    pub fn push_spanned(&mut self, string: AnsiGenericString<'a, S>, span: SourceSpan) {
+        self.spans.push((self.strings.len(), span));
+        self.push(string);
+    }
+
+    /// The span of source the segment at `ix` was rendered from, if it was
+    /// pushed with one.
+    pub fn span(&self, ix: usize) -> Option<SourceSpan> {
+        self.spans
+            .iter()
+            .rev()
+            .find(|(span_ix, _)| *span_ix == ix)
+            .map(|&(_, span)| span)
+    }
+
+    /// The span of each segment (see [`span`](Self::span)), by index, found
+    /// in one pass rather than one search per segment.
+    pub(crate) fn spans_by_segment(&self) -> Vec<Option<SourceSpan>> {
+        let mut by_segment = vec![None; self.strings.len()];
+        for &(ix, span) in &self.spans {
+            if let Some(slot) = by_segment.get_mut(ix) {
+                *slot = Some(span);
+            }
+        }
+        by_segment
+    }
+
+    /// Whether any segment of this sequence has a lazily resolved style.
+    pub fn has_lazy_styles(&self) -> bool {
+        !self.lazy_styles.is_empty()
+    }
+
+    /// The lazily resolved style of the segment at `ix`, if it has one.
+    pub(crate) fn lazy_style(&self, ix: usize) -> Option<&LazyStyle> {
+        self.lazy_styles
+            .iter()
+            .rev()
+            .find(|(lazy_ix, _)| *lazy_ix == ix)
+            .map(|(_, lazy)| lazy)
+    }
+
+    /// Get a copy of this sequence with every lazily resolved style replaced
+    /// by the style its closure chooses for `profile`.
+    pub fn resolved(&self, profile: &TerminalProfile) -> Self {
+        if self.lazy_styles.is_empty() {
+            return self.clone();
+        }
+        let mut strings = self.strings.to_vec();
+        for (ix, lazy) in &self.lazy_styles {
+            strings[*ix].style = lazy.resolve(profile);
+        }
+        Self {
+            spans: self.spans.clone(),
+            ..Self::from_iter(strings)
+        }
+    }
+
+    /// Get a copy of this sequence with the colors of every style, including
+    /// those of nested sequences and lazily resolved styles, downgraded to
+    /// `level` (see [`Style::adapt_to`]), so that truecolor styles degrade
+    /// gracefully on older terminals. Strings written by formatting
+    /// arguments are not adapted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::{AnsiStrings, ColorLevel};
+    /// use nu_ansi_term::Color::{Fixed, Rgb};
+    ///
+    /// let strings = AnsiStrings([Rgb(255, 135, 0).paint("warm"), Fixed(21).paint("cold")]);
+    /// assert_eq!(
+    ///     strings.adapt_to(ColorLevel::Ansi16).to_string(),
+    ///     "\x1b[33mwarm\x1b[34mcold\x1b[0m"
+    /// );
+    /// assert_eq!(strings.adapt_to(ColorLevel::NoColor).to_string(), "warmcold");
+    /// ```
+    pub fn adapt_to(&self, level: ColorLevel) -> Self {
+        let mut adapted: Self = self
+            .strings
+            .iter()
+            .map(|string| {
+                let mut string = string.clone();
+                string.style = string.style.adapt_to(level);
+                if let Content::GenericStrings(nested) = &string.content {
+                    string.content = Content::GenericStrings(nested.adapt_to(level));
+                }
+                string
+            })
+            .collect();
+        adapted.lazy_styles = self
+            .lazy_styles
+            .iter()
+            .map(|(ix, lazy)| {
+                let lazy = lazy.clone();
+                (
+                    *ix,
+                    LazyStyle::new(move |profile| lazy.resolve(profile).adapt_to(level)),
+                )
+            })
+            .collect();
+        adapted.spans = self.spans.clone();
+        adapted
+    }
+
+    /// List the styling decision made for every segment, one per line, for
+    /// debugging why the written output contains (or lacks) certain escape
+    /// codes. Each line shows the segment index, whether codes are emitted,
+    /// the codes themselves, the style of the segment and its content; the
+    /// last line shows whether a final reset is written.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::AnsiStrings;
+    /// use nu_ansi_term::Color::Red;
+    ///
+    /// let strings = AnsiStrings([Red.paint("a"), Red.paint("b")]);
+    /// assert_eq!(
+    ///     strings.dump_transitions(),
+    ///     "0: emit \"\\u{1b}[31m\" Style { foreground(Red) } StrLike(\"a\")\n\
+    ///      1: keep \"\" Style { foreground(Red) } StrLike(\"b\")\n\
+    ///      end: reset \"\\u{1b}[0m\"\n"
+    /// );
+    /// ```
+    pub fn dump_transitions(&self) -> String
+    where
+        S: fmt::Debug,
+    {
+        use std::fmt::Write;
+
+        if self.has_lazy_styles() {
+            return self
+                .resolved(&TerminalProfile::rendering())
+                .dump_transitions();
+        }
+
+        let mut dump = String::new();
+        let mut last_is_plain = true;
+        for (string, update) in self.strings.iter().zip(self.style_updates().iter()) {
+            let decision = match (string.scheduled_style(), update.style_delta) {
+                (None, _) => "skip",
+                (Some(_), StyleDelta::Empty) => "keep",
+                (Some(_), StyleDelta::ExtraStyles(style)) => {
+                    last_is_plain = style.has_no_styling();
+                    "emit"
+                }
+            };
+            // Writing to a `String` cannot fail.
+            let _ = writeln!(
+                dump,
+                "{}: {} {:?} {:#?} {:?}",
+                update.begins_at,
+                decision,
+                update.to_string(),
+                string.style,
+                string.content
+            );
+        }
+        let _ = if last_is_plain {
+            writeln!(dump, "end: plain")
+        } else {
+            writeln!(dump, "end: reset {:?}", RESET)
+        };
+        dump
+    }
+
+    #[inline]
+    fn push_style_into(
+        existing_style_updates: &mut Vec<StyleUpdate>,
+        next: Option<Style>,
+        begins_at: usize,
+    ) {
+        // Deltas are always computed from the full style the terminal is in,
+        // never from the previous delta: a delta only holds what changed.
+        let in_effect = existing_style_updates
+            .last()
+            .map_or(Style::new(), |update| update.in_effect);
+        let update = match next {
+            Some(next) => StyleUpdate {
+                begins_at,
+                style_delta: in_effect.compute_delta(next),
+                in_effect: next,
+            },
+            // Control and title segments leave the style as it is.
+            None => StyleUpdate {
+                begins_at,
+                style_delta: StyleDelta::Empty,
+                in_effect,
+            },
+        };
+        existing_style_updates.push(update);
+    }
+
+    #[inline]
+    fn push_style(&self, next: Option<Style>, begins_at: usize) {
+        Self::push_style_into(self.style_updates.borrow_mut().to_mut(), next, begins_at)
+    }
+
+    fn write_iter(&self) -> WriteIter<'_, 'a, S> {
+        WriteIter {
+            style_iter: StyleIter {
+                cursor: 0,
+                instructions: self.style_updates(),
+                next_update: None,
+                current: None,
+            },
+            content_iter: ContentIter {
+                strings: self.strings.iter(),
+            },
+        }
+    }
+}
+
+/// Iterator over the minimal styles (see [`StyleDelta`]) of an [`AnsiGenericStrings`] sequence.
+pub struct StyleIter<'b> {
+    cursor: usize,
+    instructions: Ref<'b, Cow<'b, [StyleUpdate]>>,
+    next_update: Option<StyleUpdate>,
+    current: Option<StyleUpdate>,
+}
+
+/// The [`StyleDelta`] to be applied before the contents of the string at
+/// position `begin_at`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub struct StyleUpdate {
+    style_delta: StyleDelta,
+    /// The style in effect once the delta has been applied, which the delta
+    /// of the next segment is computed against.
+    in_effect: Style,
+    begins_at: usize,
+}
+
+impl StyleUpdate {
+    /// The delta applied before the segment.
+    pub const fn style_delta(&self) -> StyleDelta {
+        self.style_delta
+    }
+
+    /// The index of the segment the delta is applied before.
+    pub const fn begins_at(&self) -> usize {
+        self.begins_at
+    }
+}
+
+/// Displays the exact escape codes written for this update.
+impl fmt::Display for StyleUpdate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.style_delta, f)
+    }
+}
+
+impl<'b> StyleIter<'b> {
+    fn get_next_update(&mut self) {
+        self.cursor += 1;
+        self.next_update = self.instructions.get(self.cursor).copied();
+    }
+}
+
+impl<'b> Iterator for StyleIter<'b> {
+    type Item = StyleDelta;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match (self.current, self.next_update) {
+            (None, None) => {
+                self.current = self.instructions.get(self.cursor).copied();
+                self.get_next_update();
+                self.current
+            }
+            (current, Some(next_update)) => {
+                if self.cursor < next_update.begins_at {
+                    current
+                } else {
+                    self.current = self.next_update.take();
+                    self.get_next_update();
+                    self.current
+                }
+            }
+            (Some(current), None) => current.into(),
+        }
+        .map(|u| u.style_delta)
+    }
+}
+
+/// An iterator over the contents in an [`AnsiGenericStrings`] sequence,
+/// borrowed from the sequence rather than cloned.
+pub struct ContentIter<'b, 'a, S: 'a + ToOwned + ?Sized> {
+    strings: std::slice::Iter<'b, AnsiGenericString<'a, S>>,
+}
+
+impl<'b, 'a, S: 'a + ToOwned + ?Sized> Iterator for ContentIter<'b, 'a, S> {
+    type Item = (&'b Content<'a, S>, &'b Option<OSControl<'a, S>>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.strings.next().map(|s| (&s.content, &s.oscontrol))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.strings.size_hint()
+    }
+}
+
+/// An iterator over the data required to write out an [`AnsiGenericStrings`]
+/// sequence to an [`AnyWrite`] implementor.
+pub struct WriteIter<'b, 'a, S: 'a + ToOwned + ?Sized> {
+    style_iter: StyleIter<'b>,
+    content_iter: ContentIter<'b, 'a, S>,
+}
+
+impl<'b, 'a, S: 'a + ToOwned + ?Sized> Iterator for WriteIter<'b, 'a, S> {
+    type Item = (StyleDelta, &'b Content<'a, S>, &'b Option<OSControl<'a, S>>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (content, oscontrol) = self.content_iter.next()?;
+        let update_command = self.style_iter.next().unwrap_or_default();
+        Some((update_command, content, oscontrol))
+    }
+}
+
+impl<'a, S: 'a + ToOwned + ?Sized> FromIterator<AnsiGenericString<'a, S>>
+    for AnsiGenericStrings<'a, S>
+{
+    fn from_iter<Iterable: IntoIterator<Item = AnsiGenericString<'a, S>>>(iter: Iterable) -> Self {
+        let iter = iter.into_iter();
+        let (lower, upper) = iter.size_hint();
+        let count = upper.unwrap_or(lower);
+        let mut ansi_strings = AnsiGenericStrings::with_capacity(count);
+        for s in iter {
+            ansi_strings.push(s);
+        }
+        ansi_strings
+    }
+}
+
+impl<'a, S: 'a + ToOwned + ?Sized> Extend<AnsiGenericString<'a, S>> for AnsiGenericStrings<'a, S> {
+    fn extend<Iterable: IntoIterator<Item = AnsiGenericString<'a, S>>>(&mut self, iter: Iterable) {
+        let iter = iter.into_iter();
+        self.strings.to_mut().reserve(iter.size_hint().0);
+        for s in iter {
+            self.push(s);
+        }
+    }
+}
+
+impl<'a, S: 'a + ToOwned + ?Sized> IntoIterator for AnsiGenericStrings<'a, S> {
+    type Item = AnsiGenericString<'a, S>;
+    type IntoIter = std::vec::IntoIter<AnsiGenericString<'a, S>>;
+
+    /// Iterate over the generic strings of this sequence. Lazily resolved
+    /// styles are dropped; see [`AnsiGenericStrings::resolved`].
+    fn into_iter(self) -> Self::IntoIter {
+        self.strings.into_owned().into_iter()
+    }
+}
+
+impl<'b, 'a, S: 'a + ToOwned + ?Sized> IntoIterator for &'b AnsiGenericStrings<'a, S> {
+    type Item = &'b AnsiGenericString<'a, S>;
+    type IntoIter = std::slice::Iter<'b, AnsiGenericString<'a, S>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.strings.iter()
+    }
+}
+
+/// Concatenates two sequences, keeping the lazily resolved styles of both.
+///
+/// # Examples
+///
+/// ```
+/// use nu_ansi_term::AnsiStrings;
+/// use nu_ansi_term::Color::{Blue, Red};
+///
+/// let mut prompt = AnsiStrings([Red.paint("~")]);
+/// prompt += AnsiStrings([Blue.paint(" $ ")]);
+/// assert_eq!(prompt.len(), 2);
+/// assert_eq!(prompt.to_string(), "\x1b[31m~\x1b[34m $ \x1b[0m");
+/// ```
+impl<'a, S: 'a + ToOwned + ?Sized> std::ops::AddAssign for AnsiGenericStrings<'a, S> {
+    fn add_assign(&mut self, other: Self) {
+        let offset = self.len();
+        self.lazy_styles.extend(
+            other
+                .lazy_styles
+                .iter()
+                .map(|(ix, lazy)| (ix + offset, lazy.clone())),
+        );
+        self.spans
+            .extend(other.spans.iter().map(|&(ix, span)| (ix + offset, span)));
+        self.extend(other);
+    }
+}
+
+impl<'a, S: 'a + ToOwned + ?Sized> std::ops::Add for AnsiGenericStrings<'a, S> {
+    type Output = Self;
+
+    fn add(mut self, other: Self) -> Self {
+        self += other;
+        self
+    }
+}
+
+/// A set of `AnsiString`s collected together, in order to be written with a
+/// minimum of control characters.
+pub type AnsiStrings<'a> = AnsiGenericStrings<'a, str>;
+
+/// A function to construct an `AnsiStrings` instance.
+#[allow(non_snake_case)]
+pub fn AnsiStrings<'a>(arg: impl IntoIterator<Item = AnsiString<'a>>) -> AnsiStrings<'a> {
+    AnsiGenericStrings::from_iter(arg)
+}
+
+/// A set of `AnsiByteString`s collected together, in order to be
+/// written with a minimum of control characters.
+pub type AnsiByteStrings<'a> = AnsiGenericStrings<'a, [u8]>;
+
+/// A function to construct an `AnsiByteStrings` instance.
+#[allow(non_snake_case)]
+pub fn AnsiByteStrings<'a>(
+    arg: impl IntoIterator<Item = AnsiByteString<'a>>,
+) -> AnsiByteStrings<'a> {
+    AnsiGenericStrings::from_iter(arg)
+}
+
+// ---- paint functions ----
+impl Style {
+    /// Paints the given content with this style, returning an ANSI string.
+    ///
+    /// ```
+    /// use nu_ansi_term::Style;
+    /// use nu_ansi_term::Color;
+    ///
+    /// println!("{}", Style::new().fg(Color::Blue).paint("nice!"));
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn paint<'a, I, S: 'a + ToOwned + ?Sized>(self, input: I) -> AnsiGenericString<'a, S>
+    where
+        I: Into<Content<'a, S>>,
+    {
+        AnsiGenericString {
+            content: match input.into() {
+                x @ Content::GenericStrings(_) => x.with_context(self),
+                x => x,
+            },
+            style: self,
+            oscontrol: None,
+        }
+    }
+
+    /// Paints a sequence of styled strings nested in this style: each of
+    /// them is rebased on this style, so that unstyled parts are written in
+    /// it, and the formats and colors of styled parts are added to it. The
+    /// result is a flat sequence, written with the minimal codes between
+    /// its parts.
+    ///
+    /// Titles and control sequences are kept as they are.
+    ///
+    /// ```
+    /// use nu_ansi_term::{AnsiString, Style};
+    /// use nu_ansi_term::Color::{Red, Yellow};
+    ///
+    /// let error = Red.bold().paint_nested([
+    ///     AnsiString::from("cannot open "),
+    ///     Yellow.paint("config.toml"),
+    ///     AnsiString::from(": not found"),
+    /// ]);
+    /// assert_eq!(
+    ///     error.to_string(),
+    ///     "\x1b[1;31mcannot open \x1b[33mconfig.toml\x1b[31m: not found\x1b[0m"
+    /// );
+    /// ```
+    pub fn paint_nested<'a, S: 'a + ToOwned + ?Sized>(
+        self,
+        parts: impl IntoIterator<Item = AnsiGenericString<'a, S>>,
+    ) -> AnsiGenericStrings<'a, S> {
+        parts
+            .into_iter()
+            .map(|mut part| {
+                if let Some(style) = part.scheduled_style() {
+                    part.style = nested::flatten(style, self);
+                }
+                part
+            })
+            .collect()
+    }
+
+    /// Paints the given content with this style if `condition` holds, and
+    /// without any style otherwise. See [`Style::when`].
+    ///
+    /// ```
+    /// use nu_ansi_term::Style;
+    ///
+    /// let verbose = true;
+    /// assert_eq!(
+    ///     Style::new().bold().paint_if(verbose, "note").to_string(),
+    ///     "\x1b[1mnote\x1b[0m"
+    /// );
+    /// ```
+    #[must_use]
+    pub fn paint_if<'a, I, S: 'a + ToOwned + ?Sized>(
+        self,
+        condition: bool,
+        input: I,
+    ) -> AnsiGenericString<'a, S>
+    where
+        I: Into<Content<'a, S>>,
+    {
+        self.when(condition).paint(input)
+    }
+
+    /// Paints each of the given pieces with this style, collected into one
+    /// sequence: the style is written once before the first piece and reset
+    /// once after the last, rather than around every piece.
+    ///
+    /// ```
+    /// use nu_ansi_term::Color::Blue;
+    ///
+    /// let names = ["src", "/", "main.rs"];
+    /// assert_eq!(
+    ///     Blue.bold().paint_all(names).to_string(),
+    ///     "\x1b[1;34msrc/main.rs\x1b[0m"
+    /// );
+    /// ```
+    #[must_use]
+    pub fn paint_all<'a, I, S: 'a + ToOwned + ?Sized>(
+        self,
+        inputs: impl IntoIterator<Item = I>,
+    ) -> AnsiGenericStrings<'a, S>
+    where
+        I: Into<Content<'a, S>>,
+    {
+        inputs.into_iter().map(|input| self.paint(input)).collect()
+    }
+}
+
+impl Color {
+    /// Paints the given text with this color, returning an ANSI string.
+    /// This is a short-cut so you don’t have to use `Blue.normal()` just
+    /// to get blue text.
+    ///
+    /// ```
+    /// use nu_ansi_term::Color::Blue;
+    /// println!("{}", Blue.paint("da ba dee"));
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn paint<'a, I, S: 'a + ToOwned + ?Sized>(self, input: I) -> AnsiGenericString<'a, S>
+    where
+        I: Into<Content<'a, S>>,
+    {
+        self.normal().paint(input)
+    }
+
+    /// Paints the given content with this color if `condition` holds, and
+    /// without any style otherwise.
+    ///
+    /// ```
+    /// use nu_ansi_term::Color::Red;
+    ///
+    /// let use_color = false;
+    /// assert_eq!(Red.paint_if(use_color, "error").to_string(), "error");
+    /// ```
+    #[must_use]
+    pub fn paint_if<'a, I, S: 'a + ToOwned + ?Sized>(
+        self,
+        condition: bool,
+        input: I,
+    ) -> AnsiGenericString<'a, S>
+    where
+        I: Into<Content<'a, S>>,
+    {
+        self.normal().paint_if(condition, input)
+    }
+
+    /// Paints each of the given pieces with this color, collected into one
+    /// sequence. See [`Style::paint_all`].
+    ///
+    /// ```
+    /// use nu_ansi_term::Color::Green;
+    ///
+    /// let path = ["~", "/", "src"];
+    /// assert_eq!(Green.paint_all(path).to_string(), "\x1b[32m~/src\x1b[0m");
+    /// ```
+    #[must_use]
+    pub fn paint_all<'a, I, S: 'a + ToOwned + ?Sized>(
+        self,
+        inputs: impl IntoIterator<Item = I>,
+    ) -> AnsiGenericStrings<'a, S>
+    where
+        I: Into<Content<'a, S>>,
+    {
+        self.normal().paint_all(inputs)
+    }
+}
+
+/// Format the arguments immediately into owned content, and paint it with the
+/// given style (or color), producing an `AnsiString<'static>`.
+///
+/// This keeps the full formatting syntax (width, alignment, precision, ...)
+/// available, without tying the result to the lifetime of the formatted
+/// values as [`format_args!`] would.
+///
+/// # Examples
+///
+/// ```
+/// use nu_ansi_term::{paint_fmt, AnsiString};
+/// use nu_ansi_term::Color::Green;
+///
+/// let cell: AnsiString<'static> = paint_fmt!(Green.bold(), "{:>8.2}", 3.14159);
+/// assert_eq!(cell.to_string(), "\x1b[1;32m    3.14\x1b[0m");
+/// ```
+#[macro_export]
+macro_rules! paint_fmt {
+    ($style:expr, $($args:tt)*) => {
+        $style.paint::<_, str>($crate::owned_content!($($args)*))
+    };
+}
+
+/// Paint the arguments with the given style (or color) without formatting
+/// them first, producing an `AnsiString` which formats them when it is
+/// written, as [`format_args!`] does.
+///
+/// No `String` is allocated for the content, but like the arguments of
+/// [`format_args!`], the result borrows temporaries which only live until the
+/// end of the enclosing statement, so it is meant to be written straight
+/// away. Use [`paint_fmt!`] for a string to keep.
+///
+/// # Examples
+///
+/// ```
+/// use nu_ansi_term::paint;
+/// use nu_ansi_term::Color::Red;
+///
+/// let x = 42;
+/// println!("{}", paint!(Red.bold(), "x = {}", x));
+/// assert_eq!(paint!(Red, "x = {}", x).to_string(), "\x1b[31mx = 42\x1b[0m");
+/// ```
+#[macro_export]
+macro_rules! paint {
+    ($style:expr, $($args:tt)*) => {
+        $style.paint::<_, str>(::std::format_args!($($args)*))
+    };
+}
+
+/// Paint several parts, each with its own style (or color) and format
+/// arguments, into one `AnsiStrings` sequence written with the minimal codes
+/// between the parts. As with [`paint!`], nothing is formatted until the
+/// sequence is written, and the sequence is meant to be written straight
+/// away.
+///
+/// Each part is written as `style => (format string, arguments...)`.
+///
+/// # Examples
+///
+/// ```
+/// use nu_ansi_term::{ansi_format, Style};
+/// use nu_ansi_term::Color::Red;
+///
+/// let msg = "no such file";
+/// assert_eq!(
+///     ansi_format!(Red.bold() => ("error:"), Style::new() => (" {}", msg)).to_string(),
+///     "\x1b[1;31merror:\x1b[0m no such file"
+/// );
+/// ```
+#[macro_export]
+macro_rules! ansi_format {
+    ($($style:expr => ($($args:tt)*)),+ $(,)?) => {
+        $crate::AnsiStrings([$($crate::paint!($style, $($args)*)),+])
+    };
+}
+
+macro_rules! painted_format_methods {
+    ($($flag:ident),*) => {
+        paste! {
+            $(
+                #[doc = r"Turns on the `" $flag:lower r"` property of this string's style, then returns it."]
+                #[doc = r""]
+                #[doc = r"# Examples"]
+                #[doc = r""]
+                #[doc = r"```"]
+                #[doc = r"use nu_ansi_term::Color::Red;"]
+                #[doc = r""]
+                #[doc = r#"let string = Red.paint("hi")."# $flag:lower r"();"]
+                #[doc = r#"println!("{}", string);"# ]
+                #[doc = r"```"]
+                pub fn [< $flag:lower >](mut self) -> Self {
+                    *self.style_ref_mut() = self.style.[< $flag:lower >]();
+                    self
+                }
+
+                #[doc = r"Turns off the `" $flag:lower r"` property of this string's style, then returns it."]
+                pub fn [< without_ $flag:lower >](mut self) -> Self {
+                    *self.style_ref_mut() = self.style.[< without_ $flag:lower >]();
+                    self
+                }
+            )*
+        }
+    };
+}
+
+/// Restyling of strings which have already been painted.
+impl<'a, S: 'a + ToOwned + ?Sized> AnsiGenericString<'a, S> {
+    painted_format_methods!(
+        BOLD,
+        DIMMED,
+        ITALIC,
+        UNDERLINE,
+        BLINK,
+        REVERSE,
+        HIDDEN,
+        STRIKETHROUGH,
+        DOUBLE_UNDERLINE,
+        UNDERCURL,
+        OVERLINE
+    );
+
+    /// Sets the foreground color of this string's style, then returns it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::AnsiString;
+    /// use nu_ansi_term::Color::{Blue, Red};
+    ///
+    /// let string: AnsiString = Red.paint("hi").fg(Blue);
+    /// assert_eq!(string.style_ref(), &Blue.normal());
+    /// ```
+    pub fn fg(mut self, color: Color) -> Self {
+        *self.style_ref_mut() = self.style.fg(color);
+        self
+    }
+
+    /// Sets the background color of this string's style, then returns it.
+    pub fn bg(mut self, color: Color) -> Self {
+        *self.style_ref_mut() = self.style.bg(color);
+        self
+    }
+
+    /// Sets the background color of this string's style, then returns it.
+    /// This is the same as [`bg`](AnsiGenericString::bg), and mirrors
+    /// [`Style::on`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::AnsiString;
+    /// use nu_ansi_term::Color::{Blue, Red};
+    ///
+    /// let string: AnsiString = Red.paint("x").bold().on(Blue).underline();
+    /// assert_eq!(string.style_ref(), &Red.bold().on(Blue).underline());
+    /// ```
+    pub fn on(self, color: Color) -> Self {
+        self.bg(color)
+    }
+
+    /// Makes this string reset all styles before applying its own, then
+    /// returns it.
+    pub fn reset_before_style(mut self) -> Self {
+        *self.style_ref_mut() = self.style.reset_before_style();
+        self
+    }
+}
+
+// ---- writers for individual ANSI strings ----
+
+/// The width, fill, alignment and precision of the formatter apply to the
+/// visible text, and padding is written outside of the style.
+///
+/// # Examples
+///
+/// ```
+/// use nu_ansi_term::AnsiString;
+/// use nu_ansi_term::Color::Red;
+///
+/// let hi: AnsiString = Red.paint("hi");
+/// assert_eq!(format!("[{:>4}]", hi), "[  \x1b[31mhi\x1b[0m]");
+/// assert_eq!(format!("[{:-^6.1}]", hi), "[--\x1b[31mh\x1b[0m---]");
+/// ```
+impl<'a> fmt::Display for AnsiString<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if f.width().is_none() && f.precision().is_none() {
+            return self.write_to_any(fmt_write!(f));
+        }
+        let mut rendered = String::new();
+        self.write_to_any(fmt_write!(&mut rendered))?;
+        pad_rendered(f, &rendered)
+    }
+}
+
+impl<'a> AnsiString<'a> {
+    /// Split the rendered string into its style prefix, its body (the content,
+    /// wrapped in any hyperlink or title sequences) and its style suffix, so
+    /// that they can be interleaved with other markers, such as the `\[` and
+    /// `\]` around non-printing characters in a bash prompt.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::AnsiString;
+    /// use nu_ansi_term::Color::Green;
+    ///
+    /// let user: AnsiString = Green.bold().paint("user");
+    /// let (prefix, body, suffix) = user.parts();
+    /// let ps1 = format!("\\[{}\\]{}\\[{}\\]", prefix, body, suffix);
+    /// assert_eq!(ps1, "\\[\x1b[1;32m\\]user\\[\x1b[0m\\]");
+    /// ```
+    pub fn parts(&self) -> (String, String, String) {
+        let mut body = String::new();
+        Self::write_inner(&self.content, &self.oscontrol, fmt_write!(&mut body))
+            .expect("a Display implementation returned an error unexpectedly");
+        (
+            self.style.prefix().to_string(),
+            body,
+            self.style.suffix().to_string(),
+        )
+    }
+
+    /// The text of this string without any escape codes, e.g. for writing to
+    /// a log file or comparing in tests. Titles and control segments are not
+    /// displayed as text, so they are empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::AnsiString;
+    /// use nu_ansi_term::Color::Red;
+    ///
+    /// let alert: AnsiString = Red.bold().paint("alert");
+    /// assert_eq!(alert.unstyled(), "alert");
+    /// assert_eq!(AnsiString::title("window").unstyled(), "");
+    /// ```
+    pub fn unstyled(&self) -> String {
+        if self.scheduled_style().is_none() {
+            return String::new();
+        }
+        match &self.content {
+            Content::StrLike(s) => strip_ansi_codes(s).into_owned(),
+            Content::Shared(s) => strip_ansi_codes(s).into_owned(),
+            content => strip_ansi_codes(&content.to_string()).into_owned(),
+        }
+    }
+
+    /// Whether this string shows the same text as `other`, once both are
+    /// normalized to NFC, so that composed and decomposed forms of the same
+    /// characters (`é` and `e` followed by a combining acute accent) compare
+    /// equal. Styles and escape codes are ignored, as for
+    /// [`unstyled`](Self::unstyled).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::AnsiString;
+    /// use nu_ansi_term::Color::{Blue, Red};
+    ///
+    /// let composed: AnsiString = Red.paint("caf\u{E9}");
+    /// assert!(composed.eq_content_normalized(&Blue.paint("cafe\u{301}")));
+    /// assert!(!composed.eq_content_normalized(&Blue.paint("cafe")));
+    /// ```
+    #[cfg(feature = "unicode-normalization")]
+    pub fn eq_content_normalized(&self, other: &AnsiString<'_>) -> bool {
+        use unicode_normalization::UnicodeNormalization;
+        self.unstyled().nfc().eq(other.unstyled().nfc())
+    }
+}
+
+impl<'a> AnsiByteString<'a> {
+    /// Write an `AnsiByteString` to an `io::Write`.  This writes the escape
+    /// sequences for the associated `Style` around the bytes.
+    pub fn write_to<W: io::Write>(&self, w: &mut W) -> io::Result<()> {
+        self.write_to_any(io_write!(w))
     }
 
-    #[inline]
-    fn push_style_into(
-        existing_style_updates: &mut Vec<StyleUpdate>,
-        next: Style,
-        begins_at: usize,
-    ) {
-        let command = existing_style_updates
-            .last()
-            .copied()
-            .unwrap_or_default()
-            .style_delta
-            .delta_next(next);
-
-        existing_style_updates.push(StyleUpdate {
-            begins_at,
-            style_delta: command,
-        });
+    /// An unstyled byte string owning the bytes of `s`. Paint owned text
+    /// as bytes in a style with `style.paint(s.into_bytes())`.
+    pub fn from_string(s: String) -> Self {
+        Self::from_vec(s.into_bytes())
     }
 
-    #[inline]
-    fn push_style(&self, next: Style, begins_at: usize) {
-        Self::push_style_into(self.style_updates.borrow_mut().to_mut(), next, begins_at)
+    /// An unstyled byte string owning the bytes of an OS string, such as a
+    /// file name which is not valid UTF-8.
+    #[cfg(unix)]
+    pub fn from_os_string(s: std::ffi::OsString) -> Self {
+        use std::os::unix::ffi::OsStringExt;
+        Self::from_vec(s.into_vec())
     }
 
-    fn write_iter(&self) -> WriteIter<'_, 'a, S> {
-        WriteIter {
-            style_iter: StyleIter {
-                cursor: 0,
-                instructions: self.style_updates.borrow(),
-                next_update: None,
-                current: None,
-            },
-            content_iter: ContentIter {
-                cursor: 0,
-                strings: &self.strings,
-            },
+    fn from_vec(bytes: Vec<u8>) -> Self {
+        Self {
+            style: Style::new(),
+            content: bytes.into(),
+            oscontrol: None,
         }
     }
-}
 
-/// Iterator over the minimal styles (see [`StyleDelta`]) of an [`AnsiGenericStrings`] sequence.
-pub struct StyleIter<'b> {
-    cursor: usize,
-    instructions: Ref<'b, Cow<'b, [StyleUpdate]>>,
-    next_update: Option<StyleUpdate>,
-    current: Option<StyleUpdate>,
-}
+    /// Render this string, with its escape codes, into a `Vec<u8>`: the
+    /// equivalent of `to_string` for the bytes of an [`AnsiString`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::AnsiByteString;
+    /// use nu_ansi_term::Color::Blue;
+    ///
+    /// let name: AnsiByteString = Blue.bold().paint(b"caf\xe9".to_vec());
+    /// assert_eq!(name.to_vec(), b"\x1b[1;34mcaf\xe9\x1b[0m");
+    /// assert_eq!(AnsiByteString::title(b"ls").to_vec(), b"\x1b]2;ls\x1b\\");
+    /// ```
+    pub fn to_vec(&self) -> Vec<u8> {
+        let mut rendered = Vec::new();
+        self.write_to(&mut rendered)
+            .expect("writing to a Vec cannot fail");
+        rendered
+    }
 
-/// The [`StyleDelta`] to be applied before the contents of the string at
-/// position `begin_at`.
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
-pub struct StyleUpdate {
-    style_delta: StyleDelta,
-    begins_at: usize,
-}
+    /// Split the rendered bytes into the style prefix, the body and the style
+    /// suffix. See [`AnsiString::parts`].
+    pub fn parts(&self) -> (Vec<u8>, Vec<u8>, Vec<u8>) {
+        let mut body = Vec::new();
+        Self::write_inner(&self.content, &self.oscontrol, io_write!(&mut body))
+            .expect("writing to a Vec cannot fail");
+        (
+            self.style.prefix().to_string().into_bytes(),
+            body,
+            self.style.suffix().to_string().into_bytes(),
+        )
+    }
 
-impl<'b> StyleIter<'b> {
-    fn get_next_update(&mut self) {
-        self.cursor += 1;
-        self.next_update = self.instructions.get(self.cursor).copied();
+    /// The bytes of this string without any escape codes. See
+    /// [`AnsiString::unstyled`].
+    pub fn unstyled(&self) -> Vec<u8> {
+        if self.scheduled_style().is_none() {
+            return Vec::new();
+        }
+        let mut content = Vec::new();
+        self.content
+            .write_to(io_write!(&mut content))
+            .expect("writing to a Vec cannot fail");
+        strip_bytes(&content)
     }
 }
 
-impl<'b> Iterator for StyleIter<'b> {
-    type Item = StyleDelta;
+impl<'a, S: 'a + ToOwned + ?Sized> AnsiGenericString<'a, S> {
+    /// Rebase this style on a `base` style (effective an "OR" operation).
+    /// Useful for ANSI strings nested inside other ANSI strings.
+    pub fn rebase_on(mut self, base: Style) -> Self {
+        self.style = self.style.rebase_on(base);
+        self
+    }
+    /// Write only the part of the generic string which lies within its styling
+    /// prefix and suffix: its `content` and `oscontrol`.
+    ///
+    /// An OSC sequence of a kind the profile being written for (see
+    /// [`AnsiGenericStrings::write_for`]), or else the [render default],
+    /// does not support is left out, and only the text of a hyperlink is
+    /// written.
+    ///
+    /// [render default]: TerminalProfile::render_default
+    pub fn write_inner<W: AnyWrite + ?Sized>(
+        content: &Content<'a, S>,
+        oscontrol: &Option<OSControl<'a, S>>,
+        w: &mut W,
+    ) -> WriteResult<W::Error>
+    where
+        S: StrLike<'a, W>,
+        str: StrLike<'a, W>,
+    {
+        match osc_kind(oscontrol) {
+            Some(kind) if !TerminalProfile::rendering().supports(kind) => match oscontrol {
+                Some(OSControl::Link { .. }) => content.write_to(w),
+                _ => Ok(()),
+            },
+            _ => Self::write_osc(content, oscontrol, w),
+        }
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        match (self.current, self.next_update) {
-            (None, None) => {
-                self.current = self.instructions.get(self.cursor).copied();
-                self.get_next_update();
-                self.current
+    /// Write `content` wrapped in the sequence of `oscontrol`, whether the
+    /// terminal supports it or not.
+    fn write_osc<W: AnyWrite + ?Sized>(
+        content: &Content<'a, S>,
+        oscontrol: &Option<OSControl<'a, S>>,
+        w: &mut W,
+    ) -> WriteResult<W::Error>
+    where
+        S: StrLike<'a, W>,
+        str: StrLike<'a, W>,
+    {
+        // Sequences are reported once they have been written, with their
+        // length measured only if anyone is listening.
+        let emit = |kind: SequenceKind, intro: &str, payload: &dyn Fn() -> usize| {
+            if instrument::is_hooked() {
+                instrument::emit(kind, intro.len() + payload() + "\x1B\x5C".len());
             }
-            (current, Some(next_update)) => {
-                if self.cursor < next_update.begins_at {
-                    current
-                } else {
-                    self.current = self.next_update.take();
-                    self.get_next_update();
-                    self.current
-                }
+        };
+        match oscontrol {
+            Some(OSControl::Link { url: u, params }) => {
+                write_str!(w, "\x1B]8;")?;
+                for_link_param_pieces(params, |piece| write_str!(w, piece))?;
+                write_str!(w, ";")?;
+                u.write_osc_payload_to(w, percent_encode_url)?;
+                write_str!(w, "\x1B\x5C")?;
+                emit(SequenceKind::Hyperlink, "\x1B]8;", &|| {
+                    let mut params_len = 0;
+                    let _ = for_link_param_pieces::<()>(params, |piece| {
+                        params_len += piece.len();
+                        Ok(())
+                    });
+                    params_len + ";".len() + u.osc_payload_len(percent_encode_url)
+                });
+                content.write_to(w)?;
+                write_str!(w, "\x1B]8;;\x1B\x5C")?;
+                emit(SequenceKind::Hyperlink, "\x1B]8;;", &|| 0);
+                Ok(())
             }
-            (Some(current), None) => current.into(),
+            Some(OSControl::Title) => {
+                write_str!(w, "\x1B]2;")?;
+                content.write_osc_payload_to(w, strip_control_chars)?;
+                write_str!(w, "\x1B\x5C")?;
+                emit(SequenceKind::Title, "\x1B]2;", &|| {
+                    content.osc_payload_len(strip_control_chars)
+                });
+                Ok(())
+            }
+            Some(OSControl::Clipboard) => {
+                write_str!(w, "\x1B]52;c;")?;
+                content.write_osc_payload_to(w, strip_control_chars)?;
+                write_str!(w, "\x1B\x5C")?;
+                emit(SequenceKind::Clipboard, "\x1B]52;c;", &|| {
+                    content.osc_payload_len(strip_control_chars)
+                });
+                Ok(())
+            }
+            Some(OSControl::Notify { title: Some(title) }) => {
+                write_str!(w, "\x1B]777;notify;")?;
+                title.write_osc_payload_to(w, strip_notification_title)?;
+                write_str!(w, ";")?;
+                content.write_osc_payload_to(w, strip_control_chars)?;
+                write_str!(w, "\x1B\x5C")?;
+                emit(SequenceKind::Notification, "\x1B]777;notify;", &|| {
+                    title.osc_payload_len(strip_notification_title)
+                        + ";".len()
+                        + content.osc_payload_len(strip_control_chars)
+                });
+                Ok(())
+            }
+            Some(OSControl::Notify { title: None }) => {
+                write_str!(w, "\x1B]9;")?;
+                content.write_osc_payload_to(w, strip_control_chars)?;
+                write_str!(w, "\x1B\x5C")?;
+                emit(SequenceKind::Notification, "\x1B]9;", &|| {
+                    content.osc_payload_len(strip_control_chars)
+                });
+                Ok(())
+            }
+            Some(OSControl::Cwd) => {
+                write_str!(w, "\x1B]7;")?;
+                content.write_osc_payload_to(w, percent_encode_url)?;
+                write_str!(w, "\x1B\x5C")?;
+                emit(SequenceKind::Cwd, "\x1B]7;", &|| {
+                    content.osc_payload_len(percent_encode_url)
+                });
+                Ok(())
+            }
+            Some(OSControl::Control) | None => content.write_to(w),
         }
-        .map(|u| u.style_delta)
     }
-}
 
-/// An iterator over the contents in an [`AnsiGenericStrings`] sequence.
-pub struct ContentIter<'b, 'a, S: 'a + ToOwned + ?Sized> {
-    cursor: usize,
-    strings: &'b [AnsiGenericString<'a, S>],
-}
+    /// Write this generic string to the given `AnyWrite` implementor.
+    pub fn write_to_any<W: AnyWrite + ?Sized>(&self, w: &mut W) -> WriteResult<W::Error>
+    where
+        S: StrLike<'a, W>,
+        str: StrLike<'a, W>,
+    {
+        if !is_enabled() {
+            return self.write_content_in(self.style, w);
+        }
+        if let Some(enclosing) = nested::enclosing_style() {
+            let style = match self.scheduled_style() {
+                Some(style) => nested::flatten(style, enclosing),
+                None => return Self::write_inner(&self.content, &self.oscontrol, w),
+            };
+            nested::switch(enclosing, style, w)?;
+            self.write_content_in(style, w)?;
+            return nested::switch(style, enclosing, w);
+        }
 
-impl<'b, 'a, S: 'a + ToOwned + ?Sized> Iterator for ContentIter<'b, 'a, S> {
-    type Item = (Content<'a, S>, Option<OSControl<'a, S>>);
+        self.style.write_prefix(w)?;
+        self.write_content_in(self.style, w)?;
+        self.style.write_suffix(w)
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        let r = self
-            .strings
-            .get(self.cursor)
-            .map(|s| (s.content.clone(), s.oscontrol.clone()));
-        if r.is_some() {
-            self.cursor += 1;
+    /// Write the content of this string, which is displayed in `style`. If
+    /// it is made of `fmt::Arguments`, strings written by them are flattened
+    /// into `style` (see [`nested`](crate::nested)).
+    fn write_content_in<W: AnyWrite + ?Sized>(
+        &self,
+        style: Style,
+        w: &mut W,
+    ) -> WriteResult<W::Error>
+    where
+        S: StrLike<'a, W>,
+        str: StrLike<'a, W>,
+    {
+        match self.content {
+            Content::FmtArgs(_) => nested::within(style, || {
+                Self::write_inner(&self.content, &self.oscontrol, w)
+            }),
+            _ => Self::write_inner(&self.content, &self.oscontrol, w),
         }
-        r
     }
 }
 
-/// An iterator over the data required to write out an [`AnsiGenericStrings`]
-/// sequence to an [`AnyWrite`] implementor.
-pub struct WriteIter<'b, 'a, S: 'a + ToOwned + ?Sized> {
-    style_iter: StyleIter<'b>,
-    content_iter: ContentIter<'b, 'a, S>,
+/// The kind of OSC sequence written for `oscontrol`, if any: control
+/// segments are written verbatim, and are not OSC sequences of their own.
+fn osc_kind<S: ToOwned + ?Sized>(oscontrol: &Option<OSControl<'_, S>>) -> Option<SequenceKind> {
+    match oscontrol.as_ref()? {
+        OSControl::Link { .. } => Some(SequenceKind::Hyperlink),
+        OSControl::Title => Some(SequenceKind::Title),
+        OSControl::Clipboard => Some(SequenceKind::Clipboard),
+        OSControl::Notify { .. } => Some(SequenceKind::Notification),
+        OSControl::Cwd => Some(SequenceKind::Cwd),
+        OSControl::Control => None,
+    }
 }
 
-impl<'b, 'a, S: 'a + ToOwned + ?Sized> Iterator for WriteIter<'b, 'a, S> {
-    type Item = (StyleDelta, Content<'a, S>, Option<OSControl<'a, S>>);
+/// Percent-encode every byte of `url` which may not appear in the URI of an
+/// OSC 8 hyperlink (anything outside of printable ASCII).
+pub(crate) fn percent_encode_url(url: &str) -> Cow<'_, str> {
+    use std::fmt::Write;
 
-    fn next(&mut self) -> Option<Self::Item> {
-        let (content, oscontrol) = self.content_iter.next()?;
-        let update_command = self.style_iter.next().unwrap_or_default();
-        Some((update_command, content, oscontrol))
+    if url.bytes().all(|b| b.is_ascii_graphic()) {
+        return Cow::Borrowed(url);
+    }
+    let mut encoded = String::with_capacity(url.len());
+    for b in url.bytes() {
+        if b.is_ascii_graphic() {
+            encoded.push(b as char);
+        } else {
+            // Writing to a `String` cannot fail.
+            let _ = write!(encoded, "%{:02X}", b);
+        }
     }
+    Cow::Owned(encoded)
 }
 
-impl<'a, S: 'a + ToOwned + ?Sized> FromIterator<AnsiGenericString<'a, S>>
-    for AnsiGenericStrings<'a, S>
-{
-    fn from_iter<Iterable: IntoIterator<Item = AnsiGenericString<'a, S>>>(iter: Iterable) -> Self {
-        let iter = iter.into_iter();
-        let (lower, upper) = iter.size_hint();
-        let count = upper.unwrap_or(lower);
-        let mut ansi_strings = AnsiGenericStrings::with_capacity(count);
-        for s in iter {
-            ansi_strings.push(s);
+/// Remove the control characters from `text`, so that it cannot terminate the
+/// operating system command it is written into.
+pub(crate) fn strip_control_chars(text: &str) -> Cow<'_, str> {
+    if text.chars().any(char::is_control) {
+        Cow::Owned(text.chars().filter(|c| !c.is_control()).collect())
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+
+/// Call `piece` with each piece of the parameters of a link as they are
+/// written in its OSC 8 sequence: `key=value` pairs separated by colons,
+/// without the control characters, colons and semicolons (and `=` in keys)
+/// which would end a field early.
+fn for_link_param_pieces<E>(
+    params: &[(String, String)],
+    mut piece: impl FnMut(&str) -> Result<(), E>,
+) -> Result<(), E> {
+    let clean = |text: &str, is_key: bool, piece: &mut dyn FnMut(&str) -> Result<(), E>| {
+        text.split(|c: char| c.is_control() || c == ':' || c == ';' || (is_key && c == '='))
+            .filter(|part| !part.is_empty())
+            .try_for_each(piece)
+    };
+    for (ix, (key, value)) in params.iter().enumerate() {
+        if ix > 0 {
+            piece(":")?;
         }
-        ansi_strings
+        clean(key, true, &mut piece)?;
+        piece("=")?;
+        clean(value, false, &mut piece)?;
     }
+    Ok(())
 }
 
-/// A set of `AnsiString`s collected together, in order to be written with a
-/// minimum of control characters.
-pub type AnsiStrings<'a> = AnsiGenericStrings<'a, str>;
+/// The parameters of a link as they are written in its OSC 8 sequence:
+/// `key=value` pairs separated by colons.
+pub(crate) fn format_link_params(params: &[(String, String)]) -> String {
+    let mut formatted = String::new();
+    let _ = for_link_param_pieces::<()>(params, |piece| {
+        formatted.push_str(piece);
+        Ok(())
+    });
+    formatted
+}
 
-/// A function to construct an `AnsiStrings` instance.
-#[allow(non_snake_case)]
-pub fn AnsiStrings<'a>(arg: impl IntoIterator<Item = AnsiString<'a>>) -> AnsiStrings<'a> {
-    AnsiGenericStrings::from_iter(arg)
+/// Remove the control characters and semicolons from the title of a
+/// notification, which is followed by another field of the command.
+fn strip_notification_title(title: &str) -> Cow<'_, str> {
+    if title.chars().any(|c| c.is_control() || c == ';') {
+        Cow::Owned(
+            title
+                .chars()
+                .filter(|&c| !c.is_control() && c != ';')
+                .collect(),
+        )
+    } else {
+        Cow::Borrowed(title)
+    }
 }
 
-/// A set of `AnsiByteString`s collected together, in order to be
-/// written with a minimum of control characters.
-pub type AnsiByteStrings<'a> = AnsiGenericStrings<'a, [u8]>;
+// ---- writers for combined ANSI strings ----
 
-/// A function to construct an `AnsiByteStrings` instance.
-#[allow(non_snake_case)]
-pub fn AnsiByteStrings<'a>(
-    arg: impl IntoIterator<Item = AnsiByteString<'a>>,
-) -> AnsiByteStrings<'a> {
-    AnsiGenericStrings::from_iter(arg)
+/// Formatting flags apply to the visible text of the whole sequence, as for
+/// [`AnsiString`].
+impl<'a> fmt::Display for AnsiStrings<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if f.width().is_none() && f.precision().is_none() {
+            return self.write_to_any(fmt_write!(f));
+        }
+        let mut rendered = String::new();
+        self.write_to_any(fmt_write!(&mut rendered))?;
+        pad_rendered(f, &rendered)
+    }
 }
 
-// ---- paint functions ----
-impl Style {
-    /// Paints the given content with this style, returning an ANSI string.
+impl<'a> AnsiStrings<'a> {
+    /// The text of all of these strings without any escape codes. See
+    /// [`AnsiString::unstyled`].
+    ///
+    /// # Examples
     ///
     /// ```
-    /// use nu_ansi_term::Style;
-    /// use nu_ansi_term::Color;
+    /// use nu_ansi_term::AnsiStrings;
+    /// use nu_ansi_term::Color::{Green, Red};
     ///
-    /// println!("{}", Style::new().fg(Color::Blue).paint("nice!"));
+    /// let status = AnsiStrings([Green.paint("3 passed"), ", ".into(), Red.paint("1 failed")]);
+    /// assert_eq!(status.unstyled(), "3 passed, 1 failed");
     /// ```
-    #[inline]
-    #[must_use]
-    pub fn paint<'a, I, S: 'a + ToOwned + ?Sized>(self, input: I) -> AnsiGenericString<'a, S>
+    pub fn unstyled(&self) -> String {
+        self.iter().map(AnsiString::unstyled).collect()
+    }
+
+    /// Whether these strings show the same text as `other`, once both are
+    /// normalized to NFC. See [`AnsiString::eq_content_normalized`].
+    #[cfg(feature = "unicode-normalization")]
+    pub fn eq_content_normalized(&self, other: &AnsiStrings<'_>) -> bool {
+        use unicode_normalization::UnicodeNormalization;
+        self.unstyled().nfc().eq(other.unstyled().nfc())
+    }
+}
+
+impl<'a> AnsiByteStrings<'a> {
+    /// Write `AnsiByteStrings` to an `io::Write`.  This writes the minimal
+    /// escape sequences for the associated `Style`s around each set of
+    /// bytes.
+    pub fn write_to<W: io::Write>(&self, w: &mut W) -> io::Result<()> {
+        self.write_to_any(io_write!(w))
+    }
+
+    /// Render these strings, with their escape codes, into a `Vec<u8>`. See
+    /// [`AnsiByteString::to_vec`].
+    pub fn to_vec(&self) -> Vec<u8> {
+        let mut rendered = Vec::new();
+        self.write_to(&mut rendered)
+            .expect("writing to a Vec cannot fail");
+        rendered
+    }
+
+    /// The bytes of all of these strings without any escape codes. See
+    /// [`AnsiString::unstyled`].
+    pub fn unstyled(&self) -> Vec<u8> {
+        self.iter().flat_map(AnsiByteString::unstyled).collect()
+    }
+}
+
+impl<'a, S: 'a + ToOwned + ?Sized> AnsiGenericStrings<'a, S> {
+    /// Write this sequence to the given [`AnyWrite`] implementor.
+    pub fn write_to_any<W: AnyWrite + ?Sized>(&self, w: &mut W) -> WriteResult<W::Error>
     where
-        I: Into<Content<'a, S>>,
+        S: StrLike<'a, W>,
+        str: StrLike<'a, W>,
     {
-        AnsiGenericString {
-            content: match input.into() {
-                x @ Content::GenericStrings(_) => x.with_context(self),
-                x => x,
-            },
-            style: self,
-            oscontrol: None,
-        }
+        self.write_optimized_to_any(OptimizeFor::Transitions, w)
     }
-}
 
-impl Color {
-    /// Paints the given text with this color, returning an ANSI string.
-    /// This is a short-cut so you don’t have to use `Blue.normal()` just
-    /// to get blue text.
+    /// Write this sequence to the given [`AnyWrite`] implementor for a
+    /// terminal with `profile` rather than the render default: lazily
+    /// resolved styles are resolved for it, colors are downgraded to the
+    /// ones it supports, and OSC sequences it does not support are left out
+    /// (only the text of a hyperlink is written), at every level of nesting.
+    ///
+    /// # Examples
     ///
     /// ```
+    /// use std::fmt;
+    /// use nu_ansi_term::{fmt_write, AnsiStrings, TerminalProfile};
     /// use nu_ansi_term::Color::Blue;
-    /// println!("{}", Blue.paint("da ba dee"));
+    ///
+    /// let strings = AnsiStrings([Blue.paint("docs").hyperlink("https://example.com")]);
+    /// let mut plain = String::new();
+    /// strings
+    ///     .write_for(&TerminalProfile::new().without_hyperlinks(), fmt_write!(&mut plain))
+    ///     .unwrap();
+    /// assert_eq!(plain, "\x1b[34mdocs\x1b[0m");
     /// ```
-    #[inline]
-    #[must_use]
-    pub fn paint<'a, I, S: 'a + ToOwned + ?Sized>(self, input: I) -> AnsiGenericString<'a, S>
+    pub fn write_for<W: AnyWrite + ?Sized>(
+        &self,
+        profile: &TerminalProfile,
+        w: &mut W,
+    ) -> WriteResult<W::Error>
     where
-        I: Into<Content<'a, S>>,
+        S: StrLike<'a, W>,
+        str: StrLike<'a, W>,
     {
-        self.normal().paint(input)
-    }
-}
-
-// ---- writers for individual ANSI strings ----
-
-impl<'a> fmt::Display for AnsiString<'a> {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        self.write_to_any(fmt_write!(f))
-    }
-}
-
-impl<'a> AnsiByteString<'a> {
-    /// Write an `AnsiByteString` to an `io::Write`.  This writes the escape
-    /// sequences for the associated `Style` around the bytes.
-    pub fn write_to<W: io::Write>(&self, w: &mut W) -> io::Result<()> {
-        self.write_to_any(io_write!(w))
+        crate::profile::render_for(*profile, || self.adapt_to(profile.colors).write_to_any(w))
     }
-}
 
-impl<'a, S: 'a + ToOwned + ?Sized> AnsiGenericString<'a, S> {
-    /// Rebase this style on a `base` style (effective an "OR" operation).
-    /// Useful for ANSI strings nested inside other ANSI strings.
-    pub fn rebase_on(mut self, base: Style) -> Self {
-        self.style = self.style.rebase_on(base);
-        self
-    }
-    /// Write only the part of the generic string which lies within its styling
-    /// prefix and suffix: its `content` and `oscontrol`.
-    pub fn write_inner<W: AnyWrite + ?Sized>(
-        content: &Content<'a, S>,
-        oscontrol: &Option<OSControl<'a, S>>,
+    /// Write this sequence to the given [`AnyWrite`] implementor, choosing
+    /// the codes between segments as `optimize` says.
+    pub fn write_optimized_to_any<W: AnyWrite + ?Sized>(
+        &self,
+        optimize: OptimizeFor,
         w: &mut W,
     ) -> WriteResult<W::Error>
     where
         S: StrLike<'a, W>,
         str: StrLike<'a, W>,
     {
-        match oscontrol {
-            Some(OSControl::Link { url: u, .. }) => {
-                write_str!(w, "\x1B]8;;")?;
-                u.write_to(w)?;
-                write_str!(w, "\x1B\x5C")?;
-                content.write_to(w)?;
-                write_str!(w, "\x1B]8;;\x1B\x5C")
+        if self.has_lazy_styles() {
+            return self
+                .resolved(&TerminalProfile::rendering())
+                .write_optimized_to_any(optimize, w);
+        }
+
+        if !is_enabled() {
+            for string in self.strings.iter() {
+                string.write_content_in(string.style, w)?;
             }
-            Some(OSControl::Title) => {
-                write_str!(w, "\x1B]2;")?;
-                content.write_to(w)?;
-                write_str!(w, "\x1B\x5C")
+            return Ok(());
+        }
+
+        let last = self.len().saturating_sub(1);
+        if let Some(enclosing) = nested::enclosing_style() {
+            let mut current = enclosing;
+            for (ix, string) in self.strings.iter().enumerate() {
+                instrument::in_segment(ix, || {
+                    if let Some(style) = string.scheduled_style() {
+                        let style = nested::flatten(style, enclosing);
+                        nested::switch(current, style, w)?;
+                        current = style;
+                    }
+                    string.write_content_in(current, w)
+                })?;
             }
-            None => content.write_to(w),
+            return instrument::in_segment(last, || nested::switch(current, enclosing, w));
         }
+
+        let mut segments = self.segment_writer(optimize);
+        while segments.write_next(w)? {}
+        segments.finish(w)
     }
 
-    /// Write this generic string to the given `AnyWrite` implementor.
-    pub fn write_to_any<W: AnyWrite + ?Sized>(&self, w: &mut W) -> WriteResult<W::Error>
+    /// A writer for the segments of this sequence, one at a time, as
+    /// [`write_optimized_to_any`](Self::write_optimized_to_any) writes them
+    /// when output is enabled, not nested and has no lazy styles.
+    pub(crate) fn segment_writer(&self, optimize: OptimizeFor) -> SegmentWriter<'_, 'a, S> {
+        SegmentWriter {
+            segments: self.write_iter(),
+            updates: self.style_updates(),
+            optimize,
+            ix: 0,
+            last_is_plain: true,
+            in_effect: Style::new(),
+        }
+    }
+
+    /// The style the terminal is left in by the last segment of this
+    /// sequence (before the final reset), for continuing the output with
+    /// [`render_continuing_from`](Self::render_continuing_from).
+    pub fn final_style(&self) -> Style {
+        if self.has_lazy_styles() {
+            return self.resolved(&TerminalProfile::rendering()).final_style();
+        }
+        let mut style = self
+            .strings
+            .iter()
+            .rev()
+            .find_map(|s| s.scheduled_style())
+            .unwrap_or_default();
+        style.prefix_before_reset = false;
+        style
+    }
+
+    /// Render this sequence as the continuation of output which left the
+    /// terminal in the style `previous`, such as an earlier page or frame.
+    ///
+    /// Only the codes needed to get from `previous` to the first segment's
+    /// style are written at the start, and no reset is written at the end:
+    /// the terminal is left in [`final_style`](Self::final_style), ready for
+    /// the next part. Write that style's [`suffix`](Style::suffix) after the
+    /// last part.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::{AnsiStrings, Style};
+    /// use nu_ansi_term::Color::{Blue, Red};
+    ///
+    /// let first = AnsiStrings([Red.paint("page one, ")]);
+    /// let second = AnsiStrings([Red.paint("still red, "), Blue.paint("blue")]);
+    ///
+    /// let output = format!(
+    ///     "{}{}{}",
+    ///     first.render_continuing_from(Style::new()),
+    ///     second.render_continuing_from(first.final_style()),
+    ///     second.final_style().suffix(),
+    /// );
+    /// assert_eq!(output, "\x1b[31mpage one, still red, \x1b[34mblue\x1b[0m");
+    /// ```
+    pub fn render_continuing_from(&self, previous: Style) -> Continuation<'_, 'a, S> {
+        Continuation {
+            strings: self,
+            previous,
+        }
+    }
+
+    /// Write this sequence to the given [`AnyWrite`] implementor as the
+    /// continuation of output in the style `previous`. See
+    /// [`render_continuing_from`](Self::render_continuing_from).
+    pub fn write_continuing_from_any<W: AnyWrite + ?Sized>(
+        &self,
+        previous: Style,
+        w: &mut W,
+    ) -> WriteResult<W::Error>
     where
         S: StrLike<'a, W>,
         str: StrLike<'a, W>,
     {
-        write_fmt!(w, "{}", self.style.prefix())?;
-        Self::write_inner(&self.content, &self.oscontrol, w)?;
-        write_fmt!(w, "{}", self.style.suffix())
+        if self.has_lazy_styles() {
+            return self
+                .resolved(&TerminalProfile::rendering())
+                .write_continuing_from_any(previous, w);
+        }
+
+        let mut current = previous;
+        for string in self.strings.iter() {
+            if let Some(style) = string.scheduled_style() {
+                if let StyleDelta::ExtraStyles(delta) = current.compute_delta(style) {
+                    delta.write_prefix(w)?;
+                }
+                current = style;
+            }
+            AnsiGenericString::write_inner(&string.content, &string.oscontrol, w)?;
+        }
+        Ok(())
     }
 }
 
-// ---- writers for combined ANSI strings ----
+/// An [`AnsiGenericStrings`] sequence rendered as the continuation of earlier
+/// output, created by [`AnsiGenericStrings::render_continuing_from`].
+pub struct Continuation<'b, 'a, S: 'a + ToOwned + ?Sized> {
+    strings: &'b AnsiGenericStrings<'a, S>,
+    previous: Style,
+}
 
-impl<'a> fmt::Display for AnsiStrings<'a> {
+impl<'b, 'a> fmt::Display for Continuation<'b, 'a, str> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        self.write_to_any(fmt_write!(f))
+        self.strings
+            .write_continuing_from_any(self.previous, fmt_write!(f))
     }
 }
 
-impl<'a> AnsiByteStrings<'a> {
-    /// Write `AnsiByteStrings` to an `io::Write`.  This writes the minimal
-    /// escape sequences for the associated `Style`s around each set of
-    /// bytes.
+impl<'b, 'a> Continuation<'b, 'a, [u8]> {
+    /// Write this continuation to an `io::Write`.
     pub fn write_to<W: io::Write>(&self, w: &mut W) -> io::Result<()> {
-        self.write_to_any(io_write!(w))
+        self.strings
+            .write_continuing_from_any(self.previous, io_write!(w))
     }
 }
 
-impl<'a, S: 'a + ToOwned + ?Sized> AnsiGenericStrings<'a, S> {
-    /// Write this sequence to the given [`AnyWrite`] implementor.
-    pub fn write_to_any<W: AnyWrite + ?Sized>(&self, w: &mut W) -> WriteResult<W::Error>
+/// Writes the segments of an [`AnsiGenericStrings`] sequence one at a time,
+/// so that long output can be streamed. Created by
+/// [`AnsiGenericStrings::segment_writer`].
+pub(crate) struct SegmentWriter<'b, 'a, S: 'a + ToOwned + ?Sized> {
+    segments: WriteIter<'b, 'a, S>,
+    updates: Ref<'b, Cow<'b, [StyleUpdate]>>,
+    optimize: OptimizeFor,
+    ix: usize,
+    last_is_plain: bool,
+    in_effect: Style,
+}
+
+impl<'b, 'a, S: 'a + ToOwned + ?Sized> SegmentWriter<'b, 'a, S> {
+    /// Write the next segment with the codes before it, returning whether
+    /// there was one.
+    pub(crate) fn write_next<W: AnyWrite + ?Sized>(&mut self, w: &mut W) -> Result<bool, W::Error>
     where
         S: StrLike<'a, W>,
         str: StrLike<'a, W>,
     {
-        let mut last_is_plain = true;
-
-        for (style_command, content, oscontrol) in self.write_iter() {
-            match style_command {
-                StyleDelta::ExtraStyles(style) => {
+        let (style_command, content, oscontrol) = match self.segments.next() {
+            Some(segment) => segment,
+            None => return Ok(false),
+        };
+        let update = self.updates[self.ix];
+        instrument::in_segment(self.ix, || {
+            match (style_command, self.optimize) {
+                (StyleDelta::ExtraStyles(style), OptimizeFor::Transitions) => {
                     style.write_prefix(w)?;
-                    last_is_plain = style.has_no_styling();
+                    self.last_is_plain = style.has_no_styling();
+                }
+                (StyleDelta::ExtraStyles(_), OptimizeFor::Bytes) => {
+                    self.in_effect
+                        .write_shortest_transition(update.in_effect, w)?;
+                    self.last_is_plain = update.in_effect.has_no_styling();
                 }
-                StyleDelta::Empty => {}
+                (StyleDelta::Empty, _) => {}
             }
-            AnsiGenericString::write_inner(&content, &oscontrol, w)?;
-        }
+            self.in_effect = update.in_effect;
+            match content {
+                Content::FmtArgs(_) => nested::within(update.in_effect, || {
+                    AnsiGenericString::write_inner(content, oscontrol, w)
+                }),
+                _ => AnsiGenericString::write_inner(content, oscontrol, w),
+            }
+        })?;
+        self.ix += 1;
+        Ok(true)
+    }
 
-        if last_is_plain {
-            dbg!(last_is_plain);
-            Ok(())
-        } else {
-            w.write_str(RESET.as_ref())
+    /// Write the reset after the last segment, if it left a style in effect.
+    pub(crate) fn finish<W: AnyWrite + ?Sized>(self, w: &mut W) -> WriteResult<W::Error>
+    where
+        str: StrLike<'a, W>,
+    {
+        if !self.last_is_plain {
+            w.write_str(RESET.as_ref())?;
+            let last = self.ix.saturating_sub(1);
+            instrument::in_segment(last, || instrument::emit(SequenceKind::Reset, RESET.len()));
         }
+        Ok(())
     }
 }
 
@@ -824,6 +3056,42 @@ mod tests {
     pub use crate::style::Color::*;
     pub use crate::style::Style;
 
+    #[test]
+    fn composed_styles_keep_overrides() {
+        use crate::FormatFlags;
+        let base = White.on(Black).italic();
+        let mut style = base | FormatFlags::BOLD | Red;
+        assert_eq!(style, Red.on(Black).italic().bold());
+        style |= Style::new().clear_formats(FormatFlags::ITALIC);
+        assert_eq!(style, base + Red.bold().clear_formats(FormatFlags::ITALIC));
+        assert_eq!(
+            style,
+            Red.on(Black).bold().clear_formats(FormatFlags::ITALIC)
+        );
+    }
+
+    #[test]
+    fn equal_strings_hash_alike() {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+        let hash = |string: &AnsiGenericString<'_, str>| {
+            let mut hasher = DefaultHasher::new();
+            string.hash(&mut hasher);
+            hasher.finish()
+        };
+        let red: AnsiGenericString<'_, str> = Red.paint("a");
+        let shared = Red.paint(crate::write::Content::Shared("a".into()));
+        assert_eq!(shared, red);
+        assert_eq!(hash(&shared), hash(&red));
+        assert_ne!(red, Red.bold().paint("a"));
+        assert_ne!(red, Red.paint(format_args!("a")));
+        assert_eq!(
+            Red.paint(AnsiStrings([Blue.paint("b")])),
+            Red.paint(AnsiStrings([Blue.paint("b")]))
+        );
+        assert_ne!(AnsiGenericString::title("a"), AnsiGenericString::from("a"));
+    }
+
     #[test]
     fn no_control_codes_for_plain() {
         let one = Style::default().paint("one");
@@ -889,7 +3157,7 @@ mod tests {
             format_args!(
                 "{}{}{}",
                 after_g.style.prefix(),
-                after_g.content.to_string(),
+                after_g.content,
                 after_g.style.suffix()
             )
         });
@@ -901,17 +3169,15 @@ mod tests {
         let unstyled = AnsiGenericString::title("hello");
         let before_g = Green.paint("Before is Green.");
 
-        // Check that reset precedes unstyled, but does not follow it
+        // Check that the style is kept across the title, which is not
+        // displayed, and is reset only after it
         let joined = AnsiStrings([before_g.clone(), unstyled.clone()]).to_string();
         let expected = format!(
-            "{}{}",
-            format_args!(
-                "{}{}{}",
-                before_g.style.prefix().to_string(),
-                before_g.content.to_string(),
-                before_g.style.suffix().to_string()
-            ),
-            unstyled
+            "{}{}{}{}",
+            before_g.style.prefix(),
+            before_g.content,
+            unstyled,
+            before_g.style.suffix()
         );
         assert_required!(joined, expected);
     }
@@ -924,20 +3190,12 @@ mod tests {
 
         let joined = AnsiStrings([before_g.clone(), unstyled.clone(), after_g.clone()]).to_string();
         let expected = format!(
-            "{}{}{}",
-            format_args!(
-                "{}{}{}",
-                before_g.style.prefix().to_string(),
-                before_g.content.to_string(),
-                before_g.style.suffix().to_string()
-            ),
+            "{}{}{}{}{}",
+            before_g.style.prefix(),
+            before_g.content,
             unstyled,
-            format_args!(
-                "{}{}{}",
-                after_g.style.prefix(),
-                after_g.content.to_string(),
-                after_g.style.suffix()
-            )
+            after_g.content,
+            after_g.style.suffix()
         );
         assert_required!(joined, expected);
     }
@@ -959,7 +3217,6 @@ mod tests {
             .underline()
             .paint("Link to example.com.")
             .hyperlink_content("https://example.com");
-        dbg!("link: {:?}", &link);
         // Assemble with link by itself
         let joined = AnsiStrings([link.clone()]).to_string();
         #[cfg(feature = "gnu_legacy")]
@@ -974,7 +3231,6 @@ mod tests {
             .underline()
             .paint("Link to example.com.")
             .hyperlink_content("https://example.com");
-        dbg!("link: {:?}", &link);
         let after = Green.paint(" After link.");
         // Assemble with link first
         let joined = AnsiStrings([link.clone(), after.clone()]).to_string();
@@ -991,7 +3247,6 @@ mod tests {
             .underline()
             .paint("Link to example.com.")
             .hyperlink_content("https://example.com");
-        dbg!("link: {:?}", &link);
         // Assemble with link at the end
         let joined = AnsiStrings([before.clone(), link.clone()]).to_string();
         #[cfg(feature = "gnu_legacy")]
@@ -1007,9 +3262,7 @@ mod tests {
             .underline()
             .paint("Link to example.com.")
             .hyperlink_content("https://example.com");
-        dbg!("link: {:?}", &link);
         let after = Green.paint(" After link.");
-        dbg!("link: {:?}", &link);
         // Assemble with link in the middle
         let joined = AnsiStrings([before.clone(), link.clone(), after.clone()]).to_string();
         #[cfg(feature = "gnu_legacy")]
@@ -1017,4 +3270,451 @@ mod tests {
         #[cfg(not(feature = "gnu_legacy"))]
         assert_eq!(joined, format!("\x1B[32mBefore link. \x1B[4;34m\x1B]8;;https://example.com\x1B\\Link to example.com.\x1B]8;;\x1B\\\x1B[0m\x1B[32m After link.\x1B[0m"));
     }
+
+    #[test]
+    fn fmt_args_url_is_percent_encoded() {
+        let page = "a b\x1B";
+        assert_eq!(
+            Blue.paint("link")
+                .hyperlink_content(format_args!("https://example.com/{}", page))
+                .to_string(),
+            "\x1B[34m\x1B]8;;https://example.com/a%20b%1B\x1B\\link\x1B]8;;\x1B\\\x1B[0m"
+        );
+    }
+
+    #[test]
+    fn link_params() {
+        let link = Blue
+            .paint("link")
+            .hyperlink("https://example.com")
+            .link_id("a:b")
+            .link_param("k=;", "v\x07")
+            .link_id("c");
+        assert_eq!(
+            link.to_string(),
+            "\x1B[34m\x1B]8;k=v:id=c;https://example.com\x1B\\link\x1B]8;;\x1B\\\x1B[0m"
+        );
+        assert!(crate::AnsiString::from("text")
+            .link_id("c")
+            .link_params()
+            .is_empty());
+    }
+
+    #[test]
+    fn fmt_args_title_has_controls_stripped() {
+        let name = "evil\x07\x1B]2;pwned";
+        assert_eq!(
+            AnsiGenericString::title_content(format_args!("editing {}", name)).to_string(),
+            "\x1B]2;editing evil]2;pwned\x1B\\"
+        );
+    }
+
+    #[test]
+    fn reset_byte_string() {
+        let mut out = Vec::new();
+        crate::AnsiByteString::reset().write_to(&mut out).unwrap();
+        assert_eq!(out, crate::RESET_BYTES);
+    }
+
+    #[test]
+    fn restyle_after_painting() {
+        let restyled = Red.paint("x").bold().on(Blue).underline().without_bold();
+        assert_eq!(restyled.style_ref(), &Red.underline().on(Blue));
+        assert_eq!(
+            restyled.to_string(),
+            Red.underline().on(Blue).paint("x").to_string()
+        );
+    }
+
+    #[test]
+    fn paint_fmt_owns_its_content() {
+        let cells: Vec<crate::AnsiString<'static>> = (1..=2)
+            .map(|n| {
+                let value = n as f64 / 3.0;
+                paint_fmt!(Red, "{:<6.3}|", value)
+            })
+            .collect();
+        assert_eq!(
+            AnsiStrings(cells).to_string(),
+            "\x1b[31m0.333 |0.667 |\x1b[0m"
+        );
+
+        // The macro must not pick up a `std` defined where it is used.
+        mod std {}
+        assert_eq!(paint_fmt!(Red, "{}", 1).to_string(), "\x1b[31m1\x1b[0m");
+    }
+
+    #[test]
+    fn paint_restores_the_style_after_nested_arguments() {
+        let name = Blue.paint("x");
+        assert_eq!(
+            ansi_format!(Red => ("{} = {}", name, 1), Red.bold() => ("!")).to_string(),
+            "\x1b[31m\x1b[34mx\x1b[31m = 1\x1b[1m!\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn owned_content_outlives_its_values() {
+        let bytes: crate::AnsiByteString<'static> = {
+            let data = vec![b'o', b'k'];
+            Green.paint(crate::Content::<[u8]>::owned(data))
+        };
+        let mut written = Vec::new();
+        bytes.write_to(&mut written).unwrap();
+        assert_eq!(written, b"\x1b[32mok\x1b[0m");
+
+        let text: crate::AnsiString<'static> = {
+            let n = 7;
+            Red.paint(crate::owned_content!("{}!", n))
+        };
+        assert_eq!(text.to_string(), "\x1b[31m7!\x1b[0m");
+    }
+
+    #[test]
+    fn into_owned_outlives_borrows() {
+        let strings: crate::AnsiStrings<'static> = {
+            let (name, n) = (String::from("name"), 3);
+            let inner = Blue.paint(name.as_str());
+            let mut lazy = AnsiStrings::with_capacity(1);
+            lazy.push_lazy(name.as_str(), |_| Blue.bold());
+            (AnsiStrings([
+                Red.paint(format_args!("{} {}", inner, n)),
+                Green.paint(name.as_str()).hyperlink(name.as_str()),
+            ]) + lazy)
+                .into_owned()
+        };
+        assert_eq!(
+            strings.to_string(),
+            AnsiStrings([
+                Red.paint(format_args!("{} 3", Blue.paint("name"))),
+                Green.paint("name").hyperlink("name"),
+                Blue.bold().paint("name"),
+            ])
+            .to_string()
+        );
+
+        let bytes: crate::AnsiByteString<'static> = {
+            let data = vec![b'o', b'k'];
+            Green.paint(data.as_slice()).into_owned()
+        };
+        assert_eq!(bytes.unstyled(), b"ok");
+    }
+
+    #[test]
+    fn sendable_strings_cross_threads() {
+        fn assert_send<T: Send>(value: T) -> T {
+            value
+        }
+        let name = String::from("name");
+        let mut lazy = AnsiStrings::with_capacity(1);
+        lazy.push_lazy(name.as_str(), |_| Blue.bold());
+        let strings = assert_send(
+            (AnsiStrings([Red.paint(format_args!("{}!", name))]) + lazy).into_sendable(),
+        );
+        let link = assert_send(Green.paint("ok").hyperlink(name.as_str()).into_sendable());
+        let nested = assert_send(
+            AnsiStrings([Red.paint(AnsiStrings([Blue
+                .paint(format_args!("<{}>", name))
+                .hyperlink_content(format_args!("https://{}", name))]))])
+            .into_sendable(),
+        );
+        let bytes = assert_send(Green.paint(name.as_bytes()).into_sendable());
+        let (strings, link, nested, bytes) = std::thread::spawn(move || {
+            (
+                strings.into_inner().to_string(),
+                link.into_inner().to_string(),
+                nested.into_inner().to_string(),
+                bytes.into_inner().unstyled().to_vec(),
+            )
+        })
+        .join()
+        .unwrap();
+        assert_eq!(strings, "\x1b[31mname!\x1b[1;34mname\x1b[0m");
+        assert_eq!(link, Green.paint("ok").hyperlink("name").to_string());
+        assert_eq!(
+            nested,
+            AnsiStrings([Red.paint(AnsiStrings([Blue
+                .paint("<name>")
+                .hyperlink("https://name")]))])
+            .to_string()
+        );
+        assert_eq!(bytes, b"name");
+    }
+
+    #[test]
+    fn byte_strings_match_their_text_counterparts() {
+        let owned: crate::AnsiByteString<'static> = Green
+            .paint(String::from("ok").into_bytes())
+            .hyperlink_content(String::from("file:///ok").into_bytes());
+        let text: crate::AnsiString = Green.paint("ok").hyperlink("file:///ok");
+        assert_eq!(owned.to_vec(), text.to_string().into_bytes());
+
+        let title = crate::AnsiByteString::title_content(b"\xfftitle".to_vec());
+        let strings = crate::AnsiByteStrings([title, owned]);
+        // Bytes which are not UTF-8 cannot be cleaned of control codes, so
+        // they are replaced.
+        assert!(strings
+            .to_vec()
+            .starts_with("\x1b]2;\u{FFFD}title".as_bytes()));
+        assert_eq!(strings.unstyled(), b"ok");
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::ffi::OsStringExt;
+            let name = std::ffi::OsString::from_vec(b"caf\xe9".to_vec());
+            let mut name = crate::AnsiByteString::from_os_string(name);
+            *name.style_ref_mut() = Blue.normal();
+            assert_eq!(name.to_vec(), b"\x1b[34mcaf\xe9\x1b[0m");
+        }
+        let text = crate::AnsiByteString::from_string(String::from("ok"));
+        assert_eq!(text.to_vec(), b"ok");
+
+        // Owned strings paint into text strings without annotations.
+        let painted = vec![Red.paint(String::from("x"))];
+        assert!(format!("{:?}", painted).contains("\"x\""));
+    }
+
+    #[test]
+    fn lazy_styles_resolve_per_profile() {
+        use crate::TerminalProfile;
+
+        let mut strings = AnsiStrings([Blue.paint("a")]);
+        strings.push_lazy("b", |profile: &TerminalProfile| {
+            if profile.unicode {
+                Red.bold()
+            } else {
+                Blue.normal()
+            }
+        });
+        assert!(strings.has_lazy_styles());
+        assert_eq!(
+            strings.resolved(&TerminalProfile::new()).to_string(),
+            "\x1b[34ma\x1b[1;31mb\x1b[0m"
+        );
+        assert_eq!(
+            strings.resolved(&TerminalProfile::ascii()).to_string(),
+            "\x1b[34mab\x1b[0m"
+        );
+
+        let replaced = strings.update_strings([(1, Red.paint("c"))]);
+        assert!(!replaced.has_lazy_styles());
+    }
+
+    #[test]
+    fn continue_across_chunks() {
+        let first = AnsiStrings([Red.paint("a"), Red.bold().paint("b")]);
+        let second = AnsiStrings([Red.bold().paint("c"), Blue.paint("d")]);
+        assert_eq!(first.final_style(), Red.bold());
+
+        let start = first.render_continuing_from(Style::new()).to_string();
+        let rest = second
+            .render_continuing_from(first.final_style())
+            .to_string();
+        assert_eq!(start, "\x1b[31ma\x1b[1mb");
+        assert_eq!(rest, "c\x1b[0m\x1b[34md");
+        assert_eq!(second.final_style().suffix().to_string(), "\x1b[0m");
+
+        let plain = AnsiStrings([Style::new().paint("e")]);
+        assert_eq!(
+            plain
+                .render_continuing_from(second.final_style())
+                .to_string(),
+            "\x1b[0me"
+        );
+
+        let cleared = AnsiStrings([Blue.paint("f"), AnsiGenericString::control("\x1b[K")]);
+        assert_eq!(cleared.final_style(), Blue.normal());
+        assert_eq!(
+            cleared.render_continuing_from(Blue.normal()).to_string(),
+            "f\x1b[K"
+        );
+    }
+
+    #[test]
+    fn parts() {
+        let hi: crate::AnsiString = Red.underline().paint("hi");
+        let (prefix, body, suffix) = hi.parts();
+        assert_eq!(prefix, "\x1b[4;31m");
+        assert_eq!(body, "hi");
+        assert_eq!(suffix, "\x1b[0m");
+
+        let link: crate::AnsiString = Style::new().paint("x").hyperlink("http://a");
+        assert_eq!(
+            link.parts(),
+            (
+                String::new(),
+                "\x1b]8;;http://a\x1b\\x\x1b]8;;\x1b\\".to_string(),
+                String::new()
+            )
+        );
+
+        let bytes: crate::AnsiByteString = Blue.paint("b".as_bytes());
+        let bytes = bytes.parts();
+        assert_eq!(
+            bytes,
+            (b"\x1b[34m".to_vec(), b"b".to_vec(), b"\x1b[0m".to_vec())
+        );
+    }
+
+    #[test]
+    fn control_segments_keep_style() {
+        let mut strings = AnsiStrings([Red.paint("a")]);
+        strings.push_control("\x1b[1A");
+        strings.push(Red.paint("b"));
+        strings.push_style_change(Blue.normal());
+        strings.push_control("\x1b[K");
+        strings.push(Blue.paint("c"));
+        assert_eq!(
+            strings.to_string(),
+            "\x1b[31ma\x1b[1Ab\x1b[34m\x1b[Kc\x1b[0m"
+        );
+        assert_eq!(crate::utils::unstyle(&strings), "abc");
+
+        let alone = crate::AnsiString::control("\x1b[2J");
+        assert!(alone.is_control());
+        assert_eq!(alone.to_string(), "\x1b[2J");
+        assert_eq!(AnsiStrings([alone]).to_string(), "\x1b[2J");
+    }
+
+    #[test]
+    fn borrowed_slice_outlived_by_sequence() {
+        let segments: [crate::AnsiString; 2] = [Red.paint("a"), Blue.paint("b")];
+        let strings = super::AnsiGenericStrings::new(&segments);
+        assert_eq!(strings.iter().count(), 2);
+    }
+
+    fn sgr_count(output: &str) -> usize {
+        output.matches("\x1B[").count()
+    }
+
+    #[test]
+    fn link_listing_escape_counts() {
+        let rows = 300;
+        let names: Vec<String> = (0..rows).map(|ix| format!("file{}", ix)).collect();
+        let urls: Vec<String> = names.iter().map(|n| format!("file:///{}", n)).collect();
+
+        // Every row is a link of the same style, separated by newlines in
+        // that same style: one prefix and one reset for the whole listing.
+        let mut same = crate::AnsiStrings::with_capacity(2 * rows);
+        for (name, url) in names.iter().zip(&urls) {
+            same.push(Blue.paint(name.as_str()).hyperlink(url.as_str()));
+            same.push(Blue.paint("\n"));
+        }
+        let output = same.to_string();
+        assert_eq!(sgr_count(&output), 2);
+        assert_eq!(output.matches("\x1B]8;;").count(), 2 * rows);
+
+        // Links separated by plain newlines switch style once per row, and
+        // back to plain once per row.
+        let mut alternating = crate::AnsiStrings::with_capacity(2 * rows);
+        for (name, url) in names.iter().zip(&urls) {
+            alternating.push(Blue.bold().paint(name.as_str()).hyperlink(url.as_str()));
+            alternating.push(Style::new().paint("\n"));
+        }
+        let output = alternating.to_string();
+        assert_eq!(sgr_count(&output), 2 * rows);
+        assert_eq!(output.matches("\x1B[0m").count(), rows);
+    }
+
+    #[test]
+    fn deltas_follow_the_full_style() {
+        // Turning bold off must reset, even though the delta before it only
+        // added bold.
+        let strings = AnsiStrings([Red.paint("a"), Red.bold().paint("b"), Red.paint("c")]);
+        assert_eq!(
+            strings.to_string(),
+            "\x1B[31ma\x1B[1mb\x1B[0m\x1B[31mc\x1B[0m"
+        );
+
+        let strings = AnsiStrings([
+            Red.paint("a"),
+            Red.bold().paint("b"),
+            Red.bold().underline().paint("c"),
+        ]);
+        assert_eq!(strings.to_string(), "\x1B[31ma\x1B[1mb\x1B[4mc\x1B[0m");
+
+        let segments: [crate::AnsiString; 2] = [Red.paint("a"), Red.bold().paint("b")];
+        assert_eq!(
+            super::AnsiGenericStrings::new(&segments).to_string(),
+            "\x1B[31ma\x1B[1mb\x1B[0m"
+        );
+    }
+
+    #[test]
+    fn dump_transitions() {
+        let mut strings = AnsiStrings([Red.bold().paint("a")]);
+        strings.push_control("\x1b[K");
+        strings.push(Style::new().paint("b"));
+        let dump = strings.dump_transitions();
+        let lines: Vec<&str> = dump.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].starts_with("0: emit \"\\u{1b}[1;31m\""));
+        assert!(lines[1].starts_with("1: skip \"\""));
+        assert!(lines[2].starts_with("2: emit \"\\u{1b}[0m\""));
+        assert_eq!(lines[3], "end: plain");
+    }
+
+    #[test]
+    fn unstyled() {
+        let mut strings = AnsiStrings([
+            Red.paint("a").hyperlink("https://example.com"),
+            Style::new().paint(AnsiStrings([Blue.bold().paint("b")])),
+            Green.paint(String::from("c")),
+        ]);
+        strings.push_control("\x1b[K");
+        strings.push(AnsiGenericString::title("title"));
+        assert_eq!(strings.unstyled(), "abc");
+
+        let bytes = crate::AnsiByteStrings([
+            Red.paint(b"x\x1b[1my".as_slice()),
+            crate::AnsiByteString::control(b"\x1b[2J".as_slice()),
+        ]);
+        assert_eq!(bytes.unstyled(), b"xy");
+    }
+
+    #[test]
+    fn formatting_flags() {
+        let strings = AnsiStrings([
+            Red.paint("日本"),
+            Blue.paint("link").hyperlink("https://example.com"),
+        ]);
+        assert_eq!(format!("{:<10}|", strings), format!("{}  |", strings));
+        assert_eq!(
+            format!("{:.5}", strings),
+            "\x1B[31m日本\x1B[34m\x1B]8;;https://example.com\x1B\\l\x1B]8;;\x1B\\\x1B[0m"
+        );
+        assert_eq!(format!("{:.3}", Red.paint("日本")), "\x1B[31m日\x1B[0m");
+        assert_eq!(format!("{:*>3}", Red.paint("long")), "\x1B[31mlong\x1B[0m");
+    }
+
+    #[test]
+    fn collection() {
+        let mut strings: AnsiStrings = [Red.paint("a")].into_iter().collect();
+        strings.extend([Blue.paint("b"), Blue.paint("c")]);
+        let mut other = AnsiStrings([Red.paint("d")]);
+        other.push_lazy("e", |_| Blue.bold());
+        let joined = strings.clone() + other;
+        assert_eq!(joined.len(), 5);
+        assert!(joined.has_lazy_styles());
+        assert_eq!(
+            joined.to_string(),
+            AnsiStrings([
+                Red.paint("a"),
+                Blue.paint("bc"),
+                Red.paint("d"),
+                Blue.bold().paint("e"),
+            ])
+            .to_string()
+        );
+        assert_eq!(joined.get(3).map(|s| s.style_ref()), Some(&Red.normal()));
+        assert!(joined.get(5).is_none());
+        assert_eq!(
+            (&strings)
+                .into_iter()
+                .map(|s| s.to_string())
+                .collect::<String>(),
+            "\x1B[31ma\x1B[0m\x1B[34mb\x1B[0m\x1B[34mc\x1B[0m"
+        );
+        assert!(AnsiStrings::with_capacity(1).is_empty());
+    }
 }
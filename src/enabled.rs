@@ -0,0 +1,189 @@
+use std::env;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static ENABLED: AtomicBool = AtomicBool::new(true);
+
+/// Turn the escape codes of styles on or off for the whole program.
+///
+/// While they are off, painted strings are written without the codes which
+/// set and reset their styles (hyperlinks, titles and control segments are
+/// still written). They are on until this is called, e.g. with the result of
+/// [`enabled_from_env`].
+///
+/// # Examples
+///
+/// ```
+/// use nu_ansi_term::Color::Red;
+///
+/// nu_ansi_term::set_enabled(false);
+/// assert_eq!(Red.bold().paint("plain").to_string(), "plain");
+/// assert_eq!(Red.prefix().to_string(), "");
+///
+/// nu_ansi_term::set_enabled(true);
+/// assert_eq!(Red.paint("red").to_string(), "\x1b[31mred\x1b[0m");
+/// ```
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether the escape codes of styles are written (see [`set_enabled`]).
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Whether the environment asks for colored output, following the
+/// [`NO_COLOR`](https://no-color.org) and
+/// [`CLICOLOR`](https://bixense.com/clicolors/) conventions:
+///
+/// * a non-empty `NO_COLOR` turns colors off,
+/// * otherwise a `CLICOLOR_FORCE` other than `0` turns them on,
+/// * otherwise `TERM=dumb` or `CLICOLOR=0` turns them off.
+///
+/// Colors are on if none of these are set. Whether the output is a terminal
+/// is not checked.
+pub fn enabled_from_env() -> bool {
+    enabled_from(|name| env::var(name).ok())
+}
+
+/// Whether colors are enabled by the variables read through `var`.
+fn enabled_from(var: impl Fn(&str) -> Option<String>) -> bool {
+    color_for(var, true)
+}
+
+/// Whether colors are enabled by the variables read through `var`, for
+/// output which goes to a terminal if `tty` is true. Only `CLICOLOR_FORCE`
+/// colors output which does not.
+fn color_for(var: impl Fn(&str) -> Option<String>, tty: bool) -> bool {
+    let set = |name| var(name).filter(|value| !value.is_empty());
+    if set("NO_COLOR").is_some() {
+        return false;
+    }
+    if set("CLICOLOR_FORCE").map_or(false, |value| value != "0") {
+        return true;
+    }
+    tty && set("TERM").map_or(true, |term| term != "dumb")
+        && set("CLICOLOR").map_or(true, |v| v != "0")
+}
+
+/// One of the standard streams of the process.
+#[cfg(feature = "tty")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Stream {
+    /// The standard output.
+    Stdout,
+    /// The standard error.
+    Stderr,
+    /// The standard input.
+    Stdin,
+}
+
+#[cfg(feature = "tty")]
+impl Stream {
+    /// Whether the stream is attached to a terminal (rather than, say, a
+    /// pipe or a file).
+    pub fn is_tty(self) -> bool {
+        sys::is_tty(self)
+    }
+}
+
+/// Whether output written to `stream` should be colored: if it goes to a
+/// terminal and the environment does not ask otherwise (see
+/// [`enabled_from_env`]), or if `CLICOLOR_FORCE` asks for colors anyway.
+///
+/// Unlike [`set_enabled`], this turns nothing on or off by itself: it is
+/// meant for deciding on the styles to use for each stream, for instance
+/// with [`Style::when`](crate::Style::when).
+///
+/// # Examples
+///
+/// ```
+/// use nu_ansi_term::{should_color, Stream};
+/// use nu_ansi_term::Color::Red;
+///
+/// let error = Red.bold().when(should_color(Stream::Stderr));
+/// eprintln!("{}: no such file", error.paint("error"));
+/// ```
+#[cfg(feature = "tty")]
+pub fn should_color(stream: Stream) -> bool {
+    color_for(|name| env::var(name).ok(), stream.is_tty())
+}
+
+#[cfg(all(feature = "tty", unix))]
+mod sys {
+    use super::Stream;
+
+    pub(super) fn is_tty(stream: Stream) -> bool {
+        let fd = match stream {
+            Stream::Stdout => libc::STDOUT_FILENO,
+            Stream::Stderr => libc::STDERR_FILENO,
+            Stream::Stdin => libc::STDIN_FILENO,
+        };
+        // SAFETY: `isatty` only inspects the file descriptor.
+        unsafe { libc::isatty(fd) == 1 }
+    }
+}
+
+#[cfg(all(feature = "tty", windows))]
+mod sys {
+    use super::Stream;
+    use windows::Win32::System::Console::{
+        GetConsoleMode, GetStdHandle, CONSOLE_MODE, STD_ERROR_HANDLE, STD_INPUT_HANDLE,
+        STD_OUTPUT_HANDLE,
+    };
+
+    pub(super) fn is_tty(stream: Stream) -> bool {
+        let std_handle = match stream {
+            Stream::Stdout => STD_OUTPUT_HANDLE,
+            Stream::Stderr => STD_ERROR_HANDLE,
+            Stream::Stdin => STD_INPUT_HANDLE,
+        };
+        // Only consoles have a console mode; pipes and files do not.
+        unsafe {
+            let mut mode: CONSOLE_MODE = 0;
+            GetConsoleMode(GetStdHandle(std_handle), &mut mode) != 0
+        }
+    }
+}
+
+#[cfg(all(feature = "tty", not(any(unix, windows))))]
+mod sys {
+    use super::Stream;
+
+    pub(super) fn is_tty(_: Stream) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn enabled_for(vars: &[(&str, &str)]) -> bool {
+        enabled_from(|name| {
+            vars.iter()
+                .find(|(var, _)| *var == name)
+                .map(|(_, value)| value.to_string())
+        })
+    }
+
+    #[test]
+    fn env_conventions() {
+        assert!(enabled_for(&[]));
+        assert!(enabled_for(&[("NO_COLOR", ""), ("TERM", "xterm")]));
+        assert!(!enabled_for(&[("NO_COLOR", "1"), ("CLICOLOR_FORCE", "1")]));
+        assert!(enabled_for(&[("CLICOLOR_FORCE", "1"), ("TERM", "dumb")]));
+        assert!(!enabled_for(&[("CLICOLOR_FORCE", "0"), ("TERM", "dumb")]));
+        assert!(!enabled_for(&[("CLICOLOR", "0")]));
+        assert!(enabled_for(&[("CLICOLOR", "1")]));
+    }
+
+    #[test]
+    fn only_forcing_colors_output_which_is_not_a_terminal() {
+        let var = |forced: &'static str| {
+            move |name: &str| (name == "CLICOLOR_FORCE").then(|| forced.to_string())
+        };
+        assert!(!color_for(var("0"), false));
+        assert!(color_for(var("1"), false));
+        assert!(color_for(var("0"), true));
+    }
+}
@@ -0,0 +1,235 @@
+use std::fmt;
+use std::io::{self, Write};
+
+use encoding_rs::{Encoder, EncoderResult, Encoding};
+
+/// The characters of code page 437 from `0x80` to `0xFF`, in order.
+const CP437_HIGH: &str = "ÇüéâäàåçêëèïîìÄÅÉæÆôöòûùÿÖÜ¢£¥₧ƒáíóúñÑªº¿⌐¬½¼¡«»\
+                          ░▒▓│┤╡╢╖╕╣║╗╝╜╛┐└┴┬├─┼╞╟╚╔╩╦╠═╬╧╨╤╥╙╘╒╓╫╪┘┌█▄▌▐▀\
+                          αßΓπΣσµτΦΘΩδ∞φε∩≡±≥≤⌠⌡÷≈°∙·√ⁿ²■\u{a0}";
+
+/// The encoding written by an [`EncodingWriter`].
+enum Target {
+    Encoder(Encoder),
+    Cp437,
+}
+
+impl fmt::Debug for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Target::Encoder(encoder) => f.write_str(encoder.encoding().name()),
+            Target::Cp437 => f.write_str("IBM437"),
+        }
+    }
+}
+
+/// Writer adapter for terminals which do not use UTF-8: the text written
+/// through it is transcoded into another encoding, such as Shift_JIS or
+/// code page 437, and characters which the encoding has no place for are
+/// replaced (by `?`, unless another replacement is given).
+///
+/// Escape sequences are ASCII, so they pass through unchanged as long as
+/// the encoding is ASCII-compatible (see
+/// [`Encoding::is_ascii_compatible`]); in ISO-2022-JP, which is not, they
+/// are replaced. Bytes which are not UTF-8 are replaced as well. Characters split
+/// between writes are put back together, so painted strings can be written
+/// through the adapter in any number of pieces.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Write;
+/// use nu_ansi_term::EncodingWriter;
+/// use nu_ansi_term::Color::Red;
+///
+/// let mut w = EncodingWriter::new(Vec::new(), encoding_rs::SHIFT_JIS);
+/// write!(w, "{}", Red.paint("エラー ✗")).unwrap();
+/// assert_eq!(
+///     w.finish().unwrap(),
+///     b"\x1b[31m\x83\x47\x83\x89\x81\x5b ?\x1b[0m"
+/// );
+/// ```
+#[derive(Debug)]
+pub struct EncodingWriter<W> {
+    inner: W,
+    target: Target,
+    replacement: Vec<u8>,
+    /// The start of a character whose other bytes are yet to be written.
+    pending: Vec<u8>,
+    scratch: Vec<u8>,
+}
+
+impl<W: Write> EncodingWriter<W> {
+    /// Create an adapter writing to `inner` in `encoding`.
+    ///
+    /// Encodings which cannot be written, like UTF-16, are written in
+    /// UTF-8 instead (see [`Encoding::output_encoding`]).
+    pub fn new(inner: W, encoding: &'static Encoding) -> Self {
+        Self::with_target(inner, Target::Encoder(encoding.new_encoder()))
+    }
+
+    /// Create an adapter writing to `inner` in code page 437, the encoding
+    /// of the original IBM PC, which is still used by DOS consoles and many
+    /// serial terminals.
+    ///
+    /// ```
+    /// use std::io::Write;
+    /// use nu_ansi_term::EncodingWriter;
+    ///
+    /// let mut w = EncodingWriter::cp437(Vec::new());
+    /// write!(w, "┌─┐ café").unwrap();
+    /// assert_eq!(w.finish().unwrap(), b"\xda\xc4\xbf caf\x82");
+    /// ```
+    pub fn cp437(inner: W) -> Self {
+        Self::with_target(inner, Target::Cp437)
+    }
+
+    fn with_target(inner: W, target: Target) -> Self {
+        Self {
+            inner,
+            target,
+            replacement: b"?".to_vec(),
+            pending: Vec::new(),
+            scratch: Vec::new(),
+        }
+    }
+
+    /// Write `replacement` in place of the characters which the encoding
+    /// has no place for. It is written as is, so it should already be in
+    /// the encoding.
+    pub fn with_replacement(mut self, replacement: impl Into<Vec<u8>>) -> Self {
+        self.replacement = replacement.into();
+        self
+    }
+
+    /// Get a reference to the wrapped writer.
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Get a mutable reference to the wrapped writer.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Finish the encoded text, writing the replacement for a character
+    /// left incomplete and whatever the encoding needs at the end of its
+    /// text, and return the wrapped writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.scratch.clear();
+        if !self.pending.is_empty() {
+            self.scratch.extend_from_slice(&self.replacement);
+        }
+        self.encode("", true);
+        self.inner.write_all(&self.scratch)?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+
+    /// Encode `text` onto the end of the scratch buffer.
+    fn encode(&mut self, mut text: &str, last: bool) {
+        match &mut self.target {
+            Target::Encoder(encoder) => loop {
+                let needed = encoder
+                    .max_buffer_length_from_utf8_without_replacement(text.len())
+                    .unwrap_or(text.len());
+                self.scratch.reserve(needed);
+                let (result, read) = encoder.encode_from_utf8_to_vec_without_replacement(
+                    text,
+                    &mut self.scratch,
+                    last,
+                );
+                text = &text[read..];
+                match result {
+                    EncoderResult::InputEmpty => break,
+                    EncoderResult::OutputFull => {}
+                    EncoderResult::Unmappable(_) => {
+                        self.scratch.extend_from_slice(&self.replacement);
+                    }
+                }
+            },
+            Target::Cp437 => {
+                for c in text.chars() {
+                    if c.is_ascii() {
+                        self.scratch.push(c as u8);
+                    } else if let Some(ix) = CP437_HIGH.chars().position(|high| high == c) {
+                        self.scratch.push(0x80 + ix as u8);
+                    } else {
+                        self.scratch.extend_from_slice(&self.replacement);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<W: Write> Write for EncodingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.scratch.clear();
+        let mut input = std::mem::take(&mut self.pending);
+        input.extend_from_slice(buf);
+        let mut rest = &input[..];
+        loop {
+            match std::str::from_utf8(rest) {
+                Ok(text) => {
+                    self.encode(text, false);
+                    break;
+                }
+                Err(err) => {
+                    let (valid, invalid) = rest.split_at(err.valid_up_to());
+                    self.encode(std::str::from_utf8(valid).expect("checked above"), false);
+                    match err.error_len() {
+                        Some(len) => {
+                            self.scratch.extend_from_slice(&self.replacement);
+                            rest = &invalid[len..];
+                        }
+                        None => {
+                            // The character may be finished by the next write.
+                            self.pending = invalid.to_vec();
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+        self.inner.write_all(&self.scratch)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::AnsiStrings;
+    use crate::Color::*;
+
+    #[test]
+    fn split_characters() {
+        let mut w = EncodingWriter::new(Vec::new(), encoding_rs::WINDOWS_1252);
+        let text = AnsiStrings([Blue.paint("naïve"), Red.bold().paint("€5")]).to_string();
+        for byte in text.as_bytes() {
+            w.write_all(&[*byte]).unwrap();
+        }
+        assert_eq!(
+            w.finish().unwrap(),
+            b"\x1b[34mna\xefve\x1b[1;31m\x805\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn replacements() {
+        let mut w = EncodingWriter::cp437(Vec::new()).with_replacement(b"<?>".as_ref());
+        w.write_all(b"a\xffb \xe2\x9c").unwrap();
+        w.write_all("\u{2717}".as_bytes()).unwrap();
+        w.write_all(b" \xc3").unwrap();
+        assert_eq!(w.finish().unwrap(), b"a<?>b <?><?> <?>");
+
+        let mut w = EncodingWriter::new(Vec::new(), encoding_rs::ISO_2022_JP);
+        w.write_all("日本".as_bytes()).unwrap();
+        assert_eq!(w.finish().unwrap(), b"\x1b$BF|K\\\x1b(B");
+    }
+}
@@ -0,0 +1,132 @@
+//! A C interface for painting and stripping text, so that tools written in
+//! other languages produce exactly the same escape codes as this crate.
+//!
+//! Styles are passed in the encoding of the [`bits`](crate::bits) module.
+//! Every string returned is allocated by this crate, and must be released
+//! with [`nu_ansi_string_free`]. To build a shared library, enable the `ffi`
+//! feature and build with `cargo rustc --release --features ffi --crate-type
+//! cdylib`.
+
+use crate::strip::strip_ansi_codes;
+use crate::{AnsiString, Style};
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::ptr;
+
+/// Copy `s` into a string owned by the caller, to be released with
+/// [`nu_ansi_string_free`].
+fn into_c_string(s: String) -> *mut c_char {
+    CString::new(s).map_or(ptr::null_mut(), CString::into_raw)
+}
+
+/// Paint the NUL terminated UTF-8 `text` with the style encoded in
+/// `style_bits` (see [`Style::to_bits`]). Invalid UTF-8 is replaced with
+/// `U+FFFD`, and bits which are not understood are ignored.
+///
+/// Returns a null pointer if `text` is null.
+///
+/// # Safety
+///
+/// `text` must be null, or point to a NUL terminated string which stays
+/// valid for the duration of the call.
+#[no_mangle]
+pub unsafe extern "C" fn nu_ansi_paint(style_bits: u64, text: *const c_char) -> *mut c_char {
+    if text.is_null() {
+        return ptr::null_mut();
+    }
+    let text = CStr::from_ptr(text).to_string_lossy();
+    let painted: AnsiString<'_> = Style::from_bits_truncate(style_bits).paint(&*text);
+    into_c_string(painted.to_string())
+}
+
+/// Paint the NUL terminated UTF-8 `text` as [`nu_ansi_paint`] does, with
+/// underlines in the color encoded in `underline_bits` (see
+/// [`Style::underline_color_bits`]), which `style_bits` has no room for.
+///
+/// Returns a null pointer if `text` is null.
+///
+/// # Safety
+///
+/// `text` must be null, or point to a NUL terminated string which stays
+/// valid for the duration of the call.
+#[no_mangle]
+pub unsafe extern "C" fn nu_ansi_paint_underlined(
+    style_bits: u64,
+    underline_bits: u64,
+    text: *const c_char,
+) -> *mut c_char {
+    if text.is_null() {
+        return ptr::null_mut();
+    }
+    let text = CStr::from_ptr(text).to_string_lossy();
+    let style = Style::from_bits_truncate(style_bits).with_underline_color_bits(underline_bits);
+    into_c_string(style.paint(&*text).to_string())
+}
+
+/// Remove every ANSI escape sequence from the NUL terminated UTF-8 `text`.
+///
+/// Returns a null pointer if `text` is null.
+///
+/// # Safety
+///
+/// `text` must be null, or point to a NUL terminated string which stays
+/// valid for the duration of the call.
+#[no_mangle]
+pub unsafe extern "C" fn nu_ansi_strip(text: *const c_char) -> *mut c_char {
+    if text.is_null() {
+        return ptr::null_mut();
+    }
+    let text = CStr::from_ptr(text).to_string_lossy();
+    into_c_string(strip_ansi_codes(&text).into_owned())
+}
+
+/// Release a string returned by this module. Null pointers are ignored.
+///
+/// # Safety
+///
+/// `s` must be null, or a string returned by a function of this module
+/// which has not been released yet.
+#[no_mangle]
+pub unsafe extern "C" fn nu_ansi_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Color::{Blue, Red};
+
+    /// Take ownership of a string returned by this module.
+    unsafe fn take(s: *mut c_char) -> String {
+        let owned = CStr::from_ptr(s).to_str().unwrap().to_owned();
+        nu_ansi_string_free(s);
+        owned
+    }
+
+    #[test]
+    fn paint_and_strip() {
+        let text = CString::new("alert").unwrap();
+        let painted = unsafe { take(nu_ansi_paint(Red.bold().to_bits(), text.as_ptr())) };
+        assert_eq!(painted, Red.bold().paint("alert").to_string());
+
+        let painted = CString::new(painted).unwrap();
+        assert_eq!(unsafe { take(nu_ansi_strip(painted.as_ptr())) }, "alert");
+
+        let style = Red.undercurl().underline_color(Blue);
+        let (bits, underline) = (style.to_bits(), style.underline_color_bits());
+        let painted = unsafe { take(nu_ansi_paint_underlined(bits, underline, text.as_ptr())) };
+        assert_eq!(painted, style.paint("alert").to_string());
+    }
+
+    #[test]
+    fn null_pointers() {
+        unsafe {
+            assert!(nu_ansi_paint(0, ptr::null()).is_null());
+            assert!(nu_ansi_paint_underlined(0, 0, ptr::null()).is_null());
+            assert!(nu_ansi_strip(ptr::null()).is_null());
+            nu_ansi_string_free(ptr::null_mut());
+        }
+    }
+}
@@ -1,4 +1,5 @@
-use crate::{rgb::Rgb, Color};
+use crate::width::clusters;
+use crate::{rgb::Rgb, AnsiStrings, Color, Style};
 
 /// Linear color gradient between two color stops
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -45,6 +46,12 @@ impl Gradient {
         Self::new(self.end, self.start)
     }
 
+    /// Paints each character of `text` in its color along this gradient.
+    /// See [`MultiGradient::paint_text`].
+    pub fn paint_text(&self, text: &str) -> AnsiStrings<'static> {
+        MultiGradient::from(*self).paint_text(text)
+    }
+
     /// Creates a string with the given `text` wrapped in ANSI escape codes that
     /// represent a color gradient.
     pub fn build(&self, text: &str, target: TargetGround) -> String {
@@ -64,7 +71,189 @@ impl Gradient {
     }
 }
 
+/// The color space in which a [`MultiGradient`] mixes its colors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GradientSpace {
+    /// Mix the red, green and blue channels separately, like [`Rgb::lerp`].
+    #[default]
+    Rgb,
+    /// Mix colors in the Oklab color space, so that the lightness changes
+    /// evenly, without the dull or dark middle of some RGB gradients.
+    OkLab,
+}
+
+/// A color gradient through any number of color stops.
+///
+/// # Examples
+///
+/// ```
+/// use nu_ansi_term::{GradientSpace, MultiGradient, Rgb};
+///
+/// let (red, green, blue) = (Rgb::new(255, 0, 0), Rgb::new(0, 255, 0), Rgb::new(0, 0, 255));
+/// let rainbow = MultiGradient::new([red, green, blue]);
+/// assert_eq!(rainbow.at(0.0), red);
+/// assert_eq!(rainbow.at(0.5), green);
+/// assert_eq!(rainbow.at(1.0), blue);
 ///
+/// let banner = rainbow.space(GradientSpace::OkLab).paint_text("nushell");
+/// assert_eq!(banner.iter().count(), 7);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct MultiGradient {
+    /// The stops, sorted by position.
+    stops: Vec<(f32, Rgb)>,
+    space: GradientSpace,
+}
+
+impl MultiGradient {
+    /// Creates a gradient through `colors`, spaced evenly from 0 to 1.
+    pub fn new(colors: impl IntoIterator<Item = Rgb>) -> Self {
+        let colors: Vec<Rgb> = colors.into_iter().collect();
+        let last = colors.len().saturating_sub(1).max(1) as f32;
+        Self::with_positions(
+            colors
+                .into_iter()
+                .enumerate()
+                .map(|(ix, color)| (ix as f32 / last, color)),
+        )
+    }
+
+    /// Creates a gradient through colors at the given positions, which run
+    /// from 0 to 1. The colors of the first and last stops extend to the
+    /// ends of the gradient.
+    pub fn with_positions(stops: impl IntoIterator<Item = (f32, Rgb)>) -> Self {
+        let mut stops: Vec<(f32, Rgb)> = stops.into_iter().collect();
+        stops.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+        Self {
+            stops,
+            space: GradientSpace::default(),
+        }
+    }
+
+    /// Mix the colors of this gradient in `space`.
+    pub fn space(mut self, space: GradientSpace) -> Self {
+        self.space = space;
+        self
+    }
+
+    /// Computes the [Rgb] color of this gradient at `t`, which runs from 0
+    /// to 1. A gradient without stops is black.
+    pub fn at(&self, t: f32) -> Rgb {
+        let after = self.stops.iter().position(|&(position, _)| position > t);
+        let (start, end) = match after {
+            Some(0) => return self.stops[0].1,
+            Some(ix) => (self.stops[ix - 1], self.stops[ix]),
+            None => return self.stops.last().map_or(Rgb::gray(0), |&(_, color)| color),
+        };
+        let t = (t - start.0) / (end.0 - start.0);
+        match self.space {
+            GradientSpace::Rgb => start.1.lerp(end.1, t),
+            GradientSpace::OkLab => {
+                let (a, b) = (oklab(start.1), oklab(end.1));
+                from_oklab([0, 1, 2].map(|i| a[i] + (b[i] - a[i]) * t.clamp(0.0, 1.0)))
+            }
+        }
+    }
+
+    /// Paints each character of `text` in its color along this gradient,
+    /// from the first stop for the first character to the last stop for the
+    /// last one. Characters are kept together with the combining marks and
+    /// joined characters which follow them, so that they are painted whole.
+    pub fn paint_text(&self, text: &str) -> AnsiStrings<'static> {
+        let clusters = clusters(text);
+        let last = clusters.len().saturating_sub(1).max(1) as f32;
+        clusters
+            .into_iter()
+            .enumerate()
+            .map(|(ix, cluster)| Color::from(self.at(ix as f32 / last)).paint(cluster.to_owned()))
+            .collect()
+    }
+}
+
+impl From<Gradient> for MultiGradient {
+    fn from(gradient: Gradient) -> Self {
+        Self::new([gradient.start, gradient.end])
+    }
+}
+
+/// The style of a heatmap cell holding `value`: its background is the color
+/// of `ramp` where `value` falls between `min` and `max`, and its foreground
+/// is black or white, whichever is easier to read on that background.
+///
+/// Values outside the range take the color of the nearest end of `ramp`. If
+/// the range is empty, or `value` is not a number, the start of `ramp` is
+/// used.
+///
+/// # Examples
+///
+/// ```
+/// use nu_ansi_term::{heatmap_style, Color, MultiGradient, Rgb};
+///
+/// let ramp = MultiGradient::new([Rgb::new(0, 0, 139), Rgb::new(255, 255, 0)]);
+/// let cold = heatmap_style(-5.0, 0.0, 100.0, &ramp);
+/// assert_eq!(cold, Color::Rgb(255, 255, 255).on(Color::Rgb(0, 0, 139)));
+/// let hot = heatmap_style(100.0, 0.0, 100.0, &ramp);
+/// assert_eq!(hot, Color::Rgb(0, 0, 0).on(Color::Rgb(255, 255, 0)));
+/// ```
+pub fn heatmap_style(value: f64, min: f64, max: f64, ramp: &MultiGradient) -> Style {
+    let t = if max > min {
+        ((value - min) / (max - min)).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    let background = ramp.at(if t.is_nan() { 0.0 } else { t as f32 });
+    Color::from(Rgb::best_foreground_on(background)).on(Color::from(background))
+}
+
+/// The channels of `color` in linear light, from 0 to 1.
+fn linear(color: Rgb) -> [f32; 3] {
+    [color.r, color.g, color.b].map(|c| {
+        let c = c as f32 / 255.0;
+        if c <= 0.04045 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    })
+}
+
+/// `color` in the Oklab color space.
+fn oklab(color: Rgb) -> [f32; 3] {
+    let [r, g, b] = linear(color);
+    let l = (0.412_221_46 * r + 0.536_332_55 * g + 0.051_445_995 * b).cbrt();
+    let m = (0.211_903_5 * r + 0.680_699_5 * g + 0.107_396_96 * b).cbrt();
+    let s = (0.088_302_46 * r + 0.281_718_85 * g + 0.629_978_7 * b).cbrt();
+    [
+        0.210_454_26 * l + 0.793_617_8 * m - 0.004_072_047 * s,
+        1.977_998_5 * l - 2.428_592_2 * m + 0.450_593_7 * s,
+        0.025_904_037 * l + 0.782_771_77 * m - 0.808_675_77 * s,
+    ]
+}
+
+/// The color at `lab` in the Oklab color space.
+fn from_oklab([lab_l, lab_a, lab_b]: [f32; 3]) -> Rgb {
+    let l = (lab_l + 0.396_337_78 * lab_a + 0.215_803_76 * lab_b).powi(3);
+    let m = (lab_l - 0.105_561_346 * lab_a - 0.063_854_17 * lab_b).powi(3);
+    let s = (lab_l - 0.089_484_18 * lab_a - 1.291_485_5 * lab_b).powi(3);
+    let [r, g, b] = [
+        4.076_741_7 * l - 3.307_711_6 * m + 0.230_969_94 * s,
+        -1.268_438 * l + 2.609_757_4 * m - 0.341_319_38 * s,
+        -0.004_196_086_3 * l - 0.703_418_6 * m + 1.707_614_7 * s,
+    ]
+    .map(|c| {
+        let c = c.clamp(0.0, 1.0);
+        let c = if c <= 0.003_130_8 {
+            c * 12.92
+        } else {
+            1.055 * c.powf(1.0 / 2.4) - 0.055
+        };
+        (c * 255.0).round() as u8
+    });
+    Rgb::new(r, g, b)
+}
+
+/// Creates a string with the given `text` wrapped in ANSI escape codes that
+/// apply both a `foreground` and a `background` color gradient.
 pub fn build_all_gradient_text(text: &str, foreground: Gradient, background: Gradient) -> String {
     let delta = 1.0 / text.len() as f32;
     let mut result = text.char_indices().fold(String::new(), |mut acc, (i, c)| {
@@ -112,3 +301,56 @@ pub trait ANSIColorCode {
     /// Get the ANSI color code associated with this item.
     fn ansi_color_code(&self, target: TargetGround) -> String;
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn stops() {
+        let (black, white) = (Rgb::gray(0), Rgb::gray(255));
+        let gradient = MultiGradient::with_positions([(0.75, white), (0.25, black)]);
+        assert_eq!(gradient.at(0.0), black);
+        assert_eq!(gradient.at(0.5), Rgb::gray(127));
+        assert_eq!(gradient.at(2.0), white);
+        assert_eq!(MultiGradient::new([]).at(0.5), black);
+        assert_eq!(MultiGradient::new([white]).at(0.5), white);
+
+        let even = gradient.space(GradientSpace::OkLab);
+        assert_eq!(even.at(0.25), black);
+        assert_eq!(even.at(0.75), white);
+        // The middle of red and green is a dull olive in RGB, but not in Oklab.
+        let red_green = MultiGradient::new([Rgb::new(255, 0, 0), Rgb::new(0, 255, 0)]);
+        assert_eq!(red_green.at(0.5), Rgb::new(127, 127, 0));
+        assert_eq!(
+            red_green.space(GradientSpace::OkLab).at(0.5),
+            Rgb::new(208, 168, 0)
+        );
+        for color in [
+            Rgb::new(255, 136, 0),
+            Rgb::new(1, 2, 3),
+            Rgb::new(92, 92, 255),
+        ] {
+            assert_eq!(from_oklab(oklab(color)), color);
+        }
+    }
+
+    #[test]
+    fn paints_whole_characters() {
+        let gradient = Gradient::new(Rgb::new(255, 0, 0), Rgb::new(0, 0, 255));
+        let painted = gradient.paint_text("e\u{301}👩\u{200d}💻!");
+        let segments: Vec<(String, Option<Color>)> = painted
+            .iter()
+            .map(|s| (s.content().to_string(), s.style_ref().coloring.fg))
+            .collect();
+        assert_eq!(
+            segments,
+            [
+                ("e\u{301}".to_string(), Some(Color::Rgb(255, 0, 0))),
+                ("👩\u{200d}💻".to_string(), Some(Color::Rgb(127, 0, 127))),
+                ("!".to_string(), Some(Color::Rgb(0, 0, 255))),
+            ]
+        );
+        assert_eq!(gradient.paint_text("").iter().count(), 0);
+    }
+}
@@ -0,0 +1,346 @@
+use crate::{AnsiStrings, Color, Overlay, Style, TerminalProfile};
+use std::ops::Range;
+
+/// Style the text of an input line with the styles a highlighter picked for
+/// ranges of it, as a line editor does after every keystroke.
+///
+/// `spans` are byte ranges into `line`, in order. Text which no span covers
+/// is left unstyled, the part of a span overlapping the span before it is
+/// ignored, and ranges are clamped to the line and to character boundaries,
+/// so a highlighter which is a keystroke behind cannot cause a panic.
+/// Adjacent runs of text with equal styles are merged into one segment.
+///
+/// The segments borrow `line` rather than copying it, and are collected into
+/// a sequence allocated once, so this is cheap enough to run on every
+/// keystroke.
+///
+/// # Examples
+///
+/// ```
+/// use nu_ansi_term::highlight_input;
+/// use nu_ansi_term::Color::{Green, Purple};
+///
+/// let line = "ls | where size > 10kb";
+/// let spans = [(0..2, Green.bold()), (5..10, Green.bold()), (18..22, Purple.normal())];
+/// let highlighted = highlight_input(line, &spans);
+///
+/// assert_eq!(highlighted.len(), 5);
+/// assert_eq!(
+///     highlighted.to_string(),
+///     "\x1b[1;32mls\x1b[0m | \x1b[1;32mwhere\x1b[0m size > \x1b[35m10kb\x1b[0m"
+/// );
+/// ```
+pub fn highlight_input<'a>(line: &'a str, spans: &[(Range<usize>, Style)]) -> AnsiStrings<'a> {
+    let mut strings = AnsiStrings::with_capacity(2 * spans.len() + 1);
+    highlight_input_into(line, spans, &mut strings);
+    strings
+}
+
+/// [`highlight_input`], replacing the segments of `strings` rather than
+/// allocating a new sequence, so that a line editor can reuse one buffer
+/// from keystroke to keystroke.
+///
+/// # Examples
+///
+/// ```
+/// use nu_ansi_term::{highlight_input_into, AnsiStrings};
+/// use nu_ansi_term::Color::Green;
+///
+/// let mut highlighted = AnsiStrings::with_capacity(8);
+/// highlight_input_into("ls", &[(0..2, Green.bold())], &mut highlighted);
+/// highlight_input_into("ls -l", &[(0..2, Green.bold())], &mut highlighted);
+/// assert_eq!(highlighted.to_string(), "\x1b[1;32mls\x1b[0m -l");
+/// ```
+pub fn highlight_input_into<'a>(
+    line: &'a str,
+    spans: &[(Range<usize>, Style)],
+    strings: &mut AnsiStrings<'a>,
+) {
+    strings.clear();
+    let mut run = (0..0, Style::new());
+    let mut extend = |strings: &mut AnsiStrings<'a>, range: Range<usize>, style: Style| {
+        if range.is_empty() {
+            return;
+        }
+        if run.1 == style || run.0.is_empty() {
+            run = (run.0.start..range.end, style);
+        } else {
+            strings.push(run.1.paint(&line[run.0.clone()]));
+            run = (range, style);
+        }
+    };
+
+    let mut pos = 0;
+    for (range, style) in spans {
+        let start = char_boundary(line, range.start.max(pos));
+        let end = char_boundary(line, range.end).max(start);
+        extend(strings, pos..start, Style::new());
+        extend(strings, start..end, *style);
+        pos = end;
+    }
+    extend(strings, pos..line.len(), Style::new());
+    if !run.0.is_empty() {
+        strings.push(run.1.paint(&line[run.0]));
+    }
+}
+
+/// Overlays styling the bracket at the `cursor` (a byte offset into `line`)
+/// and the bracket matching it, for laying over a highlighted input line
+/// with [`StyleLayering::apply`](crate::StyleLayering::apply).
+///
+/// As in most editors, the bracket under the cursor is matched if there is
+/// one, and otherwise the bracket just before it, so that a bracket is
+/// matched as soon as it is typed. `pairs` are the opening and closing
+/// characters of each kind of bracket, which must differ; brackets of other
+/// kinds are ignored when matching. There are no overlays if the cursor is
+/// not next to a bracket, or if the bracket is unmatched.
+///
+/// # Examples
+///
+/// ```
+/// use nu_ansi_term::{bracket_overlays, StyleLayering};
+/// use nu_ansi_term::Color::{Blue, Cyan};
+///
+/// let line = "each { |x| ($x + 1) }";
+/// let pairs = [('(', ')'), ('{', '}'), ('[', ']')];
+/// let overlays = bracket_overlays(line, 19, &pairs, Cyan.bold());
+/// let ranges: Vec<_> = overlays.iter().map(|overlay| overlay.range()).collect();
+/// assert_eq!(ranges, [18..19, 11..12]);
+///
+/// let highlighted = [Blue.paint(&line[..4]), line[4..].into()];
+/// let matched = StyleLayering::Merge.apply(&highlighted, &overlays);
+/// assert_eq!(matched.iter().nth(2).unwrap().to_string(), "\x1b[1;36m(\x1b[0m");
+/// ```
+pub fn bracket_overlays(
+    line: &str,
+    cursor: usize,
+    pairs: &[(char, char)],
+    style: Style,
+) -> Vec<Overlay> {
+    let cursor = char_boundary(line, cursor);
+    let at_cursor = line[cursor..].chars().next().map(|c| (cursor, c));
+    let before_cursor = line[..cursor].char_indices().next_back();
+    let bracket = [at_cursor, before_cursor]
+        .into_iter()
+        .flatten()
+        .find_map(|(pos, c)| {
+            pairs
+                .iter()
+                .find(|(open, close)| open != close && (c == *open || c == *close))
+                .map(|&pair| (pos, c, pair))
+        });
+    let (pos, c, (open, close)) = match bracket {
+        Some(bracket) => bracket,
+        None => return Vec::new(),
+    };
+
+    let step = |depth: &mut usize, other: char| {
+        if other == c {
+            *depth += 1;
+        } else if other == open || other == close {
+            *depth -= 1;
+        }
+        *depth == 0
+    };
+    let mut depth = 0;
+    let matching = if c == open {
+        line[pos..]
+            .char_indices()
+            .find(|&(_, other)| step(&mut depth, other))
+            .map(|(offset, _)| pos + offset)
+    } else {
+        line[..pos + c.len_utf8()]
+            .char_indices()
+            .rev()
+            .find(|&(_, other)| step(&mut depth, other))
+            .map(|(offset, _)| offset)
+    };
+    match matching {
+        Some(matching) if matching != pos => {
+            let other = if c == open { close } else { open };
+            vec![
+                Overlay::new(pos..pos + c.len_utf8(), style),
+                Overlay::new(matching..matching + other.len_utf8(), style),
+            ]
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// The style of inline suggestions (ghost text), such as the rest of a line
+/// from history shown after the cursor, which the user can accept or type
+/// over. See [`ghost_text`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GhostText {
+    style: Style,
+    fallback: Style,
+}
+
+impl Default for GhostText {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GhostText {
+    /// Show suggestions dimmed and in italics, or in dark gray on terminals
+    /// which do not show dimmed text as faint (see [`TerminalProfile::dim`]).
+    pub const fn new() -> Self {
+        Self {
+            style: Style::new().dimmed().italic(),
+            fallback: Color::DarkGray.normal().italic(),
+        }
+    }
+
+    /// Show suggestions in `style` on terminals which show dimmed text.
+    pub const fn style(mut self, style: Style) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Show suggestions in `style` on terminals which do not show dimmed
+    /// text as faint.
+    pub const fn fallback(mut self, style: Style) -> Self {
+        self.fallback = style;
+        self
+    }
+
+    /// The style suggestions are shown in on terminals with `profile`.
+    pub const fn style_for(&self, profile: &TerminalProfile) -> Style {
+        if profile.dim {
+            self.style
+        } else {
+            self.fallback
+        }
+    }
+
+    /// Paint a suggestion, in a style resolved for the terminal it is
+    /// written to (see [`AnsiStrings::push_lazy`]).
+    pub fn paint<'a>(self, suggestion: &'a str) -> AnsiStrings<'a> {
+        let mut strings = AnsiStrings::with_capacity(1);
+        self.append(&mut strings, suggestion);
+        strings
+    }
+
+    /// Paint a suggestion at the end of `line`, and return the column the
+    /// cursor belongs in: the width of the line before the suggestion, which
+    /// is not part of the input.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::{highlight_input, GhostText, TerminalProfile};
+    /// use nu_ansi_term::Color::Green;
+    ///
+    /// let mut line = highlight_input("git ch", &[(0..3, Green.normal())]);
+    /// let cursor = GhostText::new().append(&mut line, "eckout main");
+    /// assert_eq!(cursor, 6);
+    /// assert_eq!(line.width(), 17);
+    ///
+    /// let console = TerminalProfile::new().without_dim();
+    /// assert_eq!(
+    ///     line.resolved(&console).to_string(),
+    ///     "\x1b[32mgit\x1b[0m ch\x1b[3;90meckout main\x1b[0m"
+    /// );
+    /// ```
+    pub fn append<'a>(self, line: &mut AnsiStrings<'a>, suggestion: &'a str) -> usize {
+        let cursor = line.width();
+        line.push_lazy(suggestion, move |profile| self.style_for(profile));
+        cursor
+    }
+}
+
+/// Paint an inline suggestion in the default style of [`GhostText`]:
+/// dimmed and in italics, or in dark gray where dimmed text is not faint.
+///
+/// # Examples
+///
+/// ```
+/// use nu_ansi_term::ghost_text;
+///
+/// assert_eq!(ghost_text("eckout main").to_string(), "\x1b[2;3meckout main\x1b[0m");
+/// ```
+pub fn ghost_text(suggestion: &str) -> AnsiStrings<'_> {
+    GhostText::new().paint(suggestion)
+}
+
+/// The last character boundary of `line` at or before `index`.
+fn char_boundary(line: &str, index: usize) -> usize {
+    let mut index = index.min(line.len());
+    while !line.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::unstyle;
+    use crate::Color::*;
+
+    #[test]
+    fn merges_runs_and_survives_stale_spans() {
+        let line = "héllo wörld";
+        let highlighted = highlight_input(
+            line,
+            &[
+                (0..2, Red.normal()),
+                (1..6, Red.normal()),
+                (6..7, Style::new()),
+                (9..40, Blue.normal()),
+            ],
+        );
+        let styles: Vec<_> = highlighted.iter().map(|s| *s.style_ref()).collect();
+        assert_eq!(styles, [Red.normal(), Style::new(), Blue.normal()]);
+        assert_eq!(unstyle(&highlighted), line);
+        assert_eq!(highlight_input("", &[(0..3, Red.normal())]).len(), 0);
+    }
+
+    #[test]
+    fn reuses_a_borrowed_buffer() {
+        let previous = [Red.paint("stale"), Blue.paint("segments")];
+        let mut highlighted = AnsiStrings::new(&previous);
+        highlight_input_into("cd ~", &[(0..2, Green.normal())], &mut highlighted);
+        assert_eq!(highlighted.len(), 2);
+        assert_eq!(highlighted.to_string(), "\x1b[32mcd\x1b[0m ~");
+    }
+
+    #[test]
+    fn ghost_text_falls_back_without_dim() {
+        let ghost = GhostText::new().fallback(Blue.normal());
+        let mut line = highlight_input("日本", &[]);
+        assert_eq!(ghost.append(&mut line, "語"), 4);
+        let styles = |profile: &TerminalProfile| -> Vec<Style> {
+            line.resolved(profile)
+                .iter()
+                .map(|s| *s.style_ref())
+                .collect()
+        };
+        assert_eq!(
+            styles(&TerminalProfile::new()),
+            [Style::new(), Style::new().dimmed().italic()]
+        );
+        assert_eq!(
+            styles(&TerminalProfile::ascii().without_dim()),
+            [Style::new(), Blue.normal()]
+        );
+    }
+
+    #[test]
+    fn matches_nested_brackets_of_the_same_kind() {
+        let pairs = [('(', ')'), ('[', ']')];
+        let ranges = |line, cursor| -> Vec<_> {
+            bracket_overlays(line, cursor, &pairs, Red.normal())
+                .iter()
+                .map(Overlay::range)
+                .collect()
+        };
+        assert_eq!(ranges("(a [b) (c)]", 0), [0..1, 5..6]);
+        assert_eq!(ranges("(a [b) (c)]", 11), [10..11, 3..4]);
+        assert_eq!(ranges("f(é(x))", 8), [7..8, 1..2]);
+        assert_eq!(ranges("f(é(x))", 1), [1..2, 7..8]);
+        assert!(ranges("(unclosed", 0).is_empty());
+        assert!(ranges("no brackets", 3).is_empty());
+    }
+}
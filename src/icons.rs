@@ -0,0 +1,209 @@
+use crate::Color::{Blue, Cyan, Green, Purple, Red, Yellow};
+use crate::{AnsiString, Color, Style, TerminalProfile};
+use std::path::Path;
+
+/// An icon for decorating prompts and listings, with a glyph for terminals
+/// with Nerd Fonts and fallbacks for those without.
+///
+/// # Examples
+///
+/// ```
+/// use nu_ansi_term::{Icon, TerminalProfile};
+///
+/// let icon = Icon::for_file("src/main.rs");
+/// assert_eq!(icon.glyph(&TerminalProfile::new()), "\u{e7a8}");
+/// assert_eq!(icon.glyph(&TerminalProfile::new().without_nerd_fonts()), "🦀");
+/// assert_eq!(icon.glyph(&TerminalProfile::ascii()), "*");
+///
+/// let branch = Icon::named("git_branch").unwrap();
+/// println!("{} main", branch.paint(&TerminalProfile::detect()));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Icon {
+    /// The glyph shown with Nerd Fonts.
+    pub nerd: &'static str,
+    /// The glyph shown without Nerd Fonts, if the terminal supports unicode.
+    pub unicode: &'static str,
+    /// The glyph shown if the terminal only supports ASCII.
+    pub ascii: &'static str,
+    /// The style the glyph is painted in.
+    pub style: Style,
+}
+
+impl Icon {
+    /// An icon made of the given glyphs, painted in `style`.
+    pub const fn new(
+        nerd: &'static str,
+        unicode: &'static str,
+        ascii: &'static str,
+        style: Style,
+    ) -> Self {
+        Self {
+            nerd,
+            unicode,
+            ascii,
+            style,
+        }
+    }
+
+    /// The icon of the catalog with the semantic `name`, such as `folder`,
+    /// `git_branch` or `warning` (see [`names`](Self::names)).
+    pub fn named(name: &str) -> Option<Icon> {
+        CATALOG
+            .iter()
+            .find(|(entry, _)| *entry == name)
+            .map(|&(_, icon)| icon)
+    }
+
+    /// The semantic names of the icons of the catalog.
+    pub fn names() -> impl Iterator<Item = &'static str> {
+        CATALOG.iter().map(|&(name, _)| name)
+    }
+
+    /// The icon of the type of the file at `path`, judged by its name or
+    /// its extension, or the `file` icon if the type is not known.
+    pub fn for_file(path: impl AsRef<Path>) -> Icon {
+        let path = path.as_ref();
+        let by_name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| FILE_NAMES.iter().find(|(entry, _)| *entry == name));
+        let by_extension = || {
+            let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+            EXTENSIONS.iter().find(|(entry, _)| *entry == extension)
+        };
+        by_name
+            .or_else(by_extension)
+            .and_then(|&(_, name)| Icon::named(name))
+            .unwrap_or(FILE)
+    }
+
+    /// The glyph to show on a terminal with the capabilities of `profile`.
+    pub fn glyph(&self, profile: &TerminalProfile) -> &'static str {
+        if !profile.unicode {
+            self.ascii
+        } else if profile.nerd_fonts {
+            self.nerd
+        } else {
+            self.unicode
+        }
+    }
+
+    /// The glyph to show on a terminal with the capabilities of `profile`,
+    /// painted in the style of this icon, adapted to the colors of the
+    /// profile.
+    pub fn paint(&self, profile: &TerminalProfile) -> AnsiString<'static> {
+        self.style
+            .adapt_to(profile.colors)
+            .paint(self.glyph(profile))
+    }
+
+    /// A copy of this icon painted in `style`.
+    pub const fn style(mut self, style: Style) -> Self {
+        self.style = style;
+        self
+    }
+}
+
+const fn icon(
+    nerd: &'static str,
+    unicode: &'static str,
+    ascii: &'static str,
+    color: Color,
+) -> Icon {
+    Icon::new(nerd, unicode, ascii, color.normal())
+}
+
+const FILE: Icon = Icon::new("\u{f15b}", "📄", "-", Style::new());
+
+/// The semantic names of the icons of the catalog, with their icons.
+const CATALOG: &[(&str, Icon)] = &[
+    ("file", FILE),
+    ("folder", icon("\u{f115}", "📁", "/", Blue)),
+    ("symlink", icon("\u{f0c1}", "🔗", "@", Cyan)),
+    ("executable", icon("\u{f489}", "⚙", "*", Green)),
+    ("home", icon("\u{f015}", "🏠", "~", Cyan)),
+    ("git", icon("\u{f1d3}", "🔀", "git", Red)),
+    ("git_branch", icon("\u{e0a0}", "⎇", "git:", Purple)),
+    ("lock", icon("\u{f023}", "🔒", "#", Yellow)),
+    ("clock", icon("\u{f017}", "⏱", "@", Cyan)),
+    ("success", icon("\u{f00c}", "✔", "+", Green)),
+    ("error", icon("\u{f00d}", "✘", "x", Red)),
+    ("warning", icon("\u{f071}", "⚠", "!", Yellow)),
+    ("info", icon("\u{f05a}", "ℹ", "i", Blue)),
+    ("archive", icon("\u{f410}", "📦", "=", Red)),
+    ("image", icon("\u{f1c5}", "🖼", "%", Purple)),
+    ("docker", icon("\u{f308}", "🐳", "*", Blue)),
+    ("markdown", icon("\u{e73e}", "📝", "*", Blue)),
+    ("config", icon("\u{e615}", "⚙", "*", Yellow)),
+    ("rust", icon("\u{e7a8}", "🦀", "*", Red)),
+    ("python", icon("\u{e73c}", "🐍", "*", Yellow)),
+    ("javascript", icon("\u{e74e}", "📜", "*", Yellow)),
+    ("typescript", icon("\u{e628}", "📜", "*", Blue)),
+    ("shell", icon("\u{f489}", "🐚", "$", Green)),
+];
+
+/// File names which have an icon of their own, with its name.
+const FILE_NAMES: &[(&str, &str)] = &[
+    ("Cargo.toml", "rust"),
+    ("Cargo.lock", "rust"),
+    ("Dockerfile", "docker"),
+    (".gitignore", "git"),
+    (".gitmodules", "git"),
+];
+
+/// File extensions (in lower case) with the names of their icons.
+const EXTENSIONS: &[(&str, &str)] = &[
+    ("rs", "rust"),
+    ("py", "python"),
+    ("js", "javascript"),
+    ("mjs", "javascript"),
+    ("ts", "typescript"),
+    ("md", "markdown"),
+    ("json", "config"),
+    ("toml", "config"),
+    ("yaml", "config"),
+    ("yml", "config"),
+    ("sh", "shell"),
+    ("nu", "shell"),
+    ("zip", "archive"),
+    ("tar", "archive"),
+    ("gz", "archive"),
+    ("png", "image"),
+    ("jpg", "image"),
+    ("jpeg", "image"),
+    ("gif", "image"),
+    ("svg", "image"),
+    ("lock", "lock"),
+];
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn catalog_is_consistent() {
+        let names: Vec<&str> = Icon::names().collect();
+        assert!(names
+            .iter()
+            .enumerate()
+            .all(|(ix, name)| !names[..ix].contains(name)));
+        for (_, name) in FILE_NAMES.iter().chain(EXTENSIONS) {
+            assert!(Icon::named(name).is_some(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn file_types() {
+        assert_eq!(Icon::for_file("Cargo.toml"), Icon::named("rust").unwrap());
+        assert_eq!(Icon::for_file("photo.JPG"), Icon::named("image").unwrap());
+        assert_eq!(Icon::for_file("README"), FILE);
+        assert_eq!(
+            Icon::named("error")
+                .unwrap()
+                .paint(&TerminalProfile::ascii())
+                .to_string(),
+            Red.paint("x").to_string()
+        );
+    }
+}
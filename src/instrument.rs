@@ -0,0 +1,204 @@
+//! A hook called for every escape sequence written, for gathering metrics
+//! about the overhead of escape codes, or finding where needless resets
+//! come from.
+//!
+//! Nothing is measured until a hook is set with [`set_hook`]; without one,
+//! writing only pays for checking a flag.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::sync::atomic::{AtomicUsize, Ordering};
+//! use std::sync::Arc;
+//! use nu_ansi_term::instrument::{self, SequenceKind};
+//! use nu_ansi_term::AnsiStrings;
+//! use nu_ansi_term::Color::{Blue, Red};
+//!
+//! let resets = Arc::new(AtomicUsize::new(0));
+//! let counter = Arc::clone(&resets);
+//! instrument::set_hook(move |event| {
+//!     if event.kind == SequenceKind::Reset {
+//!         counter.fetch_add(1, Ordering::Relaxed);
+//!     }
+//! });
+//!
+//! AnsiStrings([Red.bold().paint("a"), Blue.paint("b")]).to_string();
+//! instrument::clear_hook();
+//! assert_eq!(resets.load(Ordering::Relaxed), 2);
+//! ```
+
+use crate::utils::leaked_once;
+use std::cell::Cell;
+use std::fmt;
+use std::ptr;
+use std::sync::atomic::{AtomicBool, AtomicPtr, Ordering};
+use std::sync::{Arc, RwLock};
+
+/// The kind of an escape sequence.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SequenceKind {
+    /// A code setting the style of the text after it (SGR).
+    Sgr,
+    /// A code resetting the style back to the terminal's default.
+    Reset,
+    /// The start or end of a hyperlink (OSC 8).
+    Hyperlink,
+    /// A change of the window title (OSC 2).
+    Title,
+    /// A copy to the clipboard (OSC 52).
+    Clipboard,
+    /// A desktop notification (OSC 9 or 777).
+    Notification,
+    /// A report of the working directory (OSC 7).
+    Cwd,
+}
+
+/// An escape sequence which has been written.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SequenceEvent {
+    /// What the sequence does.
+    pub kind: SequenceKind,
+    /// The number of bytes written for the sequence.
+    pub len: usize,
+    /// The index of the segment of the sequence being written which the
+    /// escape sequence belongs to, if it was written by a sequence of
+    /// strings. Final resets belong to the last segment.
+    pub segment: Option<usize>,
+}
+
+type Hook = Arc<dyn Fn(&SequenceEvent) + Send + Sync>;
+
+/// Whether a hook is set, so that writing does not take the lock otherwise.
+static HOOKED: AtomicBool = AtomicBool::new(false);
+static HOOK: AtomicPtr<RwLock<Option<Hook>>> = AtomicPtr::new(ptr::null_mut());
+
+thread_local! {
+    /// The index of the segment being written on this thread, if any.
+    static SEGMENT: Cell<Option<usize>> = const { Cell::new(None) };
+}
+
+fn hook_slot() -> &'static RwLock<Option<Hook>> {
+    leaked_once(&HOOK, || RwLock::new(None))
+}
+
+/// Call `hook` for every escape sequence written from now on, by any
+/// thread, replacing the hook set before.
+pub fn set_hook(hook: impl Fn(&SequenceEvent) + Send + Sync + 'static) {
+    *hook_slot().write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(hook));
+    HOOKED.store(true, Ordering::Release);
+}
+
+/// Stop calling the hook set with [`set_hook`].
+pub fn clear_hook() {
+    HOOKED.store(false, Ordering::Release);
+    *hook_slot().write().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+/// Whether a hook is set. Lengths only need to be measured if it is.
+pub(crate) fn is_hooked() -> bool {
+    HOOKED.load(Ordering::Acquire)
+}
+
+/// Call the hook, if any, for a sequence of `kind` which is `len` bytes
+/// long.
+pub(crate) fn emit(kind: SequenceKind, len: usize) {
+    if !is_hooked() {
+        return;
+    }
+    let hook = hook_slot()
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone();
+    if let Some(hook) = hook {
+        hook(&SequenceEvent {
+            kind,
+            len,
+            segment: SEGMENT.with(Cell::get),
+        });
+    }
+}
+
+/// Call `write` with the escape sequences it writes attributed to the
+/// segment at `index`.
+pub(crate) fn in_segment<R>(index: usize, write: impl FnOnce() -> R) -> R {
+    if !is_hooked() {
+        return write();
+    }
+    /// Restores the outer segment, even if `write` panics.
+    struct Guard(Option<usize>);
+
+    impl Drop for Guard {
+        fn drop(&mut self) {
+            let _ = SEGMENT.try_with(|segment| segment.set(self.0));
+        }
+    }
+
+    let _guard = Guard(SEGMENT.with(|segment| segment.replace(Some(index))));
+    write()
+}
+
+/// A writer which only counts the bytes written to it.
+#[derive(Default)]
+pub(crate) struct ByteCount(pub(crate) usize);
+
+impl fmt::Write for ByteCount {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0 += s.len();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Color::*;
+    use crate::{AnsiString, AnsiStrings};
+    use std::sync::Mutex;
+    use std::thread;
+
+    #[test]
+    fn events_add_up_to_the_escape_codes() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&events);
+        // Other tests write from their own threads at the same time.
+        let this_thread = thread::current().id();
+        set_hook(move |event| {
+            if thread::current().id() == this_thread {
+                recorded.lock().unwrap().push(*event);
+            }
+        });
+        let strings = AnsiStrings([
+            AnsiString::title("t\x07"),
+            Red.bold().paint("a"),
+            Blue.paint("b").hyperlink("https://example.com"),
+        ]);
+        let rendered = strings.to_string();
+        let lone = Green.paint("c").to_string();
+        let panicked = std::panic::catch_unwind(|| in_segment(7, || panic!("write failed")));
+        assert!(panicked.is_err());
+        assert_eq!(SEGMENT.with(Cell::get), None);
+        clear_hook();
+        let _ = Green.paint("unobserved").to_string();
+
+        let events = events.lock().unwrap();
+        let summary: Vec<_> = events.iter().map(|e| (e.kind, e.segment)).collect();
+        assert_eq!(
+            summary,
+            [
+                (SequenceKind::Title, Some(0)),
+                (SequenceKind::Sgr, Some(1)),
+                (SequenceKind::Reset, Some(2)),
+                (SequenceKind::Sgr, Some(2)),
+                (SequenceKind::Hyperlink, Some(2)),
+                (SequenceKind::Hyperlink, Some(2)),
+                (SequenceKind::Reset, Some(2)),
+                (SequenceKind::Sgr, None),
+                (SequenceKind::Reset, None),
+            ]
+        );
+        assert!(!rendered.contains('\x07'));
+        // Everything but the visible text is written by escape sequences.
+        let escapes = rendered.len() + lone.len() - "abc".len();
+        assert_eq!(events.iter().map(|e| e.len).sum::<usize>(), escapes);
+    }
+}
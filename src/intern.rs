@@ -0,0 +1,143 @@
+use crate::write::Content;
+use crate::{AnsiGenericString, Style};
+use std::collections::HashSet;
+use std::hash::Hash;
+use std::sync::Arc;
+
+/// A store of unique content, which hands out shared references to it, so
+/// that documents with many identical segments (table borders, padding,
+/// repeated values, …) only keep one copy of each.
+///
+/// See [`AnsiGenericStrings::interned`](crate::AnsiGenericStrings::interned)
+/// for interning a whole document.
+///
+/// # Examples
+///
+/// ```
+/// use nu_ansi_term::{AnsiStrings, ContentInterner, Style};
+///
+/// let mut interner = ContentInterner::new();
+/// let border = Style::new().dimmed();
+/// let row: AnsiStrings = (0..100)
+///     .map(|_| interner.paint(border, "│"))
+///     .collect();
+/// assert_eq!(row.to_string().matches('│').count(), 100);
+/// assert_eq!(interner.len(), 1);
+/// ```
+#[derive(Debug)]
+pub struct ContentInterner<S: ?Sized + Eq + Hash = str> {
+    contents: HashSet<Arc<S>>,
+}
+
+impl<S: ?Sized + Eq + Hash> Default for ContentInterner<S> {
+    fn default() -> Self {
+        Self {
+            contents: HashSet::new(),
+        }
+    }
+}
+
+impl<S: ?Sized + Eq + Hash> ContentInterner<S> {
+    /// Create an empty interner.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of unique contents stored.
+    pub fn len(&self) -> usize {
+        self.contents.len()
+    }
+
+    /// Whether no content is stored.
+    pub fn is_empty(&self) -> bool {
+        self.contents.is_empty()
+    }
+
+    /// Total size in bytes of the unique contents stored.
+    pub fn content_size(&self) -> usize {
+        self.contents
+            .iter()
+            .map(|content| std::mem::size_of_val(&**content))
+            .sum()
+    }
+
+    /// A shared copy of `content`, stored if it was not already.
+    pub fn intern(&mut self, content: &S) -> Arc<S>
+    where
+        for<'s> Arc<S>: From<&'s S>,
+    {
+        if let Some(shared) = self.contents.get(content) {
+            return Arc::clone(shared);
+        }
+        let shared: Arc<S> = Arc::from(content);
+        self.contents.insert(Arc::clone(&shared));
+        shared
+    }
+
+    /// Paint a shared copy of `content` in `style`.
+    pub fn paint(&mut self, style: Style, content: &S) -> AnsiGenericString<'static, S>
+    where
+        S: ToOwned,
+        for<'s> Arc<S>: From<&'s S>,
+    {
+        style.paint(Content::Shared(self.intern(content)))
+    }
+}
+
+impl ContentInterner<str> {
+    /// A shared copy of `content`, with formatting arguments rendered and
+    /// nested strings interned in turn.
+    pub(crate) fn intern_content(&mut self, content: &Content<'_, str>) -> Content<'static, str> {
+        match content {
+            Content::FmtArgs(args) => Content::Shared(self.intern(&args.to_string())),
+            Content::StrLike(s) => Content::Shared(self.intern(s)),
+            Content::Shared(s) => Content::Shared(self.intern(s)),
+            Content::GenericStrings(strings) => Content::GenericStrings(strings.interned(self)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::AnsiStrings;
+    use crate::Color::*;
+
+    #[test]
+    fn repeated_cells_are_shared() {
+        let cells: Vec<String> = (0..1000).map(|n| format!("{:>5}", n % 10)).collect();
+        let table: AnsiStrings = cells.iter().map(|cell| Red.paint(cell.as_str())).collect();
+
+        let mut interner = ContentInterner::new();
+        let interned = table.interned(&mut interner);
+        assert_eq!(interned.to_string(), table.to_string());
+        assert_eq!(interner.len(), 10);
+        assert_eq!(interner.content_size(), 50);
+
+        let first = interned.iter().next().unwrap().content();
+        let eleventh = interned.iter().nth(10).unwrap().content();
+        match (first, eleventh) {
+            (Content::Shared(a), Content::Shared(b)) => assert!(Arc::ptr_eq(a, b)),
+            other => panic!("expected shared content, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn links_and_nested_strings_are_interned() {
+        let mut interner = ContentInterner::new();
+        let interned: AnsiStrings<'static> = {
+            let url = String::from("https://example.com");
+            let nested = AnsiStrings([Blue.paint("in"), "ner".into()]);
+            AnsiStrings([
+                Red.paint(format_args!("{}", 42)).hyperlink(url.as_str()),
+                Green.paint(nested),
+            ])
+            .interned(&mut interner)
+        };
+        assert!(interned.to_string().contains("https://example.com"));
+        assert_eq!(interner.len(), 4);
+
+        let bytes: ContentInterner<[u8]> = ContentInterner::new();
+        assert!(bytes.is_empty());
+    }
+}
@@ -0,0 +1,302 @@
+//! Conversions between the styles and colors of this crate and those of
+//! other terminal styling crates, each behind a feature of the same name as
+//! the crate:
+//!
+//! - `anstyle`: [`anstyle::Style`] and [`anstyle::Color`], as used by clap.
+//! - `crossterm`: [`crossterm::style::ContentStyle`] and
+//!   [`crossterm::style::Color`].
+//!
+//! Conversions are lossy where the other crate has nothing equivalent: the
+//! formats of a [`Style`](crate::Style) which the other crate does not
+//! support are dropped, as are explicitly cleared formats and
+//! [`reset_before_style`](crate::Style::reset_before_style). Underlines the
+//! other crate has and this one does not (dotted, dashed) become plain
+//! underlines.
+
+/// Pairs of formats of this crate and of another one: either equivalents,
+/// for converting in both directions, or the closest format of this crate to
+/// one it does not have, for converting from the other crate.
+macro_rules! format_pairs {
+    ($name:ident: $other:ty = [$(($ours:ident, $theirs:expr)),* $(,)?]) => {
+        const $name: &[(crate::FormatFlags, $other)] = &[
+            $((crate::FormatFlags::$ours, $theirs),)*
+        ];
+    };
+}
+
+#[cfg(feature = "anstyle")]
+mod anstyle_impls {
+    use crate::{Color, Style};
+    use anstyle::{Ansi256Color, AnsiColor, Effects, RgbColor};
+
+    format_pairs!(EFFECTS: Effects = [
+        (BOLD, Effects::BOLD),
+        (DIMMED, Effects::DIMMED),
+        (ITALIC, Effects::ITALIC),
+        (UNDERLINE, Effects::UNDERLINE),
+        (DOUBLE_UNDERLINE, Effects::DOUBLE_UNDERLINE),
+        (UNDERCURL, Effects::CURLY_UNDERLINE),
+        (BLINK, Effects::BLINK),
+        (REVERSE, Effects::INVERT),
+        (HIDDEN, Effects::HIDDEN),
+        (STRIKETHROUGH, Effects::STRIKETHROUGH),
+    ]);
+
+    format_pairs!(CLOSEST_EFFECTS: Effects = [
+        (UNDERLINE, Effects::DOTTED_UNDERLINE),
+        (UNDERLINE, Effects::DASHED_UNDERLINE),
+    ]);
+
+    /// [`Color::Default`] has no equivalent, and becomes `None`.
+    impl From<Color> for Option<anstyle::Color> {
+        fn from(color: Color) -> Self {
+            let ansi = match color {
+                Color::Black => AnsiColor::Black,
+                Color::DarkGray => AnsiColor::BrightBlack,
+                Color::Red => AnsiColor::Red,
+                Color::LightRed => AnsiColor::BrightRed,
+                Color::Green => AnsiColor::Green,
+                Color::LightGreen => AnsiColor::BrightGreen,
+                Color::Yellow => AnsiColor::Yellow,
+                Color::LightYellow => AnsiColor::BrightYellow,
+                Color::Blue => AnsiColor::Blue,
+                Color::LightBlue => AnsiColor::BrightBlue,
+                Color::Purple | Color::Magenta => AnsiColor::Magenta,
+                Color::LightPurple | Color::LightMagenta => AnsiColor::BrightMagenta,
+                Color::Cyan => AnsiColor::Cyan,
+                Color::LightCyan => AnsiColor::BrightCyan,
+                Color::White => AnsiColor::White,
+                Color::LightGray => AnsiColor::BrightWhite,
+                Color::Fixed(n) => return Some(Ansi256Color(n).into()),
+                Color::Rgb(r, g, b) => return Some(RgbColor(r, g, b).into()),
+                Color::Default => return None,
+            };
+            Some(ansi.into())
+        }
+    }
+
+    impl From<anstyle::Color> for Color {
+        fn from(color: anstyle::Color) -> Self {
+            match color {
+                anstyle::Color::Ansi(ansi) => match ansi {
+                    AnsiColor::Black => Color::Black,
+                    AnsiColor::Red => Color::Red,
+                    AnsiColor::Green => Color::Green,
+                    AnsiColor::Yellow => Color::Yellow,
+                    AnsiColor::Blue => Color::Blue,
+                    AnsiColor::Magenta => Color::Magenta,
+                    AnsiColor::Cyan => Color::Cyan,
+                    AnsiColor::White => Color::White,
+                    AnsiColor::BrightBlack => Color::DarkGray,
+                    AnsiColor::BrightRed => Color::LightRed,
+                    AnsiColor::BrightGreen => Color::LightGreen,
+                    AnsiColor::BrightYellow => Color::LightYellow,
+                    AnsiColor::BrightBlue => Color::LightBlue,
+                    AnsiColor::BrightMagenta => Color::LightMagenta,
+                    AnsiColor::BrightCyan => Color::LightCyan,
+                    AnsiColor::BrightWhite => Color::LightGray,
+                },
+                anstyle::Color::Ansi256(Ansi256Color(n)) => Color::Fixed(n),
+                anstyle::Color::Rgb(RgbColor(r, g, b)) => Color::Rgb(r, g, b),
+            }
+        }
+    }
+
+    impl From<Style> for anstyle::Style {
+        fn from(style: Style) -> Self {
+            let color = |color: Option<Color>| color.and_then(Into::into);
+            let effects = EFFECTS
+                .iter()
+                .filter(|(ours, _)| style.formats.contains(*ours))
+                .fold(Effects::new(), |effects, (_, theirs)| effects | *theirs);
+            anstyle::Style::new()
+                .fg_color(color(style.coloring.fg))
+                .bg_color(color(style.coloring.bg))
+                .underline_color(color(style.coloring.underline))
+                .effects(effects)
+        }
+    }
+
+    impl From<anstyle::Style> for Style {
+        fn from(style: anstyle::Style) -> Self {
+            let effects = style.get_effects();
+            let formats = EFFECTS
+                .iter()
+                .chain(CLOSEST_EFFECTS)
+                .filter(|(_, theirs)| effects.contains(*theirs))
+                .fold(crate::FormatFlags::empty(), |formats, (ours, _)| {
+                    formats | *ours
+                });
+            let mut converted = Style::new().insert_formats(formats);
+            converted.coloring.fg = style.get_fg_color().map(Into::into);
+            converted.coloring.bg = style.get_bg_color().map(Into::into);
+            converted.coloring.underline = style.get_underline_color().map(Into::into);
+            converted
+        }
+    }
+}
+
+#[cfg(feature = "crossterm")]
+mod crossterm_impls {
+    use crate::{Color, Style};
+    use crossterm::style::{Attribute, Attributes, ContentStyle};
+
+    format_pairs!(ATTRIBUTES: Attribute = [
+        (BOLD, Attribute::Bold),
+        (DIMMED, Attribute::Dim),
+        (ITALIC, Attribute::Italic),
+        (UNDERLINE, Attribute::Underlined),
+        (DOUBLE_UNDERLINE, Attribute::DoubleUnderlined),
+        (UNDERCURL, Attribute::Undercurled),
+        (BLINK, Attribute::SlowBlink),
+        (REVERSE, Attribute::Reverse),
+        (HIDDEN, Attribute::Hidden),
+        (STRIKETHROUGH, Attribute::CrossedOut),
+        (OVERLINE, Attribute::OverLined),
+    ]);
+
+    format_pairs!(CLOSEST_ATTRIBUTES: Attribute = [
+        (UNDERLINE, Attribute::Underdotted),
+        (UNDERLINE, Attribute::Underdashed),
+        (BLINK, Attribute::RapidBlink),
+    ]);
+
+    /// Crossterm names the bright colors without a prefix and the standard
+    /// ones as dark: [`Color::Red`] is `DarkRed`, and [`Color::LightRed`] is
+    /// `Red`. [`Color::Default`] is `Reset`.
+    impl From<Color> for crossterm::style::Color {
+        fn from(color: Color) -> Self {
+            use crossterm::style::Color as C;
+            match color {
+                Color::Black => C::Black,
+                Color::DarkGray => C::DarkGrey,
+                Color::Red => C::DarkRed,
+                Color::LightRed => C::Red,
+                Color::Green => C::DarkGreen,
+                Color::LightGreen => C::Green,
+                Color::Yellow => C::DarkYellow,
+                Color::LightYellow => C::Yellow,
+                Color::Blue => C::DarkBlue,
+                Color::LightBlue => C::Blue,
+                Color::Purple | Color::Magenta => C::DarkMagenta,
+                Color::LightPurple | Color::LightMagenta => C::Magenta,
+                Color::Cyan => C::DarkCyan,
+                Color::LightCyan => C::Cyan,
+                Color::White => C::Grey,
+                Color::LightGray => C::White,
+                Color::Fixed(n) => C::AnsiValue(n),
+                Color::Rgb(r, g, b) => C::Rgb { r, g, b },
+                Color::Default => C::Reset,
+            }
+        }
+    }
+
+    impl From<crossterm::style::Color> for Color {
+        fn from(color: crossterm::style::Color) -> Self {
+            use crossterm::style::Color as C;
+            match color {
+                C::Reset => Color::Default,
+                C::Black => Color::Black,
+                C::DarkGrey => Color::DarkGray,
+                C::DarkRed => Color::Red,
+                C::Red => Color::LightRed,
+                C::DarkGreen => Color::Green,
+                C::Green => Color::LightGreen,
+                C::DarkYellow => Color::Yellow,
+                C::Yellow => Color::LightYellow,
+                C::DarkBlue => Color::Blue,
+                C::Blue => Color::LightBlue,
+                C::DarkMagenta => Color::Magenta,
+                C::Magenta => Color::LightMagenta,
+                C::DarkCyan => Color::Cyan,
+                C::Cyan => Color::LightCyan,
+                C::Grey => Color::White,
+                C::White => Color::LightGray,
+                C::AnsiValue(n) => Color::Fixed(n),
+                C::Rgb { r, g, b } => Color::Rgb(r, g, b),
+            }
+        }
+    }
+
+    impl From<Style> for ContentStyle {
+        fn from(style: Style) -> Self {
+            let mut attributes = Attributes::default();
+            for (ours, theirs) in ATTRIBUTES {
+                if style.formats.contains(*ours) {
+                    attributes.set(*theirs);
+                }
+            }
+            ContentStyle {
+                foreground_color: style.coloring.fg.map(Into::into),
+                background_color: style.coloring.bg.map(Into::into),
+                underline_color: style.coloring.underline.map(Into::into),
+                attributes,
+            }
+        }
+    }
+
+    impl From<ContentStyle> for Style {
+        fn from(style: ContentStyle) -> Self {
+            let formats = ATTRIBUTES
+                .iter()
+                .chain(CLOSEST_ATTRIBUTES)
+                .filter(|(_, theirs)| style.attributes.has(*theirs))
+                .fold(crate::FormatFlags::empty(), |formats, (ours, _)| {
+                    formats | *ours
+                });
+            let mut converted = Style::new().insert_formats(formats);
+            converted.coloring.fg = style.foreground_color.map(Into::into);
+            converted.coloring.bg = style.background_color.map(Into::into);
+            converted.coloring.underline = style.underline_color.map(Into::into);
+            converted
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::Color::*;
+    use crate::{FormatFlags, Style};
+
+    #[test]
+    #[cfg(feature = "anstyle")]
+    fn anstyle_round_trip() {
+        use anstyle::{AnsiColor, Effects};
+        let style = Red.on(Fixed(208)).bold().undercurl().overline();
+        let converted = anstyle::Style::from(style);
+        assert_eq!(
+            converted,
+            anstyle::Style::new()
+                .fg_color(Some(AnsiColor::Red.into()))
+                .bg_color(Some(anstyle::Ansi256Color(208).into()))
+                .effects(Effects::BOLD | Effects::CURLY_UNDERLINE)
+        );
+        assert_eq!(
+            Style::from(converted),
+            style.remove_formats(FormatFlags::OVERLINE)
+        );
+        assert_eq!(anstyle::Style::from(Default.on(Blue)).get_fg_color(), None);
+        let dotted = anstyle::Style::new().effects(Effects::DOTTED_UNDERLINE);
+        assert_eq!(Style::from(dotted), Style::new().underline());
+    }
+
+    #[test]
+    #[cfg(feature = "crossterm")]
+    fn crossterm_round_trip() {
+        use crossterm::style::{Attribute, ContentStyle};
+        let style = LightGray.on(Default).bold().overline();
+        let converted = ContentStyle::from(style);
+        assert_eq!(
+            converted.foreground_color,
+            Some(crossterm::style::Color::White)
+        );
+        assert_eq!(
+            converted.background_color,
+            Some(crossterm::style::Color::Reset)
+        );
+        assert!(converted.attributes.has(Attribute::OverLined));
+        assert!(!converted.attributes.has(Attribute::Underdotted));
+        assert_eq!(Style::from(converted), style);
+        assert_eq!(Style::from(ContentStyle::new()), Style::new());
+    }
+}
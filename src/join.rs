@@ -0,0 +1,150 @@
+use crate::display::AnsiGenericString;
+use crate::nested;
+use crate::{fmt_write, AnsiString, Color, Style};
+use std::fmt;
+
+/// Items of an iterator joined by a separator, painted lazily when
+/// displayed. Created by [`Style::paint_join`].
+///
+/// Only the codes needed to switch between the style of the items and the
+/// style of the separator are written, and a single reset at the end.
+#[derive(Clone, Debug)]
+pub struct PaintJoin<'a, I> {
+    items: I,
+    style: Style,
+    separator: AnsiString<'a>,
+}
+
+impl Style {
+    /// Join the items of `items` with `separator`, painting the items in
+    /// this style and the separator in its own. Nothing is collected: the
+    /// items are formatted one by one as the result is displayed, which can
+    /// be done as often as the iterator can be cloned.
+    ///
+    /// Styled strings among the items are nested in this style (see
+    /// [`nested`](crate::nested)).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::Style;
+    /// use nu_ansi_term::Color::Green;
+    ///
+    /// let commands = ["ls", "cd", "rm"];
+    /// let joined = Green.bold().paint_join(commands.iter(), Style::new().dimmed().paint(", "));
+    /// assert_eq!(
+    ///     joined.to_string(),
+    ///     "\x1b[1;32mls\x1b[0m\x1b[2m, \x1b[0m\x1b[1;32mcd\x1b[0m\x1b[2m, \x1b[0m\x1b[1;32mrm\x1b[0m"
+    /// );
+    ///
+    /// let numbers = Green.paint_join(1..=3, "+");
+    /// assert_eq!(numbers.to_string(), "\x1b[32m1\x1b[0m+\x1b[32m2\x1b[0m+\x1b[32m3\x1b[0m");
+    /// ```
+    pub fn paint_join<'a, I>(
+        self,
+        items: I,
+        separator: impl Into<AnsiString<'a>>,
+    ) -> PaintJoin<'a, I::IntoIter>
+    where
+        I: IntoIterator,
+        I::IntoIter: Clone,
+        I::Item: fmt::Display,
+    {
+        PaintJoin {
+            items: items.into_iter(),
+            style: self,
+            separator: separator.into(),
+        }
+    }
+}
+
+impl Color {
+    /// Join the items of `items` with `separator`, painting the items in
+    /// this color. See [`Style::paint_join`].
+    pub fn paint_join<'a, I>(
+        self,
+        items: I,
+        separator: impl Into<AnsiString<'a>>,
+    ) -> PaintJoin<'a, I::IntoIter>
+    where
+        I: IntoIterator,
+        I::IntoIter: Clone,
+        I::Item: fmt::Display,
+    {
+        self.normal().paint_join(items, separator)
+    }
+}
+
+impl<'a, I> fmt::Display for PaintJoin<'a, I>
+where
+    I: Iterator + Clone,
+    I::Item: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let item_style = Style {
+            prefix_before_reset: false,
+            ..self.style
+        };
+        let separator_style = self.separator.scheduled_style().map(|style| Style {
+            prefix_before_reset: false,
+            ..style
+        });
+
+        let mut current = Style::new();
+        for (ix, item) in self.items.clone().enumerate() {
+            if ix > 0 {
+                if let Some(style) = separator_style {
+                    nested::switch(current, style, fmt_write!(f))?;
+                    current = style;
+                }
+                AnsiGenericString::write_inner(
+                    self.separator.content(),
+                    self.separator.oscontrol(),
+                    fmt_write!(f),
+                )?;
+            }
+            nested::switch(current, item_style, fmt_write!(f))?;
+            current = item_style;
+            nested::within(item_style, || write!(f, "{}", item))?;
+        }
+        nested::switch(current, Style::new(), fmt_write!(f))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Color::*;
+
+    #[test]
+    fn empty_and_single() {
+        let none: [&str; 0] = [];
+        assert_eq!(Red.paint_join(none, ", ").to_string(), "");
+        assert_eq!(
+            Red.paint_join(["a"], Blue.paint(", ")).to_string(),
+            "\x1b[31ma\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn items_and_separators_are_styled_independently() {
+        let words = ["a", "b"];
+        let joined = Red.paint_join(words.iter(), Red.underline().paint("-"));
+        assert_eq!(
+            joined.to_string(),
+            "\x1b[31ma\x1b[4m-\x1b[0m\x1b[31mb\x1b[0m"
+        );
+        // Displaying twice gives the same output.
+        assert_eq!(joined.to_string(), joined.to_string());
+
+        let plain = Style::new().paint_join(words.iter(), Blue.paint("|"));
+        assert_eq!(plain.to_string(), "a\x1b[34m|\x1b[0mb");
+
+        let styled = [Blue.paint("x"), AnsiString::from("y")];
+        let nested = Red.bold().paint_join(styled.iter(), " ");
+        assert_eq!(
+            nested.to_string(),
+            "\x1b[1;31m\x1b[34mx\x1b[31m\x1b[0m \x1b[1;31my\x1b[0m"
+        );
+    }
+}
@@ -0,0 +1,239 @@
+use crate::write::Content;
+use crate::{nested, AnsiGenericString, AnsiString, AnsiStrings, Style};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// A styled document: the segments of an [`AnsiStrings`] sequence.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct JsonDocument {
+    pub(crate) segments: Vec<JsonSegment>,
+}
+
+/// One segment of a [`JsonDocument`], tagged with its `kind`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub(crate) enum JsonSegment {
+    /// Styled text, which may be a hyperlink.
+    Text {
+        style: Style,
+        #[serde(flatten)]
+        content: JsonContent,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        link: Option<String>,
+    },
+    /// A window title.
+    Title { text: String },
+    /// A control sequence, written verbatim.
+    Control { text: String },
+}
+
+/// The content of a text segment: either text, or a nested sequence.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum JsonContent {
+    Text(String),
+    Segments(Vec<JsonSegment>),
+}
+
+impl JsonDocument {
+    fn describe(strings: &AnsiStrings<'_>) -> Self {
+        Self {
+            segments: strings.iter().map(JsonSegment::describe).collect(),
+        }
+    }
+
+    fn into_strings(segments: Vec<JsonSegment>) -> AnsiStrings<'static> {
+        segments.into_iter().map(JsonSegment::into_string).collect()
+    }
+}
+
+impl JsonSegment {
+    fn describe(segment: &AnsiGenericString<'_, str>) -> Self {
+        let content = match segment.content() {
+            Content::StrLike(s) => JsonContent::Text(s.to_string()),
+            Content::Shared(s) => JsonContent::Text(s.to_string()),
+            Content::FmtArgs(args) => JsonContent::Text(match args.as_str() {
+                Some(s) => s.to_owned(),
+                None => nested::within(segment.style, || args.to_string()),
+            }),
+            Content::GenericStrings(strings) => {
+                JsonContent::Segments(JsonDocument::describe(strings).segments)
+            }
+        };
+        let text = || match &content {
+            JsonContent::Text(text) => text.clone(),
+            JsonContent::Segments(_) => segment.content().to_string(),
+        };
+        if segment.is_control() {
+            // Commands such as notifications are kept as the sequences they are
+            // written as, and read back as control sequences.
+            JsonSegment::Control {
+                text: segment.control_command(),
+            }
+        } else if segment.scheduled_style().is_none() {
+            JsonSegment::Title { text: text() }
+        } else {
+            JsonSegment::Text {
+                style: segment.style,
+                link: segment.url_string().map(|url| url.to_string()),
+                content,
+            }
+        }
+    }
+
+    fn into_string(self) -> AnsiString<'static> {
+        match self {
+            JsonSegment::Text {
+                style,
+                content,
+                link,
+            } => {
+                let string = match content {
+                    JsonContent::Text(text) => style.paint(text),
+                    JsonContent::Segments(segments) => {
+                        style.paint(JsonDocument::into_strings(segments))
+                    }
+                };
+                match link {
+                    Some(url) => string.hyperlink_content(url),
+                    None => string,
+                }
+            }
+            JsonSegment::Title { text } => AnsiString::title_content(text),
+            JsonSegment::Control { text } => AnsiString::control(text),
+        }
+    }
+}
+
+/// Painted strings are written as a single segment of a [`JsonDocument`],
+/// e.g. `{"kind": "text", "style": …, "text": "…"}`.
+impl<'a> Serialize for AnsiGenericString<'a, str> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        JsonSegment::describe(self).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for AnsiString<'static> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        JsonSegment::deserialize(deserializer).map(JsonSegment::into_string)
+    }
+}
+
+#[cfg(feature = "json")]
+impl<'a> AnsiStrings<'a> {
+    /// Describe the segments of this sequence as JSON, so that tools outside
+    /// of Rust can inspect it without parsing escape codes.
+    ///
+    /// The document is an object with a `segments` array. Each segment has a
+    /// `kind`: `"text"` segments have a `style`, an optional `link`, and
+    /// either a `text` string or the `segments` of a nested sequence;
+    /// `"title"` and `"control"` segments only have a `text`. Lazily resolved
+    /// styles are not resolved; use [`resolved`](Self::resolved) first.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::{AnsiString, AnsiStrings};
+    /// use nu_ansi_term::Color::Red;
+    ///
+    /// let strings = AnsiStrings([AnsiString::title("build"), Red.paint("failed")]);
+    /// let json: serde_json::Value = serde_json::from_str(&strings.to_json()).unwrap();
+    /// assert_eq!(json["segments"][0]["kind"], "title");
+    /// assert_eq!(json["segments"][1]["text"], "failed");
+    /// assert_eq!(json["segments"][1]["style"]["foreground"], "Red");
+    /// ```
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(&JsonDocument::describe(self))
+            .expect("a styled document is always representable as JSON")
+    }
+}
+
+#[cfg(feature = "json")]
+impl AnsiStrings<'static> {
+    /// Read a sequence from the JSON written by [`to_json`](Self::to_json),
+    /// e.g. pre-styled output handed over by another process.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::AnsiStrings;
+    /// use nu_ansi_term::Color::{Blue, Red};
+    ///
+    /// let strings = AnsiStrings([Red.bold().paint("error: "), Blue.paint("see the docs")]);
+    /// let copy = AnsiStrings::from_json(&strings.to_json()).unwrap();
+    /// assert_eq!(copy.to_string(), strings.to_string());
+    ///
+    /// assert!(AnsiStrings::from_json(r#"{"segments": [{"kind": "blink"}]}"#).is_err());
+    /// ```
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        let document: JsonDocument = serde_json::from_str(json)?;
+        Ok(JsonDocument::into_strings(document.segments))
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "json")]
+mod test {
+    use crate::Color::*;
+    use crate::{AnsiString, AnsiStrings, Style};
+    use serde_json::{json, Value};
+
+    #[test]
+    fn segments() {
+        let mut strings = AnsiStrings([
+            Blue.paint("docs").hyperlink("https://example.com"),
+            Style::new().paint(AnsiStrings([Red.bold().paint("nested")])),
+        ]);
+        strings.push_control("\x1b[K");
+
+        let json: Value = serde_json::from_str(&strings.to_json()).unwrap();
+        let segments = &json["segments"];
+        assert_eq!(segments[0]["kind"], "text");
+        assert_eq!(segments[0]["link"], "https://example.com");
+        assert_eq!(segments[1]["segments"][0]["text"], "nested");
+        assert_eq!(segments[1]["segments"][0]["style"]["is_bold"], true);
+        assert!(segments[1].get("link").is_none());
+        assert_eq!(segments[2], json!({ "kind": "control", "text": "\x1b[K" }));
+        assert_eq!(
+            serde_json::from_str::<Value>(&AnsiStrings([AnsiString::title("t")]).to_json())
+                .unwrap(),
+            json!({ "segments": [{ "kind": "title", "text": "t" }] })
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut strings = AnsiStrings([
+            AnsiString::title("t"),
+            Blue.underline()
+                .paint("docs")
+                .hyperlink("https://example.com"),
+            Green.paint(AnsiStrings([Red.bold().paint("nested"), "plain".into()])),
+        ]);
+        strings.push_control("\x1b[K");
+        strings.push(AnsiString::notify_body("done"));
+        strings.push(Style::reset().paint("after"));
+
+        let json = strings.to_json();
+        let copy = AnsiStrings::from_json(&json).unwrap();
+        assert_eq!(copy.to_string(), strings.to_string());
+        assert_eq!(copy.to_json(), json);
+    }
+
+    #[test]
+    fn painted_string() {
+        let docs = Blue.bold().paint("docs").hyperlink("https://example.com");
+        let json = serde_json::to_value(&docs).unwrap();
+        assert_eq!(json["kind"], "text");
+        assert_eq!(json["link"], "https://example.com");
+        let copy: AnsiString = serde_json::from_value(json).unwrap();
+        assert_eq!(copy.to_string(), docs.to_string());
+
+        let warning: AnsiString =
+            serde_json::from_str(r#"{"kind": "text", "style": "yellow bold", "text": "warning"}"#)
+                .unwrap();
+        assert_eq!(
+            warning.to_string(),
+            Yellow.bold().paint("warning").to_string()
+        );
+    }
+}
@@ -0,0 +1,195 @@
+use crate::prompt::DEFAULT_WIDTH;
+use crate::terminal::detect_width;
+use crate::width::{wrap_segments, WidthPolicy};
+use crate::{AnsiString, AnsiStrings, ContinuationMarker, Style};
+
+/// Renderer for definition lists: keys aligned in a column, each followed by
+/// a separator and a value which is wrapped to the remaining width.
+///
+/// # Examples
+///
+/// ```
+/// use nu_ansi_term::{AnsiStrings, KvBlock};
+/// use nu_ansi_term::Color::{Blue, Green};
+/// use nu_ansi_term::Style;
+///
+/// let pairs = [
+///     (Green.paint("name"), AnsiStrings([Blue.paint("ls")])),
+///     (Green.paint("usage"), AnsiStrings(["List the files in a directory".into()])),
+/// ];
+/// let block = KvBlock::new()
+///     .width(24)
+///     .separator(Style::new().dimmed().paint(" │ "));
+/// println!("{}", block.render(&pairs));
+/// ```
+#[derive(Clone, Debug)]
+pub struct KvBlock<'a> {
+    width: Option<usize>,
+    separator: AnsiString<'a>,
+    wrap_marker: Option<ContinuationMarker>,
+    width_policy: WidthPolicy,
+    #[cfg(feature = "unicode-bidi")]
+    bidi_isolation: Option<crate::BidiIsolation>,
+}
+
+impl<'a> Default for KvBlock<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> KvBlock<'a> {
+    /// Create a renderer which separates keys and values with an unstyled
+    /// `": "`, and detects the width it is rendered at.
+    pub fn new() -> Self {
+        Self {
+            width: None,
+            separator: AnsiString::from(": "),
+            wrap_marker: None,
+            width_policy: WidthPolicy::new(),
+            #[cfg(feature = "unicode-bidi")]
+            bidi_isolation: None,
+        }
+    }
+
+    /// Render at a fixed `width` instead of detecting it.
+    pub fn width(mut self, width: usize) -> Self {
+        self.width = Some(width);
+        self
+    }
+
+    /// Place `separator` between each key and its value.
+    pub fn separator(mut self, separator: impl Into<AnsiString<'a>>) -> Self {
+        self.separator = separator.into();
+        self
+    }
+
+    /// End value lines which are wrapped onto the next line with `marker`.
+    pub fn wrap_marker(mut self, marker: ContinuationMarker) -> Self {
+        self.wrap_marker = Some(marker);
+        self
+    }
+
+    /// Measure keys and values with `policy`.
+    pub fn width_policy(mut self, policy: WidthPolicy) -> Self {
+        self.width_policy = policy;
+        self
+    }
+
+    /// Isolate keys and value lines containing right-to-left text, so that
+    /// they do not swap places with their padding and separators.
+    #[cfg(feature = "unicode-bidi")]
+    pub fn bidi_isolation(mut self, isolation: crate::BidiIsolation) -> Self {
+        self.bidi_isolation = Some(isolation);
+        self
+    }
+
+    fn isolate(&self, segments: Vec<AnsiString<'a>>) -> Vec<AnsiString<'a>> {
+        #[cfg(feature = "unicode-bidi")]
+        if let Some(isolation) = self.bidi_isolation {
+            return isolation.isolate(segments);
+        }
+        segments
+    }
+
+    /// The width this block will be rendered at: the supplied width if any,
+    /// otherwise the detected terminal width, falling back to
+    /// [`DEFAULT_WIDTH`].
+    pub fn effective_width(&self) -> usize {
+        self.width.or_else(detect_width).unwrap_or(DEFAULT_WIDTH)
+    }
+
+    /// Render `pairs`, with lines separated by `\n`. Continuation lines of
+    /// wrapped values are indented to the value column.
+    pub fn render(&self, pairs: &[(AnsiString<'a>, AnsiStrings<'a>)]) -> AnsiStrings<'a> {
+        let key_width = pairs
+            .iter()
+            .map(|(key, _)| self.width_policy.segment_width(key))
+            .max()
+            .unwrap_or(0);
+        let indent = key_width + self.width_policy.segment_width(&self.separator);
+        let value_width = self.effective_width().saturating_sub(indent);
+        let padding = |count: usize| Style::new().paint(" ".repeat(count));
+
+        let mut segments: Vec<AnsiString<'a>> = Vec::new();
+        for (key, value) in pairs {
+            if !segments.is_empty() {
+                segments.push(AnsiString::from("\n"));
+            }
+            segments.extend(self.isolate(vec![key.clone()]));
+            let key_padding = key_width - self.width_policy.segment_width(key);
+            if key_padding > 0 {
+                segments.push(padding(key_padding));
+            }
+            segments.push(self.separator.clone());
+
+            let value: Vec<AnsiString<'a>> = value.iter().cloned().collect();
+            for (ix, line) in wrap_segments(
+                &value,
+                value_width,
+                self.wrap_marker.as_ref(),
+                self.width_policy,
+            )
+            .into_iter()
+            .enumerate()
+            {
+                if ix > 0 {
+                    segments.push(AnsiString::from("\n"));
+                    segments.push(padding(indent));
+                }
+                segments.extend(self.isolate(line));
+            }
+        }
+        AnsiStrings(segments)
+    }
+}
+
+/// Render `pairs` as an aligned definition list using the default
+/// [`KvBlock`] settings.
+pub fn kv_block<'a>(pairs: &[(AnsiString<'a>, AnsiStrings<'a>)]) -> AnsiStrings<'a> {
+    KvBlock::new().render(pairs)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::unstyle;
+    use crate::Color::*;
+
+    #[test]
+    fn keys_are_aligned() {
+        let pairs = [
+            (Red.paint("a"), AnsiStrings(["one".into()])),
+            (Red.paint("abc"), AnsiStrings([Blue.paint("two")])),
+        ];
+        let block = KvBlock::new().width(40).render(&pairs);
+        assert_eq!(unstyle(&block), "a  : one\nabc: two");
+    }
+
+    #[test]
+    fn values_wrap_under_value_column() {
+        let pairs = [(
+            Red.paint("k"),
+            AnsiStrings(["a long value which wraps".into()]),
+        )];
+        let block = KvBlock::new().width(14).separator(" - ").render(&pairs);
+        assert_eq!(
+            unstyle(&block),
+            "k - a long\n    value\n    which\n    wraps"
+        );
+    }
+
+    #[cfg(feature = "unicode-bidi")]
+    #[test]
+    fn rtl_keys_are_isolated() {
+        let pairs = [
+            (Red.paint("שם"), AnsiStrings(["one".into()])),
+            (Red.paint("key"), AnsiStrings(["two".into()])),
+        ];
+        let block = KvBlock::new()
+            .width(40)
+            .bidi_isolation(crate::BidiIsolation::Mark)
+            .render(&pairs);
+        assert_eq!(unstyle(&block), "שם\u{200E} : one\nkey: two");
+    }
+}
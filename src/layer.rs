@@ -0,0 +1,226 @@
+use crate::write::Content;
+use crate::{AnsiString, AnsiStrings, Style};
+use std::ops::Range;
+
+/// A style laid over part of some styled text, such as a selection or a
+/// search match, with a priority deciding which overlay wins where several
+/// of them overlap.
+///
+/// The range is a range of bytes in the visible text of the segments the
+/// overlay is applied to (see [`StyleLayering::apply`]).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Overlay {
+    range: Range<usize>,
+    style: Style,
+    priority: i32,
+}
+
+impl Overlay {
+    /// Lay `style` over the text in `range`, with priority 0.
+    pub fn new(range: Range<usize>, style: Style) -> Self {
+        Self {
+            range,
+            style,
+            priority: 0,
+        }
+    }
+
+    /// Set the priority of this overlay. Overlays with a higher priority are
+    /// laid over those with a lower one; overlays with equal priorities are
+    /// laid in the order they are given.
+    pub fn priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// The range of visible text covered by this overlay.
+    pub fn range(&self) -> Range<usize> {
+        self.range.clone()
+    }
+
+    /// The style of this overlay.
+    pub fn style(&self) -> Style {
+        self.style
+    }
+
+    fn covers(&self, pos: usize) -> bool {
+        self.range.contains(&pos)
+    }
+}
+
+/// How the styles of overlapping [`Overlay`]s are combined with each other
+/// and with the style of the text beneath them.
+///
+/// # Examples
+///
+/// ```
+/// use nu_ansi_term::{Overlay, StyleLayering};
+/// use nu_ansi_term::Color::{Blue, Green, Yellow};
+///
+/// let code = [Green.paint("let"), " x = 1;".into()];
+/// let search = Overlay::new(0..5, Yellow.bold()).priority(1);
+/// let selection = Overlay::new(4..10, Blue.reverse());
+///
+/// let merged = StyleLayering::Merge.apply(&code, &[selection.clone(), search.clone()]);
+/// let styles: Vec<_> = merged.iter().map(|s| *s.style_ref()).collect();
+/// assert_eq!(
+///     styles,
+///     [Yellow.bold(), Yellow.bold(), Yellow.bold().reverse(), Blue.reverse()]
+/// );
+///
+/// let topmost = StyleLayering::Topmost.apply(&code, &[selection, search]);
+/// assert_eq!(topmost.iter().nth(2).unwrap().style_ref(), &Yellow.bold());
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StyleLayering {
+    /// Lay the overlays over the text in order of priority: each one
+    /// replaces the colors it sets, adds its formats, and turns off those it
+    /// clears (see [`Style::clear_formats`]).
+    #[default]
+    Merge,
+    /// Only the overlay with the highest priority applies, replacing the
+    /// style of the text beneath it.
+    Topmost,
+}
+
+impl StyleLayering {
+    /// The style of text in the `base` style, covered by `overlays`.
+    pub fn resolve<'o>(
+        self,
+        base: Style,
+        overlays: impl IntoIterator<Item = &'o Overlay>,
+    ) -> Style {
+        let mut overlays: Vec<&Overlay> = overlays.into_iter().collect();
+        // A stable sort, so that ties keep their order.
+        overlays.sort_by_key(|overlay| overlay.priority);
+        match self {
+            StyleLayering::Merge => overlays.iter().fold(base, |style, overlay| Style {
+                prefix_before_reset: style.prefix_before_reset,
+                ..overlay.style.inherit_from(&style)
+            }),
+            StyleLayering::Topmost => overlays.last().map_or(base, |overlay| Style {
+                prefix_before_reset: base.prefix_before_reset,
+                ..overlay.style
+            }),
+        }
+    }
+
+    /// Lay `overlays` over `segments`, splitting segments where the
+    /// overlays covering them change. Links are kept, and titles and
+    /// control segments are passed through unchanged.
+    pub fn apply<'a>(self, segments: &[AnsiString<'a>], overlays: &[Overlay]) -> AnsiStrings<'a> {
+        let mut applied = Vec::with_capacity(segments.len());
+        let mut pos = 0;
+        for segment in segments {
+            if segment.scheduled_style().is_none() {
+                applied.push(segment.clone());
+                continue;
+            }
+            let text = segment.content.to_string();
+            let len = text.len();
+            let mut cuts: Vec<usize> = overlays
+                .iter()
+                .flat_map(|overlay| [overlay.range.start, overlay.range.end])
+                .filter(|&cut| cut > pos && cut < pos + len)
+                .map(|cut| cut - pos)
+                .filter(|&cut| text.is_char_boundary(cut))
+                .chain([0, len])
+                .collect();
+            cuts.sort_unstable();
+            cuts.dedup();
+            for piece in cuts.windows(2) {
+                let covering = overlays.iter().filter(|o| o.covers(pos + piece[0]));
+                let mut split = segment.clone();
+                split.style = self.resolve(segment.style, covering);
+                if cuts.len() > 2 {
+                    split.content = Content::from(text[piece[0]..piece[1]].to_string());
+                }
+                applied.push(split);
+            }
+            pos += len;
+        }
+        AnsiStrings(applied)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::unstyle;
+    use crate::Color::*;
+    use crate::FormatFlags;
+
+    #[test]
+    fn priorities_decide_colors() {
+        let low = Overlay::new(0..1, Red.normal()).priority(-1);
+        let high = Overlay::new(0..1, Blue.underline()).priority(5);
+        let tie = Overlay::new(0..1, Green.normal()).priority(5);
+        let base = Yellow.bold();
+        for layering in [StyleLayering::Merge, StyleLayering::Topmost] {
+            assert_eq!(
+                layering.resolve(base, [&high, &low]),
+                layering.resolve(base, [&low, &high])
+            );
+        }
+        assert_eq!(
+            StyleLayering::Merge.resolve(base, [&high, &low]),
+            Blue.bold().underline()
+        );
+        assert_eq!(
+            StyleLayering::Merge.resolve(base, [&high, &tie]),
+            Green.bold().underline()
+        );
+        assert_eq!(
+            StyleLayering::Merge.resolve(base, [&tie, &high]),
+            Blue.bold().underline()
+        );
+        assert_eq!(
+            StyleLayering::Topmost.resolve(base, [&low, &high]),
+            Blue.underline()
+        );
+        assert_eq!(StyleLayering::Topmost.resolve(base, []), base);
+    }
+
+    #[test]
+    fn overlays_turn_off_cleared_formats() {
+        let unbold = Overlay::new(0..1, Blue.normal().clear_formats(FormatFlags::BOLD));
+        let emphasis = Overlay::new(0..1, Style::new().bold().italic()).priority(1);
+        let base = Yellow.bold().underline();
+        assert_eq!(
+            StyleLayering::Merge.resolve(base, [&unbold]),
+            Blue.underline().clear_formats(FormatFlags::BOLD)
+        );
+        assert_eq!(
+            StyleLayering::Merge.resolve(base, [&unbold, &emphasis]),
+            Blue.bold().italic().underline()
+        );
+    }
+
+    #[test]
+    fn segments_are_split_at_overlay_edges() {
+        let segments = [
+            Red.paint("héllo").hyperlink("https://example.com"),
+            AnsiString::title_content("title"),
+            Blue.paint(" world"),
+        ];
+        let overlays = [Overlay::new(3..8, Style::new().underline())];
+        let applied = StyleLayering::Merge.apply(&segments, &overlays);
+        let pieces: Vec<(String, Style)> = applied
+            .iter()
+            .filter(|s| s.scheduled_style().is_some())
+            .map(|s| (s.content().to_string(), *s.style_ref()))
+            .collect();
+        assert_eq!(
+            pieces,
+            [
+                ("hé".to_string(), Red.normal()),
+                ("llo".to_string(), Red.underline()),
+                (" w".to_string(), Blue.underline()),
+                ("orld".to_string(), Blue.normal()),
+            ]
+        );
+        assert_eq!(unstyle(&applied), unstyle(&AnsiStrings(segments.to_vec())));
+        let linked = applied.iter().take(2).all(|s| s.url_string().is_some());
+        assert!(linked);
+    }
+}
@@ -0,0 +1,347 @@
+use crate::{Color, Rgb, Style};
+use std::env;
+
+/// The range of colors a terminal can display.
+///
+/// Levels are ordered by the number of colors, so a style can be checked
+/// against a minimum level with comparison operators.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ColorLevel {
+    /// No colors at all.
+    NoColor,
+    /// The sixteen named colors.
+    Ansi16,
+    /// The 256 colors of [`Color::Fixed`].
+    Ansi256,
+    /// Any 24-bit [`Color::Rgb`] color.
+    #[default]
+    TrueColor,
+}
+
+impl ColorLevel {
+    /// Detect the colors supported by the current terminal from the
+    /// environment.
+    ///
+    /// `COLORTERM=truecolor` (or `24bit`) means 24-bit color. Otherwise
+    /// `TERM` decides: `dumb` has no colors, names containing `256color`
+    /// have 256 colors, and any other terminal the named colors. Without
+    /// `TERM`, Windows consoles are assumed to support 24-bit color, and
+    /// anything else the named colors.
+    pub fn detect() -> Self {
+        level_from(|name| env::var(name).ok())
+    }
+}
+
+/// The color level described by the variables read through `var`.
+fn level_from(var: impl Fn(&str) -> Option<String>) -> ColorLevel {
+    let set = |name| var(name).filter(|value| !value.is_empty());
+    if let Some("truecolor" | "24bit") = set("COLORTERM").as_deref() {
+        return ColorLevel::TrueColor;
+    }
+    match set("TERM") {
+        Some(term) if term == "dumb" => ColorLevel::NoColor,
+        Some(term) if term.contains("256color") => ColorLevel::Ansi256,
+        Some(_) => ColorLevel::Ansi16,
+        None if cfg!(windows) => ColorLevel::TrueColor,
+        None => ColorLevel::Ansi16,
+    }
+}
+
+/// The sixteen named colors, in palette order.
+const NAMED: [Color; 16] = [
+    Color::Black,
+    Color::Red,
+    Color::Green,
+    Color::Yellow,
+    Color::Blue,
+    Color::Purple,
+    Color::Cyan,
+    Color::White,
+    Color::DarkGray,
+    Color::LightRed,
+    Color::LightGreen,
+    Color::LightYellow,
+    Color::LightBlue,
+    Color::LightPurple,
+    Color::LightCyan,
+    Color::LightGray,
+];
+
+/// The usual (xterm) values of the sixteen named colors.
+const NAMED_RGB: [(u8, u8, u8); 16] = [
+    (0, 0, 0),
+    (205, 0, 0),
+    (0, 205, 0),
+    (205, 205, 0),
+    (0, 0, 238),
+    (205, 0, 205),
+    (0, 205, 205),
+    (229, 229, 229),
+    (127, 127, 127),
+    (255, 0, 0),
+    (0, 255, 0),
+    (255, 255, 0),
+    (92, 92, 255),
+    (255, 0, 255),
+    (0, 255, 255),
+    (255, 255, 255),
+];
+
+/// The intensities of each channel in the 6×6×6 color cube.
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// How different two colors look: the squared distance between them, with
+/// the channels weighted by how sensitive the eye is to them at the mean
+/// redness of the two (the "redmean" approximation).
+fn distance((r1, g1, b1): (u8, u8, u8), (r2, g2, b2): (u8, u8, u8)) -> u32 {
+    let d = |a: u8, b: u8| (a as i32 - b as i32).unsigned_abs();
+    let red_mean = (r1 as u32 + r2 as u32) / 2;
+    (((512 + red_mean) * d(r1, r2).pow(2)) >> 8)
+        + 4 * d(g1, g2).pow(2)
+        + (((767 - red_mean) * d(b1, b2).pow(2)) >> 8)
+}
+
+/// The RGB value of the color numbered `n` in the 256 color palette.
+const fn fixed_rgb(n: u8) -> (u8, u8, u8) {
+    match n {
+        0..=15 => NAMED_RGB[n as usize],
+        16..=231 => {
+            let n = n - 16;
+            (
+                CUBE_LEVELS[(n / 36) as usize],
+                CUBE_LEVELS[(n / 6 % 6) as usize],
+                CUBE_LEVELS[(n % 6) as usize],
+            )
+        }
+        232..=255 => {
+            let gray = 8 + (n - 232) * 10;
+            (gray, gray, gray)
+        }
+    }
+}
+
+/// The usual (xterm) values of the 256 colors of [`Color::Fixed`]: the
+/// sixteen named colors, a 6×6×6 color cube and a ramp of 24 grays.
+///
+/// # Examples
+///
+/// ```
+/// use nu_ansi_term::{Rgb, XTERM_PALETTE};
+///
+/// assert_eq!(XTERM_PALETTE[208], Rgb::new(255, 135, 0));
+/// assert_eq!(XTERM_PALETTE[232], Rgb::gray(8));
+/// ```
+pub const XTERM_PALETTE: [Rgb; 256] = {
+    let mut palette = [Rgb::new(0, 0, 0); 256];
+    let mut n = 0;
+    while n < 256 {
+        let (r, g, b) = fixed_rgb(n as u8);
+        palette[n] = Rgb::new(r, g, b);
+        n += 1;
+    }
+    palette
+};
+
+/// The usual RGB value of `color`, or `None` for [`Color::Default`].
+pub(crate) fn color_rgb(color: Color) -> Option<(u8, u8, u8)> {
+    let rgb = match color {
+        Color::Rgb(r, g, b) => (r, g, b),
+        Color::Fixed(n) => fixed_rgb(n),
+        Color::Magenta => NAMED_RGB[5],
+        Color::LightMagenta => NAMED_RGB[13],
+        Color::Default => return None,
+        named => NAMED_RGB[NAMED.iter().position(|&c| c == named)?],
+    };
+    Some(rgb)
+}
+
+/// The number of `color` in the 256-color palette, or `None` for RGB colors
+/// and [`Color::Default`].
+pub(crate) fn palette_index(color: Color) -> Option<u8> {
+    match color {
+        Color::Fixed(n) => Some(n),
+        Color::Magenta => Some(5),
+        Color::LightMagenta => Some(13),
+        named => NAMED.iter().position(|&c| c == named).map(|ix| ix as u8),
+    }
+}
+
+/// The entry of the color cube or of the gray ramp nearest to `rgb`.
+fn nearest_fixed(rgb: (u8, u8, u8)) -> u8 {
+    let cube_index = |c: u8| {
+        (0..6)
+            .min_by_key(|&i| (CUBE_LEVELS[i] as i32 - c as i32).abs())
+            .expect("the cube has levels") as u8
+    };
+    let (r, g, b) = rgb;
+    let cube = 16 + 36 * cube_index(r) + 6 * cube_index(g) + cube_index(b);
+    let average = (r as u32 + g as u32 + b as u32) / 3;
+    let gray = 232 + (average.saturating_sub(3) / 10).min(23) as u8;
+    if distance(fixed_rgb(gray), rgb) < distance(fixed_rgb(cube), rgb) {
+        gray
+    } else {
+        cube
+    }
+}
+
+/// The named color nearest to `rgb`.
+fn nearest_named(rgb: (u8, u8, u8)) -> Color {
+    let index = (0..16)
+        .min_by_key(|&i| distance(NAMED_RGB[i], rgb))
+        .expect("there are named colors");
+    NAMED[index]
+}
+
+impl Color {
+    /// The closest color a terminal supporting `level` can display: `Rgb`
+    /// colors are replaced by the nearest entry of the 256 color palette, or
+    /// of the named colors, and `Fixed` colors by the nearest named color.
+    /// There is no color at all for [`ColorLevel::NoColor`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::{Color, ColorLevel};
+    ///
+    /// let orange = Color::Rgb(255, 135, 0);
+    /// assert_eq!(orange.downgrade(ColorLevel::TrueColor), Some(orange));
+    /// assert_eq!(orange.downgrade(ColorLevel::Ansi256), Some(Color::Fixed(208)));
+    /// assert_eq!(orange.downgrade(ColorLevel::Ansi16), Some(Color::Yellow));
+    /// assert_eq!(Color::Fixed(244).downgrade(ColorLevel::Ansi16), Some(Color::DarkGray));
+    /// assert_eq!(orange.downgrade(ColorLevel::NoColor), None);
+    /// ```
+    pub fn downgrade(self, level: ColorLevel) -> Option<Color> {
+        match (self, level) {
+            (_, ColorLevel::NoColor) => None,
+            (Color::Rgb(r, g, b), ColorLevel::Ansi256) => {
+                Some(Color::Fixed(nearest_fixed((r, g, b))))
+            }
+            (Color::Rgb(r, g, b), ColorLevel::Ansi16) => Some(nearest_named((r, g, b))),
+            (Color::Fixed(n), ColorLevel::Ansi16) if n < 16 => Some(NAMED[n as usize]),
+            (Color::Fixed(n), ColorLevel::Ansi16) => Some(nearest_named(fixed_rgb(n))),
+            (color, _) => Some(color),
+        }
+    }
+
+    /// The usual RGB value of this color: the xterm value of named and
+    /// `Fixed` colors (see [`XTERM_PALETTE`]), or `None` for
+    /// [`Color::Default`], whose value depends on the terminal.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::{Color, Rgb};
+    ///
+    /// assert_eq!(Color::Fixed(196).to_rgb(), Some(Rgb::new(255, 0, 0)));
+    /// assert_eq!(Color::Blue.to_rgb(), Some(Rgb::new(0, 0, 238)));
+    /// assert_eq!(Color::Default.to_rgb(), None);
+    /// ```
+    pub fn to_rgb(self) -> Option<Rgb> {
+        color_rgb(self).map(|(r, g, b)| Rgb::new(r, g, b))
+    }
+}
+
+impl Rgb {
+    /// The entry of the color cube or gray ramp of the 256 color palette
+    /// which looks nearest to this color, as a [`Color::Fixed`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::{Color, Rgb};
+    ///
+    /// assert_eq!(Rgb::from_hex(0xff8700).to_fixed(), Color::Fixed(208));
+    /// assert_eq!(Rgb::gray(100).to_fixed(), Color::Fixed(241));
+    /// ```
+    pub fn to_fixed(&self) -> Color {
+        Color::Fixed(nearest_fixed((self.r, self.g, self.b)))
+    }
+
+    /// The named color which looks nearest to this color.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::{Color, Rgb};
+    ///
+    /// assert_eq!(Rgb::from_hex(0xff8700).to_ansi16(), Color::Yellow);
+    /// assert_eq!(Rgb::from_hex(0x1e90ff).to_ansi16(), Color::LightBlue);
+    /// ```
+    pub fn to_ansi16(&self) -> Color {
+        nearest_named((self.r, self.g, self.b))
+    }
+}
+
+impl Style {
+    /// A copy of this style with its colors downgraded to `level` (see
+    /// [`Color::downgrade`]). Other properties, such as bold, are kept.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::{Color, ColorLevel};
+    ///
+    /// let style = Color::Rgb(0, 0, 0).on(Color::Rgb(255, 255, 255)).bold();
+    /// assert_eq!(
+    ///     style.adapt_to(ColorLevel::Ansi16),
+    ///     Color::Black.on(Color::LightGray).bold()
+    /// );
+    /// ```
+    pub fn adapt_to(self, level: ColorLevel) -> Style {
+        let mut style = self;
+        style.coloring.fg = self.coloring.fg.and_then(|c| c.downgrade(level));
+        style.coloring.bg = self.coloring.bg.and_then(|c| c.downgrade(level));
+        style.coloring.underline = self.coloring.underline.and_then(|c| c.downgrade(level));
+        style
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn level_for(vars: &[(&str, &str)]) -> ColorLevel {
+        level_from(|name| {
+            vars.iter()
+                .find(|(var, _)| *var == name)
+                .map(|(_, value)| value.to_string())
+        })
+    }
+
+    #[test]
+    fn detection() {
+        assert_eq!(
+            level_for(&[("COLORTERM", "truecolor"), ("TERM", "xterm")]),
+            ColorLevel::TrueColor
+        );
+        assert_eq!(
+            level_for(&[("TERM", "xterm-256color")]),
+            ColorLevel::Ansi256
+        );
+        assert_eq!(level_for(&[("TERM", "xterm")]), ColorLevel::Ansi16);
+        assert_eq!(level_for(&[("TERM", "dumb")]), ColorLevel::NoColor);
+    }
+
+    #[test]
+    fn palette_round_trips() {
+        for n in 16..=255 {
+            assert_eq!(nearest_fixed(fixed_rgb(n)), n, "color {}", n);
+        }
+        for (color, rgb) in NAMED.iter().zip(NAMED_RGB) {
+            assert_eq!(nearest_named(rgb), *color);
+        }
+        for (n, rgb) in XTERM_PALETTE.iter().enumerate() {
+            assert_eq!(Color::Fixed(n as u8).to_rgb(), Some(*rgb));
+        }
+    }
+
+    #[test]
+    fn grays() {
+        assert_eq!(nearest_fixed((128, 128, 128)), 244);
+        assert_eq!(nearest_fixed((0, 0, 0)), 16);
+        assert_eq!(
+            Color::Rgb(130, 130, 130).downgrade(ColorLevel::Ansi16),
+            Some(Color::DarkGray)
+        );
+    }
+}
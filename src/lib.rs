@@ -73,6 +73,9 @@
 //! The complete list of styles you can use are: [`bold`], [`dimmed`], [`italic`],
 //! [`underline`], [`blink`], [`reverse`], [`hidden`], [`strikethrough`], and [`on`] for
 //! background colors.
+//! Terminals such as kitty, WezTerm and foot also support
+//! [`double_underline`], [`undercurl`], [`overline`] and colored underlines
+//! ([`underline_color`]), which other terminals ignore.
 //!
 //! In some cases, you may find it easier to change the foreground on an
 //! existing `Style` rather than starting from the appropriate `Color`.
@@ -226,6 +229,10 @@
 //! [`reverse`]: struct.Style.html#method.reverse
 //! [`hidden`]: struct.Style.html#method.hidden
 //! [`strikethrough`]: struct.Style.html#method.strikethrough
+//! [`double_underline`]: struct.Style.html#method.double_underline
+//! [`undercurl`]: struct.Style.html#method.undercurl
+//! [`overline`]: struct.Style.html#method.overline
+//! [`underline_color`]: struct.Style.html#method.underline_color
 //! [`fg`]: struct.Style.html#method.fg
 //! [`on`]: struct.Style.html#method.on
 
@@ -244,12 +251,21 @@ doc_comment::doctest!("../README.md");
 /// relevant ANSI escape codes.
 pub mod ansi;
 pub mod utils;
-pub use ansi::{Infix, Prefix, Suffix};
+pub use ansi::{Infix, Prefix, PrefixStr, Suffix, RESET, RESET_BYTES};
 
 mod style;
-pub use style::{Color, Style};
+pub use style::{Color, FormatFlags, Style};
+
+pub mod bits;
 
 mod difference;
+pub use difference::{Difference, OptimizeFor, PreciseDelta, StyleDelta};
+
+/// Turning the escape codes of styles on and off for the whole program.
+mod enabled;
+pub use enabled::{enabled_from_env, is_enabled, set_enabled};
+#[cfg(feature = "tty")]
+pub use enabled::{should_color, Stream};
 /// Functionality to write an ANSI string to [`AnyWrite`] implementors.
 mod display;
 pub use display::*;
@@ -261,9 +277,11 @@ pub use write::*;
 
 /// Helpers for managing MS Windows related details.
 mod windows;
+#[cfg(windows)]
 pub use crate::windows::*;
 
 /// Helpers for debugging ANSI strings.
+#[allow(dead_code)]
 mod debug;
 
 /// Helpers for creating color gradients.
@@ -273,3 +291,236 @@ pub use gradient::*;
 /// Helpers for specifying RGB colors.
 mod rgb;
 pub use rgb::*;
+
+/// Measuring the number of terminal columns occupied by styled content.
+mod width;
+#[cfg(feature = "unicode-bidi")]
+pub use width::BidiIsolation;
+pub use width::{ContinuationMarker, MarkerPlacement, MeasureCache, MeasureStats, WidthPolicy};
+
+/// Detecting the size of the attached terminal.
+pub mod terminal;
+pub use terminal::*;
+
+/// Builders for multi-line prompts with left, center and right aligned
+/// segments.
+pub mod prompt;
+pub use prompt::{powerline, Alignment, DynamicSegment, PromptLayout, PromptLine, Shrink};
+
+/// Styling the lines of a reader, for building log colorizers and similar
+/// filters.
+pub mod colorize;
+pub use colorize::{colorize_lines, ColorizeReader};
+
+/// Removing ANSI escape sequences from text, including streamed text.
+pub mod strip;
+
+/// Duplicating output to a terminal and to an escape-free log.
+mod tee;
+pub use tee::TeeWriter;
+
+/// Streaming output in a changing style, writing only the codes needed.
+mod stream;
+pub use stream::AnsiWriter;
+
+/// Recording what is written as styled text, for testing rendering code.
+mod capture;
+pub use capture::{CaptureWriter, Captured};
+
+/// Emulating a terminal, for end-to-end tests of rendering code.
+pub mod mock;
+pub use mock::MockTerm;
+
+/// Recording frames of output, and exporting them as asciicast recordings.
+mod record;
+pub use record::{Frame, Recorder};
+
+/// Painting any value which implements `Display`, without formatting it
+/// first.
+mod painted;
+pub use painted::Painted;
+
+/// Formatting numbers with styles chosen from their values.
+pub mod number;
+pub use number::NumberStyle;
+
+/// Rendering aligned key/value blocks.
+pub mod kv;
+pub use kv::{kv_block, KvBlock};
+
+/// Rendering trees with styled branch guides.
+pub mod tree;
+pub use tree::{TreeNode, TreeRenderer};
+
+/// Rendering compiler-style snippets of source code with labelled spans.
+pub mod snippet;
+pub use snippet::{Label, Snippet};
+
+pub mod control;
+
+/// Paging styled documents a screen at a time.
+mod pager;
+pub use pager::Pager;
+
+/// Rendering tables with cells spanning several columns and rows, and
+/// building them from records of text.
+pub mod table;
+pub use table::{RecordStyles, Table, TableCell};
+
+/// Describing the capabilities of the terminal being written to.
+pub mod profile;
+pub use profile::TerminalProfile;
+
+/// Detecting the colors a terminal supports, and downgrading colors to them.
+pub mod level;
+pub use level::{ColorLevel, XTERM_PALETTE};
+
+pub mod bindings;
+
+/// Box drawing characters, with an ASCII fallback.
+pub mod boxes;
+pub use boxes::BoxChars;
+
+/// Drawing sparklines and bars with block characters.
+pub mod chart;
+pub use chart::{hbar, sparkline, ChartStyle};
+
+/// Icons for prompts and listings, with fallbacks for terminals without
+/// Nerd Fonts.
+#[cfg(feature = "icons")]
+pub mod icons;
+#[cfg(feature = "icons")]
+pub use icons::Icon;
+
+/// Replacing non-ASCII punctuation and box drawing characters, for consoles
+/// which can only show ASCII.
+pub mod ascii;
+
+/// Transforming the text of styled strings, such as changing its case,
+/// without touching their escape codes.
+mod transform;
+pub use transform::WhitespaceStyle;
+
+/// Cutting styled strings at the positions of their visible characters.
+mod slice;
+
+/// Wrapping and truncating styled strings to a width, keeping their styles.
+mod wrap;
+pub use wrap::WrappedRow;
+
+/// Mapping columns of the output back to spans of the source it shows.
+mod span;
+pub use span::SourceSpan;
+
+/// Helpers for writing to terminals in raw mode.
+pub mod raw;
+pub use raw::CrlfWriter;
+#[cfg(feature = "raw_mode")]
+pub use raw::RawModeGuard;
+
+/// Transcoding output for terminals which do not use UTF-8.
+#[cfg(feature = "encoding_rs")]
+mod encoding;
+#[cfg(feature = "encoding_rs")]
+pub use encoding::EncodingWriter;
+
+/// Writing byte strings to asynchronous writers.
+#[cfg(feature = "async")]
+mod async_write;
+
+/// Shell-quoting words with styled quotes and escapes.
+pub mod quote;
+pub use quote::{quote_styled, ShellQuote};
+
+/// The default styles nushell uses for each kind of value.
+pub mod nu_defaults;
+
+/// Sets of named styles, which can be read from config strings.
+pub mod theme;
+pub use theme::{Theme, ThemeError};
+
+/// Checks for styles which are invisible or hard to read.
+pub mod lint;
+pub use lint::StyleLint;
+
+/// Reading and writing styles and colors as text, like `bold red on blue`.
+mod spec;
+pub use spec::ParseStyleError;
+
+/// Laying overlapping styles, such as selections and search matches, over
+/// styled text.
+pub mod layer;
+pub use layer::{Overlay, StyleLayering};
+
+/// Highlighting the input line of a line editor: styled spans, matching
+/// brackets and ghost text.
+mod highlight;
+pub use highlight::{
+    bracket_overlays, ghost_text, highlight_input, highlight_input_into, GhostText,
+};
+
+/// Rendering the rows of completion menus.
+pub mod menu;
+pub use menu::{menu_row, MenuStyle};
+
+/// Styled diffs of text, for readable test assertion failures.
+pub mod diff;
+pub use diff::{word_diff, DiffLayout, DiffStyle};
+
+#[cfg(any(feature = "anstyle", feature = "crossterm"))]
+pub mod interop;
+
+/// Rendering hyperlinks for terminals which do not support them, and as
+/// footnotes.
+pub mod link;
+pub use link::{FootnoteCollector, FootnoteMarkers, LinkFallback};
+
+/// Encoding the payloads of commands such as clipboard copies.
+mod osc;
+
+/// Sharing the content of repeated segments.
+pub mod intern;
+pub use intern::ContentInterner;
+
+/// Writing styled strings nested in the `fmt::Arguments` of other styled
+/// strings.
+pub mod nested;
+
+/// Observing the escape sequences written, for metrics and debugging.
+pub mod instrument;
+
+/// Joining the items of iterators with styled separators.
+pub mod join;
+pub use join::PaintJoin;
+
+/// Messages whose styled placeholders are filled into translatable
+/// templates.
+pub mod message;
+pub use message::{Message, MessageError};
+
+/// Keeping the title of the terminal window in sync with templates.
+pub mod title;
+pub use title::{TitleTemplate, TitleValues};
+
+/// Parsing text containing escape sequences back into styled segments.
+pub mod parse;
+
+/// Rendering a sequence once to several outputs, each in its own format.
+pub mod multi;
+pub use multi::{RenderTarget, TargetFormat};
+
+/// Tracking how far writing got, for restoring the terminal after a failed
+/// write.
+mod recover;
+pub use recover::{PartialWrite, Written};
+
+/// Describing styled documents for serialization, and as JSON.
+#[cfg(feature = "serde")]
+mod json;
+
+/// Rendering styled text in formats other than escape codes.
+#[cfg(feature = "html")]
+pub mod render;
+
+#[cfg(feature = "ffi")]
+pub mod ffi;
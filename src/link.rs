@@ -0,0 +1,310 @@
+use crate::{AnsiString, AnsiStrings, Style, TerminalProfile};
+
+/// How to render hyperlinks for terminals which do not support them (see
+/// [`TerminalProfile::hyperlinks`]).
+///
+/// # Examples
+///
+/// ```
+/// use nu_ansi_term::{AnsiStrings, LinkFallback, Style, TerminalProfile};
+/// use nu_ansi_term::utils::unstyle;
+///
+/// let docs = AnsiStrings([
+///     "see ".into(),
+///     Style::new().underline().paint("the docs").hyperlink("https://docs.rs"),
+/// ]);
+/// let profile = TerminalProfile::new().without_hyperlinks();
+///
+/// let inline = LinkFallback::default().apply(&docs, &profile);
+/// assert_eq!(unstyle(&inline), "see the docs (https://docs.rs)");
+///
+/// let footnotes = LinkFallback::Footnotes(Style::new()).apply(&docs, &profile);
+/// assert_eq!(unstyle(&footnotes), "see the docs[1]\n[1]: https://docs.rs\n");
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LinkFallback {
+    /// Keep the text of links, dropping their URLs.
+    Drop,
+    /// Follow the text of each link with its URL in parentheses, painted in
+    /// the given style.
+    Parenthesized(Style),
+    /// Follow the text of each link with a numbered reference like `[1]`,
+    /// and list the URLs after the output, painted in the given style. Links
+    /// to the same URL share a number.
+    Footnotes(Style),
+}
+
+impl Default for LinkFallback {
+    /// URLs in dimmed parentheses.
+    fn default() -> Self {
+        LinkFallback::Parenthesized(Style::new().dimmed())
+    }
+}
+
+impl LinkFallback {
+    /// Render the links of `strings` in this way if `profile` does not
+    /// support hyperlinks; otherwise `strings` are returned unchanged. Lazy
+    /// styles are resolved for `profile` either way.
+    ///
+    /// Consecutive segments linking to the same URL are treated as a single
+    /// link.
+    pub fn apply<'a>(
+        self,
+        strings: &AnsiStrings<'a>,
+        profile: &TerminalProfile,
+    ) -> AnsiStrings<'a> {
+        let strings = strings.resolved(profile);
+        if profile.hyperlinks {
+            return strings;
+        }
+
+        match self {
+            LinkFallback::Drop => replace_links(&strings, |_| None),
+            LinkFallback::Parenthesized(style) => {
+                replace_links(&strings, |url| Some(style.paint(format!(" ({})", url))))
+            }
+            LinkFallback::Footnotes(style) => FootnoteCollector::new()
+                .markers(FootnoteMarkers::Bracketed)
+                .marker_style(style)
+                .list_style(style)
+                .render(&strings),
+        }
+    }
+}
+
+/// How [`FootnoteCollector`] refers to footnotes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FootnoteMarkers {
+    /// Superscript numbers, like `text¹`, listed as `¹ url`.
+    Superscript,
+    /// Numbers in brackets, like `text[1]`, listed as `[1]: url`. These
+    /// only use ASCII.
+    Bracketed,
+}
+
+impl FootnoteMarkers {
+    fn reference(self, number: usize) -> String {
+        match self {
+            FootnoteMarkers::Superscript => number
+                .to_string()
+                .chars()
+                .map(|digit| SUPERSCRIPT_DIGITS[digit as usize - '0' as usize])
+                .collect(),
+            FootnoteMarkers::Bracketed => format!("[{}]", number),
+        }
+    }
+
+    fn entry(self, number: usize, url: &str) -> String {
+        match self {
+            FootnoteMarkers::Superscript => format!("{} {}", self.reference(number), url),
+            FootnoteMarkers::Bracketed => format!("[{}]: {}", number, url),
+        }
+    }
+}
+
+const SUPERSCRIPT_DIGITS: [char; 10] = ['⁰', '¹', '²', '³', '⁴', '⁵', '⁶', '⁷', '⁸', '⁹'];
+
+/// Renderer which replaces the hyperlinks of a document with numbered
+/// references, and lists their URLs after it, for exporting styled text as
+/// plain text or writing it to terminals without hyperlink support.
+///
+/// Links to the same URL share a number, and consecutive segments linking
+/// to the same URL are treated as a single link.
+///
+/// # Examples
+///
+/// ```
+/// use nu_ansi_term::{AnsiStrings, FootnoteCollector};
+/// use nu_ansi_term::utils::unstyle;
+/// use nu_ansi_term::Color::Blue;
+///
+/// let doc = AnsiStrings([
+///     Blue.paint("nushell").hyperlink("https://www.nushell.sh"),
+///     " uses ".into(),
+///     Blue.paint("nu-ansi-term").hyperlink("https://docs.rs/nu-ansi-term"),
+///     ".\n".into(),
+/// ]);
+/// let exported = FootnoteCollector::new().render(&doc);
+/// assert_eq!(
+///     unstyle(&exported),
+///     "nushell¹ uses nu-ansi-term².\n¹ https://www.nushell.sh\n² https://docs.rs/nu-ansi-term\n"
+/// );
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FootnoteCollector {
+    markers: FootnoteMarkers,
+    marker_style: Style,
+    list_style: Style,
+}
+
+impl Default for FootnoteCollector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FootnoteCollector {
+    /// Create a collector using dimmed superscript references and a dimmed
+    /// list.
+    pub const fn new() -> Self {
+        Self {
+            markers: FootnoteMarkers::Superscript,
+            marker_style: Style::new().dimmed(),
+            list_style: Style::new().dimmed(),
+        }
+    }
+
+    /// Refer to footnotes with `markers`.
+    pub const fn markers(mut self, markers: FootnoteMarkers) -> Self {
+        self.markers = markers;
+        self
+    }
+
+    /// Paint the references after links in `style`.
+    pub const fn marker_style(mut self, style: Style) -> Self {
+        self.marker_style = style;
+        self
+    }
+
+    /// Paint the list of footnotes in `style`.
+    pub const fn list_style(mut self, style: Style) -> Self {
+        self.list_style = style;
+        self
+    }
+
+    /// Replace the links of `strings` with references, followed by the list
+    /// of footnotes (one per line, starting on a new line) if there are any.
+    pub fn render<'a>(&self, strings: &AnsiStrings<'a>) -> AnsiStrings<'a> {
+        let mut footnotes: Vec<String> = Vec::new();
+        let mut rendered = replace_links(strings, |url| {
+            let number = match footnotes.iter().position(|known| known == url) {
+                Some(ix) => ix + 1,
+                None => {
+                    footnotes.push(url.to_string());
+                    footnotes.len()
+                }
+            };
+            Some(self.marker_style.paint(self.markers.reference(number)))
+        })
+        .iter()
+        .cloned()
+        .collect::<Vec<_>>();
+
+        let ends_with_newline = rendered
+            .iter()
+            .rev()
+            .find(|segment| !segment.is_control())
+            .map_or(true, |segment| {
+                segment.content().to_string().ends_with('\n')
+            });
+        if !footnotes.is_empty() && !ends_with_newline {
+            rendered.push(AnsiString::from("\n"));
+        }
+        for (ix, url) in footnotes.iter().enumerate() {
+            rendered.push(self.list_style.paint(self.markers.entry(ix + 1, url)));
+            rendered.push(AnsiString::from("\n"));
+        }
+        AnsiStrings(rendered)
+    }
+}
+
+/// Remove the links of `strings`, following the last segment of every link
+/// with what `reference` returns for its URL.
+fn replace_links<'a>(
+    strings: &AnsiStrings<'a>,
+    mut reference: impl FnMut(&str) -> Option<AnsiString<'a>>,
+) -> AnsiStrings<'a> {
+    let mut rendered: Vec<AnsiString<'a>> = Vec::new();
+    let segments: Vec<&AnsiString<'a>> = strings.iter().collect();
+    for (ix, segment) in segments.iter().enumerate() {
+        let url = segment.url_string().map(ToString::to_string);
+        rendered.push((*segment).clone().without_link());
+        let url = match url {
+            Some(url) => url,
+            None => continue,
+        };
+        let link_continues = segments
+            .get(ix + 1)
+            .and_then(|next| next.url_string())
+            .map_or(false, |next| next.to_string() == url);
+        if !link_continues {
+            rendered.extend(reference(&url));
+        }
+    }
+    AnsiStrings(rendered)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::unstyle;
+    use crate::Color::*;
+
+    fn links() -> AnsiStrings<'static> {
+        AnsiStrings([
+            Red.paint("a").hyperlink("https://a.example"),
+            Blue.paint("b").hyperlink("https://a.example"),
+            " ".into(),
+            Green.paint("c").hyperlink("https://c.example"),
+            " ".into(),
+            Green.paint("d").hyperlink("https://a.example"),
+        ])
+    }
+
+    #[test]
+    fn capable_terminals_keep_links() {
+        let kept = LinkFallback::Drop.apply(&links(), &TerminalProfile::new());
+        assert_eq!(kept.to_string(), links().to_string());
+    }
+
+    #[test]
+    fn fallbacks() {
+        let profile = TerminalProfile::new().without_hyperlinks();
+        let dropped = LinkFallback::Drop.apply(&links(), &profile);
+        assert_eq!(
+            dropped.iter().filter(|s| s.url_string().is_some()).count(),
+            0
+        );
+        assert_eq!(unstyle(&dropped), "ab c d");
+
+        let inline = LinkFallback::Parenthesized(Style::new()).apply(&links(), &profile);
+        assert_eq!(
+            unstyle(&inline),
+            "ab (https://a.example) c (https://c.example) d (https://a.example)"
+        );
+
+        let footnotes = LinkFallback::Footnotes(Style::new().dimmed()).apply(&links(), &profile);
+        assert_eq!(
+            unstyle(&footnotes),
+            "ab[1] c[2] d[1]\n[1]: https://a.example\n[2]: https://c.example\n"
+        );
+        assert_eq!(
+            footnotes.iter().nth(2).unwrap().style_ref(),
+            &Style::new().dimmed()
+        );
+    }
+
+    #[test]
+    fn superscript_footnotes() {
+        let doc = AnsiStrings(
+            (1..=12)
+                .map(|n| {
+                    Red.paint("x")
+                        .hyperlink_content(format!("https://{}.example", n))
+                })
+                .collect::<Vec<_>>(),
+        );
+        let exported = FootnoteCollector::new()
+            .list_style(Blue.normal())
+            .render(&doc);
+        let text = unstyle(&exported);
+        assert!(text.starts_with("x¹x²x³x⁴x⁵x⁶x⁷x⁸x⁹x¹⁰x¹¹x¹²\n¹ https://1.example\n"));
+        assert!(text.ends_with("\n¹² https://12.example\n"));
+        assert!(exported.iter().all(|s| s.url_string().is_none()));
+        assert_eq!(
+            exported.iter().nth(23).unwrap().style_ref(),
+            &Style::new().dimmed()
+        );
+        assert_eq!(exported.iter().nth(25).unwrap().style_ref(), &Blue.normal());
+    }
+}
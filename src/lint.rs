@@ -0,0 +1,154 @@
+use crate::level::color_rgb;
+use crate::{Color, Rgb, Style, Theme};
+use std::fmt;
+
+/// Styles whose colors have a lower contrast ratio than this are reported
+/// as [`StyleLint::LowContrast`]. It is the ratio the WCAG asks of large
+/// text.
+const MIN_CONTRAST: f32 = 3.0;
+
+/// A problem with a style which users should be told about, such as text
+/// which is invisible or hard to read. Its `Display` impl describes it in a
+/// sentence fit for a warning.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum StyleLint {
+    /// The foreground and background are the same color.
+    SameColors(Color),
+    /// The foreground and background are different colors, but with a
+    /// contrast ratio (from 1 to 21) too low to read easily.
+    LowContrast {
+        /// The contrast ratio of the colors, as a WCAG contrast ratio.
+        ratio: f32,
+    },
+    /// The text is hidden, so its colors are never seen.
+    HiddenWithColors,
+    /// The text blinks, which is distracting, and shown by few terminals.
+    Blink,
+}
+
+impl fmt::Display for StyleLint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StyleLint::SameColors(color) => write!(
+                f,
+                "the foreground and background are both {}, so the text is invisible",
+                color
+            ),
+            StyleLint::LowContrast { ratio } => write!(
+                f,
+                "the foreground and background have a contrast of only {:.1}:1, which is hard to read",
+                ratio
+            ),
+            StyleLint::HiddenWithColors => {
+                f.write_str("the text is hidden, so its colors are never seen")
+            }
+            StyleLint::Blink => {
+                f.write_str("blinking text is distracting, and many terminals do not show it")
+            }
+        }
+    }
+}
+
+impl Style {
+    /// The problems with this style, if any.
+    ///
+    /// Colors are compared by their usual (xterm) values, so a terminal with
+    /// its own palette may show them differently; [`Color::Default`] is
+    /// never compared.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::StyleLint;
+    /// use nu_ansi_term::Color::{Blue, Fixed, Red, White};
+    ///
+    /// assert_eq!(White.on(Blue).lint(), []);
+    /// assert_eq!(Red.on(Red).lint(), [StyleLint::SameColors(Red)]);
+    /// assert!(matches!(Fixed(236).on(Fixed(235)).lint()[..], [StyleLint::LowContrast { .. }]));
+    /// assert_eq!(
+    ///     Red.on(Red).lint()[0].to_string(),
+    ///     "the foreground and background are both red, so the text is invisible"
+    /// );
+    /// ```
+    pub fn lint(&self) -> Vec<StyleLint> {
+        let mut lints = Vec::new();
+        if let (Some(fg), Some(bg)) = (self.coloring.fg, self.coloring.bg) {
+            if let (Some(fg_rgb), Some(bg_rgb)) = (color_rgb(fg), color_rgb(bg)) {
+                if fg == bg || fg_rgb == bg_rgb {
+                    lints.push(StyleLint::SameColors(fg));
+                } else {
+                    let ratio = Rgb::from(fg_rgb).contrast_ratio(bg_rgb.into());
+                    if ratio < MIN_CONTRAST {
+                        lints.push(StyleLint::LowContrast { ratio });
+                    }
+                }
+            }
+        }
+        if self.is_hidden() && self.has_color() {
+            lints.push(StyleLint::HiddenWithColors);
+        }
+        if self.is_blink() {
+            lints.push(StyleLint::Blink);
+        }
+        lints
+    }
+}
+
+impl Theme {
+    /// The problems with the styles of this theme, with the names of the
+    /// styles they were found in, e.g. to warn users about their config.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::{StyleLint, Theme};
+    ///
+    /// let theme = Theme::parse("{ header: green_bold, hints: { fg: blue, bg: blue } }").unwrap();
+    /// for (name, lint) in theme.lint() {
+    ///     eprintln!("warning: in the style of {}, {}", name, lint);
+    /// }
+    /// assert_eq!(theme.lint().len(), 1);
+    /// assert_eq!(theme.lint()[0].0, "hints");
+    /// ```
+    pub fn lint(&self) -> Vec<(String, StyleLint)> {
+        self.iter()
+            .flat_map(|(name, style)| {
+                style
+                    .lint()
+                    .into_iter()
+                    .map(move |lint| (name.to_string(), lint))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Color::*;
+
+    #[test]
+    fn contrast_ratios() {
+        assert_eq!(Black.on(LightGray).lint(), []);
+        assert_eq!(Magenta.on(Purple).lint(), [StyleLint::SameColors(Magenta)]);
+        assert_eq!(
+            Fixed(16).on(Black).lint(),
+            [StyleLint::SameColors(Fixed(16))]
+        );
+        assert_eq!(Default.on(Default).lint(), []);
+        match Blue.on(Black).lint()[..] {
+            [StyleLint::LowContrast { ratio }] => assert!(ratio > 2.0 && ratio < 3.0),
+            ref lints => panic!("unexpected lints {:?}", lints),
+        }
+    }
+
+    #[test]
+    fn formats() {
+        assert_eq!(
+            Red.hidden().blink().lint(),
+            [StyleLint::HiddenWithColors, StyleLint::Blink]
+        );
+        assert_eq!(Style::new().hidden().lint(), []);
+    }
+}
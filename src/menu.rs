@@ -0,0 +1,136 @@
+use crate::highlight::highlight_input;
+use crate::{AnsiStrings, ContinuationMarker, Style};
+use std::ops::Range;
+
+/// The width and styles of the rows of a completion menu. See
+/// [`menu_row`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MenuStyle {
+    width: usize,
+    text: Style,
+    selected: Style,
+    matched: Style,
+    ellipsis: ContinuationMarker,
+}
+
+impl MenuStyle {
+    /// Rows `width` columns wide, showing the selected row in reverse video
+    /// and the parts of labels matching the input in bold.
+    pub const fn new(width: usize) -> Self {
+        Self {
+            width,
+            text: Style::new(),
+            selected: Style::new().reverse(),
+            matched: Style::new().bold(),
+            ellipsis: ContinuationMarker::ELLIPSIS,
+        }
+    }
+
+    /// Show the labels of rows in `style`.
+    pub const fn text(mut self, style: Style) -> Self {
+        self.text = style;
+        self
+    }
+
+    /// Show the whole of the selected row in `style`, laid over the style of
+    /// its text (see [`Style::merge`]).
+    pub const fn selected(mut self, style: Style) -> Self {
+        self.selected = style;
+        self
+    }
+
+    /// Show the parts of labels matching the input in `style`, laid over
+    /// the style of the row.
+    pub const fn matched(mut self, style: Style) -> Self {
+        self.matched = style;
+        self
+    }
+
+    /// End labels which are too wide for the row with `ellipsis`.
+    pub const fn ellipsis(mut self, ellipsis: ContinuationMarker) -> Self {
+        self.ellipsis = ellipsis;
+        self
+    }
+}
+
+/// Render a row of a completion menu: `label`, with the byte ranges of it
+/// in `matched` highlighted, cut to the width of the row (see
+/// [`AnsiStrings::truncate`]) or padded with spaces to fill it.
+///
+/// The padding is in the style of the row, so that the background of a
+/// selected row reaches across the whole menu.
+///
+/// # Examples
+///
+/// ```
+/// use nu_ansi_term::menu::{menu_row, MenuStyle};
+/// use nu_ansi_term::utils::unstyle;
+/// use nu_ansi_term::Color::{Green, Yellow};
+///
+/// let menu = MenuStyle::new(10).selected(Green.reverse()).matched(Yellow.underline());
+/// let row = menu_row("str length", &[0..3], true, &menu);
+/// assert_eq!(unstyle(&row), "str length");
+///
+/// let row = menu_row("into", &[0..2], false, &menu);
+/// assert_eq!(unstyle(&row), "into      ");
+/// assert_eq!(
+///     row.to_string(),
+///     "\x1b[4;33min\x1b[0mto      "
+/// );
+///
+/// let row = menu_row("str distance", &[], false, &menu);
+/// assert_eq!(unstyle(&row), "str dista…");
+/// ```
+pub fn menu_row(
+    label: &str,
+    matched: &[Range<usize>],
+    selected: bool,
+    style: &MenuStyle,
+) -> AnsiStrings<'static> {
+    let base = if selected {
+        style.text.merge(style.selected)
+    } else {
+        style.text
+    };
+    let highlight = base.merge(style.matched);
+
+    let mut matched = matched.to_vec();
+    matched.sort_by_key(|range| range.start);
+    let mut spans = Vec::with_capacity(2 * matched.len() + 1);
+    let mut pos = 0;
+    for range in matched {
+        spans.push((pos..range.start, base));
+        spans.push((range.start..range.end, highlight));
+        pos = pos.max(range.end);
+    }
+    spans.push((pos..label.len(), base));
+
+    let mut row = highlight_input(label, &spans).truncate(style.width, &style.ellipsis);
+    let padding = style.width.saturating_sub(row.width());
+    if padding > 0 {
+        row.push(base.paint(" ".repeat(padding)));
+    }
+    row
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::unstyle;
+    use crate::Color::*;
+
+    #[test]
+    fn selection_fills_the_row() {
+        let menu = MenuStyle::new(8)
+            .text(Blue.normal())
+            .matched(Style::new().bold());
+        let row = menu_row("ls", &[1..2, 0..1], true, &menu);
+        let styles: Vec<_> = row.iter().map(|s| *s.style_ref()).collect();
+        assert_eq!(styles, [Blue.bold().reverse(), Blue.reverse()]);
+        assert_eq!(unstyle(&row), "ls      ");
+
+        let wide = menu_row("日本語のラベル", &[0..3, 6..9], false, &menu);
+        assert_eq!(unstyle(&wide), "日本語… ");
+        assert_eq!(wide.width(), 8);
+    }
+}
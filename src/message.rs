@@ -0,0 +1,254 @@
+use crate::style::BasedOn;
+use crate::{AnsiString, AnsiStrings, Style};
+use std::borrow::Cow;
+use std::{error, fmt};
+
+/// A message built from a template with named placeholders, like
+/// `"copied {count} files to {dest}"`, and the styles and texts which fill
+/// them in.
+///
+/// The styles of the placeholders are kept apart from the template, so a
+/// translated template can put the placeholders in another order, or drop
+/// some, and still have them styled the same way. Braces are written in a
+/// template as `{{` and `}}`.
+///
+/// # Examples
+///
+/// ```
+/// use nu_ansi_term::{AnsiStrings, Message};
+/// use nu_ansi_term::Color::{Blue, Green};
+///
+/// let message = Message::new("{dest} now holds {count} files")
+///     .placeholder_style("count", Green.bold())
+///     .placeholder_style("dest", Blue.normal())
+///     .value("count", "3")
+///     .value("dest", "/tmp");
+/// assert_eq!(
+///     message.render().unwrap().to_string(),
+///     AnsiStrings([
+///         Blue.paint("/tmp"),
+///         " now holds ".into(),
+///         Green.bold().paint("3"),
+///         " files".into(),
+///     ])
+///     .to_string()
+/// );
+/// ```
+#[derive(Clone, Debug)]
+pub struct Message<'a> {
+    template: Cow<'a, str>,
+    style: Style,
+    placeholder_styles: Vec<(Cow<'a, str>, Style)>,
+    values: Vec<(Cow<'a, str>, AnsiString<'a>)>,
+}
+
+impl<'a> Message<'a> {
+    /// Create a message from `template`, with no values yet.
+    pub fn new(template: impl Into<Cow<'a, str>>) -> Self {
+        Self {
+            template: template.into(),
+            style: Style::new(),
+            placeholder_styles: Vec::new(),
+            values: Vec::new(),
+        }
+    }
+
+    /// Paint the text of the template around the placeholders in `style`.
+    pub fn style(mut self, style: Style) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Paint the value of the placeholder called `name` in `style`. A value
+    /// which is already painted keeps its own style, on top of this one.
+    pub fn placeholder_style(mut self, name: impl Into<Cow<'a, str>>, style: Style) -> Self {
+        let name = name.into();
+        self.placeholder_styles.retain(|(other, _)| *other != name);
+        self.placeholder_styles.push((name, style));
+        self
+    }
+
+    /// Fill in the placeholder called `name` with `value`, replacing any
+    /// value it already had.
+    pub fn value(
+        mut self,
+        name: impl Into<Cow<'a, str>>,
+        value: impl Into<AnsiString<'a>>,
+    ) -> Self {
+        let name = name.into();
+        self.values.retain(|(other, _)| *other != name);
+        self.values.push((name, value.into()));
+        self
+    }
+
+    /// The names of the placeholders in the template, in the order they
+    /// appear, e.g. to check a translation uses the same ones.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::Message;
+    ///
+    /// let message = Message::new("{{not}} {b} then {a}");
+    /// assert_eq!(message.placeholders().unwrap(), ["b", "a"]);
+    /// ```
+    pub fn placeholders(&self) -> Result<Vec<&str>, MessageError> {
+        let mut names = Vec::new();
+        for piece in parse_template(&self.template)? {
+            if let Piece::Placeholder(name) = piece {
+                names.push(name);
+            }
+        }
+        Ok(names)
+    }
+
+    /// The segments of this message: the text of the template, and the
+    /// values of its placeholders in their styles.
+    ///
+    /// Fails if the template is malformed, or a placeholder has no value.
+    pub fn render(&self) -> Result<AnsiStrings<'a>, MessageError> {
+        let mut strings = Vec::new();
+        for piece in parse_template(&self.template)? {
+            match piece {
+                Piece::Text(text) => strings.push(self.style.paint(text)),
+                Piece::Placeholder(name) => {
+                    let (_, value) = self
+                        .values
+                        .iter()
+                        .find(|(other, _)| other == name)
+                        .ok_or_else(|| MessageError::MissingValue(name.to_string()))?;
+                    let style = self
+                        .placeholder_styles
+                        .iter()
+                        .find(|(other, _)| other == name)
+                        .map_or(self.style, |&(_, style)| style.rebase_on(self.style));
+                    strings.push(value.clone().rebase_on(style));
+                }
+            }
+        }
+        Ok(AnsiStrings(strings))
+    }
+}
+
+/// A part of a template.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Piece<'t> {
+    /// Text, with its escaped braces unescaped.
+    Text(String),
+    /// A placeholder, with the name it is filled in by.
+    Placeholder(&'t str),
+}
+
+/// Split `template` into its text and placeholders.
+pub(crate) fn parse_template(template: &str) -> Result<Vec<Piece<'_>>, MessageError> {
+    let mut pieces = Vec::new();
+    let mut text = String::new();
+    let mut rest = template;
+    while let Some(ix) = rest.find(['{', '}'].as_ref()) {
+        text.push_str(&rest[..ix]);
+        let offset = template.len() - rest.len() + ix;
+        let brace = rest.as_bytes()[ix];
+        rest = &rest[ix + 1..];
+        if rest.as_bytes().first() == Some(&brace) {
+            text.push(brace as char);
+            rest = &rest[1..];
+        } else if brace == b'}' {
+            return Err(MessageError::Syntax { offset });
+        } else {
+            let end = rest
+                .find(['{', '}'].as_ref())
+                .filter(|&end| rest.as_bytes()[end] == b'}')
+                .ok_or(MessageError::Syntax { offset })?;
+            if !text.is_empty() {
+                pieces.push(Piece::Text(std::mem::take(&mut text)));
+            }
+            pieces.push(Piece::Placeholder(rest[..end].trim()));
+            rest = &rest[end + 1..];
+        }
+    }
+    text.push_str(rest);
+    if !text.is_empty() {
+        pieces.push(Piece::Text(text));
+    }
+    Ok(pieces)
+}
+
+/// An error rendering a [`Message`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MessageError {
+    /// The placeholder with this name has no value.
+    MissingValue(String),
+    /// The brace at byte `offset` of the template is not matched.
+    Syntax {
+        /// Byte offset of the brace in the template.
+        offset: usize,
+    },
+}
+
+impl fmt::Display for MessageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MessageError::MissingValue(name) => write!(f, "no value for placeholder {}", name),
+            MessageError::Syntax { offset } => write!(f, "unmatched brace at byte {}", offset),
+        }
+    }
+}
+
+impl error::Error for MessageError {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Color::*;
+
+    #[test]
+    fn templates() {
+        assert_eq!(
+            parse_template("a {{b}} { c }d").unwrap(),
+            [
+                Piece::Text("a {b} ".to_string()),
+                Piece::Placeholder("c"),
+                Piece::Text("d".to_string()),
+            ]
+        );
+        assert_eq!(parse_template("{x}{y}").unwrap().len(), 2);
+        assert_eq!(
+            parse_template("a } b"),
+            Err(MessageError::Syntax { offset: 2 })
+        );
+        assert_eq!(
+            parse_template("a {b"),
+            Err(MessageError::Syntax { offset: 2 })
+        );
+        assert_eq!(
+            parse_template("é {b{c}"),
+            Err(MessageError::Syntax { offset: 3 })
+        );
+    }
+
+    #[test]
+    fn styles() {
+        let message = Message::new("{{{name}}} is {state}")
+            .style(Style::new().italic())
+            .placeholder_style("name", Blue.normal())
+            .value("name", Red.bold().paint("x"))
+            .value("state", "ok");
+        assert_eq!(
+            message.render().unwrap().to_string(),
+            AnsiStrings([
+                Style::new().italic().paint("{"),
+                Red.bold().italic().paint("x"),
+                Style::new().italic().paint("} is "),
+                Style::new().italic().paint("ok"),
+            ])
+            .to_string()
+        );
+        assert_eq!(
+            Message::new("{a} {b}")
+                .value("a", "1")
+                .render()
+                .unwrap_err(),
+            MessageError::MissingValue("b".to_string())
+        );
+    }
+}
@@ -0,0 +1,491 @@
+//! An emulated terminal, for end-to-end tests of prompts, tables and other
+//! rendering code.
+//!
+//! A [`MockTerm`] interprets what is written to it as a terminal of a given
+//! size and [`TerminalProfile`] would: text is laid out in cells, wrapping at
+//! the right edge and scrolling at the bottom, SGR codes set the style of
+//! the cells written after them, and cursor movements and clears (see
+//! [`control`](crate::control)) act on the screen. Tests then check the
+//! contents and styles of the screen, rather than the escape codes which
+//! produced them.
+//!
+//! # Examples
+//!
+//! ```
+//! use nu_ansi_term::control::{ClearLine, CursorToColumn};
+//! use nu_ansi_term::mock::MockTerm;
+//! use nu_ansi_term::{AnsiStrings, TerminalProfile, TerminalSize};
+//! use nu_ansi_term::Color::Green;
+//!
+//! let size = TerminalSize { columns: 10, rows: 3 };
+//! let mut term = MockTerm::new(size, TerminalProfile::new());
+//! term.render(&AnsiStrings(["loading...".into()])).unwrap();
+//! term.render(&AnsiStrings([
+//!     CursorToColumn(0).into(),
+//!     ClearLine.into(),
+//!     Green.paint("done"),
+//! ]))
+//! .unwrap();
+//!
+//! assert_eq!(term.contents(), "done");
+//! assert_eq!(term.style_at(0, 0), Some(Green.normal()));
+//! assert_eq!(term.cursor(), (4, 0));
+//! ```
+
+use crate::parse::{apply_sgr, complete_len, tokenize, Token};
+use crate::{
+    AnsiStrings, FormatFlags, RenderTarget, Style, TerminalProfile, TerminalSize, WidthPolicy,
+};
+use std::io;
+
+/// A cell of the screen of a [`MockTerm`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cell {
+    /// The character in the cell, or `None` for the second column of a wide
+    /// character.
+    pub symbol: Option<char>,
+    /// The style the cell is shown in, as the terminal can show it: colors
+    /// it cannot display are downgraded, and dimming is dropped if it does
+    /// not show dimmed text.
+    pub style: Style,
+    /// The target of the hyperlink the cell is part of, if the terminal
+    /// supports hyperlinks.
+    pub link: Option<String>,
+}
+
+impl Cell {
+    /// An empty cell, with the background of `style`.
+    fn blank(style: Style) -> Self {
+        Self {
+            symbol: Some(' '),
+            style: Style::new().set_bg(style.coloring.bg),
+            link: None,
+        }
+    }
+}
+
+/// The cursor position and style saved by `ESC 7`.
+#[derive(Clone, Copy, Debug, Default)]
+struct Saved {
+    column: usize,
+    row: usize,
+    style: Style,
+}
+
+/// A terminal emulated in memory. See the [module documentation](self).
+///
+/// Output is interpreted as by a terminal in cooked mode, so `\n` moves the
+/// cursor to the start of the next line. Escape sequences the terminal does
+/// not act on (including those its profile does not support) are ignored,
+/// and sequences split across writes are put back together.
+#[derive(Clone, Debug)]
+pub struct MockTerm {
+    size: TerminalSize,
+    profile: TerminalProfile,
+    cells: Vec<Cell>,
+    /// The main screen, while the alternate screen is shown.
+    main_screen: Option<Vec<Cell>>,
+    column: usize,
+    row: usize,
+    style: Style,
+    link: Option<String>,
+    saved: Saved,
+    cursor_visible: bool,
+    title: Option<String>,
+    /// Written bytes which end in the middle of an escape sequence or a
+    /// character.
+    pending: Vec<u8>,
+}
+
+impl MockTerm {
+    /// An empty terminal of `size` with the capabilities of `profile`.
+    pub fn new(size: TerminalSize, profile: TerminalProfile) -> Self {
+        Self {
+            size,
+            profile,
+            cells: vec![Cell::blank(Style::new()); size.columns * size.rows],
+            main_screen: None,
+            column: 0,
+            row: 0,
+            style: Style::new(),
+            link: None,
+            saved: Saved::default(),
+            cursor_visible: true,
+            title: None,
+            pending: Vec::new(),
+        }
+    }
+
+    /// The size of the terminal.
+    pub fn size(&self) -> TerminalSize {
+        self.size
+    }
+
+    /// The capabilities of the terminal.
+    pub fn profile(&self) -> TerminalProfile {
+        self.profile
+    }
+
+    /// Write `strings` for this terminal's profile (see
+    /// [`AnsiStrings::render_all`]) and interpret them.
+    pub fn render(&mut self, strings: &AnsiStrings<'_>) -> io::Result<()> {
+        let profile = self.profile;
+        strings.render_all(&mut [RenderTarget::ansi(self, profile)])
+    }
+
+    /// The column and row of the cursor, both counted from 0.
+    pub fn cursor(&self) -> (usize, usize) {
+        (self.column, self.row)
+    }
+
+    /// Whether the cursor is shown.
+    pub fn cursor_visible(&self) -> bool {
+        self.cursor_visible
+    }
+
+    /// Whether the alternate screen is shown.
+    pub fn in_alternate_screen(&self) -> bool {
+        self.main_screen.is_some()
+    }
+
+    /// The title of the window, if one was set.
+    pub fn title(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
+
+    /// The cell at `column` and `row`.
+    pub fn cell(&self, column: usize, row: usize) -> Option<&Cell> {
+        if column < self.size.columns && row < self.size.rows {
+            self.cells.get(row * self.size.columns + column)
+        } else {
+            None
+        }
+    }
+
+    /// The style of the cell at `column` and `row`.
+    pub fn style_at(&self, column: usize, row: usize) -> Option<Style> {
+        self.cell(column, row).map(|cell| cell.style)
+    }
+
+    /// The text of `row`, without the spaces at its end.
+    pub fn row_text(&self, row: usize) -> String {
+        let start = row.min(self.size.rows) * self.size.columns;
+        let end = (start + self.size.columns).min(self.cells.len());
+        let text: String = self.cells[start..end]
+            .iter()
+            .filter_map(|cell| cell.symbol)
+            .collect();
+        text.trim_end_matches(' ').to_owned()
+    }
+
+    /// The text of the screen, a line for each row, without the spaces at
+    /// the end of rows or the empty rows at its end.
+    pub fn contents(&self) -> String {
+        let rows: Vec<String> = (0..self.size.rows).map(|row| self.row_text(row)).collect();
+        let used = rows
+            .iter()
+            .rposition(|row| !row.is_empty())
+            .map_or(0, |last| last + 1);
+        rows[..used].join("\n")
+    }
+
+    /// The style `style` is shown in by this terminal.
+    fn shown(&self, style: Style) -> Style {
+        let mut shown = style.adapt_to(self.profile.colors);
+        shown.prefix_before_reset = false;
+        if !self.profile.dim {
+            shown = shown.remove_formats(FormatFlags::DIMMED);
+        }
+        shown
+    }
+
+    /// Interpret the complete sequences and characters of `bytes`.
+    fn interpret(&mut self, bytes: &[u8]) {
+        for token in tokenize(bytes) {
+            match token {
+                Token::Text(range) => {
+                    for c in String::from_utf8_lossy(&bytes[range]).chars() {
+                        self.put(c);
+                    }
+                }
+                Token::Sgr(params) => {
+                    self.style = apply_sgr(self.style, &String::from_utf8_lossy(&bytes[params]));
+                }
+                Token::Osc(payload, _) => self.osc(&String::from_utf8_lossy(&bytes[payload])),
+                Token::Other(sequence) => self.control(&bytes[sequence]),
+            }
+        }
+    }
+
+    /// Write a character at the cursor, or act on a control character.
+    fn put(&mut self, c: char) {
+        match c {
+            '\n' => {
+                self.column = 0;
+                self.line_feed();
+            }
+            '\r' => self.column = 0,
+            '\t' => self.column = ((self.column / 8 + 1) * 8).min(self.last_column()),
+            '\x08' => self.column = self.column.saturating_sub(1),
+            _ => {
+                let width = WidthPolicy::new().char_width(c);
+                if width == 0 || self.size.columns == 0 || self.size.rows == 0 {
+                    return;
+                }
+                if self.column + width > self.size.columns {
+                    self.column = 0;
+                    self.line_feed();
+                }
+                let style = self.shown(self.style);
+                let link = self.link.clone();
+                let at = self.row * self.size.columns + self.column;
+                self.cells[at] = Cell {
+                    symbol: Some(c),
+                    style,
+                    link: link.clone(),
+                };
+                if width == 2 && self.column + 1 < self.size.columns {
+                    self.cells[at + 1] = Cell {
+                        symbol: None,
+                        style,
+                        link,
+                    };
+                }
+                // Like real terminals, the cursor stays in the last column
+                // until the next character wraps.
+                self.column += width;
+            }
+        }
+    }
+
+    /// The last column of the screen.
+    fn last_column(&self) -> usize {
+        self.size.columns.saturating_sub(1)
+    }
+
+    /// Move the cursor down a row, scrolling the screen up at the bottom.
+    fn line_feed(&mut self) {
+        if self.row + 1 < self.size.rows {
+            self.row += 1;
+        } else if self.size.rows > 0 {
+            self.cells.drain(..self.size.columns);
+            let blank = Cell::blank(self.shown(self.style));
+            self.cells
+                .extend(std::iter::repeat(blank).take(self.size.columns));
+        }
+    }
+
+    /// Clear the cells from `start` to `end` (indices into the screen).
+    fn clear(&mut self, start: usize, end: usize) {
+        let blank = Cell::blank(self.shown(self.style));
+        let end = end.min(self.cells.len());
+        for cell in &mut self.cells[start.min(end)..end] {
+            *cell = blank.clone();
+        }
+    }
+
+    /// Act on an OSC sequence the terminal supports.
+    fn osc(&mut self, payload: &str) {
+        if let Some(title) = payload
+            .strip_prefix("0;")
+            .or_else(|| payload.strip_prefix("2;"))
+        {
+            if self.profile.titles {
+                self.title = Some(title.to_owned());
+            }
+        } else if let Some(link) = payload.strip_prefix("8;") {
+            if self.profile.hyperlinks {
+                let url = link.split_once(';').map_or("", |(_, url)| url);
+                self.link = Some(url.to_owned()).filter(|url| !url.is_empty());
+            }
+        }
+    }
+
+    /// Act on an escape sequence other than SGR and OSC sequences.
+    fn control(&mut self, sequence: &[u8]) {
+        match sequence {
+            b"\x1b7" => {
+                self.saved = Saved {
+                    column: self.column,
+                    row: self.row,
+                    style: self.style,
+                }
+            }
+            b"\x1b8" => {
+                self.column = self.saved.column;
+                self.row = self.saved.row;
+                self.style = self.saved.style;
+            }
+            b"\x1b[?25h" => self.cursor_visible = true,
+            b"\x1b[?25l" => self.cursor_visible = false,
+            b"\x1b[?1049h" if self.main_screen.is_none() => {
+                let blank = vec![Cell::blank(Style::new()); self.cells.len()];
+                self.main_screen = Some(std::mem::replace(&mut self.cells, blank));
+            }
+            b"\x1b[?1049l" => {
+                if let Some(main_screen) = self.main_screen.take() {
+                    self.cells = main_screen;
+                }
+            }
+            [0x1B, b'[', params @ .., final_byte] => {
+                let params = String::from_utf8_lossy(params);
+                let numbers: Vec<usize> = params
+                    .split(';')
+                    .map(|param| param.parse().unwrap_or(0))
+                    .collect();
+                let n = |ix: usize| numbers.get(ix).copied().unwrap_or(0);
+                let count = |ix: usize| n(ix).max(1);
+                self.csi(*final_byte, n(0), count(0), count(1));
+            }
+            _ => {}
+        }
+    }
+
+    /// Act on a CSI sequence ending in `final_byte`, with its first
+    /// parameter `n` (0 if missing), and its first two parameters as counts
+    /// (1 if missing or 0).
+    fn csi(&mut self, final_byte: u8, n: usize, first: usize, second: usize) {
+        let columns = self.size.columns;
+        let last_row = self.size.rows.saturating_sub(1);
+        let row_start = self.row * columns;
+        let at = (row_start + self.column.min(self.last_column())).min(self.cells.len());
+        match final_byte {
+            b'A' => self.row = self.row.saturating_sub(first),
+            b'B' => self.row = (self.row + first).min(last_row),
+            b'C' => self.column = (self.column + first).min(self.last_column()),
+            b'D' => self.column = self.column.min(self.last_column()).saturating_sub(first),
+            b'E' => {
+                self.row = (self.row + first).min(last_row);
+                self.column = 0;
+            }
+            b'F' => {
+                self.row = self.row.saturating_sub(first);
+                self.column = 0;
+            }
+            b'G' => self.column = (first - 1).min(self.last_column()),
+            b'H' | b'f' => {
+                self.row = (first - 1).min(last_row);
+                self.column = (second - 1).min(self.last_column());
+            }
+            b'K' => match n {
+                0 => self.clear(at, row_start + columns),
+                1 => self.clear(row_start, at + 1),
+                _ => self.clear(row_start, row_start + columns),
+            },
+            b'J' => match n {
+                0 => self.clear(at, self.cells.len()),
+                1 => self.clear(0, at + 1),
+                _ => self.clear(0, self.cells.len()),
+            },
+            _ => {}
+        }
+    }
+}
+
+impl io::Write for MockTerm {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut bytes = std::mem::take(&mut self.pending);
+        bytes.extend_from_slice(buf);
+        let mut end = complete_len(&bytes);
+        if let Err(e) = std::str::from_utf8(&bytes[..end]) {
+            if e.error_len().is_none() {
+                end = e.valid_up_to();
+            }
+        }
+        self.interpret(&bytes[..end]);
+        self.pending = bytes.split_off(end);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::control::{
+        ClearLineRight, ClearScreen, CursorUp, EnterAlternateScreen, LeaveAlternateScreen, MoveTo,
+    };
+    use crate::Color::*;
+    use crate::{AnsiString, ColorLevel};
+    use std::io::Write;
+
+    fn term(columns: usize, rows: usize, profile: TerminalProfile) -> MockTerm {
+        MockTerm::new(TerminalSize { columns, rows }, profile)
+    }
+
+    #[test]
+    fn wraps_scrolls_and_clears() {
+        let mut term = term(4, 2, TerminalProfile::new());
+        term.render(&AnsiStrings([Red.paint("abcdef"), "\n日本".into()]))
+            .unwrap();
+        assert_eq!(term.contents(), "ef\n日本");
+        assert_eq!(term.row_text(1), "日本");
+        assert_eq!(term.cell(1, 1).unwrap().symbol, None);
+        assert_eq!(term.style_at(0, 0), Some(Red.normal()));
+        assert_eq!(term.style_at(0, 1), Some(Style::new()));
+
+        term.render(&AnsiStrings([
+            MoveTo(1, 0).into(),
+            AnsiString::control(ClearLineRight.to_string()),
+            CursorUp(3).into(),
+            Blue.on(Green).paint("x"),
+        ]))
+        .unwrap();
+        assert_eq!(term.contents(), "ex\n日本");
+        assert_eq!(term.cursor(), (2, 0));
+
+        term.write_all(b"\x1b[?1049h\x1b[44m\x1b[2").unwrap();
+        term.write_all(b"J\xe2\x9c").unwrap();
+        term.write_all(b"\x93").unwrap();
+        assert!(term.in_alternate_screen());
+        assert_eq!(term.contents(), "  ✓");
+        assert_eq!(term.style_at(0, 1), Some(Style::new().on(Blue)));
+        write!(term, "{}{}", ClearScreen, LeaveAlternateScreen).unwrap();
+        assert_eq!(term.contents(), "ex\n日本");
+        write!(term, "{}", EnterAlternateScreen).unwrap();
+        assert_eq!(term.contents(), "");
+    }
+
+    #[test]
+    fn shows_styles_as_the_profile_allows() {
+        let profile = TerminalProfile::new()
+            .without_dim()
+            .without_hyperlinks()
+            .with_colors(ColorLevel::Ansi16);
+        let mut term = term(20, 2, profile);
+        term.write_all(b"\x1b]2;build\x07\x1b[2;38;2;255;0;0mhot\x1b[0m")
+            .unwrap();
+        term.render(&AnsiStrings([Blue
+            .paint("link")
+            .hyperlink("https://example.com")]))
+            .unwrap();
+
+        assert_eq!(term.contents(), "hotlink");
+        assert_eq!(term.title(), Some("build"));
+        assert_eq!(term.style_at(0, 0), Some(LightRed.normal()));
+        assert_eq!(term.cell(3, 0).unwrap().link, None);
+
+        let mut linked = self::term(20, 1, TerminalProfile::new());
+        linked
+            .render(&AnsiStrings([Blue
+                .paint("link")
+                .hyperlink("https://example.com")]))
+            .unwrap();
+        assert_eq!(
+            linked.cell(0, 0).unwrap().link.as_deref(),
+            Some("https://example.com")
+        );
+    }
+
+    #[test]
+    fn empty_screens_ignore_text() {
+        for (columns, rows) in [(0, 0), (4, 0), (0, 2)] {
+            let mut term = term(columns, rows, TerminalProfile::new());
+            term.render(&AnsiStrings([Red.paint("abc\ndef")])).unwrap();
+            assert_eq!(term.row_text(0), "");
+        }
+    }
+}
@@ -0,0 +1,262 @@
+use crate::display::{format_link_params, percent_encode_url, strip_control_chars};
+use crate::profile::render_for;
+use crate::strip::strip_ansi_codes;
+use crate::write::Content;
+use crate::{is_enabled, nested, AnsiGenericStrings, Style, TerminalProfile, RESET};
+use std::borrow::Cow;
+use std::io::{self, Write};
+
+/// The format a [`RenderTarget`] is written in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum TargetFormat {
+    /// Styled text with escape codes, for a terminal with the given
+    /// capabilities: lazy styles are resolved for it, and links are only
+    /// written if it supports them.
+    Ansi(TerminalProfile),
+    /// The text alone, without any escape codes, titles or control
+    /// sequences, e.g. for a log file.
+    Plain,
+}
+
+/// One of the outputs of [`AnsiStrings::render_all`](crate::AnsiStrings),
+/// with the format it is written in.
+pub struct RenderTarget<'w> {
+    writer: &'w mut dyn Write,
+    format: TargetFormat,
+    /// The style the output is currently in.
+    style: Style,
+}
+
+impl<'w> RenderTarget<'w> {
+    /// A target writing to `writer` in `format`.
+    pub fn new(writer: &'w mut dyn Write, format: TargetFormat) -> Self {
+        Self {
+            writer,
+            format,
+            style: Style::new(),
+        }
+    }
+
+    /// A target writing styled text for a terminal with `profile`.
+    pub fn ansi(writer: &'w mut dyn Write, profile: TerminalProfile) -> Self {
+        Self::new(writer, TargetFormat::Ansi(profile))
+    }
+
+    /// A target writing plain text.
+    pub fn plain(writer: &'w mut dyn Write) -> Self {
+        Self::new(writer, TargetFormat::Plain)
+    }
+
+    /// The format of this target.
+    pub fn format(&self) -> TargetFormat {
+        self.format
+    }
+}
+
+impl<'w> std::fmt::Debug for RenderTarget<'w> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RenderTarget")
+            .field("format", &self.format)
+            .field("style", &self.style)
+            .finish()
+    }
+}
+
+impl<'a> AnsiGenericStrings<'a, str> {
+    /// Write this sequence to all of `targets` at once, each in its own
+    /// format.
+    ///
+    /// The segments are walked once, and the text of each of them (which
+    /// may need formatting) is only produced once for each profile among
+    /// the targets, so that the strings nested in it are also written for
+    /// the profile. Writing stops at the first error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::{AnsiStrings, RenderTarget, TerminalProfile};
+    /// use nu_ansi_term::Color::{Blue, Red};
+    ///
+    /// let report = AnsiStrings([
+    ///     Red.paint("2 errors"),
+    ///     ", see ".into(),
+    ///     Blue.paint("the log").hyperlink("file:///tmp/build.log"),
+    /// ]);
+    ///
+    /// let (mut terminal, mut plain_terminal, mut log) = (Vec::new(), Vec::new(), Vec::new());
+    /// report
+    ///     .render_all(&mut [
+    ///         RenderTarget::ansi(&mut terminal, TerminalProfile::new()),
+    ///         RenderTarget::ansi(&mut plain_terminal, TerminalProfile::new().without_hyperlinks()),
+    ///         RenderTarget::plain(&mut log),
+    ///     ])
+    ///     .unwrap();
+    /// assert_eq!(terminal, report.to_string().as_bytes());
+    /// assert_eq!(plain_terminal, b"\x1b[31m2 errors\x1b[0m, see \x1b[34mthe log\x1b[0m");
+    /// assert_eq!(log, b"2 errors, see the log");
+    /// ```
+    pub fn render_all(&self, targets: &mut [RenderTarget<'_>]) -> io::Result<()> {
+        for (ix, segment) in self.iter().enumerate() {
+            let literal: Option<&str> = match segment.content() {
+                Content::StrLike(s) => Some(s),
+                Content::Shared(s) => Some(s),
+                Content::FmtArgs(args) => args.as_str(),
+                Content::GenericStrings(_) => None,
+            };
+            // The text written for each profile, if it is not a literal.
+            let mut rendered: Vec<(TerminalProfile, String)> = Vec::new();
+            // Only computed if a plain target needs it.
+            let mut plain: Option<String> = None;
+            let url = segment.url_string().map(|url| url.to_string());
+
+            for target in targets.iter_mut() {
+                let text_profile = match target.format {
+                    TargetFormat::Ansi(profile) => profile,
+                    TargetFormat::Plain => TerminalProfile::rendering(),
+                };
+                let text: Cow<'_, str> = match literal {
+                    Some(s) => Cow::Borrowed(s),
+                    None => {
+                        let ix = match rendered.iter().position(|(p, _)| *p == text_profile) {
+                            Some(ix) => ix,
+                            None => {
+                                let text = render_for(text_profile, || match segment.content() {
+                                    Content::FmtArgs(args) => {
+                                        nested::within(segment.style, || args.to_string())
+                                    }
+                                    content => content.to_string(),
+                                });
+                                rendered.push((text_profile, text));
+                                rendered.len() - 1
+                            }
+                        };
+                        Cow::Borrowed(&rendered[ix].1)
+                    }
+                };
+                let profile = match target.format {
+                    TargetFormat::Ansi(profile) => profile,
+                    TargetFormat::Plain => {
+                        if segment.scheduled_style().is_some() {
+                            let plain =
+                                plain.get_or_insert_with(|| strip_ansi_codes(&text).into_owned());
+                            target.writer.write_all(plain.as_bytes())?;
+                        }
+                        continue;
+                    }
+                };
+
+                if segment
+                    .sequence_kind()
+                    .map_or(false, |kind| !profile.supports(kind))
+                    && segment.url_string().is_none()
+                {
+                    continue;
+                }
+                if segment.is_control() {
+                    target
+                        .writer
+                        .write_all(segment.control_command().as_bytes())?;
+                    continue;
+                }
+                let style = match segment.scheduled_style() {
+                    Some(style) => self
+                        .lazy_style(ix)
+                        .map_or(style, |lazy| lazy.resolve(&profile))
+                        .adapt_to(profile.colors),
+                    None => {
+                        // A title.
+                        let title = strip_control_chars(&text);
+                        write!(target.writer, "\x1B]2;{}\x1B\\", title)?;
+                        continue;
+                    }
+                };
+                write!(target.writer, "{}", target.style.compute_delta(style))?;
+                target.style = style;
+                match &url {
+                    Some(url) if profile.hyperlinks => write!(
+                        target.writer,
+                        "\x1B]8;{};{}\x1B\\{}\x1B]8;;\x1B\\",
+                        format_link_params(segment.link_params()),
+                        percent_encode_url(url),
+                        text
+                    )?,
+                    _ => target.writer.write_all(text.as_bytes())?,
+                }
+            }
+        }
+
+        for target in targets.iter_mut() {
+            if !target.style.has_no_styling() && is_enabled() {
+                target.writer.write_all(RESET.as_bytes())?;
+            }
+            target.style = Style::new();
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Color::*;
+    use crate::{AnsiString, AnsiStrings};
+
+    #[test]
+    fn each_target_gets_its_own_format() {
+        let mut strings = AnsiStrings([
+            AnsiString::title("build"),
+            Green.bold().paint("3 ok"),
+            AnsiString::control("\x1b[2K"),
+            Style::new().paint(AnsiStrings([Red.paint("nested")])),
+        ]);
+        strings.push_lazy(" lazy", |profile: &TerminalProfile| {
+            if profile.unicode {
+                Blue.normal()
+            } else {
+                Yellow.normal()
+            }
+        });
+
+        let (mut unicode, mut ascii, mut plain) = (Vec::new(), Vec::new(), Vec::new());
+        strings
+            .render_all(&mut [
+                RenderTarget::ansi(&mut unicode, TerminalProfile::new()),
+                RenderTarget::ansi(&mut ascii, TerminalProfile::ascii()),
+                RenderTarget::plain(&mut plain),
+            ])
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(unicode).unwrap(),
+            strings.resolved(&TerminalProfile::new()).to_string()
+        );
+        assert_eq!(
+            String::from_utf8(ascii).unwrap(),
+            strings.resolved(&TerminalProfile::ascii()).to_string()
+        );
+        assert_eq!(plain, b"3 oknested lazy");
+    }
+
+    #[test]
+    fn unsupported_sequences_are_left_out() {
+        let strings = AnsiStrings([
+            AnsiString::title("build"),
+            Red.paint("docs").hyperlink("https://docs.rs"),
+            Red.paint(AnsiStrings([Blue.paint("api").hyperlink("https://a")])),
+            AnsiString::notify("done", "ok"),
+            AnsiString::cwd("/"),
+        ]);
+        let mut out = Vec::new();
+        let profile = TerminalProfile::new()
+            .without_titles()
+            .without_hyperlinks()
+            .without_notifications();
+        strings
+            .render_all(&mut [RenderTarget::ansi(&mut out, profile)])
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "\x1b[31mdocs\x1b[34mapi\x1b[0m\x1b]7;file:///\x1b\\\x1b[0m"
+        );
+    }
+}
@@ -0,0 +1,202 @@
+//! Styled strings written inside the [`fmt::Arguments`](std::fmt::Arguments)
+//! content of other styled strings.
+//!
+//! The content of a string painted with `format_args!` may itself format
+//! styled strings, as in:
+//!
+//! ```
+//! use nu_ansi_term::Color::{Blue, Red};
+//!
+//! let inner = Blue.paint("world");
+//! assert_eq!(
+//!     Red.paint(format_args!("hello {}!", inner)).to_string(),
+//!     "\x1b[31mhello \x1b[34mworld\x1b[31m!\x1b[0m"
+//! );
+//! ```
+//!
+//! Written on their own, the inner string would end with a reset, leaving
+//! the `!` of the outer string unstyled. Instead, the styles of strings
+//! written at a [`render_depth`] above zero are flattened into the
+//! enclosing one:
+//!
+//! * the inner string is rebased on the enclosing style (its own colors
+//!   take precedence, and the formats of both apply), and only the codes
+//!   needed to switch from the enclosing style are written before it;
+//! * after it, the codes needed to switch back to the enclosing style are
+//!   written instead of a reset.
+//!
+//! Sequences nested as [`Content::GenericStrings`](crate::Content) are not
+//! written through `fmt::Arguments`; see [`Style::paint_nested`] for
+//! building those.
+
+use crate::difference::StyleDelta;
+use crate::style::BasedOn;
+use crate::write::{AnyWrite, WriteResult};
+use crate::Style;
+use std::cell::RefCell;
+
+thread_local! {
+    /// The styles of the strings whose `fmt::Arguments` are being written on
+    /// this thread, innermost last.
+    static ENCLOSING: RefCell<Vec<Style>> = const { RefCell::new(Vec::new()) };
+}
+
+/// How many styled strings are currently writing their `fmt::Arguments`
+/// content on this thread. This is zero outside of any such write.
+///
+/// # Examples
+///
+/// ```
+/// use std::fmt;
+/// use nu_ansi_term::nested::render_depth;
+/// use nu_ansi_term::Color::Red;
+///
+/// struct Depth;
+///
+/// impl fmt::Display for Depth {
+///     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+///         write!(f, "{}", render_depth())
+///     }
+/// }
+///
+/// assert_eq!(Depth.to_string(), "0");
+/// let inner = Red.paint(format_args!("{}", Depth)).to_string();
+/// assert!(inner.contains('1'));
+/// let nested = Red
+///     .paint(format_args!("{}", Red.paint(format_args!("{}", Depth))))
+///     .to_string();
+/// assert!(nested.contains('2'));
+/// ```
+pub fn render_depth() -> usize {
+    ENCLOSING.with(|enclosing| enclosing.borrow().len())
+}
+
+/// The style of the innermost string whose `fmt::Arguments` are being
+/// written on this thread, if any.
+pub(crate) fn enclosing_style() -> Option<Style> {
+    ENCLOSING.with(|enclosing| enclosing.borrow().last().copied())
+}
+
+/// Write the content of a string in `style` with `write`, making `style` the
+/// enclosing style of any string written meanwhile.
+pub(crate) fn within<R>(style: Style, write: impl FnOnce() -> R) -> R {
+    /// Pops the style again, even if `write` panics.
+    struct Guard;
+
+    impl Drop for Guard {
+        fn drop(&mut self) {
+            // `try_with`, as this may run while the thread is exiting.
+            let _ = ENCLOSING.try_with(|enclosing| enclosing.borrow_mut().pop());
+        }
+    }
+
+    let style = Style {
+        prefix_before_reset: false,
+        ..style
+    };
+    ENCLOSING.with(|enclosing| enclosing.borrow_mut().push(style));
+    let _guard = Guard;
+    write()
+}
+
+/// The style a string in `style` is written in, within `enclosing`.
+pub(crate) fn flatten(style: Style, enclosing: Style) -> Style {
+    Style {
+        prefix_before_reset: false,
+        ..style.rebase_on(enclosing)
+    }
+}
+
+/// Write the codes switching the terminal from the style `from` to `to`.
+pub(crate) fn switch<W: AnyWrite + ?Sized>(
+    from: Style,
+    to: Style,
+    w: &mut W,
+) -> WriteResult<W::Error>
+where
+    str: AsRef<W::Buf>,
+{
+    match from.compute_delta(to) {
+        StyleDelta::ExtraStyles(style) => style.write_prefix(w),
+        StyleDelta::Empty => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Color::*;
+    use crate::{AnsiStrings, FormatFlags};
+
+    #[test]
+    fn nested_strings_restore_the_enclosing_style() {
+        let inner = Blue.paint("world");
+        assert_eq!(
+            Red.bold()
+                .paint(format_args!("hello {}!", inner))
+                .to_string(),
+            "\x1b[1;31mhello \x1b[34mworld\x1b[31m!\x1b[0m"
+        );
+        assert_eq!(
+            Style::new()
+                .paint(format_args!("a {} b", Green.paint("c")))
+                .to_string(),
+            "a \x1b[32mc\x1b[0m b"
+        );
+        assert_eq!(render_depth(), 0);
+    }
+
+    #[test]
+    fn nested_sequences_are_flattened() {
+        let inner = AnsiStrings([Blue.paint("x"), "y".into(), Green.underline().paint("z")]);
+        assert_eq!(
+            AnsiStrings([
+                Red.paint(format_args!("<{}>", inner)),
+                Red.bold().paint("!"),
+            ])
+            .to_string(),
+            "\x1b[31m<\x1b[34mx\x1b[31my\x1b[4;32mz\x1b[0m\x1b[31m>\x1b[1m!\x1b[0m"
+        );
+
+        let c = Blue.paint("c");
+        assert_eq!(
+            Red.paint(format_args!(
+                "a{}d",
+                Style::new().italic().paint(format_args!("b{}", c))
+            ))
+            .to_string(),
+            "\x1b[31ma\x1b[3mb\x1b[34mc\x1b[31m\x1b[0m\x1b[31md\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn cleared_formats_are_not_inherited() {
+        let plain = Style::new().clear_formats(FormatFlags::BOLD);
+        assert_eq!(
+            Red.bold()
+                .paint(format_args!("a{}c", plain.paint("b")))
+                .to_string(),
+            "\x1b[1;31ma\x1b[0m\x1b[31mb\x1b[1mc\x1b[0m"
+        );
+        assert_eq!(
+            flatten(plain, Red.bold()),
+            Red.normal().clear_formats(FormatFlags::BOLD)
+        );
+    }
+
+    #[test]
+    fn panicking_content_pops_its_style() {
+        struct Panics;
+
+        impl std::fmt::Display for Panics {
+            fn fmt(&self, _: &mut std::fmt::Formatter) -> std::fmt::Result {
+                panic!("content failed to render")
+            }
+        }
+
+        let result = std::panic::catch_unwind(|| Red.paint(format_args!("{}", Panics)).to_string());
+        assert!(result.is_err());
+        assert_eq!(render_depth(), 0);
+        assert_eq!(Blue.paint("ok").to_string(), "\x1b[34mok\x1b[0m");
+    }
+}
@@ -0,0 +1,86 @@
+use crate::{Color, Style};
+
+/// The default styles nushell uses for the kinds of values it displays,
+/// keyed by the names used in its `color_config` (`int`, `filesize`,
+/// `cell-path`, …), plus the styles of table elements such as `header` and
+/// `row_index`.
+///
+/// Plugins and external tools can use these to render values the way the
+/// shell does, and since this is plain data, nushell itself can build its
+/// default config from it.
+///
+/// Nushell styles dates by their age by default; `date` is the style used
+/// when no such rule applies.
+pub const VALUE_STYLES: &[(&str, Style)] = &[
+    ("separator", Color::White.normal()),
+    ("leading_trailing_space_bg", Style::new()),
+    ("header", Color::Green.normal().bold()),
+    ("empty", Color::Blue.normal()),
+    ("bool", Color::LightCyan.normal()),
+    ("int", Color::White.normal()),
+    ("filesize", Color::Cyan.normal()),
+    ("duration", Color::White.normal()),
+    ("date", Color::Purple.normal()),
+    ("range", Color::White.normal()),
+    ("float", Color::White.normal()),
+    ("string", Color::White.normal()),
+    ("nothing", Color::White.normal()),
+    ("binary", Color::White.normal()),
+    ("cell-path", Color::White.normal()),
+    ("row_index", Color::Green.normal().bold()),
+    ("record", Color::White.normal()),
+    ("list", Color::White.normal()),
+    ("block", Color::White.normal()),
+    ("closure", Color::Green.normal().bold()),
+    ("glob", Color::Cyan.normal().bold()),
+    ("hints", Color::DarkGray.normal()),
+    ("search_result", Color::White.normal().on(Color::Red)),
+];
+
+/// The default style for values of `kind` (a key of [`VALUE_STYLES`]), if
+/// there is one.
+///
+/// # Examples
+///
+/// ```
+/// use nu_ansi_term::nu_defaults::style_for;
+/// use nu_ansi_term::Color::Cyan;
+///
+/// assert_eq!(style_for("filesize"), Some(Cyan.normal()));
+/// assert_eq!(style_for("unknown"), None);
+/// ```
+pub fn style_for(kind: &str) -> Option<Style> {
+    VALUE_STYLES
+        .iter()
+        .find(|(name, _)| *name == kind)
+        .map(|(_, style)| *style)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn kinds_are_unique() {
+        for (ix, (name, _)) in VALUE_STYLES.iter().enumerate() {
+            assert!(
+                VALUE_STYLES[ix + 1..]
+                    .iter()
+                    .all(|(other, _)| other != name),
+                "{} is listed twice",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn lookup() {
+        assert_eq!(style_for("header"), Some(Color::Green.bold()));
+        assert_eq!(style_for("bool"), Some(Color::LightCyan.normal()));
+        assert_eq!(
+            style_for("search_result"),
+            Some(Color::White.on(Color::Red))
+        );
+        assert_eq!(style_for("Int"), None);
+    }
+}
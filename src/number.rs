@@ -0,0 +1,155 @@
+use crate::{AnsiString, Color, Style};
+
+/// Rules for formatting numbers and choosing their style from their value,
+/// e.g. for coloring gains and losses or resource usage levels.
+///
+/// By default, negative numbers are red, zero is dimmed and positive
+/// numbers are green. Thresholds, when given, take precedence over these
+/// sign-based styles.
+///
+/// # Examples
+///
+/// ```
+/// use nu_ansi_term::NumberStyle;
+/// use nu_ansi_term::Color::{Red, Yellow};
+///
+/// let change = NumberStyle::new().precision(2);
+/// assert_eq!(change.paint(-1.234).to_string(), "\x1b[31m-1.23\x1b[0m");
+/// assert_eq!(change.paint(-0.001).to_string(), "\x1b[2m0.00\x1b[0m");
+///
+/// let usage = NumberStyle::new()
+///     .precision(0)
+///     .threshold(75.0, Yellow.normal())
+///     .threshold(90.0, Red.bold());
+/// assert_eq!(usage.paint(93.2).to_string(), "\x1b[1;31m93\x1b[0m");
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct NumberStyle {
+    negative: Style,
+    zero: Style,
+    positive: Style,
+    /// Sorted by ascending threshold.
+    thresholds: Vec<(f64, Style)>,
+    precision: Option<usize>,
+}
+
+impl Default for NumberStyle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NumberStyle {
+    /// Create number styling with red negative numbers, dimmed zero and green
+    /// positive numbers, formatted with full precision.
+    pub fn new() -> Self {
+        Self {
+            negative: Color::Red.normal(),
+            zero: Style::new().dimmed(),
+            positive: Color::Green.normal(),
+            thresholds: Vec::new(),
+            precision: None,
+        }
+    }
+
+    /// Set the style for negative numbers.
+    pub fn negative(mut self, style: Style) -> Self {
+        self.negative = style;
+        self
+    }
+
+    /// Set the style for zero.
+    pub fn zero(mut self, style: Style) -> Self {
+        self.zero = style;
+        self
+    }
+
+    /// Set the style for positive numbers.
+    pub fn positive(mut self, style: Style) -> Self {
+        self.positive = style;
+        self
+    }
+
+    /// Use `style` for numbers greater than or equal to `at`, unless a higher
+    /// threshold applies. Numbers below every threshold keep their sign-based
+    /// style.
+    pub fn threshold(mut self, at: f64, style: Style) -> Self {
+        let ix = self.thresholds.partition_point(|(t, _)| *t <= at);
+        self.thresholds.insert(ix, (at, style));
+        self
+    }
+
+    /// Round numbers to `digits` decimal places when formatting them.
+    pub fn precision(mut self, digits: usize) -> Self {
+        self.precision = Some(digits);
+        self
+    }
+
+    /// Get the style used for `value`. `NaN` is left unstyled.
+    pub fn style_for(&self, value: f64) -> Style {
+        if value.is_nan() {
+            return Style::new();
+        }
+        if let Some((_, style)) = self.thresholds.iter().rev().find(|(t, _)| value >= *t) {
+            return *style;
+        }
+        if value < 0.0 {
+            self.negative
+        } else if value > 0.0 {
+            self.positive
+        } else {
+            self.zero
+        }
+    }
+
+    /// Format `value` with the configured precision, and paint it with the
+    /// style for its value after rounding, so that numbers which are
+    /// displayed as zero are styled (and signed) as zero.
+    pub fn paint(&self, value: impl Into<f64>) -> AnsiString<'static> {
+        let mut value = value.into();
+        let text = match self.precision {
+            Some(digits) => {
+                let scale = 10f64.powi(digits.min(i32::MAX as usize) as i32);
+                if (value * scale).round() == 0.0 {
+                    value = 0.0;
+                }
+                format!("{:.*}", digits, value)
+            }
+            None => value.to_string(),
+        };
+        self.style_for(value).paint(text)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Color::*;
+
+    #[test]
+    fn sign_styles() {
+        let style = NumberStyle::new();
+        assert_eq!(style.style_for(-3.0), Red.normal());
+        assert_eq!(style.style_for(0.0), Style::new().dimmed());
+        assert_eq!(style.style_for(2.5), Green.normal());
+        assert_eq!(style.style_for(f64::NAN), Style::new());
+    }
+
+    #[test]
+    fn thresholds_override_sign() {
+        let style = NumberStyle::new()
+            .threshold(90.0, Red.bold())
+            .threshold(50.0, Yellow.normal());
+        assert_eq!(style.style_for(10.0), Green.normal());
+        assert_eq!(style.style_for(50.0), Yellow.normal());
+        assert_eq!(style.style_for(95.0), Red.bold());
+    }
+
+    #[test]
+    fn rounding() {
+        let style = NumberStyle::new().precision(1);
+        assert_eq!(style.paint(2.25f32).to_string(), "\x1b[32m2.2\x1b[0m");
+        assert_eq!(style.paint(-0.04).to_string(), "\x1b[2m0.0\x1b[0m");
+        assert_eq!(NumberStyle::new().paint(7).to_string(), "\x1b[32m7\x1b[0m");
+    }
+}
@@ -0,0 +1,101 @@
+use std::path::Path;
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Encode `data` in standard base64, with padding.
+pub(crate) fn base64_encode(data: &[u8]) -> String {
+    let mut encoded = String::with_capacity((data.len() + 2) / 3 * 4);
+    for chunk in data.chunks(3) {
+        let bytes = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let group = u32::from(bytes[0]) << 16 | u32::from(bytes[1]) << 8 | u32::from(bytes[2]);
+        for ix in 0..4 {
+            if ix <= chunk.len() {
+                encoded.push(BASE64_ALPHABET[(group >> (18 - 6 * ix) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+/// The `file://` URL of `path`, with no host (which terminals take to mean
+/// the local machine). Bytes other than unreserved characters and path
+/// separators are percent-encoded.
+pub(crate) fn file_url(path: &Path) -> String {
+    let path = path.to_string_lossy();
+    let mut url = String::from("file://");
+    if !path.starts_with(['/', '\\'].as_ref()) {
+        // A Windows path, such as `C:\Users`.
+        url.push('/');
+    }
+    for b in path.bytes() {
+        match b {
+            b'\\' => url.push('/'),
+            b if b.is_ascii_alphanumeric() || b"-._~/:".contains(&b) => url.push(b as char),
+            b => url.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    url
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::unstyle;
+    use crate::AnsiString;
+    use crate::AnsiStrings;
+    use crate::Color::Red;
+
+    #[test]
+    fn base64() {
+        let cases = [
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("foobar", "Zm9vYmFy"),
+        ];
+        for (data, encoded) in cases {
+            assert_eq!(base64_encode(data.as_bytes()), encoded);
+        }
+        assert_eq!(base64_encode(&[0xfb, 0xff]), "+/8=");
+    }
+
+    #[test]
+    pub(crate) fn file_urls() {
+        assert_eq!(file_url(Path::new("/tmp/a%b")), "file:///tmp/a%25b");
+        assert_eq!(
+            file_url(Path::new("C:\\Users\\é")),
+            "file:///C:/Users/%C3%A9"
+        );
+    }
+
+    #[test]
+    fn formatted_titles_are_sanitized() {
+        assert_eq!(
+            AnsiString::notify(format_args!("a;{}", "b\x07"), "c").to_string(),
+            "\x1b]777;notify;ab;c\x1b\\"
+        );
+    }
+
+    #[test]
+    fn commands_in_sequences() {
+        let strings = AnsiStrings([
+            Red.paint("done"),
+            AnsiString::notify("build", "c"),
+            Red.paint("!"),
+            AnsiString::cwd("/"),
+        ]);
+        assert_eq!(
+            strings.to_string(),
+            "\x1b[31mdone\x1b]777;notify;build;c\x1b\\!\x1b]7;file:///\x1b\\\x1b[0m"
+        );
+        assert_eq!(unstyle(&strings), "done!");
+    }
+}
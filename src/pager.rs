@@ -0,0 +1,253 @@
+use crate::control::{
+    ClearLine, EnterAlternateScreen, HideCursor, LeaveAlternateScreen, MoveTo, ShowCursor,
+};
+use crate::{AnsiStrings, MarkerPlacement, TerminalSize, WidthPolicy, WrappedRow};
+use std::fmt;
+use std::io;
+use std::ops::Range;
+
+/// A scrollable view of a styled document, such as the output of `--help`,
+/// for showing it a screen at a time without spawning `less`.
+///
+/// The document is wrapped to the width of the terminal (see
+/// [`AnsiStrings::wrap`]) and the pager tracks which of its lines are at the
+/// top of the screen. Reading keys is left to the caller, who maps them to
+/// the scrolling methods and writes the pager (its [`Display`] form redraws
+/// the whole screen) after each one.
+///
+/// [`Display`]: fmt::Display
+///
+/// # Examples
+///
+/// ```no_run
+/// use nu_ansi_term::{AnsiStrings, Pager, TerminalSize};
+/// use nu_ansi_term::Color::Green;
+/// use std::io::{self, Read, Write};
+///
+/// let help = AnsiStrings([Green.bold().paint("USAGE:"), "\n    nu [OPTIONS]\n".into()]);
+/// let mut pager = Pager::new(help, TerminalSize { columns: 80, rows: 24 });
+///
+/// let mut stdout = io::stdout();
+/// Pager::enter(&mut stdout)?;
+/// write!(stdout, "{}", pager)?;
+/// for key in io::stdin().bytes() {
+///     match key? {
+///         b'q' => break,
+///         b'j' => pager.scroll_by(1),
+///         b'k' => pager.scroll_by(-1),
+///         b' ' => pager.page_down(),
+///         b'b' => pager.page_up(),
+///         _ => continue,
+///     }
+///     write!(stdout, "{}", pager)?;
+///     stdout.flush()?;
+/// }
+/// Pager::leave(&mut stdout)?;
+/// # Ok::<(), io::Error>(())
+/// ```
+#[derive(Clone, Debug)]
+pub struct Pager<'a> {
+    document: AnsiStrings<'a>,
+    lines: Vec<WrappedRow>,
+    size: TerminalSize,
+    offset: usize,
+}
+
+impl<'a> Pager<'a> {
+    /// A pager showing `document` from its first line, on a screen of
+    /// `size`.
+    pub fn new(document: AnsiStrings<'a>, size: TerminalSize) -> Self {
+        let mut pager = Self {
+            document,
+            lines: Vec::new(),
+            size,
+            offset: 0,
+        };
+        pager.resize(size);
+        pager
+    }
+
+    /// Wrap the document again for a screen of `size`, as after the
+    /// terminal was resized, keeping the line of the document at the top of
+    /// the screen there where possible. If only the end of that line was on
+    /// the screen, the pager scrolls back to its start.
+    pub fn resize(&mut self, size: TerminalSize) {
+        let top = self.lines.get(self.offset).map_or(0, |row| row.line);
+        self.size = size;
+        self.lines = self.document.wrap_rows(
+            size.columns.max(1),
+            None,
+            MarkerPlacement::LineEnd,
+            WidthPolicy::new(),
+        );
+        if self.lines.last().map_or(false, |row| row.text.width() == 0) {
+            self.lines.pop();
+        }
+        self.scroll_to(self.lines.partition_point(|row| row.line < top));
+    }
+
+    /// The number of lines of the wrapped document, not counting an empty
+    /// line after a final newline.
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    /// Whether the document has no lines.
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    /// The index of the line at the top of the screen.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// The indices of the lines on the screen.
+    pub fn visible(&self) -> Range<usize> {
+        self.offset..self.len().min(self.offset + self.size.rows)
+    }
+
+    /// Whether the last line of the document is on the screen.
+    pub fn at_end(&self) -> bool {
+        self.visible().end == self.len()
+    }
+
+    /// Scroll so that line `offset` is at the top of the screen, or as close
+    /// to it as possible without scrolling past the end of the document.
+    pub fn scroll_to(&mut self, offset: usize) {
+        let last = self.len().saturating_sub(self.size.rows);
+        self.offset = offset.min(last);
+    }
+
+    /// Scroll down by `lines`, or up if it is negative.
+    pub fn scroll_by(&mut self, lines: isize) {
+        let offset = if lines < 0 {
+            self.offset.saturating_sub(lines.unsigned_abs())
+        } else {
+            self.offset.saturating_add(lines.unsigned_abs())
+        };
+        self.scroll_to(offset);
+    }
+
+    /// Scroll down by a screen.
+    pub fn page_down(&mut self) {
+        self.scroll_to(self.offset.saturating_add(self.size.rows.max(1)));
+    }
+
+    /// Scroll up by a screen.
+    pub fn page_up(&mut self) {
+        self.scroll_to(self.offset.saturating_sub(self.size.rows.max(1)));
+    }
+
+    /// Scroll to the start of the document.
+    pub fn home(&mut self) {
+        self.scroll_to(0);
+    }
+
+    /// Scroll to the end of the document.
+    pub fn end(&mut self) {
+        self.scroll_to(usize::MAX);
+    }
+
+    /// Switch to the alternate screen and hide the cursor, so that the
+    /// pager does not overwrite what was on the screen before it.
+    pub fn enter<W: io::Write>(w: &mut W) -> io::Result<()> {
+        write!(w, "{}{}", EnterAlternateScreen, HideCursor)
+    }
+
+    /// Show the cursor and switch back to the screen from before
+    /// [`enter`](Self::enter).
+    pub fn leave<W: io::Write>(w: &mut W) -> io::Result<()> {
+        write!(w, "{}{}", ShowCursor, LeaveAlternateScreen)?;
+        w.flush()
+    }
+}
+
+/// Redraws every row of the screen: each row is cleared, then the line of
+/// the document on it is written, and rows past the end of the document are
+/// left blank. Lines are written separately, each resetting its styles, so
+/// that no background color leaks into the cleared rows.
+impl fmt::Display for Pager<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in 0..self.size.rows {
+            let row_index = u16::try_from(row).unwrap_or(u16::MAX);
+            write!(f, "{}{}", MoveTo(0, row_index), ClearLine)?;
+            if let Some(line) = self.lines.get(self.offset + row) {
+                write!(f, "{}", line.text)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Color::*;
+
+    #[test]
+    fn scrolling_stays_within_the_document() {
+        let text: String = (1..=10).map(|n| format!("line {}\n", n)).collect();
+        let document = AnsiStrings([Blue.paint(text.as_str())]);
+        let mut pager = Pager::new(
+            document,
+            TerminalSize {
+                columns: 4,
+                rows: 3,
+            },
+        );
+        assert_eq!(pager.len(), 20);
+        assert_eq!(pager.visible(), 0..3);
+
+        pager.scroll_by(-2);
+        assert_eq!(pager.offset(), 0);
+        pager.page_down();
+        assert_eq!(pager.visible(), 3..6);
+        pager.end();
+        assert_eq!(pager.visible(), 17..20);
+        assert!(pager.at_end());
+
+        pager.resize(TerminalSize {
+            columns: 20,
+            rows: 4,
+        });
+        assert_eq!(pager.len(), 10);
+        assert_eq!(pager.visible(), 6..10);
+
+        pager.home();
+        pager.scroll_by(5);
+        pager.resize(TerminalSize {
+            columns: 4,
+            rows: 3,
+        });
+        assert_eq!(pager.visible(), 10..13);
+        pager.scroll_by(1);
+        pager.resize(TerminalSize {
+            columns: 20,
+            rows: 2,
+        });
+        assert_eq!(pager.visible(), 5..7);
+        pager.resize(TerminalSize {
+            columns: 20,
+            rows: 4,
+        });
+        pager.end();
+        assert_eq!(
+            pager.to_string(),
+            "\x1b[1;1H\x1b[2K\x1b[34mline 7\x1b[0m\
+             \x1b[2;1H\x1b[2K\x1b[34mline 8\x1b[0m\
+             \x1b[3;1H\x1b[2K\x1b[34mline 9\x1b[0m\
+             \x1b[4;1H\x1b[2K\x1b[34mline 10\x1b[0m"
+        );
+
+        let short = Pager::new(
+            AnsiStrings(["only".into()]),
+            TerminalSize {
+                columns: 20,
+                rows: 2,
+            },
+        );
+        assert!(short.at_end());
+        assert_eq!(short.to_string(), "\x1b[1;1H\x1b[2Konly\x1b[2;1H\x1b[2K");
+    }
+}
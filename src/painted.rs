@@ -0,0 +1,113 @@
+use crate::enabled::is_enabled;
+use crate::{fmt_write, nested, Color, Style};
+use std::fmt;
+
+/// A value painted in a style, written through its own [`Display`]
+/// implementation between the codes of the style. See [`Style::wrap`].
+///
+/// Formatting options, such as the width, apply to the value and not to the
+/// codes around it. Styled strings written by the value are nested in the
+/// style (see [`nested`](crate::nested)).
+///
+/// [`Display`]: fmt::Display
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Painted<D> {
+    style: Style,
+    value: D,
+}
+
+impl<D> Painted<D> {
+    /// The style the value is painted in.
+    pub fn style(&self) -> Style {
+        self.style
+    }
+
+    /// Get a reference to the painted value.
+    pub fn value(&self) -> &D {
+        &self.value
+    }
+
+    /// Take the painted value back.
+    pub fn into_inner(self) -> D {
+        self.value
+    }
+}
+
+impl<D: fmt::Display> fmt::Display for Painted<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !is_enabled() {
+            return fmt::Display::fmt(&self.value, f);
+        }
+        if let Some(enclosing) = nested::enclosing_style() {
+            let style = nested::flatten(self.style, enclosing);
+            nested::switch(enclosing, style, fmt_write!(f))?;
+            nested::within(style, || fmt::Display::fmt(&self.value, f))?;
+            return nested::switch(style, enclosing, fmt_write!(f));
+        }
+
+        self.style.write_prefix(fmt_write!(f))?;
+        nested::within(self.style, || fmt::Display::fmt(&self.value, f))?;
+        self.style.write_suffix(fmt_write!(f))
+    }
+}
+
+impl Style {
+    /// Paint any value which implements [`Display`](fmt::Display) with this
+    /// style, keeping the value rather than borrowing or formatting it: the
+    /// value is only formatted when the result is written.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use nu_ansi_term::Color::{Green, Red};
+    ///
+    /// let count = Red.bold().wrap(3);
+    /// assert_eq!(format!("{} errors", count), "\x1b[1;31m3\x1b[0m errors");
+    ///
+    /// let elapsed = Green.normal().wrap(Duration::from_millis(1500).as_secs_f32());
+    /// assert_eq!(format!("{:>5.1}s", elapsed), "\x1b[32m  1.5\x1b[0ms");
+    /// ```
+    pub const fn wrap<D: fmt::Display>(self, value: D) -> Painted<D> {
+        Painted { style: self, value }
+    }
+}
+
+impl Color {
+    /// Paint any value which implements [`Display`](fmt::Display) with this
+    /// color. See [`Style::wrap`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::Path;
+    /// use nu_ansi_term::Color::Blue;
+    ///
+    /// let path = Blue.wrap(Path::new("/tmp").display());
+    /// assert_eq!(path.to_string(), "\x1b[34m/tmp\x1b[0m");
+    /// ```
+    pub fn wrap<D: fmt::Display>(self, value: D) -> Painted<D> {
+        self.normal().wrap(value)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Color::*;
+
+    #[test]
+    fn nested_values_restore_the_style() {
+        let inner = Blue.wrap(1);
+        assert_eq!(
+            Red.bold().wrap(format_args!("a{}b", inner)).to_string(),
+            "\x1b[1;31ma\x1b[34m1\x1b[31mb\x1b[0m"
+        );
+        assert_eq!(
+            Red.paint(format_args!("[{}]", Style::new().wrap("x")))
+                .to_string(),
+            "\x1b[31m[x]\x1b[0m"
+        );
+        assert_eq!(Style::new().wrap(2.5).to_string(), "2.5");
+    }
+}
@@ -0,0 +1,814 @@
+use crate::strip::Stripper;
+use crate::style::FormatFlags;
+use crate::write::Content;
+use crate::{io_write, AnsiGenericString, AnsiGenericStrings, Color, Style};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+use std::ops::Range;
+
+/// A piece of text containing escape sequences.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Token {
+    /// Text between escape sequences.
+    Text(Range<usize>),
+    /// An SGR sequence, with the range of its parameters.
+    Sgr(Range<usize>),
+    /// An OSC sequence, with the range of its payload, and the range of the
+    /// whole sequence.
+    Osc(Range<usize>, Range<usize>),
+    /// Any other escape sequence (cursor movements, screen clears, …), kept
+    /// as it is.
+    Other(Range<usize>),
+}
+
+/// Split `bytes` into text and escape sequences. Sequences which are cut
+/// off at the end of `bytes` run to its end.
+pub(crate) fn tokenize(bytes: &[u8]) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut ix = 0;
+    while ix < bytes.len() {
+        if bytes[ix] != 0x1B {
+            let end = bytes[ix..]
+                .iter()
+                .position(|&b| b == 0x1B)
+                .map_or(bytes.len(), |len| ix + len);
+            tokens.push(Token::Text(ix..end));
+            ix = end;
+            continue;
+        }
+
+        let start = ix;
+        match bytes.get(ix + 1) {
+            Some(b'[') => {
+                let params = ix + 2;
+                let end = bytes[params..]
+                    .iter()
+                    .position(|b| (0x40..=0x7E).contains(b))
+                    .map(|len| params + len);
+                tokens.push(match end {
+                    Some(end) if bytes[end] == b'm' => Token::Sgr(params..end),
+                    Some(end) => Token::Other(start..end + 1),
+                    None => Token::Other(start..bytes.len()),
+                });
+                ix = end.map_or(bytes.len(), |end| end + 1);
+            }
+            Some(b']') => {
+                let payload = ix + 2;
+                let mut end = payload;
+                let (payload_end, sequence_end) = loop {
+                    match bytes.get(end) {
+                        Some(0x07) => break (end, end + 1),
+                        Some(0x1B) if bytes.get(end + 1) == Some(&b'\\') => break (end, end + 2),
+                        Some(_) => end += 1,
+                        None => break (end, end),
+                    }
+                };
+                tokens.push(Token::Osc(payload..payload_end, start..sequence_end));
+                ix = sequence_end;
+            }
+            _ => {
+                // `ESC`, intermediate bytes, and a final byte. A final byte
+                // starting a multibyte character takes the whole character,
+                // so that the sequence ends on a character boundary.
+                let mut end = ix + 1;
+                while bytes.get(end).map_or(false, |b| (0x20..=0x2F).contains(b)) {
+                    end += 1;
+                }
+                ix = (end + 1).min(bytes.len());
+                if bytes.get(end).map_or(false, |&b| b >= 0xC0) {
+                    while bytes.get(ix).map_or(false, |b| (0x80..=0xBF).contains(b)) {
+                        ix += 1;
+                    }
+                }
+                tokens.push(Token::Other(start..ix));
+            }
+        }
+    }
+    tokens
+}
+
+/// The style resulting from applying the parameters of an SGR sequence
+/// (the `1;31` of `ESC[1;31m`) to `style`.
+///
+/// Unknown parameters are ignored. Both `;` and `:` are accepted between
+/// the components of 256-color and RGB colors, and the underline styles
+/// are read from `4:n`.
+///
+/// # Examples
+///
+/// ```
+/// use nu_ansi_term::parse::apply_sgr;
+/// use nu_ansi_term::Style;
+/// use nu_ansi_term::Color::{Blue, Fixed, Red, Rgb};
+///
+/// assert_eq!(apply_sgr(Style::new(), "1;31"), Red.bold());
+/// assert_eq!(apply_sgr(Red.bold(), "22;44"), Red.on(Blue));
+/// assert_eq!(apply_sgr(Red.bold(), "0"), Style::new());
+/// assert_eq!(apply_sgr(Style::new(), "38;5;208;48:2::1:2:3"), Fixed(208).on(Rgb(1, 2, 3)));
+/// assert_eq!(apply_sgr(Style::new(), "4:3;58:5:1"), Style::new().undercurl().underline_color(Fixed(1)));
+/// ```
+pub fn apply_sgr(style: Style, params: &str) -> Style {
+    apply_sgr_noting(style, params, |_| {})
+}
+
+/// How exactly a parameter of an SGR sequence is represented by a [`Style`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum SgrFidelity {
+    /// The parameter resets the style.
+    Reset,
+    /// The parameter is represented exactly.
+    Exact,
+    /// The parameter is shown differently by the style (a dotted underline
+    /// as a plain one, say), or not at all.
+    Lossy,
+}
+
+/// [`apply_sgr`], calling `note` with how exactly each parameter (with the
+/// components of a color) is represented.
+pub(crate) fn apply_sgr_noting(
+    style: Style,
+    params: &str,
+    mut note: impl FnMut(SgrFidelity),
+) -> Style {
+    let mut style = style;
+    let mut params = params.split(';');
+    while let Some(param) = params.next() {
+        let mut parts = param.split(':');
+        let code = match parts.next().unwrap_or("") {
+            "" => 0,
+            code => match code.parse::<u16>() {
+                Ok(code) => code,
+                Err(_) => {
+                    note(SgrFidelity::Lossy);
+                    continue;
+                }
+            },
+        };
+        let fidelity = match code {
+            0 => SgrFidelity::Reset,
+            // Dotted and dashed underlines have no format of their own.
+            4 if matches!(param, "4" | "4:0" | "4:1" | "4:2" | "4:3") => SgrFidelity::Exact,
+            // Colors which cannot be read are noted below.
+            38 | 48 | 58 => SgrFidelity::Exact,
+            1..=3
+            | 5
+            | 7..=9
+            | 21..=25
+            | 27..=37
+            | 39..=47
+            | 49
+            | 53
+            | 55
+            | 59
+            | 90..=97
+            | 100..=107
+                if !param.contains(':') =>
+            {
+                SgrFidelity::Exact
+            }
+            _ => SgrFidelity::Lossy,
+        };
+        style = match code {
+            0 => Style::new(),
+            1 => style.bold(),
+            2 => style.dimmed(),
+            3 => style.italic(),
+            4 => match parts.next() {
+                Some("0") => style.remove_formats(UNDERLINES),
+                Some("2") => style.remove_formats(UNDERLINES).double_underline(),
+                Some("3") => style.remove_formats(UNDERLINES).undercurl(),
+                // Dotted and dashed underlines are shown as plain ones.
+                _ => style.remove_formats(UNDERLINES).underline(),
+            },
+            5 | 6 => style.blink(),
+            7 => style.reverse(),
+            8 => style.hidden(),
+            9 => style.strikethrough(),
+            21 => style.double_underline(),
+            22 => style.remove_formats(FormatFlags::BOLD | FormatFlags::DIMMED),
+            23 => style.remove_formats(FormatFlags::ITALIC),
+            24 => style.remove_formats(UNDERLINES),
+            25 => style.remove_formats(FormatFlags::BLINK),
+            27 => style.remove_formats(FormatFlags::REVERSE),
+            28 => style.remove_formats(FormatFlags::HIDDEN),
+            29 => style.remove_formats(FormatFlags::STRIKETHROUGH),
+            53 => style.overline(),
+            55 => style.remove_formats(FormatFlags::OVERLINE),
+            30..=37 => style.fg(BASIC[code as usize - 30]),
+            90..=97 => style.fg(BRIGHT[code as usize - 90]),
+            40..=47 => style.bg(BASIC[code as usize - 40]),
+            100..=107 => style.bg(BRIGHT[code as usize - 100]),
+            39 => style.set_fg(None),
+            49 => style.set_bg(None),
+            59 => style.set_underline_color(None),
+            38 | 48 | 58 => {
+                // Colons keep the components in the same parameter.
+                let color = if param.contains(':') {
+                    extended_color(&mut parts, true)
+                } else {
+                    extended_color(&mut params, false)
+                };
+                match (code, color) {
+                    (38, Some(color)) => style.fg(color),
+                    (48, Some(color)) => style.bg(color),
+                    (_, Some(color)) => style.underline_color(color),
+                    (_, None) => {
+                        note(SgrFidelity::Lossy);
+                        continue;
+                    }
+                }
+            }
+            _ => style,
+        };
+        note(fidelity);
+    }
+    style
+}
+
+impl Style {
+    /// The style set by the parameters of an SGR sequence, such as the
+    /// `01;34` of an `LS_COLORS` entry. See [`apply_sgr`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::Style;
+    /// use nu_ansi_term::Color::{Blue, Fixed};
+    ///
+    /// assert_eq!(Style::from_sgr_params("01;34"), Blue.bold());
+    /// assert_eq!(Style::from_sgr_params("38;5;208"), Fixed(208).normal());
+    /// ```
+    pub fn from_sgr_params(params: &str) -> Style {
+        apply_sgr(Style::new(), params)
+    }
+}
+
+/// Parse a mapping of names to SGR parameters, in the format of the
+/// `LS_COLORS` environment variable (`di=01;34:ln=01;36:*.tar=01;31`) and
+/// of similar variables, such as `GREP_COLORS`.
+///
+/// Entries without a `=`, such as the boolean flags of `GREP_COLORS`, are
+/// skipped.
+///
+/// # Examples
+///
+/// ```
+/// use nu_ansi_term::parse::parse_ls_colors;
+/// use nu_ansi_term::Color::{Blue, Cyan, Red};
+///
+/// let colors = parse_ls_colors("di=01;34:ln=01;36:*.tar=01;31:ne");
+/// assert_eq!(colors["di"], Blue.bold());
+/// assert_eq!(colors["ln"], Cyan.bold());
+/// assert_eq!(colors["*.tar"], Red.bold());
+/// assert_eq!(colors.len(), 3);
+/// ```
+pub fn parse_ls_colors(text: &str) -> HashMap<String, Style> {
+    text.split(':')
+        .filter_map(|entry| entry.split_once('='))
+        .map(|(name, params)| (name.to_string(), Style::from_sgr_params(params)))
+        .collect()
+}
+
+/// Read a 256-color (`5;n`) or RGB (`2;r;g;b`) color from `components`.
+/// RGB colors given with colons may have a color space id before their
+/// components (`2::r:g:b`).
+fn extended_color<'p>(
+    components: &mut impl Iterator<Item = &'p str>,
+    colons: bool,
+) -> Option<Color> {
+    let number = |component: Option<&str>| component.and_then(|c| c.parse::<u8>().ok());
+    match components.next()? {
+        "5" => number(components.next()).map(Color::Fixed),
+        "2" => {
+            let mut rgb: Vec<&str> = components.take(if colons { 4 } else { 3 }).collect();
+            if rgb.len() == 4 {
+                rgb.remove(0);
+            }
+            let mut rgb = rgb.into_iter();
+            Some(Color::Rgb(
+                number(rgb.next())?,
+                number(rgb.next())?,
+                number(rgb.next())?,
+            ))
+        }
+        _ => None,
+    }
+}
+
+/// The formats of the different styles of underline, of which there is one
+/// at a time.
+const UNDERLINES: FormatFlags = FormatFlags::UNDERLINE
+    .union(FormatFlags::DOUBLE_UNDERLINE)
+    .union(FormatFlags::UNDERCURL);
+
+const BASIC: [Color; 8] = [
+    Color::Black,
+    Color::Red,
+    Color::Green,
+    Color::Yellow,
+    Color::Blue,
+    Color::Purple,
+    Color::Cyan,
+    Color::White,
+];
+
+const BRIGHT: [Color; 8] = [
+    Color::DarkGray,
+    Color::LightRed,
+    Color::LightGreen,
+    Color::LightYellow,
+    Color::LightBlue,
+    Color::LightPurple,
+    Color::LightCyan,
+    Color::LightGray,
+];
+
+/// Parse `bytes`, which start out in `style`, into segments, taking their
+/// content from `slice`.
+pub(crate) fn parse_generic<'a, S: 'a + ?Sized + ToOwned>(
+    bytes: &'a [u8],
+    mut style: Style,
+    slice: impl Fn(Range<usize>) -> &'a S,
+) -> AnsiGenericStrings<'a, S> {
+    let borrowed = |range: Range<usize>| Content::StrLike(Cow::Borrowed(slice(range)));
+    let mut link: Option<(Range<usize>, Vec<_>)> = None;
+    let mut segments = Vec::new();
+    for token in tokenize(bytes) {
+        match token {
+            Token::Text(range) => {
+                let segment = style.paint(borrowed(range));
+                segments.push(match &link {
+                    Some((url, params)) => params.iter().fold(
+                        segment.hyperlink(slice(url.clone())),
+                        |segment, (key, value)| segment.link_param(key, value),
+                    ),
+                    None => segment,
+                });
+            }
+            Token::Sgr(params) => {
+                style = apply_sgr(style, &String::from_utf8_lossy(&bytes[params]));
+            }
+            Token::Osc(payload, sequence) => match &bytes[payload.clone()] {
+                // `8;params;url`, where an empty URL ends the link.
+                [b'8', b';', rest @ ..] => {
+                    link = rest.iter().position(|&b| b == b';').and_then(|len| {
+                        let url = payload.start + 2 + len + 1..payload.end;
+                        let params = String::from_utf8_lossy(&rest[..len])
+                            .split(':')
+                            .filter_map(|param| param.split_once('='))
+                            .map(|(key, value)| (key.to_string(), value.to_string()))
+                            .collect();
+                        Some((url, params)).filter(|(url, _)| !url.is_empty())
+                    });
+                }
+                [b'0' | b'2', b';', ..] => {
+                    segments.push(AnsiGenericString::title_content(borrowed(
+                        payload.start + 2..payload.end,
+                    )));
+                }
+                _ => segments.push(AnsiGenericString::control(borrowed(sequence))),
+            },
+            Token::Other(range) => segments.push(AnsiGenericString::control(borrowed(range))),
+        }
+    }
+    segments.into_iter().collect()
+}
+
+/// A piece of rendered text split off by [`split_at_byte_limit`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Chunk<'a> {
+    /// The text of the chunk, escape sequences included.
+    pub text: &'a str,
+    /// The style in effect at the end of the chunk, which the next chunk
+    /// starts out in.
+    pub style: Style,
+}
+
+/// Split `rendered` (text which may contain escape sequences, such as a
+/// rendered [`AnsiStrings`](crate::AnsiStrings)) into chunks of at most
+/// `limit` bytes, for protocols which limit the size of their messages.
+///
+/// Chunks are never split inside an escape sequence or a character, and
+/// are made as long as they can be, so the same input is always split the
+/// same way. A single sequence or character longer than `limit` gets a
+/// chunk of its own. Each chunk comes with the style in effect at its end,
+/// so that a receiver which stops early knows whether it needs to reset.
+///
+/// # Examples
+///
+/// ```
+/// use nu_ansi_term::parse::split_at_byte_limit;
+/// use nu_ansi_term::{AnsiStrings, Style};
+/// use nu_ansi_term::Color::{Green, Red};
+///
+/// let rendered = AnsiStrings([Red.paint("héllo"), " ".into(), Green.bold().paint("world")]).to_string();
+/// let chunks = split_at_byte_limit(&rendered, 8);
+/// let texts: Vec<&str> = chunks.iter().map(|chunk| chunk.text).collect();
+/// assert_eq!(
+///     texts,
+///     ["\x1b[31mhé", "llo\x1b[0m ", "\x1b[1;32mw", "orld\x1b[0m"]
+/// );
+/// assert_eq!(chunks[0].style, Red.normal());
+/// assert_eq!(chunks[1].style, Style::new());
+/// assert_eq!(texts.concat(), rendered);
+/// ```
+pub fn split_at_byte_limit(rendered: &str, limit: usize) -> Vec<Chunk<'_>> {
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    let mut style = Style::new();
+    // End the current chunk before `end`, if the piece of text ending at
+    // `end` would not fit in it.
+    let mut split_before = |piece_start: usize, end: usize, start: &mut usize, style: Style| {
+        if end - *start > limit && piece_start > *start {
+            chunks.push(Chunk {
+                text: &rendered[*start..piece_start],
+                style,
+            });
+            *start = piece_start;
+        }
+    };
+    for token in tokenize(rendered.as_bytes()) {
+        match token {
+            Token::Text(range) => {
+                for (ix, c) in rendered[range.clone()].char_indices() {
+                    let ix = range.start + ix;
+                    split_before(ix, ix + c.len_utf8(), &mut start, style);
+                }
+            }
+            Token::Sgr(params) => {
+                split_before(params.start - 2, params.end + 1, &mut start, style);
+                style = apply_sgr(style, &rendered[params]);
+            }
+            Token::Osc(_, sequence) | Token::Other(sequence) => {
+                split_before(sequence.start, sequence.end, &mut start, style);
+            }
+        }
+    }
+    if start < rendered.len() {
+        chunks.push(Chunk {
+            text: &rendered[start..],
+            style,
+        });
+    }
+    chunks
+}
+
+/// The length of the part of `bytes` before an escape sequence which is
+/// cut off at its end, or the length of `bytes` if there is none.
+pub(crate) fn complete_len(bytes: &[u8]) -> usize {
+    let mut stripper = Stripper::new();
+    let mut scratch = Vec::new();
+    let mut start = 0;
+    for (ix, &byte) in bytes.iter().enumerate() {
+        if !stripper.in_escape() {
+            start = ix;
+        }
+        stripper.strip(&[byte], &mut scratch);
+        scratch.clear();
+    }
+    if stripper.in_escape() {
+        start
+    } else {
+        bytes.len()
+    }
+}
+
+/// Copy everything read from `reader` to `writer`, rewriting its SGR
+/// sequences into the fewest bytes which leave the terminal in the same
+/// style, and return the number of bytes written.
+///
+/// This fixes the output of tools which reset and restate the whole style
+/// around every word before it reaches the terminal. Styles are only
+/// written once text is written in them, as by
+/// [`OptimizeFor::Bytes`](crate::OptimizeFor::Bytes), so codes which are
+/// overridden before any text are dropped. SGR sequences with parameters
+/// a [`Style`] cannot represent (such as the dotted underline `4:4`, rapid
+/// blink, or fonts) are copied unchanged, as is every SGR sequence after
+/// them until the style is reset, so that nothing is lost. Other escape
+/// sequences are copied unchanged, after the background color is brought up
+/// to date for those (such as clearing a line) which fill cells with it. The
+/// output ends in the style the input ends in.
+///
+/// The input is read in chunks, and sequences split across chunks are
+/// handled, so this can filter a pipe as it is written to.
+///
+/// # Examples
+///
+/// ```
+/// use nu_ansi_term::parse::minimize_escapes;
+///
+/// let noisy = "\x1b[0m\x1b[31mfoo\x1b[0m\x1b[31m bar\x1b[0m\x1b[0m\x1b[1;31m!\x1b[0m\n";
+/// let mut minimal = Vec::new();
+/// let written = minimize_escapes(noisy.as_bytes(), &mut minimal).unwrap();
+/// assert_eq!(minimal, b"\x1b[31mfoo bar\x1b[1m!\x1b[0m\n");
+/// assert_eq!(written, minimal.len() as u64);
+/// ```
+pub fn minimize_escapes<R: Read, W: Write>(mut reader: R, mut writer: W) -> io::Result<u64> {
+    let mut input = Vec::new();
+    let mut chunk = vec![0; 8 * 1024];
+    let mut output = Vec::new();
+    let mut written_len = 0;
+    // The style of the input, and the style the codes written so far leave
+    // the terminal in.
+    let (mut style, mut written) = (Style::new(), Style::new());
+    // Whether the terminal has been left in a state a `Style` cannot
+    // represent, such as a dotted underline, by a sequence copied as it is.
+    // Until a reset, every SGR sequence is copied as it is, so that none of
+    // that state is lost.
+    let mut unknown = false;
+    loop {
+        let read = match reader.read(&mut chunk) {
+            Ok(read) => read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        input.extend_from_slice(&chunk[..read]);
+        let end = if read == 0 {
+            input.len()
+        } else {
+            complete_len(&input)
+        };
+
+        let bytes = &input[..end];
+        for token in tokenize(bytes) {
+            match token {
+                Token::Text(range) => {
+                    if written != style {
+                        written.write_shortest_transition(style, io_write!(&mut output))?;
+                        written = style;
+                    }
+                    output.extend_from_slice(&bytes[range]);
+                }
+                Token::Sgr(params) => {
+                    let sequence = params.start - 2..params.end + 1;
+                    let mut lossy = false;
+                    let mut unknown_after = unknown;
+                    let next = apply_sgr_noting(
+                        style,
+                        &String::from_utf8_lossy(&bytes[params]),
+                        |fidelity| match fidelity {
+                            SgrFidelity::Reset => unknown_after = false,
+                            SgrFidelity::Exact => {}
+                            SgrFidelity::Lossy => {
+                                lossy = true;
+                                unknown_after = true;
+                            }
+                        },
+                    );
+                    if unknown || lossy {
+                        if written != style {
+                            written.write_shortest_transition(style, io_write!(&mut output))?;
+                        }
+                        output.extend_from_slice(&bytes[sequence]);
+                        written = next;
+                    }
+                    style = next;
+                    unknown = unknown_after;
+                }
+                Token::Osc(_, sequence) => output.extend_from_slice(&bytes[sequence]),
+                Token::Other(sequence) => {
+                    if written.coloring.bg != style.coloring.bg {
+                        written.write_shortest_transition(style, io_write!(&mut output))?;
+                        written = style;
+                    }
+                    output.extend_from_slice(&bytes[sequence]);
+                }
+            }
+        }
+        if read == 0 && written != style {
+            written.write_shortest_transition(style, io_write!(&mut output))?;
+        }
+
+        writer.write_all(&output)?;
+        written_len += output.len() as u64;
+        output.clear();
+        input.drain(..end);
+        if read == 0 {
+            return Ok(written_len);
+        }
+    }
+}
+
+impl<'a> AnsiGenericStrings<'a, str> {
+    /// Parse text containing escape sequences, such as the output of
+    /// another program, into styled segments borrowing from it.
+    ///
+    /// SGR sequences set the style of the text after them, OSC 8 sequences
+    /// its hyperlink, and OSC 0 and 2 sequences become
+    /// [`title`](AnsiGenericString::title_content) segments. Other escape
+    /// sequences are kept as [`control`](AnsiGenericString::control)
+    /// segments, so writing the result again only changes how the styles
+    /// are encoded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::AnsiStrings;
+    /// use nu_ansi_term::Color::{Blue, Green};
+    ///
+    /// let output = "\x1b[1;32mok\x1b[0m: \x1b]8;;https://example.com\x1b\\docs\x1b]8;;\x1b\\";
+    /// let parsed = AnsiStrings::parse(output);
+    /// let segments: Vec<_> = parsed.iter().collect();
+    /// assert_eq!(segments[0].style_ref(), &Green.bold());
+    /// assert_eq!(segments[1].content().to_string(), ": ");
+    /// assert_eq!(segments[2].url_string().unwrap().to_string(), "https://example.com");
+    ///
+    /// // Re-style the parsed output.
+    /// let restyled: AnsiStrings = parsed.iter().cloned().map(|s| s.fg(Blue)).collect();
+    /// assert!(restyled.to_string().starts_with("\x1b[1;34mok"));
+    /// ```
+    pub fn parse(text: &'a str) -> Self {
+        parse_generic(text.as_bytes(), Style::new(), |range| &text[range])
+    }
+}
+
+impl<'a> AnsiGenericStrings<'a, [u8]> {
+    /// Parse bytes containing escape sequences into styled segments
+    /// borrowing from them. See [`AnsiStrings::parse`](crate::AnsiStrings).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::AnsiByteStrings;
+    /// use nu_ansi_term::Color::Red;
+    ///
+    /// let parsed = AnsiByteStrings::parse(b"\x1b[31m\xffbytes\x1b[0m");
+    /// let mut written = Vec::new();
+    /// parsed.write_to(&mut written).unwrap();
+    /// assert_eq!(written, b"\x1b[31m\xffbytes\x1b[0m");
+    /// assert_eq!(parsed.iter().next().unwrap().style_ref(), &Red.normal());
+    /// ```
+    pub fn parse(bytes: &'a [u8]) -> Self {
+        parse_generic(bytes, Style::new(), |range| &bytes[range])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Color::*;
+    use crate::{AnsiString, AnsiStrings};
+
+    #[test]
+    fn round_trip() {
+        let original = AnsiStrings([
+            Red.bold().paint("error"),
+            AnsiString::from(": "),
+            Fixed(244).on(Rgb(1, 2, 3)).italic().paint("file"),
+            Blue.underline()
+                .paint("link")
+                .hyperlink("https://example.com/a b"),
+            Blue.paint("more").hyperlink("https://x").link_id("x"),
+            AnsiString::control("\x1b[2K"),
+            AnsiString::title("title"),
+            LightCyan.strikethrough().paint("end"),
+        ])
+        .to_string();
+        let parsed = AnsiStrings::parse(&original);
+        assert_eq!(parsed.to_string(), original);
+        assert_eq!(parsed.iter().filter(|s| s.is_control()).count(), 1);
+    }
+
+    #[test]
+    fn styles_accumulate_and_reset() {
+        let parsed = AnsiStrings::parse("\x1b[1ma\x1b[31mb\x1b[22;4mc\x1b[mdef\x1b[39;49m");
+        let segments: Vec<(String, Style)> = parsed
+            .iter()
+            .map(|s| (s.content().to_string(), *s.style_ref()))
+            .collect();
+        assert_eq!(
+            segments,
+            [
+                ("a".to_string(), Style::new().bold()),
+                ("b".to_string(), Red.bold()),
+                ("c".to_string(), Red.underline()),
+                ("def".to_string(), Style::new()),
+            ]
+        );
+    }
+
+    #[test]
+    fn malformed_input() {
+        for text in [
+            "\x1b",
+            "a\x1b[",
+            "a\x1b[31",
+            "\x1b]8;;http",
+            "\x1b(",
+            "\x1b[38;5m",
+        ] {
+            let parsed = AnsiStrings::parse(text);
+            let plain: String = parsed
+                .iter()
+                .filter(|s| !s.is_control())
+                .map(|s| s.content().to_string())
+                .collect();
+            assert!(text.starts_with(&plain));
+        }
+        assert_eq!(
+            tokenize(b"\x1b(Bx\x1b"),
+            [Token::Other(0..3), Token::Text(3..4), Token::Other(4..5)]
+        );
+        assert_eq!(tokenize("\x1bé".as_bytes()), [Token::Other(0..3)]);
+        assert_eq!(AnsiStrings::parse("\x1béa").unstyled(), "a");
+        assert_eq!(AnsiStrings::parse("\x1bé").to_string(), "\x1bé");
+    }
+
+    #[test]
+    fn sgr_params_round_trip() {
+        for style in [
+            Style::new(),
+            Blue.bold(),
+            Fixed(208).on(Rgb(1, 2, 3)).italic().strikethrough(),
+            LightGray.on(DarkGray).dimmed().reverse().hidden(),
+        ] {
+            assert_eq!(Style::from_sgr_params(&style.sgr_params()), style);
+        }
+        let colors = parse_ls_colors("rs=0:di=01;34::=:no=:*.rs=38;2;222;165;132");
+        assert_eq!(colors["rs"], Style::new());
+        assert_eq!(colors["*.rs"], Rgb(222, 165, 132).normal());
+        assert_eq!(colors[""], Style::new());
+        assert_eq!(colors.len(), 5);
+    }
+
+    #[test]
+    fn chunks_fit_the_limit() {
+        let rendered = AnsiStrings([
+            Red.bold().paint("日本語"),
+            Blue.paint("link").hyperlink("https://example.com"),
+            AnsiString::control("\x1b[2K"),
+            Fixed(208).on(Rgb(1, 2, 3)).paint("end"),
+        ])
+        .to_string();
+        for limit in 0..=rendered.len() + 1 {
+            let chunks = split_at_byte_limit(&rendered, limit);
+            let texts: Vec<&str> = chunks.iter().map(|chunk| chunk.text).collect();
+            assert_eq!(texts.concat(), rendered);
+            let mut style = Style::new();
+            for chunk in &chunks {
+                let tokens = tokenize(chunk.text.as_bytes());
+                assert!(chunk.text.len() <= limit || tokens.len() == 1);
+                for token in tokens {
+                    if let Token::Sgr(params) = token {
+                        style = apply_sgr(style, &chunk.text[params]);
+                    }
+                }
+                assert_eq!(chunk.style, style);
+            }
+        }
+        assert_eq!(split_at_byte_limit("", 4), []);
+    }
+
+    #[test]
+    fn minimizes_escapes_split_across_reads() {
+        /// A reader returning a byte at a time.
+        struct Trickle<'a>(&'a [u8]);
+        impl Read for Trickle<'_> {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                let len = self.0.len().min(buf.len()).min(1);
+                buf[..len].copy_from_slice(&self.0[..len]);
+                self.0 = &self.0[len..];
+                Ok(len)
+            }
+        }
+
+        let input =
+            "\x1b[0;31ma\x1b[0m\x1b[31mb\x1b]8;;url\x1b\\c\x1b]8;;\x1b\\\x1b[44m\x1b[2Kd\x1b[0m";
+        let mut output = Vec::new();
+        minimize_escapes(Trickle(input.as_bytes()), &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "\x1b[31mab\x1b]8;;url\x1b\\c\x1b]8;;\x1b\\\x1b[44m\x1b[2Kd\x1b[0m"
+        );
+
+        let mut output = Vec::new();
+        minimize_escapes(&b"\x1b[1mbold\x1b[3"[..], &mut output).unwrap();
+        assert_eq!(output, b"\x1b[1mbold\x1b[3");
+    }
+
+    #[test]
+    fn minimizing_keeps_codes_styles_cannot_represent() {
+        let minimize = |input: &str| {
+            let mut output = Vec::new();
+            minimize_escapes(input.as_bytes(), &mut output).unwrap();
+            String::from_utf8(output).unwrap()
+        };
+        for code in ["4:4", "6", "51", "10", "1:2", "38;5"] {
+            let input = format!("\x1b[{}mx\x1b[0m", code);
+            assert_eq!(minimize(&input), input);
+        }
+        assert_eq!(
+            minimize("\x1b[31m\x1b[4:4mx\x1b[4my\x1b[24mz\x1b[0m"),
+            "\x1b[31m\x1b[4:4mx\x1b[4my\x1b[24mz\x1b[0m"
+        );
+        assert_eq!(
+            minimize("\x1b[51mx\x1b[0m\x1b[31my\x1b[0m\x1b[31mz\x1b[0m"),
+            "\x1b[51mx\x1b[0m\x1b[31myz\x1b[0m"
+        );
+        assert_eq!(
+            minimize("\x1b[1;4:5;0;32mx\x1b[0m\x1b[32my"),
+            "\x1b[1;4:5;0;32mxy"
+        );
+    }
+}
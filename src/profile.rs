@@ -0,0 +1,377 @@
+use crate::instrument::SequenceKind;
+use crate::ColorLevel;
+use std::cell::Cell;
+use std::env;
+use std::sync::atomic::{AtomicU16, Ordering};
+
+/// The profile set with [`TerminalProfile::set_render_default`], packed by
+/// [`TerminalProfile::to_bits`], or [`UNSET`] if there is none.
+static RENDER_DEFAULT: AtomicU16 = AtomicU16::new(UNSET);
+const UNSET: u16 = u16::MAX;
+
+thread_local! {
+    /// The profile output is being written for on this thread, if it was
+    /// passed in explicitly (see [`render_for`]).
+    static RENDERING: Cell<Option<TerminalProfile>> = const { Cell::new(None) };
+}
+
+/// Run `render` with `profile` as the profile output is written for on this
+/// thread, in place of the render default: lazily resolved styles are
+/// resolved for it, and OSC sequences it does not support are left out,
+/// including those of nested strings and formatting arguments.
+pub(crate) fn render_for<R>(profile: TerminalProfile, render: impl FnOnce() -> R) -> R {
+    /// Restores the profile from before, even if `render` panics.
+    struct Guard(Option<TerminalProfile>);
+
+    impl Drop for Guard {
+        fn drop(&mut self) {
+            // `try_with`, as this may run while the thread is exiting.
+            let _ = RENDERING.try_with(|rendering| rendering.set(self.0));
+        }
+    }
+
+    let _guard = Guard(RENDERING.with(|rendering| rendering.replace(Some(profile))));
+    render()
+}
+
+/// Capabilities of the terminal output is written to, which renderers
+/// consult to decide which features they can use.
+///
+/// # Examples
+///
+/// ```
+/// use nu_ansi_term::TerminalProfile;
+///
+/// let profile = TerminalProfile::detect();
+/// if !profile.unicode {
+///     println!("falling back to ASCII");
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TerminalProfile {
+    /// Whether the terminal can display characters outside of ASCII, such
+    /// as box drawing characters.
+    pub unicode: bool,
+    /// Whether the terminal supports hyperlinks (OSC 8). See
+    /// [`LinkFallback`](crate::LinkFallback) for rendering them elsewhere.
+    pub hyperlinks: bool,
+    /// The colors the terminal can display. Renderers which honour it
+    /// downgrade colors it cannot display (see [`Style::adapt_to`]).
+    ///
+    /// [`Style::adapt_to`]: crate::Style::adapt_to
+    pub colors: ColorLevel,
+    /// Whether the terminal's font is patched with the icons of
+    /// [Nerd Fonts](https://www.nerdfonts.com), which icon lookups prefer
+    /// over emoji when it is.
+    pub nerd_fonts: bool,
+    /// Whether the terminal shows dimmed text (SGR 2) as fainter than normal
+    /// text, rather than ignoring it or showing it in another color.
+    pub dim: bool,
+    /// Whether the terminal supports setting the window title (OSC 2).
+    pub titles: bool,
+    /// Whether the terminal supports copying to the clipboard (OSC 52).
+    pub clipboard: bool,
+    /// Whether the terminal supports desktop notifications (OSC 9 and 777).
+    pub notifications: bool,
+    /// Whether the terminal supports reporting the working directory
+    /// (OSC 7).
+    pub cwd: bool,
+}
+
+impl Default for TerminalProfile {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TerminalProfile {
+    /// A profile for a fully capable terminal.
+    pub const fn new() -> Self {
+        Self {
+            unicode: true,
+            hyperlinks: true,
+            colors: ColorLevel::TrueColor,
+            nerd_fonts: true,
+            dim: true,
+            titles: true,
+            clipboard: true,
+            notifications: true,
+            cwd: true,
+        }
+    }
+
+    /// A profile for a terminal which only supports ASCII.
+    pub const fn ascii() -> Self {
+        Self {
+            unicode: false,
+            nerd_fonts: false,
+            ..Self::new()
+        }
+    }
+
+    /// A copy of this profile for a terminal which does not support
+    /// hyperlinks.
+    pub const fn without_hyperlinks(mut self) -> Self {
+        self.hyperlinks = false;
+        self
+    }
+
+    /// A copy of this profile for a terminal whose font does not include
+    /// the icons of Nerd Fonts.
+    pub const fn without_nerd_fonts(mut self) -> Self {
+        self.nerd_fonts = false;
+        self
+    }
+
+    /// A copy of this profile for a terminal which does not show dimmed
+    /// text as fainter than normal text.
+    pub const fn without_dim(mut self) -> Self {
+        self.dim = false;
+        self
+    }
+
+    /// A copy of this profile for a terminal which does not support setting
+    /// the window title.
+    pub const fn without_titles(mut self) -> Self {
+        self.titles = false;
+        self
+    }
+
+    /// A copy of this profile for a terminal which does not support copying
+    /// to the clipboard.
+    pub const fn without_clipboard(mut self) -> Self {
+        self.clipboard = false;
+        self
+    }
+
+    /// A copy of this profile for a terminal which does not support desktop
+    /// notifications.
+    pub const fn without_notifications(mut self) -> Self {
+        self.notifications = false;
+        self
+    }
+
+    /// A copy of this profile for a terminal which does not support
+    /// reporting the working directory.
+    pub const fn without_cwd(mut self) -> Self {
+        self.cwd = false;
+        self
+    }
+
+    /// Whether the terminal supports escape sequences of `kind`. Terminal
+    /// multiplexers often pass some kinds of OSC sequences through and not
+    /// others, and the writers of this crate leave out the sequences a
+    /// profile does not support: see [`set_render_default`] and
+    /// [`render_all`].
+    ///
+    /// [`set_render_default`]: Self::set_render_default
+    /// [`render_all`]: crate::AnsiStrings::render_all
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::instrument::SequenceKind;
+    /// use nu_ansi_term::TerminalProfile;
+    ///
+    /// let profile = TerminalProfile::new().without_clipboard();
+    /// assert!(profile.supports(SequenceKind::Title));
+    /// assert!(!profile.supports(SequenceKind::Clipboard));
+    /// ```
+    pub const fn supports(&self, kind: SequenceKind) -> bool {
+        match kind {
+            SequenceKind::Sgr | SequenceKind::Reset => true,
+            SequenceKind::Hyperlink => self.hyperlinks,
+            SequenceKind::Title => self.titles,
+            SequenceKind::Clipboard => self.clipboard,
+            SequenceKind::Notification => self.notifications,
+            SequenceKind::Cwd => self.cwd,
+        }
+    }
+
+    /// A copy of this profile for a terminal which displays the colors of
+    /// `level`.
+    pub const fn with_colors(mut self, level: ColorLevel) -> Self {
+        self.colors = level;
+        self
+    }
+
+    /// Detect the capabilities of the current terminal from the environment.
+    ///
+    /// On unix, the locale variables `LC_ALL`, `LC_CTYPE` and `LANG` are
+    /// checked (in that order, as the C library does) for a UTF-8 codeset.
+    /// Hyperlinks and the other OSC sequences (titles, the clipboard,
+    /// notifications and the working directory) are assumed to be supported
+    /// unless `TERM` is `dumb`, since most other terminals at least ignore
+    /// them. Colors are detected by
+    /// [`ColorLevel::detect`]. Since fonts cannot be detected, Nerd Fonts
+    /// are only assumed if `NERD_FONTS` is set to something other than `0`.
+    /// Dimmed text is assumed to be shown as faint except on the Linux
+    /// console (`TERM=linux`), which shows it in a different color.
+    pub fn detect() -> Self {
+        let osc = locale_var("TERM").map_or(true, |term| term != "dumb");
+        Self {
+            unicode: cfg!(windows) || unicode_from_locale(locale_var),
+            hyperlinks: osc,
+            colors: ColorLevel::detect(),
+            nerd_fonts: locale_var("NERD_FONTS").map_or(false, |value| value != "0"),
+            dim: locale_var("TERM").map_or(true, |term| term != "linux"),
+            titles: osc,
+            clipboard: osc,
+            notifications: osc,
+            cwd: osc,
+        }
+    }
+}
+
+impl TerminalProfile {
+    /// Set the profile lazily resolved styles are resolved for when a
+    /// sequence is written without a profile of its own (e.g. through its
+    /// `Display` implementation). Until this is called, it is
+    /// [`TerminalProfile::new`].
+    ///
+    /// OSC sequences the profile does not support (see
+    /// [`supports`](Self::supports)) are left out when writing without a
+    /// profile: titles, clipboard copies, notifications and working
+    /// directory reports are dropped, and hyperlinks are written as their
+    /// text alone (see [`LinkFallback`](crate::LinkFallback) for showing
+    /// their URLs instead).
+    ///
+    /// Writing never reads the environment by itself; an application which
+    /// wants the capabilities of its terminal sets them here.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::{AnsiStrings, TerminalProfile};
+    /// use nu_ansi_term::Color::{Green, Yellow};
+    ///
+    /// let mut strings = AnsiStrings([]);
+    /// strings.push_lazy("ok", |profile: &TerminalProfile| {
+    ///     if profile.unicode { Green.normal() } else { Yellow.normal() }
+    /// });
+    /// assert_eq!(strings.to_string(), "\x1b[32mok\x1b[0m");
+    ///
+    /// TerminalProfile::set_render_default(TerminalProfile::ascii());
+    /// assert_eq!(strings.to_string(), "\x1b[33mok\x1b[0m");
+    /// ```
+    pub fn set_render_default(profile: TerminalProfile) {
+        RENDER_DEFAULT.store(profile.to_bits(), Ordering::Relaxed);
+    }
+
+    /// The profile set with [`set_render_default`](Self::set_render_default).
+    pub fn render_default() -> TerminalProfile {
+        match RENDER_DEFAULT.load(Ordering::Relaxed) {
+            UNSET => TerminalProfile::new(),
+            bits => TerminalProfile::from_bits(bits),
+        }
+    }
+
+    /// The profile output is being written for on this thread: the one
+    /// passed in explicitly, if any, and otherwise the render default.
+    pub(crate) fn rendering() -> TerminalProfile {
+        RENDERING
+            .with(Cell::get)
+            .unwrap_or_else(TerminalProfile::render_default)
+    }
+
+    fn to_bits(self) -> u16 {
+        let colors = match self.colors {
+            ColorLevel::NoColor => 0,
+            ColorLevel::Ansi16 => 1,
+            ColorLevel::Ansi256 => 2,
+            ColorLevel::TrueColor => 3,
+        };
+        self.unicode as u16
+            | (self.hyperlinks as u16) << 1
+            | colors << 2
+            | (self.nerd_fonts as u16) << 4
+            | (self.dim as u16) << 5
+            | (self.titles as u16) << 6
+            | (self.clipboard as u16) << 7
+            | (self.notifications as u16) << 8
+            | (self.cwd as u16) << 9
+    }
+
+    fn from_bits(bits: u16) -> TerminalProfile {
+        TerminalProfile {
+            unicode: bits & 1 != 0,
+            hyperlinks: bits & 2 != 0,
+            colors: match bits >> 2 & 3 {
+                0 => ColorLevel::NoColor,
+                1 => ColorLevel::Ansi16,
+                2 => ColorLevel::Ansi256,
+                _ => ColorLevel::TrueColor,
+            },
+            nerd_fonts: bits & 16 != 0,
+            dim: bits & 32 != 0,
+            titles: bits & 64 != 0,
+            clipboard: bits & 128 != 0,
+            notifications: bits & 256 != 0,
+            cwd: bits & 512 != 0,
+        }
+    }
+}
+
+/// Get the value of a locale environment variable, if it is set and non-empty.
+fn locale_var(name: &str) -> Option<String> {
+    env::var(name).ok().filter(|value| !value.is_empty())
+}
+
+/// Whether the effective locale, read through `var`, uses UTF-8.
+fn unicode_from_locale(var: impl Fn(&str) -> Option<String>) -> bool {
+    ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .find_map(|name| var(name))
+        .map_or(false, |locale| {
+            let locale = locale.to_ascii_lowercase();
+            locale.contains("utf-8") || locale.contains("utf8")
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn unicode_for(vars: &[(&str, &str)]) -> bool {
+        unicode_from_locale(|name| {
+            vars.iter()
+                .find(|(var, _)| *var == name)
+                .map(|(_, value)| value.to_string())
+        })
+    }
+
+    #[test]
+    fn locale_precedence() {
+        assert!(unicode_for(&[("LANG", "en_US.UTF-8")]));
+        assert!(unicode_for(&[("LC_CTYPE", "de_DE.utf8"), ("LANG", "C")]));
+        assert!(!unicode_for(&[("LC_ALL", "C"), ("LANG", "en_US.UTF-8")]));
+        assert!(!unicode_for(&[("LANG", "en_US.ISO-8859-1")]));
+        assert!(!unicode_for(&[]));
+    }
+
+    #[test]
+    fn packed_profiles() {
+        for colors in [
+            ColorLevel::NoColor,
+            ColorLevel::Ansi16,
+            ColorLevel::Ansi256,
+            ColorLevel::TrueColor,
+        ] {
+            for profile in [
+                TerminalProfile::new(),
+                TerminalProfile::ascii(),
+                TerminalProfile::ascii().without_hyperlinks(),
+                TerminalProfile::new().without_nerd_fonts(),
+                TerminalProfile::new().without_dim(),
+                TerminalProfile::new().without_titles().without_cwd(),
+                TerminalProfile::new()
+                    .without_clipboard()
+                    .without_notifications(),
+            ] {
+                let profile = profile.with_colors(colors);
+                assert_ne!(profile.to_bits(), UNSET);
+                assert_eq!(TerminalProfile::from_bits(profile.to_bits()), profile);
+            }
+        }
+    }
+}
@@ -0,0 +1,669 @@
+use crate::terminal::detect_width;
+use crate::width::{truncate_segments, WidthPolicy};
+use crate::{AnsiString, AnsiStrings, ContinuationMarker, Style};
+use std::fmt;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+/// Width used by a [`PromptLayout`] when none was supplied and none could be
+/// detected.
+pub const DEFAULT_WIDTH: usize = 80;
+
+/// The solid right-pointing separator of powerline fonts.
+pub const POWERLINE_ARROW: &str = "\u{E0B0}";
+
+/// Where a segment is placed on its line of a [`PromptLayout`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Alignment {
+    /// Segment is placed after the other left aligned segments.
+    Left,
+    /// Segment is placed after the other centered segments, with the group
+    /// centered on the line.
+    Center,
+    /// Segment is placed after the other right aligned segments, with the
+    /// group ending at the last column.
+    Right,
+}
+
+/// A segment of a [`PromptLine`] which is produced by a closure each time the
+/// layout is rendered, such as a clock or a counter in a status line, so
+/// that it changes without the layout being built again.
+///
+/// # Examples
+///
+/// ```
+/// use std::cell::Cell;
+/// use std::rc::Rc;
+/// use nu_ansi_term::{DynamicSegment, PromptLayout, PromptLine};
+/// use nu_ansi_term::utils::unstyle;
+/// use nu_ansi_term::Color::Green;
+///
+/// let jobs = Rc::new(Cell::new(0));
+/// let counter = Rc::clone(&jobs);
+/// let layout = PromptLayout::new().width(10).line(
+///     PromptLine::new().left("> ").dynamic_right(DynamicSegment::new(move || {
+///         Green.paint(format!("{} jobs", counter.get()))
+///     })),
+/// );
+///
+/// assert_eq!(unstyle(&layout.render()), ">   0 jobs");
+/// jobs.set(3);
+/// assert_eq!(unstyle(&layout.render()), ">   3 jobs");
+/// ```
+#[derive(Clone)]
+pub struct DynamicSegment<'a>(Rc<dyn Fn() -> AnsiString<'a> + 'a>);
+
+impl<'a> DynamicSegment<'a> {
+    /// Wrap a closure producing the segment.
+    pub fn new(f: impl Fn() -> AnsiString<'a> + 'a) -> Self {
+        Self(Rc::new(f))
+    }
+
+    /// A segment showing the time since `start` in `style`, like `42s`,
+    /// `3m 05s` or `2h 10m`.
+    pub fn since(start: Instant, style: Style) -> Self {
+        Self::new(move || style.paint(format_elapsed(start.elapsed())))
+    }
+
+    /// Produce the segment as it is now.
+    pub fn render(&self) -> AnsiString<'a> {
+        (self.0)()
+    }
+}
+
+impl<'a> fmt::Debug for DynamicSegment<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DynamicSegment").finish_non_exhaustive()
+    }
+}
+
+/// `elapsed` in its two largest units, rounded down to whole seconds.
+fn format_elapsed(elapsed: Duration) -> String {
+    let seconds = elapsed.as_secs();
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if hours > 0 {
+        format!("{}h {:02}m", hours, minutes)
+    } else if minutes > 0 {
+        format!("{}m {:02}s", minutes, seconds)
+    } else {
+        format!("{}s", seconds)
+    }
+}
+
+/// How a segment of a [`PromptLine`] gives way when the line is wider than
+/// the layout.
+///
+/// Segments with the lowest priority give way first: each is truncated (with
+/// an ellipsis) as far as its minimum width allows, or hidden if that is not
+/// enough for the line to fit, until the line fits. Segments which are not
+/// given a `Shrink` never give way.
+///
+/// # Examples
+///
+/// ```
+/// use nu_ansi_term::{PromptLayout, PromptLine, Shrink};
+/// use nu_ansi_term::utils::unstyle;
+///
+/// let line = PromptLine::new()
+///     .left("~/src/nushell/crates")
+///     .shrink(Shrink::new(2).min_width(8))
+///     .left(" main")
+///     .shrink(Shrink::new(1))
+///     .right(" 12:00");
+///
+/// let wide = PromptLayout::new().width(40).line(line.clone());
+/// assert_eq!(unstyle(&wide.render()), "~/src/nushell/crates main          12:00");
+/// let narrow = PromptLayout::new().width(22).line(line);
+/// assert_eq!(unstyle(&narrow.render()), "~/src/nushell/c… 12:00");
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Shrink {
+    priority: u32,
+    min_width: usize,
+}
+
+impl Shrink {
+    /// Give way with `priority`, hiding the segment rather than truncating
+    /// it.
+    pub const fn new(priority: u32) -> Self {
+        Self {
+            priority,
+            min_width: 0,
+        }
+    }
+
+    /// Truncate the segment to no fewer than `min_width` columns before
+    /// hiding it.
+    pub const fn min_width(mut self, min_width: usize) -> Self {
+        self.min_width = min_width;
+        self
+    }
+}
+
+/// A single line of a [`PromptLayout`], made of left, center and right
+/// aligned groups of styled segments.
+#[derive(Clone, Debug, Default)]
+pub struct PromptLine<'a> {
+    left: Vec<AnsiString<'a>>,
+    center: Vec<AnsiString<'a>>,
+    right: Vec<AnsiString<'a>>,
+    /// Dynamic segments, with their group and the number of segments in it
+    /// which come before them.
+    dynamic: Vec<(Alignment, usize, DynamicSegment<'a>)>,
+    /// How segments give way, by their group and position in it, counting
+    /// dynamic segments.
+    shrinks: Vec<(Alignment, usize, Shrink)>,
+    /// The group and position of the segment added last.
+    last: Option<(Alignment, usize)>,
+}
+
+impl<'a> PromptLine<'a> {
+    /// Create an empty line.
+    pub const fn new() -> Self {
+        Self {
+            left: Vec::new(),
+            center: Vec::new(),
+            right: Vec::new(),
+            dynamic: Vec::new(),
+            shrinks: Vec::new(),
+            last: None,
+        }
+    }
+
+    /// Add a segment with the given alignment to this line.
+    pub fn segment(mut self, alignment: Alignment, segment: impl Into<AnsiString<'a>>) -> Self {
+        self.last = Some((alignment, self.group_len(alignment)));
+        match alignment {
+            Alignment::Left => self.left.push(segment.into()),
+            Alignment::Center => self.center.push(segment.into()),
+            Alignment::Right => self.right.push(segment.into()),
+        }
+        self
+    }
+
+    /// Add a left aligned segment to this line.
+    pub fn left(self, segment: impl Into<AnsiString<'a>>) -> Self {
+        self.segment(Alignment::Left, segment)
+    }
+
+    /// Add a centered segment to this line.
+    pub fn center(self, segment: impl Into<AnsiString<'a>>) -> Self {
+        self.segment(Alignment::Center, segment)
+    }
+
+    /// Add a right aligned segment to this line.
+    pub fn right(self, segment: impl Into<AnsiString<'a>>) -> Self {
+        self.segment(Alignment::Right, segment)
+    }
+
+    /// Add a segment with the given alignment to this line, which is
+    /// produced again each time the layout is rendered.
+    pub fn dynamic(mut self, alignment: Alignment, segment: DynamicSegment<'a>) -> Self {
+        self.last = Some((alignment, self.group_len(alignment)));
+        let before = self.segments(alignment).len();
+        self.dynamic.push((alignment, before, segment));
+        self
+    }
+
+    /// Add a left aligned dynamic segment to this line.
+    pub fn dynamic_left(self, segment: DynamicSegment<'a>) -> Self {
+        self.dynamic(Alignment::Left, segment)
+    }
+
+    /// Add a centered dynamic segment to this line.
+    pub fn dynamic_center(self, segment: DynamicSegment<'a>) -> Self {
+        self.dynamic(Alignment::Center, segment)
+    }
+
+    /// Add a right aligned dynamic segment to this line.
+    pub fn dynamic_right(self, segment: DynamicSegment<'a>) -> Self {
+        self.dynamic(Alignment::Right, segment)
+    }
+
+    /// Let the segment added last give way as `shrink` says when the line
+    /// does not fit.
+    pub fn shrink(mut self, shrink: Shrink) -> Self {
+        if let Some((alignment, position)) = self.last {
+            self.shrinks
+                .retain(|&(other, at, _)| (other, at) != (alignment, position));
+            self.shrinks.push((alignment, position, shrink));
+        }
+        self
+    }
+
+    /// Number of segments with the given alignment, counting dynamic ones.
+    fn group_len(&self, alignment: Alignment) -> usize {
+        let dynamic = self
+            .dynamic
+            .iter()
+            .filter(|(group, _, _)| *group == alignment);
+        self.segments(alignment).len() + dynamic.count()
+    }
+
+    /// Get the segments of this line with the given alignment. Dynamic
+    /// segments are not included; see [`rendered`](Self::rendered).
+    pub fn segments(&self, alignment: Alignment) -> &[AnsiString<'a>] {
+        match alignment {
+            Alignment::Left => &self.left,
+            Alignment::Center => &self.center,
+            Alignment::Right => &self.right,
+        }
+    }
+
+    /// Get the segments of this line with the given alignment, with its
+    /// dynamic segments produced as they are now.
+    pub fn rendered(&self, alignment: Alignment) -> Vec<AnsiString<'a>> {
+        let segments = self.segments(alignment);
+        let mut dynamic = self
+            .dynamic
+            .iter()
+            .filter(|(group, _, _)| *group == alignment)
+            .peekable();
+        let mut rendered = Vec::with_capacity(segments.len());
+        for ix in 0..=segments.len() {
+            while let Some((_, _, segment)) = dynamic.next_if(|(_, before, _)| *before == ix) {
+                rendered.push(segment.render());
+            }
+            rendered.extend(segments.get(ix).cloned());
+        }
+        rendered
+    }
+}
+
+/// Builder for multi-line prompts whose lines contain left, center and right
+/// aligned segments, with the gaps between them filled up to the terminal
+/// width.
+///
+/// # Examples
+///
+/// ```
+/// use nu_ansi_term::{PromptLayout, PromptLine};
+/// use nu_ansi_term::Color::{Blue, Green, Purple};
+///
+/// let prompt = PromptLayout::new()
+///     .width(30)
+///     .line(
+///         PromptLine::new()
+///             .left(Blue.bold().paint("~/src/nushell"))
+///             .right(Purple.paint("main")),
+///     )
+///     .line(PromptLine::new().left(Green.paint("> ")));
+///
+/// println!("{}", prompt.render());
+/// ```
+#[derive(Clone, Debug)]
+pub struct PromptLayout<'a> {
+    lines: Vec<PromptLine<'a>>,
+    width: Option<usize>,
+    fill: char,
+    fill_style: Style,
+    width_policy: WidthPolicy,
+    #[cfg(feature = "unicode-bidi")]
+    bidi_isolation: Option<crate::BidiIsolation>,
+}
+
+impl<'a> Default for PromptLayout<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> PromptLayout<'a> {
+    /// Create an empty layout, which fills gaps with unstyled spaces and
+    /// detects the width it is rendered at.
+    pub const fn new() -> Self {
+        Self {
+            lines: Vec::new(),
+            width: None,
+            fill: ' ',
+            fill_style: Style::new(),
+            width_policy: WidthPolicy::new(),
+            #[cfg(feature = "unicode-bidi")]
+            bidi_isolation: None,
+        }
+    }
+
+    /// Render the layout at a fixed `width` instead of detecting it.
+    pub const fn width(mut self, width: usize) -> Self {
+        self.width = Some(width);
+        self
+    }
+
+    /// Fill the gaps between aligned groups with `fill`, painted in `style`.
+    pub const fn fill(mut self, fill: char, style: Style) -> Self {
+        self.fill = fill;
+        self.fill_style = style;
+        self
+    }
+
+    /// Measure segments and the fill character with `policy`.
+    pub const fn width_policy(mut self, policy: WidthPolicy) -> Self {
+        self.width_policy = policy;
+        self
+    }
+
+    /// Isolate aligned groups containing right-to-left text, so that they
+    /// do not swap places with the fill and the other groups.
+    #[cfg(feature = "unicode-bidi")]
+    pub const fn bidi_isolation(mut self, isolation: crate::BidiIsolation) -> Self {
+        self.bidi_isolation = Some(isolation);
+        self
+    }
+
+    fn isolate(&self, segments: Vec<AnsiString<'a>>) -> Vec<AnsiString<'a>> {
+        #[cfg(feature = "unicode-bidi")]
+        if let Some(isolation) = self.bidi_isolation {
+            return isolation.isolate(segments);
+        }
+        segments
+    }
+
+    /// Append a line to the layout.
+    pub fn line(mut self, line: PromptLine<'a>) -> Self {
+        self.lines.push(line);
+        self
+    }
+
+    /// The width this layout will be rendered at: the supplied width if any,
+    /// otherwise the detected terminal width (see
+    /// [`terminal_size`](crate::terminal::TerminalSize)), falling back to
+    /// [`DEFAULT_WIDTH`].
+    pub fn effective_width(&self) -> usize {
+        self.width.or_else(detect_width).unwrap_or(DEFAULT_WIDTH)
+    }
+
+    /// Render every line of the layout separately.
+    pub fn render_lines(&self) -> Vec<AnsiStrings<'a>> {
+        let width = self.effective_width();
+        self.lines
+            .iter()
+            .map(|line| self.render_line(line, width))
+            .collect()
+    }
+
+    /// Render the whole layout, with lines separated by `\n`.
+    pub fn render(&self) -> AnsiStrings<'a> {
+        let width = self.effective_width();
+        let mut segments = Vec::new();
+        for (ix, line) in self.lines.iter().enumerate() {
+            if ix > 0 {
+                segments.push(AnsiString::from("\n"));
+            }
+            segments.extend(self.render_line(line, width).iter().cloned());
+        }
+        AnsiStrings(segments)
+    }
+
+    fn render_line(&self, line: &PromptLine<'a>, width: usize) -> AnsiStrings<'a> {
+        let group_width = |group: &[AnsiString<'_>]| -> usize {
+            group
+                .iter()
+                .map(|segment| self.width_policy.segment_width(segment))
+                .sum()
+        };
+
+        let [left, center, right] = self.fit(line, width).map(|group| self.isolate(group));
+        let center_width = group_width(&center);
+        let right_width = group_width(&right);
+
+        let mut used = group_width(&left);
+        let mut segments = left;
+        if !center.is_empty() {
+            let center_start = (width.saturating_sub(center_width) / 2).max(used);
+            self.push_fill(&mut segments, center_start - used);
+            segments.extend(center);
+            used = center_start + center_width;
+        }
+        if !right.is_empty() {
+            self.push_fill(&mut segments, width.saturating_sub(used + right_width));
+            segments.extend(right);
+        }
+        AnsiStrings(segments)
+    }
+
+    /// The left, center and right groups of `line`, with segments given way
+    /// (see [`Shrink`]) until they fit in `width` columns.
+    fn fit(&self, line: &PromptLine<'a>, width: usize) -> [Vec<AnsiString<'a>>; 3] {
+        let alignments = [Alignment::Left, Alignment::Center, Alignment::Right];
+        let mut groups = alignments.map(|alignment| {
+            line.rendered(alignment)
+                .into_iter()
+                .map(|segment| vec![segment])
+                .collect::<Vec<_>>()
+        });
+        let segment_width = |segments: &[AnsiString<'_>]| -> usize {
+            segments
+                .iter()
+                .map(|segment| self.width_policy.segment_width(segment))
+                .sum()
+        };
+        let total: usize = groups
+            .iter()
+            .flatten()
+            .map(|segments| segment_width(segments))
+            .sum();
+        let mut overflow = total.saturating_sub(width);
+
+        let mut shrinks = line.shrinks.clone();
+        // Stable, so that segments with the same priority give way in the
+        // order they were added.
+        shrinks.sort_by_key(|&(_, _, shrink)| shrink.priority);
+        for (alignment, position, shrink) in shrinks {
+            if overflow == 0 {
+                break;
+            }
+            let group = alignments.iter().position(|&a| a == alignment).unwrap();
+            let segments = &mut groups[group][position];
+            let current = segment_width(segments);
+            if shrink.min_width > 0 && current >= shrink.min_width + overflow {
+                *segments = truncate_segments(
+                    segments,
+                    current - overflow,
+                    &ContinuationMarker::ELLIPSIS,
+                    self.width_policy,
+                );
+                overflow = 0;
+            } else {
+                segments.clear();
+                overflow = overflow.saturating_sub(current);
+            }
+        }
+        groups.map(|group| group.into_iter().flatten().collect())
+    }
+
+    /// Fill `columns` columns, padding with spaces if the fill character is
+    /// wider than one column and does not fit exactly.
+    fn push_fill(&self, segments: &mut Vec<AnsiString<'a>>, columns: usize) {
+        if columns > 0 {
+            let fill_width = self.width_policy.char_width(self.fill).max(1);
+            let mut fill: String = std::iter::repeat(self.fill)
+                .take(columns / fill_width)
+                .collect();
+            fill.extend(std::iter::repeat(' ').take(columns % fill_width));
+            segments.push(self.fill_style.paint(fill));
+        }
+    }
+}
+
+/// Join `segments` with `separator`, such as [`POWERLINE_ARROW`], painted
+/// in the background of the segment before it on the background of the
+/// segment after it, so that each segment appears to flow into the next.
+/// The last segment is followed by a separator on the terminal's own
+/// background.
+///
+/// # Examples
+///
+/// ```
+/// use nu_ansi_term::prompt::powerline;
+/// use nu_ansi_term::{AnsiStrings, Style};
+/// use nu_ansi_term::Color::{Black, Blue, Green};
+///
+/// let line = powerline([Black.on(Blue).paint(" ~ "), Black.on(Green).paint(" main ")], ">");
+/// assert_eq!(
+///     line.to_string(),
+///     AnsiStrings([
+///         Black.on(Blue).paint(" ~ "),
+///         Blue.on(Green).paint(">"),
+///         Black.on(Green).paint(" main "),
+///         Green.paint(">"),
+///     ])
+///     .to_string()
+/// );
+/// ```
+pub fn powerline<'a>(
+    segments: impl IntoIterator<Item = AnsiString<'a>>,
+    separator: &'a str,
+) -> AnsiStrings<'a> {
+    let mut line = Vec::new();
+    let mut segments = segments.into_iter().peekable();
+    while let Some(segment) = segments.next() {
+        let background = segment.style_ref().is_bg();
+        let next_background = segments.peek().and_then(|next| next.style_ref().is_bg());
+        line.push(segment);
+        line.push(
+            Style::new()
+                .set_fg(background)
+                .set_bg(next_background)
+                .paint(separator),
+        );
+    }
+    AnsiStrings(line)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::unstyle;
+    use crate::Color::*;
+
+    #[cfg(feature = "unicode-bidi")]
+    #[test]
+    fn rtl_groups_are_isolated() {
+        let layout = PromptLayout::new()
+            .width(12)
+            .bidi_isolation(crate::BidiIsolation::Mark)
+            .line(PromptLine::new().left(Red.paint("שלום")).right("~/src"));
+        assert_eq!(unstyle(&layout.render()), "שלום\u{200E}   ~/src");
+    }
+
+    #[test]
+    fn left_and_right() {
+        let layout = PromptLayout::new().width(12).line(
+            PromptLine::new()
+                .left(Red.paint("abc"))
+                .right(Blue.paint("xy")),
+        );
+        assert_eq!(unstyle(&layout.render()), "abc       xy");
+    }
+
+    #[test]
+    fn centered() {
+        let layout = PromptLayout::new()
+            .width(11)
+            .line(PromptLine::new().left("a").center("mid").right("z"));
+        assert_eq!(unstyle(&layout.render()), "a   mid   z");
+    }
+
+    #[test]
+    fn styled_fill() {
+        let layout = PromptLayout::new()
+            .width(5)
+            .fill('-', Style::new().dimmed())
+            .line(PromptLine::new().left("a").right("b"));
+        let lines = layout.render_lines();
+        assert_eq!(unstyle(&lines[0]), "a---b");
+        assert_eq!(
+            lines[0].iter().nth(1).unwrap().style_ref(),
+            &Style::new().dimmed()
+        );
+    }
+
+    #[test]
+    fn overflowing_line_is_not_padded() {
+        let layout = PromptLayout::new()
+            .width(4)
+            .line(PromptLine::new().left("abc").right("xyz"));
+        assert_eq!(unstyle(&layout.render()), "abcxyz");
+    }
+
+    #[test]
+    fn multiple_lines() {
+        let layout = PromptLayout::new()
+            .width(6)
+            .line(PromptLine::new().left("a").right("b"))
+            .line(PromptLine::new().left("> "));
+        assert_eq!(unstyle(&layout.render()), "a    b\n> ");
+    }
+
+    #[test]
+    fn wide_fill_and_segments() {
+        let layout = PromptLayout::new()
+            .width(8)
+            .fill('─', Style::new())
+            .width_policy(WidthPolicy::new().ambiguous_width(2))
+            .line(PromptLine::new().left("日").right("a"));
+        assert_eq!(unstyle(&layout.render()), "日── a");
+    }
+
+    #[test]
+    fn dynamic_segments_keep_their_place() {
+        let line = PromptLine::new()
+            .dynamic_left(DynamicSegment::new(|| "a".into()))
+            .left("b")
+            .dynamic_left(DynamicSegment::new(|| "c".into()))
+            .dynamic_left(DynamicSegment::new(|| "d".into()))
+            .left("e");
+        let rendered: Vec<String> = line
+            .rendered(Alignment::Left)
+            .iter()
+            .map(|segment| segment.to_string())
+            .collect();
+        assert_eq!(rendered, ["a", "b", "c", "d", "e"]);
+        assert_eq!(line.segments(Alignment::Left).len(), 2);
+    }
+
+    #[test]
+    fn elapsed() {
+        assert_eq!(format_elapsed(Duration::from_millis(4_900)), "4s");
+        assert_eq!(format_elapsed(Duration::from_secs(185)), "3m 05s");
+        assert_eq!(format_elapsed(Duration::from_secs(7_830)), "2h 10m");
+    }
+
+    #[test]
+    fn segments_give_way_by_priority() {
+        let line = PromptLine::new()
+            .left("abcdef")
+            .shrink(Shrink::new(1).min_width(3))
+            .dynamic_left(DynamicSegment::new(|| "-xy".into()))
+            .shrink(Shrink::new(0))
+            .right("z");
+        let render = |width| unstyle(&PromptLayout::new().width(width).line(line.clone()).render());
+        assert_eq!(render(10), "abcdef-xyz");
+        assert_eq!(render(9), "abcdef  z");
+        assert_eq!(render(5), "abc…z");
+        assert_eq!(render(3), "  z");
+    }
+
+    #[test]
+    fn powerline_bridges_backgrounds() {
+        let line = powerline(
+            [
+                Black.on(Red).paint("a"),
+                White.paint("b"),
+                Style::new().on(Fixed(8)).paint("c"),
+            ],
+            POWERLINE_ARROW,
+        );
+        let separators: Vec<Style> = line
+            .iter()
+            .skip(1)
+            .step_by(2)
+            .map(|s| *s.style_ref())
+            .collect();
+        assert_eq!(
+            separators,
+            [Red.normal(), Style::new().on(Fixed(8)), Fixed(8).normal()]
+        );
+        assert!(powerline([], ">").is_empty());
+    }
+}
@@ -0,0 +1,187 @@
+use crate::{AnsiStrings, Color, Style};
+
+/// Rules for shell-quoting words (like `ls -Q` or `printf %q`), styling the
+/// quotes and escapes distinctly from the quoted text.
+///
+/// Words which need no quoting are left alone. Others are put in single
+/// quotes, or in `$'…'` quotes with escapes if they contain control
+/// characters, so that the output can be copied into a POSIX shell (or
+/// bash, for `$'…'`) and never contains raw control characters.
+///
+/// # Examples
+///
+/// ```
+/// use nu_ansi_term::{ShellQuote, Style};
+/// use nu_ansi_term::utils::unstyle;
+///
+/// let quote = ShellQuote::new();
+/// assert_eq!(unstyle(&quote.render("Cargo.toml")), "Cargo.toml");
+/// assert_eq!(unstyle(&quote.render("my file")), "'my file'");
+/// assert_eq!(unstyle(&quote.render("it's")), r"'it'\''s'");
+/// assert_eq!(unstyle(&quote.render("a\nb")), r"$'a\nb'");
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ShellQuote {
+    quotes: Style,
+    escapes: Style,
+    payload: Style,
+}
+
+impl Default for ShellQuote {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ShellQuote {
+    /// Create quoting rules with dimmed quotes, purple escapes and an
+    /// unstyled payload.
+    pub const fn new() -> Self {
+        Self {
+            quotes: Style::new().dimmed(),
+            escapes: Color::Purple.normal(),
+            payload: Style::new(),
+        }
+    }
+
+    /// Set the style of the quotes.
+    pub const fn quotes(mut self, style: Style) -> Self {
+        self.quotes = style;
+        self
+    }
+
+    /// Set the style of escape sequences.
+    pub const fn escapes(mut self, style: Style) -> Self {
+        self.escapes = style;
+        self
+    }
+
+    /// Set the style of the quoted text itself.
+    pub const fn payload(mut self, style: Style) -> Self {
+        self.payload = style;
+        self
+    }
+
+    /// Quote `word`, such as a path or a command line argument.
+    pub fn render<'a>(&self, word: &str) -> AnsiStrings<'a> {
+        let mut quoted = Quoted::default();
+        if !word.is_empty() && word.chars().all(is_safe) {
+            quoted.push(self.payload, word);
+        } else if word.chars().any(char::is_control) {
+            quoted.push(self.quotes, "$'");
+            for c in word.chars() {
+                match c {
+                    '\\' => quoted.push(self.escapes, "\\\\"),
+                    '\'' => quoted.push(self.escapes, "\\'"),
+                    '\n' => quoted.push(self.escapes, "\\n"),
+                    '\r' => quoted.push(self.escapes, "\\r"),
+                    '\t' => quoted.push(self.escapes, "\\t"),
+                    '\x1b' => quoted.push(self.escapes, "\\e"),
+                    c if c.is_ascii_control() => {
+                        quoted.push(self.escapes, &format!("\\x{:02x}", c as u32))
+                    }
+                    c if c.is_control() => {
+                        quoted.push(self.escapes, &format!("\\u{:04x}", c as u32))
+                    }
+                    c => quoted.push(self.payload, c.encode_utf8(&mut [0; 4])),
+                }
+            }
+            quoted.push(self.quotes, "'");
+        } else {
+            quoted.push(self.quotes, "'");
+            for (ix, part) in word.split('\'').enumerate() {
+                if ix > 0 {
+                    quoted.push(self.quotes, "'");
+                    quoted.push(self.escapes, "\\'");
+                    quoted.push(self.quotes, "'");
+                }
+                quoted.push(self.payload, part);
+            }
+            quoted.push(self.quotes, "'");
+        }
+        AnsiStrings(quoted.0.into_iter().map(|(style, text)| style.paint(text)))
+    }
+}
+
+/// Shell-quote `word` using the default [`ShellQuote`] styles.
+pub fn quote_styled<'a>(word: &str) -> AnsiStrings<'a> {
+    ShellQuote::new().render(word)
+}
+
+/// Characters which never need quoting.
+fn is_safe(c: char) -> bool {
+    c.is_alphanumeric() || "_-./,:=@%+".contains(c)
+}
+
+/// Runs of text, merged while their style stays the same.
+#[derive(Default)]
+struct Quoted(Vec<(Style, String)>);
+
+impl Quoted {
+    fn push(&mut self, style: Style, text: &str) {
+        match self.0.last_mut() {
+            Some((last, run)) if *last == style => run.push_str(text),
+            _ if text.is_empty() => {}
+            _ => self.0.push((style, text.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::unstyle;
+    use crate::Color::*;
+
+    fn plain(word: &str) -> String {
+        unstyle(&quote_styled(word))
+    }
+
+    #[test]
+    fn safe_words_are_unquoted() {
+        assert_eq!(plain("src/lib.rs"), "src/lib.rs");
+        assert_eq!(plain("a=b,c@d"), "a=b,c@d");
+        assert_eq!(plain("naïve"), "naïve");
+    }
+
+    #[test]
+    fn single_quotes() {
+        assert_eq!(plain(""), "''");
+        assert_eq!(plain("a b"), "'a b'");
+        assert_eq!(plain("$HOME"), "'$HOME'");
+        assert_eq!(plain("'"), r"''\'''");
+        assert_eq!(plain(r"a\b"), r"'a\b'");
+    }
+
+    #[test]
+    fn control_characters_are_escaped() {
+        assert_eq!(plain("a\tb\x1b[0m"), r"$'a\tb\e[0m'");
+        assert_eq!(plain("it's\n"), r"$'it\'s\n'");
+        assert_eq!(plain("\x07\u{85}"), r"$'\x07\u0085'");
+    }
+
+    #[test]
+    fn styles() {
+        let quote = ShellQuote::new()
+            .quotes(Blue.normal())
+            .escapes(Red.normal())
+            .payload(Green.normal());
+        let quoted = quote.render("a'b");
+        let styled: Vec<(String, Style)> = quoted
+            .iter()
+            .map(|s| (s.content().to_string(), *s.style_ref()))
+            .collect();
+        assert_eq!(
+            styled,
+            [
+                ("'".to_string(), Blue.normal()),
+                ("a".to_string(), Green.normal()),
+                ("'".to_string(), Blue.normal()),
+                ("\\'".to_string(), Red.normal()),
+                ("'".to_string(), Blue.normal()),
+                ("b".to_string(), Green.normal()),
+                ("'".to_string(), Blue.normal()),
+            ]
+        );
+    }
+}
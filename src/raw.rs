@@ -0,0 +1,379 @@
+use std::io::{self, Write};
+
+use crate::ansi::RESET_BYTES;
+use crate::parse::apply_sgr;
+use crate::Style;
+
+/// Position of a [`CrlfWriter`] within the escape sequences passing through.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum State {
+    Ground,
+    Escape,
+    Csi,
+}
+
+/// Writer adapter for terminals in raw mode, where `\n` only moves the cursor
+/// down a line: every `\n` which is not already preceded by `\r` is written as
+/// `\r\n`.
+///
+/// The adapter also follows the SGR (style) sequences written through it.
+/// When a line ends while a style is active, the style is reset before the
+/// line ending and restored after it, so that backgrounds do not bleed into
+/// the rest of the line and the next line continues in the same style. Only
+/// the current [`Style`] is remembered, so codes it cannot represent (see
+/// [`apply_sgr`](crate::parse::apply_sgr)) are not restored.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Write;
+/// use nu_ansi_term::CrlfWriter;
+/// use nu_ansi_term::Color::Red;
+///
+/// let mut w = CrlfWriter::new(Vec::new());
+/// write!(w, "{}", Red.paint("one\ntwo")).unwrap();
+/// assert_eq!(w.into_inner(), b"\x1b[31mone\x1b[0m\r\n\x1b[31mtwo\x1b[0m");
+/// ```
+#[derive(Debug)]
+pub struct CrlfWriter<W> {
+    inner: W,
+    translate: bool,
+    state: State,
+    /// Parameters of the CSI sequence currently being written.
+    params: Vec<u8>,
+    /// The style set by the SGR sequences written so far.
+    active: Style,
+    last: Option<u8>,
+    scratch: Vec<u8>,
+}
+
+impl<W: Write> CrlfWriter<W> {
+    /// Create a writer which translates line endings written to `inner`.
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            translate: true,
+            state: State::Ground,
+            params: Vec::new(),
+            active: Style::new(),
+            last: None,
+            scratch: Vec::new(),
+        }
+    }
+
+    /// Turn the translation on or off, e.g. when the application leaves raw
+    /// mode. While it is off, everything is passed through as is.
+    pub fn set_translate(&mut self, translate: bool) {
+        self.translate = translate;
+    }
+
+    /// Whether line endings are currently being translated.
+    pub fn translates(&self) -> bool {
+        self.translate
+    }
+
+    /// Get a reference to the wrapped writer.
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Get a mutable reference to the wrapped writer.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Consume this adapter, returning the wrapped writer.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+
+    /// Apply the SGR sequence with parameters `params` to the active style.
+    fn observe_sgr(&mut self) {
+        let params = std::mem::take(&mut self.params);
+        let params = std::str::from_utf8(&params).unwrap_or("");
+        self.active = apply_sgr(self.active, params);
+    }
+}
+
+impl<W: Write> Write for CrlfWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.scratch.clear();
+        for &byte in buf {
+            match (self.state, byte) {
+                (State::Ground, b'\n') if self.translate && self.last != Some(b'\r') => {
+                    if self.active.has_no_styling() {
+                        self.scratch.extend_from_slice(b"\r\n");
+                    } else {
+                        self.scratch.extend_from_slice(RESET_BYTES);
+                        self.scratch.extend_from_slice(b"\r\n");
+                        self.scratch
+                            .extend_from_slice(self.active.prefix_str().as_bytes());
+                    }
+                    self.last = Some(byte);
+                    continue;
+                }
+                (State::Ground, 0x1B) => self.state = State::Escape,
+                (State::Ground, _) => {}
+                (State::Escape, b'[') => {
+                    self.params.clear();
+                    self.state = State::Csi;
+                }
+                (State::Escape, _) => self.state = State::Ground,
+                (State::Csi, 0x40..=0x7E) => {
+                    if byte == b'm' {
+                        self.observe_sgr();
+                    }
+                    self.state = State::Ground;
+                }
+                (State::Csi, _) => self.params.push(byte),
+            }
+            self.scratch.push(byte);
+            self.last = Some(byte);
+        }
+        self.inner.write_all(&self.scratch)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Guard which switches the terminal attached to standard input into raw
+/// mode (no line buffering, echo or signal keys), and restores its previous
+/// mode when dropped.
+///
+/// While the guard is alive, output should go through a [`CrlfWriter`], as
+/// `\n` no longer returns the cursor to the start of the line.
+///
+/// # Examples
+///
+/// ```no_run
+/// use nu_ansi_term::RawModeGuard;
+///
+/// let guard = RawModeGuard::new()?;
+/// // ... read key presses or terminal responses ...
+/// drop(guard);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[cfg(feature = "raw_mode")]
+#[derive(Debug)]
+pub struct RawModeGuard {
+    previous: sys::Mode,
+}
+
+#[cfg(feature = "raw_mode")]
+impl RawModeGuard {
+    /// Switch the terminal into raw mode. Fails if standard input is not a
+    /// terminal, or on platforms without raw mode support.
+    pub fn new() -> io::Result<Self> {
+        let previous = sys::enable_raw_mode()?;
+        Ok(Self { previous })
+    }
+}
+
+#[cfg(feature = "raw_mode")]
+impl Drop for RawModeGuard {
+    fn drop(&mut self) {
+        // There is nothing sensible left to do if the mode cannot be restored.
+        let _ = sys::restore(&self.previous);
+    }
+}
+
+#[cfg(all(feature = "raw_mode", unix))]
+mod sys {
+    use std::io;
+    use std::mem::MaybeUninit;
+
+    pub(super) struct Mode(pub(super) libc::termios);
+
+    impl std::fmt::Debug for Mode {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.debug_struct("Mode").finish_non_exhaustive()
+        }
+    }
+
+    pub(super) fn current_mode() -> io::Result<Mode> {
+        let mut termios = MaybeUninit::<libc::termios>::uninit();
+        // SAFETY: `tcgetattr` fully initializes `termios` when it succeeds.
+        unsafe {
+            if libc::tcgetattr(libc::STDIN_FILENO, termios.as_mut_ptr()) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(Mode(termios.assume_init()))
+        }
+    }
+
+    pub(super) fn enable_raw_mode() -> io::Result<Mode> {
+        let previous = current_mode()?;
+        let mut raw = previous.0;
+        // SAFETY: `raw` is a valid termios obtained from `tcgetattr`.
+        unsafe {
+            libc::cfmakeraw(&mut raw);
+            if libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &raw) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+        Ok(previous)
+    }
+
+    pub(super) fn restore(mode: &Mode) -> io::Result<()> {
+        // SAFETY: `mode` holds a termios obtained from `tcgetattr`.
+        if unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &mode.0) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(feature = "raw_mode", windows))]
+mod sys {
+    use std::io;
+    use windows::Win32::Foundation::{CloseHandle, HANDLE, INVALID_HANDLE_VALUE};
+    use windows::Win32::Storage::FileSystem::{
+        CreateFileW, FILE_GENERIC_READ, FILE_GENERIC_WRITE, FILE_SHARE_READ, OPEN_EXISTING,
+    };
+    use windows::Win32::System::Console::{
+        GetConsoleMode, SetConsoleMode, CONSOLE_MODE, ENABLE_ECHO_INPUT, ENABLE_LINE_INPUT,
+        ENABLE_PROCESSED_INPUT, ENABLE_VIRTUAL_TERMINAL_INPUT,
+    };
+
+    #[derive(Debug)]
+    pub(super) struct Mode {
+        handle: HANDLE,
+        mode: CONSOLE_MODE,
+    }
+
+    pub(super) fn enable_raw_mode() -> io::Result<Mode> {
+        // SAFETY: plain console API calls on a handle we own.
+        unsafe {
+            // Opening `CONIN$` works even if standard input is redirected.
+            let handle = CreateFileW(
+                windows::w!("CONIN$"),
+                FILE_GENERIC_READ | FILE_GENERIC_WRITE,
+                FILE_SHARE_READ,
+                std::ptr::null_mut(),
+                OPEN_EXISTING,
+                0,
+                0,
+            );
+            if handle == INVALID_HANDLE_VALUE {
+                return Err(io::Error::last_os_error());
+            }
+            let mut mode = 0;
+            if GetConsoleMode(handle, &mut mode) == 0 {
+                let err = io::Error::last_os_error();
+                CloseHandle(handle);
+                return Err(err);
+            }
+            let raw = (mode & !(ENABLE_LINE_INPUT | ENABLE_ECHO_INPUT | ENABLE_PROCESSED_INPUT))
+                | ENABLE_VIRTUAL_TERMINAL_INPUT;
+            if SetConsoleMode(handle, raw) == 0 {
+                let err = io::Error::last_os_error();
+                CloseHandle(handle);
+                return Err(err);
+            }
+            Ok(Mode { handle, mode })
+        }
+    }
+
+    pub(super) fn restore(mode: &Mode) -> io::Result<()> {
+        // SAFETY: `mode.handle` was opened by `enable_raw_mode` and is closed
+        // exactly once, here.
+        unsafe {
+            // Read the error before `CloseHandle` can overwrite it.
+            let restored = if SetConsoleMode(mode.handle, mode.mode) != 0 {
+                Ok(())
+            } else {
+                Err(io::Error::last_os_error())
+            };
+            CloseHandle(mode.handle);
+            restored
+        }
+    }
+}
+
+#[cfg(all(feature = "raw_mode", not(any(unix, windows))))]
+mod sys {
+    use std::io;
+
+    #[derive(Debug)]
+    pub(super) struct Mode;
+
+    pub(super) fn enable_raw_mode() -> io::Result<Mode> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "raw mode is not supported on this platform",
+        ))
+    }
+
+    pub(super) fn restore(_: &Mode) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Color::*;
+    use crate::{AnsiStrings, Style, RESET};
+
+    fn crlf(text: &str) -> String {
+        let mut w = CrlfWriter::new(Vec::new());
+        w.write_all(text.as_bytes()).unwrap();
+        String::from_utf8(w.into_inner()).unwrap()
+    }
+
+    #[test]
+    fn plain_newlines() {
+        assert_eq!(crlf("a\nb\r\nc\n"), "a\r\nb\r\nc\r\n");
+    }
+
+    #[test]
+    fn style_is_carried_across() {
+        let strings = AnsiStrings([Red.paint("a\n"), Red.bold().on(Blue).paint("b\nc")]);
+        assert_eq!(
+            crlf(&strings.to_string()),
+            format!(
+                "\x1b[31ma{reset}\r\n\x1b[31m\x1b[1;44mb{reset}\r\n\x1b[1;44;31mc{reset}",
+                reset = RESET
+            )
+        );
+    }
+
+    #[test]
+    fn reset_clears_active_style() {
+        let text = format!("{}\n{}", Green.paint("a"), Style::new().paint("b"));
+        assert_eq!(crlf(&text), "\x1b[32ma\x1b[0m\r\nb");
+    }
+
+    #[test]
+    fn split_sequences_and_toggle() {
+        let mut w = CrlfWriter::new(Vec::new());
+        w.write_all(b"\x1b[3").unwrap();
+        w.write_all(b"4mx\n").unwrap();
+        w.set_translate(false);
+        w.write_all(b"y\n").unwrap();
+        assert_eq!(w.into_inner(), b"\x1b[34mx\x1b[0m\r\n\x1b[34my\n");
+    }
+
+    #[cfg(all(feature = "raw_mode", unix))]
+    #[test]
+    fn guard_restores_mode() {
+        fn flags() -> Option<(libc::tcflag_t, libc::tcflag_t)> {
+            sys::current_mode()
+                .ok()
+                .map(|mode| (mode.0.c_lflag, mode.0.c_oflag))
+        }
+
+        // Only meaningful when the tests are run from a terminal.
+        let before = match flags() {
+            Some(before) => before,
+            None => return,
+        };
+        let guard = RawModeGuard::new().unwrap();
+        assert_eq!(flags().unwrap().0 & libc::ICANON, 0);
+        drop(guard);
+        assert_eq!(flags(), Some(before));
+    }
+}
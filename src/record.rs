@@ -0,0 +1,218 @@
+use crate::TerminalSize;
+use std::fmt::{self, Write as _};
+use std::io;
+use std::time::{Duration, Instant};
+
+/// A frame of output recorded by a [`Recorder`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Frame {
+    /// The time the frame was written, from the start of the recording.
+    pub time: Duration,
+    /// The output of the frame, escape codes included, as it was rendered.
+    pub output: String,
+}
+
+/// Recorder of successive frames of styled output, with the times they were
+/// written at, for exporting terminal recordings of a tool's output (e.g.
+/// for its documentation) without an external recorder.
+///
+/// Recordings are exported in the
+/// [asciicast v2](https://docs.asciinema.org/manual/asciicast/v2/) format
+/// played by asciinema. Frames are rendered when they are recorded, so that
+/// lazy values are captured as they were then, and each frame is written
+/// after the ones before it, so frames which redraw the screen (such as a
+/// [`Pager`](crate::Pager)) should move the cursor themselves.
+///
+/// # Examples
+///
+/// ```
+/// use nu_ansi_term::{AnsiStrings, Recorder, TerminalSize};
+/// use nu_ansi_term::control::ClearLine;
+/// use nu_ansi_term::Color::Green;
+/// use std::time::Duration;
+///
+/// let mut recorder = Recorder::new(TerminalSize { columns: 40, rows: 5 }).title("build");
+/// recorder.record_at(Duration::ZERO, "building...");
+/// recorder.record_at(
+///     Duration::from_millis(1500),
+///     AnsiStrings([ClearLine.into(), "\r".into(), Green.paint("done\n")]),
+/// );
+///
+/// assert_eq!(
+///     recorder.to_asciicast(),
+///     r#"{"version": 2, "width": 40, "height": 5, "title": "build"}
+/// [0.000000, "o", "building..."]
+/// [1.500000, "o", "\u001b[2K\r\u001b[32mdone\r\n\u001b[0m"]
+/// "#
+/// );
+/// ```
+#[derive(Clone, Debug)]
+pub struct Recorder {
+    size: TerminalSize,
+    title: Option<String>,
+    started: Instant,
+    frames: Vec<Frame>,
+}
+
+impl Recorder {
+    /// Start recording output for a terminal of `size`. The times of frames
+    /// recorded with [`record`](Self::record) count from now.
+    pub fn new(size: TerminalSize) -> Self {
+        Self {
+            size,
+            title: None,
+            started: Instant::now(),
+            frames: Vec::new(),
+        }
+    }
+
+    /// Give the recording a title, shown by players.
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// Record `frame` (such as an [`AnsiStrings`](crate::AnsiStrings)) as
+    /// written now.
+    pub fn record(&mut self, frame: impl fmt::Display) {
+        let time = self.started.elapsed();
+        self.record_at(time, frame);
+    }
+
+    /// Record `frame` as written at `time` from the start of the recording,
+    /// for recordings which should be the same every time they are made.
+    ///
+    /// Frames are kept in the order they are recorded in, and players
+    /// reject recordings whose times go backwards, so a `time` before that
+    /// of the previous frame is raised to it.
+    pub fn record_at(&mut self, time: Duration, frame: impl fmt::Display) {
+        let previous = self
+            .frames
+            .last()
+            .map_or(Duration::ZERO, |frame| frame.time);
+        self.frames.push(Frame {
+            time: time.max(previous),
+            output: frame.to_string(),
+        });
+    }
+
+    /// The frames recorded so far.
+    pub fn frames(&self) -> &[Frame] {
+        &self.frames
+    }
+
+    /// The recording in the asciicast v2 format: a header, then an output
+    /// event for each frame, each on a line of its own.
+    ///
+    /// Players emulate a terminal in raw mode, so every `\n` of the output
+    /// which is not already preceded by `\r` is exported as `\r\n`, as a
+    /// terminal in cooked mode would show it.
+    pub fn to_asciicast(&self) -> String {
+        let mut cast = String::new();
+        self.write_cast(&mut cast)
+            .expect("writing to a String cannot fail");
+        cast
+    }
+
+    /// Write the recording in the asciicast v2 format to `w`, e.g. to a
+    /// `.cast` file. See [`to_asciicast`](Self::to_asciicast).
+    pub fn write_asciicast<W: io::Write>(&self, mut w: W) -> io::Result<()> {
+        w.write_all(self.to_asciicast().as_bytes())
+    }
+
+    fn write_cast(&self, cast: &mut String) -> fmt::Result {
+        write!(
+            cast,
+            "{{\"version\": 2, \"width\": {}, \"height\": {}",
+            self.size.columns, self.size.rows
+        )?;
+        if let Some(title) = &self.title {
+            cast.push_str(", \"title\": ");
+            write_json_string(cast, title)?;
+        }
+        cast.push_str("}\n");
+
+        for frame in &self.frames {
+            write!(cast, "[{:.6}, \"o\", ", frame.time.as_secs_f64())?;
+            write_json_string(cast, &crlf(&frame.output))?;
+            cast.push_str("]\n");
+        }
+        Ok(())
+    }
+}
+
+/// `output` with every `\n` which is not preceded by `\r` written as `\r\n`.
+fn crlf(output: &str) -> String {
+    let mut translated = String::with_capacity(output.len());
+    let mut last = None;
+    for c in output.chars() {
+        if c == '\n' && last != Some('\r') {
+            translated.push('\r');
+        }
+        translated.push(c);
+        last = Some(c);
+    }
+    translated
+}
+
+/// Write `s` as a JSON string, with control characters escaped.
+fn write_json_string(out: &mut String, s: &str) -> fmt::Result {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => write!(out, "\\u{:04x}", c as u32)?,
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Color::*;
+
+    #[test]
+    fn escapes_frames_as_json() {
+        let mut recorder = Recorder::new(TerminalSize {
+            columns: 80,
+            rows: 24,
+        });
+        recorder.record_at(
+            Duration::from_micros(2_500_001),
+            Blue.paint("\"a\\b\"\r\n\t"),
+        );
+        recorder.record(ClearLineMarker);
+        assert_eq!(recorder.frames().len(), 2);
+        assert!(recorder.frames()[1].time >= Duration::from_micros(2_500_001));
+        assert!(recorder.frames()[1].time < Duration::from_secs(60));
+
+        let cast = recorder.to_asciicast();
+        let lines: Vec<&str> = cast.lines().collect();
+        assert_eq!(lines[0], r#"{"version": 2, "width": 80, "height": 24}"#);
+        assert_eq!(
+            lines[1],
+            r#"[2.500001, "o", "\u001b[34m\"a\\b\"\r\n\t\u001b[0m"]"#
+        );
+        assert!(lines[2].ends_with(r#", "o", "\u001b[2K\u0007"]"#));
+
+        let mut written = Vec::new();
+        recorder.write_asciicast(&mut written).unwrap();
+        assert_eq!(written, cast.as_bytes());
+    }
+
+    /// A frame which is not styled text.
+    struct ClearLineMarker;
+
+    impl fmt::Display for ClearLineMarker {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "{}\x07", crate::control::ClearLine)
+        }
+    }
+}
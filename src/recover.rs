@@ -0,0 +1,253 @@
+use crate::parse::{apply_sgr, tokenize, Token};
+use crate::write::StrLike;
+use crate::{io_write, AnsiGenericStrings, Style, RESET};
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+/// How far writing a sequence got, and the state it left the terminal in.
+/// Returned by [`AnsiGenericStrings::write_tracked`], on success and (inside
+/// a [`PartialWrite`]) on failure.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Written {
+    /// The number of bytes written.
+    pub bytes: usize,
+    /// The style set by the last escape code written in full.
+    pub style: Style,
+    in_link: bool,
+    in_escape: bool,
+}
+
+impl Written {
+    /// The escape codes which put the terminal back in its default state
+    /// after this output: a `CAN` cancelling an escape sequence which was
+    /// cut off, the end of a hyperlink left open, and a reset if the style
+    /// is not the default one. Empty if the output was written in full.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::AnsiStrings;
+    /// use nu_ansi_term::Color::Red;
+    ///
+    /// // A writer with room for 7 bytes: the prefix and the first letters.
+    /// let mut buffer = [0_u8; 7];
+    /// let strings = AnsiStrings([Red.paint("error")]);
+    /// let partial = strings.write_tracked(&mut &mut buffer[..]).unwrap_err();
+    ///
+    /// assert_eq!(&buffer, b"\x1b[31mer");
+    /// assert_eq!(partial.written.style, Red.normal());
+    /// assert_eq!(partial.written.abort_sequence(), "\x1b[0m");
+    /// ```
+    pub fn abort_sequence(&self) -> String {
+        let mut abort = String::new();
+        if self.in_escape {
+            abort.push('\x18');
+        }
+        if self.in_link {
+            abort.push_str("\x1b]8;;\x1b\\");
+        }
+        if !self.style.has_no_styling() {
+            abort.push_str(RESET);
+        }
+        abort
+    }
+
+    /// How far writing `rendered` got after its first `bytes` bytes.
+    fn after(rendered: &[u8], bytes: usize) -> Self {
+        let mut written = Self {
+            bytes,
+            style: Style::new(),
+            in_link: false,
+            in_escape: false,
+        };
+        for token in tokenize(rendered) {
+            let (start, end) = match &token {
+                Token::Text(_) => continue,
+                Token::Sgr(params) => (params.start - 2, params.end + 1),
+                Token::Osc(_, sequence) | Token::Other(sequence) => (sequence.start, sequence.end),
+            };
+            if start >= bytes {
+                break;
+            }
+            if end > bytes {
+                written.in_escape = true;
+                break;
+            }
+            match token {
+                Token::Sgr(params) => {
+                    let params = String::from_utf8_lossy(&rendered[params]);
+                    written.style = apply_sgr(written.style, &params);
+                }
+                Token::Osc(payload, _) => {
+                    let payload = &rendered[payload];
+                    if let Some(link) = payload.strip_prefix(b"8;") {
+                        let uri = link.splitn(2, |&b| b == b';').nth(1).unwrap_or(&[]);
+                        written.in_link = !uri.is_empty();
+                    }
+                }
+                _ => {}
+            }
+        }
+        written
+    }
+}
+
+/// A failure to write a sequence in full, with how far writing got. See
+/// [`AnsiGenericStrings::write_tracked`].
+///
+/// It converts into the underlying [`io::Error`], so `?` works in functions
+/// returning [`io::Result`].
+#[derive(Debug)]
+pub struct PartialWrite {
+    /// The error writing stopped at.
+    pub error: io::Error,
+    /// The output written before the error.
+    pub written: Written,
+}
+
+impl fmt::Display for PartialWrite {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "write failed after {} bytes: {}",
+            self.written.bytes, self.error
+        )
+    }
+}
+
+impl Error for PartialWrite {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+impl From<PartialWrite> for io::Error {
+    fn from(partial: PartialWrite) -> Self {
+        partial.error
+    }
+}
+
+impl<'a, S: 'a + ToOwned + ?Sized> AnsiGenericStrings<'a, S>
+where
+    S: StrLike<'a, dyn io::Write>,
+    str: StrLike<'a, dyn io::Write>,
+{
+    /// Write this sequence to an `io::Write`, reporting how many bytes were
+    /// written and the style the terminal was left in.
+    ///
+    /// If writing fails part of the way through, as when a pipe is closed,
+    /// the terminal may be left with a style set, a hyperlink open, or an
+    /// escape sequence cut off. The [`PartialWrite`] returned then says how
+    /// far writing got, and its [`abort_sequence`](Written::abort_sequence)
+    /// restores the terminal, for writing to it (or to another stream on
+    /// the same terminal, such as stderr) before giving up.
+    ///
+    /// The sequence is rendered before any of it is written, so it is
+    /// written with as few calls to the writer as it accepts.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::AnsiStrings;
+    /// use nu_ansi_term::Color::Blue;
+    ///
+    /// let mut out = Vec::new();
+    /// let written = AnsiStrings([Blue.paint("ok")]).write_tracked(&mut out)?;
+    /// assert_eq!(written.bytes, out.len());
+    /// assert!(written.abort_sequence().is_empty());
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn write_tracked<W: io::Write>(&self, w: &mut W) -> Result<Written, PartialWrite> {
+        let mut rendered = Vec::new();
+        self.write_to_any(io_write!(&mut rendered))
+            .expect("writing to a Vec cannot fail");
+
+        let mut bytes = 0;
+        while bytes < rendered.len() {
+            let error = match w.write(&rendered[bytes..]) {
+                Ok(0) => io::Error::new(io::ErrorKind::WriteZero, "failed to write whole sequence"),
+                Ok(len) => {
+                    bytes += len;
+                    continue;
+                }
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+                Err(error) => error,
+            };
+            return Err(PartialWrite {
+                error,
+                written: Written::after(&rendered, bytes),
+            });
+        }
+        Ok(Written::after(&rendered, bytes))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Color::*;
+    use crate::{AnsiString, AnsiStrings};
+
+    /// A writer which accepts `room` bytes, a byte at a time, then fails.
+    struct Closing {
+        written: Vec<u8>,
+        room: usize,
+    }
+
+    impl io::Write for Closing {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if self.written.len() == self.room {
+                return Err(io::ErrorKind::BrokenPipe.into());
+            }
+            self.written.push(buf[0]);
+            Ok(1)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn abort_sequence_restores_the_terminal_wherever_writing_stops() {
+        let strings = AnsiStrings([
+            Red.bold().paint("a"),
+            AnsiString::from("b").hyperlink("https://nu.sh"),
+            Blue.paint("c"),
+        ]);
+        let rendered = strings.to_string();
+        let link_end = rendered.find("b").unwrap() + 1;
+
+        let abort_at = |room| {
+            let mut w = Closing {
+                written: Vec::new(),
+                room,
+            };
+            let partial = strings.write_tracked(&mut w).unwrap_err();
+            assert_eq!(partial.error.kind(), io::ErrorKind::BrokenPipe);
+            assert_eq!(partial.written.bytes, room);
+            (partial.written.style, partial.written.abort_sequence())
+        };
+        assert_eq!(abort_at(0), (Style::new(), String::new()));
+        assert_eq!(abort_at(3), (Style::new(), "\x18".to_string()));
+        assert_eq!(abort_at(8), (Red.bold(), "\x1b[0m".to_string()));
+        assert_eq!(
+            abort_at(link_end),
+            (Style::new(), "\x1b]8;;\x1b\\".to_string())
+        );
+
+        let mut out = Vec::new();
+        let written = strings.write_tracked(&mut out).unwrap();
+        assert_eq!(out, rendered.as_bytes());
+        assert_eq!(written.bytes, rendered.len());
+        assert!(written.abort_sequence().is_empty());
+
+        let mut w = Closing {
+            written: Vec::new(),
+            room: 1,
+        };
+        let error: io::Error = strings.write_tracked(&mut w).unwrap_err().into();
+        assert_eq!(error.kind(), io::ErrorKind::BrokenPipe);
+    }
+}
@@ -0,0 +1,2 @@
+/// Rendering as HTML, e.g. to embed captured output in reports and docs.
+pub mod html;
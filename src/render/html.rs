@@ -0,0 +1,298 @@
+use crate::level::color_rgb;
+use crate::parse::parse_generic;
+use crate::write::Content;
+use crate::{nested, AnsiStrings, Color, Style, TerminalProfile};
+use std::borrow::Cow;
+use std::fmt;
+
+/// The CSS declarations showing text in `style`, separated by semicolons.
+///
+/// Named and 256-palette colors are given their usual (xterm) values, and
+/// [`Color::Default`] leaves the color of the surrounding text. Reversed
+/// colors are swapped, with the page's own colors standing in for the
+/// missing ones.
+///
+/// # Examples
+///
+/// ```
+/// use nu_ansi_term::render::html::style_css;
+/// use nu_ansi_term::Style;
+/// use nu_ansi_term::Color::{Fixed, Red, Rgb};
+///
+/// assert_eq!(
+///     style_css(Rgb(255, 136, 0).on(Fixed(17)).bold()),
+///     "color:#ff8800;background-color:#00005f;font-weight:bold"
+/// );
+/// assert_eq!(
+///     style_css(Red.underline().strikethrough()),
+///     "color:#cd0000;text-decoration:underline line-through"
+/// );
+/// assert_eq!(
+///     style_css(Style::new().undercurl().underline_color(Red)),
+///     "text-decoration:underline;text-decoration-style:wavy;text-decoration-color:#cd0000"
+/// );
+/// assert_eq!(
+///     style_css(Style::new().reverse()),
+///     "color:Canvas;background-color:CanvasText"
+/// );
+/// ```
+pub fn style_css(style: Style) -> String {
+    let css_color = |color: Option<Color>| {
+        color
+            .and_then(color_rgb)
+            .map(|(r, g, b)| format!("#{:02x}{:02x}{:02x}", r, g, b))
+    };
+    let (mut fg, mut bg) = (css_color(style.coloring.fg), css_color(style.coloring.bg));
+    if style.is_reverse() {
+        (fg, bg) = (
+            Some(bg.unwrap_or_else(|| "Canvas".to_string())),
+            Some(fg.unwrap_or_else(|| "CanvasText".to_string())),
+        );
+    }
+
+    let mut declarations = Vec::new();
+    if let Some(fg) = fg {
+        declarations.push(format!("color:{}", fg));
+    }
+    if let Some(bg) = bg {
+        declarations.push(format!("background-color:{}", bg));
+    }
+    if style.is_bold() {
+        declarations.push("font-weight:bold".to_string());
+    }
+    if style.is_dimmed() {
+        declarations.push("opacity:0.5".to_string());
+    }
+    if style.is_italic() {
+        declarations.push("font-style:italic".to_string());
+    }
+    let underlined = style.is_underline() || style.is_double_underline() || style.is_undercurl();
+    let decorations: Vec<&str> = [
+        (underlined, "underline"),
+        (style.is_overline(), "overline"),
+        (style.is_strikethrough(), "line-through"),
+        (style.is_blink(), "blink"),
+    ]
+    .iter()
+    .filter(|(set, _)| *set)
+    .map(|(_, decoration)| *decoration)
+    .collect();
+    if !decorations.is_empty() {
+        declarations.push(format!("text-decoration:{}", decorations.join(" ")));
+    }
+    if style.is_undercurl() {
+        declarations.push("text-decoration-style:wavy".to_string());
+    } else if style.is_double_underline() {
+        declarations.push("text-decoration-style:double".to_string());
+    }
+    if let Some(color) = css_color(style.coloring.underline).filter(|_| underlined) {
+        declarations.push(format!("text-decoration-color:{}", color));
+    }
+    if style.is_hidden() {
+        declarations.push("visibility:hidden".to_string());
+    }
+    declarations.join(";")
+}
+
+/// Write `text` with the characters which are special in HTML escaped.
+fn write_escaped<W: fmt::Write + ?Sized>(text: &str, w: &mut W) -> fmt::Result {
+    let mut rest = text;
+    while let Some(ix) = rest.find(['&', '<', '>', '"', '\''].as_ref()) {
+        w.write_str(&rest[..ix])?;
+        w.write_str(match rest.as_bytes()[ix] {
+            b'&' => "&amp;",
+            b'<' => "&lt;",
+            b'>' => "&gt;",
+            b'"' => "&quot;",
+            _ => "&#39;",
+        })?;
+        rest = &rest[ix + 1..];
+    }
+    w.write_str(rest)
+}
+
+/// Write `text` in `style`, as a `<span>` if it is styled at all.
+fn write_span<W: fmt::Write + ?Sized>(style: Style, text: &str, w: &mut W) -> fmt::Result {
+    if text.is_empty() {
+        return Ok(());
+    }
+    let css = style_css(style);
+    if css.is_empty() {
+        return write_escaped(text, w);
+    }
+    write!(w, "<span style=\"{}\">", css)?;
+    write_escaped(text, w)?;
+    w.write_str("</span>")
+}
+
+/// Whether `url` is safe to link to from a page: its scheme is one of
+/// `http`, `https`, `file` and `mailto`. Links with other schemes (such as
+/// `javascript:` or `data:`, which would run in the page) or no scheme are
+/// written as plain text.
+fn is_safe_url(url: &str) -> bool {
+    let scheme = match url.split_once(':') {
+        Some((scheme, _)) => scheme,
+        None => return false,
+    };
+    ["http", "https", "file", "mailto"]
+        .iter()
+        .any(|safe| scheme.eq_ignore_ascii_case(safe))
+}
+
+/// Write the segments of `strings` within `enclosing`, flattening nested
+/// sequences.
+fn write_segments<W: fmt::Write + ?Sized>(
+    strings: &AnsiStrings<'_>,
+    enclosing: Style,
+    w: &mut W,
+) -> fmt::Result {
+    for (ix, segment) in strings.iter().enumerate() {
+        // Titles and control sequences have no place in a page.
+        let style = match segment.scheduled_style() {
+            Some(style) => strings
+                .lazy_style(ix)
+                .map_or(style, |lazy| lazy.resolve(&TerminalProfile::new())),
+            None => continue,
+        };
+        let style = nested::flatten(style, enclosing);
+        let url = segment
+            .url_string()
+            .map(|url| url.to_string())
+            .filter(|url| is_safe_url(url));
+        if let Some(url) = &url {
+            w.write_str("<a href=\"")?;
+            write_escaped(url, w)?;
+            w.write_str("\">")?;
+        }
+
+        let text: Cow<'_, str> = match segment.content() {
+            Content::StrLike(s) => Cow::Borrowed(s),
+            Content::Shared(s) => Cow::Borrowed(s),
+            Content::FmtArgs(args) => match args.as_str() {
+                Some(s) => Cow::Borrowed(s),
+                None => Cow::Owned(nested::within(style, || args.to_string())),
+            },
+            Content::GenericStrings(strings) => {
+                write_segments(strings, style, w)?;
+                Cow::Borrowed("")
+            }
+        };
+        if text.contains('\x1b') {
+            // Painted values formatted into the text are written with escape
+            // codes, which are read back into styles starting from this one.
+            let parsed = parse_generic(text.as_bytes(), style, |range| &text[range]);
+            write_segments(&parsed, Style::new(), w)?;
+        } else {
+            write_span(style, &text, w)?;
+        }
+
+        if url.is_some() {
+            w.write_str("</a>")?;
+        }
+    }
+    Ok(())
+}
+
+impl<'a> AnsiStrings<'a> {
+    /// Write this sequence as HTML: styled text becomes `<span>` elements
+    /// with inline styles (see [`style_css`]), and hyperlinks become `<a>`
+    /// elements. Titles and control sequences are left out, and so are links
+    /// other than `http`, `https`, `file` and `mailto` ones, whose text is
+    /// written without them.
+    ///
+    /// Whitespace is written as is, so the output belongs in a `<pre>`
+    /// element, or one with `white-space: pre`. Lazily resolved styles are
+    /// resolved for [`TerminalProfile::new`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::{AnsiString, AnsiStrings};
+    /// use nu_ansi_term::Color::{Blue, Red};
+    ///
+    /// let report = AnsiStrings([
+    ///     AnsiString::title("build"),
+    ///     Red.bold().paint("2 errors"),
+    ///     " in <main>, see ".into(),
+    ///     Blue.paint("the log").hyperlink("file:///tmp/build.log"),
+    /// ]);
+    /// let mut html = String::new();
+    /// report.write_html(&mut html).unwrap();
+    /// assert_eq!(
+    ///     html,
+    ///     "<span style=\"color:#cd0000;font-weight:bold\">2 errors</span> in &lt;main&gt;, see \
+    ///      <a href=\"file:///tmp/build.log\"><span style=\"color:#0000ee\">the log</span></a>"
+    /// );
+    /// ```
+    pub fn write_html<W: fmt::Write>(&self, w: &mut W) -> fmt::Result {
+        write_segments(self, Style::new(), w)
+    }
+
+    /// This sequence as HTML (see [`write_html`](Self::write_html)).
+    pub fn to_html(&self) -> String {
+        let mut html = String::new();
+        self.write_html(&mut html)
+            .expect("writing to a String cannot fail");
+        html
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Color::*;
+    use crate::{AnsiString, FormatFlags};
+
+    #[test]
+    fn nested_content() {
+        let inner = Blue.paint("c");
+        let html = AnsiStrings([
+            Red.paint(format_args!("a {} b", inner)),
+            Green
+                .bold()
+                .paint(AnsiStrings([Yellow.paint("d"), "e".into()])),
+            Style::new()
+                .clear_formats(FormatFlags::BOLD)
+                .paint(AnsiStrings([AnsiString::control("\x1b[K"), "f".into()])),
+        ])
+        .to_html();
+        assert_eq!(
+            html,
+            "<span style=\"color:#cd0000\">a </span>\
+             <span style=\"color:#0000ee\">c</span>\
+             <span style=\"color:#cd0000\"> b</span>\
+             <span style=\"color:#cdcd00;font-weight:bold\">d</span>\
+             <span style=\"color:#00cd00;font-weight:bold\">e</span>\
+             f"
+        );
+    }
+
+    #[test]
+    fn escaping() {
+        let link = Style::new()
+            .paint("<&>")
+            .hyperlink("https://example.com/?a=1&b=\"2\"");
+        assert_eq!(
+            AnsiStrings([link]).to_html(),
+            "<a href=\"https://example.com/?a=1&amp;b=&quot;2&quot;\">&lt;&amp;&gt;</a>"
+        );
+        assert_eq!(AnsiStrings([Red.paint("")]).to_html(), "");
+        for url in [
+            "javascript:alert(1)",
+            "JavaScript:x",
+            "data:text/html,x",
+            " java\tscript:x",
+            "/relative",
+        ] {
+            assert_eq!(
+                AnsiStrings([Style::new().paint("x").hyperlink(url)]).to_html(),
+                "x"
+            );
+        }
+        assert_eq!(
+            AnsiStrings([Style::new().paint("x").hyperlink("MAILTO:a@b")]).to_html(),
+            "<a href=\"MAILTO:a@b\">x</a>"
+        );
+        assert_eq!(style_css(Default.on(Default).hidden()), "visibility:hidden");
+    }
+}
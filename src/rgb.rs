@@ -1,6 +1,5 @@
 // Code liberally borrowed from here
 // https://github.com/navierr/coloriz
-use std::u32;
 
 /// Represents RGB color with 8-bit channels.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -78,6 +77,227 @@ impl Rgb {
     }
 }
 
+/// The channel `c` of an sRGB color in linear light, from 0 to 1.
+fn to_linear(c: u8) -> f32 {
+    let c = c as f32 / 255.0;
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// The sRGB channel with the value `c` in linear light.
+fn from_linear(c: f32) -> u8 {
+    let c = c.clamp(0.0, 1.0);
+    let c = if c <= 0.003_130_8 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    };
+    (c * 255.0).round() as u8
+}
+
+/// Mixes two channels, rounding to the nearest value.
+fn mix(a: u8, b: u8, t: f32) -> u8 {
+    (a as f32 + (b as f32 - a as f32) * t).round() as u8
+}
+
+impl Rgb {
+    /// Mixes `self` with `other`, in linear light, so that halfway between
+    /// two colors is as bright as an even mix of their light. `t` is clamped
+    /// between `[0.0, 1.0]`; unlike [`lerp`](Rgb::lerp), which mixes the
+    /// channels as they are stored, the middle of a blend is not too dark.
+    ///
+    /// ```
+    /// use nu_ansi_term::Rgb;
+    ///
+    /// let (black, white) = (Rgb::gray(0), Rgb::gray(255));
+    /// assert_eq!(black.blend(white, 0.5), Rgb::gray(188));
+    /// assert_eq!(black.blend(white, 1.0), white);
+    /// ```
+    pub fn blend(&self, other: Self, t: f32) -> Self {
+        let t = t.clamp(0.0, 1.0);
+        let channel = |a: u8, b: u8| {
+            let (a, b) = (to_linear(a), to_linear(b));
+            from_linear(a + (b - a) * t)
+        };
+        Self::new(
+            channel(self.r, other.r),
+            channel(self.g, other.g),
+            channel(self.b, other.b),
+        )
+    }
+
+    /// The color seen when `self` is drawn with opacity `alpha` (from 0 to
+    /// 1) over `background`, as a browser composites translucent colors.
+    ///
+    /// ```
+    /// use nu_ansi_term::Rgb;
+    ///
+    /// let highlight = Rgb::new(255, 255, 0);
+    /// assert_eq!(highlight.with_alpha_over(Rgb::gray(0), 0.25), Rgb::new(64, 64, 0));
+    /// ```
+    pub fn with_alpha_over(&self, background: Self, alpha: f32) -> Self {
+        let alpha = alpha.clamp(0.0, 1.0);
+        Self::new(
+            mix(background.r, self.r, alpha),
+            mix(background.g, self.g, alpha),
+            mix(background.b, self.b, alpha),
+        )
+    }
+
+    /// A lighter version of `self`, with its HSL lightness raised by
+    /// `amount` (from 0 to 1).
+    ///
+    /// ```
+    /// use nu_ansi_term::Rgb;
+    ///
+    /// assert_eq!(Rgb::new(128, 0, 0).lighten(0.25), Rgb::new(255, 0, 0));
+    /// assert_eq!(Rgb::gray(200).lighten(1.0), Rgb::gray(255));
+    /// ```
+    pub fn lighten(&self, amount: f32) -> Self {
+        let (h, s, l) = self.to_hsl();
+        Self::from_hsl(h, s, (l + amount).clamp(0.0, 1.0))
+    }
+
+    /// A darker version of `self`, with its HSL lightness lowered by
+    /// `amount` (from 0 to 1).
+    ///
+    /// ```
+    /// use nu_ansi_term::Rgb;
+    ///
+    /// assert_eq!(Rgb::new(255, 0, 0).darken(0.25), Rgb::new(128, 0, 0));
+    /// ```
+    pub fn darken(&self, amount: f32) -> Self {
+        self.lighten(-amount)
+    }
+
+    /// The hue (in degrees, from 0 to 360), saturation and lightness (from
+    /// 0 to 1) of `self`.
+    ///
+    /// ```
+    /// use nu_ansi_term::Rgb;
+    ///
+    /// assert_eq!(Rgb::new(255, 0, 0).to_hsl(), (0.0, 1.0, 0.5));
+    /// assert_eq!(Rgb::from_hsl(120.0, 1.0, 0.25), Rgb::new(0, 128, 0));
+    /// ```
+    pub fn to_hsl(&self) -> (f32, f32, f32) {
+        let (hue, max, min) = self.hue_max_min();
+        let lightness = (max + min) / 2.0;
+        let saturation = if max == min {
+            0.0
+        } else {
+            (max - min) / (1.0 - (2.0 * lightness - 1.0).abs())
+        };
+        (hue, saturation, lightness)
+    }
+
+    /// The color with the given hue (in degrees), saturation and lightness
+    /// (from 0 to 1).
+    pub fn from_hsl(hue: f32, saturation: f32, lightness: f32) -> Self {
+        let (s, l) = (saturation.clamp(0.0, 1.0), lightness.clamp(0.0, 1.0));
+        let chroma = (1.0 - (2.0 * l - 1.0).abs()) * s;
+        Self::from_hue_chroma(hue, chroma, l - chroma / 2.0)
+    }
+
+    /// The hue (in degrees, from 0 to 360), saturation and value (from 0 to
+    /// 1) of `self`.
+    ///
+    /// ```
+    /// use nu_ansi_term::Rgb;
+    ///
+    /// assert_eq!(Rgb::new(0, 0, 128).to_hsv(), (240.0, 1.0, 128.0 / 255.0));
+    /// assert_eq!(Rgb::from_hsv(60.0, 0.5, 1.0), Rgb::new(255, 255, 128));
+    /// ```
+    pub fn to_hsv(&self) -> (f32, f32, f32) {
+        let (hue, max, min) = self.hue_max_min();
+        let saturation = if max == 0.0 { 0.0 } else { (max - min) / max };
+        (hue, saturation, max)
+    }
+
+    /// The color with the given hue (in degrees), saturation and value
+    /// (from 0 to 1).
+    pub fn from_hsv(hue: f32, saturation: f32, value: f32) -> Self {
+        let (s, v) = (saturation.clamp(0.0, 1.0), value.clamp(0.0, 1.0));
+        let chroma = v * s;
+        Self::from_hue_chroma(hue, chroma, v - chroma)
+    }
+
+    /// The hue of `self` in degrees, and its largest and smallest channels,
+    /// from 0 to 1.
+    fn hue_max_min(&self) -> (f32, f32, f32) {
+        let [r, g, b] = [self.r, self.g, self.b].map(|c| c as f32 / 255.0);
+        let (max, min) = (r.max(g).max(b), r.min(g).min(b));
+        let delta = max - min;
+        let hue = if delta == 0.0 {
+            0.0
+        } else if max == r {
+            60.0 * ((g - b) / delta).rem_euclid(6.0)
+        } else if max == g {
+            60.0 * ((b - r) / delta + 2.0)
+        } else {
+            60.0 * ((r - g) / delta + 4.0)
+        };
+        (hue, max, min)
+    }
+
+    /// The color with `hue` (in degrees) and `chroma`, with `offset` added
+    /// to each channel.
+    fn from_hue_chroma(hue: f32, chroma: f32, offset: f32) -> Self {
+        let sector = hue.rem_euclid(360.0) / 60.0;
+        let x = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+        let (r, g, b) = match sector as u8 {
+            0 => (chroma, x, 0.0),
+            1 => (x, chroma, 0.0),
+            2 => (0.0, chroma, x),
+            3 => (0.0, x, chroma),
+            4 => (x, 0.0, chroma),
+            _ => (chroma, 0.0, x),
+        };
+        let channel = |c: f32| ((c + offset).clamp(0.0, 1.0) * 255.0).round() as u8;
+        Self::new(channel(r), channel(g), channel(b))
+    }
+
+    /// The relative luminance of `self`, from 0 (black) to 1 (white), as
+    /// defined by the WCAG.
+    pub fn luminance(&self) -> f32 {
+        0.2126 * to_linear(self.r) + 0.7152 * to_linear(self.g) + 0.0722 * to_linear(self.b)
+    }
+
+    /// The WCAG contrast ratio of `self` and `other`, from 1 (the same
+    /// luminance) to 21 (black and white). Text should have a ratio of at
+    /// least 4.5 with its background to be easy to read.
+    ///
+    /// ```
+    /// use nu_ansi_term::Rgb;
+    ///
+    /// let ratio = Rgb::gray(0).contrast_ratio(Rgb::gray(255));
+    /// assert!((ratio - 21.0).abs() < 0.01);
+    /// ```
+    pub fn contrast_ratio(&self, other: Self) -> f32 {
+        let (a, b) = (self.luminance(), other.luminance());
+        (a.max(b) + 0.05) / (a.min(b) + 0.05)
+    }
+
+    /// Black or white, whichever is easier to read on `background`.
+    ///
+    /// ```
+    /// use nu_ansi_term::Rgb;
+    ///
+    /// assert_eq!(Rgb::best_foreground_on(Rgb::new(255, 255, 0)), Rgb::gray(0));
+    /// assert_eq!(Rgb::best_foreground_on(Rgb::new(0, 0, 238)), Rgb::gray(255));
+    /// ```
+    pub fn best_foreground_on(background: Self) -> Self {
+        let (black, white) = (Self::gray(0), Self::gray(255));
+        if black.contrast_ratio(background) >= white.contrast_ratio(background) {
+            black
+        } else {
+            white
+        }
+    }
+}
+
 impl From<(u8, u8, u8)> for Rgb {
     fn from((r, g, b): (u8, u8, u8)) -> Self {
         Self::new(r, g, b)
@@ -90,6 +310,12 @@ impl From<(f32, f32, f32)> for Rgb {
     }
 }
 
+impl From<Rgb> for crate::Color {
+    fn from(Rgb { r, g, b }: Rgb) -> Self {
+        crate::Color::Rgb(r, g, b)
+    }
+}
+
 use crate::ANSIColorCode;
 use crate::TargetGround;
 impl ANSIColorCode for Rgb {
@@ -269,3 +495,36 @@ impl std::ops::Neg for &Rgb {
         rgb_negate(self)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn hsl_and_hsv_round_trip() {
+        for color in [
+            Rgb::new(255, 136, 0),
+            Rgb::new(1, 2, 3),
+            Rgb::new(92, 92, 255),
+            Rgb::gray(127),
+            Rgb::new(200, 0, 150),
+        ] {
+            let (h, s, l) = color.to_hsl();
+            assert_eq!(Rgb::from_hsl(h, s, l), color);
+            let (h, s, v) = color.to_hsv();
+            assert_eq!(Rgb::from_hsv(h, s, v), color);
+        }
+        assert_eq!(Rgb::from_hsl(-120.0, 1.0, 0.5), Rgb::new(0, 0, 255));
+        assert_eq!(Rgb::from_hsv(360.0, 1.0, 1.0), Rgb::new(255, 0, 0));
+    }
+
+    #[test]
+    fn mixing() {
+        let (red, blue) = (Rgb::new(255, 0, 0), Rgb::new(0, 0, 255));
+        assert_eq!(red.blend(blue, 0.0), red);
+        assert_eq!(red.blend(blue, 0.5), Rgb::new(188, 0, 188));
+        assert_eq!(red.with_alpha_over(blue, 1.0), red);
+        assert_eq!(red.with_alpha_over(blue, 0.5), Rgb::new(128, 0, 128));
+        assert_eq!(Rgb::gray(10).darken(0.5), Rgb::gray(0));
+    }
+}
@@ -0,0 +1,407 @@
+use crate::width::{clusters, escape_len};
+use crate::write::Content;
+use crate::{nested, AnsiString, AnsiStrings, Style};
+use std::ops::{Bound, RangeBounds};
+
+/// Number of characters of `text` outside of escape sequences.
+fn visible_chars(text: &str) -> usize {
+    let mut count = 0;
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        if c == '\x1B' {
+            rest = &rest[escape_len(rest)..];
+        } else {
+            count += 1;
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    count
+}
+
+/// A range of visible characters: from the first position up to the
+/// second, or to the end if there is none.
+type Cut = (usize, Option<usize>);
+
+/// Whether position `ix` is within `cut`.
+fn in_cut(&(start, end): &Cut, ix: usize) -> bool {
+    ix >= start && end.map_or(true, |end| ix < end)
+}
+
+/// The character position in `text` at which cluster number `cluster` of
+/// `text` (see [`clusters`]) starts, with positions past the last cluster
+/// counting one character each.
+fn cluster_start(text: &str, cluster: usize) -> usize {
+    let mut start = 0;
+    let mut count = 0;
+    for chars in clusters(text) {
+        if count == cluster {
+            return start;
+        }
+        start += chars.chars().count();
+        count += 1;
+    }
+    start.saturating_add(cluster - count)
+}
+
+/// The characters of `text` in each of `cuts` (which are in order and do not
+/// overlap), counting only those outside of escape sequences, in one pass
+/// over `text`. Every escape sequence is kept in every cut, so that the
+/// styles and links of values painted into `text` are still closed after
+/// it.
+fn cut_rendered(text: &str, cuts: &[Cut]) -> Vec<String> {
+    let mut pieces = vec![String::new(); cuts.len()];
+    let mut current = 0;
+    let mut ix = 0;
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        let len = if c == '\x1B' {
+            let len = escape_len(rest);
+            for piece in &mut pieces {
+                piece.push_str(&rest[..len]);
+            }
+            len
+        } else {
+            while cuts
+                .get(current)
+                .map_or(false, |&(_, end)| end.map_or(false, |end| end <= ix))
+            {
+                current += 1;
+            }
+            if cuts.get(current).map_or(false, |cut| in_cut(cut, ix)) {
+                pieces[current].push(c);
+            }
+            ix += 1;
+            c.len_utf8()
+        };
+        rest = &rest[len..];
+    }
+    pieces
+}
+
+impl<'a> AnsiStrings<'a> {
+    /// The visible characters of these strings in `range`, with the style
+    /// and link of the segment each one came from, e.g. to scroll a line of
+    /// painted segments sideways.
+    ///
+    /// Positions count clusters rather than code points: a character takes
+    /// one position together with the combining marks, variation selectors
+    /// and joined characters after it, so it is never cut apart from them.
+    ///
+    /// Segments without visible characters, such as titles, controls and
+    /// style changes, are kept if they come at a position within the range.
+    /// Formatting arguments are rendered in the process.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::AnsiStrings;
+    /// use nu_ansi_term::Color::{Blue, Red};
+    ///
+    /// let line = AnsiStrings([
+    ///     Red.paint("error: "),
+    ///     Blue.paint("see docs").hyperlink("https://example.com"),
+    /// ]);
+    /// assert_eq!(
+    ///     line.substring(4..11).to_string(),
+    ///     AnsiStrings([
+    ///         Red.paint("r: "),
+    ///         Blue.paint("see ").hyperlink("https://example.com"),
+    ///     ])
+    ///     .to_string()
+    /// );
+    ///
+    /// // A decomposed "é" takes one position.
+    /// let word = AnsiStrings([Red.paint("e\u{301}x"), Blue.paint("yz")]);
+    /// assert_eq!(
+    ///     word.substring(1..3).to_string(),
+    ///     AnsiStrings([Red.paint("x"), Blue.paint("y")]).to_string()
+    /// );
+    /// ```
+    pub fn substring(&self, range: impl RangeBounds<usize>) -> AnsiStrings<'static> {
+        let start = match range.start_bound() {
+            Bound::Included(&start) => start,
+            Bound::Excluded(&start) => start.saturating_add(1),
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            // Past the last position, the range is unbounded.
+            Bound::Included(&end) => end.checked_add(1),
+            Bound::Excluded(&end) => Some(end),
+            Bound::Unbounded => None,
+        };
+        self.cut(start, end)
+    }
+
+    /// These strings split into the visible characters before position
+    /// `index` and those from `index` on, counting clusters as
+    /// [`substring`](Self::substring) does.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::AnsiStrings;
+    /// use nu_ansi_term::Color::Red;
+    ///
+    /// let (left, right) = AnsiStrings([Red.paint("left|right")]).split_at(4);
+    /// assert_eq!(left.to_string(), Red.paint("left").to_string());
+    /// assert_eq!(right.to_string(), Red.paint("|right").to_string());
+    /// ```
+    pub fn split_at(&self, index: usize) -> (AnsiStrings<'static>, AnsiStrings<'static>) {
+        (self.cut(0, Some(index)), self.cut(index, None))
+    }
+
+    /// These strings with each line prefixed by its number, counting from
+    /// `start`, right-aligned and painted in `gutter_style` along with the
+    /// separator after it, as in a printout of source code.
+    ///
+    /// Lines are cut apart as by [`substring`](Self::substring), so each
+    /// one keeps the styles and links of its text and resets them at its
+    /// end, and the gutter is never painted in the style of the text. Every
+    /// gutter has the same width, so the lines stay aligned. There is no
+    /// number for the empty line after a final newline, and numbers stop
+    /// at `usize::MAX` rather than overflowing.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::AnsiStrings;
+    /// use nu_ansi_term::Color::{Blue, DarkGray, Green};
+    /// use nu_ansi_term::utils::unstyle;
+    ///
+    /// let code = AnsiStrings([Blue.paint("fn"), " main() {\n    ".into(), Green.paint("\"hi\"\n}\n")]);
+    /// let numbered = code.with_line_numbers(9, DarkGray.normal());
+    /// assert_eq!(
+    ///     unstyle(&numbered),
+    ///     " 9 │ fn main() {\n10 │     \"hi\"\n11 │ }\n"
+    /// );
+    /// assert_eq!(
+    ///     numbered.iter().take(3).map(|s| s.to_string()).collect::<String>(),
+    ///     "\x1b[90m 9 │ \x1b[0m\x1b[34mfn\x1b[0m main() {"
+    /// );
+    /// ```
+    pub fn with_line_numbers(&self, start: usize, gutter_style: Style) -> AnsiStrings<'static> {
+        let text = self.unstyled();
+        if text.is_empty() {
+            return AnsiStrings::with_capacity(0);
+        }
+        let mut breaks: Vec<usize> = text
+            .chars()
+            .enumerate()
+            .filter(|&(_, c)| c == '\n')
+            .map(|(ix, _)| ix)
+            .collect();
+        let len = text.chars().count();
+        let ends_with_newline = breaks.last() == Some(&len.wrapping_sub(1));
+        if !ends_with_newline {
+            breaks.push(len);
+        }
+        let number_width = start
+            .saturating_add(breaks.len().saturating_sub(1))
+            .to_string()
+            .len();
+
+        // Every line is cut in the same pass over the segments.
+        let mut line_start = 0;
+        let cuts: Vec<Cut> = breaks
+            .iter()
+            .map(|&line_end| {
+                let cut = (line_start, Some(line_end));
+                line_start = line_end + 1;
+                cut
+            })
+            .collect();
+
+        let mut numbered = AnsiStrings::with_capacity(3 * breaks.len());
+        for (ix, line) in self.cut_many(&cuts).into_iter().enumerate() {
+            if ix > 0 {
+                numbered.push(AnsiString::from("\n"));
+            }
+            numbered.push(gutter_style.paint(format!(
+                "{:>width$} │ ",
+                start.saturating_add(ix),
+                width = number_width
+            )));
+            numbered += line;
+        }
+        if ends_with_newline {
+            numbered.push(AnsiString::from("\n"));
+        }
+        numbered
+    }
+
+    fn cut(&self, start: usize, end: Option<usize>) -> AnsiStrings<'static> {
+        let text = self.unstyled();
+        let start = cluster_start(&text, start);
+        let end = end.map(|end| cluster_start(&text, end));
+        self.cut_many(&[(start, end)])
+            .pop()
+            .expect("there is a piece for every cut")
+    }
+
+    /// These strings cut into a piece for each of `cuts`, which are in order
+    /// and do not overlap, in one pass over the segments.
+    fn cut_many(&self, cuts: &[Cut]) -> Vec<AnsiStrings<'static>> {
+        let mut pieces: Vec<AnsiStrings<'static>> =
+            cuts.iter().map(|_| AnsiStrings::with_capacity(1)).collect();
+        // The first cut which does not end before the current segment.
+        let mut first = 0;
+        let mut offset = 0;
+        for (ix, segment) in self.iter().enumerate() {
+            while cuts
+                .get(first)
+                .map_or(false, |&(_, end)| end.map_or(false, |end| end <= offset))
+            {
+                first += 1;
+            }
+            let (len, rendered) = match segment.content() {
+                _ if segment.scheduled_style().is_none() => (0, None),
+                Content::GenericStrings(strings) => (strings.len_visible(), None),
+                content => {
+                    let text = match content {
+                        Content::FmtArgs(args) => {
+                            nested::within(*segment.style_ref(), || args.to_string())
+                        }
+                        content => content.to_string(),
+                    };
+                    (visible_chars(&text), Some(text))
+                }
+            };
+
+            let hits: Vec<usize> = (first..cuts.len())
+                .take_while(|&hit| cuts[hit].0 <= offset + len)
+                .filter(|&hit| {
+                    let (start, end) = cuts[hit];
+                    if len == 0 {
+                        in_cut(&cuts[hit], offset)
+                    } else {
+                        offset + len > start && end.map_or(true, |end| offset < end)
+                    }
+                })
+                .collect();
+            if hits.is_empty() {
+                offset += len;
+                continue;
+            }
+            let nested_cuts: Vec<Cut> = hits
+                .iter()
+                .map(|&hit| {
+                    let (start, end) = cuts[hit];
+                    (
+                        start.saturating_sub(offset),
+                        end.map(|end| end.saturating_sub(offset)),
+                    )
+                })
+                .collect();
+            let contents: Vec<Content<'static, str>> = match (segment.content(), rendered) {
+                (content, _) if segment.scheduled_style().is_none() => hits
+                    .iter()
+                    .map(|_| Content::owned(content.to_string()))
+                    .collect(),
+                (Content::GenericStrings(strings), _) => strings
+                    .cut_many(&nested_cuts)
+                    .into_iter()
+                    .map(|piece| Content::GenericStrings(piece.rebase_on(*segment.style_ref())))
+                    .collect(),
+                (_, Some(text)) => cut_rendered(&text, &nested_cuts)
+                    .into_iter()
+                    .map(Content::owned)
+                    .collect(),
+                (_, None) => unreachable!("text segments are rendered"),
+            };
+            for (hit, content) in hits.into_iter().zip(contents) {
+                match self.lazy_style(ix).cloned() {
+                    Some(lazy) => {
+                        pieces[hit].push_lazy(content, move |profile| lazy.resolve(profile))
+                    }
+                    None => pieces[hit].push(segment.with_content(content)),
+                }
+            }
+            offset += len;
+        }
+        pieces
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::AnsiString;
+    use crate::Color::*;
+
+    #[test]
+    fn rendered_text() {
+        let text = "ab\x1b[31mcd\x1b[0mef";
+        assert_eq!(visible_chars(text), 6);
+        assert_eq!(
+            cut_rendered(text, &[(1, Some(3)), (4, None)]),
+            ["b\x1b[31mc\x1b[0m", "\x1b[31m\x1b[0mef"]
+        );
+    }
+
+    #[test]
+    fn segments_across_the_cut() {
+        let mut strings = AnsiStrings([
+            AnsiString::title("t"),
+            Red.paint("ab"),
+            Green.paint(AnsiStrings([Blue.paint("cd"), "ef".into()])),
+        ]);
+        strings.push_control("\x1b[K");
+        strings.push_lazy("gh", |_| Yellow.bold());
+
+        let (left, right) = strings.split_at(3);
+        assert_eq!(
+            left.to_string(),
+            AnsiStrings([
+                AnsiString::title("t"),
+                Red.paint("ab"),
+                Green.paint(AnsiStrings([Blue.paint("c")])),
+            ])
+            .to_string()
+        );
+        assert_eq!(
+            right.to_string(),
+            AnsiStrings([
+                Green.paint(AnsiStrings([Blue.paint("d"), "ef".into()])),
+                AnsiString::control("\x1b[K"),
+                Yellow.bold().paint("gh"),
+            ])
+            .to_string()
+        );
+        assert_eq!(strings.substring(..=0).len(), 2);
+        assert_eq!(strings.substring(7..).to_string(), "\x1b[1;33mh\x1b[0m");
+        assert!(strings.substring(8..).is_empty());
+        assert_eq!(strings.substring(6..=usize::MAX).len(), 2);
+    }
+
+    #[test]
+    fn combining_marks_take_one_position() {
+        let text = AnsiStrings([Red.paint("e\u{301}"), Blue.paint("\u{302}x")]);
+        let (left, right) = text.split_at(1);
+        assert_eq!(
+            left.to_string(),
+            AnsiStrings([Red.paint("e\u{301}"), Blue.paint("\u{302}")]).to_string()
+        );
+        assert_eq!(right.to_string(), Blue.paint("x").to_string());
+        assert_eq!(text.substring(1..).to_string(), Blue.paint("x").to_string());
+        assert!(text.substring(2..).is_empty());
+        assert_eq!(cluster_start("e\u{301}x", 1), 2);
+        assert_eq!(cluster_start("ab", 5), 5);
+    }
+
+    #[test]
+    fn numbered_lines_keep_their_styles() {
+        let text = AnsiStrings([Red.paint("a\nb"), "c\n\nd".into()]);
+        let numbered = text.with_line_numbers(1, Blue.normal());
+        assert_eq!(
+            numbered.to_string(),
+            "\x1b[34m1 │ \x1b[31ma\x1b[0m\n\x1b[34m2 │ \x1b[31mb\x1b[0mc\n\
+             \x1b[34m3 │ \x1b[0m\n\x1b[34m4 │ \x1b[0md"
+        );
+        assert!(AnsiStrings([])
+            .with_line_numbers(1, Blue.normal())
+            .is_empty());
+
+        let last = AnsiStrings(["a\nb".into()]).with_line_numbers(usize::MAX, Style::new());
+        assert_eq!(last.to_string(), format!("{0} │ a\n{0} │ b", usize::MAX));
+    }
+}
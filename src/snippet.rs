@@ -0,0 +1,485 @@
+use crate::width::WidthPolicy;
+use crate::Color::{Blue, Red};
+use crate::{AnsiString, AnsiStrings, BoxChars, SourceSpan, Style};
+
+/// Columns a tab is expanded to, so that markers line up under it.
+const TAB: &str = "    ";
+
+/// A span of source marked in a [`Snippet`], with a message explaining it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Label {
+    span: SourceSpan,
+    message: String,
+    style: Style,
+    marker: char,
+}
+
+impl Label {
+    /// The label of the main cause of a diagnostic, underlined with `^` in
+    /// bold red.
+    pub fn primary(span: impl Into<SourceSpan>, message: impl Into<String>) -> Self {
+        Self {
+            span: span.into(),
+            message: message.into(),
+            style: Red.bold(),
+            marker: '^',
+        }
+    }
+
+    /// A label adding context to a diagnostic, underlined with `-` in bold
+    /// blue.
+    pub fn secondary(span: impl Into<SourceSpan>, message: impl Into<String>) -> Self {
+        Self {
+            span: span.into(),
+            message: message.into(),
+            style: Blue.bold(),
+            marker: '-',
+        }
+    }
+
+    /// Paint the underline and message in `style`.
+    pub fn style(mut self, style: Style) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Underline the span with `marker`.
+    pub fn marker(mut self, marker: char) -> Self {
+        self.marker = marker;
+        self
+    }
+}
+
+/// The lines of source spanned by a label, and the columns it starts and
+/// ends at on them (the end column is exclusive).
+#[derive(Clone, Copy, Debug)]
+struct Placement {
+    start_line: usize,
+    start_col: usize,
+    end_line: usize,
+    end_col: usize,
+}
+
+impl Placement {
+    fn is_multiline(&self) -> bool {
+        self.start_line != self.end_line
+    }
+}
+
+/// A row of annotations below a line of source: a character (or nothing) in
+/// each column after the gutter, and a message at the end.
+#[derive(Default)]
+struct Row {
+    cells: Vec<Option<(char, Style)>>,
+    message: Option<(String, Style)>,
+}
+
+impl Row {
+    fn put(&mut self, column: usize, c: char, style: Style) {
+        if self.cells.len() <= column {
+            self.cells.resize(column + 1, None);
+        }
+        self.cells[column] = Some((c, style));
+    }
+
+    /// Put the message at `column`, after the cells before it.
+    fn put_message(&mut self, column: usize, message: &str, style: Style) {
+        self.cells.truncate(column);
+        self.pad(column);
+        self.message = Some((message.to_string(), style));
+    }
+
+    /// Pad the row with blank cells to at least `width` columns.
+    fn pad(&mut self, width: usize) {
+        if self.cells.len() < width {
+            self.cells.resize(width, None);
+        }
+    }
+
+    fn into_segments(self, segments: &mut Vec<AnsiString<'static>>) {
+        let mut run = String::new();
+        let mut run_style = Style::new();
+        for (c, style) in self
+            .cells
+            .into_iter()
+            .map(|cell| cell.unwrap_or((' ', Style::new())))
+        {
+            if style != run_style && !run.is_empty() {
+                segments.push(run_style.paint(std::mem::take(&mut run)));
+            }
+            run_style = style;
+            run.push(c);
+        }
+        if !run.is_empty() {
+            segments.push(run_style.paint(run));
+        }
+        if let Some((message, style)) = self.message {
+            segments.push(style.paint(message));
+        }
+    }
+}
+
+/// A compiler-style excerpt of source code, with labels underlining spans of
+/// it, and line numbers in a gutter. Labels spanning several lines are
+/// routed along a margin to the left of the source.
+///
+/// # Examples
+///
+/// ```
+/// use nu_ansi_term::snippet::{Label, Snippet};
+/// use nu_ansi_term::utils::unstyle;
+///
+/// let source = "let x = 1;\nlet y = x + ;\n";
+/// let snippet = Snippet::new(source)
+///     .label(Label::secondary(4..5, "defined here"))
+///     .label(Label::primary(23..24, "expected an expression"));
+///
+/// assert_eq!(
+///     unstyle(&snippet.render()),
+///     "1 │ let x = 1;\n  \
+///        │     - defined here\n\
+///      2 │ let y = x + ;\n  \
+///        │             ^ expected an expression"
+/// );
+/// ```
+#[derive(Clone, Debug)]
+pub struct Snippet<'s> {
+    source: &'s str,
+    first_line: usize,
+    labels: Vec<Label>,
+    context: usize,
+    gutter_style: Style,
+    chars: BoxChars,
+    width_policy: WidthPolicy,
+}
+
+impl<'s> Snippet<'s> {
+    /// Create a snippet of `source` without labels, numbering its lines from
+    /// 1 and drawing with [`BoxChars::ROUNDED`].
+    pub fn new(source: &'s str) -> Self {
+        Self {
+            source,
+            first_line: 1,
+            labels: Vec::new(),
+            context: 0,
+            gutter_style: Blue.bold(),
+            chars: BoxChars::ROUNDED,
+            width_policy: WidthPolicy::new(),
+        }
+    }
+
+    /// Add a label. Spans are byte offsets into the source of the snippet.
+    pub fn label(mut self, label: Label) -> Self {
+        self.labels.push(label);
+        self
+    }
+
+    /// Number the first line of the source `line`, for sources which are an
+    /// excerpt of a larger file.
+    pub fn first_line(mut self, line: usize) -> Self {
+        self.first_line = line;
+        self
+    }
+
+    /// Show `lines` lines of source around each labelled line.
+    pub fn context(mut self, lines: usize) -> Self {
+        self.context = lines;
+        self
+    }
+
+    /// Paint the line numbers and gutter in `style`.
+    pub fn gutter_style(mut self, style: Style) -> Self {
+        self.gutter_style = style;
+        self
+    }
+
+    /// Draw the gutter and multi-line labels with `chars`.
+    pub fn chars(mut self, chars: BoxChars) -> Self {
+        self.chars = chars;
+        self
+    }
+
+    /// Measure the source with `policy`.
+    pub fn width_policy(mut self, policy: WidthPolicy) -> Self {
+        self.width_policy = policy;
+        self
+    }
+
+    /// Render the labelled lines of the source with their annotations, with
+    /// lines separated by `\n`. Lines which are skipped between labelled
+    /// lines are shown as `...`.
+    pub fn render(&self) -> AnsiStrings<'static> {
+        let lines: Vec<(usize, &str)> = self
+            .source
+            .split('\n')
+            .scan(0, |start, line| {
+                let line_start = *start;
+                *start += line.len() + 1;
+                Some((line_start, line.strip_suffix('\r').unwrap_or(line)))
+            })
+            .collect();
+        let placements: Vec<Placement> = self
+            .labels
+            .iter()
+            .map(|label| self.place(&lines, label.span))
+            .collect();
+        let multiline: Vec<usize> = (0..self.labels.len())
+            .filter(|&ix| placements[ix].is_multiline())
+            .collect();
+
+        let mut shown = vec![false; lines.len()];
+        for placement in &placements {
+            let first = placement.start_line.saturating_sub(self.context);
+            let last = (placement.end_line + self.context).min(lines.len() - 1);
+            shown[first..=last].iter_mut().for_each(|line| *line = true);
+        }
+        let number_width = shown
+            .iter()
+            .rposition(|&shown| shown)
+            .map_or(1, |last| (self.first_line + last).to_string().len());
+        let margin = multiline.len() * 2;
+
+        let mut segments = Vec::new();
+        let mut previous = None;
+        for (ix, &(_, text)) in lines.iter().enumerate().filter(|&(ix, _)| shown[ix]) {
+            if matches!(previous, Some(previous) if previous + 1 != ix) {
+                self.push_line(&mut segments, vec![self.gutter_style.paint("...")]);
+            }
+            previous = Some(ix);
+
+            let mut line = vec![self.gutter(Some(self.first_line + ix), number_width)];
+            let mut margin_row = Row::default();
+            for (slot, &label) in multiline.iter().enumerate() {
+                let placement = placements[label];
+                let c = if placement.start_line == ix {
+                    self.chars.top_left
+                } else if placement.start_line < ix && ix <= placement.end_line {
+                    self.chars.vertical
+                } else {
+                    continue;
+                };
+                margin_row.put(slot * 2, c, self.labels[label].style);
+            }
+            margin_row.pad(margin);
+            margin_row.into_segments(&mut line);
+            line.push(Style::new().paint(text.replace('\t', TAB)));
+            self.push_line(&mut segments, line);
+
+            for row in self.annotations(ix, &placements, &multiline, margin) {
+                let mut line = vec![self.gutter(None, number_width)];
+                row.into_segments(&mut line);
+                self.push_line(&mut segments, line);
+            }
+        }
+        AnsiStrings(segments)
+    }
+
+    /// The rows of annotations below the line at `ix`: the underlines of
+    /// labels on the line, with their messages routed below one another,
+    /// and the ends of labels spanning several lines.
+    fn annotations(
+        &self,
+        ix: usize,
+        placements: &[Placement],
+        multiline: &[usize],
+        margin: usize,
+    ) -> Vec<Row> {
+        // Multi-line labels still open below this line keep their margin.
+        let margin_row = |ending: Option<usize>| {
+            let mut row = Row::default();
+            for (slot, &label) in multiline.iter().enumerate() {
+                let placement = placements[label];
+                if Some(label) != ending && placement.start_line <= ix && ix <= placement.end_line {
+                    row.put(slot * 2, self.chars.vertical, self.labels[label].style);
+                }
+            }
+            row
+        };
+
+        let mut labels: Vec<usize> = (0..self.labels.len())
+            .filter(|&label| {
+                !placements[label].is_multiline() && placements[label].start_line == ix
+            })
+            .collect();
+        labels.sort_by_key(|&label| placements[label].start_col);
+
+        let mut rows = Vec::new();
+        if let Some(&last) = labels.last() {
+            let mut row = margin_row(None);
+            for &label in &labels {
+                let Placement {
+                    start_col, end_col, ..
+                } = placements[label];
+                let Label { marker, style, .. } = self.labels[label];
+                for column in start_col..end_col.max(start_col + 1) {
+                    row.put(margin + column, marker, style);
+                }
+            }
+            let Label { message, style, .. } = &self.labels[last];
+            if !message.is_empty() {
+                let end = margin + placements[last].end_col.max(placements[last].start_col + 1);
+                row.put_message(end + 1, message, *style);
+            }
+            rows.push(row);
+
+            // The messages of the others go below, from right to left, each
+            // connected to its label by a vertical line.
+            let routed: Vec<usize> = labels[..labels.len() - 1]
+                .iter()
+                .copied()
+                .filter(|&label| !self.labels[label].message.is_empty())
+                .collect();
+            for (nth, &label) in routed.iter().enumerate().rev() {
+                let mut connectors = margin_row(None);
+                for &left in &routed[..=nth] {
+                    let column = margin + placements[left].start_col;
+                    connectors.put(column, self.chars.vertical, self.labels[left].style);
+                }
+                rows.push(connectors);
+
+                let mut row = margin_row(None);
+                for &left in &routed[..nth] {
+                    let column = margin + placements[left].start_col;
+                    row.put(column, self.chars.vertical, self.labels[left].style);
+                }
+                let Label { message, style, .. } = &self.labels[label];
+                row.put_message(margin + placements[label].start_col, message, *style);
+                rows.push(row);
+            }
+        }
+
+        for (slot, &label) in multiline.iter().enumerate() {
+            if placements[label].end_line != ix {
+                continue;
+            }
+            let Label {
+                message,
+                style,
+                marker,
+                ..
+            } = &self.labels[label];
+            let mut row = margin_row(Some(label));
+            let end = margin + placements[label].end_col.max(1) - 1;
+            row.put(slot * 2, self.chars.bottom_left, *style);
+            for column in slot * 2 + 1..end {
+                row.put(column, self.chars.horizontal, *style);
+            }
+            row.put(end, *marker, *style);
+            if !message.is_empty() {
+                row.put_message(end + 2, message, *style);
+            }
+            rows.push(row);
+        }
+        rows
+    }
+
+    /// The line and column `span` starts and ends at. A span whose end is
+    /// before its start is taken to cover the same bytes the other way round.
+    fn place(&self, lines: &[(usize, &str)], span: SourceSpan) -> Placement {
+        let span = SourceSpan::new(span.start.min(span.end), span.start.max(span.end));
+        let locate = |offset: usize| {
+            let line = lines
+                .iter()
+                .rposition(|&(start, _)| start <= offset)
+                .unwrap_or(0);
+            let (start, text) = lines[line];
+            let mut within = offset.saturating_sub(start).min(text.len());
+            while !text.is_char_boundary(within) {
+                within -= 1;
+            }
+            let column = self
+                .width_policy
+                .str_width(&text[..within].replace('\t', TAB));
+            (line, column)
+        };
+        let (start_line, start_col) = locate(span.start);
+        // A span ending with a newline ends on the line before it.
+        let (end_line, end_col) = match locate(span.end) {
+            (line, 0) if line > start_line => (line - 1, {
+                let text = lines[line - 1].1;
+                self.width_policy.str_width(&text.replace('\t', TAB)) + 1
+            }),
+            end => end,
+        };
+        Placement {
+            start_line,
+            start_col,
+            end_line,
+            end_col: if end_line == start_line {
+                end_col.max(start_col)
+            } else {
+                end_col
+            },
+        }
+    }
+
+    /// The gutter of a line, with its number if it is a line of source.
+    fn gutter(&self, number: Option<usize>, width: usize) -> AnsiString<'static> {
+        let number = number.map_or(String::new(), |number| number.to_string());
+        self.gutter_style
+            .paint(format!("{:>width$} {} ", number, self.chars.vertical))
+    }
+
+    fn push_line(&self, segments: &mut Vec<AnsiString<'static>>, line: Vec<AnsiString<'static>>) {
+        if !segments.is_empty() {
+            segments.push(AnsiString::from("\n"));
+        }
+        segments.extend(line);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::unstyle;
+
+    #[test]
+    fn labels_on_one_line_are_routed_below_each_other() {
+        let source = "call(a, b, c)";
+        let snippet = Snippet::new(source)
+            .label(Label::secondary(5..6, "first"))
+            .label(Label::secondary(8..9, "second"))
+            .label(Label::primary(11..12, "third"));
+        assert_eq!(
+            unstyle(&snippet.render()),
+            "1 │ call(a, b, c)\n  \
+               │      -  -  ^ third\n  \
+               │      │  │\n  \
+               │      │  second\n  \
+               │      │\n  \
+               │      first"
+        );
+    }
+
+    #[test]
+    fn multiline_labels_use_the_margin() {
+        let source = "fn main() {\n    let x = 1;\n}\nfn other() {}\n\n// end";
+        let snippet = Snippet::new(source)
+            .first_line(10)
+            .chars(BoxChars::ASCII)
+            .label(Label::primary(10..26, "unclosed"))
+            .label(Label::secondary(44..46, "here"));
+        assert_eq!(
+            unstyle(&snippet.render()),
+            "10 | + fn main() {\n\
+             11 | |     let x = 1;\n   \
+                | +--------------^ unclosed\n\
+             ...\n\
+             15 |   // end\n   \
+                |   -- here"
+        );
+    }
+
+    #[test]
+    fn reversed_spans_cover_the_same_bytes() {
+        let source = "let x = 1;\nlet y = 2;";
+        for (reversed, forward) in [((7, 1), (1, 7)), ((15, 2), (2, 15))] {
+            let render = |(start, end)| {
+                let snippet =
+                    Snippet::new(source).label(Label::primary(SourceSpan::new(start, end), "here"));
+                unstyle(&snippet.render())
+            };
+            assert_eq!(render(reversed), render(forward));
+        }
+    }
+}
@@ -0,0 +1,187 @@
+use crate::{AnsiStrings, WidthPolicy};
+use std::ops::Range;
+
+/// A span of the source some output was rendered from, as byte offsets into
+/// the source, for diagnostics which need to point from the output back at
+/// the source (see [`AnsiStrings::push_spanned`]).
+///
+/// # Examples
+///
+/// ```
+/// use nu_ansi_term::SourceSpan;
+///
+/// let source = "let x = 1\nlet y = x +;";
+/// let span = SourceSpan::from_line_col(source, 2, 9, 3).unwrap();
+/// assert_eq!(span, SourceSpan::new(18, 21));
+/// assert_eq!(&source[span.range()], "x +");
+/// assert_eq!(span.line_col(source), (2, 9));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SourceSpan {
+    /// The byte offset of the start of the span.
+    pub start: usize,
+    /// The byte offset just past the end of the span.
+    pub end: usize,
+}
+
+impl SourceSpan {
+    /// The span from byte offset `start` up to (but not including) `end`.
+    pub const fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+
+    /// The span of `len` bytes starting at the 1-based `line` and `column`
+    /// (counted in characters) of `source`, if `source` has that position.
+    pub fn from_line_col(source: &str, line: usize, column: usize, len: usize) -> Option<Self> {
+        let line_start = if line == 1 {
+            0
+        } else {
+            source.match_indices('\n').nth(line.checked_sub(2)?)?.0 + 1
+        };
+        let line_text = source[line_start..].split('\n').next().unwrap_or("");
+        let offset = match line_text.char_indices().nth(column.checked_sub(1)?) {
+            Some((offset, _)) => offset,
+            // Just past the last character, as for a missing token.
+            None if column - 1 == line_text.chars().count() => line_text.len(),
+            None => return None,
+        };
+        let start = line_start + offset;
+        Some(Self::new(start, start + len))
+    }
+
+    /// The span as a range of byte offsets, for slicing the source.
+    pub fn range(&self) -> Range<usize> {
+        self.start..self.end
+    }
+
+    /// The number of bytes of the span.
+    pub fn len(&self) -> usize {
+        self.end.saturating_sub(self.start)
+    }
+
+    /// Check if the span covers no bytes.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The 1-based line and column (counted in characters) of the start of
+    /// the span in `source`. A start inside a character is taken to be the
+    /// start of that character.
+    pub fn line_col(&self, source: &str) -> (usize, usize) {
+        let mut start = self.start.min(source.len());
+        while !source.is_char_boundary(start) {
+            start -= 1;
+        }
+        let before = &source[..start];
+        let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+        (
+            before.matches('\n').count() + 1,
+            before[line_start..].chars().count() + 1,
+        )
+    }
+}
+
+impl From<Range<usize>> for SourceSpan {
+    fn from(range: Range<usize>) -> Self {
+        Self::new(range.start, range.end)
+    }
+}
+
+impl<'a> AnsiStrings<'a> {
+    /// The columns occupied by each segment pushed with a span (see
+    /// [`push_spanned`](Self::push_spanned)), with the span, in order.
+    /// Columns are counted from the start of these strings, which are
+    /// taken to be written on a single line.
+    pub fn column_spans(&self) -> Vec<(Range<usize>, SourceSpan)> {
+        self.column_spans_with(WidthPolicy::new())
+    }
+
+    /// The columns occupied by each segment pushed with a span (see
+    /// [`column_spans`](Self::column_spans)), measured with `policy`.
+    pub fn column_spans_with(&self, policy: WidthPolicy) -> Vec<(Range<usize>, SourceSpan)> {
+        let mut column = 0;
+        let mut spans = Vec::new();
+        for (string, span) in self.iter().zip(self.spans_by_segment()) {
+            let width = string.width_with(policy);
+            if let Some(span) = span {
+                spans.push((column..column + width, span));
+            }
+            column += width;
+        }
+        spans
+    }
+
+    /// The span of source shown at the 0-based `column` of the output, if
+    /// the segment there was pushed with one, for pointing at the source of
+    /// output which was clicked or selected.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::{AnsiStrings, SourceSpan};
+    /// use nu_ansi_term::Color::{Blue, Red};
+    ///
+    /// let mut line = AnsiStrings([]);
+    /// line.push_spanned(Blue.paint("let"), SourceSpan::new(0, 3));
+    /// line.push(" ".into());
+    /// line.push_spanned(Red.underline().paint("y"), SourceSpan::new(4, 5));
+    ///
+    /// assert_eq!(line.span_at_column(1), Some(SourceSpan::new(0, 3)));
+    /// assert_eq!(line.span_at_column(3), None);
+    /// assert_eq!(line.span_at_column(4), Some(SourceSpan::new(4, 5)));
+    /// ```
+    pub fn span_at_column(&self, column: usize) -> Option<SourceSpan> {
+        self.column_spans()
+            .into_iter()
+            .find(|(columns, _)| columns.contains(&column))
+            .map(|(_, span)| span)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Color::*;
+    use crate::{AnsiString, TerminalProfile};
+
+    #[test]
+    fn spans_survive_concatenation_and_wide_characters() {
+        let mut first = AnsiStrings([Green.paint("日本")]);
+        first.push_spanned(Red.paint("x"), SourceSpan::new(7, 8));
+        let mut second = AnsiStrings([AnsiString::title("ignored")]);
+        second.push_spanned(Blue.paint("yz"), (10..12).into());
+        let joined = first + second;
+
+        assert_eq!(
+            joined.column_spans(),
+            [
+                (4..5, SourceSpan::new(7, 8)),
+                (5..7, SourceSpan::new(10, 12)),
+            ]
+        );
+        assert_eq!(joined.span_at_column(6), Some(SourceSpan::new(10, 12)));
+        assert_eq!(
+            joined.resolved(&TerminalProfile::new()).span(2),
+            joined.span(2)
+        );
+    }
+
+    #[test]
+    fn line_and_column() {
+        let source = "a\nbé\n";
+        assert_eq!(SourceSpan::new(3, 5).line_col(source), (2, 2));
+        // Inside the `é`.
+        assert_eq!(SourceSpan::new(4, 5).line_col(source), (2, 2));
+        assert_eq!(
+            SourceSpan::from_line_col(source, 2, 2, 2),
+            Some(SourceSpan::new(3, 5))
+        );
+        // Just past the end of a line, as for a missing token.
+        assert_eq!(
+            SourceSpan::from_line_col(source, 2, 3, 0),
+            Some(SourceSpan::new(5, 5))
+        );
+        assert_eq!(SourceSpan::from_line_col(source, 2, 5, 0), None);
+        assert_eq!(SourceSpan::from_line_col(source, 4, 1, 0), None);
+    }
+}
@@ -0,0 +1,312 @@
+use crate::style::{BasedOn, FormatFlags};
+use crate::theme::parse_style;
+use crate::{Color, Style};
+use std::str::FromStr;
+use std::{error, fmt};
+
+/// The named colors, by the names they are read and written with.
+const COLOR_NAMES: [(&str, Color); 19] = [
+    ("black", Color::Black),
+    ("red", Color::Red),
+    ("green", Color::Green),
+    ("yellow", Color::Yellow),
+    ("blue", Color::Blue),
+    ("purple", Color::Purple),
+    ("magenta", Color::Magenta),
+    ("cyan", Color::Cyan),
+    ("white", Color::White),
+    ("dark_gray", Color::DarkGray),
+    ("light_red", Color::LightRed),
+    ("light_green", Color::LightGreen),
+    ("light_yellow", Color::LightYellow),
+    ("light_blue", Color::LightBlue),
+    ("light_purple", Color::LightPurple),
+    ("light_magenta", Color::LightMagenta),
+    ("light_cyan", Color::LightCyan),
+    ("light_gray", Color::LightGray),
+    ("default", Color::Default),
+];
+
+/// The formats, by the names they are read and written with, in the order
+/// they are written.
+const FORMAT_NAMES: [(&str, FormatFlags); 11] = [
+    ("bold", FormatFlags::BOLD),
+    ("dimmed", FormatFlags::DIMMED),
+    ("italic", FormatFlags::ITALIC),
+    ("underline", FormatFlags::UNDERLINE),
+    ("blink", FormatFlags::BLINK),
+    ("reverse", FormatFlags::REVERSE),
+    ("hidden", FormatFlags::HIDDEN),
+    ("strikethrough", FormatFlags::STRIKETHROUGH),
+    ("double_underline", FormatFlags::DOUBLE_UNDERLINE),
+    ("undercurl", FormatFlags::UNDERCURL),
+    ("overline", FormatFlags::OVERLINE),
+];
+
+/// Parse a color written like `light_cyan` or `#ff8000`.
+pub(crate) fn parse_color(text: &str) -> Option<Color> {
+    if let Some(&(_, color)) = COLOR_NAMES.iter().find(|(name, _)| *name == text) {
+        return Some(color);
+    }
+    let hex = text.strip_prefix('#')?;
+    if hex.len() != 6 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    let rgb = u32::from_str_radix(hex, 16).ok()?;
+    Some(Color::Rgb((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8))
+}
+
+/// Parse the name of a format, like `bold`.
+pub(crate) fn parse_format(text: &str) -> Option<FormatFlags> {
+    let flag = match text {
+        "dim" => FormatFlags::DIMMED,
+        "underlined" => FormatFlags::UNDERLINE,
+        "strike" => FormatFlags::STRIKETHROUGH,
+        "curly_underline" => FormatFlags::UNDERCURL,
+        _ => FORMAT_NAMES.iter().find(|(name, _)| *name == text)?.1,
+    };
+    Some(flag)
+}
+
+/// An error parsing a [`Style`] or [`Color`] from its text form, with the
+/// word which was not understood.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseStyleError {
+    word: String,
+}
+
+impl ParseStyleError {
+    fn new(word: &str) -> Self {
+        Self {
+            word: word.to_string(),
+        }
+    }
+
+    /// The word which was not understood.
+    pub fn word(&self) -> &str {
+        &self.word
+    }
+}
+
+impl fmt::Display for ParseStyleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown style {:?}", self.word)
+    }
+}
+
+impl error::Error for ParseStyleError {}
+
+/// Parses a color written like `red`, `light_cyan`, `bright-cyan`,
+/// `#ff8000`, `208` (a [`Color::Fixed`] index), or by its variant name, like
+/// `LightCyan`. Dashes and underscores are interchangeable, and `bright` is
+/// another name for `light`.
+///
+/// ```
+/// use nu_ansi_term::Color;
+///
+/// assert_eq!("light_cyan".parse(), Ok(Color::LightCyan));
+/// assert_eq!("bright-cyan".parse(), Ok(Color::LightCyan));
+/// assert_eq!("LightCyan".parse(), Ok(Color::LightCyan));
+/// assert_eq!("#303030".parse(), Ok(Color::Rgb(0x30, 0x30, 0x30)));
+/// assert_eq!("208".parse(), Ok(Color::Fixed(208)));
+/// assert!("mauve".parse::<Color>().is_err());
+/// ```
+impl FromStr for Color {
+    type Err = ParseStyleError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut name = String::with_capacity(text.len() + 1);
+        if text.starts_with(|c: char| c.is_ascii_uppercase()) {
+            // Variant names, such as `LightCyan`, are the names in camel case.
+            for (ix, c) in text.char_indices() {
+                if c.is_ascii_uppercase() && ix > 0 {
+                    name.push('_');
+                }
+                name.push(c.to_ascii_lowercase());
+            }
+        } else {
+            name.extend(text.chars().map(|c| if c == '-' { '_' } else { c }));
+        }
+        if let Some(rest) = name.strip_prefix("bright_") {
+            name = format!("light_{}", rest);
+        }
+        if let Some(color) = parse_color(&name) {
+            return Ok(color);
+        }
+        text.parse()
+            .map(Color::Fixed)
+            .map_err(|_| ParseStyleError::new(text))
+    }
+}
+
+/// Writes a color in the form it is parsed from: its name, like
+/// `light_cyan`, a [`Color::Fixed`] index, or `#rrggbb`.
+///
+/// ```
+/// use nu_ansi_term::Color;
+///
+/// assert_eq!(Color::LightCyan.to_string(), "light_cyan");
+/// assert_eq!(Color::Fixed(208).to_string(), "208");
+/// assert_eq!(Color::Rgb(255, 136, 0).to_string(), "#ff8800");
+/// ```
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Color::Fixed(index) => write!(f, "{}", index),
+            Color::Rgb(r, g, b) => write!(f, "#{:02x}{:02x}{:02x}", r, g, b),
+            color => {
+                let (name, _) = COLOR_NAMES
+                    .iter()
+                    .find(|(_, named)| *named == color)
+                    .expect("every other color is named");
+                f.write_str(name)
+            }
+        }
+    }
+}
+
+/// Parses a style written as words separated by whitespace, like
+/// `bold underline bright-red on blue` or `italic #ff8800`. Each word is
+/// one of:
+///
+/// * a format, such as `bold` or `underline`;
+/// * a cleared format, such as `!bold` (see [`Style::clear_formats`]);
+/// * a color (see the [`Color`] parser), which sets the foreground;
+/// * `on` followed by a color, which sets the background;
+/// * `fg:` or `bg:` immediately followed by a color, or `ul:` followed by
+///   the color of underlines;
+/// * `reset`, to reset before the style is applied;
+/// * a style written as in a [`Theme`](crate::Theme), like
+///   `light_cyan_bold` or `bg_red`.
+///
+/// ```
+/// use nu_ansi_term::{Color, Style};
+///
+/// let style: Style = "bold underline bright-red on blue".parse().unwrap();
+/// assert_eq!(style, Color::LightRed.on(Color::Blue).bold().underline());
+/// assert_eq!("italic #ff8800".parse(), Ok(Color::Rgb(255, 136, 0).italic()));
+/// assert_eq!("bold fg:red bg:#303030".parse(), Ok(Color::Red.on(Color::Rgb(0x30, 0x30, 0x30)).bold()));
+/// assert_eq!("green_italic underline".parse(), Ok(Color::Green.italic().underline()));
+/// assert_eq!("undercurl ul:red".parse(), Ok(Style::new().undercurl().underline_color(Color::Red)));
+/// assert_eq!("bold on mauve".parse::<Style>().unwrap_err().word(), "mauve");
+/// ```
+impl FromStr for Style {
+    type Err = ParseStyleError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut style = Style::new();
+        let mut words = text.split_whitespace();
+        while let Some(word) = words.next() {
+            style = if word == "on" {
+                let color = words.next().ok_or_else(|| ParseStyleError::new(word))?;
+                style.bg(color.parse()?)
+            } else if let Some(color) = word.strip_prefix("fg:") {
+                style.fg(color.parse()?)
+            } else if let Some(color) = word.strip_prefix("bg:") {
+                style.bg(color.parse()?)
+            } else if let Some(color) = word.strip_prefix("ul:") {
+                style.underline_color(color.parse()?)
+            } else if let Some(format) = word.strip_prefix('!') {
+                let flag = parse_format(format).ok_or_else(|| ParseStyleError::new(word))?;
+                style.clear_formats(flag)
+            } else if word == "reset" {
+                style.reset_before_style()
+            } else if let Some(flag) = parse_format(word) {
+                style.insert_formats(flag)
+            } else if let Ok(color) = word.parse() {
+                style.fg(color)
+            } else {
+                let theme_style = parse_style(word).ok_or_else(|| ParseStyleError::new(word))?;
+                Style {
+                    formats: style.formats | theme_style.formats,
+                    coloring: theme_style.coloring.rebase_on(style.coloring),
+                    ..style
+                }
+            };
+        }
+        Ok(style)
+    }
+}
+
+/// Writes a style in the form it is parsed from, so that it can be stored
+/// in a config file and read back. A style with no properties is written
+/// as an empty string.
+///
+/// ```
+/// use nu_ansi_term::{Color, FormatFlags, Style};
+///
+/// let style = Color::LightRed.on(Color::Blue).bold().underline();
+/// assert_eq!(style.to_string(), "bold underline light_red on blue");
+/// assert_eq!(style.to_string().parse(), Ok(style));
+///
+/// let style = Style::new().italic().clear_formats(FormatFlags::BOLD);
+/// assert_eq!(style.to_string(), "italic !bold");
+/// ```
+impl fmt::Display for Style {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut words: Vec<String> = Vec::new();
+        if self.prefix_before_reset {
+            words.push("reset".to_string());
+        }
+        for (name, flag) in FORMAT_NAMES {
+            if self.formats.contains(flag) {
+                words.push(name.to_string());
+            }
+        }
+        for (name, flag) in FORMAT_NAMES {
+            if self.cleared.contains(flag) {
+                words.push(format!("!{}", name));
+            }
+        }
+        if let Some(fg) = self.coloring.fg {
+            words.push(fg.to_string());
+        }
+        if let Some(bg) = self.coloring.bg {
+            words.push(format!("on {}", bg));
+        }
+        if let Some(underline) = self.coloring.underline {
+            words.push(format!("ul:{}", underline));
+        }
+        f.write_str(&words.join(" "))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Color::*;
+
+    #[test]
+    fn round_trip() {
+        let styles = [
+            Style::new(),
+            Red.normal(),
+            Fixed(208).on(Rgb(1, 2, 3)).dimmed().strikethrough(),
+            Default.bg().reset_before_style(),
+            Blue.blink()
+                .clear_formats(FormatFlags::UNDERLINE | FormatFlags::HIDDEN),
+            Style::new()
+                .undercurl()
+                .overline()
+                .underline_color(Fixed(9)),
+        ];
+        for style in styles {
+            let parsed: Style = style.to_string().parse().unwrap();
+            assert_eq!(parsed, style);
+            assert_eq!(parsed.cleared, style.cleared);
+            assert_eq!(parsed.prefix_before_reset, style.prefix_before_reset);
+        }
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!("bold on".parse::<Style>().unwrap_err().word(), "on");
+        assert_eq!("!red".parse::<Style>().unwrap_err().word(), "!red");
+        assert_eq!("fg:".parse::<Style>().unwrap_err().word(), "");
+        assert_eq!("256".parse::<Color>().unwrap_err().word(), "256");
+        assert_eq!(
+            "bold mauve".parse::<Style>().unwrap_err().to_string(),
+            "unknown style \"mauve\""
+        );
+    }
+}
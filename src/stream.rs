@@ -0,0 +1,140 @@
+use crate::{fmt_write, io_write, nested, Style};
+use std::{fmt, io};
+
+/// Writer which writes everything written to it in a current style, set with
+/// [`set_style`](Self::set_style), so that large amounts of styled output can
+/// be streamed without collecting it first.
+///
+/// Only the codes needed to switch from one style to the next are written,
+/// and only once text is written in the new style. Call
+/// [`finish`](Self::finish) (or [`finish_fmt`](Self::finish_fmt)) at the
+/// end to reset the terminal's style.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Write;
+/// use nu_ansi_term::AnsiWriter;
+/// use nu_ansi_term::Color::Red;
+///
+/// let mut w = AnsiWriter::new(Vec::new());
+/// w.set_style(Red.normal());
+/// write!(w, "error").unwrap();
+/// w.set_style(Red.bold());
+/// write!(w, ": no such file").unwrap();
+///
+/// let written = w.finish().unwrap();
+/// assert_eq!(written, b"\x1b[31merror\x1b[1m: no such file\x1b[0m");
+/// ```
+#[derive(Debug)]
+pub struct AnsiWriter<W> {
+    inner: W,
+    /// The style text is written in from now on.
+    style: Style,
+    /// The style the codes written so far leave the terminal in.
+    written: Style,
+}
+
+impl<W> AnsiWriter<W> {
+    /// Create a writer to `inner`, starting with no style.
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            style: Style::new(),
+            written: Style::new(),
+        }
+    }
+
+    /// Write the text written from now on in `style`.
+    pub fn set_style(&mut self, style: Style) {
+        self.style = style;
+    }
+
+    /// The style text is written in.
+    pub fn style(&self) -> Style {
+        self.style
+    }
+
+    /// Get a reference to the underlying writer.
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Consume this writer, returning the underlying writer without
+    /// resetting its style.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: io::Write> AnsiWriter<W> {
+    /// Reset the style of the underlying writer, if any was written, and
+    /// return it.
+    pub fn finish(mut self) -> io::Result<W> {
+        nested::switch(self.written, Style::new(), io_write!(&mut self.inner))?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: fmt::Write> AnsiWriter<W> {
+    /// Reset the style of the underlying [`fmt::Write`] implementor, if any
+    /// was written, and return it.
+    pub fn finish_fmt(mut self) -> Result<W, fmt::Error> {
+        nested::switch(self.written, Style::new(), fmt_write!(&mut self.inner))?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: io::Write> io::Write for AnsiWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if self.written != self.style {
+            nested::switch(self.written, self.style, io_write!(&mut self.inner))?;
+            self.written = self.style;
+        }
+        self.inner.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<W: fmt::Write> fmt::Write for AnsiWriter<W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        if s.is_empty() {
+            return Ok(());
+        }
+        if self.written != self.style {
+            nested::switch(self.written, self.style, fmt_write!(&mut self.inner))?;
+            self.written = self.style;
+        }
+        self.inner.write_str(s)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Color::*;
+    use std::fmt::Write;
+
+    #[test]
+    fn only_deltas_are_written() {
+        let mut w = AnsiWriter::new(String::new());
+        w.set_style(Blue.bold());
+        w.set_style(Green.bold());
+        w.write_str("a").unwrap();
+        w.write_str("").unwrap();
+        w.write_str("b").unwrap();
+        w.set_style(Style::new());
+        write!(w, "c").unwrap();
+        w.set_style(Red.normal());
+        assert_eq!(w.finish_fmt().unwrap(), "\x1b[1;32mab\x1b[0mc");
+
+        let w = AnsiWriter::new(Vec::new());
+        assert_eq!(w.finish().unwrap(), b"");
+    }
+}
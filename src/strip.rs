@@ -0,0 +1,219 @@
+use std::borrow::Cow;
+use std::io::{self, Write};
+
+/// Position of a [`Stripper`] within the escape sequence grammar.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum State {
+    /// Plain text, which is passed through.
+    #[default]
+    Ground,
+    /// Just after an `ESC`.
+    Escape,
+    /// Inside a two byte escape with intermediate bytes, e.g. `ESC ( B`.
+    EscapeIntermediate,
+    /// Inside a CSI sequence, such as an SGR code.
+    Csi,
+    /// Inside an OSC, DCS, SOS, PM or APC string, terminated by BEL or ST.
+    String,
+    /// Just after an `ESC` inside a string, which may start ST (`ESC \`).
+    StringEscape,
+}
+
+/// Incremental remover of ANSI escape sequences.
+///
+/// The stripper remembers where it is in an escape sequence between calls to
+/// [`strip`](Stripper::strip), so sequences split across several chunks of
+/// input (e.g. across separate `write` calls) are still removed.
+///
+/// # Examples
+///
+/// ```
+/// use nu_ansi_term::strip::Stripper;
+///
+/// let mut stripper = Stripper::new();
+/// let mut plain = Vec::new();
+/// stripper.strip(b"\x1b[1;3", &mut plain);
+/// stripper.strip(b"1mred\x1b[0m", &mut plain);
+/// assert_eq!(plain, b"red");
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Stripper {
+    state: State,
+}
+
+impl Stripper {
+    /// Create a stripper which starts outside of any escape sequence.
+    pub const fn new() -> Self {
+        Self {
+            state: State::Ground,
+        }
+    }
+
+    /// Whether the stripper is in the middle of an escape sequence.
+    pub fn in_escape(&self) -> bool {
+        self.state != State::Ground
+    }
+
+    /// Append the bytes of `input` which are not part of an escape sequence
+    /// to `out`.
+    pub fn strip(&mut self, input: &[u8], out: &mut Vec<u8>) {
+        for &byte in input {
+            self.state = match (self.state, byte) {
+                (State::Ground, 0x1B) => State::Escape,
+                (State::Ground, _) => {
+                    out.push(byte);
+                    State::Ground
+                }
+                (State::StringEscape, b'\\') => State::Ground,
+                (State::Escape | State::StringEscape, b'[') => State::Csi,
+                (State::Escape | State::StringEscape, b']' | b'P' | b'X' | b'^' | b'_') => {
+                    State::String
+                }
+                (State::Escape | State::StringEscape | State::EscapeIntermediate, 0x20..=0x2F) => {
+                    State::EscapeIntermediate
+                }
+                (State::Escape | State::StringEscape | State::EscapeIntermediate, _) => {
+                    State::Ground
+                }
+                (State::Csi, 0x40..=0x7E) => State::Ground,
+                (State::Csi, _) => State::Csi,
+                (State::String, 0x07) => State::Ground,
+                (State::String, 0x1B) => State::StringEscape,
+                (State::String, _) => State::String,
+            };
+        }
+    }
+}
+
+/// Remove every ANSI escape sequence from `input`.
+///
+/// # Examples
+///
+/// ```
+/// use nu_ansi_term::strip::strip_bytes;
+/// use nu_ansi_term::Color::Red;
+///
+/// let painted = Red.bold().paint("alert").to_string();
+/// assert_eq!(strip_bytes(painted.as_bytes()), b"alert");
+/// ```
+pub fn strip_bytes(input: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(input.len());
+    Stripper::new().strip(input, &mut out);
+    out
+}
+
+/// Remove every ANSI escape sequence (SGR codes, OSC hyperlinks and titles,
+/// and so on) from `input`, borrowing it if it contains none.
+///
+/// Anything left invalid as UTF-8 by a malformed escape is replaced with
+/// `U+FFFD`.
+///
+/// # Examples
+///
+/// ```
+/// use nu_ansi_term::strip::strip_ansi_codes;
+/// use nu_ansi_term::Color::Blue;
+///
+/// let link = Blue.paint("docs").hyperlink("https://example.com").to_string();
+/// assert_eq!(strip_ansi_codes(&link), "docs");
+/// assert_eq!(strip_ansi_codes("plain"), "plain");
+/// ```
+pub fn strip_ansi_codes(input: &str) -> Cow<'_, str> {
+    if input.contains('\x1B') {
+        Cow::Owned(String::from_utf8_lossy(&strip_bytes(input.as_bytes())).into_owned())
+    } else {
+        Cow::Borrowed(input)
+    }
+}
+
+/// Writer adapter which removes ANSI escape sequences from everything written
+/// through it before passing it on to the inner writer.
+#[derive(Debug)]
+pub struct StripWriter<W> {
+    inner: W,
+    stripper: Stripper,
+    scratch: Vec<u8>,
+}
+
+impl<W: Write> StripWriter<W> {
+    /// Create a writer which strips escapes before writing to `inner`.
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            stripper: Stripper::new(),
+            scratch: Vec::new(),
+        }
+    }
+
+    /// Get a reference to the wrapped writer.
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Get a mutable reference to the wrapped writer.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Consume this adapter, returning the wrapped writer.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: Write> Write for StripWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.scratch.clear();
+        self.stripper.strip(buf, &mut self.scratch);
+        self.inner.write_all(&self.scratch)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn strip(input: &str) -> String {
+        String::from_utf8(strip_bytes(input.as_bytes())).unwrap()
+    }
+
+    #[test]
+    fn sgr() {
+        assert_eq!(strip("\x1b[1;31mred\x1b[0m plain"), "red plain");
+    }
+
+    #[test]
+    fn osc_terminators() {
+        assert_eq!(strip("\x1b]2;title\x07text"), "text");
+        assert_eq!(
+            strip("\x1b]8;;https://example.com\x1b\\link\x1b]8;;\x1b\\"),
+            "link"
+        );
+    }
+
+    #[test]
+    fn other_escapes() {
+        assert_eq!(strip("\x1b(Bcharset\x1b7saved\x1b8"), "charsetsaved");
+        assert_eq!(strip("\x1bPdcs payload\x1b\\after"), "after");
+    }
+
+    #[test]
+    fn borrows_plain_text() {
+        assert!(matches!(strip_ansi_codes("no escapes"), Cow::Borrowed(_)));
+        assert_eq!(strip_ansi_codes("\x1b[4mü\x1b[24m"), "ü");
+    }
+
+    #[test]
+    fn split_across_writes() {
+        let mut writer = StripWriter::new(Vec::new());
+        for chunk in ["ab\x1b", "[3", "2mc\x1b]8;;url\x1b", "\\d"] {
+            writer.write_all(chunk.as_bytes()).unwrap();
+        }
+        assert_eq!(writer.into_inner(), b"abcd");
+    }
+}
@@ -4,7 +4,8 @@ use bitflags::bitflags;
 use paste::paste;
 
 bitflags! {
-    #[derive(Clone, Copy, Default, PartialEq, Eq)]
+    #[derive(Clone, Copy, Default, PartialEq, Eq, Hash)]
+    #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
     pub struct FormatFlags: u16 {
         /// Whether this style is bold.
         const BOLD = 1 << 1;
@@ -22,6 +23,13 @@ bitflags! {
         const HIDDEN = 1 << 7;
         /// Whether this style is struckthrough.
         const STRIKETHROUGH = 1 << 8;
+        /// Whether this style is underlined with a double line.
+        const DOUBLE_UNDERLINE = 1 << 9;
+        /// Whether this style is underlined with a curly line, as spelling
+        /// errors and diagnostics often are.
+        const UNDERCURL = 1 << 10;
+        /// Whether this style has a line over the text.
+        const OVERLINE = 1 << 11;
     }
 }
 
@@ -37,16 +45,24 @@ impl FormatFlags {
     }
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Default)]
+/// The colors of a [`Style`].
+///
+/// More colors may be added (as the underline color was), so this struct
+/// cannot be built with a struct expression outside of this crate; build it
+/// from [`Coloring::default`] instead.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[non_exhaustive]
 pub struct Coloring {
     pub fg: Option<Color>,
     pub bg: Option<Color>,
+    /// The color of underlines, which is the foreground color unless set.
+    pub underline: Option<Color>,
 }
 
 impl Coloring {
     /// Check if there are no colors set.
     pub const fn is_empty(&self) -> bool {
-        self.fg.is_none() && self.bg.is_none()
+        self.fg.is_none() && self.bg.is_none() && self.underline.is_none()
     }
 }
 
@@ -69,6 +85,7 @@ impl BasedOn for Coloring {
         Self {
             fg: self.fg.rebase_on(base.fg),
             bg: self.bg.rebase_on(base.bg),
+            underline: self.underline.rebase_on(base.underline),
         }
     }
 }
@@ -96,25 +113,41 @@ impl BasedOn for bool {
 /// let style = Style::new().bold().bg(Color::Black);
 /// println!("{}", style.paint("Bold on black"));
 /// ```
+///
+/// More properties may be added (as the cleared formats were), so a style
+/// cannot be built with a struct expression outside of this crate; start
+/// from [`Style::new`] instead.
 #[derive(Clone, Copy)]
+#[non_exhaustive]
 #[cfg_attr(
-    feature = "derive_serde_style",
-    derive(serde::Deserialize, serde::Serialize)
+    feature = "serde",
+    derive(serde::Deserialize, serde::Serialize),
+    serde(try_from = "serde_style::StyleRepr", into = "serde_style::SerdeStyle")
 )]
 pub struct Style {
     /// Whether this style will be prefixed with [`RESET`](crate::ansi::RESET).
     pub prefix_before_reset: bool,
     /// Flags representing whether particular formatting properties are set or not.
     pub formats: FormatFlags,
+    /// Formatting properties which are explicitly turned off, rather than
+    /// left unspecified, so that they are not inherited from a parent style
+    /// (see [`Style::inherit_from`]).
+    pub cleared: FormatFlags,
     /// Data regarding the foreground/background color applied by this style.
     pub coloring: Coloring,
 }
 
 impl BasedOn for Style {
+    /// Formats explicitly turned off in this style are not taken from `base`.
     fn rebase_on(self, base: Self) -> Self {
         Style {
             prefix_before_reset: self.prefix_before_reset.rebase_on(base.prefix_before_reset),
-            formats: self.formats.rebase_on(base.formats),
+            formats: self
+                .formats
+                .rebase_on(base.formats.difference(self.cleared)),
+            cleared: self
+                .cleared
+                .rebase_on(base.cleared.difference(self.formats)),
             coloring: self.coloring.rebase_on(base.coloring),
         }
     }
@@ -123,13 +156,58 @@ impl BasedOn for Style {
 impl PartialEq for Style {
     fn eq(&self, other: &Self) -> bool {
         self.formats.symmetric_difference(other.formats).is_empty()
+            && self.cleared == other.cleared
             && self.is_fg() == other.is_fg()
             && self.is_bg() == other.is_bg()
+            && self.is_underline_color() == other.is_underline_color()
     }
 }
 
 impl Eq for Style {}
 
+/// Hashes what [`PartialEq`] compares: the formats, cleared formats and
+/// colors of the style.
+impl Hash for Style {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.formats.hash(state);
+        self.cleared.hash(state);
+        self.coloring.hash(state);
+    }
+}
+
+/// Combines styles with [`Style::merge`]. The right-hand side may also be
+/// formats, or a color (as a foreground color).
+///
+/// ```
+/// use nu_ansi_term::{FormatFlags, Style};
+/// use nu_ansi_term::Color::{Blue, Red};
+///
+/// let base = Style::new().on(Blue).italic();
+/// assert_eq!(base | FormatFlags::BOLD | Red, Red.on(Blue).italic().bold());
+/// ```
+impl<T: Into<Style>> std::ops::BitOr<T> for Style {
+    type Output = Style;
+
+    fn bitor(self, other: T) -> Style {
+        self.merge(other.into())
+    }
+}
+
+impl<T: Into<Style>> std::ops::BitOrAssign<T> for Style {
+    fn bitor_assign(&mut self, other: T) {
+        *self = *self | other;
+    }
+}
+
+/// The same as `|`: see [`Style::merge`].
+impl<T: Into<Style>> std::ops::Add<T> for Style {
+    type Output = Style;
+
+    fn add(self, other: T) -> Style {
+        self.merge(other.into())
+    }
+}
+
 impl Default for Style {
     /// Returns a style with *no* properties set. Formatting text using this
     /// style returns the exact same text.
@@ -145,6 +223,7 @@ impl Default for Style {
         Style {
             prefix_before_reset: false,
             formats: FormatFlags::empty(),
+            cleared: FormatFlags::empty(),
             coloring: Coloring::default(),
         }
     }
@@ -236,28 +315,167 @@ impl Style {
         Style {
             prefix_before_reset: false,
             formats: FormatFlags::empty(),
-            coloring: Coloring { fg: None, bg: None },
+            cleared: FormatFlags::empty(),
+            coloring: Coloring {
+                fg: None,
+                bg: None,
+                underline: None,
+            },
         }
     }
 
+    /// Creates a Style which sets no properties, but resets all styles
+    /// previously set, i.e. which only emits [`RESET`](crate::RESET).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::Style;
+    ///
+    /// assert_eq!("\x1b[0mplain", Style::reset().paint("plain").to_string());
+    /// ```
+    pub const fn reset() -> Style {
+        Style::new().reset_before_style()
+    }
+
     /// Insert (turn on) style properties in this style that are true in given `formats`.
     pub const fn insert_formats(self, formats: FormatFlags) -> Self {
         Self {
             prefix_before_reset: self.prefix_before_reset,
             formats: self.formats.union(formats),
+            cleared: self.cleared.difference(formats),
             coloring: self.coloring,
         }
     }
 
-    /// Remove (turn off) the format properties specified by `formats`.
+    /// Remove (turn off) the format properties specified by `formats`,
+    /// leaving them unspecified.
     pub const fn remove_formats(self, formats: FormatFlags) -> Self {
         Self {
             prefix_before_reset: self.prefix_before_reset,
             formats: self.formats.intersection(formats.complement()),
+            cleared: self.cleared.difference(formats),
             coloring: self.coloring,
         }
     }
 
+    /// Explicitly turn off the format properties specified by `formats`, so
+    /// that they are not inherited from a parent style.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::FormatFlags;
+    /// use nu_ansi_term::Color::Red;
+    ///
+    /// let style = Red.bold().clear_formats(FormatFlags::BOLD);
+    /// assert_eq!(style.format_state(FormatFlags::BOLD), Some(false));
+    /// assert_eq!(style.format_state(FormatFlags::ITALIC), None);
+    /// ```
+    pub const fn clear_formats(self, formats: FormatFlags) -> Self {
+        Self {
+            prefix_before_reset: self.prefix_before_reset,
+            formats: self.formats.intersection(formats.complement()),
+            cleared: self.cleared.union(formats),
+            coloring: self.coloring,
+        }
+    }
+
+    /// Whether the format properties in `formats` are all turned on
+    /// (`Some(true)`), all explicitly turned off (`Some(false)`), or
+    /// otherwise unspecified (`None`).
+    pub const fn format_state(&self, formats: FormatFlags) -> Option<bool> {
+        if self.formats.contains(formats) {
+            Some(true)
+        } else if self.cleared.contains(formats) {
+            Some(false)
+        } else {
+            None
+        }
+    }
+
+    /// This style, with every property it leaves unspecified taken from
+    /// `parent`: formats which are neither turned on nor explicitly turned
+    /// off (see [`clear_formats`](Style::clear_formats)), and colors which
+    /// are not set. Explicitly turning a color off is done by setting it to
+    /// [`Color::Default`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::FormatFlags;
+    /// use nu_ansi_term::{Color, Style};
+    ///
+    /// let base = Color::Red.on(Color::Blue).bold().underline();
+    /// let child = Style::new()
+    ///     .italic()
+    ///     .fg(Color::Default)
+    ///     .clear_formats(FormatFlags::UNDERLINE);
+    /// assert_eq!(
+    ///     child.inherit_from(&base),
+    ///     Color::Default
+    ///         .on(Color::Blue)
+    ///         .bold()
+    ///         .italic()
+    ///         .clear_formats(FormatFlags::UNDERLINE)
+    /// );
+    /// ```
+    pub const fn inherit_from(&self, parent: &Style) -> Style {
+        let inherited = parent
+            .formats
+            .difference(self.formats)
+            .difference(self.cleared);
+        Style {
+            prefix_before_reset: self.prefix_before_reset,
+            formats: self.formats.union(inherited),
+            cleared: self.cleared.union(parent.cleared.difference(self.formats)),
+            coloring: Coloring {
+                fg: match self.coloring.fg {
+                    Some(fg) => Some(fg),
+                    None => parent.coloring.fg,
+                },
+                bg: match self.coloring.bg {
+                    Some(bg) => Some(bg),
+                    None => parent.coloring.bg,
+                },
+                underline: match self.coloring.underline {
+                    Some(underline) => Some(underline),
+                    None => parent.coloring.underline,
+                },
+            },
+        }
+    }
+
+    /// This style with `other` laid over it, as for applying per-item
+    /// overrides to a base style. Where the two conflict, `other` wins:
+    ///
+    /// - colors set in `other` replace those of this style, and colors it
+    ///   leaves unset are kept;
+    /// - formats turned on in either style are on, except those `other`
+    ///   explicitly turns off (see [`clear_formats`](Style::clear_formats));
+    /// - the reset before the style is written if either asks for it.
+    ///
+    /// This is [`other.inherit_from(self)`](Style::inherit_from), and is
+    /// also available as `self | other`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::{Color, FormatFlags, Style};
+    ///
+    /// let base = Color::White.on(Color::Black).bold();
+    /// let error = Color::Red.normal().clear_formats(FormatFlags::BOLD);
+    /// assert_eq!(
+    ///     base.merge(error),
+    ///     Color::Red.on(Color::Black).clear_formats(FormatFlags::BOLD)
+    /// );
+    /// ```
+    pub const fn merge(self, other: Style) -> Style {
+        let mut merged = other.inherit_from(&self);
+        merged.prefix_before_reset = self.prefix_before_reset || other.prefix_before_reset;
+        merged
+    }
+
     /// Create a copy of this style, and insert into it any formats
     /// that are true in `flags`.
     #[inline]
@@ -280,12 +498,47 @@ impl Style {
         BLINK,
         REVERSE,
         HIDDEN,
-        STRIKETHROUGH
+        STRIKETHROUGH,
+        DOUBLE_UNDERLINE,
+        UNDERCURL,
+        OVERLINE
     );
 
     style_color_methods!(fg);
     style_color_methods!(bg);
 
+    /// Sets the color of underlines (SGR 58) to the provided color option
+    /// (possibly `None`, in which case they take the foreground color).
+    #[inline]
+    pub const fn set_underline_color(mut self, color: Option<Color>) -> Self {
+        self.coloring.underline = color;
+        self
+    }
+
+    /// Set the color of underlines, including double and curly ones. Not
+    /// every terminal supports it; those which do not ignore it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::Style;
+    /// use nu_ansi_term::Color::{Red, Rgb};
+    ///
+    /// let error = Style::new().undercurl().underline_color(Red);
+    /// assert_eq!(error.paint("teh").to_string(), "\x1b[4:3;58;5;1mteh\x1b[0m");
+    /// assert_eq!(Style::new().underline_color(Rgb(255, 0, 0)).prefix().to_string(), "\x1b[58;2;255;0;0m");
+    /// ```
+    #[inline]
+    pub const fn underline_color(self, color: Color) -> Self {
+        self.set_underline_color(Some(color))
+    }
+
+    /// Gets the color of underlines if it is set.
+    #[inline]
+    pub const fn is_underline_color(&self) -> Option<Color> {
+        self.coloring.underline
+    }
+
     /// Return true if this `Style` requires no escape codes to be represented.
     ///
     /// # Examples
@@ -310,7 +563,7 @@ impl Style {
     /// Check if style has any coloring.
     #[inline]
     pub const fn has_color(self) -> bool {
-        self.coloring.fg.is_some() || self.coloring.bg.is_some()
+        !self.coloring.is_empty()
     }
 
     /// Get the formatting flags of this style.
@@ -328,15 +581,110 @@ impl Style {
 
     /// Create a copy of this style with the specified `coloring`.
     pub fn coloring(self, coloring: Coloring) -> Self {
-        self.set_fg(coloring.fg).set_bg(coloring.bg)
+        self.set_fg(coloring.fg)
+            .set_bg(coloring.bg)
+            .set_underline_color(coloring.underline)
+    }
+
+    /// Only the colors of this style, without any formats.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::Color::{Blue, Red};
+    ///
+    /// assert_eq!(Red.on(Blue).bold().colors_only(), Red.on(Blue));
+    /// ```
+    pub const fn colors_only(self) -> Self {
+        Self {
+            prefix_before_reset: false,
+            formats: FormatFlags::empty(),
+            cleared: FormatFlags::empty(),
+            coloring: self.coloring,
+        }
+    }
+
+    /// Only the formats of this style, including those it explicitly turns
+    /// off, without any colors.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::Style;
+    /// use nu_ansi_term::Color::{Blue, Red};
+    ///
+    /// assert_eq!(Red.on(Blue).bold().formats_only(), Style::new().bold());
+    /// ```
+    pub const fn formats_only(self) -> Self {
+        Self {
+            prefix_before_reset: false,
+            formats: self.formats,
+            cleared: self.cleared,
+            coloring: Coloring {
+                fg: None,
+                bg: None,
+                underline: None,
+            },
+        }
+    }
+
+    /// This style, with the colors set in `other` replacing its own. Its
+    /// formats are kept, so that a layer of syntax colors can be combined
+    /// with a separate layer of emphasis.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::Style;
+    /// use nu_ansi_term::Color::{Blue, Green, Yellow};
+    ///
+    /// let emphasis = Style::new().bold().on(Yellow);
+    /// assert_eq!(emphasis.apply_colors_from(Green.italic()), Green.on(Yellow).bold());
+    /// assert_eq!(emphasis.apply_colors_from(Blue.on(Green)), Blue.on(Green).bold());
+    /// ```
+    pub const fn apply_colors_from(self, other: Self) -> Self {
+        Self {
+            coloring: Coloring {
+                fg: match other.coloring.fg {
+                    Some(fg) => Some(fg),
+                    None => self.coloring.fg,
+                },
+                bg: match other.coloring.bg {
+                    Some(bg) => Some(bg),
+                    None => self.coloring.bg,
+                },
+                underline: match other.coloring.underline {
+                    Some(underline) => Some(underline),
+                    None => self.coloring.underline,
+                },
+            },
+            ..self
+        }
     }
 
     /// Create a copy of this style, with the styling properties updated using
-    /// the other style.
+    /// the other style. Formats explicitly turned off in this style stay off.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::{Color, FormatFlags, Style};
+    ///
+    /// let keyword = Style::new().clear_formats(FormatFlags::ITALIC);
+    /// let comment = Color::DarkGray.italic().bold();
+    /// assert_eq!(
+    ///     keyword.update_with(comment),
+    ///     Color::DarkGray.bold().clear_formats(FormatFlags::ITALIC)
+    /// );
+    /// assert_eq!(keyword.update_with(comment).format_state(FormatFlags::ITALIC), Some(false));
+    /// ```
     pub const fn update_with(self, other: Self) -> Self {
         Self {
             prefix_before_reset: !self.prefix_before_reset && other.prefix_before_reset,
-            formats: self.formats.set_flags(other.formats),
+            formats: self
+                .formats
+                .set_flags(other.formats.difference(self.cleared)),
+            cleared: self.cleared.union(other.cleared.difference(self.formats)),
             coloring: Coloring {
                 fg: if self.coloring.fg.is_none() {
                     other.coloring.fg
@@ -348,10 +696,36 @@ impl Style {
                 } else {
                     self.coloring.bg
                 },
+                underline: if self.coloring.underline.is_none() {
+                    other.coloring.underline
+                } else {
+                    self.coloring.underline
+                },
             },
         }
     }
 
+    /// This style if `condition` holds, or no style at all otherwise, for
+    /// styling output only when colors are wanted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::Style;
+    /// use nu_ansi_term::Color::Red;
+    ///
+    /// let use_color = false;
+    /// assert_eq!(Red.bold().when(use_color), Style::new());
+    /// assert_eq!(Red.bold().when(use_color).paint("x").to_string(), "x");
+    /// ```
+    pub const fn when(self, condition: bool) -> Style {
+        if condition {
+            self
+        } else {
+            Style::new()
+        }
+    }
+
     /// Return whether or not `reset_before_style` is set.
     pub const fn is_reset_before_style(&self) -> bool {
         self.prefix_before_reset
@@ -384,11 +758,8 @@ impl Style {
 ///
 /// These use the standard numeric sequences.
 /// See <http://invisible-island.net/xterm/ctlseqs/ctlseqs.html>
-#[derive(Eq, PartialEq, Clone, Copy, Debug, Default)]
-#[cfg_attr(
-    feature = "derive_serde_style",
-    derive(serde::Deserialize, serde::Serialize)
-)]
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum Color {
     /// Color #0 (foreground code `30`, background code `40`).
     ///
@@ -556,7 +927,10 @@ impl Color {
         BLINK,
         REVERSE,
         HIDDEN,
-        STRIKETHROUGH
+        STRIKETHROUGH,
+        DOUBLE_UNDERLINE,
+        UNDERCURL,
+        OVERLINE
     );
 }
 
@@ -576,10 +950,208 @@ impl From<Color> for Style {
     }
 }
 
+impl From<FormatFlags> for Style {
+    /// A style with the given formats turned on, and nothing else set.
+    fn from(formats: FormatFlags) -> Style {
+        Style::new().insert_formats(formats)
+    }
+}
+
+/// The serialized forms of [`Style`] and [`Color`]. Both are written in
+/// their structured form, and read from either that or the compact form
+/// parsed by their [`FromStr`](std::str::FromStr) impls, such as
+/// `"bold fg:red"` or `"#303030"`.
+#[cfg(feature = "serde")]
+mod serde_style {
+    use super::{Color, Coloring, FormatFlags, Style};
+    use crate::ParseStyleError;
+    use serde::de::{self, Deserializer, MapAccess, Visitor};
+    use serde::Deserialize;
+    use std::fmt;
+
+    /// A [`Style`] with one field per property. Missing fields are unset.
+    #[derive(Default, serde::Deserialize, serde::Serialize)]
+    #[serde(default)]
+    pub(super) struct SerdeStyle {
+        foreground: Option<Color>,
+        background: Option<Color>,
+        is_bold: bool,
+        is_dimmed: bool,
+        is_italic: bool,
+        is_underline: bool,
+        is_blink: bool,
+        is_reverse: bool,
+        is_hidden: bool,
+        is_strikethrough: bool,
+        reset_before_style: bool,
+        #[serde(skip_serializing_if = "FormatFlags::is_empty")]
+        cleared: FormatFlags,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        underline_color: Option<Color>,
+        #[serde(skip_serializing_if = "is_false")]
+        is_double_underline: bool,
+        #[serde(skip_serializing_if = "is_false")]
+        is_undercurl: bool,
+        #[serde(skip_serializing_if = "is_false")]
+        is_overline: bool,
+    }
+
+    /// The extended properties are only written when they are set, so that
+    /// styles without them are written as they always were.
+    fn is_false(value: &bool) -> bool {
+        !value
+    }
+
+    /// A [`Style`] as read: either compact or structured.
+    #[derive(serde::Deserialize)]
+    #[serde(untagged)]
+    pub(super) enum StyleRepr {
+        Compact(String),
+        Fields(SerdeStyle),
+    }
+
+    impl From<Style> for SerdeStyle {
+        fn from(style: Style) -> Self {
+            Self {
+                foreground: style.coloring.fg,
+                background: style.coloring.bg,
+                is_bold: style.is_bold(),
+                is_dimmed: style.is_dimmed(),
+                is_italic: style.is_italic(),
+                is_underline: style.is_underline(),
+                is_blink: style.is_blink(),
+                is_reverse: style.is_reverse(),
+                is_hidden: style.is_hidden(),
+                is_strikethrough: style.is_strikethrough(),
+                reset_before_style: style.prefix_before_reset,
+                cleared: style.cleared,
+                underline_color: style.coloring.underline,
+                is_double_underline: style.is_double_underline(),
+                is_undercurl: style.is_undercurl(),
+                is_overline: style.is_overline(),
+            }
+        }
+    }
+
+    impl From<SerdeStyle> for Style {
+        fn from(style: SerdeStyle) -> Self {
+            let mut formats = FormatFlags::empty();
+            for (set, flag) in [
+                (style.is_bold, FormatFlags::BOLD),
+                (style.is_dimmed, FormatFlags::DIMMED),
+                (style.is_italic, FormatFlags::ITALIC),
+                (style.is_underline, FormatFlags::UNDERLINE),
+                (style.is_blink, FormatFlags::BLINK),
+                (style.is_reverse, FormatFlags::REVERSE),
+                (style.is_hidden, FormatFlags::HIDDEN),
+                (style.is_strikethrough, FormatFlags::STRIKETHROUGH),
+                (style.is_double_underline, FormatFlags::DOUBLE_UNDERLINE),
+                (style.is_undercurl, FormatFlags::UNDERCURL),
+                (style.is_overline, FormatFlags::OVERLINE),
+            ] {
+                formats.set(flag, set);
+            }
+            Style {
+                prefix_before_reset: style.reset_before_style,
+                formats,
+                cleared: style.cleared.difference(formats),
+                coloring: Coloring {
+                    fg: style.foreground,
+                    bg: style.background,
+                    underline: style.underline_color,
+                },
+            }
+        }
+    }
+
+    impl TryFrom<StyleRepr> for Style {
+        type Error = ParseStyleError;
+
+        fn try_from(repr: StyleRepr) -> Result<Self, Self::Error> {
+            match repr {
+                StyleRepr::Compact(text) => text.parse(),
+                StyleRepr::Fields(fields) => Ok(fields.into()),
+            }
+        }
+    }
+
+    /// Reads the derived form of a [`Color`] (`"Red"`, `{"Fixed": 208}`,
+    /// `{"Rgb": [255, 128, 0]}`) as well as compact strings and bare
+    /// [`Color::Fixed`] indices.
+    struct ColorVisitor;
+
+    impl<'de> Visitor<'de> for ColorVisitor {
+        type Value = Color;
+
+        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("a color name, a color index, or a Fixed or Rgb color")
+        }
+
+        fn visit_str<E: de::Error>(self, text: &str) -> Result<Color, E> {
+            text.parse().map_err(E::custom)
+        }
+
+        fn visit_u64<E: de::Error>(self, index: u64) -> Result<Color, E> {
+            u8::try_from(index)
+                .map(Color::Fixed)
+                .map_err(|_| E::invalid_value(de::Unexpected::Unsigned(index), &self))
+        }
+
+        fn visit_i64<E: de::Error>(self, index: i64) -> Result<Color, E> {
+            u8::try_from(index)
+                .map(Color::Fixed)
+                .map_err(|_| E::invalid_value(de::Unexpected::Signed(index), &self))
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Color, A::Error> {
+            let variant: String = map
+                .next_key()?
+                .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+            let color = match variant.as_str() {
+                "Fixed" => Color::Fixed(map.next_value()?),
+                "Rgb" => {
+                    let (r, g, b) = map.next_value()?;
+                    Color::Rgb(r, g, b)
+                }
+                _ => return Err(de::Error::unknown_variant(&variant, &["Fixed", "Rgb"])),
+            };
+            match map.next_key::<de::IgnoredAny>()? {
+                Some(_) => Err(de::Error::invalid_length(2, &self)),
+                None => Ok(color),
+            }
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Color {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            deserializer.deserialize_any(ColorVisitor)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Color, FormatFlags, Style};
+    use std::collections::HashSet;
+
+    #[test]
+    fn cleared_formats_are_compared_and_hashed() {
+        let plain = Style::new();
+        let unbold = Style::new().clear_formats(FormatFlags::BOLD);
+        assert_ne!(plain, unbold);
+        assert_eq!(HashSet::from([plain, unbold]).len(), 2);
+
+        let parent = Color::Red.bold();
+        assert_ne!(plain.inherit_from(&parent), unbold.inherit_from(&parent));
+        // Both are written the same, so there is nothing to switch.
+        assert_eq!(plain.compute_delta(unbold).to_string(), "");
+    }
+}
+
 #[cfg(test)]
-#[cfg(feature = "derive_serde_style")]
+#[cfg(feature = "serde")]
 mod serde_json_tests {
-    use super::{Color, Style};
+    use super::{Color, FormatFlags, Style};
 
     #[test]
     fn color_serialization() {
@@ -619,4 +1191,55 @@ mod serde_json_tests {
 
         assert_eq!(serde_json::to_string(&style).unwrap(), "{\"foreground\":null,\"background\":null,\"is_bold\":false,\"is_dimmed\":false,\"is_italic\":false,\"is_underline\":false,\"is_blink\":false,\"is_reverse\":false,\"is_hidden\":false,\"is_strikethrough\":false,\"reset_before_style\":false}".to_string());
     }
+
+    #[test]
+    fn style_round_trip() {
+        let style = Color::Red.on(Color::Fixed(17)).bold().underline();
+        let serialized = serde_json::to_string(&style).unwrap();
+        let deserialized: Style = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(style, deserialized);
+        assert_eq!(style.is_fg(), deserialized.is_fg());
+        assert_eq!(style.is_bg(), deserialized.is_bg());
+    }
+
+    #[test]
+    fn compact_forms() {
+        let colors: Vec<Color> =
+            serde_json::from_str(r##"["red", "LightCyan", "#303030", 208, {"Fixed": 17}]"##)
+                .unwrap();
+        assert_eq!(
+            colors,
+            [
+                Color::Red,
+                Color::LightCyan,
+                Color::Rgb(0x30, 0x30, 0x30),
+                Color::Fixed(208),
+                Color::Fixed(17)
+            ]
+        );
+        assert!(serde_json::from_str::<Color>("\"mauve\"").is_err());
+        assert!(serde_json::from_str::<Color>("256").is_err());
+
+        let style: Style = serde_json::from_str(r##""bold fg:red bg:#303030""##).unwrap();
+        assert_eq!(style, Color::Red.on(Color::Rgb(0x30, 0x30, 0x30)).bold());
+        let style: Style =
+            serde_json::from_str(r#"{"foreground": "green", "is_italic": true}"#).unwrap();
+        assert_eq!(style, Color::Green.italic());
+        assert!(serde_json::from_str::<Style>("\"bold fg:mauve\"").is_err());
+    }
+
+    #[test]
+    fn cleared_formats() {
+        let style = Style::new().underline().clear_formats(FormatFlags::BOLD);
+        let serialized = serde_json::to_string(&style).unwrap();
+        assert!(serialized.contains(r#""cleared":"BOLD""#));
+        let deserialized: Style = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized.cleared, FormatFlags::BOLD);
+        assert_eq!(
+            serde_json::from_str::<Style>("\"underline !bold\"")
+                .unwrap()
+                .cleared,
+            FormatFlags::BOLD
+        );
+    }
 }
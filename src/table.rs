@@ -0,0 +1,667 @@
+use crate::prompt::Alignment;
+use crate::width::WidthPolicy;
+use crate::{heatmap_style, AnsiString, AnsiStrings, MultiGradient, NumberStyle, Style};
+
+/// A cell of a [`Table`], which may span several columns and rows.
+#[derive(Clone, Debug)]
+pub struct TableCell<'a> {
+    content: Vec<AnsiString<'a>>,
+    col_span: usize,
+    row_span: usize,
+    alignment: Alignment,
+    fill: Option<Style>,
+}
+
+impl<'a> TableCell<'a> {
+    /// Create a left aligned cell showing `content`, spanning one column
+    /// and one row.
+    pub fn new(content: impl Into<AnsiString<'a>>) -> Self {
+        Self::from_segments([content.into()])
+    }
+
+    /// Create a cell showing `segments` one after the other.
+    pub fn from_segments(segments: impl IntoIterator<Item = AnsiString<'a>>) -> Self {
+        Self {
+            content: segments.into_iter().collect(),
+            col_span: 1,
+            row_span: 1,
+            alignment: Alignment::Left,
+            fill: None,
+        }
+    }
+
+    /// Create a cell with no content.
+    pub fn empty() -> Self {
+        Self::from_segments([])
+    }
+
+    /// Span `columns` columns (at least one).
+    pub const fn col_span(mut self, columns: usize) -> Self {
+        self.col_span = if columns == 0 { 1 } else { columns };
+        self
+    }
+
+    /// Span `rows` rows (at least one). The content is shown in the first
+    /// of them, and the rest are left blank.
+    pub const fn row_span(mut self, rows: usize) -> Self {
+        self.row_span = if rows == 0 { 1 } else { rows };
+        self
+    }
+
+    /// Place the content within the cell according to `alignment`.
+    pub const fn align(mut self, alignment: Alignment) -> Self {
+        self.alignment = alignment;
+        self
+    }
+
+    /// Paint the whole cell in `style`, from edge to edge, e.g. to give it a
+    /// background. The content keeps its own style on top of this one.
+    pub const fn fill(mut self, style: Style) -> Self {
+        self.fill = Some(style);
+        self
+    }
+
+    /// The cell's content, rebased on its fill.
+    fn segments(&self) -> impl Iterator<Item = AnsiString<'a>> + '_ {
+        self.content.iter().map(move |segment| match self.fill {
+            Some(fill) => segment.clone().rebase_on(fill),
+            None => segment.clone(),
+        })
+    }
+}
+
+impl<'a, T: Into<AnsiString<'a>>> From<T> for TableCell<'a> {
+    fn from(content: T) -> Self {
+        Self::new(content)
+    }
+}
+
+/// A cell placed at its first row and column.
+struct Placed<'t, 'a> {
+    row: usize,
+    col: usize,
+    cell: &'t TableCell<'a>,
+}
+
+/// Renderer for tables whose columns are as wide as their widest cells.
+/// Cells are placed in the first free column of their row, after those
+/// spanning down from the rows above, like in an HTML table.
+///
+/// The first rows may be marked as a header, which stays in place while
+/// the rest of the table is scrolled (see [`render_window`]).
+///
+/// # Examples
+///
+/// ```
+/// use nu_ansi_term::{Alignment, Table, TableCell};
+/// use nu_ansi_term::utils::unstyle;
+/// use nu_ansi_term::Color::{Blue, Green};
+///
+/// let table = Table::new()
+///     .header_rows(1)
+///     .row([
+///         TableCell::new(Blue.bold().paint("PID")),
+///         TableCell::new(Blue.bold().paint("COMMAND")),
+///     ])
+///     .row([
+///         TableCell::new("1").align(Alignment::Right),
+///         TableCell::new(Green.paint("init")),
+///     ])
+///     .row([TableCell::new("stopped").col_span(2)]);
+/// assert_eq!(
+///     unstyle(&table.render()),
+///     "PID COMMAND\n  1 init   \nstopped    "
+/// );
+/// ```
+///
+/// [`render_window`]: Table::render_window
+#[derive(Clone, Debug)]
+pub struct Table<'a> {
+    rows: Vec<Vec<TableCell<'a>>>,
+    header_rows: usize,
+    separator: AnsiString<'a>,
+    width_policy: WidthPolicy,
+}
+
+impl<'a> Default for Table<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> Table<'a> {
+    /// Create an empty table, whose columns are separated by an unstyled
+    /// space.
+    pub fn new() -> Self {
+        Self {
+            rows: Vec::new(),
+            header_rows: 0,
+            separator: AnsiString::from(" "),
+            width_policy: WidthPolicy::new(),
+        }
+    }
+
+    /// Append a row of cells.
+    pub fn row<C: Into<TableCell<'a>>>(mut self, cells: impl IntoIterator<Item = C>) -> Self {
+        self.rows.push(cells.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Treat the first `rows` rows as a header, which
+    /// [`render_window`](Self::render_window) always shows.
+    pub const fn header_rows(mut self, rows: usize) -> Self {
+        self.header_rows = rows;
+        self
+    }
+
+    /// Place `separator` between adjacent columns.
+    pub fn separator(mut self, separator: impl Into<AnsiString<'a>>) -> Self {
+        self.separator = separator.into();
+        self
+    }
+
+    /// Measure cells with `policy`.
+    pub const fn width_policy(mut self, policy: WidthPolicy) -> Self {
+        self.width_policy = policy;
+        self
+    }
+
+    /// The cells in the positions they are placed at, and which of them
+    /// covers each row and column of the table.
+    fn place(&self) -> (Vec<Vec<Option<usize>>>, Vec<Placed<'_, 'a>>) {
+        let mut grid: Vec<Vec<Option<usize>>> = Vec::new();
+        let mut placed = Vec::new();
+        for (row, cells) in self.rows.iter().enumerate() {
+            let mut col = 0;
+            for cell in cells {
+                while grid
+                    .get(row)
+                    .and_then(|line| line.get(col))
+                    .map_or(false, Option::is_some)
+                {
+                    col += 1;
+                }
+                for line in row..row + cell.row_span {
+                    if grid.len() <= line {
+                        grid.resize_with(line + 1, Vec::new);
+                    }
+                    let line = &mut grid[line];
+                    if line.len() < col + cell.col_span {
+                        line.resize(col + cell.col_span, None);
+                    }
+                    for slot in &mut line[col..col + cell.col_span] {
+                        *slot = Some(placed.len());
+                    }
+                }
+                placed.push(Placed { row, col, cell });
+                col += cell.col_span;
+            }
+        }
+        grid.resize_with(grid.len().max(self.rows.len()), Vec::new);
+        (grid, placed)
+    }
+
+    /// The width of each column: enough for the cells in it, and for those
+    /// spanning it and its neighbours.
+    fn column_widths(&self, placed: &[Placed<'_, 'a>]) -> Vec<usize> {
+        let columns = placed
+            .iter()
+            .map(|p| p.col + p.cell.col_span)
+            .max()
+            .unwrap_or(0);
+        let separator_width = self.width_policy.segment_width(&self.separator);
+        let mut widths = vec![0; columns];
+        let mut spanning: Vec<&Placed<'_, 'a>> = Vec::new();
+        for p in placed {
+            if p.cell.col_span == 1 {
+                widths[p.col] = widths[p.col].max(self.content_width(p.cell));
+            } else {
+                spanning.push(p);
+            }
+        }
+        // Narrow spans first, so that wide ones share the space they add.
+        spanning.sort_by_key(|p| p.cell.col_span);
+        for p in spanning {
+            let span = p.col..p.col + p.cell.col_span;
+            let have = widths[span.clone()].iter().sum::<usize>()
+                + separator_width * (p.cell.col_span - 1);
+            let missing = self.content_width(p.cell).saturating_sub(have);
+            for (ix, width) in widths[span].iter_mut().enumerate() {
+                *width += missing / p.cell.col_span + usize::from(ix < missing % p.cell.col_span);
+            }
+        }
+        widths
+    }
+
+    fn content_width(&self, cell: &TableCell<'a>) -> usize {
+        cell.content
+            .iter()
+            .map(|segment| self.width_policy.segment_width(segment))
+            .sum()
+    }
+
+    /// Render every row of the table as a line.
+    pub fn render_lines(&self) -> Vec<AnsiStrings<'a>> {
+        let (grid, placed) = self.place();
+        let widths = self.column_widths(&placed);
+        let separator_width = self.width_policy.segment_width(&self.separator);
+        let blank =
+            |width: usize, style: Option<Style>| style.unwrap_or_default().paint(" ".repeat(width));
+
+        grid.iter()
+            .enumerate()
+            .map(|(row, line)| {
+                let mut segments = Vec::new();
+                let mut col = 0;
+                while col < widths.len() {
+                    if col > 0 {
+                        segments.push(self.separator.clone());
+                    }
+                    let p = match line.get(col).copied().flatten() {
+                        Some(ix) => &placed[ix],
+                        None => {
+                            segments.push(blank(widths[col], None));
+                            col += 1;
+                            continue;
+                        }
+                    };
+                    let span = p.cell.col_span;
+                    let width = widths[col..col + span].iter().sum::<usize>()
+                        + separator_width * (span - 1);
+                    if p.row == row {
+                        let padding = width.saturating_sub(self.content_width(p.cell));
+                        let before = match p.cell.alignment {
+                            Alignment::Left => 0,
+                            Alignment::Center => padding / 2,
+                            Alignment::Right => padding,
+                        };
+                        if before > 0 {
+                            segments.push(blank(before, p.cell.fill));
+                        }
+                        segments.extend(p.cell.segments());
+                        if padding > before {
+                            segments.push(blank(padding - before, p.cell.fill));
+                        }
+                    } else {
+                        segments.push(blank(width, p.cell.fill));
+                    }
+                    col += span;
+                }
+                AnsiStrings(segments)
+            })
+            .collect()
+    }
+
+    /// Render the whole table, with lines separated by `\n`.
+    pub fn render(&self) -> AnsiStrings<'a> {
+        let mut segments = Vec::new();
+        for (ix, line) in self.render_lines().into_iter().enumerate() {
+            if ix > 0 {
+                segments.push(AnsiString::from("\n"));
+            }
+            segments.extend(line.iter().cloned());
+        }
+        AnsiStrings(segments)
+    }
+
+    /// Render the lines shown in a view `height` lines tall whose body is
+    /// scrolled down by `offset` rows: the header rows, which stay frozen in
+    /// place, followed by as many of the other rows as fit, starting from
+    /// the `offset`th.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::{Style, Table};
+    /// use nu_ansi_term::utils::unstyle;
+    ///
+    /// let mut table = Table::new().header_rows(1).row(["N"]);
+    /// for n in 1..=5 {
+    ///     table = table.row([Style::new().paint(n.to_string())]);
+    /// }
+    /// let window: Vec<String> = table.render_window(2, 3).iter().map(unstyle).collect();
+    /// assert_eq!(window, ["N", "3", "4"]);
+    /// ```
+    pub fn render_window(&self, offset: usize, height: usize) -> Vec<AnsiStrings<'a>> {
+        let mut lines = self.render_lines();
+        let header = self.header_rows.min(lines.len()).min(height);
+        let body: Vec<AnsiStrings<'a>> = lines
+            .drain(header..)
+            .skip(offset)
+            .take(height - header)
+            .collect();
+        lines.extend(body);
+        lines
+    }
+
+    /// The number of rows the body of the table has below its header,
+    /// including those only reached by cells spanning down from the last
+    /// row, e.g. to limit how far [`render_window`](Self::render_window) is
+    /// scrolled.
+    pub fn body_rows(&self) -> usize {
+        self.place().0.len().saturating_sub(self.header_rows)
+    }
+}
+
+/// How the cells of a column of records are styled.
+#[derive(Clone, Debug)]
+enum ColumnRule {
+    /// Every cell in the same style.
+    Style(Style),
+    /// Numbers in a style chosen from their value, and other cells as they
+    /// are.
+    Numbers(NumberStyle),
+    /// Numbers with a background from a ramp, by where their value falls
+    /// between the least and greatest numbers of the column.
+    Heatmap(MultiGradient),
+}
+
+/// Rules for turning records of text, such as the rows read by a CSV
+/// parser or the lines of a TSV file, into a [`Table`].
+///
+/// Columns whose cells are all numbers (apart from the header, and empty
+/// cells) are right aligned, so that their digits line up.
+///
+/// # Examples
+///
+/// ```
+/// use nu_ansi_term::{NumberStyle, RecordStyles};
+/// use nu_ansi_term::utils::unstyle;
+/// use nu_ansi_term::Color::Cyan;
+///
+/// let tsv = "name\tsize\tchange\nbuild.log\t1,024\t-3\nREADME\t12\t+5";
+/// let table = RecordStyles::new()
+///     .header(Cyan.bold())
+///     .numbers(2, NumberStyle::new())
+///     .table(tsv.lines().map(|line| line.split('\t')));
+/// assert_eq!(
+///     unstyle(&table.render()),
+///     "name       size change\nbuild.log 1,024     -3\nREADME       12     +5"
+/// );
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct RecordStyles {
+    header: Option<Style>,
+    columns: Vec<(usize, ColumnRule)>,
+}
+
+impl RecordStyles {
+    /// Create rules which leave every cell unstyled, and treat the first
+    /// record as an ordinary row.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Treat the first record as a header, painted in `style`.
+    pub fn header(mut self, style: Style) -> Self {
+        self.header = Some(style);
+        self
+    }
+
+    /// Paint the cells of the column at `index` (from zero) in `style`.
+    pub fn column(mut self, index: usize, style: Style) -> Self {
+        self.columns.retain(|(other, _)| *other != index);
+        self.columns.push((index, ColumnRule::Style(style)));
+        self
+    }
+
+    /// Paint the numbers in the column at `index` (from zero) in the styles
+    /// `numbers` chooses for their values. They are shown as written.
+    pub fn numbers(mut self, index: usize, numbers: NumberStyle) -> Self {
+        self.columns.retain(|(other, _)| *other != index);
+        self.columns.push((index, ColumnRule::Numbers(numbers)));
+        self
+    }
+
+    /// Paint the numbers in the column at `index` (from zero) as a heatmap:
+    /// each has the background `ramp` gives its value, from the start of
+    /// `ramp` for the least number in the column to its end for the
+    /// greatest (see [`heatmap_style`]).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::{MultiGradient, RecordStyles, Rgb};
+    ///
+    /// let ramp = MultiGradient::new([Rgb::new(0, 0, 139), Rgb::new(255, 255, 0)]);
+    /// let table = RecordStyles::new()
+    ///     .heatmap(1, ramp)
+    ///     .table([["cpu0", "12"], ["cpu1", "97"]]);
+    /// println!("{}", table.render());
+    /// ```
+    pub fn heatmap(mut self, index: usize, ramp: MultiGradient) -> Self {
+        self.columns.retain(|(other, _)| *other != index);
+        self.columns.push((index, ColumnRule::Heatmap(ramp)));
+        self
+    }
+
+    /// Build a table from `records`, each of which is a row of fields.
+    pub fn table<R, F>(&self, records: impl IntoIterator<Item = R>) -> Table<'static>
+    where
+        R: IntoIterator<Item = F>,
+        F: AsRef<str>,
+    {
+        let records: Vec<Vec<String>> = records
+            .into_iter()
+            .map(|record| {
+                record
+                    .into_iter()
+                    .map(|field| field.as_ref().to_string())
+                    .collect()
+            })
+            .collect();
+        let header_rows = usize::from(self.header.is_some() && !records.is_empty());
+        let body = &records[header_rows..];
+        let columns = records.iter().map(Vec::len).max().unwrap_or(0);
+        let numeric: Vec<bool> = (0..columns)
+            .map(|col| {
+                let mut cells = body
+                    .iter()
+                    .filter_map(|record| record.get(col))
+                    .filter(|field| !field.trim().is_empty())
+                    .peekable();
+                cells.peek().is_some() && cells.all(|field| parse_number(field).is_some())
+            })
+            .collect();
+        let ranges: Vec<(f64, f64)> = (0..columns)
+            .map(|col| {
+                body.iter()
+                    .filter_map(|record| parse_number(record.get(col)?))
+                    .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), n| {
+                        (min.min(n), max.max(n))
+                    })
+            })
+            .collect();
+
+        let mut table = Table::new().header_rows(header_rows);
+        for (row, record) in records.into_iter().enumerate() {
+            let cells = record.into_iter().enumerate().map(|(col, field)| {
+                let style = match (self.header.filter(|_| row < header_rows), self.rule(col)) {
+                    (Some(header), _) => header,
+                    (None, Some(ColumnRule::Style(style))) => *style,
+                    (None, Some(ColumnRule::Numbers(numbers))) => {
+                        parse_number(&field).map_or_else(Style::new, |n| numbers.style_for(n))
+                    }
+                    (None, Some(ColumnRule::Heatmap(ramp))) => {
+                        let (min, max) = ranges[col];
+                        parse_number(&field)
+                            .map_or_else(Style::new, |n| heatmap_style(n, min, max, ramp))
+                    }
+                    (None, None) => Style::new(),
+                };
+                let cell = TableCell::new(style.paint(field));
+                if numeric[col] {
+                    cell.align(Alignment::Right)
+                } else {
+                    cell
+                }
+            });
+            table = table.row(cells);
+        }
+        table
+    }
+
+    fn rule(&self, col: usize) -> Option<&ColumnRule> {
+        self.columns
+            .iter()
+            .find(|(index, _)| *index == col)
+            .map(|(_, rule)| rule)
+    }
+}
+
+/// The value of `field` if it is a number, like `-12`, `+3.5`, `1,024`,
+/// `1_000` or `50%`.
+fn parse_number(field: &str) -> Option<f64> {
+    let field = field.trim();
+    let field = field.strip_suffix('%').unwrap_or(field);
+    let digits = field.trim_start_matches(['+', '-'].as_ref());
+    if !digits.starts_with(|c: char| c.is_ascii_digit() || c == '.') {
+        // Rules out `inf` and `NaN`, which Rust also parses.
+        return None;
+    }
+    field.replace([',', '_'].as_ref(), "").parse().ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::unstyle;
+    use crate::Color::*;
+
+    fn lines(table: &Table<'_>) -> Vec<String> {
+        table.render_lines().iter().map(unstyle).collect()
+    }
+
+    #[test]
+    fn spans() {
+        let table = Table::new()
+            .separator("|")
+            .row([
+                TableCell::new("a").row_span(2),
+                TableCell::new("b"),
+                "c".into(),
+            ])
+            .row([TableCell::new("wide cell").col_span(2)])
+            .row(["d", "e", "f"]);
+        assert_eq!(lines(&table), ["a|b   |c   ", " |wide cell", "d|e   |f   "]);
+        assert_eq!(table.body_rows(), 3);
+
+        let table = Table::new().row([TableCell::new("x").row_span(3)]);
+        assert_eq!(lines(&table), ["x", " ", " "]);
+    }
+
+    #[test]
+    fn fill_reaches_the_edges() {
+        let table = Table::new()
+            .row([TableCell::new(Red.paint("ab"))
+                .fill(Style::new().on(Blue))
+                .align(Alignment::Center)
+                .row_span(2)])
+            .row::<TableCell<'_>>([])
+            .row(["long"]);
+        let rendered = table.render_lines();
+        assert_eq!(
+            rendered[0].to_string(),
+            AnsiStrings([
+                Style::new().on(Blue).paint(" "),
+                Red.on(Blue).paint("ab"),
+                Style::new().on(Blue).paint(" "),
+            ])
+            .to_string()
+        );
+        assert_eq!(
+            rendered[1].to_string(),
+            Style::new().on(Blue).paint("    ").to_string()
+        );
+    }
+
+    #[test]
+    fn frozen_header() {
+        let table = Table::new()
+            .header_rows(2)
+            .row(["h1"])
+            .row(["h2"])
+            .row(["a"])
+            .row(["b"]);
+        let window = |offset, height| -> Vec<String> {
+            table
+                .render_window(offset, height)
+                .iter()
+                .map(unstyle)
+                .collect()
+        };
+        assert_eq!(window(1, 4), ["h1", "h2", "b "]);
+        assert_eq!(window(0, 1), ["h1"]);
+        assert_eq!(window(5, 3), ["h1", "h2"]);
+    }
+
+    #[test]
+    fn numbers() {
+        for (field, value) in [
+            ("-12", Some(-12.0)),
+            ("+3.5", Some(3.5)),
+            (" 1,024 ", Some(1024.0)),
+            ("50%", Some(50.0)),
+            (".5", Some(0.5)),
+            ("inf", None),
+            ("NaN", None),
+            ("1.2.3", None),
+            ("", None),
+        ] {
+            assert_eq!(parse_number(field), value, "{:?}", field);
+        }
+    }
+
+    #[test]
+    fn records() {
+        let records = [
+            vec!["id", "state"],
+            vec!["7", "ok"],
+            vec!["12", ""],
+            vec![""],
+        ];
+        let table = RecordStyles::new()
+            .header(Style::new().bold())
+            .column(1, Green.normal())
+            .table(records);
+        assert_eq!(
+            lines(&table),
+            ["id state", " 7 ok   ", "12      ", "        "]
+        );
+        assert_eq!(
+            table.render_lines()[1].iter().nth(3).unwrap().style_ref(),
+            &Green.normal()
+        );
+        assert_eq!(
+            RecordStyles::new()
+                .header(Style::new())
+                .table(Vec::<Vec<&str>>::new())
+                .body_rows(),
+            0
+        );
+    }
+
+    #[test]
+    fn heatmap() {
+        let (cold, hot) = (crate::Rgb::gray(0), crate::Rgb::gray(255));
+        let table = RecordStyles::new()
+            .header(Style::new())
+            .heatmap(0, MultiGradient::new([cold, hot]))
+            .table([["n"], ["10"], ["-"], ["20"], ["15"]]);
+        let backgrounds: Vec<_> = table
+            .render_lines()
+            .iter()
+            .skip(1)
+            .map(|line| line.iter().next().unwrap().style_ref().is_bg())
+            .collect();
+        assert_eq!(
+            backgrounds,
+            [
+                Some(Rgb(0, 0, 0)),
+                None,
+                Some(Rgb(255, 255, 255)),
+                Some(Rgb(127, 127, 127)),
+            ]
+        );
+    }
+}
@@ -0,0 +1,86 @@
+use std::io::{self, Write};
+
+use crate::strip::StripWriter;
+
+/// Writer which sends everything written to it both to a terminal, with
+/// escape sequences intact, and to a log, with escape sequences stripped.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Write;
+/// use nu_ansi_term::TeeWriter;
+/// use nu_ansi_term::Color::Green;
+///
+/// let mut tee = TeeWriter::new(Vec::new(), Vec::new());
+/// write!(tee, "{}", Green.paint("ok")).unwrap();
+///
+/// let (terminal, log) = tee.into_inner();
+/// assert_eq!(terminal, b"\x1b[32mok\x1b[0m");
+/// assert_eq!(log, b"ok");
+/// ```
+#[derive(Debug)]
+pub struct TeeWriter<T, L> {
+    terminal: T,
+    log: StripWriter<L>,
+}
+
+impl<T: Write, L: Write> TeeWriter<T, L> {
+    /// Create a writer duplicating its output to `terminal` and `log`.
+    pub fn new(terminal: T, log: L) -> Self {
+        Self {
+            terminal,
+            log: StripWriter::new(log),
+        }
+    }
+
+    /// Get a reference to the terminal writer.
+    pub fn terminal(&self) -> &T {
+        &self.terminal
+    }
+
+    /// Get a reference to the log writer.
+    pub fn log(&self) -> &L {
+        self.log.get_ref()
+    }
+
+    /// Consume this writer, returning the terminal and log writers.
+    pub fn into_inner(self) -> (T, L) {
+        (self.terminal, self.log.into_inner())
+    }
+}
+
+impl<T: Write, L: Write> Write for TeeWriter<T, L> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.terminal.write_all(buf)?;
+        self.log.write_all(buf)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.terminal.flush()?;
+        self.log.flush()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::AnsiStrings;
+    use crate::Color::*;
+
+    #[test]
+    fn both_outputs() {
+        let mut tee = TeeWriter::new(Vec::new(), Vec::new());
+        write!(
+            tee,
+            "{}",
+            AnsiStrings([Red.paint("a"), Blue.bold().paint("b")])
+        )
+        .unwrap();
+        let (terminal, log) = tee.into_inner();
+        assert_eq!(log, b"ab");
+        assert_eq!(crate::strip::strip_bytes(&terminal), log);
+        assert!(terminal.len() > log.len());
+    }
+}
@@ -0,0 +1,413 @@
+/// Number of rows assumed when the `LINES` environment variable is not set.
+pub const DEFAULT_ROWS: usize = 24;
+
+/// Size of a terminal, in character cells.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TerminalSize {
+    /// Number of columns (character cells per line).
+    pub columns: usize,
+    /// Number of rows (lines).
+    pub rows: usize,
+}
+
+/// Detect the size of the terminal attached to this process.
+///
+/// The size is queried from the operating system (`TIOCGWINSZ` on Unix, the
+/// console screen buffer on Windows) for stdout, stderr and stdin in that
+/// order. If none of them is a terminal, the `COLUMNS` and `LINES`
+/// environment variables are used instead.
+///
+/// Returns `None` if the size could not be determined.
+///
+/// # Examples
+///
+/// ```
+/// use nu_ansi_term::terminal_size;
+///
+/// let columns = terminal_size().map(|size| size.columns).unwrap_or(80);
+/// println!("{}", "-".repeat(columns));
+/// ```
+#[cfg(feature = "terminal_size")]
+pub fn terminal_size() -> Option<TerminalSize> {
+    sys::terminal_size().or_else(size_from_env)
+}
+
+/// Detect the number of columns of the attached terminal, using
+/// [`terminal_size`] if the `terminal_size` feature is enabled and only the
+/// `COLUMNS` environment variable otherwise.
+pub(crate) fn detect_width() -> Option<usize> {
+    #[cfg(feature = "terminal_size")]
+    let size = terminal_size();
+    #[cfg(not(feature = "terminal_size"))]
+    let size = size_from_env();
+    size.map(|size| size.columns)
+}
+
+fn size_from_env() -> Option<TerminalSize> {
+    size_from_vars(
+        std::env::var("COLUMNS").ok().as_deref(),
+        std::env::var("LINES").ok().as_deref(),
+    )
+}
+
+fn size_from_vars(columns: Option<&str>, lines: Option<&str>) -> Option<TerminalSize> {
+    let columns = columns?.trim().parse().ok().filter(|&c| c > 0)?;
+    let rows = lines
+        .and_then(|lines| lines.trim().parse().ok())
+        .filter(|&r| r > 0)
+        .unwrap_or(DEFAULT_ROWS);
+    Some(TerminalSize { columns, rows })
+}
+
+/// Interval at which the thread spawned by [`on_resize`] checks for size
+/// changes.
+#[cfg(feature = "terminal_size")]
+pub const RESIZE_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(100);
+
+/// Watches the attached terminal for size changes, so that long-running
+/// renderers can reflow their output.
+///
+/// On Unix a `SIGWINCH` handler is installed (once per process, chaining to
+/// any previously installed handler), and the terminal is only queried
+/// again after a signal was received. On other platforms the size is
+/// queried on every [`poll`](ResizeWatcher::poll).
+///
+/// # Examples
+///
+/// ```
+/// use nu_ansi_term::ResizeWatcher;
+///
+/// let mut watcher = ResizeWatcher::new();
+/// // ... in the render loop:
+/// if let Some(size) = watcher.poll() {
+///     println!("reflowing to {} columns", size.columns);
+/// }
+/// ```
+#[cfg(feature = "terminal_size")]
+#[derive(Clone, Copy, Debug)]
+pub struct ResizeWatcher {
+    last: Option<TerminalSize>,
+    generation: usize,
+}
+
+#[cfg(feature = "terminal_size")]
+impl Default for ResizeWatcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "terminal_size")]
+impl ResizeWatcher {
+    /// Create a watcher, remembering the current terminal size.
+    pub fn new() -> Self {
+        sys::install_resize_handler();
+        // Read the generation first, so that a resize between the two reads
+        // is seen by the next poll rather than lost.
+        let generation = sys::resize_generation();
+        Self {
+            last: terminal_size(),
+            generation,
+        }
+    }
+
+    /// The terminal size as of the last [`poll`](ResizeWatcher::poll).
+    pub const fn size(&self) -> Option<TerminalSize> {
+        self.last
+    }
+
+    /// Check whether the terminal was resized since the last poll, returning
+    /// the new size if it was.
+    pub fn poll(&mut self) -> Option<TerminalSize> {
+        let generation = sys::resize_generation();
+        if sys::RESIZE_SIGNALS && generation == self.generation {
+            return None;
+        }
+        self.generation = generation;
+        self.observe(terminal_size())
+    }
+
+    fn observe(&mut self, size: Option<TerminalSize>) -> Option<TerminalSize> {
+        if size == self.last {
+            None
+        } else {
+            self.last = size;
+            size
+        }
+    }
+}
+
+/// Handle to a subscription created by [`on_resize`]. Dropping it stops the
+/// watching thread.
+#[cfg(feature = "terminal_size")]
+#[derive(Debug)]
+pub struct ResizeSubscription {
+    stop: std::sync::Arc<std::sync::atomic::AtomicBool>,
+    thread: Option<std::thread::JoinHandle<()>>,
+}
+
+#[cfg(feature = "terminal_size")]
+impl Drop for ResizeSubscription {
+    fn drop(&mut self) {
+        self.stop.store(true, std::sync::atomic::Ordering::SeqCst);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Call `callback` with the new terminal size whenever the terminal is
+/// resized, from a background thread which checks a [`ResizeWatcher`] every
+/// [`RESIZE_POLL_INTERVAL`]. The subscription lasts until the returned handle
+/// is dropped.
+#[cfg(feature = "terminal_size")]
+pub fn on_resize<F>(mut callback: F) -> ResizeSubscription
+where
+    F: FnMut(TerminalSize) + Send + 'static,
+{
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let thread_stop = Arc::clone(&stop);
+    let mut watcher = ResizeWatcher::new();
+    let thread = std::thread::spawn(move || {
+        while !thread_stop.load(Ordering::SeqCst) {
+            if let Some(size) = watcher.poll() {
+                callback(size);
+            }
+            std::thread::sleep(RESIZE_POLL_INTERVAL);
+        }
+    });
+    ResizeSubscription {
+        stop,
+        thread: Some(thread),
+    }
+}
+
+#[cfg(all(feature = "terminal_size", unix))]
+mod sys {
+    use super::TerminalSize;
+    use std::os::raw::{c_int, c_void};
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::sync::Once;
+
+    /// Whether resizes are reported by a signal, so that the size only needs
+    /// to be queried after [`resize_generation`] changed.
+    pub(super) const RESIZE_SIGNALS: bool = true;
+
+    /// Incremented by the `SIGWINCH` handler.
+    static GENERATION: AtomicUsize = AtomicUsize::new(0);
+    /// Handler that was installed before ours, if any.
+    static PREVIOUS_HANDLER: AtomicUsize = AtomicUsize::new(libc::SIG_DFL);
+    /// Whether the previous handler was installed with `SA_SIGINFO`, and so
+    /// takes the signal information and context as well.
+    static PREVIOUS_SIGINFO: AtomicBool = AtomicBool::new(false);
+    static INSTALL: Once = Once::new();
+
+    extern "C" fn handle_sigwinch(signal: c_int, info: *mut libc::siginfo_t, context: *mut c_void) {
+        GENERATION.fetch_add(1, Ordering::SeqCst);
+        let previous = PREVIOUS_HANDLER.load(Ordering::SeqCst);
+        if previous == libc::SIG_DFL || previous == libc::SIG_IGN {
+            return;
+        }
+        // SAFETY: `PREVIOUS_HANDLER` holds a handler of the kind that
+        // `PREVIOUS_SIGINFO` says, see `record_previous`, and ours is
+        // installed with `SA_SIGINFO`, so `info` and `context` are valid.
+        unsafe {
+            if PREVIOUS_SIGINFO.load(Ordering::SeqCst) {
+                let previous: extern "C" fn(c_int, *mut libc::siginfo_t, *mut c_void) =
+                    std::mem::transmute(previous);
+                previous(signal, info, context);
+            } else {
+                let previous: extern "C" fn(c_int) = std::mem::transmute(previous);
+                previous(signal);
+            }
+        }
+    }
+
+    /// Remember `previous` to be called from our handler. The handler is
+    /// cleared while its kind changes, so that it is never called as the
+    /// wrong kind.
+    fn record_previous(previous: &libc::sigaction) {
+        PREVIOUS_HANDLER.store(libc::SIG_DFL, Ordering::SeqCst);
+        PREVIOUS_SIGINFO.store(previous.sa_flags & libc::SA_SIGINFO != 0, Ordering::SeqCst);
+        PREVIOUS_HANDLER.store(previous.sa_sigaction, Ordering::SeqCst);
+    }
+
+    pub(super) fn install_resize_handler() {
+        INSTALL.call_once(|| unsafe {
+            // SAFETY: `sigaction` is a plain C struct for which all zeroes is
+            // a valid value, and the installed handler is async-signal-safe.
+            // The previous handler is recorded before ours is installed, so
+            // that a signal arriving in between is still passed on to it.
+            let mut previous: libc::sigaction = std::mem::zeroed();
+            if libc::sigaction(libc::SIGWINCH, std::ptr::null(), &mut previous) != 0 {
+                return;
+            }
+            record_previous(&previous);
+            let mut action: libc::sigaction = std::mem::zeroed();
+            action.sa_sigaction = handle_sigwinch as *const () as libc::sighandler_t;
+            action.sa_flags = libc::SA_RESTART | libc::SA_SIGINFO;
+            libc::sigemptyset(&mut action.sa_mask);
+            if libc::sigaction(libc::SIGWINCH, &action, &mut previous) == 0 {
+                // Another handler may have been installed since it was read.
+                record_previous(&previous);
+            }
+        });
+    }
+
+    pub(super) fn resize_generation() -> usize {
+        GENERATION.load(Ordering::SeqCst)
+    }
+
+    pub(super) fn terminal_size() -> Option<TerminalSize> {
+        [libc::STDOUT_FILENO, libc::STDERR_FILENO, libc::STDIN_FILENO]
+            .into_iter()
+            .find_map(|fd| {
+                // SAFETY: `winsize` is a plain C struct for which all zeroes
+                // is a valid value, and `TIOCGWINSZ` only writes into it.
+                let mut size: libc::winsize = unsafe { std::mem::zeroed() };
+                let result = unsafe { libc::ioctl(fd, libc::TIOCGWINSZ, &mut size) };
+                (result == 0 && size.ws_col > 0).then(|| TerminalSize {
+                    columns: size.ws_col.into(),
+                    rows: size.ws_row.into(),
+                })
+            })
+    }
+}
+
+#[cfg(all(feature = "terminal_size", not(unix)))]
+mod sys {
+    /// Resizes are not reported by a signal: the size has to be queried on
+    /// every poll.
+    pub(super) const RESIZE_SIGNALS: bool = false;
+
+    pub(super) fn install_resize_handler() {}
+
+    pub(super) fn resize_generation() -> usize {
+        0
+    }
+
+    pub(super) use super::imp::terminal_size;
+}
+
+#[cfg(all(feature = "terminal_size", windows))]
+mod imp {
+    use super::TerminalSize;
+
+    pub(super) fn terminal_size() -> Option<TerminalSize> {
+        use windows::Win32::Foundation::INVALID_HANDLE_VALUE;
+        use windows::Win32::System::Console::{
+            GetConsoleScreenBufferInfo, GetStdHandle, CONSOLE_SCREEN_BUFFER_INFO, STD_ERROR_HANDLE,
+            STD_INPUT_HANDLE, STD_OUTPUT_HANDLE,
+        };
+
+        [STD_OUTPUT_HANDLE, STD_ERROR_HANDLE, STD_INPUT_HANDLE]
+            .into_iter()
+            .find_map(|std_handle| unsafe {
+                // ref: https://learn.microsoft.com/en-us/windows/console/getconsolescreenbufferinfo
+                let handle = GetStdHandle(std_handle);
+                if handle == INVALID_HANDLE_VALUE {
+                    return None;
+                }
+                let mut info: CONSOLE_SCREEN_BUFFER_INFO = std::mem::zeroed();
+                if 0 == GetConsoleScreenBufferInfo(handle, &mut info) {
+                    return None;
+                }
+                let window = info.srWindow;
+                Some(TerminalSize {
+                    columns: (window.Right - window.Left + 1) as usize,
+                    rows: (window.Bottom - window.Top + 1) as usize,
+                })
+            })
+    }
+}
+
+#[cfg(all(feature = "terminal_size", not(any(unix, windows))))]
+mod imp {
+    use super::TerminalSize;
+
+    pub(super) fn terminal_size() -> Option<TerminalSize> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn from_vars() {
+        assert_eq!(
+            size_from_vars(Some("120"), Some("40")),
+            Some(TerminalSize {
+                columns: 120,
+                rows: 40
+            })
+        );
+        assert_eq!(
+            size_from_vars(Some("100"), None),
+            Some(TerminalSize {
+                columns: 100,
+                rows: DEFAULT_ROWS
+            })
+        );
+    }
+
+    #[test]
+    fn invalid_vars() {
+        assert_eq!(size_from_vars(None, Some("40")), None);
+        assert_eq!(size_from_vars(Some("wide"), None), None);
+        assert_eq!(size_from_vars(Some("0"), None), None);
+    }
+
+    #[test]
+    #[cfg(feature = "terminal_size")]
+    fn watcher_reports_changes_once() {
+        let small = TerminalSize {
+            columns: 80,
+            rows: 24,
+        };
+        let large = TerminalSize {
+            columns: 120,
+            rows: 40,
+        };
+        let mut watcher = ResizeWatcher {
+            last: Some(small),
+            generation: 0,
+        };
+        assert_eq!(watcher.observe(Some(small)), None);
+        assert_eq!(watcher.observe(Some(large)), Some(large));
+        assert_eq!(watcher.observe(Some(large)), None);
+        assert_eq!(watcher.size(), Some(large));
+    }
+
+    #[test]
+    #[cfg(all(feature = "terminal_size", unix))]
+    fn sigwinch_bumps_generation() {
+        use std::os::raw::{c_int, c_void};
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        // A handler installed by another library, with `SA_SIGINFO`, which
+        // must still be called once ours is installed.
+        static CHAINED: AtomicUsize = AtomicUsize::new(0);
+        extern "C" fn other(_: c_int, info: *mut libc::siginfo_t, _: *mut c_void) {
+            if !info.is_null() {
+                CHAINED.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+        unsafe {
+            let mut action: libc::sigaction = std::mem::zeroed();
+            action.sa_sigaction = other as *const () as libc::sighandler_t;
+            action.sa_flags = libc::SA_SIGINFO;
+            libc::sigemptyset(&mut action.sa_mask);
+            libc::sigaction(libc::SIGWINCH, &action, std::ptr::null_mut());
+        }
+
+        let watcher = ResizeWatcher::new();
+        unsafe { libc::raise(libc::SIGWINCH) };
+        assert!(sys::resize_generation() > watcher.generation);
+        assert!(CHAINED.load(Ordering::SeqCst) > 0);
+    }
+}
@@ -0,0 +1,678 @@
+use crate::nu_defaults::VALUE_STYLES;
+use crate::spec::{parse_color, parse_format};
+use crate::style::FormatFlags;
+use crate::utils::leaked_once;
+use crate::{AnsiString, Color, Content, Rgb, Style};
+use std::collections::BTreeMap;
+use std::ptr;
+use std::sync::atomic::AtomicPtr;
+use std::sync::{Arc, RwLock};
+use std::{env, error, fmt};
+
+/// The default styles of the semantic roles of [`Theme::semantic`], which
+/// diagnostics and highlighters share so that a severity or kind of token
+/// looks the same whichever tool shows it.
+pub const SEMANTIC_STYLES: &[(&str, Style)] = &[
+    ("error", Color::Red.normal().bold()),
+    ("warning", Color::Yellow.normal().bold()),
+    ("info", Color::Blue.normal()),
+    ("hint", Color::Cyan.normal()),
+    ("success", Color::Green.normal()),
+    ("string_literal", Color::Green.normal()),
+    ("number_literal", Color::Purple.normal()),
+    ("keyword", Color::Blue.normal().bold()),
+    ("comment", Color::DarkGray.normal()),
+];
+
+/// The theme set with [`Theme::set_global`], created on first use.
+static GLOBAL: AtomicPtr<RwLock<Arc<Theme>>> = AtomicPtr::new(ptr::null_mut());
+
+fn global_slot() -> &'static RwLock<Arc<Theme>> {
+    leaked_once(&GLOBAL, || RwLock::new(Arc::new(Theme::semantic())))
+}
+
+/// A set of named styles, such as the styles of the kinds of values shown by
+/// nushell (see [`Theme::nu_defaults`]).
+///
+/// Themes can be read from a config string, in either JSON or nushell's
+/// record syntax, using the color names and `{ fg, bg, attr }` records of
+/// nushell's `color_config`:
+///
+/// ```
+/// use nu_ansi_term::Theme;
+/// use nu_ansi_term::Color::{Green, Red, White};
+///
+/// let theme = Theme::parse(r##"{
+///     header: green_bold
+///     int: "#ff0000"
+///     search_result: { fg: white, bg: red, attr: u }
+/// }"##).unwrap();
+/// assert_eq!(theme.get("header"), Some(Green.bold()));
+/// assert_eq!(theme.get("search_result"), Some(White.on(Red).underline()));
+///
+/// let theme = Theme::parse(r#"{"header": {"fg": "green", "attr": "b"}}"#).unwrap();
+/// assert_eq!(theme.get("header"), Some(Green.bold()));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Theme {
+    styles: BTreeMap<String, Style>,
+}
+
+impl Theme {
+    /// Create an empty theme.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a theme with the default styles nushell uses for each kind of
+    /// value. See [`nu_defaults`](crate::nu_defaults).
+    pub fn nu_defaults() -> Self {
+        VALUE_STYLES
+            .iter()
+            .fold(Self::new(), |theme, (name, style)| {
+                theme.with(*name, *style)
+            })
+    }
+
+    /// Create a theme with the styles of the semantic roles of
+    /// [`SEMANTIC_STYLES`], such as `error`, `warning`, `hint` and
+    /// `string_literal`. Roles of its own can be added to it with
+    /// [`with`](Self::with).
+    pub fn semantic() -> Self {
+        SEMANTIC_STYLES
+            .iter()
+            .fold(Self::new(), |theme, (name, style)| {
+                theme.with(*name, *style)
+            })
+    }
+
+    /// Set the style called `name`.
+    pub fn with(mut self, name: impl Into<String>, style: Style) -> Self {
+        self.insert(name, style);
+        self
+    }
+
+    /// Set the style called `name`, returning its previous style (if any).
+    pub fn insert(&mut self, name: impl Into<String>, style: Style) -> Option<Style> {
+        self.styles.insert(name.into(), style)
+    }
+
+    /// The style called `name`, if there is one.
+    pub fn get(&self, name: &str) -> Option<Style> {
+        self.styles.get(name).copied()
+    }
+
+    /// The style called `name`, inheriting (see [`Style::inherit_from`])
+    /// whatever it leaves unspecified from the styles named by each prefix of
+    /// `name` ending before a `.`, so that `"shape.string.quoted"` inherits
+    /// from `"shape.string"`, which inherits from `"shape"`. Ancestors which
+    /// are not in the theme are skipped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::{FormatFlags, Style, Theme};
+    /// use nu_ansi_term::Color::{Blue, Green};
+    ///
+    /// let theme = Theme::new()
+    ///     .with("shape", Blue.bold())
+    ///     .with("shape.string", Green.normal())
+    ///     .with("shape.string.quoted", Style::new().clear_formats(FormatFlags::BOLD));
+    /// assert_eq!(theme.get_inherited("shape.string"), Some(Green.bold()));
+    /// assert_eq!(
+    ///     theme.get_inherited("shape.string.quoted"),
+    ///     Some(Green.normal().clear_formats(FormatFlags::BOLD))
+    /// );
+    /// assert_eq!(theme.get_inherited("shape.int"), Some(Blue.bold()));
+    /// assert_eq!(theme.get_inherited("header"), None);
+    /// ```
+    pub fn get_inherited(&self, name: &str) -> Option<Style> {
+        name.match_indices('.')
+            .map(|(ix, _)| &name[..ix])
+            .chain(std::iter::once(name))
+            .filter_map(|name| self.get(name))
+            .reduce(|parent, child| child.inherit_from(&parent))
+    }
+
+    /// Paint `input` in the style of the role `name` (found as by
+    /// [`get_inherited`](Self::get_inherited)), or leave it unstyled if the
+    /// theme has no such style.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::Theme;
+    /// use nu_ansi_term::Color::{Red, Yellow};
+    ///
+    /// let theme = Theme::semantic().with("warning.deprecated", Yellow.italic());
+    /// assert_eq!(theme.paint("error", "oops").to_string(), Red.bold().paint("oops").to_string());
+    /// assert_eq!(
+    ///     theme.paint("warning.deprecated", "old").to_string(),
+    ///     Yellow.bold().italic().paint("old").to_string()
+    /// );
+    /// assert_eq!(theme.paint("unknown", "text").to_string(), "text");
+    /// ```
+    pub fn paint<'a>(&self, name: &str, input: impl Into<Content<'a, str>>) -> AnsiString<'a> {
+        self.get_inherited(name).unwrap_or_default().paint(input)
+    }
+
+    /// The theme shared by the whole program, which is
+    /// [`Theme::semantic`] until another is set with
+    /// [`set_global`](Self::set_global).
+    pub fn global() -> Arc<Theme> {
+        Arc::clone(&global_slot().read().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    /// Replace the theme shared by the whole program, e.g. with one read
+    /// from the user's configuration at startup.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::Theme;
+    /// use nu_ansi_term::Color::Blue;
+    ///
+    /// Theme::set_global(Theme::semantic().with("error", Blue.bold()));
+    /// assert_eq!(Theme::global().get("error"), Some(Blue.bold()));
+    /// ```
+    pub fn set_global(theme: Theme) {
+        *global_slot().write().unwrap_or_else(|e| e.into_inner()) = Arc::new(theme);
+    }
+
+    /// Iterate over the styles of this theme, ordered by name.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, Style)> {
+        self.styles
+            .iter()
+            .map(|(name, style)| (name.as_str(), *style))
+    }
+
+    /// Override the styles of this theme with those of `overrides`.
+    pub fn merge(mut self, overrides: Theme) -> Self {
+        self.styles.extend(overrides.styles);
+        self
+    }
+
+    /// Read a theme from `config`, a record of named styles in either JSON
+    /// or nushell syntax. The surrounding braces may be left out.
+    ///
+    /// Styles are either strings (`"green"`, `"light_cyan_bold"`,
+    /// `"bg_red"`, `"#ff8000"`) or records with optional `fg` and `bg`
+    /// colors and `attr` letters (`b`old, `d`immed, `i`talic, `u`nderline,
+    /// b`l`ink, `r`everse, `h`idden, `s`trikethrough, `n`ormal).
+    pub fn parse(config: &str) -> Result<Theme, ThemeError> {
+        let mut parser = Parser {
+            src: config,
+            pos: 0,
+        };
+        parser.skip_whitespace();
+        let braced = parser.eat(b'{');
+        let entries = parser.entries(braced)?;
+        parser.skip_whitespace();
+        if parser.pos < config.len() {
+            return Err(parser.error("unexpected text after the theme"));
+        }
+        let mut theme = Theme::new();
+        for (name, value) in entries {
+            let style = value.to_style().ok_or_else(|| ThemeError::InvalidStyle {
+                name: name.clone(),
+                value: value.to_string(),
+            })?;
+            theme.insert(name, style);
+        }
+        Ok(theme)
+    }
+
+    /// Read a theme from the environment variable `var_name` (such as
+    /// `NU_COLOR_CONFIG`). See [`Theme::parse`] for its syntax.
+    pub fn from_env(var_name: &str) -> Result<Theme, ThemeError> {
+        match env::var(var_name) {
+            Ok(config) => Theme::parse(&config),
+            Err(env::VarError::NotPresent) => Err(ThemeError::NotSet(var_name.to_string())),
+            Err(env::VarError::NotUnicode(_)) => Err(ThemeError::NotUnicode(var_name.to_string())),
+        }
+    }
+
+    /// Generate a random theme from `seed`, for stress-testing rendering
+    /// with varied styles. The same seed always generates the same theme.
+    ///
+    /// The theme has a style for each of the names of [`Theme::semantic`]
+    /// and [`Theme::nu_defaults`], with an RGB foreground color and a random
+    /// mix of bold, italic and underline. Colors are checked for contrast
+    /// so that the themes stay readable: a style with a background has a
+    /// contrast ratio of at least 4.5 with it (see [`Rgb::contrast_ratio`]),
+    /// and a style without one a ratio of at least 3 with both black and
+    /// white, so that it can be read on dark and light terminals alike.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::{Rgb, Theme};
+    /// use nu_ansi_term::Color;
+    ///
+    /// let theme = Theme::generate(42);
+    /// assert_eq!(theme, Theme::generate(42));
+    /// assert_ne!(theme, Theme::generate(43));
+    ///
+    /// let error = theme.get("error").unwrap();
+    /// if let (Some(Color::Rgb(r, g, b)), None) = (error.coloring.fg, error.coloring.bg) {
+    ///     assert!(Rgb::new(r, g, b).contrast_ratio(Rgb::gray(0)) >= 3.0);
+    ///     assert!(Rgb::new(r, g, b).contrast_ratio(Rgb::gray(255)) >= 3.0);
+    /// }
+    /// ```
+    pub fn generate(seed: u64) -> Self {
+        let mut rng = SplitMix(seed);
+        let names = SEMANTIC_STYLES
+            .iter()
+            .chain(VALUE_STYLES)
+            .map(|(name, _)| *name);
+        let mut theme = Theme::new();
+        for name in names {
+            let mut style = Style::new();
+            for format in [
+                FormatFlags::BOLD,
+                FormatFlags::ITALIC,
+                FormatFlags::UNDERLINE,
+            ] {
+                if rng.chance(4) {
+                    style = style.insert_formats(format);
+                }
+            }
+            let fg = if rng.chance(4) {
+                let bg = rng.color();
+                style = style.on(bg.into());
+                rng.readable(|fg| fg.contrast_ratio(bg) >= 4.5)
+                    .unwrap_or_else(|| Rgb::best_foreground_on(bg))
+            } else {
+                let either = |fg: Rgb| {
+                    fg.contrast_ratio(Rgb::gray(0)) >= 3.0
+                        && fg.contrast_ratio(Rgb::gray(255)) >= 3.0
+                };
+                rng.readable(either).unwrap_or(Rgb::gray(128))
+            };
+            theme.insert(name, style.fg(fg.into()));
+        }
+        theme
+    }
+}
+
+/// The SplitMix64 generator, which is good enough for picking colors and
+/// needs no dependency.
+struct SplitMix(u64);
+
+impl SplitMix {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// A number from 0 to 1.
+    fn unit(&mut self) -> f32 {
+        (self.next() >> 40) as f32 / (1_u64 << 24) as f32
+    }
+
+    /// True once in `n` times.
+    fn chance(&mut self, n: u64) -> bool {
+        self.next() % n == 0
+    }
+
+    /// A color of any hue, neither too gray nor too dark or light.
+    fn color(&mut self) -> Rgb {
+        let hue = self.unit() * 360.0;
+        Rgb::from_hsl(hue, 0.4 + 0.6 * self.unit(), 0.15 + 0.7 * self.unit())
+    }
+
+    /// The first of a few random colors which is `readable`.
+    fn readable(&mut self, readable: impl Fn(Rgb) -> bool) -> Option<Rgb> {
+        (0..32).map(|_| self.color()).find(|&color| readable(color))
+    }
+}
+
+/// An error reading a [`Theme`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ThemeError {
+    /// The environment variable with this name is not set.
+    NotSet(String),
+    /// The environment variable with this name is not valid unicode.
+    NotUnicode(String),
+    /// The config is malformed at byte `offset`.
+    Syntax {
+        /// Byte offset of the error in the config.
+        offset: usize,
+        /// What was expected there.
+        message: String,
+    },
+    /// The style called `name` is not understood.
+    InvalidStyle {
+        /// Name of the style.
+        name: String,
+        /// The style as written in the config.
+        value: String,
+    },
+}
+
+impl fmt::Display for ThemeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ThemeError::NotSet(var) => write!(f, "{} is not set", var),
+            ThemeError::NotUnicode(var) => write!(f, "{} is not valid unicode", var),
+            ThemeError::Syntax { offset, message } => {
+                write!(f, "syntax error at byte {}: {}", offset, message)
+            }
+            ThemeError::InvalidStyle { name, value } => {
+                write!(f, "invalid style for {}: {}", name, value)
+            }
+        }
+    }
+}
+
+impl error::Error for ThemeError {}
+
+/// A value in a theme config: a string, or a record of values.
+#[derive(Debug)]
+enum Value {
+    Text(String),
+    Record(Vec<(String, Value)>),
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Text(text) => write!(f, "{:?}", text),
+            Value::Record(entries) => {
+                f.write_str("{")?;
+                for (ix, (name, value)) in entries.iter().enumerate() {
+                    let sep = if ix == 0 { " " } else { ", " };
+                    write!(f, "{}{}: {}", sep, name, value)?;
+                }
+                f.write_str(" }")
+            }
+        }
+    }
+}
+
+impl Value {
+    fn to_style(&self) -> Option<Style> {
+        match self {
+            Value::Text(text) => parse_style(text),
+            Value::Record(entries) => {
+                let mut style = Style::new();
+                for (name, value) in entries {
+                    let text = match value {
+                        Value::Text(text) => text,
+                        Value::Record(_) => return None,
+                    };
+                    style = match name.as_str() {
+                        "fg" => style.fg(parse_color(text)?),
+                        "bg" => style.bg(parse_color(text)?),
+                        "attr" => style.insert_formats(parse_attrs(text)?),
+                        _ => return None,
+                    };
+                }
+                Some(style)
+            }
+        }
+    }
+}
+
+/// Parse a style written like `light_cyan_bold`, `bg_red` or `#ff8000`.
+pub(crate) fn parse_style(text: &str) -> Option<Style> {
+    if let Some(color) = text.strip_prefix("bg_") {
+        return parse_color(color).map(|color| Style::new().bg(color));
+    }
+    if let Some(color) = parse_color(text) {
+        return Some(Style::new().fg(color));
+    }
+    let (color, attr) = text.rsplit_once('_')?;
+    let formats = parse_format(attr)?;
+    Some(Style::new().fg(parse_color(color)?).insert_formats(formats))
+}
+
+fn parse_attrs(text: &str) -> Option<FormatFlags> {
+    text.chars().try_fold(FormatFlags::empty(), |formats, c| {
+        let flag = match c {
+            'b' => FormatFlags::BOLD,
+            'd' => FormatFlags::DIMMED,
+            'i' => FormatFlags::ITALIC,
+            'u' => FormatFlags::UNDERLINE,
+            'l' => FormatFlags::BLINK,
+            'r' => FormatFlags::REVERSE,
+            'h' => FormatFlags::HIDDEN,
+            's' => FormatFlags::STRIKETHROUGH,
+            'n' => FormatFlags::empty(),
+            _ => return None,
+        };
+        Some(formats | flag)
+    })
+}
+
+/// A parser for records of the form `{ name: value, … }`, where names and
+/// values may be quoted JSON-style (`"…"`) or nushell-style (`'…'`), or left
+/// bare, and the commas are optional.
+struct Parser<'s> {
+    src: &'s str,
+    pos: usize,
+}
+
+impl<'s> Parser<'s> {
+    fn error(&self, message: &str) -> ThemeError {
+        ThemeError::Syntax {
+            offset: self.pos,
+            message: message.to_string(),
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.src.as_bytes().get(self.pos).copied()
+    }
+
+    fn eat(&mut self, byte: u8) -> bool {
+        let eaten = self.peek() == Some(byte);
+        if eaten {
+            self.pos += 1;
+        }
+        eaten
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().map_or(false, |b| b.is_ascii_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    /// Parse entries up to the closing brace if `braced`, or to the end of
+    /// the input.
+    fn entries(&mut self, braced: bool) -> Result<Vec<(String, Value)>, ThemeError> {
+        let mut entries = Vec::new();
+        loop {
+            self.skip_whitespace();
+            if braced && self.eat(b'}') || !braced && self.peek().is_none() {
+                return Ok(entries);
+            }
+            if self.peek().is_none() {
+                return Err(self.error("expected `}`"));
+            }
+            let name = self.text()?;
+            self.skip_whitespace();
+            if !self.eat(b':') {
+                return Err(self.error("expected `:`"));
+            }
+            self.skip_whitespace();
+            let value = if self.eat(b'{') {
+                Value::Record(self.entries(true)?)
+            } else {
+                Value::Text(self.text()?)
+            };
+            entries.push((name, value));
+            self.skip_whitespace();
+            self.eat(b',');
+        }
+    }
+
+    /// Parse a quoted or bare string.
+    fn text(&mut self) -> Result<String, ThemeError> {
+        match self.peek() {
+            Some(b'"') => self.json_string(),
+            Some(b'\'') => {
+                self.pos += 1;
+                let len = self.src[self.pos..]
+                    .find('\'')
+                    .ok_or_else(|| self.error("unterminated string"))?;
+                let text = self.src[self.pos..self.pos + len].to_string();
+                self.pos += len + 1;
+                Ok(text)
+            }
+            _ => {
+                let len = self.src[self.pos..]
+                    .find(|c: char| c.is_whitespace() || ",:{}\"'".contains(c))
+                    .unwrap_or(self.src.len() - self.pos);
+                if len == 0 {
+                    return Err(self.error("expected a name or a value"));
+                }
+                let text = self.src[self.pos..self.pos + len].to_string();
+                self.pos += len;
+                Ok(text)
+            }
+        }
+    }
+
+    fn json_string(&mut self) -> Result<String, ThemeError> {
+        self.pos += 1;
+        let mut text = String::new();
+        let mut chars = self.src[self.pos..].char_indices();
+        while let Some((ix, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.pos += ix + 1;
+                    return Ok(text);
+                }
+                '\\' => {
+                    let escaped = match chars.next().map(|(_, c)| c) {
+                        Some('n') => '\n',
+                        Some('t') => '\t',
+                        Some('r') => '\r',
+                        Some('u') => {
+                            let hex: String = chars.by_ref().take(4).map(|(_, c)| c).collect();
+                            u32::from_str_radix(&hex, 16)
+                                .ok()
+                                .and_then(char::from_u32)
+                                .ok_or_else(|| ThemeError::Syntax {
+                                    offset: self.pos + ix,
+                                    message: "invalid unicode escape".to_string(),
+                                })?
+                        }
+                        Some(c) => c,
+                        None => break,
+                    };
+                    text.push(escaped);
+                }
+                c => text.push(c),
+            }
+        }
+        self.pos = self.src.len();
+        Err(self.error("unterminated string"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Color::*;
+
+    #[test]
+    fn nushell_syntax() {
+        let theme = Theme::parse(
+            "{ separator: white, hints: dark_gray, 'cell-path': light_cyan_underline,
+               empty: bg_blue, float: '#10a0ff', bool: { fg: cyan attr: bi } }",
+        )
+        .unwrap();
+        assert_eq!(theme.get("separator"), Some(White.normal()));
+        assert_eq!(theme.get("hints"), Some(DarkGray.normal()));
+        assert_eq!(theme.get("cell-path"), Some(LightCyan.underline()));
+        assert_eq!(theme.get("empty"), Some(Blue.bg()));
+        assert_eq!(theme.get("float"), Some(Rgb(0x10, 0xa0, 0xff).normal()));
+        assert_eq!(theme.get("bool"), Some(Cyan.bold().italic()));
+    }
+
+    #[test]
+    fn json_syntax() {
+        let theme =
+            Theme::parse(r#"{"string": "green", "row_index": {"fg": "green", "attr": "b"}}"#)
+                .unwrap();
+        assert_eq!(theme.get("string"), Some(Green.normal()));
+        assert_eq!(theme.get("row_index"), Some(Green.bold()));
+        assert_eq!(Theme::parse("{}"), Ok(Theme::new()));
+        assert_eq!(
+            Theme::parse("  int: red "),
+            Ok(Theme::new().with("int", Red.normal()))
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let syntax = |config| match Theme::parse(config) {
+            Err(ThemeError::Syntax { offset, .. }) => offset,
+            other => panic!("expected a syntax error, got {:?}", other),
+        };
+        assert_eq!(syntax("{ int red }"), 6);
+        assert_eq!(syntax("{ int: red "), 11);
+        assert_eq!(syntax(r#"{ int: "red }"#), 13);
+        assert_eq!(syntax("{ int: red } x"), 13);
+        assert_eq!(
+            Theme::parse("{ int: { fg: red, attr: bx } }"),
+            Err(ThemeError::InvalidStyle {
+                name: "int".to_string(),
+                value: r#"{ fg: "red", attr: "bx" }"#.to_string(),
+            })
+        );
+        assert_eq!(
+            Theme::parse("{ int: reddish_bold }")
+                .unwrap_err()
+                .to_string(),
+            r#"invalid style for int: "reddish_bold""#
+        );
+    }
+
+    #[test]
+    fn from_env() {
+        let var = "NU_ANSI_TERM_TEST_THEME";
+        env::set_var(var, "{ header: green_bold }");
+        let theme = Theme::nu_defaults().merge(Theme::from_env(var).unwrap());
+        assert_eq!(theme.get("header"), Some(Green.bold()));
+        assert_eq!(theme.get("filesize"), Some(Cyan.normal()));
+        env::remove_var(var);
+        assert_eq!(
+            Theme::from_env(var),
+            Err(ThemeError::NotSet(var.to_string()))
+        );
+    }
+
+    #[test]
+    fn generated_themes_are_readable() {
+        let rgb = |color: Option<Color>| match color {
+            Some(Rgb(r, g, b)) => crate::Rgb::new(r, g, b),
+            other => panic!("expected an RGB color, got {:?}", other),
+        };
+        for seed in 0..50 {
+            let theme = Theme::generate(seed);
+            assert_eq!(
+                theme.iter().count(),
+                Theme::semantic().merge(Theme::nu_defaults()).iter().count()
+            );
+            for (name, style) in theme.iter() {
+                let fg = rgb(style.coloring.fg);
+                match style.coloring.bg {
+                    Some(_) => {
+                        assert!(fg.contrast_ratio(rgb(style.coloring.bg)) >= 4.5, "{}", name)
+                    }
+                    None => {
+                        assert!(fg.contrast_ratio(crate::Rgb::gray(0)) >= 3.0, "{}", name);
+                        assert!(fg.contrast_ratio(crate::Rgb::gray(255)) >= 3.0, "{}", name);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,242 @@
+use crate::message::{parse_template, Piece};
+use crate::{AnsiString, AnsiStrings, MessageError};
+use std::env;
+use std::path::{Path, PathBuf};
+
+/// The placeholders a [`TitleTemplate`] can use.
+const PLACEHOLDERS: &[&str] = &["cwd", "dir", "command", "host"];
+
+/// Marks the start of a prompt, for terminals which understand the shell
+/// integration marks of FinalTerm (OSC 133).
+const PROMPT_START: &str = "\x1b]133;A\x1b\\";
+
+/// Marks the start of a command's output.
+const COMMAND_START: &str = "\x1b]133;C\x1b\\";
+
+/// A template for the title of the terminal window, filled in from
+/// [`TitleValues`] each time the title is updated, so that a shell can keep
+/// the title in sync by rendering it before each prompt and each command.
+///
+/// The template can use the placeholders `{cwd}` (the working directory,
+/// with the home directory shortened to `~`), `{dir}` (the last component
+/// of the working directory), `{command}` and `{host}`. Braces are written
+/// as `{{` and `}}`, as in [`Message`](crate::Message).
+///
+/// # Examples
+///
+/// ```
+/// use nu_ansi_term::{AnsiString, TitleTemplate, TitleValues};
+///
+/// let template = TitleTemplate::new("{cwd} on {host}")
+///     .unwrap()
+///     .running("{command} in {dir}")
+///     .unwrap();
+/// let values = TitleValues::new()
+///     .cwd("/home/me/src/nu")
+///     .home("/home/me")
+///     .host("box");
+///
+/// assert_eq!(template.text(&values), "~/src/nu on box");
+/// assert_eq!(
+///     template.render(&values.clone().command("cargo test")).to_string(),
+///     AnsiString::title("cargo test in nu").to_string()
+/// );
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TitleTemplate {
+    idle: String,
+    running: Option<String>,
+}
+
+impl TitleTemplate {
+    /// Create a template for the title, used both at the prompt and while a
+    /// command is running, unless [`running`](Self::running) is given.
+    pub fn new(template: impl Into<String>) -> Result<Self, MessageError> {
+        Ok(Self {
+            idle: checked(template.into())?,
+            running: None,
+        })
+    }
+
+    /// Use `template` for the title while a command is running.
+    pub fn running(mut self, template: impl Into<String>) -> Result<Self, MessageError> {
+        self.running = Some(checked(template.into())?);
+        Ok(self)
+    }
+
+    /// The text of the title for `values`, using the template for running
+    /// commands if `values` has a command. Control characters in the values
+    /// are dropped, so that they cannot end the title early.
+    pub fn text(&self, values: &TitleValues) -> String {
+        let template = match (&self.running, &values.command) {
+            (Some(running), Some(_)) => running,
+            _ => &self.idle,
+        };
+        let mut text = String::new();
+        for piece in parse_template(template).expect("templates are checked when set") {
+            match piece {
+                Piece::Text(piece) => text.push_str(&piece),
+                Piece::Placeholder(name) => {
+                    text.extend(values.placeholder(name).chars().filter(|c| !c.is_control()))
+                }
+            }
+        }
+        text
+    }
+
+    /// The string setting the title for `values`.
+    pub fn render(&self, values: &TitleValues) -> AnsiString<'static> {
+        AnsiString::title_content(self.text(values))
+    }
+
+    /// The codes to write just before a prompt is shown: the mark of the
+    /// prompt's start for shell integration, and the title for `values`
+    /// without a command.
+    pub fn prompt_start(&self, values: &TitleValues) -> AnsiStrings<'static> {
+        let mut values = values.clone();
+        values.command = None;
+        let mut strings = AnsiStrings([]);
+        strings.push_control(PROMPT_START);
+        strings.push(self.render(&values));
+        strings
+    }
+
+    /// The codes to write just before `command` is run: the title for
+    /// `values` running `command`, and the mark of the start of its output
+    /// for shell integration.
+    pub fn command_start(&self, values: &TitleValues, command: &str) -> AnsiStrings<'static> {
+        let values = values.clone().command(command);
+        let mut strings = AnsiStrings([self.render(&values)]);
+        strings.push_control(COMMAND_START);
+        strings
+    }
+}
+
+/// Check that `template` is well formed and only uses known placeholders.
+fn checked(template: String) -> Result<String, MessageError> {
+    for piece in parse_template(&template)? {
+        if let Piece::Placeholder(name) = piece {
+            if !PLACEHOLDERS.contains(&name) {
+                return Err(MessageError::MissingValue(name.to_string()));
+            }
+        }
+    }
+    Ok(template)
+}
+
+/// The values filled into a [`TitleTemplate`]. Placeholders without a
+/// value are left empty.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TitleValues {
+    cwd: Option<PathBuf>,
+    home: Option<PathBuf>,
+    command: Option<String>,
+    host: Option<String>,
+}
+
+impl TitleValues {
+    /// Values with nothing filled in.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The working directory, home directory and host name of this
+    /// process, read from the environment (`HOME` or `USERPROFILE`, and
+    /// `HOSTNAME` or `COMPUTERNAME`).
+    pub fn detect() -> Self {
+        let var = |names: [&str; 2]| {
+            names
+                .iter()
+                .find_map(|name| env::var(name).ok().filter(|value| !value.is_empty()))
+        };
+        Self {
+            cwd: env::current_dir().ok(),
+            home: var(["HOME", "USERPROFILE"]).map(PathBuf::from),
+            command: None,
+            host: var(["HOSTNAME", "COMPUTERNAME"]),
+        }
+    }
+
+    /// Set the working directory.
+    pub fn cwd(mut self, cwd: impl Into<PathBuf>) -> Self {
+        self.cwd = Some(cwd.into());
+        self
+    }
+
+    /// Set the home directory, which is shortened to `~` at the start of
+    /// the working directory.
+    pub fn home(mut self, home: impl Into<PathBuf>) -> Self {
+        self.home = Some(home.into());
+        self
+    }
+
+    /// Set the command which is running.
+    pub fn command(mut self, command: impl Into<String>) -> Self {
+        self.command = Some(command.into());
+        self
+    }
+
+    /// Set the host name.
+    pub fn host(mut self, host: impl Into<String>) -> Self {
+        self.host = Some(host.into());
+        self
+    }
+
+    fn placeholder(&self, name: &str) -> String {
+        match name {
+            "cwd" => self.cwd.as_deref().map_or(String::new(), |cwd| {
+                match self
+                    .home
+                    .as_deref()
+                    .and_then(|home| cwd.strip_prefix(home).ok())
+                {
+                    Some(rest) if rest == Path::new("") => "~".to_string(),
+                    Some(rest) => Path::new("~").join(rest).to_string_lossy().into_owned(),
+                    None => cwd.to_string_lossy().into_owned(),
+                }
+            }),
+            "dir" => self
+                .cwd
+                .as_deref()
+                .and_then(|cwd| cwd.file_name())
+                .map_or(String::new(), |dir| dir.to_string_lossy().into_owned()),
+            "command" => self.command.clone().unwrap_or_default(),
+            "host" => self.host.clone().unwrap_or_default(),
+            _ => String::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn placeholders() {
+        assert_eq!(
+            TitleTemplate::new("{user}"),
+            Err(MessageError::MissingValue("user".to_string()))
+        );
+        let template = TitleTemplate::new("{{{cwd}}} {command}").unwrap();
+        assert_eq!(template.text(&TitleValues::new()), "{} ");
+        let values = TitleValues::new()
+            .cwd("/home/me")
+            .home("/home/me")
+            .command("echo \x1b\x07hi");
+        assert_eq!(template.text(&values), "{~} echo hi");
+    }
+
+    #[test]
+    fn shell_integration_marks() {
+        let template = TitleTemplate::new("{dir}").unwrap();
+        let values = TitleValues::new().cwd("/tmp").command("ls");
+        assert_eq!(
+            template.prompt_start(&values).to_string(),
+            "\x1b]133;A\x1b\\\x1b]2;tmp\x1b\\"
+        );
+        assert_eq!(
+            template.command_start(&values, "ls").to_string(),
+            "\x1b]2;tmp\x1b\\\x1b]133;C\x1b\\"
+        );
+    }
+}
@@ -0,0 +1,428 @@
+use crate::strip::Stripper;
+use crate::width::paint_linked_runs;
+use crate::write::Content;
+use crate::{
+    nested, AnsiGenericString, AnsiGenericStrings, AnsiString, AnsiStrings, Color, Style,
+    WidthPolicy,
+};
+use std::borrow::Cow;
+
+/// The glyphs and styles [`AnsiStrings::show_whitespace`] shows spaces and
+/// tabs with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WhitespaceStyle {
+    space: char,
+    tab: char,
+    tab_width: usize,
+    glyph: Style,
+    trailing: Style,
+}
+
+impl Default for WhitespaceStyle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WhitespaceStyle {
+    /// Spaces as `·` and tabs as `→`, padded to tab stops every 4 columns,
+    /// dimmed; trailing whitespace is shown on a red background.
+    pub const fn new() -> Self {
+        Self {
+            space: '·',
+            tab: '→',
+            tab_width: 4,
+            glyph: Style::new().dimmed(),
+            trailing: Style::new().on(Color::Red),
+        }
+    }
+
+    /// Show spaces as `glyph`, which should be one column wide.
+    pub const fn space(mut self, glyph: char) -> Self {
+        self.space = glyph;
+        self
+    }
+
+    /// Show tabs as `glyph`, which should be one column wide, followed by
+    /// spaces up to the next tab stop.
+    pub const fn tab(mut self, glyph: char) -> Self {
+        self.tab = glyph;
+        self
+    }
+
+    /// Put tab stops every `width` columns (at least 1).
+    pub const fn tab_width(mut self, width: usize) -> Self {
+        self.tab_width = if width == 0 { 1 } else { width };
+        self
+    }
+
+    /// Paint the glyphs in `style`, laid over the style of the text they
+    /// are in (see [`Style::merge`]).
+    pub const fn glyph(mut self, style: Style) -> Self {
+        self.glyph = style;
+        self
+    }
+
+    /// Paint whitespace at the end of a line in `style`, laid over the
+    /// style of the glyphs.
+    pub const fn trailing(mut self, style: Style) -> Self {
+        self.trailing = style;
+        self
+    }
+}
+
+/// Apply `f` to each run of `text` outside of escape sequences, such as
+/// those of painted values formatted into it.
+fn map_printable<'t>(text: &'t str, f: &mut dyn FnMut(&str) -> Cow<'_, str>) -> Cow<'t, str> {
+    if !text.contains('\x1B') {
+        return f(text);
+    }
+    let mut mapped = String::with_capacity(text.len());
+    let mut push_run = |run: &str, printable: bool| {
+        if printable && !run.is_empty() {
+            mapped.push_str(&f(run));
+        } else {
+            mapped.push_str(run);
+        }
+    };
+    let mut stripper = Stripper::new();
+    let mut scratch = Vec::new();
+    let (mut start, mut printable) = (0, true);
+    for (ix, c) in text.char_indices() {
+        let is_printable = !stripper.in_escape() && c != '\x1B';
+        if is_printable != printable {
+            push_run(&text[start..ix], printable);
+            (start, printable) = (ix, is_printable);
+        }
+        stripper.strip(&text.as_bytes()[ix..ix + c.len_utf8()], &mut scratch);
+        scratch.clear();
+    }
+    push_run(&text[start..], printable);
+    Cow::Owned(mapped)
+}
+
+/// Whether `c` is a space or a tab, the whitespace which can be indentation.
+fn is_blank(c: char) -> bool {
+    c == ' ' || c == '\t'
+}
+
+/// Whether each of `chars` is a space or tab at the end of its line.
+fn trailing_whitespace(chars: &[(char, Style, usize, usize)]) -> Vec<bool> {
+    let mut trailing = vec![false; chars.len()];
+    let mut at_end = true;
+    for (ix, &(c, ..)) in chars.iter().enumerate().rev() {
+        if c == '\n' {
+            at_end = true;
+        } else if is_blank(c) {
+            trailing[ix] = at_end;
+        } else {
+            at_end = false;
+        }
+    }
+    trailing
+}
+
+/// Leave text as it is, for segments which are not mapped.
+fn unchanged(text: &str) -> Cow<'_, str> {
+    Cow::Borrowed(text)
+}
+
+impl<'a> AnsiGenericString<'a, str> {
+    /// An owned copy of this string, keeping its style and link, with its
+    /// text replaced by what `f` makes of it. Titles and control segments
+    /// are copied unchanged. Formatting arguments are rendered in the
+    /// process.
+    ///
+    /// `f` is only given the text which is shown, so it is called once for
+    /// each run of text between the escape codes of any painted values in
+    /// the content.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::borrow::Cow;
+    /// use nu_ansi_term::Color::{Blue, Red};
+    ///
+    /// let link = Blue.paint("docs").hyperlink("https://example.com/Docs");
+    /// assert_eq!(
+    ///     link.map_content(|text| Cow::Owned(text.replace('o', "0"))).to_string(),
+    ///     Blue.paint("d0cs").hyperlink("https://example.com/Docs").to_string()
+    /// );
+    ///
+    /// let inner = Blue.paint("low");
+    /// assert_eq!(
+    ///     Red.paint(format_args!("a {} b", inner)).to_uppercase().to_string(),
+    ///     Red.paint(format_args!("A {} B", Blue.paint("LOW"))).to_string()
+    /// );
+    /// ```
+    pub fn map_content(&self, mut f: impl FnMut(&str) -> Cow<'_, str>) -> AnsiString<'static> {
+        self.map_content_with(&mut f)
+    }
+
+    /// An owned copy of this string with its text in upper case (see
+    /// [`map_content`](Self::map_content)).
+    pub fn to_uppercase(&self) -> AnsiString<'static> {
+        self.map_content(|text| Cow::Owned(text.to_uppercase()))
+    }
+
+    /// An owned copy of this string with its text in lower case (see
+    /// [`map_content`](Self::map_content)).
+    pub fn to_lowercase(&self) -> AnsiString<'static> {
+        self.map_content(|text| Cow::Owned(text.to_lowercase()))
+    }
+
+    fn map_content_with(&self, f: &mut dyn FnMut(&str) -> Cow<'_, str>) -> AnsiString<'static> {
+        let f = if self.scheduled_style().is_some() {
+            f
+        } else {
+            &mut unchanged
+        };
+        let content = match self.content() {
+            Content::FmtArgs(args) => {
+                let text = nested::within(self.style, || args.to_string());
+                Content::owned(map_printable(&text, f).into_owned())
+            }
+            Content::StrLike(s) => Content::owned(map_printable(s, f).into_owned()),
+            Content::Shared(s) => match map_printable(s, f) {
+                mapped if *mapped == **s => Content::Shared(s.clone()),
+                mapped => Content::owned(mapped.into_owned()),
+            },
+            Content::GenericStrings(strings) => {
+                Content::GenericStrings(strings.map_content_with(f))
+            }
+        };
+        self.with_content(content)
+    }
+}
+
+impl<'a> AnsiGenericStrings<'a, str> {
+    /// An owned copy of these strings, keeping their styles and links, with
+    /// their text replaced by what `f` makes of it (see
+    /// [`AnsiGenericString::map_content`]).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::{AnsiString, AnsiStrings};
+    /// use nu_ansi_term::Color::Red;
+    ///
+    /// let strings = AnsiStrings([
+    ///     AnsiString::title("Build"),
+    ///     Red.bold().paint("Error"),
+    ///     ": Not Found".into(),
+    /// ]);
+    /// assert_eq!(
+    ///     strings.to_lowercase().to_string(),
+    ///     AnsiStrings([
+    ///         AnsiString::title("Build"),
+    ///         Red.bold().paint("error"),
+    ///         ": not found".into(),
+    ///     ])
+    ///     .to_string()
+    /// );
+    /// ```
+    pub fn map_content(&self, mut f: impl FnMut(&str) -> Cow<'_, str>) -> AnsiStrings<'static> {
+        self.map_content_with(&mut f)
+    }
+
+    /// An owned copy of these strings with their text in upper case, e.g.
+    /// for emphasis.
+    pub fn to_uppercase(&self) -> AnsiStrings<'static> {
+        self.map_content(|text| Cow::Owned(text.to_uppercase()))
+    }
+
+    /// An owned copy of these strings with their text in lower case, e.g.
+    /// to normalize it.
+    pub fn to_lowercase(&self) -> AnsiStrings<'static> {
+        self.map_content(|text| Cow::Owned(text.to_lowercase()))
+    }
+
+    /// An owned copy of these strings with spaces and tabs shown as visible
+    /// glyphs, as diff viewers and editors show them, in the style of the
+    /// text around them with the glyph style of `theme` laid over it.
+    /// Whitespace at the end of a line is marked in the trailing style as
+    /// well. Each tab is followed by spaces up to the next tab stop, so that
+    /// text stays aligned.
+    ///
+    /// As with [`wrap`](Self::wrap), only the visible text, its styles and
+    /// its links are kept.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::{AnsiStrings, WhitespaceStyle};
+    /// use nu_ansi_term::Color::{Blue, Red};
+    /// use nu_ansi_term::utils::unstyle;
+    ///
+    /// let line = AnsiStrings([Blue.paint("let x"), "\t= 1; ".into()]);
+    /// let shown = line.show_whitespace(&WhitespaceStyle::new());
+    /// assert_eq!(unstyle(&shown), "let·x→  =·1;·");
+    /// assert_eq!(
+    ///     shown.to_string(),
+    ///     "\x1b[34mlet\x1b[2m·\x1b[0m\x1b[34mx\x1b[0m\x1b[2m→  \x1b[0m=\x1b[2m·\x1b[0m1;\x1b[2;41m·\x1b[0m"
+    /// );
+    /// ```
+    pub fn show_whitespace(&self, theme: &WhitespaceStyle) -> AnsiStrings<'static> {
+        let segments = self.visible_segments(None);
+        let chars = WidthPolicy::new().styled_chars(&segments);
+        let trailing = trailing_whitespace(&chars);
+        let mut shown = Vec::with_capacity(chars.len());
+        let mut column = 0;
+        for ((c, style, width, ix), trailing) in chars.into_iter().zip(trailing) {
+            let glyph_style = if trailing {
+                style.merge(theme.glyph).merge(theme.trailing)
+            } else {
+                style.merge(theme.glyph)
+            };
+            match c {
+                ' ' => {
+                    shown.push((theme.space, glyph_style, ix));
+                    column += 1;
+                }
+                '\t' => {
+                    let stop = (column / theme.tab_width + 1) * theme.tab_width;
+                    shown.push((theme.tab, glyph_style, ix));
+                    shown.extend((column + 1..stop).map(|_| (' ', glyph_style, ix)));
+                    column = stop;
+                }
+                '\n' => {
+                    shown.push((c, style, ix));
+                    column = 0;
+                }
+                _ => {
+                    shown.push((c, style, ix));
+                    column += width;
+                }
+            }
+        }
+        AnsiStrings(paint_linked_runs(&segments, shown))
+    }
+
+    /// An owned copy of these strings with whitespace errors, as `git diff`
+    /// shows them, marked by laying `warning` (a background color, say) over
+    /// the style of their text: whitespace at the end of a line, and the
+    /// indentation of lines indented with a mix of tabs and spaces.
+    ///
+    /// As with [`wrap`](Self::wrap), only the visible text, its styles and
+    /// its links are kept.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::{AnsiStrings, Style};
+    /// use nu_ansi_term::Color::{Green, Red};
+    ///
+    /// let added = AnsiStrings([Green.paint("\t  if ok {  ")]);
+    /// assert_eq!(
+    ///     added.mark_whitespace_errors(Style::new().on(Red)).to_string(),
+    ///     "\x1b[41;32m\t  \x1b[0m\x1b[32mif ok {\x1b[41m  \x1b[0m"
+    /// );
+    /// ```
+    pub fn mark_whitespace_errors(&self, warning: Style) -> AnsiStrings<'static> {
+        let segments = self.visible_segments(None);
+        let chars = WidthPolicy::new().styled_chars(&segments);
+        let mut errors = trailing_whitespace(&chars);
+        let mut line_start = 0;
+        for line_end in (0..=chars.len()).filter(|&ix| ix == chars.len() || chars[ix].0 == '\n') {
+            let indent = chars[line_start..line_end]
+                .iter()
+                .take_while(|(c, ..)| is_blank(*c))
+                .count();
+            let indent = line_start..line_start + indent;
+            let mixed = chars[indent.clone()].iter().any(|(c, ..)| *c == ' ')
+                && chars[indent.clone()].iter().any(|(c, ..)| *c == '\t');
+            if mixed {
+                errors[indent].iter_mut().for_each(|error| *error = true);
+            }
+            line_start = line_end + 1;
+        }
+        AnsiStrings(paint_linked_runs(
+            &segments,
+            chars
+                .into_iter()
+                .zip(errors)
+                .map(|((c, style, _, ix), error)| {
+                    (c, if error { style.merge(warning) } else { style }, ix)
+                }),
+        ))
+    }
+
+    fn map_content_with(&self, f: &mut dyn FnMut(&str) -> Cow<'_, str>) -> AnsiStrings<'static> {
+        self.map_owned(|s| s.map_content_with(f))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Color::*;
+
+    #[test]
+    fn escapes_are_untouched() {
+        let text = "a\x1b[31mb\x1b]8;;https://x/Y\x1b\\c\x1b]2;t\x07d";
+        assert_eq!(
+            map_printable(text, &mut |run| Cow::Owned(run.to_uppercase())),
+            "A\x1b[31mB\x1b]8;;https://x/Y\x1b\\C\x1b]2;t\x07D"
+        );
+        let mut runs = Vec::new();
+        map_printable("\x1b[1mx\x1b[0m", &mut |run| {
+            runs.push(run.to_string());
+            Cow::Borrowed(run)
+        });
+        assert_eq!(runs, ["x"]);
+    }
+
+    #[test]
+    fn controls_and_nested_strings() {
+        let mut strings = AnsiStrings([
+            Green.paint(AnsiStrings([Yellow.paint("ß"), "i".into()])),
+            Red.paint("Ok"),
+        ]);
+        strings.push_control("\x1b[K");
+        assert_eq!(
+            strings.to_uppercase().to_string(),
+            AnsiStrings([
+                Green.paint(AnsiStrings([Yellow.paint("SS"), "I".into()])),
+                Red.paint("OK"),
+                AnsiString::control("\x1b[K"),
+            ])
+            .to_string()
+        );
+    }
+
+    #[test]
+    fn tabs_reach_the_next_stop() {
+        let text = AnsiStrings([Red.paint("ab\tc\t"), "\n日\td".into()]);
+        let theme = WhitespaceStyle::new()
+            .tab('>')
+            .tab_width(3)
+            .glyph(Style::new())
+            .trailing(Style::new().underline());
+        let shown = text.show_whitespace(&theme);
+        assert_eq!(crate::utils::unstyle(&shown), "ab>c> \n日>d");
+        let styles: Vec<_> = shown.iter().map(|s| *s.style_ref()).collect();
+        assert_eq!(styles, [Red.normal(), Red.underline(), Style::new()]);
+    }
+
+    #[test]
+    fn whitespace_errors_per_line() {
+        let text = AnsiStrings(["  \tx \n\t\ty\n \n".into(), Red.paint("  z")]);
+        let marked = text.mark_whitespace_errors(Style::new().underline());
+        let runs: Vec<_> = marked
+            .iter()
+            .map(|s| (s.unstyled(), *s.style_ref()))
+            .collect();
+        assert_eq!(
+            runs,
+            [
+                ("  \t".to_string(), Style::new().underline()),
+                ("x".to_string(), Style::new()),
+                (" ".to_string(), Style::new().underline()),
+                ("\n\t\ty\n".to_string(), Style::new()),
+                (" ".to_string(), Style::new().underline()),
+                ("\n".to_string(), Style::new()),
+                ("  z".to_string(), Red.normal()),
+            ]
+        );
+    }
+}
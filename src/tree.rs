@@ -0,0 +1,221 @@
+use crate::terminal::detect_width;
+use crate::width::{truncate_segments, WidthPolicy};
+use crate::{AnsiString, AnsiStrings, ContinuationMarker, Style};
+
+/// Guide drawn in front of a node which has later siblings.
+const BRANCH: &str = "├── ";
+/// Guide drawn in front of the last child of a node.
+const LAST_BRANCH: &str = "└── ";
+/// Guide continuing past a node which has later siblings.
+const PIPE: &str = "│   ";
+/// Guide continuing past the last child of a node.
+const SPACE: &str = "    ";
+
+/// A node of a tree to be rendered by a [`TreeRenderer`]: a styled label and
+/// any number of children.
+#[derive(Clone, Debug)]
+pub struct TreeNode<'a> {
+    label: AnsiString<'a>,
+    children: Vec<TreeNode<'a>>,
+}
+
+impl<'a> TreeNode<'a> {
+    /// Create a node without children.
+    pub fn new(label: impl Into<AnsiString<'a>>) -> Self {
+        Self {
+            label: label.into(),
+            children: Vec::new(),
+        }
+    }
+
+    /// Add a child to this node.
+    pub fn child(mut self, child: TreeNode<'a>) -> Self {
+        self.children.push(child);
+        self
+    }
+
+    /// Add a child to this node in place.
+    pub fn push(&mut self, child: TreeNode<'a>) {
+        self.children.push(child);
+    }
+
+    /// Get the label of this node.
+    pub fn label(&self) -> &AnsiString<'a> {
+        &self.label
+    }
+
+    /// Get the children of this node.
+    pub fn children(&self) -> &[TreeNode<'a>] {
+        &self.children
+    }
+}
+
+/// Renders [`TreeNode`]s with branch guides, one node per line.
+///
+/// # Examples
+///
+/// ```
+/// use nu_ansi_term::{TreeNode, TreeRenderer};
+/// use nu_ansi_term::Color::{Blue, Fixed};
+///
+/// let tree = TreeNode::new(Blue.bold().paint("src"))
+///     .child(TreeNode::new("lib.rs"))
+///     .child(TreeNode::new(Blue.bold().paint("tree")).child(TreeNode::new("mod.rs")));
+///
+/// let rendered = TreeRenderer::new()
+///     .guide_style(Fixed(244).normal())
+///     .width(40)
+///     .render(&tree);
+/// println!("{}", rendered);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct TreeRenderer {
+    guide_style: Style,
+    width: Option<usize>,
+    marker: ContinuationMarker,
+    width_policy: WidthPolicy,
+}
+
+impl Default for TreeRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TreeRenderer {
+    /// Create a renderer with unstyled guides, which truncates lines to the
+    /// detected terminal width (if any), marking them with `…`.
+    pub const fn new() -> Self {
+        Self {
+            guide_style: Style::new(),
+            width: None,
+            marker: ContinuationMarker::ELLIPSIS,
+            width_policy: WidthPolicy::new(),
+        }
+    }
+
+    /// Paint the branch guides with `style`.
+    pub const fn guide_style(mut self, style: Style) -> Self {
+        self.guide_style = style;
+        self
+    }
+
+    /// Truncate labels so that lines fit in `width` columns, instead of
+    /// the detected terminal width.
+    pub const fn width(mut self, width: usize) -> Self {
+        self.width = Some(width);
+        self
+    }
+
+    /// End truncated labels with `marker` instead of `…`.
+    pub const fn marker(mut self, marker: ContinuationMarker) -> Self {
+        self.marker = marker;
+        self
+    }
+
+    /// Measure guides and labels with `policy`.
+    pub const fn width_policy(mut self, policy: WidthPolicy) -> Self {
+        self.width_policy = policy;
+        self
+    }
+
+    /// Render the tree rooted at `root`, with lines separated by `\n`.
+    pub fn render<'a>(&self, root: &TreeNode<'a>) -> AnsiStrings<'a> {
+        let width = self.width.or_else(detect_width);
+        let mut segments = Vec::new();
+        self.render_node(root, &mut String::new(), None, width, &mut segments);
+        AnsiStrings(segments)
+    }
+
+    fn render_node<'a>(
+        &self,
+        node: &TreeNode<'a>,
+        prefix: &mut String,
+        is_last: Option<bool>,
+        width: Option<usize>,
+        segments: &mut Vec<AnsiString<'a>>,
+    ) {
+        if !segments.is_empty() {
+            segments.push(AnsiString::from("\n"));
+        }
+        let mut guides = prefix.clone();
+        match is_last {
+            Some(true) => guides.push_str(LAST_BRANCH),
+            Some(false) => guides.push_str(BRANCH),
+            None => {}
+        }
+        let guides_width = self.width_policy.str_width(&guides);
+        if !guides.is_empty() {
+            segments.push(self.guide_style.paint(guides));
+        }
+        match width {
+            Some(width) if guides_width + self.width_policy.segment_width(&node.label) > width => {
+                let available = width.saturating_sub(guides_width);
+                segments.extend(truncate_segments(
+                    std::slice::from_ref(&node.label),
+                    available,
+                    &self.marker,
+                    self.width_policy,
+                ));
+            }
+            _ => segments.push(node.label.clone()),
+        }
+
+        let prefix_len = prefix.len();
+        match is_last {
+            Some(true) => prefix.push_str(SPACE),
+            Some(false) => prefix.push_str(PIPE),
+            None => {}
+        }
+        let count = node.children.len();
+        for (ix, child) in node.children.iter().enumerate() {
+            self.render_node(child, prefix, Some(ix + 1 == count), width, segments);
+        }
+        prefix.truncate(prefix_len);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::unstyle;
+    use crate::Color::*;
+
+    fn sample() -> TreeNode<'static> {
+        TreeNode::new(Blue.paint("root"))
+            .child(
+                TreeNode::new("a")
+                    .child(TreeNode::new("a1"))
+                    .child(TreeNode::new("a2")),
+            )
+            .child(TreeNode::new("b").child(TreeNode::new(Red.paint("b1"))))
+    }
+
+    #[test]
+    fn guides() {
+        let rendered = TreeRenderer::new().width(80).render(&sample());
+        assert_eq!(
+            unstyle(&rendered),
+            "root\n├── a\n│   ├── a1\n│   └── a2\n└── b\n    └── b1"
+        );
+    }
+
+    #[test]
+    fn guide_style() {
+        let rendered = TreeRenderer::new()
+            .guide_style(Style::new().dimmed())
+            .width(80)
+            .render(&TreeNode::new("r").child(TreeNode::new("c")));
+        let segments: Vec<_> = rendered.iter().collect();
+        assert_eq!(segments[2].style_ref(), &Style::new().dimmed());
+        assert_eq!(segments[3].style_ref(), &Style::new());
+    }
+
+    #[test]
+    fn truncation() {
+        let tree = TreeNode::new("root")
+            .child(TreeNode::new(Green.paint("a long label")).child(TreeNode::new("deep")));
+        let rendered = TreeRenderer::new().width(10).render(&tree);
+        assert_eq!(unstyle(&rendered), "root\n└── a lon…\n    └── d…");
+    }
+}
@@ -1,4 +1,6 @@
 use crate::display::AnsiStrings;
+use std::ptr;
+use std::sync::atomic::{AtomicPtr, Ordering};
 
 /// Return a substring of the given AnsiStrings sequence, while keeping the formatting.
 pub fn sub_string<'a>(start: usize, len: usize, strs: &AnsiStrings) -> AnsiStrings<'a> {
@@ -6,7 +8,7 @@ pub fn sub_string<'a>(start: usize, len: usize, strs: &AnsiStrings) -> AnsiStrin
     let mut pos = start;
     let mut len_rem = len;
 
-    for i in strs.iter() {
+    for i in strs.iter().filter(|i| !i.is_control()) {
         let content = i.content.to_string();
         let frag_len = content.len();
         if pos >= frag_len {
@@ -34,10 +36,13 @@ pub fn sub_string<'a>(start: usize, len: usize, strs: &AnsiStrings) -> AnsiStrin
 }
 
 /// Return a concatenated copy of `strs` without the formatting, as an allocated `String`.
+///
+/// Control segments (see [`AnsiGenericString::control`](crate::AnsiGenericString::control))
+/// are left out.
 pub fn unstyle(strs: &AnsiStrings) -> String {
     let mut s = String::new();
 
-    for i in strs.iter() {
+    for i in strs.iter().filter(|i| !i.is_control()) {
         s += &i.content.to_string();
     }
 
@@ -47,12 +52,37 @@ pub fn unstyle(strs: &AnsiStrings) -> String {
 /// Return the unstyled length of AnsiStrings. This is equaivalent to `unstyle(strs).len()`.
 pub fn unstyled_len(strs: &AnsiStrings) -> usize {
     let mut l = 0;
-    for i in strs.iter() {
+    for i in strs.iter().filter(|i| !i.is_control()) {
         l += i.content.to_string().len();
     }
     l
 }
 
+/// The value stored in `slot`, created with `init` the first time it is
+/// needed. Once stored, the value lives as long as the program, so that
+/// globals which cannot be built in a `static` can be shared.
+pub(crate) fn leaked_once<T>(slot: &AtomicPtr<T>, init: impl FnOnce() -> T) -> &'static T {
+    let mut value = slot.load(Ordering::Acquire);
+    if value.is_null() {
+        let new = Box::into_raw(Box::new(init()));
+        value = match slot.compare_exchange(
+            ptr::null_mut(),
+            new,
+            Ordering::AcqRel,
+            Ordering::Acquire,
+        ) {
+            Ok(_) => new,
+            Err(existing) => {
+                // Another thread stored its value first.
+                drop(unsafe { Box::from_raw(new) });
+                existing
+            }
+        };
+    }
+    // Stored values are never freed or replaced.
+    unsafe { &*value }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -0,0 +1,1461 @@
+use crate::write::Content;
+use crate::{
+    AnsiByteString, AnsiByteStrings, AnsiString, AnsiStrings, OSControl, Style, TerminalProfile,
+};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt::{self, Write};
+
+/// A marker drawn where text has been cut off, or at the end of a line which
+/// was wrapped onto the next one (or in a gutter before the next one, see
+/// [`MarkerPlacement`]). Its width is taken into account when truncating or
+/// wrapping.
+///
+/// The marker is painted in the style of the text it follows, unless it has
+/// a [`style`](ContinuationMarker::style) of its own (to dim it, for
+/// instance).
+///
+/// # Examples
+///
+/// ```
+/// use nu_ansi_term::{ContinuationMarker, Style, TerminalProfile};
+///
+/// let marker = ContinuationMarker::ELLIPSIS
+///     .style(Style::new().dimmed())
+///     .for_profile(&TerminalProfile::ascii());
+/// assert_eq!(marker.text(), "...");
+/// assert_eq!(marker.width(), 3);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ContinuationMarker {
+    text: &'static str,
+    ascii: &'static str,
+    style: Option<Style>,
+}
+
+impl ContinuationMarker {
+    /// `…`, falling back to `...`: for truncated text.
+    pub const ELLIPSIS: ContinuationMarker = ContinuationMarker::new("…").ascii("...");
+
+    /// `↪`, falling back to `\`: for wrapped lines.
+    pub const WRAP_ARROW: ContinuationMarker = ContinuationMarker::new("↪").ascii("\\");
+
+    /// A marker drawing `text`, which is also used on ASCII-only terminals
+    /// unless an [`ascii`](Self::ascii) fallback is given.
+    pub const fn new(text: &'static str) -> Self {
+        Self {
+            text,
+            ascii: text,
+            style: None,
+        }
+    }
+
+    /// Draw `fallback` instead on terminals which only support ASCII.
+    pub const fn ascii(mut self, fallback: &'static str) -> Self {
+        self.ascii = fallback;
+        self
+    }
+
+    /// Paint the marker with `style`, instead of the style of the text it
+    /// follows.
+    pub const fn style(mut self, style: Style) -> Self {
+        self.style = Some(style);
+        self
+    }
+
+    /// Use the ASCII fallback if `profile` does not support unicode.
+    pub const fn for_profile(mut self, profile: &TerminalProfile) -> Self {
+        if !profile.unicode {
+            self.text = self.ascii;
+        }
+        self
+    }
+
+    /// Use the ASCII fallback if the detected terminal does not support
+    /// unicode. See [`TerminalProfile::detect`].
+    pub fn detect(self) -> Self {
+        self.for_profile(&TerminalProfile::detect())
+    }
+
+    /// The text of the marker.
+    pub const fn text(&self) -> &'static str {
+        self.text
+    }
+
+    /// The style of the marker, if it does not take the style of the text
+    /// it follows.
+    pub const fn marker_style(&self) -> Option<Style> {
+        self.style
+    }
+
+    /// Number of terminal columns occupied by the marker, under the default
+    /// [`WidthPolicy`].
+    pub fn width(&self) -> usize {
+        WidthPolicy::new().str_width(self.text)
+    }
+
+    /// Paint the marker after text in `text_style`.
+    pub fn paint<'a>(&self, text_style: Style) -> AnsiString<'a> {
+        self.style.unwrap_or(text_style).paint(self.text)
+    }
+}
+
+/// Where a [`ContinuationMarker`] goes on the rows of wrapped text.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MarkerPlacement {
+    /// At the end of each row which is wrapped onto the next one.
+    LineEnd,
+
+    /// In a gutter at the start of every row, as large as the marker: on
+    /// the rows continuing a wrapped line, and blank on the others.
+    Gutter,
+}
+
+/// Rules for the number of terminal columns occupied by characters whose
+/// width terminals disagree on, used by every renderer which measures,
+/// wraps, truncates or aligns text.
+///
+/// By default emoji are two columns wide (also when made so by the emoji
+/// variation selector, VS16), and characters of ambiguous East Asian width
+/// (box drawing, Greek and Cyrillic letters, …) are one column wide. East
+/// Asian wide characters are always two columns wide, and combining marks,
+/// zero width characters and bidirectional marks occupy no columns.
+///
+/// # Examples
+///
+/// ```
+/// use nu_ansi_term::WidthPolicy;
+///
+/// let policy = WidthPolicy::new();
+/// assert_eq!(policy.str_width("日本"), 4);
+/// assert_eq!(policy.str_width("🚀 go"), 5);
+/// assert_eq!(policy.str_width("\u{2764}\u{FE0F}"), 2);
+///
+/// let legacy = WidthPolicy::new().emoji_width(1).ambiguous_width(2).vs16_widens(false);
+/// assert_eq!(legacy.str_width("🚀 go"), 4);
+/// assert_eq!(legacy.str_width("\u{2764}\u{FE0F}"), 1);
+/// assert_eq!(legacy.str_width("αβ"), 4);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WidthPolicy {
+    emoji_width: usize,
+    ambiguous_width: usize,
+    vs16_widens: bool,
+    #[cfg(feature = "unicode-normalization")]
+    normalize: bool,
+}
+
+impl Default for WidthPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WidthPolicy {
+    /// The policy of most current terminals: two column emoji, one column
+    /// ambiguous characters, and VS16 widening the character before it.
+    pub const fn new() -> Self {
+        Self {
+            emoji_width: 2,
+            ambiguous_width: 1,
+            vs16_widens: true,
+            #[cfg(feature = "unicode-normalization")]
+            normalize: false,
+        }
+    }
+
+    /// Set the number of columns occupied by emoji.
+    pub const fn emoji_width(mut self, width: usize) -> Self {
+        self.emoji_width = width;
+        self
+    }
+
+    /// Set the number of columns occupied by characters of ambiguous East
+    /// Asian width, usually 1 (or 2 in CJK locales).
+    pub const fn ambiguous_width(mut self, width: usize) -> Self {
+        self.ambiguous_width = width;
+        self
+    }
+
+    /// Set whether the emoji variation selector (U+FE0F) widens a one
+    /// column character before it to the width of emoji.
+    pub const fn vs16_widens(mut self, widens: bool) -> Self {
+        self.vs16_widens = widens;
+        self
+    }
+
+    /// Set whether text is normalized to NFC before it is measured (and cut),
+    /// so that text in decomposed form, such as conjoining Hangul jamo, is
+    /// measured as the composed characters terminals show.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::WidthPolicy;
+    ///
+    /// let decomposed = "\u{1112}\u{1161}\u{11AB}";
+    /// assert_eq!(WidthPolicy::new().str_width(decomposed), 4);
+    /// assert_eq!(WidthPolicy::new().normalize(true).str_width(decomposed), 2);
+    /// assert_eq!(WidthPolicy::new().normalize(true).str_width("\u{D55C}"), 2);
+    /// ```
+    #[cfg(feature = "unicode-normalization")]
+    pub const fn normalize(mut self, normalize: bool) -> Self {
+        self.normalize = normalize;
+        self
+    }
+
+    /// `s` in NFC, if this policy normalizes text before measuring it.
+    fn normalized(self, s: &str) -> Cow<'_, str> {
+        #[cfg(feature = "unicode-normalization")]
+        if self.normalize {
+            use unicode_normalization::{is_nfc_quick, IsNormalized, UnicodeNormalization};
+            if is_nfc_quick(s.chars()) != IsNormalized::Yes {
+                return Cow::Owned(s.nfc().collect());
+            }
+        }
+        Cow::Borrowed(s)
+    }
+
+    /// Number of terminal columns occupied by `c` on its own.
+    pub fn char_width(self, c: char) -> usize {
+        if c.is_control() || is_bidi_format(c) || in_table(c, ZERO_WIDTH) {
+            0
+        } else if in_table(c, EMOJI) {
+            self.emoji_width
+        } else if in_table(c, WIDE) {
+            2
+        } else if in_table(c, AMBIGUOUS) {
+            self.ambiguous_width
+        } else {
+            1
+        }
+    }
+
+    /// Number of terminal columns occupied by `c`, following a character
+    /// which occupies `previous` columns.
+    fn width_after(self, c: char, previous: usize) -> usize {
+        if c == VS16 && self.vs16_widens && previous == 1 {
+            self.emoji_width.saturating_sub(previous)
+        } else {
+            self.char_width(c)
+        }
+    }
+
+    /// Number of terminal columns occupied by `s`, ignoring any ANSI escape
+    /// sequences (CSI and OSC) embedded in it.
+    pub fn str_width(self, s: &str) -> usize {
+        let s = self.normalized(s);
+        let mut width = 0;
+        let mut previous = 0;
+        let mut rest = &*s;
+        while let Some(c) = rest.chars().next() {
+            if c == '\x1B' {
+                rest = &rest[escape_len(rest)..];
+                continue;
+            }
+            previous = self.width_after(c, previous);
+            width += previous;
+            rest = &rest[c.len_utf8()..];
+        }
+        width
+    }
+
+    /// Shorten the visible text of `s` to at most `width` columns, keeping
+    /// every escape sequence in it (so that styles are still reset, and
+    /// hyperlinks closed, after the cut).
+    fn truncate_rendered(self, s: &str, width: usize) -> String {
+        let s = self.normalized(s);
+        let mut truncated = String::with_capacity(s.len());
+        let mut budget = width;
+        let mut previous = 0;
+        // A character which does not fit ends the text, even if a narrower
+        // one after it would fit.
+        let mut full = false;
+        let mut rest = &*s;
+        while let Some(c) = rest.chars().next() {
+            let len = if c == '\x1B' {
+                let len = escape_len(rest);
+                truncated.push_str(&rest[..len]);
+                len
+            } else {
+                previous = self.width_after(c, previous);
+                if !full && previous <= budget {
+                    budget -= previous;
+                    truncated.push(c);
+                } else {
+                    full = true;
+                }
+                c.len_utf8()
+            };
+            rest = &rest[len..];
+        }
+        truncated
+    }
+
+    /// Number of terminal columns occupied by the rendered form of `s`.
+    pub(crate) fn segment_width(self, s: &AnsiString<'_>) -> usize {
+        self.str_width(&s.to_string())
+    }
+
+    /// The visible characters of `segments`, with their style, their width
+    /// and the index of the segment they belong to.
+    pub(crate) fn styled_chars(
+        self,
+        segments: &[AnsiString<'_>],
+    ) -> Vec<(char, Style, usize, usize)> {
+        let mut previous = 0;
+        let mut chars = Vec::new();
+        for (ix, segment) in segments.iter().enumerate() {
+            let style = *segment.style_ref();
+            for c in self.normalized(&segment.content().to_string()).chars() {
+                previous = self.width_after(c, previous);
+                chars.push((c, style, previous, ix));
+            }
+        }
+        chars
+    }
+}
+
+impl<'a> AnsiString<'a> {
+    /// Number of terminal columns occupied by this string, ignoring escape
+    /// codes, by the rules of [`WidthPolicy::new`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::AnsiString;
+    /// use nu_ansi_term::Color::Red;
+    ///
+    /// let cell: AnsiString = Red.bold().paint("日本 ok");
+    /// assert_eq!(cell.width(), 7);
+    /// assert_eq!(cell.len_visible(), 5);
+    /// ```
+    pub fn width(&self) -> usize {
+        self.width_with(WidthPolicy::new())
+    }
+
+    /// Number of terminal columns occupied by this string, by the rules of
+    /// `policy`.
+    pub fn width_with(&self, policy: WidthPolicy) -> usize {
+        policy.str_width(&self.unstyled())
+    }
+
+    /// Number of characters of this string, ignoring escape codes.
+    pub fn len_visible(&self) -> usize {
+        self.unstyled().chars().count()
+    }
+}
+
+impl<'a> AnsiStrings<'a> {
+    /// Number of terminal columns occupied by these strings. See
+    /// [`AnsiString::width`].
+    pub fn width(&self) -> usize {
+        self.width_with(WidthPolicy::new())
+    }
+
+    /// Number of terminal columns occupied by these strings, by the rules of
+    /// `policy`.
+    pub fn width_with(&self, policy: WidthPolicy) -> usize {
+        policy.str_width(&self.unstyled())
+    }
+
+    /// Number of characters of these strings, ignoring escape codes.
+    pub fn len_visible(&self) -> usize {
+        self.unstyled().chars().count()
+    }
+
+    /// Number of bytes of the escape sequences written with these strings:
+    /// the codes between and around their segments, hyperlinks and control
+    /// segments. Together with the length of the text, this is the length
+    /// of what writing them writes, for output with a limited number of
+    /// bytes, such as a banner or a fixed-size status field.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::AnsiStrings;
+    /// use nu_ansi_term::Color::{Green, Red};
+    ///
+    /// let status = AnsiStrings([Red.paint("3 failed"), ", ".into(), Green.paint("9 ok")]);
+    /// assert_eq!(status.escape_overhead(), 18);
+    /// assert_eq!(
+    ///     status.escape_overhead() + status.unstyled().len(),
+    ///     status.to_string().len()
+    /// );
+    /// ```
+    pub fn escape_overhead(&self) -> usize {
+        self.to_string().len().saturating_sub(self.unstyled().len())
+    }
+}
+
+impl<'a> AnsiByteString<'a> {
+    /// Number of terminal columns occupied by these bytes, read as UTF-8
+    /// (where each invalid sequence occupies one column). See
+    /// [`AnsiString::width`].
+    pub fn width(&self) -> usize {
+        self.width_with(WidthPolicy::new())
+    }
+
+    /// Number of terminal columns occupied by these bytes, by the rules of
+    /// `policy`.
+    pub fn width_with(&self, policy: WidthPolicy) -> usize {
+        policy.str_width(&String::from_utf8_lossy(&self.unstyled()))
+    }
+
+    /// Number of bytes of this string, ignoring escape codes.
+    pub fn len_visible(&self) -> usize {
+        self.unstyled().len()
+    }
+}
+
+impl<'a> AnsiByteStrings<'a> {
+    /// Number of terminal columns occupied by these bytes. See
+    /// [`AnsiByteString::width`].
+    pub fn width(&self) -> usize {
+        self.width_with(WidthPolicy::new())
+    }
+
+    /// Number of terminal columns occupied by these bytes, by the rules of
+    /// `policy`.
+    pub fn width_with(&self, policy: WidthPolicy) -> usize {
+        policy.str_width(&String::from_utf8_lossy(&self.unstyled()))
+    }
+
+    /// Number of bytes of these strings, ignoring escape codes.
+    pub fn len_visible(&self) -> usize {
+        self.unstyled().len()
+    }
+}
+
+/// Split `s` into the characters a reader sees, approximately: each is a
+/// character together with the zero-width characters (combining marks,
+/// variation selectors, …) following it, and with the characters joined to
+/// it by zero-width joiners.
+pub(crate) fn clusters(s: &str) -> Vec<&str> {
+    let policy = WidthPolicy::new();
+    let mut clusters = Vec::new();
+    let mut start = 0;
+    let mut joined = false;
+    for (ix, c) in s.char_indices() {
+        let attached = ix > 0 && (joined || (!c.is_control() && policy.char_width(c) == 0));
+        if !attached && ix > start {
+            clusters.push(&s[start..ix]);
+            start = ix;
+        }
+        joined = c == ZWJ;
+    }
+    if start < s.len() {
+        clusters.push(&s[start..]);
+    }
+    clusters
+}
+
+/// Length in bytes of the escape sequence at the start of `s`, which starts
+/// with `ESC`.
+pub(crate) fn escape_len(s: &str) -> usize {
+    let mut chars = s.char_indices().skip(1).peekable();
+    match chars.next() {
+        // CSI: parameters and intermediates, terminated by a final byte in
+        // the range `@` to `~`.
+        Some((_, '[')) => chars
+            .find(|(_, c)| ('@'..='~').contains(c))
+            .map_or(s.len(), |(ix, _)| ix + 1),
+        // OSC: terminated by BEL or by ST (`ESC \`).
+        Some((_, ']')) => {
+            while let Some((ix, c)) = chars.next() {
+                if c == '\x07' {
+                    return ix + 1;
+                }
+                if c == '\x1B' && matches!(chars.peek(), Some((_, '\\'))) {
+                    return ix + 2;
+                }
+            }
+            s.len()
+        }
+        Some((ix, c)) => ix + c.len_utf8(),
+        None => s.len(),
+    }
+}
+
+/// Write `rendered` (text which may contain escape sequences) to `f`,
+/// honouring the width, fill, alignment and precision of `f` by the columns
+/// its visible text occupies. Padding is written outside of any styling.
+pub(crate) fn pad_rendered(f: &mut fmt::Formatter<'_>, rendered: &str) -> fmt::Result {
+    let policy = WidthPolicy::new();
+    let text = match f.precision() {
+        Some(precision) => Cow::Owned(policy.truncate_rendered(rendered, precision)),
+        None => Cow::Borrowed(rendered),
+    };
+    let padding = f
+        .width()
+        .map_or(0, |width| width.saturating_sub(policy.str_width(&text)));
+    let (before, after) = match f.align() {
+        Some(fmt::Alignment::Right) => (padding, 0),
+        Some(fmt::Alignment::Center) => (padding / 2, padding - padding / 2),
+        Some(fmt::Alignment::Left) | None => (0, padding),
+    };
+    let fill = f.fill();
+    for _ in 0..before {
+        f.write_char(fill)?;
+    }
+    f.write_str(&text)?;
+    for _ in 0..after {
+        f.write_char(fill)?;
+    }
+    Ok(())
+}
+
+/// A small cache of the widths of recently measured text, for render loops
+/// which measure the same segments (prompt parts, table cells, …) on every
+/// frame.
+///
+/// Text is looked up by its content, so measuring text which was measured
+/// recently does not walk its characters again. Once the cache holds
+/// `capacity` entries, the least recently used one is evicted. The
+/// [`stats`](MeasureCache::stats) of the cache tell how often measurements
+/// were served from it, for tuning its capacity.
+///
+/// # Examples
+///
+/// ```
+/// use nu_ansi_term::{MeasureCache, WidthPolicy};
+/// use nu_ansi_term::Color::Blue;
+///
+/// let mut cache = MeasureCache::new(64).policy(WidthPolicy::new().ambiguous_width(2));
+/// for _frame in 0..10 {
+///     assert_eq!(cache.width(&Blue.paint("~/src/nu-ansi-term")), 18);
+///     assert_eq!(cache.str_width("λ "), 3);
+/// }
+/// let stats = cache.stats();
+/// assert_eq!((stats.hits, stats.misses), (18, 2));
+/// assert_eq!(stats.hit_rate(), 0.9);
+/// ```
+#[derive(Clone, Debug)]
+pub struct MeasureCache {
+    policy: WidthPolicy,
+    capacity: usize,
+    /// The position in `entries` of each text stored.
+    index: HashMap<String, usize>,
+    /// The widths stored, linked from the most to the least recently used.
+    entries: Vec<CachedWidth>,
+    newest: usize,
+    oldest: usize,
+    stats: MeasureStats,
+}
+
+/// Marks the end of the list of a [`MeasureCache`]'s entries.
+const NONE: usize = usize::MAX;
+
+/// The width of a text stored in a [`MeasureCache`], with its neighbours in
+/// the order of use.
+#[derive(Clone, Debug)]
+struct CachedWidth {
+    text: String,
+    width: usize,
+    newer: usize,
+    older: usize,
+}
+
+/// How a [`MeasureCache`] has been used.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MeasureStats {
+    /// Measurements served from the cache.
+    pub hits: u64,
+    /// Measurements of text which was not in the cache.
+    pub misses: u64,
+    /// Entries evicted to make room for new ones.
+    pub evictions: u64,
+}
+
+impl MeasureStats {
+    /// The fraction of measurements served from the cache, or 0 if there
+    /// were none.
+    pub fn hit_rate(&self) -> f64 {
+        match self.hits + self.misses {
+            0 => 0.0,
+            total => self.hits as f64 / total as f64,
+        }
+    }
+}
+
+impl MeasureCache {
+    /// Create an empty cache holding at most `capacity` widths, measured
+    /// with the default [`WidthPolicy`].
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            policy: WidthPolicy::new(),
+            capacity,
+            index: HashMap::with_capacity(capacity),
+            entries: Vec::with_capacity(capacity),
+            newest: NONE,
+            oldest: NONE,
+            stats: MeasureStats::default(),
+        }
+    }
+
+    /// Measure text with `policy`. Widths measured with the previous policy
+    /// are forgotten.
+    pub fn policy(mut self, policy: WidthPolicy) -> Self {
+        if policy != self.policy {
+            self.clear();
+        }
+        self.policy = policy;
+        self
+    }
+
+    /// Number of terminal columns occupied by `s` (see
+    /// [`WidthPolicy::str_width`]).
+    pub fn str_width(&mut self, s: &str) -> usize {
+        self.lookup(s)
+    }
+
+    /// Number of terminal columns occupied by the rendered form of `s`.
+    ///
+    /// Text content is looked up as it is; other content is only rendered
+    /// to be looked up.
+    pub fn width(&mut self, s: &AnsiString<'_>) -> usize {
+        let text = match (s.content(), s.oscontrol()) {
+            (_, Some(control)) if !matches!(control, OSControl::Link { .. }) => None,
+            (Content::StrLike(text), _) => Some(&**text),
+            (Content::Shared(text), _) => Some(&**text),
+            _ => None,
+        };
+        match text {
+            // Escape codes have no width, so the text is as wide as the
+            // string is once rendered.
+            Some(text) => self.lookup(text),
+            None => self.lookup(&s.to_string()),
+        }
+    }
+
+    /// Total number of terminal columns occupied by the segments of
+    /// `strings`, each of them measured through the cache.
+    pub fn strings_width(&mut self, strings: &AnsiStrings<'_>) -> usize {
+        strings.iter().map(|s| self.width(s)).sum()
+    }
+
+    /// The number of widths stored.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether no widths are stored.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// How this cache has been used since it was created, or since its
+    /// stats were last reset.
+    pub fn stats(&self) -> MeasureStats {
+        self.stats
+    }
+
+    /// Start counting hits, misses and evictions from zero again.
+    pub fn reset_stats(&mut self) {
+        self.stats = MeasureStats::default();
+    }
+
+    /// Forget all stored widths.
+    pub fn clear(&mut self) {
+        self.index.clear();
+        self.entries.clear();
+        self.newest = NONE;
+        self.oldest = NONE;
+    }
+
+    /// The width of `text`, measured unless it is stored.
+    fn lookup(&mut self, text: &str) -> usize {
+        if let Some(&ix) = self.index.get(text) {
+            self.stats.hits += 1;
+            self.unlink(ix);
+            self.link_newest(ix);
+            return self.entries[ix].width;
+        }
+
+        self.stats.misses += 1;
+        let width = self.policy.str_width(text);
+        if self.capacity == 0 {
+            return width;
+        }
+        let entry = CachedWidth {
+            text: text.to_owned(),
+            width,
+            newer: NONE,
+            older: NONE,
+        };
+        let ix = if self.entries.len() < self.capacity {
+            self.entries.push(entry);
+            self.entries.len() - 1
+        } else {
+            let ix = self.oldest;
+            self.unlink(ix);
+            let evicted = std::mem::replace(&mut self.entries[ix], entry);
+            self.index.remove(&evicted.text);
+            self.stats.evictions += 1;
+            ix
+        };
+        self.index.insert(text.to_owned(), ix);
+        self.link_newest(ix);
+        width
+    }
+
+    /// Take the entry at `ix` out of the order of use.
+    fn unlink(&mut self, ix: usize) {
+        let CachedWidth { newer, older, .. } = self.entries[ix];
+        match newer {
+            NONE => self.newest = older,
+            newer => self.entries[newer].older = older,
+        }
+        match older {
+            NONE => self.oldest = newer,
+            older => self.entries[older].newer = newer,
+        }
+    }
+
+    /// Put the entry at `ix` first in the order of use.
+    fn link_newest(&mut self, ix: usize) {
+        self.entries[ix].newer = NONE;
+        self.entries[ix].older = self.newest;
+        match self.newest {
+            NONE => self.oldest = ix,
+            newest => self.entries[newest].newer = ix,
+        }
+        self.newest = ix;
+    }
+}
+
+/// The emoji variation selector, requesting emoji presentation.
+const VS16: char = '\u{FE0F}';
+
+/// The zero-width joiner, which joins the characters around it into one.
+const ZWJ: char = '\u{200D}';
+
+/// Whether `c` falls in one of the sorted, inclusive `ranges`.
+fn in_table(c: char, ranges: &[(u32, u32)]) -> bool {
+    let c = c as u32;
+    ranges
+        .binary_search_by(|&(start, end)| {
+            if end < c {
+                std::cmp::Ordering::Less
+            } else if start > c {
+                std::cmp::Ordering::Greater
+            } else {
+                std::cmp::Ordering::Equal
+            }
+        })
+        .is_ok()
+}
+
+/// Combining marks, variation selectors and other characters which occupy
+/// no columns of their own.
+const ZERO_WIDTH: &[(u32, u32)] = &[
+    (0x00AD, 0x00AD),
+    (0x0300, 0x036F),
+    (0x0483, 0x0489),
+    (0x0591, 0x05BD),
+    (0x0610, 0x061A),
+    (0x064B, 0x065F),
+    (0x0E31, 0x0E31),
+    (0x0E34, 0x0E3A),
+    (0x1AB0, 0x1AFF),
+    (0x1DC0, 0x1DFF),
+    (0x200B, 0x200D),
+    (0x20D0, 0x20FF),
+    (0xFE00, 0xFE0F),
+    (0xFE20, 0xFE2F),
+    (0xFEFF, 0xFEFF),
+    (0xE0100, 0xE01EF),
+];
+
+/// Characters which are displayed as emoji by default.
+const EMOJI: &[(u32, u32)] = &[
+    (0x231A, 0x231B),
+    (0x23E9, 0x23EC),
+    (0x23F0, 0x23F0),
+    (0x23F3, 0x23F3),
+    (0x25FD, 0x25FE),
+    (0x2614, 0x2615),
+    (0x2648, 0x2653),
+    (0x267F, 0x267F),
+    (0x2693, 0x2693),
+    (0x26A1, 0x26A1),
+    (0x26AA, 0x26AB),
+    (0x26BD, 0x26BE),
+    (0x26C4, 0x26C5),
+    (0x26CE, 0x26CE),
+    (0x26D4, 0x26D4),
+    (0x26EA, 0x26EA),
+    (0x26F2, 0x26F3),
+    (0x26F5, 0x26F5),
+    (0x26FA, 0x26FA),
+    (0x26FD, 0x26FD),
+    (0x2705, 0x2705),
+    (0x270A, 0x270B),
+    (0x2728, 0x2728),
+    (0x274C, 0x274C),
+    (0x274E, 0x274E),
+    (0x2753, 0x2755),
+    (0x2757, 0x2757),
+    (0x2795, 0x2797),
+    (0x27B0, 0x27B0),
+    (0x27BF, 0x27BF),
+    (0x2B1B, 0x2B1C),
+    (0x2B50, 0x2B50),
+    (0x2B55, 0x2B55),
+    (0x1F004, 0x1F004),
+    (0x1F0CF, 0x1F0CF),
+    (0x1F18E, 0x1F18E),
+    (0x1F191, 0x1F19A),
+    (0x1F1E6, 0x1F1FF),
+    (0x1F300, 0x1F64F),
+    (0x1F680, 0x1F6FF),
+    (0x1F7E0, 0x1F7EB),
+    (0x1F90C, 0x1F9FF),
+    (0x1FA70, 0x1FAFF),
+];
+
+/// East Asian wide and fullwidth characters.
+const WIDE: &[(u32, u32)] = &[
+    (0x1100, 0x115F),
+    (0x2E80, 0x303E),
+    (0x3041, 0x33FF),
+    (0x3400, 0x4DBF),
+    (0x4E00, 0x9FFF),
+    (0xA000, 0xA4CF),
+    (0xA960, 0xA97F),
+    (0xAC00, 0xD7A3),
+    (0xF900, 0xFAFF),
+    (0xFE10, 0xFE19),
+    (0xFE30, 0xFE6F),
+    (0xFF00, 0xFF60),
+    (0xFFE0, 0xFFE6),
+    (0x20000, 0x2FFFD),
+    (0x30000, 0x3FFFD),
+];
+
+/// Characters of ambiguous East Asian width.
+const AMBIGUOUS: &[(u32, u32)] = &[
+    (0x00A1, 0x00A1),
+    (0x00A4, 0x00A4),
+    (0x00A7, 0x00A8),
+    (0x00AA, 0x00AA),
+    (0x00AE, 0x00AE),
+    (0x00B0, 0x00B4),
+    (0x00B6, 0x00BA),
+    (0x00BC, 0x00BF),
+    (0x00C6, 0x00C6),
+    (0x00D0, 0x00D0),
+    (0x00D7, 0x00D8),
+    (0x00DE, 0x00E1),
+    (0x00E6, 0x00E6),
+    (0x00E8, 0x00EA),
+    (0x00EC, 0x00ED),
+    (0x00F0, 0x00F0),
+    (0x00F2, 0x00F3),
+    (0x00F7, 0x00FA),
+    (0x00FC, 0x00FC),
+    (0x00FE, 0x00FE),
+    (0x0391, 0x03A9),
+    (0x03B1, 0x03C9),
+    (0x0401, 0x0401),
+    (0x0410, 0x044F),
+    (0x0451, 0x0451),
+    (0x2010, 0x2010),
+    (0x2013, 0x2016),
+    (0x2018, 0x2019),
+    (0x201C, 0x201D),
+    (0x2020, 0x2022),
+    (0x2024, 0x2027),
+    (0x2030, 0x2030),
+    (0x2032, 0x2033),
+    (0x2035, 0x2035),
+    (0x203B, 0x203B),
+    (0x203E, 0x203E),
+    (0x20AC, 0x20AC),
+    (0x2103, 0x2103),
+    (0x2109, 0x2109),
+    (0x2113, 0x2113),
+    (0x2116, 0x2116),
+    (0x2121, 0x2122),
+    (0x2126, 0x2126),
+    (0x212B, 0x212B),
+    (0x2153, 0x2154),
+    (0x215B, 0x215E),
+    (0x2160, 0x216B),
+    (0x2170, 0x2179),
+    (0x2190, 0x2199),
+    (0x21D2, 0x21D2),
+    (0x21D4, 0x21D4),
+    (0x2200, 0x22FF),
+    (0x2460, 0x24E9),
+    (0x24EB, 0x254B),
+    (0x2550, 0x2573),
+    (0x2580, 0x258F),
+    (0x2592, 0x2595),
+    (0x25A0, 0x25A1),
+    (0x25A3, 0x25A9),
+    (0x25B2, 0x25B3),
+    (0x25B6, 0x25B7),
+    (0x25BC, 0x25BD),
+    (0x25C0, 0x25C1),
+    (0x25C6, 0x25C8),
+    (0x25CB, 0x25CB),
+    (0x25CE, 0x25D1),
+    (0x25E2, 0x25E5),
+    (0x25EF, 0x25EF),
+    (0x2605, 0x2606),
+    (0x2609, 0x2609),
+    (0x260E, 0x260F),
+    (0x261C, 0x261C),
+    (0x261E, 0x261E),
+    (0x2640, 0x2640),
+    (0x2642, 0x2642),
+    (0x2660, 0x2661),
+    (0x2663, 0x2665),
+    (0x2667, 0x266A),
+    (0x266C, 0x266D),
+    (0x266F, 0x266F),
+    (0xE000, 0xF8FF),
+    (0xFFFD, 0xFFFD),
+];
+
+/// Whether `c` is one of the invisible marks, embeddings and isolates which
+/// control the layout of bidirectional text.
+fn is_bidi_format(c: char) -> bool {
+    matches!(
+        c,
+        '\u{061C}' | '\u{200E}' | '\u{200F}' | '\u{202A}'..='\u{202E}' | '\u{2066}'..='\u{2069}'
+    )
+}
+
+/// How to keep right-to-left (Arabic, Hebrew, …) content from visually
+/// swapping places with the padding and borders around it, when it is
+/// aligned or padded. The marks added are not counted as occupying columns.
+///
+/// # Examples
+///
+/// ```
+/// use nu_ansi_term::{AnsiString, BidiIsolation};
+///
+/// let cell = [AnsiString::from("שלום")];
+/// assert_eq!(BidiIsolation::Mark.apply(&cell).to_string(), "שלום\u{200E}");
+/// assert_eq!(
+///     BidiIsolation::Isolate.apply(&cell).to_string(),
+///     "\u{2068}שלום\u{2069}"
+/// );
+/// ```
+#[cfg(feature = "unicode-bidi")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BidiIsolation {
+    /// End content whose last strongly directional character is
+    /// right-to-left with a left-to-right mark (U+200E), so that what follows
+    /// it is laid out left to right.
+    Mark,
+    /// Wrap content containing right-to-left characters in a first strong
+    /// isolate (U+2068 … U+2069), so that it does not affect the layout of
+    /// its surroundings at all. Not every terminal supports isolates.
+    Isolate,
+}
+
+#[cfg(feature = "unicode-bidi")]
+impl BidiIsolation {
+    /// Isolate the content of `segments`, if it contains right-to-left text.
+    pub fn apply<'a>(self, segments: &[AnsiString<'a>]) -> crate::AnsiStrings<'a> {
+        crate::AnsiStrings(self.isolate(segments.to_vec()))
+    }
+
+    pub(crate) fn isolate<'a>(self, mut segments: Vec<AnsiString<'a>>) -> Vec<AnsiString<'a>> {
+        use unicode_bidi::{bidi_class, BidiClass};
+
+        let classes: Vec<BidiClass> = segments
+            .iter()
+            .filter(|segment| segment.scheduled_style().is_some())
+            .flat_map(|segment| segment.content().to_string().chars().collect::<Vec<_>>())
+            .map(bidi_class)
+            .filter(|class| matches!(class, BidiClass::L | BidiClass::R | BidiClass::AL))
+            .collect();
+        let is_rtl = |class: &BidiClass| matches!(class, BidiClass::R | BidiClass::AL);
+        match self {
+            BidiIsolation::Mark if classes.last().map_or(false, is_rtl) => {
+                segments.push(AnsiString::from("\u{200E}"));
+            }
+            BidiIsolation::Isolate if classes.iter().any(is_rtl) => {
+                segments.insert(0, AnsiString::from("\u{2068}"));
+                segments.push(AnsiString::from("\u{2069}"));
+            }
+            _ => {}
+        }
+        segments
+    }
+}
+
+/// Paint runs of consecutive characters sharing a style.
+pub(crate) fn paint_runs<'a>(
+    chars: impl IntoIterator<Item = (char, Style)>,
+) -> Vec<AnsiString<'a>> {
+    let mut painted: Vec<AnsiString<'a>> = Vec::new();
+    let mut run = String::new();
+    let mut run_style = None;
+    for (c, style) in chars {
+        if run_style != Some(style) {
+            if let Some(run_style) = run_style {
+                painted.push(run_style.paint(std::mem::take(&mut run)));
+            }
+            run_style = Some(style);
+        }
+        run.push(c);
+    }
+    if let Some(run_style) = run_style {
+        painted.push(run_style.paint(run));
+    }
+    painted
+}
+
+/// The URL and parameters of a link.
+pub(crate) type LinkTarget = (String, Vec<(String, String)>);
+
+/// The link of `segment`, if it has one.
+pub(crate) fn link_target(segment: &AnsiString<'_>) -> Option<LinkTarget> {
+    segment
+        .url_string()
+        .map(|url| (url.to_string(), segment.link_params().to_vec()))
+}
+
+/// `painted`, linking to `target` if there is one.
+pub(crate) fn linked<'a>(painted: AnsiString<'a>, target: Option<&LinkTarget>) -> AnsiString<'a> {
+    match target {
+        Some((url, params)) => params.iter().fold(
+            painted.hyperlink_content(url.clone()),
+            |painted, (key, value)| painted.link_param(key.clone(), value.clone()),
+        ),
+        None => painted,
+    }
+}
+
+/// Paint runs of consecutive characters sharing a style and a link, each
+/// character linking where the segment of `segments` at its index does.
+pub(crate) fn paint_linked_runs<'a>(
+    segments: &[AnsiString<'_>],
+    chars: impl IntoIterator<Item = (char, Style, usize)>,
+) -> Vec<AnsiString<'a>> {
+    let targets: Vec<Option<LinkTarget>> = segments.iter().map(link_target).collect();
+    let mut painted: Vec<AnsiString<'a>> = Vec::new();
+    let mut run = String::new();
+    let mut run_key: Option<(Style, Option<&LinkTarget>)> = None;
+    for (c, style, ix) in chars {
+        let key = (style, targets.get(ix).and_then(Option::as_ref));
+        if run_key != Some(key) {
+            if let Some((style, target)) = run_key {
+                painted.push(linked(style.paint(std::mem::take(&mut run)), target));
+            }
+            run_key = Some(key);
+        }
+        run.push(c);
+    }
+    if let Some((style, target)) = run_key {
+        painted.push(linked(style.paint(run), target));
+    }
+    painted
+}
+
+/// Wrap the visible text of `segments` into lines of at most `width` columns,
+/// breaking at whitespace where possible and keeping the style of every
+/// character. Explicit newlines always start a new line; lines which are
+/// wrapped onto the next one end with `marker`, if any.
+pub(crate) fn wrap_segments<'a>(
+    segments: &[AnsiString<'_>],
+    width: usize,
+    marker: Option<&ContinuationMarker>,
+    policy: WidthPolicy,
+) -> Vec<Vec<AnsiString<'a>>> {
+    wrap_rows(segments, width, marker, MarkerPlacement::LineEnd, policy)
+        .into_iter()
+        .map(|(line, _)| line)
+        .collect()
+}
+
+/// Wrap `segments` as [`wrap_segments`] does, with `marker` placed as
+/// `placement` says, and whether each line was wrapped onto the next one.
+pub(crate) fn wrap_rows<'a>(
+    segments: &[AnsiString<'_>],
+    width: usize,
+    marker: Option<&ContinuationMarker>,
+    placement: MarkerPlacement,
+    policy: WidthPolicy,
+) -> Vec<(Vec<AnsiString<'a>>, bool)> {
+    let width = width
+        .saturating_sub(marker.map_or(0, |marker| policy.str_width(marker.text())))
+        .max(1);
+    let chars = policy.styled_chars(segments);
+    let line_width =
+        |line: &[(char, Style, usize, usize)]| -> usize { line.iter().map(|c| c.2).sum() };
+
+    let mut lines: Vec<Vec<(char, Style, usize, usize)>> = vec![Vec::new()];
+    // Whether each line (but the last) was wrapped onto the next one.
+    let mut wrapped = Vec::new();
+    let mut ix = 0;
+    while ix < chars.len() {
+        let (c, style, _, segment) = chars[ix];
+        if c == '\n' {
+            lines.push(Vec::new());
+            wrapped.push(false);
+            ix += 1;
+            continue;
+        }
+        if c.is_whitespace() {
+            let line = lines.last_mut().unwrap();
+            if !line.is_empty() && line_width(line) < width {
+                line.push((' ', style, 1, segment));
+            }
+            ix += 1;
+            continue;
+        }
+
+        let end = chars[ix..]
+            .iter()
+            .position(|(c, ..)| c.is_whitespace())
+            .map_or(chars.len(), |len| ix + len);
+        let used = lines.last().map_or(0, |line| line_width(line));
+        if used > 0 && used + line_width(&chars[ix..end]) > width {
+            lines.push(Vec::new());
+            wrapped.push(true);
+        }
+        for &styled in &chars[ix..end] {
+            let used = lines.last().map_or(0, |line| line_width(line));
+            if used > 0 && used + styled.2 > width {
+                lines.push(Vec::new());
+                wrapped.push(true);
+            }
+            lines.last_mut().unwrap().push(styled);
+        }
+        ix = end;
+    }
+
+    wrapped.push(false);
+    let mut continues = false;
+    lines
+        .into_iter()
+        .zip(wrapped)
+        .map(|(mut line, wrapped)| {
+            while line.last().map_or(false, |(c, ..)| *c == ' ') {
+                line.pop();
+            }
+            let first_style = line.first().map_or(Style::new(), |(_, style, ..)| *style);
+            let last_style = line.last().map_or(Style::new(), |(_, style, ..)| *style);
+            let mut painted = paint_linked_runs(
+                segments,
+                line.into_iter().map(|(c, style, _, ix)| (c, style, ix)),
+            );
+            match (marker, placement) {
+                (Some(marker), MarkerPlacement::LineEnd) if wrapped => {
+                    painted.push(marker.paint(last_style));
+                }
+                (Some(marker), MarkerPlacement::Gutter) => {
+                    let gutter = if continues {
+                        marker.paint(first_style)
+                    } else {
+                        Style::new().paint(" ".repeat(policy.str_width(marker.text())))
+                    };
+                    painted.insert(0, gutter);
+                }
+                _ => {}
+            }
+            continues = wrapped;
+            (painted, wrapped)
+        })
+        .collect()
+}
+
+/// Shorten the visible text of `segments` to at most `width` columns, ending
+/// it with `marker` if anything had to be cut off (and the marker fits).
+pub(crate) fn truncate_segments<'a>(
+    segments: &[AnsiString<'_>],
+    width: usize,
+    marker: &ContinuationMarker,
+    policy: WidthPolicy,
+) -> Vec<AnsiString<'a>> {
+    let chars = policy.styled_chars(segments);
+    let total: usize = chars.iter().map(|c| c.2).sum();
+    let marker_width = policy.str_width(marker.text());
+    let marked = total > width && marker_width <= width && width > 0;
+    let mut budget = if total <= width {
+        total
+    } else if marked {
+        width - marker_width
+    } else {
+        width
+    };
+
+    // A character which does not fit ends the text, even if a narrower one
+    // after it would fit.
+    let kept = chars.into_iter().take_while(|&(_, _, char_width, _)| {
+        let fits = char_width <= budget;
+        if fits {
+            budget -= char_width;
+        }
+        fits
+    });
+    let mut last_style = segments.first().map(|s| *s.style_ref());
+    let mut truncated = paint_linked_runs(
+        segments,
+        kept.map(|(c, style, _, ix)| {
+            last_style = Some(style);
+            (c, style, ix)
+        }),
+    );
+    if marked {
+        truncated.push(marker.paint(last_style.unwrap_or_default()));
+    }
+    truncated
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::AnsiStrings;
+    use crate::Color::*;
+
+    fn visible_width(s: &str) -> usize {
+        WidthPolicy::new().str_width(s)
+    }
+
+    #[test]
+    fn plain() {
+        assert_eq!(visible_width("hello"), 5);
+        assert_eq!(visible_width("héllo"), 5);
+    }
+
+    #[test]
+    fn escapes_are_skipped() {
+        assert_eq!(visible_width("\x1B[1;31mhi\x1B[0m"), 2);
+        assert_eq!(
+            visible_width("\x1B]8;;https://example.com\x1B\\link\x1B]8;;\x1B\\"),
+            4
+        );
+        assert_eq!(visible_width("\x1B]2;title\x07"), 0);
+    }
+
+    #[test]
+    fn painted_widths() {
+        let strings = AnsiStrings([
+            AnsiString::title("title"),
+            Red.paint("日本").hyperlink("https://example.com"),
+            Blue.paint(AnsiStrings([Green.bold().paint(" αβ")])),
+        ]);
+        assert_eq!(strings.width(), 7);
+        assert_eq!(strings.len_visible(), 5);
+        assert_eq!(strings.width_with(WidthPolicy::new().ambiguous_width(2)), 9);
+
+        let bytes = crate::AnsiByteStrings([
+            Red.paint(b"ok ".as_slice()),
+            Blue.paint(b"\xFF\xE6\x97\xA5".as_slice()),
+        ]);
+        assert_eq!(bytes.width(), 6);
+        assert_eq!(bytes.len_visible(), 7);
+    }
+
+    #[test]
+    fn painted() {
+        assert_eq!(
+            WidthPolicy::new().segment_width(&Red.bold().paint("four")),
+            4
+        );
+    }
+
+    fn wrapped(segments: &[AnsiString<'_>], width: usize) -> Vec<String> {
+        wrap_segments(segments, width, None, WidthPolicy::new())
+            .into_iter()
+            .map(|line| line.iter().map(|s| s.content().to_string()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn wrap_at_whitespace() {
+        let segments = [Red.paint("the quick "), Blue.paint("brown fox")];
+        assert_eq!(wrapped(&segments, 10), ["the quick", "brown fox"]);
+        assert_eq!(
+            wrapped(&segments, 4),
+            ["the", "quic", "k", "brow", "n", "fox"]
+        );
+    }
+
+    #[test]
+    fn wrap_keeps_styles_within_words() {
+        let lines = wrap_segments(
+            &[Red.paint("ab"), Blue.paint("cd ef")],
+            4,
+            None,
+            WidthPolicy::new(),
+        );
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0][0].style_ref(), &Red.normal());
+        assert_eq!(lines[0][1].style_ref(), &Blue.normal());
+        assert_eq!(lines[1][0].content().to_string(), "ef");
+    }
+
+    #[test]
+    fn truncate() {
+        let segments = [Red.paint("abc"), Blue.paint("def")];
+        let tilde = ContinuationMarker::new("~");
+        let text = |width| -> String {
+            truncate_segments(&segments, width, &tilde, WidthPolicy::new())
+                .iter()
+                .map(|s| s.content().to_string())
+                .collect()
+        };
+        assert_eq!(text(6), "abcdef");
+        assert_eq!(text(5), "abcd~");
+        assert_eq!(text(3), "ab~");
+        assert_eq!(text(0), "");
+        let cut = truncate_segments(&segments, 3, &tilde, WidthPolicy::new());
+        assert_eq!(cut.last().unwrap().style_ref(), &Red.normal());
+    }
+
+    #[test]
+    fn bidi_marks_are_invisible() {
+        assert_eq!(visible_width("\u{2068}שלום\u{2069}\u{200E}"), 4);
+    }
+
+    #[cfg(feature = "unicode-normalization")]
+    #[test]
+    fn normalized_text_is_cut_in_composed_form() {
+        let policy = WidthPolicy::new().normalize(true);
+        let decomposed =
+            AnsiStrings([Red.paint("\u{1112}\u{1161}\u{11AB}\u{1100}\u{1173}\u{11AF}!")]);
+        assert_eq!(decomposed.width_with(policy), 5);
+        let cut = decomposed.truncate_with(3, &ContinuationMarker::new("…"), policy);
+        assert_eq!(cut.unstyled(), "\u{D55C}…");
+    }
+
+    #[cfg(feature = "unicode-bidi")]
+    #[test]
+    fn bidi_isolation() {
+        let text = |segments: Vec<AnsiString<'_>>| -> String {
+            segments.iter().map(|s| s.content().to_string()).collect()
+        };
+        let mixed = vec![Red.paint("مرحبا"), Blue.paint(" ok")];
+        assert_eq!(text(BidiIsolation::Mark.isolate(mixed.clone())), "مرحبا ok");
+        assert_eq!(
+            text(BidiIsolation::Isolate.isolate(mixed)),
+            "\u{2068}مرحبا ok\u{2069}"
+        );
+        let ltr = vec![Red.paint("hello")];
+        assert_eq!(text(BidiIsolation::Isolate.isolate(ltr)), "hello");
+    }
+
+    #[test]
+    fn wide_markers() {
+        let segments = [Red.paint("abcdef")];
+        let dots = ContinuationMarker::ELLIPSIS
+            .style(Style::new().dimmed())
+            .for_profile(&TerminalProfile::ascii());
+        let cut = truncate_segments(&segments, 5, &dots, WidthPolicy::new());
+        assert_eq!(
+            AnsiStrings(cut).to_string(),
+            "\x1b[31mab\x1b[0m\x1b[2m...\x1b[0m"
+        );
+        let cut = truncate_segments(&segments, 2, &dots, WidthPolicy::new());
+        assert_eq!(AnsiStrings(cut).to_string(), "\x1b[31mab\x1b[0m");
+
+        let arrow = ContinuationMarker::WRAP_ARROW;
+        let lines: Vec<String> = wrap_segments(
+            &[Blue.paint("abc def\ngh")],
+            4,
+            Some(&arrow),
+            WidthPolicy::new(),
+        )
+        .into_iter()
+        .map(|line| line.iter().map(|s| s.content().to_string()).collect())
+        .collect();
+        assert_eq!(lines, ["abc↪", "def", "gh"]);
+    }
+
+    #[test]
+    fn wrap_explicit_newlines() {
+        assert_eq!(wrapped(&[Red.paint("a\n\nb")], 10), ["a", "", "b"]);
+    }
+
+    #[test]
+    fn wide_characters() {
+        let policy = WidthPolicy::new();
+        assert_eq!(policy.str_width("e\u{301}"), 1);
+        assert_eq!(policy.str_width("👩\u{200D}💻"), 4);
+        assert_eq!(policy.str_width("\u{2764}\u{FE0F}"), 2);
+        assert_eq!(policy.str_width("x\u{FE0F}"), 2);
+        assert_eq!(policy.str_width("→×"), 2);
+        assert_eq!(policy.ambiguous_width(2).str_width("→×"), 4);
+        assert_eq!(policy.emoji_width(1).str_width("✅日"), 3);
+    }
+
+    #[test]
+    fn wide_characters_wrap_and_truncate() {
+        let policy = WidthPolicy::new();
+        let segments = [Red.paint("日本語 🚀🚀")];
+        let lines: Vec<String> = wrap_segments(&segments, 5, None, policy)
+            .into_iter()
+            .map(|line| line.iter().map(|s| s.content().to_string()).collect())
+            .collect();
+        assert_eq!(lines, ["日本", "語", "🚀🚀"]);
+
+        let text = |width, policy| -> String {
+            truncate_segments(&segments, width, &ContinuationMarker::ELLIPSIS, policy)
+                .iter()
+                .map(|s| s.content().to_string())
+                .collect()
+        };
+        assert_eq!(text(6, policy), "日本…");
+        assert_eq!(text(5, policy), "日本…");
+        assert_eq!(text(4, policy), "日…");
+        assert_eq!(text(6, policy.ambiguous_width(2)), "日本…");
+        assert_eq!(text(5, policy.ambiguous_width(2)), "日…");
+    }
+
+    #[test]
+    fn measure_cache_evicts_least_recently_used() {
+        let mut cache = MeasureCache::new(2);
+        assert_eq!(cache.str_width("a"), 1);
+        assert_eq!(cache.str_width("bb"), 2);
+        assert_eq!(cache.str_width("a"), 1);
+        // "bb" is the least recently used entry, and makes room for "日本".
+        assert_eq!(cache.str_width("日本"), 4);
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.str_width("a"), 1);
+        assert_eq!(cache.str_width("bb"), 2);
+        assert_eq!(
+            cache.stats(),
+            MeasureStats {
+                hits: 2,
+                misses: 4,
+                evictions: 2,
+            }
+        );
+
+        cache.reset_stats();
+        let mut disabled = MeasureCache::new(0);
+        assert_eq!(disabled.str_width("x"), 1);
+        assert_eq!(disabled.str_width("x"), 1);
+        assert!(disabled.is_empty());
+        assert_eq!(disabled.stats().hit_rate(), 0.0);
+        assert_eq!(cache.stats().hit_rate(), 0.0);
+
+        let mut legacy = cache.policy(WidthPolicy::new().emoji_width(1));
+        assert!(legacy.is_empty());
+        assert_eq!(legacy.str_width("🚀"), 1);
+    }
+
+    #[test]
+    fn measure_cache_measures_what_is_shown() {
+        use crate::Color::Blue;
+        let mut cache = MeasureCache::new(8);
+        let name = String::from("日本");
+        assert_eq!(cache.width(&Blue.paint(name.as_str())), 4);
+        assert_eq!(
+            cache.width(&Blue.paint(name.as_str()).hyperlink("https://example.com")),
+            4
+        );
+        assert_eq!(cache.width(&AnsiString::title(name.as_str())), 0);
+        assert_eq!(cache.width(&Blue.paint(format_args!("{}!", name))), 5);
+        assert_eq!(cache.str_width("日本"), 4);
+        assert_eq!(cache.stats().hits, 2);
+    }
+}
@@ -1,23 +1,21 @@
-/// Enables ANSI code support on Windows 10.
-///
-/// This uses Windows API calls to alter the properties of the console that
-/// the program is running in.
-///
-/// https://msdn.microsoft.com/en-us/library/windows/desktop/mt638032(v=vs.85).aspx
+#[cfg(windows)]
+use windows::Win32::Foundation::HANDLE;
+#[cfg(windows)]
+use windows::Win32::System::Console::CONSOLE_MODE;
+
+/// Open the console the program is running in, with the mode it is in.
 ///
 /// Returns a `Result` with the Windows error code if unsuccessful.
 #[cfg(windows)]
-pub fn enable_ansi_support() -> Result<(), u32> {
+fn open_console() -> Result<(HANDLE, CONSOLE_MODE), u32> {
     // ref: https://docs.microsoft.com/en-us/windows/console/console-virtual-terminal-sequences#EXAMPLE_OF_ENABLING_VIRTUAL_TERMINAL_PROCESSING @@ https://archive.is/L7wRJ#76%
     use windows::w;
-    use windows::Win32::Foundation::GetLastError;
-    use windows::Win32::Foundation::INVALID_HANDLE_VALUE;
+    use windows::Win32::Foundation::{CloseHandle, GetLastError, INVALID_HANDLE_VALUE};
     use windows::Win32::Storage::FileSystem::{CreateFileW, OPEN_EXISTING};
     use windows::Win32::Storage::FileSystem::{
         FILE_GENERIC_READ, FILE_GENERIC_WRITE, FILE_SHARE_WRITE,
     };
-    use windows::Win32::System::Console::ENABLE_VIRTUAL_TERMINAL_PROCESSING;
-    use windows::Win32::System::Console::{GetConsoleMode, SetConsoleMode};
+    use windows::Win32::System::Console::GetConsoleMode;
 
     unsafe {
         // ref: https://docs.microsoft.com/en-us/windows/win32/api/fileapi/nf-fileapi-createfilew
@@ -38,11 +36,24 @@ pub fn enable_ansi_support() -> Result<(), u32> {
         // ref: https://docs.microsoft.com/en-us/windows/console/getconsolemode
         let mut console_mode = 0;
         if 0 == GetConsoleMode(console_handle, &mut console_mode) {
-            return Err(GetLastError());
+            let error = GetLastError();
+            CloseHandle(console_handle);
+            return Err(error);
         }
+        Ok((console_handle, console_mode))
+    }
+}
+
+/// Turn on virtual terminal processing in the console behind `console_handle`,
+/// which was in `console_mode`.
+#[cfg(windows)]
+fn enable_vt_processing(console_handle: HANDLE, console_mode: CONSOLE_MODE) -> Result<(), u32> {
+    use windows::Win32::Foundation::GetLastError;
+    use windows::Win32::System::Console::{SetConsoleMode, ENABLE_VIRTUAL_TERMINAL_PROCESSING};
 
-        // VT processing not already enabled?
-        if console_mode & ENABLE_VIRTUAL_TERMINAL_PROCESSING == 0 {
+    // VT processing not already enabled?
+    if console_mode & ENABLE_VIRTUAL_TERMINAL_PROCESSING == 0 {
+        unsafe {
             // https://docs.microsoft.com/en-us/windows/console/setconsolemode
             if 0 == SetConsoleMode(
                 console_handle,
@@ -51,7 +62,85 @@ pub fn enable_ansi_support() -> Result<(), u32> {
                 return Err(GetLastError());
             }
         }
+    }
+    Ok(())
+}
+
+/// Enables ANSI code support on Windows 10.
+///
+/// This uses Windows API calls to alter the properties of the console that
+/// the program is running in. The console is left in this mode when the
+/// program exits; see [`AnsiSupportGuard`] to restore its previous mode
+/// instead.
+///
+/// https://msdn.microsoft.com/en-us/library/windows/desktop/mt638032(v=vs.85).aspx
+///
+/// Returns a `Result` with the Windows error code if unsuccessful.
+#[cfg(windows)]
+pub fn enable_ansi_support() -> Result<(), u32> {
+    use windows::Win32::Foundation::CloseHandle;
+
+    let (console_handle, console_mode) = open_console()?;
+    let enabled = enable_vt_processing(console_handle, console_mode);
+    unsafe {
+        CloseHandle(console_handle);
+    }
+    enabled
+}
+
+/// Enables ANSI code support on Windows 10 (see [`enable_ansi_support`]) for
+/// as long as the guard is alive, restoring the previous mode of the console
+/// when it is dropped.
+///
+/// # Examples
+///
+/// ```no_run
+/// use nu_ansi_term::AnsiSupportGuard;
+/// use nu_ansi_term::Color::Red;
+///
+/// let guard = AnsiSupportGuard::new();
+/// println!("{}", Red.paint("shown in red, not as escape codes"));
+/// drop(guard);
+/// ```
+#[cfg(windows)]
+#[derive(Debug)]
+pub struct AnsiSupportGuard {
+    console_handle: HANDLE,
+    previous_mode: CONSOLE_MODE,
+}
+
+#[cfg(windows)]
+impl AnsiSupportGuard {
+    /// Enable ANSI code support in the console the program is running in.
+    ///
+    /// Returns a `Result` with the Windows error code if unsuccessful.
+    pub fn new() -> Result<Self, u32> {
+        use windows::Win32::Foundation::CloseHandle;
+
+        let (console_handle, previous_mode) = open_console()?;
+        if let Err(error) = enable_vt_processing(console_handle, previous_mode) {
+            unsafe {
+                CloseHandle(console_handle);
+            }
+            return Err(error);
+        }
+        Ok(Self {
+            console_handle,
+            previous_mode,
+        })
+    }
+}
 
-        Ok(())
+#[cfg(windows)]
+impl Drop for AnsiSupportGuard {
+    fn drop(&mut self) {
+        use windows::Win32::Foundation::CloseHandle;
+        use windows::Win32::System::Console::SetConsoleMode;
+
+        // There is nothing sensible left to do if the mode cannot be restored.
+        unsafe {
+            SetConsoleMode(self.console_handle, self.previous_mode);
+            CloseHandle(self.console_handle);
+        }
     }
 }
@@ -0,0 +1,358 @@
+use crate::strip::strip_ansi_codes;
+use crate::width::{link_target, linked, truncate_segments, wrap_rows, wrap_segments, LinkTarget};
+use crate::write::Content;
+use crate::{
+    nested, AnsiString, AnsiStrings, ContinuationMarker, MarkerPlacement, Style, TerminalProfile,
+    WidthPolicy,
+};
+use std::ops::Range;
+
+/// A row of wrapped text, with the line of the text it shows. See
+/// [`AnsiStrings::wrap_rows`].
+#[derive(Clone, Debug)]
+pub struct WrappedRow {
+    /// The styled text of the row, including any marker.
+    pub text: AnsiStrings<'static>,
+    /// The line of the text the row shows part of, counting explicit
+    /// newlines from 0.
+    pub line: usize,
+    /// Whether the line was wrapped onto the next row (a soft wrap), rather
+    /// than ending at a newline or the end of the text.
+    pub soft_wrapped: bool,
+}
+
+impl<'a> AnsiStrings<'a> {
+    /// Wrap these strings into lines of at most `width` columns, breaking at
+    /// whitespace where possible. Each line is a sequence of its own, which
+    /// opens the styles of the characters on it and resets them at its end,
+    /// so that lines can be printed (or padded, or placed side by side)
+    /// separately. Explicit newlines always start a new line.
+    ///
+    /// Only the visible text, its styles and its links are kept: titles and
+    /// control segments are dropped, as are the styles of values formatted
+    /// into the content of a string. Lazy styles are resolved as they are
+    /// when these strings are written.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::AnsiStrings;
+    /// use nu_ansi_term::Color::{Blue, Red};
+    ///
+    /// let text = AnsiStrings([Red.paint("the quick "), Blue.paint("brown fox")]);
+    /// let lines = text.wrap(15);
+    /// assert_eq!(lines.len(), 2);
+    /// assert_eq!(
+    ///     lines[0].to_string(),
+    ///     AnsiStrings([Red.paint("the quick "), Blue.paint("brown")]).to_string()
+    /// );
+    /// assert_eq!(lines[1].to_string(), Blue.paint("fox").to_string());
+    /// ```
+    pub fn wrap(&self, width: usize) -> Vec<AnsiStrings<'static>> {
+        self.wrap_with(width, None, WidthPolicy::new())
+    }
+
+    /// Wrap these strings (see [`wrap`](Self::wrap)), measuring them with
+    /// `policy`, and ending lines which are wrapped onto the next one with
+    /// `marker`, if any.
+    pub fn wrap_with(
+        &self,
+        width: usize,
+        marker: Option<&ContinuationMarker>,
+        policy: WidthPolicy,
+    ) -> Vec<AnsiStrings<'static>> {
+        wrap_segments(&self.visible_segments(None), width, marker, policy)
+            .into_iter()
+            .map(AnsiStrings)
+            .collect()
+    }
+
+    /// Wrap these strings (see [`wrap`](Self::wrap)) into rows which know
+    /// the line of the text they belong to, for editors and pagers which
+    /// move through wrapped text by line, marking the rows where lines were
+    /// wrapped with `marker`, if any, placed as `placement` says.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::{AnsiStrings, ContinuationMarker, MarkerPlacement, WidthPolicy};
+    /// use nu_ansi_term::utils::unstyle;
+    ///
+    /// let text = AnsiStrings(["one two three\nfour".into()]);
+    /// let rows = text.wrap_rows(
+    ///     10,
+    ///     Some(&ContinuationMarker::WRAP_ARROW),
+    ///     MarkerPlacement::Gutter,
+    ///     WidthPolicy::new(),
+    /// );
+    /// let shown: Vec<_> = rows
+    ///     .iter()
+    ///     .map(|row| (unstyle(&row.text), row.line, row.soft_wrapped))
+    ///     .collect();
+    /// assert_eq!(
+    ///     shown,
+    ///     [
+    ///         (" one two".to_string(), 0, true),
+    ///         ("↪three".to_string(), 0, false),
+    ///         (" four".to_string(), 1, false),
+    ///     ]
+    /// );
+    /// ```
+    pub fn wrap_rows(
+        &self,
+        width: usize,
+        marker: Option<&ContinuationMarker>,
+        placement: MarkerPlacement,
+        policy: WidthPolicy,
+    ) -> Vec<WrappedRow> {
+        let mut line = 0;
+        wrap_rows(
+            &self.visible_segments(None),
+            width,
+            marker,
+            placement,
+            policy,
+        )
+        .into_iter()
+        .map(|(text, soft_wrapped)| {
+            let row = WrappedRow {
+                text: AnsiStrings(text),
+                line,
+                soft_wrapped,
+            };
+            if !soft_wrapped {
+                line += 1;
+            }
+            row
+        })
+        .collect()
+    }
+
+    /// Shorten these strings to at most `width` columns, ending them with
+    /// `ellipsis` if anything had to be cut off (and it fits). As with
+    /// [`wrap`](Self::wrap), only the visible text, its styles and its links
+    /// are kept.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::{AnsiStrings, ContinuationMarker};
+    /// use nu_ansi_term::Color::{Blue, Red};
+    ///
+    /// let text = AnsiStrings([Red.paint("nu_"), Blue.paint("ansi_term")]);
+    /// assert_eq!(
+    ///     text.truncate(6, &ContinuationMarker::new("…")).to_string(),
+    ///     AnsiStrings([Red.paint("nu_"), Blue.paint("an…")]).to_string()
+    /// );
+    /// assert_eq!(text.truncate(20, &ContinuationMarker::ELLIPSIS).len(), 2);
+    /// ```
+    pub fn truncate(&self, width: usize, ellipsis: &ContinuationMarker) -> AnsiStrings<'static> {
+        self.truncate_with(width, ellipsis, WidthPolicy::new())
+    }
+
+    /// Shorten these strings (see [`truncate`](Self::truncate)), measuring
+    /// them with `policy`.
+    pub fn truncate_with(
+        &self,
+        width: usize,
+        ellipsis: &ContinuationMarker,
+        policy: WidthPolicy,
+    ) -> AnsiStrings<'static> {
+        AnsiStrings(truncate_segments(
+            &self.visible_segments(None),
+            width,
+            ellipsis,
+            policy,
+        ))
+    }
+
+    /// The runs of visible text of these strings which share a style, as
+    /// byte ranges of [`unstyled`](Self::unstyled) text with the style it is
+    /// shown in, for presenting the styling in another medium, such as to a
+    /// screen reader or an editor. Runs are in order, do not overlap, and
+    /// adjacent runs have different styles; unstyled text has a run with an
+    /// empty style, so that the runs cover all of the text.
+    ///
+    /// Nested sequences are flattened into the runs, lazy styles are
+    /// resolved as they are when these strings are written, and links,
+    /// titles and control segments are left out.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nu_ansi_term::{AnsiStrings, Style};
+    /// use nu_ansi_term::Color::{Blue, Red};
+    ///
+    /// let text = AnsiStrings([Red.paint("err"), Red.paint("or"), ": ".into(), Blue.paint("x")]);
+    /// assert_eq!(text.unstyled(), "error: x");
+    /// assert_eq!(
+    ///     text.attribute_runs(),
+    ///     [(0..5, Red.normal()), (5..7, Style::new()), (7..8, Blue.normal())]
+    /// );
+    /// ```
+    pub fn attribute_runs(&self) -> Vec<(Range<usize>, Style)> {
+        let mut runs: Vec<(Range<usize>, Style)> = Vec::new();
+        let mut offset = 0;
+        for (mut style, text, _) in self.visible_text(None, None) {
+            let len = text.len();
+            if len == 0 {
+                continue;
+            }
+            style.prefix_before_reset = false;
+            match runs.last_mut() {
+                Some((range, last)) if *last == style => range.end += len,
+                _ => runs.push((offset..offset + len, style)),
+            }
+            offset += len;
+        }
+        runs
+    }
+
+    /// The segments of text shown by these strings, each in the style it is
+    /// shown in (within `enclosing`, if these strings are nested in another
+    /// string) and with its link, with nested sequences flattened into them.
+    /// Lazy styles are resolved as they are when these strings are written.
+    pub(crate) fn visible_segments(&self, enclosing: Option<Style>) -> Vec<AnsiString<'static>> {
+        self.visible_text(enclosing, None)
+            .into_iter()
+            .map(|(style, text, target)| linked(style.paint(text), target.as_ref()))
+            .collect()
+    }
+
+    /// The text of [`visible_segments`](Self::visible_segments), with the
+    /// style it is shown in and the link it belongs to (`link`, for text
+    /// nested in a link which has none of its own).
+    fn visible_text(
+        &self,
+        enclosing: Option<Style>,
+        link: Option<&LinkTarget>,
+    ) -> Vec<(Style, String, Option<LinkTarget>)> {
+        if self.has_lazy_styles() {
+            return self
+                .resolved(&TerminalProfile::rendering())
+                .visible_text(enclosing, link);
+        }
+        let mut segments = Vec::with_capacity(self.len());
+        for (segment, (style, content)) in self.iter().zip(self.styled_contents()) {
+            if segment.scheduled_style().is_none() {
+                continue;
+            }
+            let style = enclosing.map_or(style, |enclosing| nested::flatten(style, enclosing));
+            let target = link_target(segment).or_else(|| link.cloned());
+            let text = match content {
+                Content::GenericStrings(strings) => {
+                    segments.extend(strings.visible_text(Some(style), target.as_ref()));
+                    continue;
+                }
+                Content::FmtArgs(args) => nested::within(style, || args.to_string()),
+                content => content.to_string(),
+            };
+            segments.push((style, strip_ansi_codes(&text).into_owned(), target));
+        }
+        segments
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Color::*;
+
+    #[test]
+    fn nested_strings_and_controls() {
+        let mut text = AnsiStrings([
+            Red.paint("ab "),
+            Blue.bold()
+                .paint(AnsiStrings([Green.paint("cd"), " ef".into()])),
+        ]);
+        text.push_control("\x1b[K");
+        let x = Green.paint("x");
+        let lines: Vec<String> = (text + AnsiStrings([Red.paint(format_args!(" {} gh", x))]))
+            .wrap(5)
+            .iter()
+            .map(|line| line.to_string())
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                AnsiStrings([Red.paint("ab "), Green.bold().paint("cd")]).to_string(),
+                AnsiStrings([Blue.bold().paint("ef"), Red.paint(" x")]).to_string(),
+                Red.paint("gh").to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn attribute_runs_cover_the_unstyled_text() {
+        let mut text = AnsiStrings([
+            AnsiString::title("t"),
+            Red.paint("ab"),
+            Red.paint(AnsiStrings([Red.paint("c"), Green.bold().paint("d")])),
+        ]);
+        text.push_control("\x1b[K");
+        text.push(Red.paint(""));
+        assert_eq!(text.unstyled(), "abcd");
+        assert_eq!(
+            text.attribute_runs(),
+            [(0..3, Red.normal()), (3..4, Green.bold())]
+        );
+    }
+
+    #[test]
+    fn lazy_styles_and_links_are_kept() {
+        let mut text = AnsiStrings([Red.paint("ab ").hyperlink("https://a")]);
+        text.push_lazy(
+            AnsiStrings([AnsiString::from("cd").hyperlink("https://nu.sh")]),
+            |_| Blue.bold(),
+        );
+        assert_eq!(
+            text.attribute_runs(),
+            [(0..3, Red.normal()), (3..5, Blue.bold())]
+        );
+        let lines: Vec<String> = text.wrap(3).iter().map(|line| line.to_string()).collect();
+        assert_eq!(
+            lines,
+            [
+                Red.paint("ab").hyperlink("https://a").to_string(),
+                Blue.bold()
+                    .paint("cd")
+                    .hyperlink("https://nu.sh")
+                    .to_string(),
+            ]
+        );
+        assert_eq!(
+            text.truncate(4, &ContinuationMarker::new("…")).to_string(),
+            AnsiStrings([Red.paint("ab ").hyperlink("https://a"), Red.paint("…"),]).to_string()
+        );
+    }
+
+    #[test]
+    fn rows_map_back_to_lines() {
+        let text = AnsiStrings([Red.paint("abcdef"), "\n\ngh".into()]);
+        let marker = ContinuationMarker::new("+");
+        let rows = |placement| -> Vec<(String, usize, bool)> {
+            text.wrap_rows(4, Some(&marker), placement, WidthPolicy::new())
+                .iter()
+                .map(|row| (row.text.to_string(), row.line, row.soft_wrapped))
+                .collect()
+        };
+        assert_eq!(
+            rows(MarkerPlacement::LineEnd),
+            [
+                (Red.paint("abc+").to_string(), 0, true),
+                (Red.paint("def").to_string(), 0, false),
+                (String::new(), 1, false),
+                ("gh".to_string(), 2, false),
+            ]
+        );
+        assert_eq!(
+            rows(MarkerPlacement::Gutter),
+            [
+                (" \x1b[31mabc\x1b[0m".to_string(), 0, true),
+                (Red.paint("+def").to_string(), 0, false),
+                (" ".to_string(), 1, false),
+                (" gh".to_string(), 2, false),
+            ]
+        );
+    }
+}
@@ -1,7 +1,9 @@
 use std::borrow::Cow;
 use std::fmt;
 use std::fmt::Debug;
+use std::hash::{Hash, Hasher};
 use std::io;
+use std::sync::Arc;
 
 use crate::{AnsiGenericStrings, Style};
 
@@ -28,6 +30,32 @@ macro_rules! write_fmt {
     };
 }
 
+/// Format the arguments immediately into [`Content`] which owns the
+/// formatted text, like [`format!`].
+///
+/// Content made of [`format_args!`] borrows the formatted values, so
+/// painted values built from it cannot be returned from the function which
+/// formats them; owned content can.
+///
+/// ```
+/// use nu_ansi_term::{owned_content, AnsiString, Style};
+/// use nu_ansi_term::Color::Yellow;
+///
+/// fn percentage(done: usize, total: usize) -> AnsiString<'static> {
+///     let style = if done < total { Yellow.normal() } else { Style::new() };
+///     style.paint(owned_content!("{:>3}%", done * 100 / total))
+/// }
+///
+/// assert_eq!(percentage(1, 4).to_string(), "\x1b[33m 25%\x1b[0m");
+/// assert_eq!(percentage(4, 4).to_string(), "100%");
+/// ```
+#[macro_export]
+macro_rules! owned_content {
+    ($($args:tt)*) => {
+        $crate::Content::<'static, str>::owned(::std::format!($($args)*))
+    };
+}
+
 /// Takes an [`AnyWrite`] implementor and writes some [`StrLike`] content to it.
 /// ```
 /// use std::fmt;
@@ -67,6 +95,56 @@ macro_rules! io_write {
 }
 
 /// Allows for generalization over [`fmt::Write`] and [`io::Write`] implementors.
+///
+/// Everything which renders styled text can write to an `AnyWrite`
+/// implementor, e.g. through [`AnsiGenericStrings::write_to_any`]. Besides
+/// the implementations for `dyn fmt::Write` and `dyn io::Write` (reached
+/// with [`fmt_write!`] and [`io_write!`]), there are adapters owning a
+/// writer ([`FmtAdapter`] and [`IoAdapter`]) and one passing each piece of
+/// text to a closure ([`FnWriter`]).
+///
+/// # Implementing `AnyWrite`
+///
+/// Other sinks, such as the text buffer of a GUI widget or a ring buffer of
+/// recent output, can implement the trait themselves. `Buf` is the kind of
+/// text the sink accepts: with `Buf = str`, it accepts every sequence of
+/// [`AnsiGenericStrings<str>`](AnsiGenericStrings) (and escape codes, which
+/// are written as `str`); with `Buf = [u8]`, byte strings as well. Rendering
+/// stops at the first error the sink returns.
+///
+/// ```
+/// use nu_ansi_term::{AnsiStrings, AnyWrite, WriteResult};
+/// use nu_ansi_term::Color::Red;
+/// use std::collections::VecDeque;
+/// use std::fmt;
+///
+/// /// Keeps the last `capacity` bytes written to it.
+/// struct RingBuffer {
+///     bytes: VecDeque<u8>,
+///     capacity: usize,
+/// }
+///
+/// impl AnyWrite for RingBuffer {
+///     type Buf = str;
+///     type Error = fmt::Error;
+///
+///     fn write_fmt(&mut self, args: fmt::Arguments) -> WriteResult<fmt::Error> {
+///         self.write_str(&args.to_string())
+///     }
+///
+///     fn write_str(&mut self, s: &str) -> WriteResult<fmt::Error> {
+///         self.bytes.extend(s.bytes());
+///         let excess = self.bytes.len().saturating_sub(self.capacity);
+///         self.bytes.drain(..excess);
+///         Ok(())
+///     }
+/// }
+///
+/// let mut ring = RingBuffer { bytes: VecDeque::new(), capacity: 12 };
+/// AnsiStrings(["ok ".into(), Red.paint("failed")]).write_to_any(&mut ring)?;
+/// assert_eq!(ring.bytes.iter().copied().collect::<Vec<_>>(), b"1mfailed\x1b[0m");
+/// # Ok::<(), fmt::Error>(())
+/// ```
 pub trait AnyWrite {
     /// Type of string-like data buffers accepted by this writer ([`str`] for
     /// [`fmt::Write`] and [`[u8]`] [`io::Write`]).
@@ -108,19 +186,183 @@ impl<'a> AnyWrite for dyn io::Write + 'a {
     }
 }
 
+/// Adapter implementing [`AnyWrite`] for a [`fmt::Write`] implementor which
+/// it owns (or borrows, as `&mut W` is one too), for writing to it without
+/// coercing it with [`fmt_write!`].
+///
+/// # Examples
+///
+/// ```
+/// use nu_ansi_term::{AnsiStrings, FmtAdapter};
+/// use nu_ansi_term::Color::Green;
+///
+/// let mut w = FmtAdapter::new(String::new());
+/// AnsiStrings([Green.paint("ok")]).write_to_any(&mut w).unwrap();
+/// assert_eq!(w.into_inner(), "\x1b[32mok\x1b[0m");
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct FmtAdapter<W>(W);
+
+impl<W: fmt::Write> FmtAdapter<W> {
+    /// Create an adapter writing to `inner`.
+    pub fn new(inner: W) -> Self {
+        Self(inner)
+    }
+
+    /// Get a reference to the wrapped writer.
+    pub fn get_ref(&self) -> &W {
+        &self.0
+    }
+
+    /// Get a mutable reference to the wrapped writer.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.0
+    }
+
+    /// Consume this adapter, returning the wrapped writer.
+    pub fn into_inner(self) -> W {
+        self.0
+    }
+}
+
+impl<W: fmt::Write> AnyWrite for FmtAdapter<W> {
+    type Buf = str;
+    type Error = fmt::Error;
+
+    fn write_fmt(&mut self, args: fmt::Arguments) -> WriteResult<Self::Error> {
+        self.0.write_fmt(args)
+    }
+
+    fn write_str(&mut self, s: &Self::Buf) -> WriteResult<Self::Error> {
+        self.0.write_str(s)
+    }
+}
+
+/// Adapter implementing [`AnyWrite`] for an [`io::Write`] implementor which
+/// it owns (or borrows), for writing to it without coercing it with
+/// [`io_write!`]. Unlike [`FmtAdapter`], it accepts byte strings too.
+///
+/// # Examples
+///
+/// ```
+/// use nu_ansi_term::{AnsiByteStrings, IoAdapter};
+/// use nu_ansi_term::Color::Green;
+///
+/// let mut w = IoAdapter::new(Vec::new());
+/// AnsiByteStrings([Green.paint(&b"\xffok"[..])]).write_to_any(&mut w).unwrap();
+/// assert_eq!(w.into_inner(), b"\x1b[32m\xffok\x1b[0m");
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct IoAdapter<W>(W);
+
+impl<W: io::Write> IoAdapter<W> {
+    /// Create an adapter writing to `inner`.
+    pub fn new(inner: W) -> Self {
+        Self(inner)
+    }
+
+    /// Get a reference to the wrapped writer.
+    pub fn get_ref(&self) -> &W {
+        &self.0
+    }
+
+    /// Get a mutable reference to the wrapped writer.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.0
+    }
+
+    /// Consume this adapter, returning the wrapped writer.
+    pub fn into_inner(self) -> W {
+        self.0
+    }
+}
+
+impl<W: io::Write> AnyWrite for IoAdapter<W> {
+    type Buf = [u8];
+    type Error = io::Error;
+
+    fn write_fmt(&mut self, args: fmt::Arguments) -> WriteResult<Self::Error> {
+        self.0.write_fmt(args)
+    }
+
+    fn write_str(&mut self, s: &Self::Buf) -> WriteResult<Self::Error> {
+        self.0.write_all(s)
+    }
+}
+
+/// Writer passing each piece of text written to it, escape codes included,
+/// to a closure, for sinks which take text a piece at a time, such as the
+/// text buffer of a GUI widget. Formatting arguments are rendered before
+/// they are passed on.
+///
+/// # Examples
+///
+/// ```
+/// use nu_ansi_term::{AnsiStrings, FnWriter};
+/// use nu_ansi_term::Color::Red;
+///
+/// let mut pieces = Vec::new();
+/// let mut w = FnWriter::new(|piece: &str| {
+///     pieces.push(piece.to_owned());
+///     Ok(())
+/// });
+/// AnsiStrings([Red.paint("no")]).write_to_any(&mut w).unwrap();
+/// assert_eq!(pieces.concat(), "\x1b[31mno\x1b[0m");
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct FnWriter<F>(F);
+
+impl<F: FnMut(&str) -> fmt::Result> FnWriter<F> {
+    /// Create a writer passing what is written to it to `write`.
+    pub fn new(write: F) -> Self {
+        Self(write)
+    }
+
+    /// Consume this writer, returning the closure.
+    pub fn into_inner(self) -> F {
+        self.0
+    }
+}
+
+impl<F: FnMut(&str) -> fmt::Result> fmt::Write for FnWriter<F> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        (self.0)(s)
+    }
+}
+
+impl<F: FnMut(&str) -> fmt::Result> AnyWrite for FnWriter<F> {
+    type Buf = str;
+    type Error = fmt::Error;
+
+    fn write_fmt(&mut self, args: fmt::Arguments) -> WriteResult<Self::Error> {
+        match args.as_str() {
+            Some(s) => (self.0)(s),
+            None => fmt::Write::write_fmt(self, args),
+        }
+    }
+
+    fn write_str(&mut self, s: &Self::Buf) -> WriteResult<Self::Error> {
+        (self.0)(s)
+    }
+}
+
 /// Implementors can be converted into a reference to string-like data buffer
-/// accepted by `W`.
+/// accepted by `W`, and viewed as bytes (for cleaning them when they are the
+/// payload of an operating system command).
 pub trait StrLike<'a, W: AnyWrite + ?Sized>
 where
-    Self: AsRef<W::Buf>,
+    Self: AsRef<W::Buf> + AsRef<[u8]>,
 {
     /// Write string-like data to the writer.
     fn write_str_to(&self, w: &mut W) -> WriteResult<W::Error>;
 }
 
-impl<'a, W: AnyWrite + ?Sized, S: 'a + ?Sized + ToOwned + AsRef<W::Buf>> StrLike<'a, W> for S {
+impl<'a, W: AnyWrite + ?Sized, S> StrLike<'a, W> for S
+where
+    S: 'a + ?Sized + ToOwned + AsRef<W::Buf> + AsRef<[u8]>,
+{
     fn write_str_to(&self, w: &mut W) -> WriteResult<W::Error> {
-        w.write_str(self.as_ref())
+        w.write_str(AsRef::<W::Buf>::as_ref(self))
     }
 }
 
@@ -129,6 +371,10 @@ impl<'a, W: AnyWrite + ?Sized, S: 'a + ?Sized + ToOwned + AsRef<W::Buf>> StrLike
 /// * anything that implements [`AsRef<AnyWrite::Buf>`] (conveniently
 ///   stored in either reference or owned format within a [`Cow`]).
 /// * an [`AnsiGenericString`]
+///
+/// More kinds of content may be added, so matches on this enum need a
+/// wildcard arm.
+#[non_exhaustive]
 pub enum Content<'a, S: 'a + ?Sized + ToOwned> {
     /// Content is [`fmt::Arguments`].
     FmtArgs(fmt::Arguments<'a>),
@@ -139,37 +385,44 @@ pub enum Content<'a, S: 'a + ?Sized + ToOwned> {
     /// [`AnsyGenericString`](crate::AnsiGenericString) can be converted into an
     /// [`AnsiGenericStrings`] using the appropriate [`From`] impl.
     GenericStrings(AnsiGenericStrings<'a, S>),
+    /// Content is shared with other strings, such as the content handed out
+    /// by a [`ContentInterner`](crate::ContentInterner).
+    Shared(Arc<S>),
 }
 
 impl<'a, S: 'a + ?Sized + ToOwned> Content<'a, S> {
+    /// Content owning `value`. Unlike [`Content::FmtArgs`], it does not
+    /// borrow anything, so it can be returned from the function that
+    /// created it (see [`owned_content!`](crate::owned_content)).
+    pub fn owned(value: S::Owned) -> Self {
+        Content::StrLike(Cow::Owned(value))
+    }
+
     /// If there are nested ANSI strings in this `Content`, they are rebased on
     /// this style (see various `rebase_on` methods).
     pub fn with_context(self, context: Style) -> Self {
         match self {
             x @ Content::FmtArgs(_) => Self::GenericStrings(context.paint(x).into()),
             x @ Content::StrLike(_) => Self::GenericStrings(context.paint(x).into()),
+            x @ Content::Shared(_) => Self::GenericStrings(context.paint(x).into()),
             Content::GenericStrings(x) => Self::GenericStrings(x.rebase_on(context)),
         }
     }
 }
 
-impl<'a, S: 'a + ?Sized + ToOwned> ToString for Content<'a, S>
+impl<'a, S: 'a + ?Sized + ToOwned> fmt::Display for Content<'a, S>
 where
-    S: AsRef<str>,
+    S: AsRef<str> + AsRef<[u8]>,
 {
-    fn to_string(&self) -> String {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Content::FmtArgs(x) => format!("{}", x),
+            Content::FmtArgs(x) => f.write_fmt(*x),
             Content::StrLike(x) => {
-                let mut s = String::new();
-                x.as_ref().write_str_to(fmt_write!(&mut s)).unwrap();
-                s
-            }
-            Content::GenericStrings(x) => {
-                let mut s = String::new();
-                x.write_to_any(fmt_write!(&mut s)).unwrap();
-                s
+                let s: &S = x;
+                f.write_str(AsRef::<str>::as_ref(s))
             }
+            Content::GenericStrings(x) => x.write_to_any(fmt_write!(f)),
+            Content::Shared(x) => f.write_str(AsRef::<str>::as_ref(&**x)),
         }
     }
 }
@@ -180,6 +433,37 @@ impl<'a, S: 'a + ?Sized + ToOwned> Clone for Content<'a, S> {
             Self::FmtArgs(x) => Self::FmtArgs(*x),
             Self::StrLike(x) => Self::StrLike(x.clone()),
             Self::GenericStrings(x) => Self::GenericStrings(x.clone()),
+            Self::Shared(x) => Self::Shared(Arc::clone(x)),
+        }
+    }
+}
+
+/// Contents are equal if they hold equal strings (whether borrowed, owned or
+/// shared), equal nested strings, or formatting arguments which render to
+/// the same text. Formatting arguments are not equal to strings.
+impl<'a, S: 'a + ?Sized + ToOwned + PartialEq> PartialEq for Content<'a, S> {
+    fn eq(&self, other: &Self) -> bool {
+        match (self.as_str_like(), other.as_str_like()) {
+            (Some(this), Some(that)) => this == that,
+            (Some(_), None) | (None, Some(_)) => false,
+            (None, None) => match (self, other) {
+                (Self::FmtArgs(this), Self::FmtArgs(that)) => this.to_string() == that.to_string(),
+                (Self::GenericStrings(this), Self::GenericStrings(that)) => this == that,
+                _ => false,
+            },
+        }
+    }
+}
+
+impl<'a, S: 'a + ?Sized + ToOwned + Eq> Eq for Content<'a, S> {}
+
+impl<'a, S: 'a + ?Sized + ToOwned + Hash> Hash for Content<'a, S> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match self {
+            Self::FmtArgs(args) => args.to_string().hash(state),
+            Self::StrLike(s) => (**s).hash(state),
+            Self::GenericStrings(strings) => strings.hash(state),
+            Self::Shared(s) => (**s).hash(state),
         }
     }
 }
@@ -193,11 +477,21 @@ where
             Self::FmtArgs(x) => f.debug_tuple("FmtArgs").field(x).finish(),
             Self::StrLike(x) => f.debug_tuple("StrLike").field(&x.as_ref()).finish(),
             Self::GenericStrings(x) => f.debug_tuple("Ansi").field(&x).finish(),
+            Self::Shared(x) => f.debug_tuple("Shared").field(&&**x).finish(),
         }
     }
 }
 
 impl<'a, S: 'a + ?Sized + ToOwned> Content<'a, S> {
+    /// The string held by this content, whether borrowed, owned or shared.
+    fn as_str_like(&self) -> Option<&S> {
+        match self {
+            Self::StrLike(s) => Some(s),
+            Self::Shared(s) => Some(s),
+            Self::FmtArgs(_) | Self::GenericStrings(_) => None,
+        }
+    }
+
     /// Write content to the given writer.
     pub fn write_to<T: ?Sized + ToOwned, W: AnyWrite<Buf = T> + ?Sized>(
         &self,
@@ -211,6 +505,69 @@ impl<'a, S: 'a + ?Sized + ToOwned> Content<'a, S> {
             Content::FmtArgs(args) => w.write_fmt(*args),
             Content::StrLike(s) => <S as StrLike<'a, W>>::write_str_to(s, w),
             Content::GenericStrings(x) => x.write_to_any(w),
+            Content::Shared(s) => <S as StrLike<'a, W>>::write_str_to(s, w),
+        }
+    }
+
+    /// Write content to the given writer as the payload of an operating
+    /// system command (such as a title or a hyperlink URL).
+    ///
+    /// Everything written passes through `sanitize`, which removes or
+    /// encodes anything that would otherwise end the command early:
+    /// [`Content::FmtArgs`] is rendered into a scratch buffer first, bytes
+    /// which are not UTF-8 are replaced, and nested sequences are written
+    /// without their escape codes.
+    pub(crate) fn write_osc_payload_to<T: ?Sized + ToOwned, W: AnyWrite<Buf = T> + ?Sized>(
+        &self,
+        w: &mut W,
+        sanitize: fn(&str) -> Cow<'_, str>,
+    ) -> WriteResult<W::Error>
+    where
+        S: StrLike<'a, W>,
+        str: AsRef<T>,
+    {
+        match self {
+            Content::FmtArgs(args) => {
+                let rendered = match args.as_str() {
+                    Some(s) => Cow::Borrowed(s),
+                    None => Cow::Owned(args.to_string()),
+                };
+                write_fmt!(w, "{}", sanitize(&rendered))
+            }
+            Content::StrLike(s) => w.write_str(sanitize(&as_text(&**s)).as_ref().as_ref()),
+            Content::Shared(s) => w.write_str(sanitize(&as_text(&**s)).as_ref().as_ref()),
+            Content::GenericStrings(strings) => strings
+                .iter()
+                .try_for_each(|string| string.content().write_osc_payload_to(w, sanitize)),
+        }
+    }
+}
+
+/// The text of string-like content, with any bytes which are not UTF-8
+/// replaced.
+fn as_text<S: ?Sized + AsRef<[u8]>>(s: &S) -> Cow<'_, str> {
+    String::from_utf8_lossy(s.as_ref())
+}
+
+impl<'a, S: 'a + ?Sized + ToOwned> Content<'a, S> {
+    /// The number of bytes [`write_osc_payload_to`](Self::write_osc_payload_to)
+    /// writes for this content with `sanitize`. Nested sequences count
+    /// their contents only.
+    pub(crate) fn osc_payload_len(&self, sanitize: fn(&str) -> Cow<'_, str>) -> usize
+    where
+        S: AsRef<[u8]>,
+    {
+        match self {
+            Content::FmtArgs(args) => match args.as_str() {
+                Some(s) => sanitize(s).len(),
+                None => sanitize(&args.to_string()).len(),
+            },
+            Content::StrLike(s) => sanitize(&as_text(&**s)).len(),
+            Content::Shared(s) => sanitize(&as_text(&**s)).len(),
+            Content::GenericStrings(strings) => strings
+                .iter()
+                .map(|string| string.content().osc_payload_len(sanitize))
+                .sum(),
         }
     }
 }
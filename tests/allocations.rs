@@ -0,0 +1,56 @@
+//! Building and rendering sequences must not allocate for each segment.
+
+use nu_ansi_term::AnsiStrings;
+use nu_ansi_term::Color::{Blue, Green, Red};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::fmt::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Counts the allocations made.
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+fn allocations_in(f: impl FnOnce()) -> usize {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    f();
+    ALLOCATIONS.load(Ordering::Relaxed) - before
+}
+
+#[test]
+fn prompt_segments_are_not_cloned() {
+    let styles = [Red.bold(), Green.normal(), Blue.underline()];
+    let texts: Vec<String> = (0..48).map(|ix| format!("segment {} ", ix)).collect();
+    let mut prompt = None;
+    let building = allocations_in(|| {
+        prompt = Some(AnsiStrings(
+            texts
+                .into_iter()
+                .enumerate()
+                .map(|(ix, text)| styles[ix % 3].paint(text)),
+        ));
+    });
+    let prompt = prompt.unwrap();
+    assert!(building <= 2, "{} allocations building", building);
+
+    let mut frame = String::with_capacity(4096);
+    for _ in 0..3 {
+        frame.clear();
+        let rendering = allocations_in(|| write!(frame, "{}", prompt).unwrap());
+        assert_eq!(rendering, 0);
+    }
+}
@@ -0,0 +1,177 @@
+//! Golden corpus: composed outputs of typical prompts, tables and listings are
+//! compared byte-for-byte against recordings in `tests/golden`, which are
+//! stored in the asciinema (v2) format so they can be replayed in a terminal
+//! with `asciinema play`.
+//!
+//! After an intended change to the output, re-record the corpus with
+//! `UPDATE_GOLDEN=1 cargo test --test golden` (once per feature set), and
+//! review the diff.
+
+use nu_ansi_term::Color::*;
+use nu_ansi_term::{
+    AnsiString, AnsiStrings, BoxChars, NumberStyle, PromptLayout, PromptLine, Style, TreeNode,
+    TreeRenderer,
+};
+use std::path::PathBuf;
+use std::{env, fs};
+
+/// The recording of the case `name` for the enabled features.
+fn golden_path(name: &str) -> PathBuf {
+    let suffix = if cfg!(feature = "gnu_legacy") {
+        ".gnu_legacy"
+    } else {
+        ""
+    };
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("golden")
+        .join(format!("{}{}.cast", name, suffix))
+}
+
+/// Concatenate the output events of an asciinema recording.
+fn replay(recording: &str) -> String {
+    let mut lines = recording.lines();
+    let header: serde_json::Value =
+        serde_json::from_str(lines.next().expect("recording has no header")).unwrap();
+    assert_eq!(header["version"], 2, "unsupported recording version");
+    lines
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| serde_json::from_str::<(f64, String, String)>(line).unwrap())
+        .filter(|(_, kind, _)| kind == "o")
+        .map(|(_, _, data)| data)
+        .collect()
+}
+
+/// Record `output` as one output event per line.
+fn record(output: &str) -> String {
+    let mut recording = String::from("{\"version\": 2, \"width\": 80, \"height\": 24}\n");
+    for (ix, line) in output.split_inclusive('\n').enumerate() {
+        let event = (ix as f64 * 0.1, "o", line);
+        recording.push_str(&serde_json::to_string(&event).unwrap());
+        recording.push('\n');
+    }
+    recording
+}
+
+fn check(name: &str, output: String) {
+    let path = golden_path(name);
+    if env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::write(&path, record(&output)).unwrap();
+        return;
+    }
+    let recording = fs::read_to_string(&path)
+        .unwrap_or_else(|e| panic!("cannot read {}: {}", path.display(), e));
+    assert_eq!(
+        replay(&recording),
+        output,
+        "output differs from {}",
+        path.display()
+    );
+}
+
+#[test]
+fn prompt() {
+    let layout = PromptLayout::new()
+        .width(60)
+        .fill('─', Fixed(240).normal())
+        .line(
+            PromptLine::new()
+                .left(Green.bold().paint("~/src/nu-ansi-term"))
+                .left(Style::new().paint(" on "))
+                .left(Purple.bold().paint("main"))
+                .right(Yellow.dimmed().paint("took 3s"))
+                .right(Style::new().paint(" "))
+                .right(Fixed(244).paint("12:04:51")),
+        )
+        .line(PromptLine::new().left(Green.bold().paint("❯ ")));
+    check("prompt", layout.render().to_string());
+}
+
+#[test]
+fn table() {
+    let border = Fixed(240).normal();
+    let header = Style::new().bold().underline();
+    let numbers = NumberStyle::new();
+    let chars = BoxChars::ROUNDED;
+    let rows: [(&str, f64); 4] = [("cpu", 12.5), ("mem", -3.25), ("disk", 0.0), ("net", 87.0)];
+
+    let mut segments: Vec<AnsiString> = vec![chars.top(24, border), "\n".into()];
+    segments.push(border.paint(chars.vertical.to_string()));
+    segments.push(header.paint(format!(" {:<10}", "name")));
+    segments.push(header.paint(format!("{:>11} ", "change")));
+    segments.push(border.paint(chars.vertical.to_string()));
+    segments.push("\n".into());
+    segments.push(chars.separator(24, border));
+    segments.push("\n".into());
+    for (name, value) in rows {
+        segments.push(border.paint(chars.vertical.to_string()));
+        segments.push(Cyan.paint(format!(" {:<10}", name)));
+        segments.push(numbers.style_for(value).paint(format!("{:>11.2}", value)));
+        segments.push(" ".into());
+        segments.push(border.paint(chars.vertical.to_string()));
+        segments.push("\n".into());
+    }
+    segments.push(chars.bottom(24, border));
+    check("table", AnsiStrings(segments).to_string());
+}
+
+#[test]
+fn listing() {
+    let entries = [
+        ("Cargo.toml", Style::new()),
+        ("examples", Blue.bold()),
+        ("src", Blue.bold()),
+        ("run.sh", Green.bold()),
+        ("target", Blue.bold()),
+    ];
+    let mut segments: Vec<AnsiString> = Vec::new();
+    for (name, style) in entries {
+        segments.push(
+            style
+                .paint(name)
+                .hyperlink_content(format!("file:///home/user/project/{}", name)),
+        );
+        segments.push("\n".into());
+    }
+    check("listing", AnsiStrings(segments).to_string());
+}
+
+#[test]
+fn tree() {
+    let tree = TreeNode::new(Blue.bold().paint("src"))
+        .child(TreeNode::new("lib.rs"))
+        .child(
+            TreeNode::new(Blue.bold().paint("display"))
+                .child(TreeNode::new("mod.rs"))
+                .child(TreeNode::new(
+                    Red.paint("a_file_with_a_rather_long_name.rs"),
+                )),
+        )
+        .child(TreeNode::new("style.rs"));
+    let rendered = TreeRenderer::new()
+        .guide_style(Fixed(244).normal())
+        .width(30)
+        .render(&tree);
+    check("tree", rendered.to_string());
+}
+
+#[test]
+fn chunked_status() {
+    let first = AnsiStrings([
+        Style::new().paint("status: "),
+        Yellow.bold().paint("building "),
+    ]);
+    let second = AnsiStrings([
+        Yellow.bold().paint("nu-ansi-term"),
+        Style::new().paint(" ... "),
+    ]);
+    let third = AnsiStrings([Green.bold().paint("done"), Style::new().paint("\n")]);
+    let output = format!(
+        "{}{}{}{}",
+        first.render_continuing_from(Style::new()),
+        second.render_continuing_from(first.final_style()),
+        third.render_continuing_from(second.final_style()),
+        third.final_style().suffix()
+    );
+    check("chunked_status", output);
+}